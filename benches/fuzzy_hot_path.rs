@@ -0,0 +1,151 @@
+// Micro-benchmarks of the fuzzy evaluation hot path
+//
+// Run with: cargo bench --bench fuzzy_hot_path
+// `cargo test` runs every benchmark once as a smoke test.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use examen_parcial::fuzzy_system::{compute_control_surface, gaussian, trapezoidal, triangular, FuzzySet};
+use examen_parcial::map::Map;
+use examen_parcial::navigation::{NavigationController, Quantization};
+use examen_parcial::simulation::{Simulation, SimulationBuilder};
+use examen_parcial::vehicle::{create_vehicle_preset, VehicleType};
+
+const SEED: u64 = 42;
+
+/// (label, distance, angular error in radians, relative velocity)
+const INPUTS: [(&str, f64, f64, f64); 4] = [
+    ("far_aligned", 800.0, 0.0, 0.1),
+    ("mid_deviated", 250.0, PI / 4.0, 0.1),
+    ("near_reversed", 60.0, -0.9 * PI, 0.1),
+    ("overlapping_sets", 90.0, PI / 18.0, 0.5),
+];
+
+fn bench_evaluate(c: &mut Criterion) {
+    let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+    let system = controller.fuzzy_system();
+    let mut group = c.benchmark_group("fuzzy_system_evaluate");
+
+    for (label, distance, angular_error, velocity) in INPUTS {
+        let inputs = HashMap::from([
+            ("distancia_al_objetivo".to_string(), distance),
+            ("error_angular".to_string(), angular_error),
+            ("velocidad_relativa".to_string(), velocity),
+        ]);
+        group.bench_function(label, |b| b.iter(|| system.evaluate(black_box(&inputs))));
+    }
+
+    group.finish();
+}
+
+fn bench_simulation_run(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulation_run");
+    group.sample_size(10);
+
+    for vehicle_type in VehicleType::ALL {
+        let build = || {
+            SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), vehicle_type)
+                .seed(SEED)
+                .build()
+        };
+        // Stepped by hand, `Simulation::run` prints its progress under the cli feature
+        let run = |mut sim: Simulation| {
+            while !sim.is_finished() {
+                sim.step();
+            }
+            sim.metrics()
+        };
+        group.bench_function(vehicle_type.id(), |b| b.iter_batched(build, run, BatchSize::SmallInput));
+    }
+
+    group.finish();
+}
+
+/// Same seeded run with the exact controller and with the output cache on the default grid
+fn bench_simulation_run_cached(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulation_run_cache");
+    group.sample_size(10);
+
+    for (label, cache) in [("exact", None), ("cached", Some(Quantization::default()))] {
+        let build = || {
+            SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), VehicleType::Standard)
+                .seed(SEED)
+                .control_cache(cache)
+                .build()
+        };
+        let run = |mut sim: Simulation| {
+            while !sim.is_finished() {
+                sim.step();
+            }
+            sim.metrics()
+        };
+        group.bench_function(label, |b| b.iter_batched(build, run, BatchSize::SmallInput));
+    }
+
+    group.finish();
+}
+
+fn bench_control_surface(c: &mut Criterion) {
+    let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+    let fixed_inputs = HashMap::from([("velocidad_relativa".to_string(), 0.5)]);
+
+    c.bench_function("control_surface_50x50", |b| {
+        b.iter(|| {
+            compute_control_surface(
+                controller.fuzzy_system(),
+                "error_angular",
+                "distancia_al_objetivo",
+                black_box(&fixed_inputs),
+                50,
+            )
+            .unwrap()
+        })
+    });
+}
+
+/// Points of the membership sweeps, a dense plot or surface axis
+const SWEEP_POINTS: usize = 10_000;
+
+/// One set of every shape swept point by point through the trait object and as one slice
+fn bench_membership_sweep(c: &mut Criterion) {
+    let xs: Vec<f64> = (0..SWEEP_POINTS).map(|i| -200.0 + 400.0 * i as f64 / (SWEEP_POINTS - 1) as f64).collect();
+    let sets = [
+        FuzzySet::new("triangular", triangular(-100.0, 0.0, 100.0)),
+        FuzzySet::new("trapezoidal", trapezoidal(-150.0, -50.0, 50.0, 150.0)),
+        FuzzySet::new("gaussian", gaussian(0.0, 60.0)),
+    ];
+    let mut group = c.benchmark_group("membership_sweep_10k");
+
+    for set in &sets {
+        let mut out = vec![0.0; xs.len()];
+        group.bench_function(format!("{}/scalar", set.name), |b| {
+            b.iter(|| {
+                for (&x, y) in black_box(&xs).iter().zip(out.iter_mut()) {
+                    *y = set.evaluate(x);
+                }
+                black_box(&out);
+            })
+        });
+        group.bench_function(format!("{}/slice", set.name), |b| {
+            b.iter(|| {
+                set.evaluate_slice(black_box(&xs), &mut out);
+                black_box(&out);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_evaluate,
+    bench_simulation_run,
+    bench_simulation_run_cached,
+    bench_control_surface,
+    bench_membership_sweep
+);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+// Embeds the git commit and the build profile (src/build_info.rs), and regenerates
+// include/fuzzy_nav.h (cbindgen.toml) when building with the `ffi` feature
+
+use std::path::Path;
+use std::process::Command;
+
+/// Output of a git command, None outside a checkout or without git
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Short commit hash, `-dirty` with uncommitted changes to tracked files
+///
+/// Builds without a checkout (a deploy archive) can pass it as FUZZY_NAV_GIT_HASH.
+fn git_hash() -> String {
+    if let Ok(hash) = std::env::var("FUZZY_NAV_GIT_HASH") {
+        return hash;
+    }
+    match git(&["rev-parse", "--short=12", "HEAD"]) {
+        Some(hash) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(changes) if !changes.is_empty() => format!("{}-dirty", hash),
+            _ => hash,
+        },
+        None => "unknown".to_string(),
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // A new commit moves HEAD or the branch it points to, edits under src change the dirty flag
+    println!("cargo:rerun-if-env-changed=FUZZY_NAV_GIT_HASH");
+    println!("cargo:rerun-if-changed=src");
+    for path in [".git/HEAD", ".git/index"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        let reference = format!(".git/{}", branch);
+        if Path::new(&reference).exists() {
+            println!("cargo:rerun-if-changed={}", reference);
+        }
+    }
+    println!("cargo:rustc-env=FUZZY_NAV_GIT_HASH={}", git_hash());
+    println!("cargo:rustc-env=FUZZY_NAV_BUILD_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("invalid cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_src(format!("{}/src/ffi.rs", crate_dir))
+            .with_config(config)
+            .generate()
+            .expect("failed to generate the C header")
+            .write_to_file(format!("{}/include/fuzzy_nav.h", crate_dir));
+    }
+}
@@ -0,0 +1,161 @@
+#ifndef FUZZY_NAV_H
+#define FUZZY_NAV_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. Rebuild with --features ffi. */
+
+#include <stdint.h>
+
+/**
+ * Status codes returned by `fuzzy_nav_controller_compute` and `fuzzy_nav_controller_observe`
+ */
+enum FuzzyNavStatus
+#ifdef __cplusplus
+  : int32_t
+#endif // __cplusplus
+ {
+  FUZZY_NAV_STATUS_OK = 0,
+  /**
+   * The controller or an output pointer is null
+   */
+  FUZZY_NAV_STATUS_NULL_POINTER = -1,
+  /**
+   * An input is NaN or infinite
+   */
+  FUZZY_NAV_STATUS_NON_FINITE_INPUT = -2,
+  /**
+   * The controller panicked, the handle should be destroyed
+   */
+  FUZZY_NAV_STATUS_PANIC = -3,
+  /**
+   * The map has non-positive dimensions or its target is outside it
+   */
+  FUZZY_NAV_STATUS_INVALID_MAP = -4,
+};
+#ifndef __cplusplus
+typedef int32_t FuzzyNavStatus;
+#endif // __cplusplus
+
+/**
+ * Vehicle type codes accepted by `fuzzy_nav_controller_create`
+ */
+enum FuzzyNavVehicleType
+#ifdef __cplusplus
+  : uint32_t
+#endif // __cplusplus
+ {
+  FUZZY_NAV_VEHICLE_TYPE_HEAVY = 0,
+  FUZZY_NAV_VEHICLE_TYPE_STANDARD = 1,
+  FUZZY_NAV_VEHICLE_TYPE_AGILE = 2,
+  FUZZY_NAV_VEHICLE_TYPE_ULTRA_AGILE = 3,
+};
+#ifndef __cplusplus
+typedef uint32_t FuzzyNavVehicleType;
+#endif // __cplusplus
+
+/**
+ * Opaque controller handle, owned by the caller until `fuzzy_nav_controller_destroy`
+ */
+typedef struct FuzzyNavController FuzzyNavController;
+
+/**
+ * Map a vehicle navigates on, the target of `Map::new` (arrival heading 90°)
+ */
+typedef struct {
+  double width;
+  double height;
+  double target_x;
+  double target_y;
+} FuzzyNavMap;
+
+/**
+ * Vehicle state, `angle` in radians counterclockwise from +x
+ */
+typedef struct {
+  double x;
+  double y;
+  double angle;
+  double velocity;
+} FuzzyNavState;
+
+/**
+ * Controller inputs of a vehicle state, the arguments of `fuzzy_nav_controller_compute`
+ */
+typedef struct {
+  double distance;
+  /**
+   * Radians towards the approach point
+   */
+  double angular_error;
+  double velocity_rel;
+  /**
+   * Point the controller steers to, the target itself when far away
+   */
+  double approach_x;
+  double approach_y;
+} FuzzyNavObservation;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create the controller of a vehicle type (`FuzzyNavVehicleType`)
+ *
+ * Returns null for an unknown vehicle type.
+ */
+FuzzyNavController *fuzzy_nav_controller_create(uint32_t vehicle_type);
+
+/**
+ * Evaluate the controller
+ *
+ * `distance` in map units, `angular_error` in radians (positive when the
+ * target is counterclockwise from the heading), `velocity_rel` as a fraction
+ * of the max velocity. On `FUZZY_NAV_STATUS_OK` writes the angular adjustment
+ * (rad/s) and the velocity adjustment (always 0, velocity is constant) to the
+ * outputs, on any other status leaves them untouched.
+ *
+ * # Safety
+ *
+ * `ctrl` must be null or a live handle from `fuzzy_nav_controller_create`,
+ * the outputs null or valid for writing an `f64`.
+ */
+int32_t fuzzy_nav_controller_compute(const FuzzyNavController *ctrl,
+                                     double distance,
+                                     double angular_error,
+                                     double velocity_rel,
+                                     double *out_angular,
+                                     double *out_velocity);
+
+/**
+ * Observe a vehicle state as the simulation does before evaluating the controller
+ *
+ * Uses `navigation::build_observation` with the characteristics of the
+ * controller, so passing the observation to `fuzzy_nav_controller_compute`
+ * steers exactly like a simulation step. On `FUZZY_NAV_STATUS_OK` writes the
+ * observation to `out`, on any other status leaves it untouched.
+ *
+ * # Safety
+ *
+ * `ctrl` must be null or a live handle from `fuzzy_nav_controller_create`,
+ * `out` null or valid for writing a `FuzzyNavObservation`.
+ */
+int32_t fuzzy_nav_controller_observe(const FuzzyNavController *ctrl,
+                                     FuzzyNavMap map,
+                                     FuzzyNavState state,
+                                     FuzzyNavObservation *out);
+
+/**
+ * Free a controller, null is a no-op
+ *
+ * # Safety
+ *
+ * `ctrl` must be null or a handle from `fuzzy_nav_controller_create` not
+ * destroyed yet; it must not be used afterwards.
+ */
+void fuzzy_nav_controller_destroy(FuzzyNavController *ctrl);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FUZZY_NAV_H */
@@ -0,0 +1,70 @@
+"""Tests of the fuzzy_nav Python module, run with ./test-python.sh"""
+import math
+
+import pytest
+
+import fuzzy_nav
+
+SEEDED = {"vehicle_types": ["Heavy", "Agile"], "max_time": 5.0, "seed": 42}
+
+
+def test_simulate_matches_api_schema():
+    result = fuzzy_nav.simulate(SEEDED)
+
+    assert set(result) == {"success", "vehicles", "total_simulation_time", "message"}
+    assert [v["vehicle_type"] for v in result["vehicles"]] == ["Barco", "Avión"]
+    assert {"t", "x", "y", "angle"} <= set(result["vehicles"][0]["trajectory"][0])
+
+
+def test_simulate_is_deterministic_with_seed():
+    assert fuzzy_nav.simulate(SEEDED) == fuzzy_nav.simulate(SEEDED)
+
+    other = fuzzy_nav.simulate({**SEEDED, "seed": 43})
+    assert other["vehicles"][0]["trajectory"][0] != fuzzy_nav.simulate(SEEDED)["vehicles"][0]["trajectory"][0]
+
+
+def test_simulate_rejects_unknown_vehicle():
+    with pytest.raises(ValueError):
+        fuzzy_nav.simulate({"vehicle_types": ["Submarine"]})
+
+
+def test_benchmark_aggregates_per_vehicle():
+    config = {"iterations": 4, "vehicle_types": ["Heavy", "Agile"], "max_time": 5.0, "seed": 3}
+    result = fuzzy_nav.benchmark(config)
+
+    assert result["num_iterations"] == 4
+    assert [s["total_runs"] for s in result["aggregate_stats"]] == [4, 4]
+    assert result == fuzzy_nav.benchmark(config)
+
+
+def test_evaluate_controller_turns_towards_error():
+    assert fuzzy_nav.evaluate_controller("Standard", 500.0, 0.5, 0.1) > 0.0
+    assert fuzzy_nav.evaluate_controller("Standard", 500.0, -0.5, 0.1) < 0.0
+
+
+def test_build_observation_feeds_evaluate_controller():
+    # Far below the target heading north: steers to the target itself, straight ahead
+    far = fuzzy_nav.build_observation("Standard", 500.0, 100.0, math.pi / 2, 6.0)
+    assert far["approach_point"] == {"x": 500.0, "y": 700.0}
+    assert far["distance_to_target"] == 600.0
+    assert abs(far["angular_error"]) < 1e-12
+
+    # Inside the approach corridor the approach point drops below the target
+    near = fuzzy_nav.build_observation("Standard", 440.0, 640.0, -0.5, 6.0)
+    assert near["approach_point"]["y"] < 700.0
+    adjustment = fuzzy_nav.evaluate_controller("Standard", near["distance_to_target"], near["angular_error"], near["velocity_relative"])
+    assert adjustment > 0.0
+
+    with pytest.raises(ValueError):
+        fuzzy_nav.build_observation("Submarine", 0.0, 0.0, 0.0, 0.0)
+    with pytest.raises(ValueError):
+        fuzzy_nav.build_observation("Standard", 440.0, 640.0, 0.0, 6.0, target_y=900.0)
+
+
+def test_control_surface_is_numpy_compatible():
+    np = pytest.importorskip("numpy")
+    x, y, values = fuzzy_nav.control_surface("Agile", resolution=20, fixed_inputs={"velocidad_relativa": 0.3})
+
+    grid = np.asarray(values)
+    assert grid.shape == (len(y), len(x)) == (20, 20)
+    assert np.isfinite(grid).all()
@@ -0,0 +1,326 @@
+// Analysis module - Post-processing of recorded trajectories
+
+use serde::Serialize;
+
+use crate::simulation::{TrajectoryPoint, VehicleResult};
+
+pub mod resampling;
+pub mod robustness;
+pub mod similarity;
+pub mod stats;
+
+pub use resampling::{mean_trajectory, resample, resample_for_playback};
+pub use robustness::{robustness_report, robustness_sweep, RobustnessAxis, RobustnessCurve, RobustnessLevel, RobustnessReport};
+pub use similarity::{discrete_frechet, trajectory_similarity, TrajectorySimilarity};
+
+/// Metric differences of one vehicle between two runs (run B minus run A)
+///
+/// Vehicles are matched by id, so a vehicle present in only one of the runs keeps
+/// the other index as `None` and has no deltas. Lower values are better for
+/// every metric, so negative deltas mean run B improved.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VehicleDelta {
+    /// Id of the vehicle in run A, or in run B when only there
+    pub id: String,
+    pub vehicle_type: String,
+    pub index_a: Option<usize>,
+    pub index_b: Option<usize>,
+    /// Only when the vehicle arrived in both runs
+    pub arrival_time_delta: Option<f64>,
+    pub final_angle_error_delta: Option<f64>,
+    pub distance_traveled_delta: Option<f64>,
+    /// How differently the two paths went, when both have trajectory points
+    pub similarity: Option<TrajectorySimilarity>,
+}
+
+/// Match the vehicles of two runs by id and compute their metric differences
+/// and the similarity of their paths
+///
+/// Results recorded without ids fall back to the type, repeated types paired in
+/// order of appearance (the second "Barco" of run A with the second "Barco" of
+/// run B). Rows follow the order of run A, followed by the vehicles that only
+/// exist in run B.
+pub fn compare_runs(run_a: &[VehicleResult], run_b: &[VehicleResult]) -> Vec<VehicleDelta> {
+    let mut deltas = compare_metrics(run_a, run_b);
+    for delta in &mut deltas {
+        if let (Some(a), Some(b)) = (delta.index_a, delta.index_b) {
+            delta.similarity = trajectory_similarity(&run_a[a].trajectory, &run_b[b].trajectory);
+        }
+    }
+    deltas
+}
+
+/// `compare_runs` without the path similarity, cheap enough to redo while a run grows
+pub fn compare_metrics(run_a: &[VehicleResult], run_b: &[VehicleResult]) -> Vec<VehicleDelta> {
+    let mut used_b = vec![false; run_b.len()];
+    let mut deltas = Vec::with_capacity(run_a.len().max(run_b.len()));
+
+    for (idx_a, vehicle_a) in run_a.iter().enumerate() {
+        let matched = run_b.iter()
+            .enumerate()
+            .position(|(idx_b, vehicle_b)| !used_b[idx_b] && same_vehicle(vehicle_a, vehicle_b));
+
+        let delta = match matched {
+            Some(idx_b) => {
+                used_b[idx_b] = true;
+                let (a, b) = (&vehicle_a.metrics, &run_b[idx_b].metrics);
+                VehicleDelta {
+                    id: vehicle_a.id.clone(),
+                    vehicle_type: vehicle_a.vehicle_type.clone(),
+                    index_a: Some(idx_a),
+                    index_b: Some(idx_b),
+                    arrival_time_delta: a.arrival_time.zip(b.arrival_time).map(|(ta, tb)| tb - ta),
+                    final_angle_error_delta: Some(b.final_angle_error - a.final_angle_error),
+                    distance_traveled_delta: Some(b.distance_traveled - a.distance_traveled),
+                    similarity: None,
+                }
+            }
+            None => unmatched(vehicle_a, Some(idx_a), None),
+        };
+        deltas.push(delta);
+    }
+
+    for (idx_b, vehicle_b) in run_b.iter().enumerate() {
+        if !used_b[idx_b] {
+            deltas.push(unmatched(vehicle_b, None, Some(idx_b)));
+        }
+    }
+
+    deltas
+}
+
+/// Same id, or same type when either run predates vehicle ids
+fn same_vehicle(a: &VehicleResult, b: &VehicleResult) -> bool {
+    if a.id.is_empty() || b.id.is_empty() {
+        a.vehicle_type == b.vehicle_type
+    } else {
+        a.id == b.id
+    }
+}
+
+fn unmatched(vehicle: &VehicleResult, index_a: Option<usize>, index_b: Option<usize>) -> VehicleDelta {
+    VehicleDelta {
+        id: vehicle.id.clone(),
+        vehicle_type: vehicle.vehicle_type.clone(),
+        index_a,
+        index_b,
+        arrival_time_delta: None,
+        final_angle_error_delta: None,
+        distance_traveled_delta: None,
+        similarity: None,
+    }
+}
+
+/// Index of the last trajectory point recorded at or before time `t`
+///
+/// Used to play two runs with a shared clock. Returns 0 for an empty trajectory
+/// or a time before the first point.
+pub fn index_at_time(trajectory: &[TrajectoryPoint], t: f64) -> usize {
+    trajectory.partition_point(|p| p.t <= t).saturating_sub(1)
+}
+
+/// True if at least one point of the trajectory carries rule activations
+pub fn has_rule_activations(trajectory: &[TrajectoryPoint]) -> bool {
+    trajectory.iter().any(|p| p.rule_activations.is_some())
+}
+
+/// Maximum firing strength reached by each rule over the whole trajectory
+///
+/// Points without recorded activations are skipped. The result has one entry per
+/// rule (the longest activation vector found), empty if nothing was recorded.
+pub fn rule_activation_max(trajectory: &[TrajectoryPoint]) -> Vec<f64> {
+    let mut max_activations: Vec<f64> = Vec::new();
+
+    for activations in trajectory.iter().filter_map(|p| p.rule_activations.as_ref()) {
+        if activations.len() > max_activations.len() {
+            max_activations.resize(activations.len(), 0.0);
+        }
+        for (max, &value) in max_activations.iter_mut().zip(activations) {
+            *max = max.max(value);
+        }
+    }
+
+    max_activations
+}
+
+/// Index of the strongest rule at every trajectory point
+///
+/// `None` when the point has no recorded activations or no rule fired.
+/// Ties resolve to the lowest rule index.
+pub fn dominant_rule_per_step(trajectory: &[TrajectoryPoint]) -> Vec<Option<usize>> {
+    trajectory
+        .iter()
+        .map(|p| {
+            p.rule_activations.as_ref().and_then(|activations| {
+                activations
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &value)| value > f64::EPSILON)
+                    .fold(None, |best: Option<(usize, f64)>, (idx, &value)| match best {
+                        Some((_, best_value)) if best_value >= value => best,
+                        _ => Some((idx, value)),
+                    })
+                    .map(|(idx, _)| idx)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationMetrics;
+
+    /// Vehicle without an id, as recorded before ids existed
+    fn vehicle(name: &str, arrival_time: Option<f64>, angle_error: f64, distance: f64) -> VehicleResult {
+        VehicleResult {
+            id: String::new(),
+            vehicle_type: name.to_string(),
+            trajectory: Vec::new(),
+            metrics: SimulationMetrics {
+                success: arrival_time.is_some(),
+                arrival_time,
+                distance_traveled: distance,
+                final_angle_error: angle_error,
+                final_distance_to_target: 0.0,
+                termination: None,
+                ..Default::default()
+            },
+            target: None,
+        }
+    }
+
+    fn point_with(activations: Option<Vec<f64>>) -> TrajectoryPoint {
+        TrajectoryPoint {
+            rule_activations: activations,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rule_activation_max() {
+        let trajectory = vec![
+            point_with(Some(vec![0.2, 0.0, 0.5])),
+            point_with(None),
+            point_with(Some(vec![0.7, 0.1, 0.3])),
+        ];
+        assert_eq!(rule_activation_max(&trajectory), vec![0.7, 0.1, 0.5]);
+        assert!(has_rule_activations(&trajectory));
+    }
+
+    #[test]
+    fn test_rule_activation_max_without_data() {
+        let trajectory = vec![point_with(None), point_with(None)];
+        assert!(rule_activation_max(&trajectory).is_empty());
+        assert!(!has_rule_activations(&trajectory));
+    }
+
+    #[test]
+    fn test_dominant_rule_per_step() {
+        let trajectory = vec![
+            point_with(Some(vec![0.2, 0.6, 0.5])),
+            point_with(None),
+            point_with(Some(vec![0.0, 0.0, 0.0])),
+            point_with(Some(vec![0.4, 0.4, 0.1])),
+        ];
+        assert_eq!(dominant_rule_per_step(&trajectory), vec![Some(1), None, None, Some(0)]);
+    }
+
+    #[test]
+    fn test_compare_runs_matching_sets() {
+        let run_a = vec![vehicle("Barco", Some(40.0), 3.0, 900.0), vehicle("Lancha", Some(30.0), 1.0, 700.0)];
+        let run_b = vec![vehicle("Lancha", Some(25.0), 0.5, 650.0), vehicle("Barco", None, 12.0, 1000.0)];
+
+        let deltas = compare_runs(&run_a, &run_b);
+        assert_eq!(deltas.len(), 2);
+
+        assert_eq!(deltas[0].vehicle_type, "Barco");
+        assert_eq!((deltas[0].index_a, deltas[0].index_b), (Some(0), Some(1)));
+        assert_eq!(deltas[0].arrival_time_delta, None);
+        assert_eq!(deltas[0].final_angle_error_delta, Some(9.0));
+        assert_eq!(deltas[0].distance_traveled_delta, Some(100.0));
+
+        assert_eq!((deltas[1].index_a, deltas[1].index_b), (Some(1), Some(0)));
+        assert_eq!(deltas[1].arrival_time_delta, Some(-5.0));
+        assert_eq!(deltas[1].distance_traveled_delta, Some(-50.0));
+    }
+
+    #[test]
+    fn test_compare_runs_mismatched_sets() {
+        let run_a = vec![
+            vehicle("Barco", Some(40.0), 3.0, 900.0),
+            vehicle("Lancha", Some(30.0), 1.0, 700.0),
+            vehicle("Barco", Some(42.0), 2.0, 950.0),
+        ];
+        let run_b = vec![vehicle("Barco", Some(38.0), 2.5, 880.0), vehicle("Avión", Some(20.0), 1.5, 600.0)];
+
+        let deltas = compare_runs(&run_a, &run_b);
+        let pairs: Vec<(&str, Option<usize>, Option<usize>)> = deltas.iter()
+            .map(|d| (d.vehicle_type.as_str(), d.index_a, d.index_b))
+            .collect();
+        assert_eq!(pairs, vec![
+            ("Barco", Some(0), Some(0)),
+            ("Lancha", Some(1), None),
+            ("Barco", Some(2), None),
+            ("Avión", None, Some(1)),
+        ]);
+
+        assert_eq!(deltas[0].arrival_time_delta, Some(-2.0));
+        for unmatched in &deltas[1..] {
+            assert_eq!(unmatched.arrival_time_delta, None);
+            assert_eq!(unmatched.final_angle_error_delta, None);
+            assert_eq!(unmatched.distance_traveled_delta, None);
+        }
+    }
+
+    #[test]
+    fn test_compare_runs_matches_by_id() {
+        let labeled = |id: &str, arrival_time: f64| VehicleResult { id: id.to_string(), ..vehicle("Ágil", Some(arrival_time), 1.0, 500.0) };
+        let run_a = vec![labeled("left", 30.0), labeled("right", 40.0)];
+        let run_b = vec![labeled("right", 35.0), labeled("left", 31.0), labeled("spare", 50.0)];
+
+        let deltas = compare_metrics(&run_a, &run_b);
+        let pairs: Vec<(&str, Option<usize>, Option<usize>)> = deltas.iter()
+            .map(|d| (d.id.as_str(), d.index_a, d.index_b))
+            .collect();
+        assert_eq!(pairs, vec![("left", Some(0), Some(1)), ("right", Some(1), Some(0)), ("spare", None, Some(2))]);
+        assert_eq!((deltas[0].arrival_time_delta, deltas[1].arrival_time_delta), (Some(1.0), Some(-5.0)));
+    }
+
+    #[test]
+    fn test_compare_runs_measures_matched_paths() {
+        let path = |y: f64| -> Vec<TrajectoryPoint> {
+            (0..4).map(|i| TrajectoryPoint { t: i as f64, x: i as f64 * 10.0, y, ..Default::default() }).collect()
+        };
+        let mut run_a = vec![vehicle("Barco", Some(3.0), 1.0, 30.0), vehicle("Lancha", None, 1.0, 30.0)];
+        let mut run_b = vec![vehicle("Barco", Some(3.0), 1.0, 30.0)];
+        run_a[0].trajectory = path(0.0);
+        run_a[1].trajectory = path(0.0);
+        run_b[0].trajectory = path(4.0);
+
+        let deltas = compare_runs(&run_a, &run_b);
+        let similarity = deltas[0].similarity.unwrap();
+        assert_eq!((similarity.frechet_distance, similarity.mean_deviation), (4.0, Some(4.0)));
+        assert_eq!(deltas[1].similarity, None);
+        assert!(compare_metrics(&run_a, &run_b).iter().all(|delta| delta.similarity.is_none()));
+    }
+
+    #[test]
+    fn test_compare_runs_empty() {
+        assert!(compare_runs(&[], &[]).is_empty());
+        let only_b = compare_runs(&[], &[vehicle("Barco", None, 0.0, 0.0)]);
+        assert_eq!((only_b[0].index_a, only_b[0].index_b), (None, Some(0)));
+    }
+
+    #[test]
+    fn test_index_at_time() {
+        let trajectory: Vec<TrajectoryPoint> = (0..5)
+            .map(|i| TrajectoryPoint { t: i as f64 * 0.5, ..Default::default() })
+            .collect();
+        assert_eq!(index_at_time(&trajectory, 0.0), 0);
+        assert_eq!(index_at_time(&trajectory, 0.7), 1);
+        assert_eq!(index_at_time(&trajectory, 1.0), 2);
+        assert_eq!(index_at_time(&trajectory, 99.0), 4);
+        assert_eq!(index_at_time(&[], 1.0), 0);
+    }
+}
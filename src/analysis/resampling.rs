@@ -0,0 +1,275 @@
+// Trajectories on a common time grid: resampling of recorded points, frames
+// for a fixed rate playback and the mean path of many runs with its spread
+
+use crate::map::normalize_angle;
+use crate::simulation::{SimulationResult, TrajectoryPoint, VehicleResult};
+use crate::units::{Degrees, Radians};
+
+use super::stats::calculate_stats;
+
+/// Fraction of `grid_dt` within which a recorded time counts as on the grid,
+/// so a run resampled at its own dt keeps its points
+const GRID_TOLERANCE: f64 = 1e-9;
+
+/// Trajectory sampled at the multiples of `grid_dt` within its recorded span
+///
+/// Position, velocity and distance to the target are interpolated linearly,
+/// the heading the short way around the circle (179° to -179° passes 180°,
+/// not 0°). The control-signal fields are those of the recorded point that
+/// ends the step the sample falls in. A sample on a recorded time is that
+/// point, the last one when several share the time. Empty for an empty
+/// trajectory or a `grid_dt` that is not positive and finite.
+pub fn resample(trajectory: &[TrajectoryPoint], grid_dt: f64) -> Vec<TrajectoryPoint> {
+    let (Some(first), Some(last)) = (trajectory.first(), trajectory.last()) else {
+        return Vec::new();
+    };
+    if !(grid_dt > 0.0 && grid_dt.is_finite()) {
+        return Vec::new();
+    }
+    let first_index = (first.t / grid_dt - GRID_TOLERANCE).ceil() as i64;
+    let last_index = (last.t / grid_dt + GRID_TOLERANCE).floor() as i64;
+    (first_index..=last_index).map(|k| sample_at(trajectory, k as f64 * grid_dt, grid_dt)).collect()
+}
+
+/// Frames of an animation playing `speed` simulated seconds per second at `fps` frames per second
+///
+/// Frame `k` is the trajectory at `k * speed / fps` seconds after its first
+/// point, interpolated like `resample` (headings the short way around the
+/// circle). The last frame is the last recorded point itself, wherever it
+/// falls between frames, so at most `ceil(fps * duration / speed) + 1` frames.
+/// Empty for an empty trajectory or an `fps` or `speed` that is not positive and finite.
+pub fn resample_for_playback(trajectory: &[TrajectoryPoint], fps: f64, speed: f64) -> Vec<TrajectoryPoint> {
+    let (Some(first), Some(last)) = (trajectory.first(), trajectory.last()) else {
+        return Vec::new();
+    };
+    let frame_dt = speed / fps;
+    if !(fps > 0.0 && speed > 0.0 && frame_dt > 0.0 && frame_dt.is_finite()) {
+        return Vec::new();
+    }
+    let frames = ((last.t - first.t) / frame_dt + GRID_TOLERANCE).floor().max(0.0) as usize;
+    let mut playback: Vec<TrajectoryPoint> =
+        (0..=frames).map(|k| sample_at(trajectory, first.t + k as f64 * frame_dt, frame_dt)).collect();
+    // A last frame on the final time is replaced, so the playback ends on the recorded point exactly
+    if playback.last().is_some_and(|frame| (last.t - frame.t).abs() <= GRID_TOLERANCE * frame_dt) {
+        playback.pop();
+    }
+    playback.push(last.clone());
+    playback
+}
+
+impl SimulationResult {
+    /// The trajectory at a fixed animation frame rate, see `resample_for_playback`
+    pub fn resampled_for_playback(&self, fps: f64, speed: f64) -> Vec<TrajectoryPoint> {
+        resample_for_playback(&self.trajectory, fps, speed)
+    }
+}
+
+impl VehicleResult {
+    /// The trajectory at a fixed animation frame rate, see `resample_for_playback`
+    pub fn resampled_for_playback(&self, fps: f64, speed: f64) -> Vec<TrajectoryPoint> {
+        resample_for_playback(&self.trajectory, fps, speed)
+    }
+}
+
+/// Point of the trajectory at `t`, which lies within its recorded span
+fn sample_at(trajectory: &[TrajectoryPoint], t: f64, grid_dt: f64) -> TrajectoryPoint {
+    let tolerance = GRID_TOLERANCE * grid_dt;
+    let after = trajectory.partition_point(|p| p.t <= t + tolerance).max(1);
+    let before = &trajectory[after - 1];
+    if after == trajectory.len() || (t - before.t).abs() <= tolerance {
+        return TrajectoryPoint { t, ..before.clone() };
+    }
+    let next = &trajectory[after];
+    let s = (t - before.t) / (next.t - before.t);
+    let lerp = |a: f64, b: f64| a + s * (b - a);
+    TrajectoryPoint {
+        t,
+        x: lerp(before.x, next.x),
+        y: lerp(before.y, next.y),
+        angle: lerp_angle(before.angle, next.angle, s),
+        velocity: lerp(before.velocity, next.velocity),
+        distance_to_target: lerp(before.distance_to_target, next.distance_to_target),
+        ..next.clone()
+    }
+}
+
+/// Heading a fraction `s` of the way from `from` to `to`, turning the short way
+fn lerp_angle(from: Degrees, to: Degrees, s: f64) -> Degrees {
+    let turn = normalize_angle((to - from).to_radians()).to_degrees();
+    normalize_angle((from + turn * s).to_radians()).to_degrees()
+}
+
+/// Mean path of the runs and its standard deviation, both on the multiples of `grid_dt`
+///
+/// The grid spans from the earliest start to the latest end. A run that
+/// ended earlier stays at its last point, an arrived vehicle counts as parked
+/// on the target until the longest run ends. Position, velocity and distance
+/// are averaged per sample, the heading by its circular mean with the
+/// circular standard deviation. The control-signal fields are left out. Both
+/// are empty without runs that have points.
+pub fn mean_trajectory(runs: &[&[TrajectoryPoint]], grid_dt: f64) -> (Vec<TrajectoryPoint>, Vec<TrajectoryPoint>) {
+    let resampled: Vec<(i64, Vec<TrajectoryPoint>)> = runs
+        .iter()
+        .map(|run| resample(run, grid_dt))
+        .filter(|run| !run.is_empty())
+        .map(|run| ((run[0].t / grid_dt).round() as i64, run))
+        .collect();
+    let Some(first_index) = resampled.iter().map(|(start, _)| *start).min() else {
+        return (Vec::new(), Vec::new());
+    };
+    let last_index = resampled.iter().map(|(start, run)| start + run.len() as i64 - 1).max().unwrap_or(first_index);
+
+    (first_index..=last_index)
+        .map(|k| {
+            let samples: Vec<&TrajectoryPoint> = resampled
+                .iter()
+                .map(|(start, run)| &run[(k - start).clamp(0, run.len() as i64 - 1) as usize])
+                .collect();
+            let stats = |field: fn(&TrajectoryPoint) -> f64| {
+                let values: Vec<f64> = samples.iter().map(|p| field(p)).collect();
+                let (mean, std, _, _) = calculate_stats(&values);
+                (mean, std)
+            };
+            let (x, x_std) = stats(|p| p.x);
+            let (y, y_std) = stats(|p| p.y);
+            let (velocity, velocity_std) = stats(|p| p.velocity);
+            let (distance, distance_std) = stats(|p| p.distance_to_target);
+            let (sin, _) = stats(|p| p.angle.to_radians().sin());
+            let (cos, _) = stats(|p| p.angle.to_radians().cos());
+            // Mean resultant length, 1 when every heading agrees
+            let resultant = sin.hypot(cos).min(1.0);
+
+            let t = k as f64 * grid_dt;
+            let mean = TrajectoryPoint {
+                t,
+                x,
+                y,
+                angle: Radians(sin.atan2(cos)).to_degrees(),
+                velocity,
+                distance_to_target: distance,
+                ..Default::default()
+            };
+            let std = TrajectoryPoint {
+                t,
+                x: x_std,
+                y: y_std,
+                angle: Radians((-2.0 * resultant.ln()).max(0.0).sqrt()).to_degrees(),
+                velocity: velocity_std,
+                distance_to_target: distance_std,
+                ..Default::default()
+            };
+            (mean, std)
+        })
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+    use crate::simulation::SimulationBuilder;
+    use crate::vehicle::VehicleType;
+
+    fn point(t: f64, x: f64, y: f64, angle: f64) -> TrajectoryPoint {
+        TrajectoryPoint { t, x, y, angle: Degrees(angle), ..Default::default() }
+    }
+
+    #[test]
+    fn test_angle_interpolates_across_the_seam() {
+        let trajectory = [point(0.0, 0.0, 0.0, 179.0), point(1.0, 1.0, 0.0, -179.0)];
+        let resampled = resample(&trajectory, 0.5);
+        assert_eq!(resampled.len(), 3);
+        assert!((resampled[1].angle.abs().0 - 180.0).abs() < 1e-9, "{}", resampled[1].angle);
+        assert!((lerp_angle(Degrees(-179.0), Degrees(179.0), 0.25).0 + 179.5).abs() < 1e-9);
+        assert!((lerp_angle(Degrees(10.0), Degrees(50.0), 0.25).0 - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_playback_frames_cross_the_seam_the_short_way() {
+        let trajectory = [point(0.0, 0.0, 0.0, 170.0), point(0.05, 1.0, 0.0, -170.0), point(0.1, 2.0, 0.0, -160.0)];
+        let playback = resample_for_playback(&trajectory, 60.0, 1.0);
+        assert_eq!(playback.iter().map(|p| p.t).collect::<Vec<_>>()[..3], [0.0, 1.0 / 60.0, 2.0 / 60.0]);
+        // 170° to -170° turns 20° through 180°, never back through 0°
+        assert!(playback[1..3].iter().all(|p| p.angle.abs().0 > 170.0), "{:?}", playback.iter().map(|p| p.angle).collect::<Vec<_>>());
+        assert!((playback[1].angle.0 - 176.666_666_666_666_7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_playback_ends_on_the_recorded_point() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let result = SimulationBuilder::new(map, VehicleType::Agile).seed(9).max_time(12.34).build().run();
+        let last = result.trajectory.last().unwrap();
+
+        for (fps, speed) in [(60.0, 1.0), (24.0, 4.0), (30.0, 0.25)] {
+            let playback = result.resampled_for_playback(fps, speed);
+            assert_eq!(playback[0].t, result.trajectory[0].t);
+            assert_eq!(serde_json::to_value(playback.last().unwrap()).unwrap(), serde_json::to_value(last).unwrap());
+            assert!(playback.windows(2).all(|pair| pair[1].t > pair[0].t));
+        }
+        assert!(result.resampled_for_playback(0.0, 1.0).is_empty());
+        assert!(result.resampled_for_playback(60.0, f64::NAN).is_empty());
+    }
+
+    #[test]
+    fn test_playback_length_is_bounded_by_fps_times_duration() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let result = SimulationBuilder::new(map, VehicleType::Standard).seed(2).max_time(30.0).build().run();
+        let duration = result.trajectory.last().unwrap().t - result.trajectory[0].t;
+
+        for (fps, speed) in [(60.0, 1.0), (60.0, 2.0), (144.0, 0.5), (7.0, 3.0)] {
+            let frames = result.resampled_for_playback(fps, speed).len();
+            let bound = (fps * duration / speed).ceil() as usize + 1;
+            assert!(frames <= bound && frames + 1 >= bound, "{} frames at {} fps x{} for {} s", frames, fps, speed, duration);
+        }
+    }
+
+    #[test]
+    fn test_resampling_at_the_recorded_dt_is_the_identity() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let mut sim = SimulationBuilder::new(map, VehicleType::Standard).seed(4).max_time(20.0).build();
+        let trajectory = sim.run().trajectory;
+        assert!(trajectory.len() > 300);
+
+        let resampled = resample(&trajectory, sim.dt);
+        assert_eq!(serde_json::to_value(&resampled).unwrap(), serde_json::to_value(&trajectory).unwrap());
+    }
+
+    #[test]
+    fn test_samples_between_points_are_interpolated() {
+        let mut next = TrajectoryPoint { velocity: 30.0, distance_to_target: 80.0, ..point(2.0, 20.0, 10.0, 90.0) };
+        next.angular_adjustment = Some(4.0);
+        let trajectory = [TrajectoryPoint { velocity: 10.0, distance_to_target: 100.0, ..point(1.0, 10.0, 0.0, 0.0) }, next];
+
+        let resampled = resample(&trajectory, 0.25);
+        assert_eq!(resampled.iter().map(|p| p.t).collect::<Vec<_>>(), [1.0, 1.25, 1.5, 1.75, 2.0]);
+        let middle = &resampled[2];
+        assert_eq!((middle.x, middle.y, middle.angle), (15.0, 5.0, Degrees(45.0)));
+        assert_eq!((middle.velocity, middle.distance_to_target), (20.0, 90.0));
+        // Within the step that ends at t=2
+        assert_eq!(middle.angular_adjustment, Some(4.0));
+        assert_eq!(resampled[0].angular_adjustment, None);
+
+        // Off-grid ends are left out
+        assert_eq!(resample(&trajectory, 0.3).len(), 3);
+        assert!(resample(&trajectory, 0.0).is_empty() && resample(&trajectory, f64::NAN).is_empty());
+        assert!(resample(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_mean_trajectory_holds_runs_that_ended() {
+        let short: Vec<TrajectoryPoint> = (0..=2).map(|i| point(i as f64, i as f64 * 10.0, 0.0, 170.0)).collect();
+        let long: Vec<TrajectoryPoint> = (0..=4).map(|i| point(i as f64, i as f64 * 10.0, 2.0, -170.0)).collect();
+
+        let (mean, std) = mean_trajectory(&[&short, &long], 1.0);
+        assert_eq!(mean.len(), 5);
+        assert_eq!((mean[1].x, mean[1].y, std[1].x, std[1].y), (10.0, 1.0, 0.0, 1.0));
+        // The short run stays at x=20 once it ended
+        assert_eq!((mean[4].x, std[4].x), (30.0, 10.0));
+        // Headings 20° apart around the seam average to 180°, not 0°
+        assert!((mean[0].angle.abs().0 - 180.0).abs() < 1e-9);
+        assert!(std[0].angle > Degrees(0.0) && std[0].angle < Degrees(20.0));
+
+        let (mean, std) = mean_trajectory(&[&[]], 1.0);
+        assert!(mean.is_empty() && std.is_empty());
+    }
+}
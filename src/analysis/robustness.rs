@@ -0,0 +1,214 @@
+// Robustness sweep: success rate and final angle error of one vehicle type
+// while a disturbance grows level by level
+//
+// Run `i` of every level uses the seed `seed + i`, so the levels differ only in
+// the disturbance and level 0 gives the undisturbed runs of the same seeds.
+
+use serde::{Deserialize, Serialize};
+
+use super::stats::{calculate_stats, mean_of_present};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::simulation::{DropoutConfig, Scenario, ScenarioVehicle};
+use crate::vehicle::VehicleType;
+
+/// Dropout durations of a sweep over a scenario without its own (seconds)
+pub const DEFAULT_DROPOUT_DURATION: (f64, f64) = (1.0, 3.0);
+
+/// Disturbance a sweep grows
+///
+/// Sensor dropouts are the only disturbance the simulation models so far,
+/// observation noise and currents would be axes of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RobustnessAxis {
+    /// Expected sensor dropout starts per simulated minute
+    Dropout,
+}
+
+impl RobustnessAxis {
+    pub const ALL: [RobustnessAxis; 1] = [RobustnessAxis::Dropout];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            RobustnessAxis::Dropout => "dropout",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|axis| axis.id() == s)
+    }
+
+    /// Axis title of the plots
+    pub fn label(&self) -> &'static str {
+        match self {
+            RobustnessAxis::Dropout => "Cortes de sensor por minuto",
+        }
+    }
+
+    /// `scenario` disturbed at `level`, drawing the disturbance from `seed`
+    ///
+    /// Dropouts keep the durations of the scenario config, or `DEFAULT_DROPOUT_DURATION`.
+    pub fn apply(&self, scenario: &Scenario, level: f64, seed: u64) -> Scenario {
+        match self {
+            RobustnessAxis::Dropout => {
+                let duration_range = scenario.dropout.map_or(DEFAULT_DROPOUT_DURATION, |dropout| dropout.duration_range);
+                Scenario {
+                    dropout: Some(DropoutConfig { probability_per_minute: level, duration_range, seed }),
+                    ..scenario.clone()
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RobustnessAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Outcome of the runs at one disturbance level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobustnessLevel {
+    pub level: f64,
+    pub runs: usize,
+    pub successes: usize,
+    /// Percentage of the runs that arrived
+    pub success_rate: f64,
+    /// Mean over every run (degrees)
+    pub mean_final_angle_error: f64,
+    /// Mean seconds per run the controller went without fresh observations, dropout axis only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_dropout_seconds: Option<f64>,
+}
+
+/// Sweep of one vehicle type, a point per level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobustnessCurve {
+    pub vehicle_type: String,
+    pub levels: Vec<RobustnessLevel>,
+}
+
+/// Sweeps of several vehicle types over the same levels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobustnessReport {
+    pub axis: RobustnessAxis,
+    pub iterations: usize,
+    pub seed: u64,
+    /// One per vehicle type, in the order asked for
+    pub curves: Vec<RobustnessCurve>,
+}
+
+/// `robustness_sweep` of every vehicle type in `vehicle_types`
+pub fn robustness_report(
+    scenario: &Scenario,
+    vehicle_types: &[VehicleType],
+    axis: RobustnessAxis,
+    levels: &[f64],
+    iterations: usize,
+    seed: u64,
+) -> Result<RobustnessReport, FuzzyNavError> {
+    if vehicle_types.is_empty() {
+        return Err(SimulationError::NoVehicles.into());
+    }
+    let curves = vehicle_types
+        .iter()
+        .map(|&vehicle_type| {
+            let levels = robustness_sweep(scenario, vehicle_type, axis, levels, iterations, seed)?;
+            Ok(RobustnessCurve { vehicle_type: vehicle_type.id().to_string(), levels })
+        })
+        .collect::<Result<_, FuzzyNavError>>()?;
+    Ok(RobustnessReport { axis, iterations, seed, curves })
+}
+
+/// Run `iterations` runs of `vehicle_type` alone in `scenario` at every level of `axis`
+///
+/// The vehicles of the scenario are replaced by one random start of
+/// `vehicle_type`, everything else (map, timing, criteria, heading) is kept.
+pub fn robustness_sweep(
+    scenario: &Scenario,
+    vehicle_type: VehicleType,
+    axis: RobustnessAxis,
+    levels: &[f64],
+    iterations: usize,
+    seed: u64,
+) -> Result<Vec<RobustnessLevel>, FuzzyNavError> {
+    if levels.is_empty() {
+        return Err(SimulationError::NoRobustnessLevels.into());
+    }
+    if iterations == 0 {
+        return Err(SimulationError::NoIterations.into());
+    }
+    let alone = Scenario { vehicles: vec![ScenarioVehicle::random(vehicle_type)], ..scenario.clone() };
+
+    levels
+        .iter()
+        .map(|&level| {
+            let mut metrics = Vec::with_capacity(iterations);
+            for iteration in 0..iterations {
+                let run_seed = seed.wrapping_add(iteration as u64);
+                let disturbed = Scenario { seed: Some(run_seed), ..axis.apply(&alone, level, run_seed) };
+                metrics.extend(disturbed.run()?.vehicles.into_iter().map(|vehicle| vehicle.metrics));
+            }
+            let successes = metrics.iter().filter(|m| m.success).count();
+            let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
+            Ok(RobustnessLevel {
+                level,
+                runs: metrics.len(),
+                successes,
+                success_rate: successes as f64 / metrics.len() as f64 * 100.0,
+                mean_final_angle_error: calculate_stats(&angle_errors).0,
+                mean_dropout_seconds: mean_of_present(metrics.iter().map(|m| m.dropout_seconds_total)),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short() -> Scenario {
+        Scenario { max_time: 60.0, ..Scenario::default_exam() }
+    }
+
+    #[test]
+    fn test_level_zero_matches_the_undisturbed_runs() {
+        let levels = robustness_sweep(&short(), VehicleType::Agile, RobustnessAxis::Dropout, &[0.0, 60.0], 3, 11).unwrap();
+        assert_eq!(levels.len(), 2);
+
+        let baseline: Vec<_> = (0..3u64)
+            .map(|i| {
+                let scenario = Scenario { vehicles: vec![ScenarioVehicle::random(VehicleType::Agile)], seed: Some(11 + i), ..short() };
+                scenario.run().unwrap().vehicles.remove(0).metrics
+            })
+            .collect();
+        let successes = baseline.iter().filter(|m| m.success).count();
+        let angle_errors: Vec<f64> = baseline.iter().map(|m| m.final_angle_error).collect();
+        assert_eq!((levels[0].runs, levels[0].successes), (3, successes));
+        assert_eq!(levels[0].mean_final_angle_error, calculate_stats(&angle_errors).0);
+        assert_eq!(levels[0].mean_dropout_seconds, Some(0.0));
+    }
+
+    #[test]
+    fn test_levels_reach_the_simulation() {
+        let scenario = short();
+        let disturbed = RobustnessAxis::Dropout.apply(&scenario, 12.0, 5);
+        assert_eq!(disturbed.dropout, Some(DropoutConfig { probability_per_minute: 12.0, duration_range: DEFAULT_DROPOUT_DURATION, seed: 5 }));
+        assert_eq!(Scenario { dropout: None, ..disturbed }, scenario);
+
+        let levels = robustness_sweep(&scenario, VehicleType::Heavy, RobustnessAxis::Dropout, &[0.0, 60.0], 2, 3).unwrap();
+        let blind: Vec<f64> = levels.iter().map(|level| level.mean_dropout_seconds.unwrap()).collect();
+        assert!(blind[0] == 0.0 && blind[1] > 0.0, "{:?}", blind);
+    }
+
+    #[test]
+    fn test_sweep_needs_levels_and_iterations() {
+        let sweep = |levels: &[f64], iterations| robustness_sweep(&short(), VehicleType::Standard, RobustnessAxis::Dropout, levels, iterations, 0);
+        assert!(matches!(sweep(&[], 2), Err(FuzzyNavError::Simulation(SimulationError::NoRobustnessLevels))));
+        assert!(matches!(sweep(&[1.0], 0), Err(FuzzyNavError::Simulation(SimulationError::NoIterations))));
+        assert!(matches!(sweep(&[-1.0], 1), Err(FuzzyNavError::Simulation(SimulationError::InvalidDropout { .. }))));
+        assert_eq!(RobustnessAxis::parse("dropout"), Some(RobustnessAxis::Dropout));
+        assert_eq!(RobustnessAxis::parse("wind"), None);
+    }
+}
@@ -0,0 +1,187 @@
+// Path similarity of two trajectories: discrete Fréchet distance and mean
+// deviation after aligning them in time
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::TrajectoryPoint;
+
+/// How far apart two trajectories are, in map units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrajectorySimilarity {
+    /// Discrete Fréchet distance between the recorded paths, regardless of timing
+    pub frechet_distance: f64,
+    /// Mean distance between the positions at the same time, over the time both
+    /// runs last, `None` when they do not overlap in time
+    pub mean_deviation: Option<f64>,
+    /// Largest of those distances
+    pub max_deviation: Option<f64>,
+    /// Common times the deviations were measured at
+    pub aligned_samples: usize,
+}
+
+/// Fréchet distance and time-aligned deviation of two trajectories, `None` if either is empty
+pub fn trajectory_similarity(a: &[TrajectoryPoint], b: &[TrajectoryPoint]) -> Option<TrajectorySimilarity> {
+    let frechet_distance = discrete_frechet(a, b)?;
+    let deviations = aligned_deviations(a, b);
+    let mean_deviation = (!deviations.is_empty()).then(|| deviations.iter().sum::<f64>() / deviations.len() as f64);
+    let max_deviation = deviations.iter().copied().reduce(f64::max);
+
+    Some(TrajectorySimilarity {
+        frechet_distance,
+        mean_deviation,
+        max_deviation,
+        aligned_samples: deviations.len(),
+    })
+}
+
+fn distance(a: &TrajectoryPoint, b: &TrajectoryPoint) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+/// Discrete Fréchet distance of the two point sequences, `None` if either is empty
+///
+/// Dynamic programming over the n×m coupling table in O(nm) time, keeping a
+/// single row of it.
+pub fn discrete_frechet(a: &[TrajectoryPoint], b: &[TrajectoryPoint]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    // row[j]: Fréchet distance of a[..=i] and b[..=j]
+    let mut row: Vec<f64> = Vec::with_capacity(b.len());
+    for (j, point) in b.iter().enumerate() {
+        let d = distance(&a[0], point);
+        row.push(if j == 0 { d } else { d.max(row[j - 1]) });
+    }
+
+    for point_a in &a[1..] {
+        let mut diagonal = row[0];
+        row[0] = row[0].max(distance(point_a, &b[0]));
+        for j in 1..b.len() {
+            let reachable = diagonal.min(row[j]).min(row[j - 1]);
+            diagonal = row[j];
+            row[j] = reachable.max(distance(point_a, &b[j]));
+        }
+    }
+
+    row.last().copied()
+}
+
+/// Position of the trajectory at time `t`, interpolated between the points around it
+///
+/// `t` must lie within the recorded times.
+fn position_at(trajectory: &[TrajectoryPoint], t: f64) -> (f64, f64) {
+    let next = trajectory.partition_point(|p| p.t < t).min(trajectory.len() - 1);
+    let after = &trajectory[next];
+    if next == 0 || after.t <= t {
+        return (after.x, after.y);
+    }
+    let before = &trajectory[next - 1];
+    let s = (t - before.t) / (after.t - before.t);
+    (before.x + s * (after.x - before.x), before.y + s * (after.y - before.y))
+}
+
+/// Distance between the two runs at common times over the span both cover
+///
+/// The span is sampled at the finer of the two mean time steps, so runs
+/// recorded with different `dt` are compared at the same instants.
+fn aligned_deviations(a: &[TrajectoryPoint], b: &[TrajectoryPoint]) -> Vec<f64> {
+    let (Some(first_a), Some(last_a), Some(first_b), Some(last_b)) = (a.first(), a.last(), b.first(), b.last()) else {
+        return Vec::new();
+    };
+    let start = first_a.t.max(first_b.t);
+    let end = last_a.t.min(last_b.t);
+    if start > end {
+        return Vec::new();
+    }
+
+    let mean_step = |trajectory: &[TrajectoryPoint], first: f64, last: f64| {
+        (trajectory.len() > 1).then(|| (last - first) / (trajectory.len() - 1) as f64)
+    };
+    let step = [mean_step(a, first_a.t, last_a.t), mean_step(b, first_b.t, last_b.t)]
+        .into_iter()
+        .flatten()
+        .filter(|step| *step > 0.0)
+        .fold(f64::INFINITY, f64::min);
+    let samples = if step.is_finite() { ((end - start) / step).round() as usize + 1 } else { 1 };
+
+    (0..samples)
+        .map(|i| {
+            let t = if samples == 1 { start } else { start + (end - start) * i as f64 / (samples - 1) as f64 };
+            let (xa, ya) = position_at(a, t);
+            let (xb, yb) = position_at(b, t);
+            (xa - xb).hypot(ya - yb)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(points: &[(f64, f64, f64)]) -> Vec<TrajectoryPoint> {
+        points.iter().map(|&(t, x, y)| TrajectoryPoint { t, x, y, ..Default::default() }).collect()
+    }
+
+    /// Straight segment along x at height `y`, one point per second
+    fn segment(y: f64, points: usize) -> Vec<TrajectoryPoint> {
+        (0..points).map(|i| TrajectoryPoint { t: i as f64, x: i as f64 * 10.0, y, ..Default::default() }).collect()
+    }
+
+    #[test]
+    fn test_parallel_segments_are_their_spacing_apart() {
+        let similarity = trajectory_similarity(&segment(0.0, 11), &segment(7.5, 11)).unwrap();
+        assert!((similarity.frechet_distance - 7.5).abs() < 1e-12);
+        assert!((similarity.mean_deviation.unwrap() - 7.5).abs() < 1e-12);
+        assert!((similarity.max_deviation.unwrap() - 7.5).abs() < 1e-12);
+        assert_eq!(similarity.aligned_samples, 11);
+    }
+
+    #[test]
+    fn test_frechet_of_known_paths() {
+        let a = segment(0.0, 5);
+        assert_eq!(discrete_frechet(&a, &a), Some(0.0));
+        assert_eq!(discrete_frechet(&a, &[]), None);
+
+        // One point against a path: the farthest point of the path
+        let origin = path(&[(0.0, 0.0, 0.0)]);
+        assert_eq!(discrete_frechet(&origin, &a), Some(40.0));
+
+        // A detour of 5 units in the middle of an otherwise shared path
+        let detour = path(&[(0.0, 0.0, 0.0), (1.0, 10.0, 0.0), (2.0, 20.0, 5.0), (3.0, 30.0, 0.0), (4.0, 40.0, 0.0)]);
+        assert!((discrete_frechet(&a, &detour).unwrap() - 5.0).abs() < 1e-12);
+
+        // Same path walked in reverse: the ends must be coupled to each other
+        let reversed: Vec<TrajectoryPoint> = a.iter().rev().cloned().collect();
+        assert_eq!(discrete_frechet(&a, &reversed), Some(40.0));
+    }
+
+    #[test]
+    fn test_frechet_ignores_timing_but_deviation_does_not() {
+        // Same straight path, b twice as slow: equal shapes, apart in time
+        let a = path(&[(0.0, 0.0, 0.0), (1.0, 10.0, 0.0), (2.0, 20.0, 0.0)]);
+        let b = path(&[(0.0, 0.0, 0.0), (1.0, 5.0, 0.0), (2.0, 10.0, 0.0), (3.0, 15.0, 0.0), (4.0, 20.0, 0.0)]);
+
+        let similarity = trajectory_similarity(&a, &b).unwrap();
+        assert!((similarity.frechet_distance - 5.0).abs() < 1e-12);
+        // Aligned over t in [0, 2] at 1 s: 0, 5 and 10 units apart
+        assert_eq!(similarity.aligned_samples, 3);
+        assert!((similarity.mean_deviation.unwrap() - 5.0).abs() < 1e-12);
+        assert!((similarity.max_deviation.unwrap() - 10.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_alignment_interpolates_between_different_time_steps() {
+        let coarse = path(&[(0.0, 0.0, 0.0), (2.0, 20.0, 0.0)]);
+        let fine = path(&[(0.0, 0.0, 3.0), (0.5, 5.0, 3.0), (1.0, 10.0, 3.0), (1.5, 15.0, 3.0), (2.0, 20.0, 3.0)]);
+
+        let similarity = trajectory_similarity(&coarse, &fine).unwrap();
+        assert_eq!(similarity.aligned_samples, 5);
+        assert!((similarity.mean_deviation.unwrap() - 3.0).abs() < 1e-12);
+
+        let later = path(&[(5.0, 0.0, 0.0), (6.0, 1.0, 0.0)]);
+        let disjoint = trajectory_similarity(&coarse, &later).unwrap();
+        assert_eq!(disjoint.aligned_samples, 0);
+        assert_eq!((disjoint.mean_deviation, disjoint.max_deviation), (None, None));
+    }
+}
@@ -0,0 +1,641 @@
+// Summary statistics and confidence intervals of benchmark results
+
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimulationError;
+use crate::map::{euclidean_distance, normalize_angle, Point};
+use crate::units::Radians;
+
+/// Confidence level of the reported intervals unless configured otherwise
+pub const DEFAULT_CONFIDENCE: f64 = 0.95;
+
+/// Resamples drawn for each bootstrap interval
+pub const BOOTSTRAP_RESAMPLES: usize = 2000;
+
+/// Sum of `values` in iteration order with Neumaier compensation
+///
+/// The order is fixed by the caller (iteration, then vehicle order in the
+/// benchmarks), and the compensation keeps the rounding from depending on how
+/// large values and small ones interleave, so aggregates of the same runs match
+/// on every platform.
+pub fn stable_sum(values: impl IntoIterator<Item = f64>) -> f64 {
+    let (mut sum, mut compensation) = (0.0f64, 0.0f64);
+    for value in values {
+        let total = sum + value;
+        compensation += if sum.abs() >= value.abs() { (sum - total) + value } else { (value - total) + sum };
+        sum = total;
+    }
+    sum + compensation
+}
+
+/// Mean, population standard deviation, min and max, all zero for no values
+pub fn calculate_stats(values: &[f64]) -> (f64, f64, f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0, 0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = stable_sum(values.iter().copied()) / n;
+    // Squares as products, `powi` may round differently between targets
+    let variance = stable_sum(values.iter().map(|x| (x - mean) * (x - mean))) / n;
+    let std = variance.sqrt();
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    (mean, std, min, max)
+}
+
+/// Standard error of the mean, from the sample standard deviation, None below two values
+pub fn standard_error(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = stable_sum(values.iter().copied()) / n;
+    let variance = stable_sum(values.iter().map(|x| (x - mean) * (x - mean))) / (n - 1.0);
+    Some((variance / n).sqrt())
+}
+
+/// Mean of the values that are present, None when none is
+pub fn mean_of_present(values: impl IntoIterator<Item = Option<f64>>) -> Option<f64> {
+    let present: Vec<f64> = values.into_iter().flatten().collect();
+    (!present.is_empty()).then(|| calculate_stats(&present).0)
+}
+
+/// Nearest-rank percentile, `fraction` in [0, 1] (0.95 for the p95), zero for no values
+pub fn percentile(values: &[f64], fraction: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = (fraction.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.saturating_sub(1)]
+}
+
+/// True if `confidence` can be used for an interval, strictly between 0 and 1
+pub fn is_valid_confidence(confidence: f64) -> bool {
+    confidence > 0.0 && confidence < 1.0
+}
+
+/// Two-sided critical value of the standard normal distribution (1.96 for 0.95)
+///
+/// Inverse normal CDF by Acklam's rational approximation, relative error below 1.2e-9.
+pub fn z_score(confidence: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416,
+    ];
+
+    // Upper tail probability, z is the quantile at 1 - p
+    let p = (1.0 - confidence) / 2.0;
+    if p < 0.02425 {
+        let q = (-2.0 * p.ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        -(((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Wilson score interval of a success proportion, as fractions in [0, 1]
+///
+/// Unlike the normal approximation it stays inside [0, 1] and does not collapse
+/// to a single point at 0 or all successes. Without runs nothing is known, (0, 1).
+pub fn wilson_interval(successes: usize, total: usize, confidence: f64) -> (f64, f64) {
+    if total == 0 {
+        return (0.0, 1.0);
+    }
+    let n = total as f64;
+    let p = successes as f64 / n;
+    let z = z_score(confidence);
+    let z2 = z * z;
+
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half_width = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - half_width).max(0.0), (center + half_width).min(1.0))
+}
+
+/// Percentile bootstrap interval of the mean, resampled from a generator seeded with `seed`
+///
+/// Same values and seed give the same interval. All zero for no values, like `calculate_stats`.
+pub fn bootstrap_mean_interval(values: &[f64], confidence: f64, resamples: usize, seed: u64) -> (f64, f64) {
+    if values.is_empty() || resamples == 0 {
+        return (0.0, 0.0);
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut means: Vec<f64> = (0..resamples)
+        .map(|_| stable_sum((0..values.len()).map(|_| values[rng.gen_range(0..values.len())])) / values.len() as f64)
+        .collect();
+    means.sort_by(f64::total_cmp);
+
+    let alpha = (1.0 - confidence) / 2.0;
+    let last = (resamples - 1) as f64;
+    let low = means[(alpha * last).round() as usize];
+    let high = means[((1.0 - alpha) * last).round() as usize];
+    (low, high)
+}
+
+/// Fraction of the runs arrived by each of `bins + 1` evenly spaced times from 0 to `max_time`
+///
+/// Point k is at `k * max_time / bins`. A run counts from its arrival time on,
+/// one that never arrived stays in the denominator throughout. Empty without
+/// runs or bins, or for a `max_time` that is not positive and finite.
+pub fn arrival_funnel(arrival_times: &[Option<f64>], max_time: f64, bins: usize) -> Vec<(f64, f64)> {
+    if arrival_times.is_empty() || bins == 0 || !(max_time > 0.0 && max_time.is_finite()) {
+        return Vec::new();
+    }
+    let mut arrived: Vec<f64> = arrival_times.iter().flatten().copied().collect();
+    arrived.sort_by(f64::total_cmp);
+    let total = arrival_times.len() as f64;
+    (0..=bins)
+        .map(|k| {
+            let t = k as f64 * max_time / bins as f64;
+            // Tolerance for arrivals that land on a bin time up to rounding
+            let count = arrived.partition_point(|&arrival| arrival <= t + 1e-9 * max_time);
+            (t, count as f64 / total)
+        })
+        .collect()
+}
+
+/// Interquartile ranges past the quartiles beyond which a value is an outlier (Tukey's fences)
+pub const IQR_FENCE: f64 = 1.5;
+
+/// Modified z-score above which a value is an outlier (Iglewicz and Hoaglin)
+pub const MODIFIED_Z_THRESHOLD: f64 = 3.5;
+
+/// Fewest values outliers are looked for in, the quartiles of smaller samples say little
+pub const MIN_OUTLIER_SAMPLE: usize = 4;
+
+/// Quantile by linear interpolation between the closest ranks of `sorted`, `fraction` in [0, 1]
+fn interpolated_quantile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = fraction.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// Values outside Tukey's fences, `IQR_FENCE` interquartile ranges below the first quartile or above the third
+///
+/// None is flagged in samples under `MIN_OUTLIER_SAMPLE` values.
+pub fn iqr_outliers(values: &[f64]) -> Vec<bool> {
+    if values.len() < MIN_OUTLIER_SAMPLE {
+        return vec![false; values.len()];
+    }
+    let sorted = sorted(values);
+    let (q1, q3) = (interpolated_quantile(&sorted, 0.25), interpolated_quantile(&sorted, 0.75));
+    let fence = IQR_FENCE * (q3 - q1);
+    values.iter().map(|&x| x < q1 - fence || x > q3 + fence).collect()
+}
+
+/// Values whose modified z-score, 0.6745·|x - median| / MAD, is above `MODIFIED_Z_THRESHOLD`
+///
+/// MAD is the median absolute deviation from the median. None is flagged in
+/// samples under `MIN_OUTLIER_SAMPLE` values, or when more than half the values
+/// are equal and the MAD is 0.
+pub fn modified_z_outliers(values: &[f64]) -> Vec<bool> {
+    if values.len() < MIN_OUTLIER_SAMPLE {
+        return vec![false; values.len()];
+    }
+    let median = interpolated_quantile(&sorted(values), 0.5);
+    let deviations: Vec<f64> = values.iter().map(|x| (x - median).abs()).collect();
+    let mad = interpolated_quantile(&sorted(&deviations), 0.5);
+    if mad < f64::EPSILON {
+        return vec![false; values.len()];
+    }
+    deviations.iter().map(|deviation| 0.6745 * deviation / mad > MODIFIED_Z_THRESHOLD).collect()
+}
+
+/// Values flagged by either `iqr_outliers` or `modified_z_outliers`
+pub fn outliers(values: &[f64]) -> Vec<bool> {
+    iqr_outliers(values).into_iter().zip(modified_z_outliers(values)).map(|(iqr, z)| iqr || z).collect()
+}
+
+/// Mean and population standard deviation of a sample without its `outliers`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrimmedStats {
+    pub mean: f64,
+    pub std: f64,
+    /// Values left out
+    pub outliers: usize,
+}
+
+impl TrimmedStats {
+    /// Statistics of `values` minus their outliers, all zero for no values like `calculate_stats`
+    pub fn new(values: &[f64]) -> Self {
+        let flags = outliers(values);
+        let kept: Vec<f64> = values.iter().zip(&flags).filter(|(_, &outlier)| !outlier).map(|(&x, _)| x).collect();
+        let (mean, std, _, _) = calculate_stats(&kept);
+        Self { mean, std, outliers: values.len() - kept.len() }
+    }
+}
+
+/// Metrics of one benchmark run that are outliers of its (heading, vehicle type) group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricOutliers {
+    pub arrival_time: bool,
+    pub distance_traveled: bool,
+}
+
+impl MetricOutliers {
+    /// Flags of every run of a group, from its counted arrival time (`None` when
+    /// it did not arrive or is left out) and its distance traveled
+    pub fn of_runs(arrival_times: &[Option<f64>], distances: &[f64]) -> Vec<Self> {
+        let arrived: Vec<f64> = arrival_times.iter().flatten().copied().collect();
+        let mut arrival_flags = outliers(&arrived).into_iter();
+        arrival_times
+            .iter()
+            .zip(outliers(distances))
+            .map(|(arrival, distance_traveled)| Self {
+                arrival_time: arrival.is_some() && arrival_flags.next().unwrap_or(false),
+                distance_traveled,
+            })
+            .collect()
+    }
+
+    pub fn any(&self) -> bool {
+        self.arrival_time || self.distance_traveled
+    }
+}
+
+/// Run with an outlier metric, with what it takes to replay it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlierRun {
+    /// 1-based benchmark iteration
+    pub iteration: usize,
+    /// Seed the start states of the iteration are drawn from, see `BenchmarkConfig::iteration_seed`
+    pub seed: u64,
+    pub is_outlier: MetricOutliers,
+}
+
+impl OutlierRun {
+    /// Outlier runs of a group whose `flags` are in iteration order, `seed` of the 0-based iteration
+    pub fn list(flags: &[MetricOutliers], seed: impl Fn(usize) -> u64) -> Vec<Self> {
+        flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flags)| flags.any())
+            .map(|(i, &is_outlier)| Self { iteration: i + 1, seed: seed(i), is_outlier })
+            .collect()
+    }
+}
+
+/// Start of a run as seen from its target, what makes a start easy or hard
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InitialCondition {
+    /// Distance from the start position to the target (map units)
+    pub distance_to_target: f64,
+    /// Angle between the start heading and the bearing to the target, in [0, 180] degrees
+    pub heading_error: f64,
+}
+
+impl InitialCondition {
+    /// Condition of a start at `position` heading `angle` towards `target`
+    pub fn new(position: &Point, angle: Radians, target: &Point) -> Self {
+        let bearing = Radians((target.y - position.y).atan2(target.x - position.x));
+        Self {
+            distance_to_target: euclidean_distance(position, target),
+            heading_error: normalize_angle(angle - bearing).abs().to_degrees().0,
+        }
+    }
+}
+
+/// Interval of one bucket, `None` ends are unbounded
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BucketRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl BucketRange {
+    /// The intervals `edges` split the line into, `[edges[i-1], edges[i])`
+    fn split(edges: &[f64]) -> Vec<Self> {
+        (0..=edges.len())
+            .map(|i| Self { min: i.checked_sub(1).map(|j| edges[j]), max: edges.get(i).copied() })
+            .collect()
+    }
+}
+
+impl fmt::Display for BucketRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => write!(f, "[{}, {})", min, max),
+            (Some(min), None) => write!(f, ">= {}", min),
+            (None, Some(max)) => write!(f, "< {}", max),
+            (None, None) => write!(f, "any"),
+        }
+    }
+}
+
+/// Group of runs with similar initial conditions, a range of each split quantity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InitialConditionBucket {
+    /// Range of the distance to the target, `None` when not split by distance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_to_target: Option<BucketRange>,
+    /// Range of the heading error (degrees), `None` when not split by heading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading_error: Option<BucketRange>,
+}
+
+impl fmt::Display for InitialConditionBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.distance_to_target, self.heading_error) {
+            (Some(distance), Some(heading)) => write!(f, "distance {}, heading error {}°", distance, heading),
+            (Some(distance), None) => write!(f, "distance {}", distance),
+            (None, Some(heading)) => write!(f, "heading error {}°", heading),
+            (None, None) => write!(f, "all starts"),
+        }
+    }
+}
+
+/// Bin edges grouping runs by initial condition, a quantity without edges is not split
+///
+/// `n` edges make `n + 1` buckets of that quantity, the outer ones unbounded;
+/// with both quantities split every combination is a bucket.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InitialConditionBuckets {
+    /// Edges of the distance to the target (map units)
+    #[serde(default)]
+    pub distance_edges: Vec<f64>,
+    /// Edges of the heading error (degrees)
+    #[serde(default)]
+    pub heading_error_edges: Vec<f64>,
+}
+
+impl InitialConditionBuckets {
+    /// Reject edges that are not finite and strictly increasing, or that split nothing
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.distance_edges.is_empty() && self.heading_error_edges.is_empty() {
+            return Err(SimulationError::NoBucketEdges);
+        }
+        for edges in [&self.distance_edges, &self.heading_error_edges] {
+            if edges.iter().any(|edge| !edge.is_finite()) || edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(SimulationError::InvalidBucketEdges(edges.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Every bucket, distance-major
+    pub fn buckets(&self) -> Vec<InitialConditionBucket> {
+        let split = |edges: &[f64]| -> Vec<Option<BucketRange>> {
+            if edges.is_empty() {
+                vec![None]
+            } else {
+                BucketRange::split(edges).into_iter().map(Some).collect()
+            }
+        };
+        let headings = split(&self.heading_error_edges);
+        split(&self.distance_edges)
+            .into_iter()
+            .flat_map(|distance_to_target| {
+                headings
+                    .iter()
+                    .map(move |&heading_error| InitialConditionBucket { distance_to_target, heading_error })
+            })
+            .collect()
+    }
+
+    /// Index in `buckets` of the bucket holding `condition`
+    pub fn index_of(&self, condition: &InitialCondition) -> usize {
+        let bin = |edges: &[f64], value: f64| edges.partition_point(|&edge| edge <= value);
+        bin(&self.distance_edges, condition.distance_to_target) * (self.heading_error_edges.len() + 1)
+            + bin(&self.heading_error_edges, condition.heading_error)
+    }
+
+    /// Runs of every non-empty bucket, in bucket order and keeping the order of `runs`
+    pub fn group<'a, T>(
+        &self,
+        runs: &'a [T],
+        condition: impl Fn(&T) -> InitialCondition,
+    ) -> Vec<(InitialConditionBucket, Vec<&'a T>)> {
+        let mut grouped: Vec<(InitialConditionBucket, Vec<&T>)> =
+            self.buckets().into_iter().map(|bucket| (bucket, Vec::new())).collect();
+        for run in runs {
+            grouped[self.index_of(&condition(run))].1.push(run);
+        }
+        grouped.retain(|(_, runs)| !runs.is_empty());
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Degrees;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        assert!((actual.1 - expected.1).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_mean_of_present_skips_missing_values() {
+        assert_eq!(mean_of_present([Some(2.0), None, Some(4.0)]), Some(3.0));
+        assert_eq!(mean_of_present([None, None]), None);
+    }
+
+    #[test]
+    fn test_stable_sum_keeps_small_terms_next_to_large_ones() {
+        assert_eq!([1e16, 1.0, -1e16].iter().sum::<f64>(), 0.0);
+        assert_eq!(stable_sum([1e16, 1.0, -1e16]), 1.0);
+        assert_eq!(stable_sum([1.0, 1e16, -1e16]), 1.0);
+        assert_eq!(stable_sum([]), 0.0);
+    }
+
+    /// Twelve arrival times around 30 s and two orbiters
+    const ORBITER_SAMPLE: [f64; 14] = [29.0, 31.0, 30.0, 28.5, 32.0, 30.5, 29.5, 31.5, 30.0, 118.0, 28.0, 32.5, 30.0, 145.0];
+
+    #[test]
+    fn test_outliers_flag_the_orbiters() {
+        let flagged: Vec<usize> = (0..ORBITER_SAMPLE.len()).filter(|&i| outliers(&ORBITER_SAMPLE)[i]).collect();
+        assert_eq!(flagged, [9, 13]);
+        assert_eq!(iqr_outliers(&ORBITER_SAMPLE), modified_z_outliers(&ORBITER_SAMPLE));
+
+        // Too few values, or more than half of them equal for the MAD
+        assert_eq!(outliers(&[1.0, 2.0, 100.0]), [false; 3]);
+        assert_eq!(modified_z_outliers(&[5.0, 5.0, 5.0, 5.0, 9.0]), [false; 5]);
+        assert_eq!(iqr_outliers(&[5.0, 5.0, 5.0, 5.0, 9.0]), [false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_trimmed_stats_leave_the_outliers_out() {
+        let trimmed = TrimmedStats::new(&ORBITER_SAMPLE);
+        let kept: Vec<f64> = ORBITER_SAMPLE.iter().copied().filter(|&x| x < 100.0).collect();
+        let (mean, std, _, _) = calculate_stats(&kept);
+        assert_eq!(trimmed, TrimmedStats { mean, std, outliers: 2 });
+        assert!((trimmed.mean - 30.208).abs() < 1e-3);
+        assert!(calculate_stats(&ORBITER_SAMPLE).1 > 10.0 * trimmed.std);
+        assert_eq!(TrimmedStats::new(&[]), TrimmedStats::default());
+    }
+
+    #[test]
+    fn test_outlier_runs_keep_their_iteration_and_seed() {
+        let mut arrivals: Vec<Option<f64>> = ORBITER_SAMPLE.iter().copied().map(Some).collect();
+        arrivals.insert(2, None);
+        let distances: Vec<f64> = (0..arrivals.len()).map(|i| if i == 5 { 9000.0 } else { 400.0 + i as f64 }).collect();
+        let flags = MetricOutliers::of_runs(&arrivals, &distances);
+
+        let runs = OutlierRun::list(&flags, |i| 100 + i as u64);
+        let listed: Vec<(usize, u64, MetricOutliers)> = runs.iter().map(|run| (run.iteration, run.seed, run.is_outlier)).collect();
+        assert_eq!(listed, [
+            (6, 105, MetricOutliers { arrival_time: false, distance_traveled: true }),
+            (11, 110, MetricOutliers { arrival_time: true, distance_traveled: false }),
+            (15, 114, MetricOutliers { arrival_time: true, distance_traveled: false }),
+        ]);
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let values: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        assert_eq!(percentile(&values, 0.95), 19.0);
+        assert_eq!(percentile(&values, 0.5), 10.0);
+        assert_eq!(percentile(&values, 0.0), 1.0);
+        assert_eq!(percentile(&values, 1.0), 20.0);
+        assert_eq!(percentile(&[3.5], 0.95), 3.5);
+        assert_eq!(percentile(&[], 0.95), 0.0);
+    }
+
+    #[test]
+    fn test_arrival_funnel_counts_the_runs_arrived_by_each_time() {
+        let times = [Some(10.0), Some(25.0), None, Some(40.0), Some(100.0), None, Some(0.0), Some(55.0)];
+        let funnel = arrival_funnel(&times, 100.0, 4);
+        assert_eq!(funnel, [(0.0, 0.125), (25.0, 0.375), (50.0, 0.5), (75.0, 0.625), (100.0, 0.75)]);
+
+        // Runs that never arrive keep the fraction below one
+        let funnel = arrival_funnel(&[None, None, Some(3.0)], 6.0, 3);
+        assert_eq!(funnel.iter().map(|p| p.1).collect::<Vec<_>>(), [0.0, 0.0, 1.0 / 3.0, 1.0 / 3.0]);
+
+        // Arrivals on a bin time up to rounding count in that bin
+        assert_eq!(arrival_funnel(&[Some(0.1 + 0.2)], 0.9, 3)[1], (0.3, 1.0));
+    }
+
+    #[test]
+    fn test_arrival_funnel_of_no_runs_is_empty() {
+        assert!(arrival_funnel(&[], 100.0, 10).is_empty());
+        assert!(arrival_funnel(&[Some(1.0)], 100.0, 0).is_empty());
+        assert!(arrival_funnel(&[Some(1.0)], 0.0, 10).is_empty());
+        assert!(arrival_funnel(&[Some(1.0)], f64::INFINITY, 10).is_empty());
+    }
+
+    #[test]
+    fn test_z_score_matches_normal_table() {
+        assert!((z_score(0.95) - 1.959964).abs() < 1e-6);
+        assert!((z_score(0.90) - 1.644854).abs() < 1e-6);
+        assert!((z_score(0.99) - 2.575829).abs() < 1e-6);
+        assert!((z_score(0.999) - 3.290527).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_standard_error_uses_the_sample_deviation() {
+        // Sample variance 2.5, over 5 values
+        assert!((standard_error(&[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap() - 0.5f64.sqrt()).abs() < 1e-12);
+        assert_eq!(standard_error(&[7.0, 7.0]), Some(0.0));
+        assert_eq!(standard_error(&[7.0]), None);
+    }
+
+    #[test]
+    fn test_wilson_interval_known_values() {
+        // 28 of 30 runs, the "93.3% over 30 runs" case
+        assert_close(wilson_interval(28, 30, 0.95), (0.786765, 0.981523));
+        assert_close(wilson_interval(5, 10, 0.95), (0.236593, 0.763407));
+        assert_close(wilson_interval(0, 10, 0.95), (0.0, 0.277532));
+        assert_close(wilson_interval(10, 10, 0.95), (0.722468, 1.0));
+        assert_close(wilson_interval(81, 263, 0.95), (0.255289, 0.366210));
+    }
+
+    #[test]
+    fn test_wilson_interval_narrows_with_lower_confidence_and_more_runs() {
+        let (low_95, high_95) = wilson_interval(28, 30, 0.95);
+        let (low_90, high_90) = wilson_interval(28, 30, 0.90);
+        let (low_big, high_big) = wilson_interval(280, 300, 0.95);
+        assert!(low_90 > low_95 && high_90 < high_95);
+        assert!(high_big - low_big < high_95 - low_95);
+        assert_eq!(wilson_interval(0, 0, 0.95), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_bootstrap_interval_is_deterministic_under_seed() {
+        let values = [41.2, 38.9, 45.0, 39.7, 52.3, 40.1, 43.8, 37.5];
+        let a = bootstrap_mean_interval(&values, 0.95, BOOTSTRAP_RESAMPLES, 42);
+        let b = bootstrap_mean_interval(&values, 0.95, BOOTSTRAP_RESAMPLES, 42);
+        assert_eq!(a, b);
+
+        let (mean, _, min, max) = calculate_stats(&values);
+        assert!(min <= a.0 && a.0 < mean && mean < a.1 && a.1 <= max);
+
+        let (low_80, high_80) = bootstrap_mean_interval(&values, 0.80, BOOTSTRAP_RESAMPLES, 42);
+        assert!(low_80 >= a.0 && high_80 <= a.1);
+    }
+
+    #[test]
+    fn test_bootstrap_interval_degenerate_inputs() {
+        assert_eq!(bootstrap_mean_interval(&[], 0.95, BOOTSTRAP_RESAMPLES, 1), (0.0, 0.0));
+        assert_eq!(bootstrap_mean_interval(&[7.5], 0.95, BOOTSTRAP_RESAMPLES, 1), (7.5, 7.5));
+    }
+
+    #[test]
+    fn test_initial_condition_measures_from_the_target() {
+        let target = Point::new(500.0, 700.0);
+        let below = InitialCondition::new(&Point::new(500.0, 100.0), Degrees(90.0).to_radians(), &target);
+        assert!((below.distance_to_target - 600.0).abs() < 1e-9);
+        assert!(below.heading_error.abs() < 1e-9);
+
+        // Facing away, and a start heading east from the left of the target
+        let away = InitialCondition::new(&Point::new(500.0, 100.0), Degrees(-90.0).to_radians(), &target);
+        assert!((away.heading_error - 180.0).abs() < 1e-9);
+        let left = InitialCondition::new(&Point::new(0.0, 200.0), Radians::ZERO, &target);
+        assert!((left.heading_error - 45.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buckets_split_at_the_edges() {
+        let buckets = InitialConditionBuckets { distance_edges: vec![300.0, 600.0], heading_error_edges: vec![90.0] };
+        assert!(buckets.validate().is_ok());
+        assert_eq!(buckets.buckets().len(), 6);
+
+        let condition = |distance_to_target, heading_error| InitialCondition { distance_to_target, heading_error };
+        assert_eq!(buckets.index_of(&condition(10.0, 10.0)), 0);
+        assert_eq!(buckets.index_of(&condition(300.0, 90.0)), 3);
+        assert_eq!(buckets.index_of(&condition(900.0, 120.0)), 5);
+        assert_eq!(buckets.buckets()[3].to_string(), "distance [300, 600), heading error >= 90°");
+
+        let runs = [condition(100.0, 0.0), condition(700.0, 0.0), condition(650.0, 0.0)];
+        let by_distance = InitialConditionBuckets { distance_edges: vec![300.0, 600.0], ..Default::default() };
+        let grouped = by_distance.group(&runs, |run| *run);
+        // The middle bucket is empty and left out
+        let sizes: Vec<(String, usize)> = grouped.iter().map(|(bucket, runs)| (bucket.to_string(), runs.len())).collect();
+        assert_eq!(sizes, [("distance < 300".to_string(), 1), ("distance >= 600".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_buckets_reject_unordered_or_missing_edges() {
+        assert_eq!(InitialConditionBuckets::default().validate(), Err(SimulationError::NoBucketEdges));
+        let unordered = InitialConditionBuckets { distance_edges: vec![600.0, 300.0], ..Default::default() };
+        assert_eq!(unordered.validate(), Err(SimulationError::InvalidBucketEdges(vec![600.0, 300.0])));
+        let infinite = InitialConditionBuckets { heading_error_edges: vec![f64::INFINITY], ..Default::default() };
+        assert!(infinite.validate().is_err());
+    }
+}
@@ -1,16 +1,23 @@
 // API handlers for REST endpoints
+use std::sync::Arc;
+
 use shuttle_axum::axum::{
-    extract::Json,
-    http::StatusCode,
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
-use rayon::prelude::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
 
-use crate::map::Map;
-use crate::simulation::Simulation;
+use crate::build_info::BuildMeta;
+use crate::error::FuzzyNavError;
+use crate::navigation::SharedControllers;
+use crate::simulation::CancelToken;
+#[cfg(feature = "storage")]
+use crate::storage::RunStore;
+use super::jobs::{JobError, JobStore, JOBS_DIR_ENV};
 use super::models::*;
+use super::quota;
+use super::router::ApiState;
+use super::service;
 
 // ============================================================================
 // ERROR HANDLING
@@ -18,14 +25,22 @@ use super::models::*;
 
 pub enum ApiError {
     BadRequest(String),
+    NotFound(String),
+    /// The resource is not in a state that allows the request
+    Conflict(String),
     InternalError(String),
+    /// The projected cost of the request does not fit in the remaining quota of the client
+    QuotaExceeded(Box<QuotaStatus>),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            ApiError::QuotaExceeded(status) => return (StatusCode::TOO_MANY_REQUESTS, Json(status)).into_response(),
         };
 
         let body = Json(ErrorResponse {
@@ -37,6 +52,26 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Invalid requests are client errors, anything else is a server error
+impl From<FuzzyNavError> for ApiError {
+    fn from(error: FuzzyNavError) -> Self {
+        match error {
+            FuzzyNavError::Simulation(_) | FuzzyNavError::Map(_) => ApiError::BadRequest(error.to_string()),
+            FuzzyNavError::Fuzzy(_) | FuzzyNavError::Serialization(_) => ApiError::InternalError(error.to_string()),
+        }
+    }
+}
+
+impl From<JobError> for ApiError {
+    fn from(error: JobError) -> Self {
+        match error {
+            JobError::NotFound(_) => ApiError::NotFound(error.to_string()),
+            JobError::NotResumable { .. } | JobError::NotCompleted { .. } => ApiError::Conflict(error.to_string()),
+            JobError::Run(error) => error.into(),
+        }
+    }
+}
+
 // ============================================================================
 // HEALTH CHECK
 // ============================================================================
@@ -49,299 +84,511 @@ pub async fn health_check() -> Json<HealthResponse> {
     })
 }
 
+/// Controllers of every preset and a short simulation, 503 naming the failed checks when one fails
+///
+/// The report is cached for `health::DEEP_HEALTH_TTL`.
+pub async fn deep_health_check(State(state): State<ApiState>) -> Response {
+    match tokio::task::spawn_blocking(move || state.health.report()).await {
+        Ok(report) if report.is_healthy() => (StatusCode::OK, Json(report)).into_response(),
+        Ok(report) => (StatusCode::SERVICE_UNAVAILABLE, Json(report)).into_response(),
+        Err(e) => {
+            let body = ErrorResponse {
+                error: StatusCode::SERVICE_UNAVAILABLE.to_string(),
+                details: Some(format!("Deep health check task failed: {}", e)),
+            };
+            (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+        }
+    }
+}
+
 // ============================================================================
 // SIMULATION ENDPOINT
 // ============================================================================
 
+/// `?format=geojson` answers with a FeatureCollection placed by the `origin_lon`,
+/// `origin_lat` and `meters_per_unit` query parameters,
+/// `?format=xlsx` with a workbook of the trajectories and metrics
+///
+/// A client that disconnects drops this future, and with it the guard that
+/// cancels the simulation still running in the blocking task. Controllers
+/// come from the server state, shared with every earlier request.
 pub async fn run_simulation(
+    State(state): State<ApiState>,
+    Query(query): Query<SimulationQuery>,
+    headers: HeaderMap,
     Json(request): Json<SimulationRequest>,
-) -> Result<Json<SimulationResponse>, ApiError> {
-    // Parse vehicle types
-    let vehicle_types = request.parse_vehicle_types()
-        .map_err(|e| ApiError::BadRequest(e))?;
-
-    if vehicle_types.is_empty() {
-        return Err(ApiError::BadRequest(
-            "At least one vehicle type must be specified".to_string()
-        ));
+) -> Result<Response, ApiError> {
+    if let Some(quota) = &state.quota {
+        quota.charge(&quota::client_id(&headers), service::simulation_cost(&request)?).map_err(ApiError::QuotaExceeded)?;
     }
-
-    // Create map
-    let map = Map::new(
-        request.map_width,
-        request.map_height,
-        request.target_x,
-        request.target_y,
-    );
+    let cancel = CancelToken::new();
+    let _cancel_on_drop = cancel.drop_guard();
 
     // Run simulations in blocking task to avoid blocking async runtime
-    let vehicles_result = tokio::task::spawn_blocking(move || {
-        let mut simulations: Vec<Simulation> = vehicle_types
-            .iter()
-            .map(|&vtype| Simulation::new(map.clone(), vtype, request.dt, request.max_time))
-            .collect();
-
-        let mut time = 0.0;
-        let mut all_arrived = false;
-
-        while time < request.max_time && !all_arrived {
-            for sim in &mut simulations {
-                if !sim.vehicle.has_arrived {
-                    sim.step();
-                }
-            }
-
-            time += request.dt;
-            all_arrived = simulations.iter().all(|s| s.vehicle.has_arrived);
+    let response = match query.format {
+        ResponseFormat::Json => {
+            let response = tokio::task::spawn_blocking(move || simulate_json(&state, &request, cancel))
+                .await
+                .map_err(|e| ApiError::InternalError(format!("Simulation task failed: {}", e)))??;
+            Json(response).into_response()
         }
+        ResponseFormat::GeoJson => {
+            let georef = query.georef();
+            let geojson = tokio::task::spawn_blocking(move || service::simulate_geojson(&request, georef, &state.controllers, cancel))
+                .await
+                .map_err(|e| ApiError::InternalError(format!("Simulation task failed: {}", e)))??;
+            ([(header::CONTENT_TYPE, "application/geo+json")], geojson).into_response()
+        }
+        ResponseFormat::Xlsx => simulation_xlsx_response(request, state.controllers, cancel).await?,
+    };
 
-        // Collect results
-        let vehicle_results: Vec<VehicleSimulationResult> = simulations
-            .into_iter()
-            .map(|sim| {
-                let success = sim.vehicle.has_arrived;
-                let arrival_time = if success { Some(sim.vehicle.time_elapsed) } else { None };
-
-                // Handle empty trajectory case
-                let (final_distance, final_angle_error) = if let Some(final_point) = sim.trajectory.last() {
-                    (final_point.distance_to_target, (90.0 - final_point.angle).abs())
-                } else {
-                    // If no trajectory points, calculate from current vehicle state
-                    let dx = sim.vehicle.state.position.x - sim.map.target.position.x;
-                    let dy = sim.vehicle.state.position.y - sim.map.target.position.y;
-                    let dist = (dx * dx + dy * dy).sqrt();
-                    let angle_error = (90.0 - sim.vehicle.state.angle.to_degrees()).abs();
-                    (dist, angle_error)
-                };
-
-                let mut distance_traveled = 0.0;
-                for j in 1..sim.trajectory.len() {
-                    let p1 = &sim.trajectory[j - 1];
-                    let p2 = &sim.trajectory[j];
-                    let dx = p2.x - p1.x;
-                    let dy = p2.y - p1.y;
-                    distance_traveled += (dx * dx + dy * dy).sqrt();
-                }
-
-                let metrics = crate::simulation::SimulationMetrics {
-                    success,
-                    arrival_time,
-                    distance_traveled,
-                    final_angle_error,
-                    final_distance_to_target: final_distance,
-                };
-
-                VehicleSimulationResult {
-                    vehicle_type: sim.vehicle.vehicle_type.name().to_string(),
-                    trajectory: sim.trajectory.clone(),
-                    metrics,
-                }
-            })
-            .collect();
-
-        (vehicle_results, time)
-    })
-    .await
-    .map_err(|e| ApiError::InternalError(format!("Simulation task failed: {}", e)))?;
+    Ok(response)
+}
+
+#[cfg(feature = "xlsx")]
+async fn simulation_xlsx_response(
+    request: SimulationRequest,
+    controllers: Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<Response, ApiError> {
+    let xlsx = tokio::task::spawn_blocking(move || service::simulate_xlsx(&request, &controllers, cancel))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Simulation task failed: {}", e)))??;
+
+    Ok(xlsx_attachment("simulation.xlsx".to_string(), xlsx))
+}
 
-    let (vehicles, total_time) = vehicles_result;
+#[cfg(not(feature = "xlsx"))]
+async fn simulation_xlsx_response(
+    _request: SimulationRequest,
+    _controllers: Arc<SharedControllers>,
+    _cancel: CancelToken,
+) -> Result<Response, ApiError> {
+    Err(ApiError::BadRequest("Excel output is not enabled in this build (xlsx feature)".to_string()))
+}
 
-    let success_count = vehicles.iter().filter(|v| v.metrics.success).count();
-    let message = format!(
-        "Simulation completed: {}/{} vehicles arrived successfully",
-        success_count,
-        vehicles.len()
-    );
+/// `bytes` as a downloaded workbook named `filename`
+#[cfg(feature = "xlsx")]
+fn xlsx_attachment(filename: String, bytes: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        bytes,
+    )
+        .into_response()
+}
 
-    Ok(Json(SimulationResponse {
-        success: true,
-        vehicles,
-        total_simulation_time: total_time,
-        message,
-    }))
+/// `service::simulate_shared`, stored in the run database when the server has one
+fn simulate_json(state: &ApiState, request: &SimulationRequest, cancel: CancelToken) -> Result<SimulationResponse, FuzzyNavError> {
+    #[cfg(feature = "storage")]
+    if let Some(path) = &state.store {
+        return service::simulate_stored(request, &state.controllers, cancel, &mut RunStore::open(path)?);
+    }
+    service::simulate_shared(request, &state.controllers, cancel)
 }
 
 // ============================================================================
 // BENCHMARK ENDPOINT
 // ============================================================================
 
-#[derive(Clone)]
-struct VehicleMetrics {
-    vehicle_type: String,
-    success: bool,
-    arrival_time: Option<f64>,
-    distance_traveled: f64,
-    final_distance: f64,
-    final_angle_error: f64,
+/// `?format=parquet` downloads the raw per-run table instead of the statistics,
+/// `?format=xlsx` the statistics as a workbook
+pub async fn run_benchmark(
+    State(state): State<ApiState>,
+    Query(query): Query<BenchmarkQuery>,
+    headers: HeaderMap,
+    Json(request): Json<BenchmarkRequest>,
+) -> Result<Response, ApiError> {
+    let client = quota::client_id(&headers);
+    if let Some(quota) = &state.quota {
+        quota.charge(&client, service::benchmark_cost(&request)?).map_err(ApiError::QuotaExceeded)?;
+    }
+    let quota = state.quota.clone();
+    let (response, overrun) = match query.format {
+        BenchmarkFormat::Json => {
+            // Run benchmark in blocking task
+            let response = tokio::task::spawn_blocking(move || benchmark_json(&state, &request))
+                .await
+                .map_err(|e| ApiError::InternalError(format!("Benchmark task failed: {}", e)))??;
+            let overrun = service::budget_overrun(&response.config, response.num_iterations);
+            (Json(response).into_response(), overrun)
+        }
+        BenchmarkFormat::Parquet => benchmark_parquet_response(request, state.controllers).await?,
+        BenchmarkFormat::Xlsx => benchmark_xlsx_response(request, state.controllers).await?,
+    };
+    // A budgeted run was charged one iteration, count the others it completed
+    if let (Some(quota), Some(overrun)) = (quota, overrun) {
+        quota.record(&client, overrun);
+    }
+    Ok(response)
+}
+
+/// `service::benchmark_shared`, stored in the run database when the server has one
+fn benchmark_json(state: &ApiState, request: &BenchmarkRequest) -> Result<BenchmarkResponse, FuzzyNavError> {
+    #[cfg(feature = "storage")]
+    if let Some(path) = &state.store {
+        return service::benchmark_stored(request, &state.controllers, &mut RunStore::open(path)?);
+    }
+    service::benchmark_shared(request, &state.controllers)
+}
+
+#[cfg(feature = "arrow")]
+async fn benchmark_parquet_response(
+    request: BenchmarkRequest,
+    controllers: Arc<SharedControllers>,
+) -> Result<(Response, Option<QuotaCost>), ApiError> {
+    let filename = match request.duration_seconds {
+        Some(seconds) => format!("benchmark_{}s.parquet", seconds),
+        None => format!("benchmark_{}iterations.parquet", request.iterations.unwrap_or_else(default_iterations)),
+    };
+    let (parquet, overrun) = tokio::task::spawn_blocking(move || service::benchmark_parquet(&request, &controllers))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Benchmark task failed: {}", e)))??;
+
+    let response = (
+        [
+            (header::CONTENT_TYPE, "application/vnd.apache.parquet".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        parquet,
+    )
+        .into_response();
+    Ok((response, overrun))
+}
+
+#[cfg(not(feature = "arrow"))]
+async fn benchmark_parquet_response(
+    _request: BenchmarkRequest,
+    _controllers: Arc<SharedControllers>,
+) -> Result<(Response, Option<QuotaCost>), ApiError> {
+    Err(ApiError::BadRequest("Parquet output is not enabled in this build (arrow feature)".to_string()))
+}
+
+#[cfg(feature = "xlsx")]
+async fn benchmark_xlsx_response(
+    request: BenchmarkRequest,
+    controllers: Arc<SharedControllers>,
+) -> Result<(Response, Option<QuotaCost>), ApiError> {
+    let filename = match request.duration_seconds {
+        Some(seconds) => format!("benchmark_{}s.xlsx", seconds),
+        None => format!("benchmark_{}iterations.xlsx", request.iterations.unwrap_or_else(default_iterations)),
+    };
+    let (xlsx, overrun) = tokio::task::spawn_blocking(move || service::benchmark_xlsx(&request, &controllers))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Benchmark task failed: {}", e)))??;
+
+    Ok((xlsx_attachment(filename, xlsx), overrun))
+}
+
+#[cfg(not(feature = "xlsx"))]
+async fn benchmark_xlsx_response(
+    _request: BenchmarkRequest,
+    _controllers: Arc<SharedControllers>,
+) -> Result<(Response, Option<QuotaCost>), ApiError> {
+    Err(ApiError::BadRequest("Excel output is not enabled in this build (xlsx feature)".to_string()))
+}
+
+// ============================================================================
+// REACHABILITY ENDPOINT
+// ============================================================================
+
+pub async fn run_reachability(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<ReachabilityRequest>,
+) -> Result<Json<ReachabilityResponse>, ApiError> {
+    if let Some(quota) = &state.quota {
+        quota.charge(&quota::client_id(&headers), service::reachability_cost(&request)?).map_err(ApiError::QuotaExceeded)?;
+    }
+    let response = tokio::task::spawn_blocking(move || service::reachability(&request))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Reachability task failed: {}", e)))??;
+    Ok(Json(response))
 }
 
-fn calculate_stats(values: &[f64]) -> (f64, f64, f64, f64) {
-    if values.is_empty() {
-        return (0.0, 0.0, 0.0, 0.0);
+// ============================================================================
+// ROBUSTNESS ENDPOINT
+// ============================================================================
+
+pub async fn run_robustness(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<RobustnessRequest>,
+) -> Result<Json<RobustnessResponse>, ApiError> {
+    if let Some(quota) = &state.quota {
+        quota.charge(&quota::client_id(&headers), service::robustness_cost(&request)?).map_err(ApiError::QuotaExceeded)?;
     }
-    let n = values.len() as f64;
-    let mean = values.iter().sum::<f64>() / n;
-    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
-    let std = variance.sqrt();
-    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    (mean, std, min, max)
+    let response = tokio::task::spawn_blocking(move || service::robustness(&request))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Robustness task failed: {}", e)))??;
+    Ok(Json(response))
 }
 
-pub async fn run_benchmark(
+// ============================================================================
+// SINGLE STEP ENDPOINT
+// ============================================================================
+
+/// One controller step is cheap enough to run on the async runtime
+pub async fn run_step(Json(request): Json<StepRequest>) -> Result<Json<StepResponse>, ApiError> {
+    Ok(Json(service::step(&request)?))
+}
+
+// ============================================================================
+// COMPARISON ENDPOINT
+// ============================================================================
+
+pub async fn run_compare(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<CompareRequest>,
+) -> Result<Json<CompareResponse>, ApiError> {
+    if let Some(quota) = &state.quota {
+        quota.charge(&quota::client_id(&headers), service::compare_cost(&request)?).map_err(ApiError::QuotaExceeded)?;
+    }
+    let response = tokio::task::spawn_blocking(move || service::compare(&request))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Comparison task failed: {}", e)))??;
+    Ok(Json(response))
+}
+
+// ============================================================================
+// CONTROLLER DESCRIPTION ENDPOINT
+// ============================================================================
+
+pub async fn describe_controller(Query(query): Query<ControllerQuery>) -> Result<Json<ControllerResponse>, ApiError> {
+    Ok(Json(service::controller(&query)?))
+}
+
+// ============================================================================
+// CONFIG ENDPOINT
+// ============================================================================
+
+pub async fn get_config() -> Json<ConfigResponse> {
+    Json(service::config())
+}
+
+// ============================================================================
+// BENCHMARK JOBS
+// ============================================================================
+
+/// Job store of the server, jobs are disabled without `BENCHMARK_JOBS_DIR`
+fn job_store(state: &ApiState) -> Result<Arc<JobStore>, ApiError> {
+    state.jobs.clone().ok_or_else(|| {
+        ApiError::NotFound(format!("Benchmark jobs are disabled, start the server with {} set", JOBS_DIR_ENV))
+    })
+}
+
+/// Run job `id` in a blocking task, its progress is read through the store
+fn spawn_job(jobs: Arc<JobStore>, controllers: Arc<SharedControllers>, id: u64) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = jobs.run(id, &controllers) {
+            tracing::error!("Benchmark job {} could not run: {}", id, e);
+        }
+    });
+}
+
+fn job_response(job: JobInfo, message: String) -> BenchmarkJobResponse {
+    BenchmarkJobResponse { success: true, job, meta: BuildMeta::current(1), message }
+}
+
+/// Start a fixed count benchmark in the background, answered with 202 and the job id
+pub async fn create_benchmark_job(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
     Json(request): Json<BenchmarkRequest>,
-) -> Result<Json<BenchmarkResponse>, ApiError> {
-    // Parse vehicle types
-    let vehicle_types = request.parse_vehicle_types()
-        .map_err(|e| ApiError::BadRequest(e))?;
-
-    if vehicle_types.is_empty() {
-        return Err(ApiError::BadRequest(
-            "At least one vehicle type must be specified".to_string()
-        ));
+) -> Result<Response, ApiError> {
+    let jobs = job_store(&state)?;
+    let config = service::benchmark_job_config(&request)?;
+    if let Some(quota) = &state.quota {
+        quota.charge(&quota::client_id(&headers), service::config_cost(&config, config.iterations)).map_err(ApiError::QuotaExceeded)?;
+    }
+    let job = jobs.create(config)?;
+    spawn_job(jobs, state.controllers, job.id);
+    let message = format!("Benchmark job {} started", job.id);
+    Ok((StatusCode::ACCEPTED, Json(job_response(job, message))).into_response())
+}
+
+/// Progress of a benchmark job
+pub async fn get_benchmark_job(State(state): State<ApiState>, Path(id): Path<u64>) -> Result<Json<BenchmarkJobResponse>, ApiError> {
+    let job = job_store(&state)?.info(id)?;
+    let message = format!("Benchmark job {} is {}", id, job.status);
+    Ok(Json(job_response(job, message)))
+}
+
+/// Continue a job a restart interrupted from its next iteration, 409 unless it is resumable
+///
+/// The job was charged to the quota when it was created.
+pub async fn resume_benchmark_job(State(state): State<ApiState>, Path(id): Path<u64>) -> Result<Response, ApiError> {
+    let jobs = job_store(&state)?;
+    let job = jobs.resume(id)?;
+    spawn_job(jobs, state.controllers, id);
+    let message = format!("Benchmark job {} resumed at iteration {}", id, job.completed_iterations);
+    Ok((StatusCode::ACCEPTED, Json(job_response(job, message))).into_response())
+}
+
+/// Benchmark response of a completed job as a JSON download, 409 until it completes
+pub async fn get_benchmark_job_result(State(state): State<ApiState>, Path(id): Path<u64>) -> Result<Response, ApiError> {
+    let result = job_store(&state)?.result(id)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"benchmark_job_{}.json\"", id)),
+        ],
+        result,
+    )
+        .into_response())
+}
+
+// ============================================================================
+// QUOTA ENDPOINT
+// ============================================================================
+
+/// Usage of the caller within the quota window
+pub async fn get_quota(State(state): State<ApiState>, headers: HeaderMap) -> Json<QuotaStatus> {
+    let client = quota::client_id(&headers);
+    Json(match &state.quota {
+        Some(quota) => quota.status(&client),
+        None => quota::unlimited_status(&client),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{FuzzyError, MapError, SimulationError};
+    use crate::map::{Map, Point};
+    use crate::simulation::SimulationBuilder;
+    use crate::units::Degrees;
+    use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+    fn status_of(error: FuzzyNavError) -> StatusCode {
+        ApiError::from(error).into_response().status()
+    }
+
+    #[test]
+    fn test_error_variants_map_to_status_codes() {
+        assert_eq!(status_of(SimulationError::NoVehicles.into()), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            status_of(MapError::InvalidDimensions { width: 0.0, height: 1.0 }.into()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(status_of(FuzzyError::ZeroResolution.into()), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    fn step_request(json: &str) -> StepRequest {
+        serde_json::from_str(json).unwrap()
     }
 
-    if request.iterations == 0 {
-        return Err(ApiError::BadRequest(
-            "Number of iterations must be greater than 0".to_string()
-        ));
+    #[tokio::test]
+    async fn test_step_matches_simulation_step() {
+        let request = step_request(
+            r#"{"vehicle_type": "Agile", "state": {"x": 200.0, "y": 40.0, "angle_degrees": 60.0, "velocity": 12.0}, "dt": 0.1}"#,
+        );
+        let Json(response) = run_step(Json(request)).await.ok().unwrap();
+
+        let mut sim = SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), VehicleType::Agile)
+            .dt(0.1)
+            .start_position(Point::new(200.0, 40.0))
+            .start_angle(Degrees(60.0).to_radians())
+            .build();
+        sim.vehicle.state.velocity = 12.0;
+        sim.step();
+        let point = sim.trajectory.last().unwrap();
+
+        assert!(!response.arrived);
+        assert_eq!(response.next_state, StepState::from_vehicle_state(&sim.vehicle.state));
+        assert_eq!(Some(response.angular_adjustment), point.angular_adjustment);
+        assert_eq!(Some(response.commanded_adjustment), point.commanded_adjustment);
+        assert_eq!(Some(Degrees(response.inputs.desired_heading)), point.desired_heading);
+        assert!(!response.activated_sets.is_empty());
+        assert!(response.activated_sets.iter().all(|set| set.degree > 0.0 && set.degree <= 1.0));
     }
 
-    // Store count before moving vehicle_types
-    let num_vehicle_types = vehicle_types.len();
-
-    // Run benchmark in blocking task
-    let aggregate_stats = tokio::task::spawn_blocking(move || {
-        // Configure rayon thread pool
-        let available_threads = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(4);
-
-        let threads_to_use = request.threads.unwrap_or(available_threads / 2);
-
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(threads_to_use)
-            .build_global()
-            .ok();
-
-        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
-
-        let completed = Arc::new(AtomicUsize::new(0));
-        let completed_clone = Arc::clone(&completed);
-
-        // Run iterations in parallel
-        let all_results: Vec<Vec<VehicleMetrics>> = (0..request.iterations)
-            .into_par_iter()
-            .map(|_| {
-                let iteration_vehicles: Vec<VehicleMetrics> = vehicle_types
-                    .iter()
-                    .map(|&vtype| {
-                        let mut sim = Simulation::new(map.clone(), vtype, request.dt, request.max_time);
-
-                        while sim.time < request.max_time && !sim.vehicle.has_arrived {
-                            sim.step();
-                        }
-
-                        let success = sim.vehicle.has_arrived;
-                        let arrival_time = if success { Some(sim.vehicle.time_elapsed) } else { None };
-
-                        // Handle empty trajectory case
-                        let (final_distance, final_angle_error) = if let Some(final_point) = sim.trajectory.last() {
-                            (final_point.distance_to_target, (90.0 - final_point.angle).abs())
-                        } else {
-                            // If no trajectory points, calculate from current vehicle state
-                            let dx = sim.vehicle.state.position.x - sim.map.target.position.x;
-                            let dy = sim.vehicle.state.position.y - sim.map.target.position.y;
-                            let dist = (dx * dx + dy * dy).sqrt();
-                            let angle_error = (90.0 - sim.vehicle.state.angle.to_degrees()).abs();
-                            (dist, angle_error)
-                        };
-
-                        let mut distance_traveled = 0.0;
-                        for j in 1..sim.trajectory.len() {
-                            let p1 = &sim.trajectory[j - 1];
-                            let p2 = &sim.trajectory[j];
-                            let dx = p2.x - p1.x;
-                            let dy = p2.y - p1.y;
-                            distance_traveled += (dx * dx + dy * dy).sqrt();
-                        }
-
-                        VehicleMetrics {
-                            vehicle_type: vtype.name().to_string(),
-                            success,
-                            arrival_time,
-                            distance_traveled,
-                            final_distance,
-                            final_angle_error,
-                        }
-                    })
-                    .collect();
-
-                completed_clone.fetch_add(1, Ordering::Relaxed);
-                iteration_vehicles
-            })
-            .collect();
-
-        // Reorganize results by vehicle type
-        let mut all_metrics: Vec<Vec<VehicleMetrics>> = vec![Vec::new(); vehicle_types.len()];
-        for iteration_result in &all_results {
-            for (idx, metrics) in iteration_result.iter().enumerate() {
-                all_metrics[idx].push(metrics.clone());
-            }
+    #[tokio::test]
+    async fn test_step_at_target_reports_arrival_without_moving() {
+        let request = step_request(
+            r#"{"state": {"x": 500.0, "y": 690.0, "angle_degrees": 90.0, "velocity": 8.0}}"#,
+        );
+        let state = request.state.clone();
+        let Json(response) = run_step(Json(request)).await.ok().unwrap();
+
+        assert!(response.arrived);
+        assert_eq!(response.next_state, state);
+        assert!(response.activated_sets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_arrival_compares_the_angles_around_the_circle() {
+        // 450° is 90°, and -179.5° is half a degree from a 180° target across the seam
+        for json in [
+            r#"{"state": {"x": 500.0, "y": 690.0, "angle_degrees": 450.0, "velocity": 8.0}}"#,
+            r#"{"state": {"x": 510.0, "y": 700.0, "angle_degrees": -179.5, "velocity": 8.0}, "target": {"x": 500.0, "y": 700.0, "angle_degrees": 180.0}}"#,
+            r#"{"state": {"x": 510.0, "y": 700.0, "angle_degrees": 179.5, "velocity": 8.0}, "target": {"x": 500.0, "y": 700.0, "angle_degrees": -180.0}}"#,
+        ] {
+            let Json(response) = run_step(Json(step_request(json))).await.ok().unwrap();
+            assert!(response.arrived, "{}", json);
         }
 
-        // Calculate aggregate statistics
-        let mut stats: Vec<AggregateStats> = Vec::new();
-
-        for (idx, vtype) in vehicle_types.iter().enumerate() {
-            let metrics = &all_metrics[idx];
-            let successes = metrics.iter().filter(|m| m.success).count();
-            let success_rate = successes as f64 / request.iterations as f64 * 100.0;
-
-            let arrival_times: Vec<f64> = metrics.iter()
-                .filter_map(|m| m.arrival_time)
-                .collect();
-            let (avg_time, std_time, min_time, max_time) = calculate_stats(&arrival_times);
-
-            let distances: Vec<f64> = metrics.iter().map(|m| m.distance_traveled).collect();
-            let (avg_dist, std_dist, _, _) = calculate_stats(&distances);
-
-            let final_dists: Vec<f64> = metrics.iter().map(|m| m.final_distance).collect();
-            let (avg_final_dist, _, _, _) = calculate_stats(&final_dists);
-
-            let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
-            let (avg_angle_error, _, _, _) = calculate_stats(&angle_errors);
-
-            stats.push(AggregateStats {
-                vehicle_type: vtype.name().to_string(),
-                total_runs: request.iterations,
-                successes,
-                success_rate,
-                avg_arrival_time: avg_time,
-                std_arrival_time: std_time,
-                min_arrival_time: min_time,
-                max_arrival_time: max_time,
-                avg_distance_traveled: avg_dist,
-                std_distance_traveled: std_dist,
-                avg_final_distance: avg_final_dist,
-                avg_final_angle_error: avg_angle_error,
-            });
+        let misaligned = r#"{"state": {"x": 500.0, "y": 690.0, "angle_degrees": 270.0, "velocity": 8.0}}"#;
+        let Json(response) = run_step(Json(step_request(misaligned))).await.ok().unwrap();
+        assert!(!response.arrived);
+    }
+
+    #[tokio::test]
+    async fn test_step_rejects_invalid_requests() {
+        let bad_dt = step_request(r#"{"state": {"x": 1.0, "y": 1.0, "angle_degrees": 0.0, "velocity": 1.0}, "dt": 0.0}"#);
+        let response = run_step(Json(bad_dt)).await.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let bad_type = step_request(r#"{"vehicle_type": "Submarine", "state": {"x": 1.0, "y": 1.0, "angle_degrees": 0.0, "velocity": 1.0}}"#);
+        let response = run_step(Json(bad_type)).await.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A negative maneuverability would panic building the membership functions
+        let bad_characteristics = step_request(
+            r#"{"characteristics": {"size": 6.0, "maneuverability": -1.0, "max_velocity": 100.0, "max_acceleration": 30.0},
+                "state": {"x": 1.0, "y": 1.0, "angle_degrees": 0.0, "velocity": 1.0}}"#,
+        );
+        let response = run_step(Json(bad_characteristics)).await.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        for dt in [f64::NAN, f64::INFINITY, 1e300] {
+            let mut request = step_request(r#"{"state": {"x": 1.0, "y": 1.0, "angle_degrees": 0.0, "velocity": 1.0}}"#);
+            request.dt = dt;
+            let response = run_step(Json(request)).await.err().unwrap().into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "dt {}", dt);
         }
+    }
 
-        stats
-    })
-    .await
-    .map_err(|e| ApiError::InternalError(format!("Benchmark task failed: {}", e)))?;
-
-    let message = format!("Benchmark completed: {} iterations across {} vehicle types",
-        request.iterations,
-        num_vehicle_types
-    );
-
-    Ok(Json(BenchmarkResponse {
-        success: true,
-        num_iterations: request.iterations,
-        aggregate_stats,
-        message,
-    }))
+    fn controller_query(vehicle_type: &str) -> ControllerQuery {
+        ControllerQuery { vehicle_type: vehicle_type.to_string(), map_width: 1000.0, map_height: 800.0 }
+    }
+
+    #[tokio::test]
+    async fn test_controller_describes_the_agile_rule_base() {
+        let Json(response) = describe_controller(Query(controller_query("Agile"))).await.ok().unwrap();
+
+        let maneuverability = create_vehicle_preset(VehicleType::Agile).maneuverability;
+        assert_eq!(response.output.range, (-maneuverability, maneuverability));
+        assert_eq!(response.inputs.len(), 3);
+        assert!(response.inputs.iter().flat_map(|v| &v.sets).all(|set| set.membership.is_some()));
+        let circular: Vec<&str> = response.inputs.iter().filter(|v| v.circular).map(|v| v.name.as_str()).collect();
+        assert_eq!(circular, vec!["error_angular"]);
+
+        assert_eq!(response.rules.len(), 12);
+        let first = &response.rules[0];
+        assert_eq!(first.number, 1);
+        assert_eq!(first.operator, "and");
+        assert_eq!(first.text, "if distancia_al_objetivo is lejos AND error_angular is alineado then ajuste_angular is mantener");
+        assert!(response.description.contains(&format!("  11: {}", response.rules[10].text)));
+        assert_eq!(response.defuzzification.method, "Centroid");
+    }
+
+    #[tokio::test]
+    async fn test_controller_rejects_unknown_vehicle() {
+        let response = describe_controller(Query(controller_query("Submarine"))).await.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -0,0 +1,184 @@
+// Deep health check of the API: builds the controller of every vehicle preset
+// and runs a short seeded simulation, so a deployment whose fuzzy engine is
+// broken reports it instead of a static "healthy"
+//
+// The report is cached for a few seconds, probes hitting the endpoint often
+// do not run the checks each time.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::models::{DeepHealthResponse, HealthCheck};
+use crate::map::MapPreset;
+use crate::navigation::NavigationController;
+use crate::simulation::SimulationBuilder;
+use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+/// How long a report is served before the checks run again
+pub const DEEP_HEALTH_TTL: Duration = Duration::from_secs(10);
+
+/// Steps of the mini simulation
+pub const MINI_SIMULATION_STEPS: usize = 50;
+
+/// Seed of the start state of the mini simulation
+const MINI_SIMULATION_SEED: u64 = 7;
+
+/// Last report of the checks, shared by the requests
+#[derive(Debug)]
+pub struct DeepHealth {
+    ttl: Duration,
+    last: Mutex<Option<(Instant, DeepHealthResponse)>>,
+    /// Vehicle type whose controller construction panics, to test the failure path
+    #[cfg(test)]
+    poisoned_controller: Option<VehicleType>,
+}
+
+impl Default for DeepHealth {
+    fn default() -> Self {
+        Self::new(DEEP_HEALTH_TTL)
+    }
+}
+
+impl DeepHealth {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: Mutex::default(),
+            #[cfg(test)]
+            poisoned_controller: None,
+        }
+    }
+
+    /// Checks whose `vehicle_type` controller construction panics
+    #[cfg(test)]
+    pub(crate) fn poisoned(vehicle_type: VehicleType) -> Self {
+        Self { poisoned_controller: Some(vehicle_type), ..Self::default() }
+    }
+
+    /// Report of the checks, run again once the cached one is older than the TTL
+    ///
+    /// Concurrent requests wait for the one running the checks and share its report.
+    pub fn report(&self) -> DeepHealthResponse {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((ran_at, report)) = last.as_ref() {
+            let age = ran_at.elapsed();
+            if age < self.ttl {
+                return DeepHealthResponse { cached: true, age_seconds: age.as_secs_f64(), ..report.clone() };
+            }
+        }
+        let report = self.run_checks();
+        *last = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    fn run_checks(&self) -> DeepHealthResponse {
+        let started = Instant::now();
+        let mut checks: Vec<HealthCheck> = VehicleType::ALL
+            .iter()
+            .map(|&vehicle_type| timed_check(format!("controller:{}", vehicle_type.id()), || self.build_controller(vehicle_type)))
+            .collect();
+        checks.push(timed_check("simulation".to_string(), mini_simulation));
+
+        let mut report = DeepHealthResponse {
+            status: String::new(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            cached: false,
+            age_seconds: 0.0,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            checks,
+        };
+        report.status = if report.is_healthy() { "healthy" } else { "unhealthy" }.to_string();
+        report
+    }
+
+    fn build_controller(&self, vehicle_type: VehicleType) -> Result<(), String> {
+        #[cfg(test)]
+        if self.poisoned_controller == Some(vehicle_type) {
+            panic!("controller construction poisoned by the test hook");
+        }
+        NavigationController::new(&create_vehicle_preset(vehicle_type));
+        Ok(())
+    }
+}
+
+/// Run `check`, timing it and turning a panic into a failure
+fn timed_check(name: String, check: impl FnOnce() -> Result<(), String>) -> HealthCheck {
+    let started = Instant::now();
+    let outcome = panic::catch_unwind(AssertUnwindSafe(check)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(format!("panicked: {}", message))
+    });
+    HealthCheck {
+        name,
+        passed: outcome.is_ok(),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        error: outcome.err(),
+    }
+}
+
+/// A Standard vehicle on the exam map for `MINI_SIMULATION_STEPS` steps: its state stays finite and it moves
+fn mini_simulation() -> Result<(), String> {
+    let mut simulation = SimulationBuilder::new(MapPreset::Exam.map(), VehicleType::Standard)
+        .seed(MINI_SIMULATION_SEED)
+        .build();
+    let start = simulation.vehicle.state.position.clone();
+    for _ in 0..MINI_SIMULATION_STEPS {
+        if simulation.is_finished() {
+            break;
+        }
+        simulation.step();
+    }
+
+    let state = &simulation.vehicle.state;
+    if ![state.position.x, state.position.y, state.angle.0, state.velocity].iter().all(|value| value.is_finite()) {
+        return Err(format!("vehicle state is not finite after {} steps: {:?}", simulation.step_index, state));
+    }
+    if state.position == start {
+        return Err(format!("vehicle did not move in {} steps", simulation.step_index));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deep_check_passes_on_the_current_code() {
+        let report = DeepHealth::default().report();
+        assert!(report.is_healthy(), "{:?}", report.checks);
+        assert_eq!(report.status, "healthy");
+        let names: Vec<&str> = report.checks.iter().map(|check| check.name.as_str()).collect();
+        assert_eq!(names, ["controller:heavy", "controller:standard", "controller:agile", "controller:ultra_agile", "simulation"]);
+        assert!(report.checks.iter().all(|check| check.error.is_none() && check.duration_ms >= 0.0));
+    }
+
+    #[test]
+    fn test_report_is_cached_for_the_ttl() {
+        let health = DeepHealth::default();
+        let first = health.report();
+        let second = health.report();
+        assert!(!first.cached && second.cached);
+        assert_eq!(second.duration_ms, first.duration_ms);
+
+        let uncached = DeepHealth::new(Duration::ZERO);
+        uncached.report();
+        assert!(!uncached.report().cached);
+    }
+
+    #[test]
+    fn test_a_panicking_check_is_reported_as_failed() {
+        let report = DeepHealth::poisoned(VehicleType::Agile).report();
+        assert!(!report.is_healthy());
+        assert_eq!(report.status, "unhealthy");
+        let failed: Vec<&HealthCheck> = report.checks.iter().filter(|check| !check.passed).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "controller:agile");
+        assert!(failed[0].error.as_deref().unwrap().contains("poisoned by the test hook"));
+    }
+}
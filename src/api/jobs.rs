@@ -0,0 +1,387 @@
+// Benchmark jobs that survive a restart of the server
+//
+// Every job is a set of files in the jobs directory: `<id>.json` with its
+// configuration, `<id>.jsonl` with the metrics of every finished iteration, a
+// line each in index order, and `<id>.result.json` once it completed. Opening
+// the directory after a restart reloads the unfinished jobs as resumable, and
+// since iteration `i` draws from `seed + i` a resumed job aggregates exactly
+// like one that never stopped.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::models::{JobInfo, JobStatus};
+use super::service::{self, VehicleMetrics};
+use crate::error::{FuzzyNavError, SerializationError};
+use crate::navigation::SharedControllers;
+use crate::simulation::benchmark::BenchmarkConfig;
+
+/// Environment variable with the directory of the benchmark jobs, see `ApiState::from_env`
+pub const JOBS_DIR_ENV: &str = "BENCHMARK_JOBS_DIR";
+
+/// Why a job request could not be served
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("No benchmark job {0}")]
+    NotFound(u64),
+    #[error("Benchmark job {id} is {status}, only resumable jobs can be resumed")]
+    NotResumable { id: u64, status: JobStatus },
+    #[error("Benchmark job {id} is {status}, its result is not ready")]
+    NotCompleted { id: u64, status: JobStatus },
+    #[error(transparent)]
+    Run(#[from] FuzzyNavError),
+}
+
+/// Configuration of a job, the `<id>.json` file
+#[derive(Serialize, Deserialize)]
+struct JobDescriptor {
+    id: u64,
+    config: BenchmarkConfig,
+}
+
+/// Metrics of one finished iteration, a line of the `<id>.jsonl` file
+#[derive(Serialize, Deserialize)]
+struct IterationRecord {
+    iteration: usize,
+    /// Wall time of the iteration (seconds)
+    seconds: f64,
+    metrics: Vec<VehicleMetrics>,
+}
+
+struct Job {
+    config: BenchmarkConfig,
+    status: JobStatus,
+    completed_iterations: usize,
+    error: Option<String>,
+}
+
+impl Job {
+    fn info(&self, id: u64) -> JobInfo {
+        JobInfo {
+            id,
+            status: self.status,
+            completed_iterations: self.completed_iterations,
+            total_iterations: self.config.iterations,
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Benchmark jobs of the server, persisted in one directory
+pub struct JobStore {
+    dir: PathBuf,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+}
+
+impl JobStore {
+    /// Open the jobs directory at `dir`, created when missing, and reload the jobs it holds
+    ///
+    /// A job without a result comes back resumable whatever it was when the
+    /// server stopped, the iterations it stored are kept.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, FuzzyNavError> {
+        let store = Self { dir: dir.as_ref().to_path_buf(), jobs: Mutex::default() };
+        let dir = &store.dir;
+        fs::create_dir_all(dir).map_err(|e| file_error(dir, e))?;
+
+        let mut jobs = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(|e| file_error(dir, e))? {
+            let path = entry.map_err(|e| file_error(dir, e))?.path();
+            let is_descriptor = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                name.ends_with(".json") && !name.ends_with(".result.json")
+            });
+            if !is_descriptor {
+                continue;
+            }
+            let descriptor: JobDescriptor = serde_json::from_str(&read(&path)?)?;
+            let job = if store.result_path(descriptor.id).exists() {
+                Job {
+                    completed_iterations: descriptor.config.iterations,
+                    config: descriptor.config,
+                    status: JobStatus::Completed,
+                    error: None,
+                }
+            } else {
+                let (records, _) = read_iterations(&store.iterations_path(descriptor.id))?;
+                Job { config: descriptor.config, status: JobStatus::Resumable, completed_iterations: records.len(), error: None }
+            };
+            jobs.insert(descriptor.id, job);
+        }
+        *store.lock() = jobs;
+        Ok(store)
+    }
+
+    /// Store a new running job of `config`, with the id after the last one
+    pub fn create(&self, config: BenchmarkConfig) -> Result<JobInfo, FuzzyNavError> {
+        let mut jobs = self.lock();
+        let id = jobs.last_key_value().map_or(1, |(id, _)| id + 1);
+        let descriptor = JobDescriptor { id, config };
+        File::create(self.iterations_path(id)).map_err(|e| file_error(&self.iterations_path(id), e))?;
+        write_atomically(&self.descriptor_path(id), &serde_json::to_vec_pretty(&descriptor)?)?;
+
+        let job = Job { config: descriptor.config, status: JobStatus::Running, completed_iterations: 0, error: None };
+        let info = job.info(id);
+        jobs.insert(id, job);
+        Ok(info)
+    }
+
+    /// Progress of job `id`
+    pub fn info(&self, id: u64) -> Result<JobInfo, JobError> {
+        self.lock().get(&id).map(|job| job.info(id)).ok_or(JobError::NotFound(id))
+    }
+
+    /// Mark the resumable job `id` running again, `run` then continues from its next iteration
+    pub fn resume(&self, id: u64) -> Result<JobInfo, JobError> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(&id).ok_or(JobError::NotFound(id))?;
+        if job.status != JobStatus::Resumable {
+            return Err(JobError::NotResumable { id, status: job.status });
+        }
+        job.status = JobStatus::Running;
+        Ok(job.info(id))
+    }
+
+    /// Stored `BenchmarkResponse` of the completed job `id`, as JSON
+    pub fn result(&self, id: u64) -> Result<Vec<u8>, JobError> {
+        let status = self.info(id)?.status;
+        if status != JobStatus::Completed {
+            return Err(JobError::NotCompleted { id, status });
+        }
+        let path = self.result_path(id);
+        Ok(fs::read(&path).map_err(|e| file_error(&path, e))?)
+    }
+
+    /// Run job `id` to its last iteration, blocking
+    ///
+    /// Every batch of iterations is appended to the job file as it finishes,
+    /// a failure is recorded in the job rather than returned.
+    pub fn run(&self, id: u64, controllers: &Arc<SharedControllers>) -> Result<JobInfo, JobError> {
+        self.run_until(id, controllers, usize::MAX)
+    }
+
+    /// `run`, leaving the job running once `stop` iterations are stored, as a server stopped mid-run would
+    fn run_until(&self, id: u64, controllers: &Arc<SharedControllers>, stop: usize) -> Result<JobInfo, JobError> {
+        let config = self.lock().get(&id).map(|job| job.config.clone()).ok_or(JobError::NotFound(id))?;
+        let outcome = self.run_iterations(id, &config, controllers, stop);
+
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(&id).ok_or(JobError::NotFound(id))?;
+        match outcome {
+            Ok(true) => job.status = JobStatus::Completed,
+            Ok(false) => {}
+            Err(error) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(error.to_string());
+            }
+        }
+        Ok(job.info(id))
+    }
+
+    /// Run the iterations after the stored ones up to `stop`, `true` once the result is stored
+    fn run_iterations(
+        &self,
+        id: u64,
+        config: &BenchmarkConfig,
+        controllers: &Arc<SharedControllers>,
+        stop: usize,
+    ) -> Result<bool, FuzzyNavError> {
+        let started = Instant::now();
+        let path = self.iterations_path(id);
+        let (records, valid_len) = read_iterations(&path)?;
+
+        // A line cut short when the server stopped is dropped, its iteration runs again
+        let mut file = OpenOptions::new().create(true).append(true).open(&path).map_err(|e| file_error(&path, e))?;
+        file.set_len(valid_len).map_err(|e| file_error(&path, e))?;
+
+        let (mut results, mut times): (Vec<_>, Vec<_>) = records.into_iter().map(|record| (record.metrics, record.seconds)).unzip();
+        let end = config.iterations.min(stop);
+        while results.len() < end {
+            let start = results.len();
+            let batch = service::run_iteration_range(config, controllers, start..(start + rayon::current_num_threads()).min(end))?;
+            let mut lines = Vec::new();
+            for (iteration, (metrics, seconds)) in (start..).zip(batch) {
+                let record = IterationRecord { iteration, seconds, metrics };
+                serde_json::to_writer(&mut lines, &record)?;
+                lines.push(b'\n');
+                results.push(record.metrics);
+                times.push(seconds);
+            }
+            file.write_all(&lines).map_err(|e| file_error(&path, e))?;
+            file.sync_data().map_err(|e| file_error(&path, e))?;
+            if let Some(job) = self.lock().get_mut(&id) {
+                job.completed_iterations = results.len();
+            }
+        }
+        if results.len() < config.iterations {
+            return Ok(false);
+        }
+
+        let response = service::iterations_response(config, &results, &times, started.elapsed());
+        write_atomically(&self.result_path(id), &serde_json::to_vec(&response)?)?;
+        Ok(true)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<u64, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn descriptor_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn iterations_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", id))
+    }
+
+    fn result_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.result.json", id))
+    }
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> FuzzyNavError {
+    SerializationError::File { path: path.display().to_string(), message: error.to_string() }.into()
+}
+
+fn read(path: &Path) -> Result<String, FuzzyNavError> {
+    fs::read_to_string(path).map_err(|e| file_error(path, e))
+}
+
+/// Write `bytes` to a temporary file renamed over `path`, a restart never leaves half of it
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), FuzzyNavError> {
+    let partial = path.with_extension("partial");
+    let mut file = File::create(&partial).map_err(|e| file_error(&partial, e))?;
+    file.write_all(bytes).and_then(|_| file.sync_all()).map_err(|e| file_error(&partial, e))?;
+    fs::rename(&partial, path).map_err(|e| file_error(path, e))
+}
+
+/// Iterations stored at `path` and the length of the file they take
+///
+/// Reading stops at the first line that is unfinished, invalid or out of
+/// order, what follows it is left to be run again.
+fn read_iterations(path: &Path) -> Result<(Vec<IterationRecord>, u64), FuzzyNavError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(file_error(path, error)),
+    };
+    let mut records = Vec::new();
+    let mut valid_len = 0;
+    for line in contents.split_inclusive('\n') {
+        if !line.ends_with('\n') {
+            break;
+        }
+        match serde_json::from_str::<IterationRecord>(line) {
+            Ok(record) if record.iteration == records.len() => records.push(record),
+            _ => break,
+        }
+        valid_len += line.len() as u64;
+    }
+    Ok((records, valid_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BenchmarkRequest;
+
+    /// Empty directory of its own for every test
+    fn jobs_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("benchmark_jobs_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config(iterations: usize) -> BenchmarkConfig {
+        let request: BenchmarkRequest = serde_json::from_value(serde_json::json!({
+            "iterations": iterations, "vehicle_types": ["Agile", "Heavy"], "max_time": 10.0, "seed": 21, "threads": 2,
+        }))
+        .unwrap();
+        service::benchmark_job_config(&request).unwrap()
+    }
+
+    /// Aggregates of a serialized response without the wall times, which differ between runs
+    fn aggregates(mut response: serde_json::Value) -> serde_json::Value {
+        for stats in response["aggregate_stats"].as_array_mut().unwrap() {
+            let stats = stats.as_object_mut().unwrap();
+            stats.remove("avg_wall_time");
+            stats.remove("p95_wall_time");
+        }
+        response["aggregate_stats"].take()
+    }
+
+    #[test]
+    fn test_resumed_job_aggregates_like_an_uninterrupted_run() {
+        let dir = jobs_dir("resume");
+        let controllers = Arc::new(SharedControllers::default());
+        let config = config(5);
+
+        let store = JobStore::open(&dir).unwrap();
+        let id = store.create(config.clone()).unwrap().id;
+        let stopped = store.run_until(id, &controllers, 3).unwrap();
+        assert_eq!((stopped.status, stopped.completed_iterations), (JobStatus::Running, 3));
+        drop(store);
+
+        // The server restarts: a fresh store reads the job back from its files
+        let restarted = JobStore::open(&dir).unwrap();
+        let reloaded = restarted.info(id).unwrap();
+        assert_eq!((reloaded.status, reloaded.completed_iterations, reloaded.total_iterations), (JobStatus::Resumable, 3, 5));
+        assert!(matches!(restarted.result(id), Err(JobError::NotCompleted { status: JobStatus::Resumable, .. })));
+
+        assert_eq!(restarted.resume(id).unwrap().status, JobStatus::Running);
+        assert!(matches!(restarted.resume(id), Err(JobError::NotResumable { status: JobStatus::Running, .. })));
+        let done = restarted.run(id, &controllers).unwrap();
+        assert_eq!((done.status, done.completed_iterations), (JobStatus::Completed, 5));
+
+        let resumed: serde_json::Value = serde_json::from_slice(&restarted.result(id).unwrap()).unwrap();
+        let reference = serde_json::to_value(service::run_benchmark(&config).unwrap()).unwrap();
+        assert_eq!(resumed["num_iterations"], 5);
+        assert_eq!(resumed["config"], reference["config"]);
+        assert_eq!(aggregates(resumed), aggregates(reference));
+
+        // The result outlives another restart
+        let again = JobStore::open(&dir).unwrap();
+        assert_eq!(again.info(id).unwrap().status, JobStatus::Completed);
+        assert_eq!(again.result(id).unwrap(), restarted.result(id).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_a_torn_last_line_is_run_again() {
+        let dir = jobs_dir("torn");
+        let controllers = Arc::new(SharedControllers::default());
+        let store = JobStore::open(&dir).unwrap();
+        let id = store.create(config(3)).unwrap().id;
+        store.run_until(id, &controllers, 2).unwrap();
+
+        // The server died while writing the second line
+        let path = store.iterations_path(id);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &contents[..contents.len() - 10]).unwrap();
+
+        let restarted = JobStore::open(&dir).unwrap();
+        assert_eq!(restarted.info(id).unwrap().completed_iterations, 1);
+        restarted.resume(id).unwrap();
+        assert_eq!(restarted.run(id, &controllers).unwrap().status, JobStatus::Completed);
+        let lines: Vec<IterationRecord> = read(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.iter().map(|record| record.iteration).collect::<Vec<_>>(), [0, 1, 2]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ids_follow_the_stored_jobs_and_unknown_ones_are_not_found() {
+        let dir = jobs_dir("ids");
+        let store = JobStore::open(&dir).unwrap();
+        assert_eq!(store.create(config(1)).unwrap().id, 1);
+        assert_eq!(store.create(config(1)).unwrap().id, 2);
+        assert_eq!(JobStore::open(&dir).unwrap().create(config(1)).unwrap().id, 3);
+        assert!(matches!(store.info(9), Err(JobError::NotFound(9))));
+        assert!(matches!(store.resume(9), Err(JobError::NotFound(9))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,23 @@
 // API module for REST endpoints
 pub mod models;
+pub mod service;
+#[cfg(feature = "api")]
 pub mod handlers;
+#[cfg(feature = "api")]
+pub mod router;
+#[cfg(feature = "api")]
+pub mod quota;
+#[cfg(feature = "api")]
+pub mod health;
+#[cfg(feature = "api")]
+pub mod jobs;
 
 pub use models::*;
+#[cfg(feature = "api")]
 pub use handlers::*;
+#[cfg(feature = "api")]
+pub use router::{build_router, ApiState, RUN_STORE_ENV};
+#[cfg(feature = "api")]
+pub use quota::{QuotaLimits, QuotaTracker};
+#[cfg(feature = "api")]
+pub use jobs::{JobStore, JOBS_DIR_ENV};
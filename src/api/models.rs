@@ -1,7 +1,24 @@
 // API models for requests and responses
 use serde::{Deserialize, Serialize};
-use crate::vehicle::VehicleType;
-use crate::simulation::{SimulationMetrics, TrajectoryPoint};
+use crate::analysis::stats::{InitialConditionBucket, InitialConditionBuckets, OutlierRun, TrimmedStats};
+use crate::analysis::{RobustnessAxis, RobustnessReport, VehicleDelta};
+use crate::build_info::BuildMeta;
+use crate::error::{MapError, SimulationError};
+use crate::fuzzy_system::{FuzzyRule, LinguisticVariable, MembershipSpec, RuleOperator};
+use crate::map::{
+    ApproachGeometry, HeadingDistribution, Map, MapPreset, Point, StartDistribution, Target, APPROACH_MAX_OFFSET,
+    APPROACH_START, EXAM_MAP_SIZE, TARGET_REQUIRED_ANGLE,
+};
+use crate::navigation::DistanceScale;
+use crate::resource_usage::ResourceUsage;
+use crate::vehicle::{VehicleCharacteristics, VehicleState, VehicleType};
+use crate::simulation::benchmark::{BenchmarkConfig, VehicleSpec};
+use crate::simulation::scenario::DEFAULT_VEHICLE_TYPES;
+use crate::simulation::{
+    ArrivalCriteria, Convergence, ConvergenceReport, DropoutConfig, Scenario, ScenarioVehicle, SimulationMetrics, TrajectoryPoint, DEFAULT_DT, DEFAULT_MAX_TIME,
+};
+use crate::trajectory_export::{FrameOptions, GeoRef};
+use crate::units::Degrees;
 
 // ============================================================================
 // REQUEST MODELS
@@ -9,14 +26,22 @@ use crate::simulation::{SimulationMetrics, TrajectoryPoint};
 
 #[derive(Debug, Deserialize)]
 pub struct SimulationRequest {
-    /// Vehicle types to simulate (Heavy, Standard, Agile)
+    /// Vehicle types to simulate (Heavy, Standard, Agile, UltraAgile)
     #[serde(default = "default_vehicle_types")]
     pub vehicle_types: Vec<String>,
 
+    /// Label of every vehicle in `vehicle_types` order, unique (default: "<type>-<n>", like "agile-2")
+    #[serde(default)]
+    pub vehicle_ids: Vec<String>,
+
     /// Time step in seconds (default: 0.05)
     #[serde(default = "default_dt")]
     pub dt: f64,
 
+    /// Ignore `dt` and run at the largest stable time step of the vehicles (default: false)
+    #[serde(default)]
+    pub auto_dt: bool,
+
     /// Maximum simulation time in seconds (default: 600.0)
     #[serde(default = "default_max_time")]
     pub max_time: f64,
@@ -36,24 +61,107 @@ pub struct SimulationRequest {
     /// Target Y coordinate (default: 700.0)
     #[serde(default = "default_target_y")]
     pub target_y: f64,
+
+    /// Seed for reproducible start positions and angles (default: random)
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Arrival criteria, a preset name or explicit values (default: strict)
+    #[serde(default)]
+    pub criteria: Option<CriteriaSelection>,
+
+    /// Distribution of the random start headings (default: toward_target)
+    #[serde(default)]
+    pub heading: HeadingDistribution,
+
+    /// Controller rules to skip, 0-based indices (default: none)
+    #[serde(default)]
+    pub disabled_rules: Vec<usize>,
+
+    /// Placement of the controller distance sets (default: absolute on the 1000x800 map, relative to the diagonal of any other)
+    #[serde(default)]
+    pub distance_scale: Option<DistanceScale>,
+
+    /// Constant velocity as a fraction of the max velocity of every vehicle (default: each vehicle cruise fraction)
+    #[serde(default)]
+    pub velocity_fraction: Option<f64>,
+
+    /// Origin, y direction and angle convention of the returned trajectories (default: map frame)
+    #[serde(default)]
+    pub frame: Option<FrameOptions>,
+
+    /// Also return the approach corridor of the target (default: false)
+    #[serde(default)]
+    pub include_geometry: bool,
+
+    /// Random windows in which the controller keeps its last observation (default: none)
+    #[serde(default)]
+    pub dropout: Option<DropoutConfig>,
+
+    /// Runs of the scenario, repeat i uses seed + i, at most MAX_REPEATS (default: 1)
+    #[serde(default = "default_repeats")]
+    pub repeats: usize,
+
+    /// Also return every trajectory resampled for an animation at this frame rate, at most MAX_PLAYBACK_FPS (default: none)
+    #[serde(default)]
+    pub playback_fps: Option<f64>,
+
+    /// Simulated seconds per second of the `playback_fps` animation, at most MAX_PLAYBACK_FRAMES frames over `max_time` (default: 1.0)
+    #[serde(default = "default_playback_speed")]
+    pub playback_speed: f64,
+
+    /// With `playback_fps`, return only the playback trajectory and leave the raw one empty (default: false)
+    #[serde(default)]
+    pub playback_only: bool,
 }
 
+/// Most repeats of one simulation request, heavier sweeps belong in `/api/benchmark`
+pub const MAX_REPEATS: usize = 50;
+
+/// Highest `playback_fps`, beyond any display refresh rate
+pub const MAX_PLAYBACK_FPS: f64 = 240.0;
+
+/// Most playback frames of one vehicle over `max_time`, room for `MAX_PLAYBACK_FPS`
+/// over `DEFAULT_MAX_TIME` at normal speed. Frames are built up front and a
+/// tiny `playback_speed` would otherwise ask for billions of them.
+pub const MAX_PLAYBACK_FRAMES: usize = 250_000;
+
+/// Animation the trajectories of a simulation response are resampled for, see `analysis::resample_for_playback`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playback {
+    pub fps: f64,
+    pub speed: f64,
+    /// Leave the raw trajectories out of the response
+    pub only: bool,
+}
+
+// Defaults shared with `Scenario::default_exam` and reported by `GET /api/config`
 fn default_vehicle_types() -> Vec<String> {
-    vec!["Heavy".to_string(), "Standard".to_string(), "Agile".to_string()]
+    DEFAULT_VEHICLE_TYPES.iter().map(|vehicle_type| vehicle_type.id().to_string()).collect()
 }
 
-fn default_dt() -> f64 { 0.05 }
-fn default_max_time() -> f64 { 600.0 }
-fn default_map_width() -> f64 { 1000.0 }
-fn default_map_height() -> f64 { 800.0 }
-fn default_target_x() -> f64 { 500.0 }
-fn default_target_y() -> f64 { 700.0 }
+fn default_dt() -> f64 { DEFAULT_DT }
+fn default_max_time() -> f64 { DEFAULT_MAX_TIME }
+fn default_map_width() -> f64 { EXAM_MAP_SIZE.0 }
+fn default_map_height() -> f64 { EXAM_MAP_SIZE.1 }
+fn default_target_x() -> f64 { MapPreset::Exam.target().x }
+fn default_target_y() -> f64 { MapPreset::Exam.target().y }
+fn default_repeats() -> usize { 1 }
+fn default_playback_speed() -> f64 { 1.0 }
 
 #[derive(Debug, Deserialize)]
 pub struct BenchmarkRequest {
-    /// Number of iterations to run (default: 30)
-    #[serde(default = "default_iterations")]
-    pub iterations: usize,
+    /// Number of iterations to run (default: 30), not together with `duration_seconds`
+    #[serde(default)]
+    pub iterations: Option<usize>,
+
+    /// Wall-clock budget, run as many iterations as complete within it instead of a fixed count
+    #[serde(default)]
+    pub duration_seconds: Option<f64>,
+
+    /// Run in batches and stop once the estimates are precise enough, `iterations` is then the cap
+    #[serde(default)]
+    pub convergence: Option<Convergence>,
 
     /// Vehicle types to benchmark (default: all types)
     #[serde(default = "default_vehicle_types")]
@@ -69,9 +177,290 @@ pub struct BenchmarkRequest {
     /// Maximum simulation time in seconds (default: 600.0)
     #[serde(default = "default_max_time")]
     pub max_time: f64,
+
+    /// Seed for reproducible runs, iteration i uses seed + i (default: random)
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Confidence level of the reported intervals (default: 0.95)
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+
+    /// Arrival criteria, a preset name or explicit values (default: strict)
+    #[serde(default)]
+    pub criteria: Option<CriteriaSelection>,
+
+    /// Start heading distribution, or a list of them to sweep (default: toward_target)
+    #[serde(default)]
+    pub heading: Option<HeadingSelection>,
+
+    /// Start position distribution (default: uniform)
+    #[serde(default)]
+    pub start_distribution: StartDistribution,
+
+    /// Controller rules to skip in every run, 0-based indices (default: none)
+    #[serde(default)]
+    pub disabled_rules: Vec<usize>,
+
+    /// Constant velocity as a fraction of the max velocity of every vehicle (default: each vehicle cruise fraction)
+    #[serde(default)]
+    pub velocity_fraction: Option<f64>,
+
+    /// Also report the runs grouped by initial distance and/or heading error (default: not grouped)
+    #[serde(default)]
+    pub buckets: Option<InitialConditionBuckets>,
+
+    /// Also report the fraction of runs arrived over time in this many bins up to `max_time` (default: not reported)
+    #[serde(default)]
+    pub arrival_funnel_bins: Option<usize>,
+
+    /// Draw again the random starts that already meet the arrival criteria (default: false)
+    #[serde(default)]
+    pub reroll_degenerate: bool,
+
+    /// Count the arrivals at t=0 of degenerate starts in the arrival time statistics (default: false)
+    #[serde(default)]
+    pub include_degenerate_arrivals: bool,
+
+    /// Run even when the controller preflight fails (default: false)
+    #[serde(default)]
+    pub force: bool,
+}
+
+pub(super) fn default_iterations() -> usize { 30 }
+
+/// One start heading distribution, or several benchmarked one after the other
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum HeadingSelection {
+    Single(HeadingDistribution),
+    Sweep(Vec<HeadingDistribution>),
+}
+
+/// `"strict"`, `"normal"`, `"loose"` or an explicit `ArrivalCriteria` object
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CriteriaSelection {
+    Preset(String),
+    Explicit(ArrivalCriteria),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReachabilityRequest {
+    /// Vehicle type to analyze (default: Standard)
+    #[serde(default = "default_reachability_vehicle")]
+    pub vehicle_type: String,
+
+    /// Grid columns along x (default: 10)
+    #[serde(default = "default_grid_columns")]
+    pub columns: usize,
+
+    /// Grid rows along y (default: 8), columns x rows is capped at MAX_REACHABILITY_CELLS
+    #[serde(default = "default_grid_rows")]
+    pub rows: usize,
+
+    /// Time step in seconds (default: 0.05)
+    #[serde(default = "default_dt")]
+    pub dt: f64,
+
+    /// Maximum simulation time in seconds (default: 600.0)
+    #[serde(default = "default_max_time")]
+    pub max_time: f64,
+
+    /// Cell i (row-major) is built with seed + i (default: 0)
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Disturbance sweep of every vehicle type alone on the exam map
+#[derive(Debug, Deserialize)]
+pub struct RobustnessRequest {
+    /// Disturbance to grow (dropout)
+    pub axis: RobustnessAxis,
+
+    /// Disturbance levels in order, dropout starts per simulated minute for `dropout`
+    pub levels: Vec<f64>,
+
+    /// Runs per level and vehicle type, run i uses seed + i (default: 10)
+    #[serde(default = "default_robustness_iterations")]
+    pub iterations: usize,
+
+    /// Vehicle types to sweep (default: Heavy, Standard, Agile)
+    #[serde(default = "default_vehicle_types")]
+    pub vehicle_types: Vec<String>,
+
+    /// Time step in seconds (default: 0.05)
+    #[serde(default = "default_dt")]
+    pub dt: f64,
+
+    /// Maximum simulation time in seconds (default: 600.0)
+    #[serde(default = "default_max_time")]
+    pub max_time: f64,
+
+    /// Arrival criteria, a preset name or explicit values (default: strict)
+    #[serde(default)]
+    pub criteria: Option<CriteriaSelection>,
+
+    /// Distribution of the random start headings (default: toward_target)
+    #[serde(default)]
+    pub heading: HeadingDistribution,
+
+    /// Seed of the first run (default: random)
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Most runs of one robustness request, levels x iterations x vehicle types
+pub const MAX_ROBUSTNESS_RUNS: usize = 1000;
+
+fn default_robustness_iterations() -> usize { 10 }
+
+/// Two simulations to compare, give both the same `seed` so only the controller differs
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub a: SimulationRequest,
+    pub b: SimulationRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StepRequest {
+    /// Vehicle type whose preset drives the controller (default: Standard)
+    #[serde(default = "default_reachability_vehicle")]
+    pub vehicle_type: String,
+
+    /// Characteristics used instead of the preset, maneuverability in rad/s (default: preset)
+    #[serde(default)]
+    pub characteristics: Option<VehicleCharacteristics>,
+
+    /// Current vehicle state
+    pub state: StepState,
+
+    /// Target and required arrival heading (default: (500, 700) at 90°)
+    #[serde(default)]
+    pub target: StepTarget,
+
+    /// Map width, the approach point keeps turning room to the edges (default: 1000.0)
+    #[serde(default = "default_map_width")]
+    pub map_width: f64,
+
+    /// Map height (default: 800.0)
+    #[serde(default = "default_map_height")]
+    pub map_height: f64,
+
+    /// Time step in seconds (default: 0.05)
+    #[serde(default = "default_dt")]
+    pub dt: f64,
+
+    /// Arrival criteria, a preset name or explicit values (default: strict)
+    #[serde(default)]
+    pub criteria: Option<CriteriaSelection>,
+}
+
+/// Vehicle state as sent and returned by `POST /api/step`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepState {
+    pub x: f64,
+    pub y: f64,
+    pub angle_degrees: f64,
+    pub velocity: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StepTarget {
+    pub x: f64,
+    pub y: f64,
+    /// Required arrival heading (default: 90.0)
+    #[serde(default = "default_required_angle")]
+    pub angle_degrees: f64,
+}
+
+impl Default for StepTarget {
+    fn default() -> Self {
+        Self { x: default_target_x(), y: default_target_y(), angle_degrees: default_required_angle() }
+    }
+}
+
+fn default_required_angle() -> f64 { TARGET_REQUIRED_ANGLE.to_degrees().0 }
+
+fn default_reachability_vehicle() -> String { "Standard".to_string() }
+fn default_grid_columns() -> usize { 10 }
+fn default_grid_rows() -> usize { 8 }
+fn default_confidence() -> f64 { crate::analysis::stats::DEFAULT_CONFIDENCE }
+
+/// Body format of `POST /api/simulate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    GeoJson,
+    /// Trajectories and metrics as an Excel workbook, needs the `xlsx` feature
+    Xlsx,
+}
+
+/// Query string of `POST /api/simulate`
+#[derive(Debug, Deserialize)]
+pub struct SimulationQuery {
+    /// `json` (default), `geojson` or `xlsx`
+    #[serde(default)]
+    pub format: ResponseFormat,
+
+    /// Longitude of the map origin (default: 0.0)
+    #[serde(default)]
+    pub origin_lon: f64,
+
+    /// Latitude of the map origin (default: 0.0)
+    #[serde(default)]
+    pub origin_lat: f64,
+
+    /// Meters per map unit (default: 1.0)
+    #[serde(default = "default_meters_per_unit")]
+    pub meters_per_unit: f64,
 }
 
-fn default_iterations() -> usize { 30 }
+fn default_meters_per_unit() -> f64 { 1.0 }
+
+/// Body format of `POST /api/benchmark`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BenchmarkFormat {
+    /// Aggregate statistics per vehicle type
+    #[default]
+    Json,
+    /// Raw per-run table as a Parquet file, needs the `arrow` feature
+    Parquet,
+    /// Aggregate statistics as an Excel workbook, needs the `xlsx` feature
+    Xlsx,
+}
+
+/// Query string of `POST /api/benchmark`
+#[derive(Debug, Deserialize)]
+pub struct BenchmarkQuery {
+    /// `json` (default), `parquet` or `xlsx`
+    #[serde(default)]
+    pub format: BenchmarkFormat,
+}
+
+/// Query string of `GET /api/controller`
+#[derive(Debug, Deserialize)]
+pub struct ControllerQuery {
+    /// Vehicle type whose preset sizes the output range (default: Standard)
+    #[serde(default = "default_reachability_vehicle")]
+    pub vehicle_type: String,
+
+    /// Map the controller steers on, its diagonal places the distance sets (default: 1000x800)
+    #[serde(default = "default_map_width")]
+    pub map_width: f64,
+
+    #[serde(default = "default_map_height")]
+    pub map_height: f64,
+}
+
+impl SimulationQuery {
+    pub fn georef(&self) -> GeoRef {
+        GeoRef::new(self.origin_lon, self.origin_lat, self.meters_per_unit)
+    }
+}
 
 // ============================================================================
 // RESPONSE MODELS
@@ -82,38 +471,342 @@ pub struct SimulationResponse {
     pub success: bool,
     pub vehicles: Vec<VehicleSimulationResult>,
     pub total_simulation_time: f64,
+    /// Approach corridor of the target in the trajectory frame, only with `include_geometry`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approach_geometry: Option<ApproachGeometry>,
+    /// Build that computed the response
+    pub meta: BuildMeta,
+    /// Id of the run in the server run database, only when the server stores runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<i64>,
+    /// Spread over the repeats, only with `repeats > 1` (the trajectories are those of the first repeat)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_stats: Option<RepeatStats>,
     pub message: String,
 }
 
+/// Results of every vehicle across the repeats of one simulation request
+#[derive(Debug, Serialize)]
+pub struct RepeatStats {
+    pub repeats: usize,
+    /// Seed of every repeat, in repeat order
+    pub seeds: Vec<u64>,
+    pub vehicles: Vec<VehicleRepeatStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VehicleRepeatStats {
+    pub id: String,
+    pub vehicle_type: String,
+    pub successes: usize,
+    /// Over the repeats in which the vehicle arrived, 0 when it never did
+    pub avg_arrival_time: f64,
+    pub std_arrival_time: f64,
+    pub min_arrival_time: f64,
+    pub max_arrival_time: f64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct VehicleSimulationResult {
+    /// The requested label, or "<type>-<n>" for the n-th vehicle of its type
+    pub id: String,
     pub vehicle_type: String,
+    /// Empty with `playback_only`
     pub trajectory: Vec<TrajectoryPoint>,
+    /// The trajectory at the `playback_fps` frame rate, only with `playback_fps`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playback_trajectory: Option<Vec<TrajectoryPoint>>,
     pub metrics: SimulationMetrics,
+    /// Target the vehicle navigated to, in the same frame as the trajectory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<Point>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct BenchmarkResponse {
+    /// Layout version of the response, `BenchmarkConfig::from_results_json` upgrades older ones
+    pub schema_version: u32,
     pub success: bool,
     pub num_iterations: usize,
+    /// Wall-clock budget of a budgeted run, `num_iterations` is how many completed within it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+    /// Why an early stopping run stopped and the precision it reached, only with `convergence`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convergence: Option<ConvergenceReport>,
+    /// Every input of the run, send it back through `BenchmarkConfig` to repeat it
+    pub config: BenchmarkConfig,
     pub aggregate_stats: Vec<AggregateStats>,
+    /// Statistics of every non-empty initial condition bucket of every group, only with `buckets`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bucket_stats: Vec<AggregateStats>,
+    /// Wall time, throughput and peak memory of the whole benchmark
+    pub resource_usage: ResourceUsage,
+    /// Build that computed the response, on `resource_usage.threads` threads
+    pub meta: BuildMeta,
+    /// Id of the run in the server run database, only when the server stores runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<i64>,
     pub message: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct AggregateStats {
     pub vehicle_type: String,
+    /// Start heading distribution of these runs
+    pub heading: HeadingDistribution,
+    /// Initial conditions of these runs, absent for the overall statistics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<InitialConditionBucket>,
     pub total_runs: usize,
     pub successes: usize,
+    /// Runs that arrived before the first step, left out of the arrival times unless `include_degenerate_arrivals`
+    pub degenerate_starts: usize,
     pub success_rate: f64,
+    /// Wilson score interval of the success rate, in percent
+    pub success_rate_ci_low: f64,
+    pub success_rate_ci_high: f64,
     pub avg_arrival_time: f64,
+    /// Bootstrap interval of the mean arrival time
+    pub avg_arrival_time_ci_low: f64,
+    pub avg_arrival_time_ci_high: f64,
     pub std_arrival_time: f64,
     pub min_arrival_time: f64,
     pub max_arrival_time: f64,
     pub avg_distance_traveled: f64,
     pub std_distance_traveled: f64,
+    /// Arrival time and distance traveled without their outlier runs (`stats::outliers`), the figures above stay the headline
+    pub trimmed_arrival_time: TrimmedStats,
+    pub trimmed_distance_traveled: TrimmedStats,
     pub avg_final_distance: f64,
     pub avg_final_angle_error: f64,
+    /// Steering sign changes per simulated minute, high values mean chattering
+    pub avg_steering_reversals_per_minute: f64,
+    /// Mean absolute second difference of the heading (degrees)
+    pub avg_heading_jerk: f64,
+    /// Velocity profile, only present for runs with velocity control
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_time_above_high_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_arrival_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_acceleration_reversals: Option<f64>,
+    /// Mean over runs of the largest approach bias (degrees), also over the failed runs alone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_max_approach_bias_deg: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_max_approach_bias_deg_failed: Option<f64>,
+    /// Mean over runs of the mean approach bias inside the corridor (degrees)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_approach_bias_deg_inside_corridor: Option<f64>,
+    /// Mean arrival time over the Dubins lower bound of the arrived runs, 1 is optimal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_time_optimality_ratio: Option<f64>,
+    /// Mean time to first get within `APPROACH_START`, over the runs that did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_transit_time: Option<f64>,
+    /// Mean time from the transit to the arrival, over the arrived runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_alignment_time: Option<f64>,
+    /// Mean time to first get within the angle tolerance, over the runs that did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_first_aligned_time: Option<f64>,
+    /// Entries into `APPROACH_START` per run
+    pub avg_alignment_attempts: f64,
+    /// (t, fraction arrived) from 0 to `max_time`, only for the overall statistics with `arrival_funnel_bins`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_funnel: Option<Vec<(f64, f64)>>,
+    /// Runs with an outlier arrival time or distance and their seeds, only for the overall statistics
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub outlier_runs: Vec<OutlierRun>,
+    /// Wall time of one run (seconds)
+    pub avg_wall_time: f64,
+    pub p95_wall_time: f64,
+}
+
+/// Reachability heatmap, `reachable[row][column]` with row 0 at the bottom of the map
+#[derive(Debug, Serialize)]
+pub struct ReachabilityResponse {
+    pub success: bool,
+    pub vehicle_type: String,
+    pub columns: usize,
+    pub rows: usize,
+    pub cell_width: f64,
+    pub cell_height: f64,
+    pub reachable: Vec<Vec<bool>>,
+    pub arrival_times: Vec<Vec<Option<f64>>>,
+    pub reachable_cells: usize,
+    pub message: String,
+}
+
+/// Success rate and final angle error per disturbance level, a curve per vehicle type
+#[derive(Debug, Serialize)]
+pub struct RobustnessResponse {
+    pub success: bool,
+    #[serde(flatten)]
+    pub report: RobustnessReport,
+    pub meta: BuildMeta,
+    pub message: String,
+}
+
+/// Where a benchmark job is, see `jobs::JobStore`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    /// Interrupted by a restart of the server, `POST .../resume` continues it
+    Resumable,
+    /// The result is stored and served by `GET .../result`
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            JobStatus::Running => "running",
+            JobStatus::Resumable => "resumable",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        })
+    }
+}
+
+/// Progress of a benchmark job
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub status: JobStatus,
+    /// Iterations whose metrics are stored, always `0..completed_iterations`
+    pub completed_iterations: usize,
+    pub total_iterations: usize,
+    /// Why a failed job stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Response of the job endpoints other than the result
+#[derive(Debug, Serialize)]
+pub struct BenchmarkJobResponse {
+    pub success: bool,
+    #[serde(flatten)]
+    pub job: JobInfo,
+    pub meta: BuildMeta,
+    pub message: String,
+}
+
+/// Differences of every vehicle between two runs, matched by type (B minus A)
+#[derive(Debug, Serialize)]
+pub struct CompareResponse {
+    pub success: bool,
+    pub vehicles: Vec<VehicleDelta>,
+    /// Build that computed both runs
+    pub meta: BuildMeta,
+    pub message: String,
+}
+
+/// One controller step from the requested state
+///
+/// When the state already meets the arrival criteria the vehicle does not
+/// move: `arrived` is set, no set is activated and `next_state` equals the
+/// requested state.
+#[derive(Debug, Serialize)]
+pub struct StepResponse {
+    pub success: bool,
+    pub vehicle_type: String,
+    pub arrived: bool,
+    pub inputs: StepInputs,
+    /// Output sets with a non-zero activation, in output variable order
+    pub activated_sets: Vec<ActivatedSet>,
+    /// Defuzzified controller output (degrees/second)
+    pub commanded_adjustment: f64,
+    /// Output clamped to the vehicle maneuverability (degrees/second)
+    pub angular_adjustment: f64,
+    pub next_state: StepState,
+    pub message: String,
+}
+
+/// Fuzzy controller inputs of the requested state
+#[derive(Debug, Serialize)]
+pub struct StepInputs {
+    pub distance_to_target: f64,
+    /// Interpolated angular error towards the approach point (degrees)
+    pub angular_error: f64,
+    pub velocity_relative: f64,
+    pub approach_x: f64,
+    pub approach_y: f64,
+    /// Heading towards the approach point (degrees)
+    pub desired_heading: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivatedSet {
+    pub name: String,
+    pub degree: f64,
+}
+
+/// Fuzzy system of a vehicle controller, as built by `NavigationController::new`
+///
+/// Angles are in radians as the controller uses them: `error_angular` spans
+/// [-π, π] and `ajuste_angular` spans ± the vehicle maneuverability (rad/s).
+#[derive(Debug, Serialize)]
+pub struct ControllerResponse {
+    pub success: bool,
+    pub vehicle_type: String,
+    pub name: String,
+    pub inputs: Vec<VariableDescription>,
+    pub output: VariableDescription,
+    pub rules: Vec<RuleDescription>,
+    pub defuzzification: DefuzzificationDescription,
+    /// Whole system as plain text, the `Display` of the fuzzy system
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VariableDescription {
+    pub name: String,
+    /// `[min, max]`
+    pub range: (f64, f64),
+    /// The range is one turn of a circle, sets past `max` continue from `min`
+    pub circular: bool,
+    pub sets: Vec<SetDescription>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetDescription {
+    pub name: String,
+    /// Shape and parameters, `null` for custom membership functions
+    pub membership: Option<MembershipSpec>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleDescription {
+    /// 1-based, as numbered in `description`
+    pub number: usize,
+    pub antecedents: Vec<RuleTerm>,
+    pub consequents: Vec<RuleTerm>,
+    /// `and` or `or`, combining the antecedents
+    pub operator: String,
+    /// Rules are not weighted, every rule fires with weight 1
+    pub weight: f64,
+    /// Human-readable form, "if ... then ..."
+    pub text: String,
+}
+
+/// `variable is set`
+#[derive(Debug, Serialize)]
+pub struct RuleTerm {
+    pub variable: String,
+    pub set: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DefuzzificationDescription {
+    pub method: String,
+    /// Integration intervals over the output range
+    pub resolution: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -122,6 +815,110 @@ pub struct ErrorResponse {
     pub details: Option<String>,
 }
 
+/// Work a request runs, what the server quota counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaCost {
+    /// Simulated vehicle steps, vehicles x steps x iterations
+    pub steps: u64,
+    /// Benchmark iterations
+    pub iterations: u64,
+}
+
+/// Usage of a client within the quota window, from `GET /api/quota` and in a 429 response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaStatus {
+    /// `X-Forwarded-For` address the usage is counted under
+    pub client: String,
+    /// False when the server has no quota, the limits are then absent
+    pub enabled: bool,
+    pub window_seconds: f64,
+    pub used: QuotaCost,
+    /// Largest usage within the window, per counter (absent when unlimited)
+    pub steps_limit: Option<u64>,
+    pub iterations_limit: Option<u64>,
+    pub steps_remaining: Option<u64>,
+    pub iterations_remaining: Option<u64>,
+    /// Seconds until the oldest counted request leaves the window, 0 without usage
+    pub reset_in_seconds: f64,
+    /// Projected cost of the rejected request, only in a 429
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested: Option<QuotaCost>,
+    pub message: String,
+}
+
+/// Defaults the endpoints apply to omitted fields, for previews drawn before the first run
+#[derive(Debug, Serialize)]
+pub struct ConfigResponse {
+    pub version: String,
+    pub map: MapDefaults,
+    /// Time step in seconds
+    pub dt: f64,
+    /// Maximum simulation time in seconds
+    pub max_time: f64,
+    /// Arrival criteria preset used when `criteria` is omitted
+    pub default_criteria: String,
+    pub criteria_presets: Vec<CriteriaPreset>,
+    pub approach: ApproachDefaults,
+    /// Identifiers accepted in `vehicle_types` and `vehicle_type`
+    pub vehicle_types: Vec<String>,
+    /// Vehicles simulated when `vehicle_types` is omitted
+    pub default_vehicle_types: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MapDefaults {
+    pub width: f64,
+    pub height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    /// Arrival heading of the target (degrees)
+    pub required_angle: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CriteriaPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub criteria: ArrivalCriteria,
+}
+
+/// Approach point geometry, the same for every vehicle
+#[derive(Debug, Serialize)]
+pub struct ApproachDefaults {
+    /// Distance to target at which the controller starts steering to the approach point
+    pub start_distance: f64,
+    /// Approach point offset below the target at `start_distance`
+    pub max_offset: f64,
+}
+
+impl ConfigResponse {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            map: MapDefaults {
+                width: default_map_width(),
+                height: default_map_height(),
+                target_x: default_target_x(),
+                target_y: default_target_y(),
+                required_angle: default_required_angle(),
+            },
+            dt: default_dt(),
+            max_time: default_max_time(),
+            default_criteria: ArrivalCriteria::presets()
+                .into_iter()
+                .find(|(_, criteria)| *criteria == ArrivalCriteria::default())
+                .map_or_else(String::new, |(name, _)| name.to_string()),
+            criteria_presets: ArrivalCriteria::presets()
+                .into_iter()
+                .map(|(name, criteria)| CriteriaPreset { name: name.to_string(), criteria })
+                .collect(),
+            approach: ApproachDefaults { start_distance: APPROACH_START, max_offset: APPROACH_MAX_OFFSET },
+            vehicle_types: VehicleType::ALL.iter().map(|vehicle_type| vehicle_type.id().to_string()).collect(),
+            default_vehicle_types: default_vehicle_types(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -129,34 +926,308 @@ pub struct HealthResponse {
     pub message: String,
 }
 
+/// Response of `GET /health/deep`, served with 503 when a check failed
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepHealthResponse {
+    /// "healthy" when every check passed, "unhealthy" otherwise
+    pub status: String,
+    pub version: String,
+    /// Served from the cache instead of running the checks again
+    pub cached: bool,
+    /// Seconds since the checks ran
+    pub age_seconds: f64,
+    /// Wall time of every check together (milliseconds)
+    pub duration_ms: f64,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl DeepHealthResponse {
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+/// One check of the deep health endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    /// `controller:<vehicle type>` or `simulation`
+    pub name: String,
+    pub passed: bool,
+    pub duration_ms: f64,
+    /// Why it failed, the panic message when it panicked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
 
+fn parse_vehicle_types(names: &[String]) -> Result<Vec<VehicleType>, SimulationError> {
+    names
+        .iter()
+        .map(|s| VehicleType::parse(s).ok_or_else(|| SimulationError::UnknownVehicleType(s.clone())))
+        .collect()
+}
+
+fn parse_criteria(selection: &Option<CriteriaSelection>) -> Result<ArrivalCriteria, SimulationError> {
+    match selection {
+        None => Ok(ArrivalCriteria::default()),
+        Some(CriteriaSelection::Preset(name)) => {
+            ArrivalCriteria::parse(name).ok_or_else(|| SimulationError::UnknownCriteria(name.clone()))
+        }
+        Some(CriteriaSelection::Explicit(criteria)) => Ok(*criteria),
+    }
+}
+
 impl SimulationRequest {
-    pub fn parse_vehicle_types(&self) -> Result<Vec<VehicleType>, String> {
-        self.vehicle_types
-            .iter()
-            .map(|s| match s.to_lowercase().as_str() {
-                "heavy" => Ok(VehicleType::Heavy),
-                "standard" => Ok(VehicleType::Standard),
-                "agile" => Ok(VehicleType::Agile),
-                _ => Err(format!("Unknown vehicle type: {}. Valid types: Heavy, Standard, Agile", s)),
-            })
-            .collect()
+    pub fn parse_vehicle_types(&self) -> Result<Vec<VehicleType>, SimulationError> {
+        parse_vehicle_types(&self.vehicle_types)
+    }
+
+    pub fn parse_criteria(&self) -> Result<ArrivalCriteria, SimulationError> {
+        parse_criteria(&self.criteria)
+    }
+
+    /// Requested vehicles in order, labeled with `vehicle_ids` when given
+    fn scenario_vehicles(&self) -> Result<Vec<ScenarioVehicle>, SimulationError> {
+        let types = self.parse_vehicle_types()?;
+        if !self.vehicle_ids.is_empty() && self.vehicle_ids.len() != types.len() {
+            return Err(SimulationError::VehicleIdCount { ids: self.vehicle_ids.len(), vehicles: types.len() });
+        }
+        let mut ids = self.vehicle_ids.iter().cloned().map(Some).chain(std::iter::repeat(None));
+        Ok(types
+            .into_iter()
+            .map(|vehicle_type| ScenarioVehicle { id: ids.next().flatten(), ..ScenarioVehicle::random(vehicle_type) })
+            .collect())
+    }
+
+    /// Seed of every repeat, `seed + i` for repeat i
+    ///
+    /// A single repeat keeps the request seed, unseeded or not, several
+    /// unseeded repeats start from a random seed.
+    pub fn repeat_seeds(&self) -> Result<Vec<Option<u64>>, SimulationError> {
+        match self.repeats {
+            0 => Err(SimulationError::InvalidRepeats { repeats: 0, max: MAX_REPEATS }),
+            1 => Ok(vec![self.seed]),
+            repeats if repeats > MAX_REPEATS => Err(SimulationError::InvalidRepeats { repeats, max: MAX_REPEATS }),
+            repeats => {
+                let seed = self.seed.unwrap_or_else(rand::random);
+                Ok((0..repeats as u64).map(|i| Some(seed.wrapping_add(i))).collect())
+            }
+        }
+    }
+
+    /// Playback resampling of the request, None without `playback_fps`
+    pub fn playback(&self) -> Result<Option<Playback>, SimulationError> {
+        let Some(fps) = self.playback_fps else {
+            return Ok(None);
+        };
+        let speed = self.playback_speed;
+        let frames = fps * self.max_time / speed;
+        let valid = fps > 0.0 && fps <= MAX_PLAYBACK_FPS && speed > 0.0 && speed.is_finite();
+        if !(valid && frames <= MAX_PLAYBACK_FRAMES as f64) {
+            return Err(SimulationError::InvalidPlayback {
+                fps,
+                speed,
+                max_fps: MAX_PLAYBACK_FPS,
+                max_frames: MAX_PLAYBACK_FRAMES,
+            });
+        }
+        Ok(Some(Playback { fps, speed, only: self.playback_only }))
+    }
+
+    /// Scenario of the request, every vehicle from a random start
+    pub fn to_scenario(&self) -> Result<Scenario, SimulationError> {
+        Ok(Scenario {
+            map_width: self.map_width,
+            map_height: self.map_height,
+            target_x: self.target_x,
+            target_y: self.target_y,
+            vehicles: self.scenario_vehicles()?,
+            dt: self.dt,
+            auto_dt: self.auto_dt,
+            max_time: self.max_time,
+            criteria: self.parse_criteria()?,
+            seed: self.seed,
+            heading: self.heading,
+            disabled_rules: self.disabled_rules.clone(),
+            distance_scale: self.distance_scale,
+            velocity_fraction: self.velocity_fraction,
+            include_geometry: self.include_geometry,
+            dropout: self.dropout,
+            ..Scenario::default_exam()
+        })
     }
 }
 
 impl BenchmarkRequest {
-    pub fn parse_vehicle_types(&self) -> Result<Vec<VehicleType>, String> {
-        self.vehicle_types
-            .iter()
-            .map(|s| match s.to_lowercase().as_str() {
-                "heavy" => Ok(VehicleType::Heavy),
-                "standard" => Ok(VehicleType::Standard),
-                "agile" => Ok(VehicleType::Agile),
-                _ => Err(format!("Unknown vehicle type: {}. Valid types: Heavy, Standard, Agile", s)),
-            })
-            .collect()
+    pub fn parse_vehicle_types(&self) -> Result<Vec<VehicleType>, SimulationError> {
+        parse_vehicle_types(&self.vehicle_types)
+    }
+
+    pub fn parse_criteria(&self) -> Result<ArrivalCriteria, SimulationError> {
+        parse_criteria(&self.criteria)
+    }
+
+    /// Full configuration of the requested benchmark on the exam map, unseeded requests get a random seed
+    pub fn to_config(&self) -> Result<BenchmarkConfig, SimulationError> {
+        let iterations = match (self.iterations, self.duration_seconds) {
+            (Some(_), Some(_)) => return Err(SimulationError::IterationsAndDuration),
+            (None, Some(_)) => 0,
+            (iterations, None) => iterations.unwrap_or_else(default_iterations),
+        };
+        Ok(BenchmarkConfig {
+            iterations,
+            duration_seconds: self.duration_seconds,
+            convergence: self.convergence,
+            vehicles: self.parse_vehicle_types()?.into_iter().map(VehicleSpec::preset).collect(),
+            dt: self.dt,
+            max_time: self.max_time,
+            headings: match &self.heading {
+                None => vec![HeadingDistribution::TowardTarget],
+                Some(HeadingSelection::Single(heading)) => vec![*heading],
+                Some(HeadingSelection::Sweep(headings)) => headings.clone(),
+            },
+            start_distribution: self.start_distribution,
+            criteria: self.parse_criteria()?,
+            disabled_rules: self.disabled_rules.clone(),
+            velocity_fraction: self.velocity_fraction,
+            buckets: self.buckets.clone(),
+            arrival_funnel_bins: self.arrival_funnel_bins,
+            reroll_degenerate: self.reroll_degenerate,
+            include_degenerate_arrivals: self.include_degenerate_arrivals,
+            seed: self.seed.unwrap_or_else(rand::random),
+            threads: self.threads,
+            confidence: self.confidence,
+            ..Default::default()
+        })
+    }
+}
+
+impl RobustnessRequest {
+    pub fn parse_vehicle_types(&self) -> Result<Vec<VehicleType>, SimulationError> {
+        parse_vehicle_types(&self.vehicle_types)
+    }
+
+    /// Runs of the sweep, refused beyond `MAX_ROBUSTNESS_RUNS`
+    pub fn runs(&self) -> Result<usize, SimulationError> {
+        let runs = self.levels.len() * self.iterations * self.vehicle_types.len();
+        if runs > MAX_ROBUSTNESS_RUNS {
+            return Err(SimulationError::TooManyRobustnessRuns { runs, max: MAX_ROBUSTNESS_RUNS });
+        }
+        Ok(runs)
+    }
+
+    /// Undisturbed scenario of the sweep, its vehicles are replaced run by run
+    pub fn to_scenario(&self) -> Result<Scenario, SimulationError> {
+        Ok(Scenario {
+            dt: self.dt,
+            max_time: self.max_time,
+            criteria: parse_criteria(&self.criteria)?,
+            heading: self.heading,
+            ..Scenario::default_exam()
+        })
+    }
+}
+
+impl StepRequest {
+    pub fn parse_vehicle_type(&self) -> Result<VehicleType, SimulationError> {
+        VehicleType::parse(&self.vehicle_type).ok_or_else(|| SimulationError::UnknownVehicleType(self.vehicle_type.clone()))
+    }
+
+    pub fn parse_criteria(&self) -> Result<ArrivalCriteria, SimulationError> {
+        parse_criteria(&self.criteria)
+    }
+
+    /// Map holding the requested target, rejecting targets off the map
+    pub fn to_map(&self) -> Result<Map, MapError> {
+        let mut map = Map::try_new(self.map_width, self.map_height, self.target.x, self.target.y)?;
+        map.target = self.target.to_target();
+        Ok(map)
+    }
+}
+
+impl StepState {
+    pub fn from_vehicle_state(state: &VehicleState) -> Self {
+        Self {
+            x: state.position.x,
+            y: state.position.y,
+            angle_degrees: state.angle.to_degrees().0,
+            velocity: state.velocity,
+        }
+    }
+
+    pub fn to_vehicle_state(&self) -> VehicleState {
+        VehicleState {
+            position: Point::new(self.x, self.y),
+            angle: Degrees(self.angle_degrees).to_radians(),
+            velocity: self.velocity,
+        }
+    }
+}
+
+impl StepTarget {
+    pub fn to_target(&self) -> Target {
+        Target { position: Point::new(self.x, self.y), required_angle: Degrees(self.angle_degrees).to_radians() }
+    }
+}
+
+impl ControllerQuery {
+    pub fn parse_vehicle_type(&self) -> Result<VehicleType, SimulationError> {
+        VehicleType::parse(&self.vehicle_type).ok_or_else(|| SimulationError::UnknownVehicleType(self.vehicle_type.clone()))
+    }
+
+    /// Distance scale of the requested map, see `DistanceScale::for_size`
+    pub fn distance_scale(&self) -> Result<DistanceScale, SimulationError> {
+        let distance_scale = DistanceScale::for_size(self.map_width, self.map_height);
+        distance_scale.validate()?;
+        Ok(distance_scale)
+    }
+}
+
+impl VariableDescription {
+    pub fn from_variable(variable: &LinguisticVariable) -> Self {
+        Self {
+            name: variable.name.clone(),
+            range: variable.range,
+            circular: variable.circular,
+            sets: variable
+                .fuzzy_sets
+                .iter()
+                .map(|set| SetDescription { name: set.name.clone(), membership: set.spec() })
+                .collect(),
+        }
+    }
+}
+
+impl RuleDescription {
+    pub fn from_rule(number: usize, rule: &FuzzyRule) -> Self {
+        Self {
+            number,
+            antecedents: rule
+                .antecedents
+                .iter()
+                .map(|a| RuleTerm { variable: a.variable.clone(), set: a.set.clone() })
+                .collect(),
+            consequents: rule
+                .consequents
+                .iter()
+                .map(|c| RuleTerm { variable: c.variable.clone(), set: c.set.clone() })
+                .collect(),
+            operator: match rule.operator {
+                RuleOperator::And => "and",
+                RuleOperator::Or => "or",
+            }
+            .to_string(),
+            weight: 1.0,
+            text: rule.to_string(),
+        }
+    }
+}
+
+impl ReachabilityRequest {
+    pub fn parse_vehicle_type(&self) -> Result<VehicleType, SimulationError> {
+        VehicleType::parse(&self.vehicle_type).ok_or_else(|| SimulationError::UnknownVehicleType(self.vehicle_type.clone()))
     }
 }
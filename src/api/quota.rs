@@ -0,0 +1,282 @@
+// Per-client quota of the API: simulated steps and benchmark iterations each
+// client ran within a sliding window, so one long benchmark cannot starve the
+// other users of a shared instance
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use shuttle_axum::axum::http::HeaderMap;
+
+use super::models::{QuotaCost, QuotaStatus};
+
+/// Environment variable with the simulated steps a client may run per window
+pub const QUOTA_STEPS_ENV: &str = "API_QUOTA_STEPS";
+/// Environment variable with the benchmark iterations a client may run per window
+pub const QUOTA_ITERATIONS_ENV: &str = "API_QUOTA_ITERATIONS";
+/// Environment variable with the window length in seconds, one hour when unset
+pub const QUOTA_WINDOW_ENV: &str = "API_QUOTA_WINDOW_SECONDS";
+
+const DEFAULT_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Client the usage of a request without `X-Forwarded-For` is counted under
+pub const LOCAL_CLIENT: &str = "local";
+
+/// Limits of every client, `None` leaves a counter unlimited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuotaLimits {
+    pub steps: Option<u64>,
+    pub iterations: Option<u64>,
+    pub window: Duration,
+}
+
+impl QuotaLimits {
+    /// Limits from `API_QUOTA_STEPS` and `API_QUOTA_ITERATIONS`, `None` when neither is set
+    pub fn from_env() -> Option<Self> {
+        let read = |name: &str| std::env::var(name).ok().and_then(|value| value.trim().parse::<u64>().ok());
+        let limits = Self {
+            steps: read(QUOTA_STEPS_ENV),
+            iterations: read(QUOTA_ITERATIONS_ENV),
+            window: read(QUOTA_WINDOW_ENV).map(Duration::from_secs).unwrap_or(DEFAULT_WINDOW),
+        };
+        (limits.steps.is_some() || limits.iterations.is_some()).then_some(limits)
+    }
+}
+
+/// Usage of every client within the window, shared by the requests
+#[derive(Debug)]
+pub struct QuotaTracker {
+    limits: QuotaLimits,
+    usage: Mutex<HashMap<String, VecDeque<(Instant, QuotaCost)>>>,
+}
+
+impl QuotaTracker {
+    pub fn new(limits: QuotaLimits) -> Self {
+        Self { limits, usage: Mutex::default() }
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        self.limits
+    }
+
+    /// Current usage of `client`
+    pub fn status(&self, client: &str) -> QuotaStatus {
+        self.status_at(client, Instant::now())
+    }
+
+    /// Count `cost` against `client` if it fits in the remaining quota
+    ///
+    /// `Err` carries the status the request was refused with, its usage is not counted.
+    pub fn charge(&self, client: &str, cost: QuotaCost) -> Result<QuotaStatus, Box<QuotaStatus>> {
+        self.charge_at(client, cost, Instant::now())
+    }
+
+    /// Count `cost` against `client` whatever the remaining quota, for work found only after it ran
+    pub fn record(&self, client: &str, cost: QuotaCost) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.entry(client.to_string()).or_default().push_back((Instant::now(), cost));
+    }
+
+    fn status_at(&self, client: &str, now: Instant) -> QuotaStatus {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        self.expire(&mut usage, client, now);
+        self.describe(client, usage.get(client).unwrap_or(&NO_ENTRIES), now, None)
+    }
+
+    fn charge_at(&self, client: &str, cost: QuotaCost, now: Instant) -> Result<QuotaStatus, Box<QuotaStatus>> {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        self.sweep(&mut usage, now);
+        self.expire(&mut usage, client, now);
+        let entries = usage.get(client).unwrap_or(&NO_ENTRIES);
+        let used = total(entries);
+        let fits = |used: u64, cost: u64, limit: Option<u64>| limit.is_none_or(|limit| used.saturating_add(cost) <= limit);
+        if !(fits(used.steps, cost.steps, self.limits.steps) && fits(used.iterations, cost.iterations, self.limits.iterations)) {
+            return Err(Box::new(self.describe(client, entries, now, Some(cost))));
+        }
+        let entries = usage.entry(client.to_string()).or_default();
+        entries.push_back((now, cost));
+        Ok(self.describe(client, entries, now, None))
+    }
+
+    /// Drop the entries of `client` past the window at `now`, and the client once none are left
+    ///
+    /// Clients are keyed by the caller-controlled `X-Forwarded-For`, so only
+    /// clients with usage in the window are kept.
+    fn expire(&self, usage: &mut HashMap<String, VecDeque<(Instant, QuotaCost)>>, client: &str, now: Instant) {
+        let Some(entries) = usage.get_mut(client) else { return };
+        while entries.front().is_some_and(|(at, _)| now.duration_since(*at) >= self.limits.window) {
+            entries.pop_front();
+        }
+        if entries.is_empty() {
+            usage.remove(client);
+        }
+    }
+
+    /// Drop every client whose newest entry is past the window at `now`
+    fn sweep(&self, usage: &mut HashMap<String, VecDeque<(Instant, QuotaCost)>>, now: Instant) {
+        usage.retain(|_, entries| entries.back().is_some_and(|(at, _)| now.duration_since(*at) < self.limits.window));
+    }
+
+    fn describe(
+        &self,
+        client: &str,
+        entries: &VecDeque<(Instant, QuotaCost)>,
+        now: Instant,
+        requested: Option<QuotaCost>,
+    ) -> QuotaStatus {
+        let used = total(entries);
+        let reset_in = entries
+            .front()
+            .map(|(at, _)| self.limits.window.saturating_sub(now.duration_since(*at)))
+            .unwrap_or_default();
+        let message = match requested {
+            Some(cost) => format!(
+                "Quota exceeded: the request needs {} steps and {} iterations, usage resets in {:.0}s",
+                cost.steps,
+                cost.iterations,
+                reset_in.as_secs_f64()
+            ),
+            None => "Within quota".to_string(),
+        };
+        QuotaStatus {
+            client: client.to_string(),
+            enabled: true,
+            window_seconds: self.limits.window.as_secs_f64(),
+            used,
+            steps_limit: self.limits.steps,
+            iterations_limit: self.limits.iterations,
+            steps_remaining: self.limits.steps.map(|limit| limit.saturating_sub(used.steps)),
+            iterations_remaining: self.limits.iterations.map(|limit| limit.saturating_sub(used.iterations)),
+            reset_in_seconds: reset_in.as_secs_f64(),
+            requested,
+            message,
+        }
+    }
+}
+
+static NO_ENTRIES: VecDeque<(Instant, QuotaCost)> = VecDeque::new();
+
+fn total(entries: &VecDeque<(Instant, QuotaCost)>) -> QuotaCost {
+    entries.iter().fold(QuotaCost::default(), |sum, (_, cost)| QuotaCost {
+        steps: sum.steps.saturating_add(cost.steps),
+        iterations: sum.iterations.saturating_add(cost.iterations),
+    })
+}
+
+/// Status of a server without quota
+pub fn unlimited_status(client: &str) -> QuotaStatus {
+    QuotaStatus {
+        client: client.to_string(),
+        enabled: false,
+        window_seconds: 0.0,
+        used: QuotaCost::default(),
+        steps_limit: None,
+        iterations_limit: None,
+        steps_remaining: None,
+        iterations_remaining: None,
+        reset_in_seconds: 0.0,
+        requested: None,
+        message: "No quota on this server".to_string(),
+    }
+}
+
+/// Client a request is counted under: the first `X-Forwarded-For` address, `LOCAL_CLIENT` without one
+///
+/// The API has no authentication, so the address set by the proxy in front of
+/// the service is the only identifier.
+pub fn client_id(headers: &HeaderMap) -> String {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .unwrap_or(LOCAL_CLIENT)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> QuotaTracker {
+        QuotaTracker::new(QuotaLimits { steps: Some(250), iterations: Some(10), window: Duration::from_secs(60) })
+    }
+
+    #[test]
+    fn test_charges_past_the_limit_are_refused_without_being_counted() {
+        let tracker = tracker();
+        let now = Instant::now();
+        let cost = QuotaCost { steps: 100, iterations: 0 };
+        assert!(tracker.charge_at("a", cost, now).is_ok());
+        let status = tracker.charge_at("a", cost, now).unwrap();
+        assert_eq!(status.steps_remaining, Some(50));
+
+        let refused = tracker.charge_at("a", cost, now).unwrap_err();
+        assert_eq!(refused.used.steps, 200);
+        assert_eq!(refused.steps_remaining, Some(50));
+        assert_eq!(refused.requested, Some(cost));
+        assert_eq!(tracker.status_at("a", now).used.steps, 200);
+        assert_eq!(tracker.status_at("b", now).used.steps, 0);
+    }
+
+    #[test]
+    fn test_costs_that_overflow_are_over_the_limit() {
+        let tracker = tracker();
+        let now = Instant::now();
+        tracker.charge_at("a", QuotaCost { steps: 100, iterations: 0 }, now).unwrap();
+        assert!(tracker.charge_at("a", QuotaCost { steps: u64::MAX, iterations: 0 }, now).is_err());
+
+        let unlimited = QuotaTracker::new(QuotaLimits { steps: None, ..tracker.limits() });
+        unlimited.charge_at("a", QuotaCost { steps: u64::MAX, iterations: 0 }, now).unwrap();
+        assert_eq!(unlimited.charge_at("a", QuotaCost { steps: 1, iterations: 0 }, now).unwrap().used.steps, u64::MAX);
+    }
+
+    #[test]
+    fn test_usage_leaves_the_window_once_it_expires() {
+        let tracker = tracker();
+        let start = Instant::now();
+        tracker.charge_at("a", QuotaCost { steps: 200, iterations: 5 }, start).unwrap();
+        let later = start + Duration::from_secs(30);
+        tracker.charge_at("a", QuotaCost { steps: 50, iterations: 5 }, later).unwrap();
+        assert!(tracker.charge_at("a", QuotaCost { steps: 100, iterations: 0 }, later).is_err());
+        assert_eq!(tracker.status_at("a", later).reset_in_seconds, 30.0);
+
+        // The first charge expires, the second one still counts
+        let status = tracker.charge_at("a", QuotaCost { steps: 100, iterations: 0 }, start + Duration::from_secs(60)).unwrap();
+        assert_eq!(status.used, QuotaCost { steps: 150, iterations: 5 });
+        let status = tracker.status_at("a", start + Duration::from_secs(120));
+        assert_eq!(status.used, QuotaCost::default());
+        assert_eq!(status.reset_in_seconds, 0.0);
+    }
+
+    #[test]
+    fn test_only_clients_with_usage_in_the_window_are_kept() {
+        let tracker = tracker();
+        let clients = || tracker.usage.lock().unwrap().len();
+        let start = Instant::now();
+        for client in ["a", "b", "c"] {
+            tracker.status_at(client, start);
+        }
+        assert_eq!(clients(), 0);
+
+        tracker.charge_at("a", QuotaCost { steps: 10, iterations: 0 }, start).unwrap();
+        tracker.charge_at("b", QuotaCost { steps: 10, iterations: 0 }, start + Duration::from_secs(30)).unwrap();
+        assert_eq!(clients(), 2);
+        tracker.status_at("a", start + Duration::from_secs(60));
+        assert_eq!(clients(), 1);
+
+        // Charging any client drops the others whose usage expired
+        tracker.charge_at("c", QuotaCost { steps: 10, iterations: 0 }, start + Duration::from_secs(90)).unwrap();
+        assert_eq!(clients(), 1);
+        assert_eq!(tracker.status_at("c", start + Duration::from_secs(90)).used.steps, 10);
+    }
+
+    #[test]
+    fn test_client_is_the_first_forwarded_address() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_id(&headers), LOCAL_CLIENT);
+        headers.insert("x-forwarded-for", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        assert_eq!(client_id(&headers), "203.0.113.7");
+    }
+}
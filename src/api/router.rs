@@ -0,0 +1,350 @@
+// Router of the API: every endpoint behind CORS and request tracing, built here
+// so the tests exercise the same wiring as the deployed service
+
+use std::sync::Arc;
+
+use shuttle_axum::axum::{
+    routing::{get, post},
+    Router,
+};
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+
+use crate::navigation::SharedControllers;
+use super::handlers;
+use super::health::DeepHealth;
+use super::jobs::{JobStore, JOBS_DIR_ENV};
+use super::quota::{QuotaLimits, QuotaTracker};
+
+/// Environment variable with the path of the run database, see `ApiState::from_env`
+pub const RUN_STORE_ENV: &str = "RUN_STORE_PATH";
+
+/// State shared by every request
+#[derive(Clone, Default)]
+pub struct ApiState {
+    /// Controllers shared by the simulation and benchmark requests
+    pub controllers: Arc<SharedControllers>,
+    /// Database the JSON simulations and benchmarks are stored in, see `storage::RunStore`
+    #[cfg(feature = "storage")]
+    pub store: Option<std::path::PathBuf>,
+    /// Usage of every client against the server quota, unlimited when `None`
+    pub quota: Option<Arc<QuotaTracker>>,
+    /// Cached report of the deep health checks
+    pub health: Arc<DeepHealth>,
+    /// Benchmark jobs that survive a restart, the job endpoints answer 404 when `None`
+    pub jobs: Option<Arc<JobStore>>,
+}
+
+impl ApiState {
+    /// Fresh controllers, with runs stored at `RUN_STORE_PATH` when it is set and the build has `storage`
+    /// and a quota when `API_QUOTA_STEPS` or `API_QUOTA_ITERATIONS` is set
+    ///
+    /// Benchmark jobs are kept in the `BENCHMARK_JOBS_DIR` directory when it is
+    /// set, the ones a restart interrupted come back resumable.
+    pub fn from_env() -> Self {
+        Self {
+            controllers: Arc::default(),
+            #[cfg(feature = "storage")]
+            store: std::env::var_os(RUN_STORE_ENV).map(Into::into),
+            quota: QuotaLimits::from_env().map(|limits| Arc::new(QuotaTracker::new(limits))),
+            health: Arc::default(),
+            jobs: std::env::var_os(JOBS_DIR_ENV).and_then(|dir| match JobStore::open(&dir) {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    tracing::error!("Benchmark jobs disabled, could not open {:?}: {}", dir, e);
+                    None
+                }
+            }),
+        }
+    }
+}
+
+/// Every endpoint, with the `state` controllers shared by the simulation and benchmark requests
+pub fn build_router(state: ApiState) -> Router {
+    // Configure CORS
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    Router::new()
+        // Health check
+        .route("/", get(handlers::health_check))
+        .route("/health", get(handlers::health_check))
+        .route("/health/deep", get(handlers::deep_health_check))
+
+        // Simulation endpoints
+        .route("/api/simulate", post(handlers::run_simulation))
+        .route("/api/benchmark", post(handlers::run_benchmark))
+        .route("/api/benchmark/jobs", post(handlers::create_benchmark_job))
+        .route("/api/benchmark/jobs/{id}", get(handlers::get_benchmark_job))
+        .route("/api/benchmark/jobs/{id}/resume", post(handlers::resume_benchmark_job))
+        .route("/api/benchmark/jobs/{id}/result", get(handlers::get_benchmark_job_result))
+        .route("/api/reachability", post(handlers::run_reachability))
+        .route("/api/robustness", post(handlers::run_robustness))
+        .route("/api/step", post(handlers::run_step))
+        .route("/api/compare", post(handlers::run_compare))
+
+        // Controller description and request defaults
+        .route("/api/controller", get(handlers::describe_controller))
+        .route("/api/config", get(handlers::get_config))
+
+        // Usage of the caller against the server quota
+        .route("/api/quota", get(handlers::get_quota))
+
+        // Controllers built once and shared by every request
+        .with_state(state)
+
+        // Add middleware
+        .layer(cors)
+        .layer(TraceLayer::new_for_http())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vehicle::VehicleType;
+    use shuttle_axum::axum::body::{to_bytes, Body};
+    use shuttle_axum::axum::http::{header, HeaderMap, Method, Request, StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    /// Response of the router to `request`: status, headers and the JSON body (null when empty)
+    async fn send(request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+        let response = build_router(ApiState::default()).oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let json = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+        (parts.status, parts.headers, json)
+    }
+
+    fn get_request(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    fn post_json(uri: &str, json: &str) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_routes_report_the_crate_version() {
+        for uri in ["/", "/health"] {
+            let (status, _, json) = send(get_request(uri)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(json["status"], "healthy");
+            assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_runs_the_fuzzy_engine() {
+        let (status, _, json) = send(get_request("/health/deep")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "healthy");
+        assert_eq!(json["checks"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_names_the_failing_check_with_503() {
+        let state = ApiState { health: Arc::new(DeepHealth::poisoned(VehicleType::Heavy)), ..ApiState::default() };
+        let response = build_router(state).oneshot(get_request("/health/deep")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(json["status"], "unhealthy");
+        let failed: Vec<&str> = json["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|check| check["passed"] == false)
+            .map(|check| check["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(failed, ["controller:heavy"]);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_returns_vehicles_with_trajectories() {
+        let (status, _, json) = send(post_json("/api/simulate", r#"{"vehicle_types": ["Agile"], "max_time": 5.0, "seed": 1}"#)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["success"], true);
+        let vehicles = json["vehicles"].as_array().unwrap();
+        assert_eq!(vehicles.len(), 1);
+        assert_eq!(vehicles[0]["id"], "agile-1");
+        assert!(!vehicles[0]["trajectory"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_rejects_unknown_vehicle_types_with_a_structured_error() {
+        let (status, _, json) = send(post_json("/api/simulate", r#"{"vehicle_types": ["Submarine"]}"#)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], StatusCode::BAD_REQUEST.to_string());
+        assert!(json["details"].as_str().unwrap().contains("Unknown vehicle type: Submarine"));
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_aggregates_every_requested_type() {
+        let body = r#"{"iterations": 2, "vehicle_types": ["Agile", "Heavy"], "max_time": 5.0, "seed": 3, "threads": 1}"#;
+        let (status, _, json) = send(post_json("/api/benchmark", body)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["num_iterations"], 2);
+        let types: Vec<&str> = json["aggregate_stats"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|stats| stats["vehicle_type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["agile", "heavy"]);
+        assert!(json["aggregate_stats"].as_array().unwrap().iter().all(|stats| stats["total_runs"] == 2));
+    }
+
+    #[tokio::test]
+    async fn test_robustness_reports_the_levels_of_every_type() {
+        let body = r#"{"axis": "dropout", "levels": [0, 30], "iterations": 1, "vehicle_types": ["Standard"], "max_time": 5.0, "seed": 1}"#;
+        let (status, _, json) = send(post_json("/api/robustness", body)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["axis"], "dropout");
+        assert_eq!(json["curves"][0]["vehicle_type"], "standard");
+        assert_eq!(json["curves"][0]["levels"].as_array().unwrap().len(), 2);
+
+        // Plain text rejection of an axis the simulation does not model
+        let response = build_router(ApiState::default())
+            .oneshot(post_json("/api/robustness", r#"{"axis": "wind", "levels": [0]}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_job_runs_in_the_background_and_serves_its_result() {
+        // Disabled without a jobs directory
+        assert_eq!(send(get_request("/api/benchmark/jobs/1")).await.0, StatusCode::NOT_FOUND);
+
+        let dir = std::env::temp_dir().join(format!("router_jobs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let state = ApiState { jobs: Some(Arc::new(JobStore::open(&dir).unwrap())), ..ApiState::default() };
+        let call = |request: Request<Body>| async {
+            let response = build_router(state.clone()).oneshot(request).await.unwrap();
+            let status = response.status();
+            (status, to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        };
+
+        let body = r#"{"iterations": 2, "vehicle_types": ["Agile"], "max_time": 5.0, "seed": 3, "threads": 1}"#;
+        let (status, bytes) = call(post_json("/api/benchmark/jobs", body)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let job: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((job["id"].as_u64(), job["total_iterations"].as_u64()), (Some(1), Some(2)));
+
+        let mut status = Value::Null;
+        for _ in 0..200 {
+            let (_, bytes) = call(get_request("/api/benchmark/jobs/1")).await;
+            status = serde_json::from_slice::<Value>(&bytes).unwrap()["status"].clone();
+            if status != "running" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(status, "completed");
+        assert_eq!(call(post_json("/api/benchmark/jobs/1/resume", "")).await.0, StatusCode::CONFLICT);
+        assert_eq!(call(get_request("/api/benchmark/jobs/2")).await.0, StatusCode::NOT_FOUND);
+
+        let (status, bytes) = call(get_request("/api/benchmark/jobs/1/result")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_slice::<Value>(&bytes).unwrap()["num_iterations"], 2);
+
+        // Budgets would stop a resumed job elsewhere than an uninterrupted one
+        let (status, _) = call(post_json("/api/benchmark/jobs", r#"{"duration_seconds": 1.0}"#)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_is_allowed() {
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/simulate")
+            .header(header::ORIGIN, "https://example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(Body::empty())
+            .unwrap();
+        let (status, headers, _) = send(request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(headers.contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+    }
+
+    #[tokio::test]
+    async fn test_requests_past_the_quota_of_a_client_are_refused() {
+        let limits = QuotaLimits { steps: Some(250), iterations: None, window: std::time::Duration::from_secs(3600) };
+        let router = build_router(ApiState { quota: Some(Arc::new(QuotaTracker::new(limits))), ..ApiState::default() });
+        let simulate = |client: &str| {
+            Request::post("/api/simulate")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("x-forwarded-for", client)
+                .body(Body::from(r#"{"vehicle_types": ["Agile"], "max_time": 5.0, "seed": 1}"#))
+                .unwrap()
+        };
+        let reachability = |client: &str, grid: &str| {
+            Request::post("/api/reachability")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("x-forwarded-for", client)
+                .body(Body::from(format!(r#"{{{}, "max_time": 1.0}}"#, grid)))
+                .unwrap()
+        };
+        let quota_of = |client: &str| Request::get("/api/quota").header("x-forwarded-for", client).body(Body::empty()).unwrap();
+        let call = |request: Request<Body>| async {
+            let response = router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+        };
+
+        // 5 s at dt 0.05: 100 steps and the step limit margin of 16 per request
+        for _ in 0..2 {
+            assert_eq!(call(simulate("203.0.113.7")).await.0, StatusCode::OK);
+        }
+        let (status, json) = call(simulate("203.0.113.7")).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(json["used"]["steps"], 232);
+        assert_eq!(json["steps_remaining"], 18);
+        assert_eq!(json["requested"]["steps"], 116);
+
+        // A reachability grid is charged a run of 20 + 16 steps per cell
+        let (status, json) = call(reachability("203.0.113.7", r#""columns": 2, "rows": 2"#)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(json["requested"]["steps"], 4 * 36);
+
+        let (_, json) = call(quota_of("203.0.113.7")).await;
+        assert_eq!(json["enabled"], true);
+        assert_eq!(json["used"]["steps"], 232);
+
+        // Other clients keep their own quota
+        assert_eq!(call(simulate("198.51.100.2")).await.0, StatusCode::OK);
+        assert_eq!(call(reachability("198.51.100.2", r#""columns": 1, "rows": 1"#)).await.0, StatusCode::OK);
+        let (_, json) = call(quota_of("198.51.100.2")).await;
+        assert_eq!(json["steps_remaining"], 250 - 116 - 36);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[tokio::test]
+    async fn test_budgeted_parquet_benchmarks_count_every_completed_iteration() {
+        let limits = QuotaLimits { steps: None, iterations: Some(1_000_000), window: std::time::Duration::from_secs(3600) };
+        let router = build_router(ApiState { quota: Some(Arc::new(QuotaTracker::new(limits))), ..ApiState::default() });
+        let benchmark = post_json("/api/benchmark?format=parquet", r#"{"duration_seconds": 0.2, "vehicle_types": ["Agile"], "max_time": 1.0}"#);
+        assert_eq!(router.clone().oneshot(benchmark).await.unwrap().status(), StatusCode::OK);
+
+        let response = router.oneshot(get_request("/api/quota")).await.unwrap();
+        let json: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert!(json["used"]["iterations"].as_u64().unwrap() > 1, "{}", json);
+    }
+
+    #[tokio::test]
+    async fn test_quota_is_disabled_without_limits() {
+        let (status, _, json) = send(get_request("/api/quota")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["enabled"], false);
+        assert_eq!(json["client"], "local");
+    }
+}
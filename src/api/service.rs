@@ -0,0 +1,1921 @@
+// Blocking simulation and benchmark logic, shared by the HTTP handlers and the Python bindings
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "api")]
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::analysis;
+use crate::build_info::BuildMeta;
+use crate::analysis::stats::{
+    arrival_funnel, bootstrap_mean_interval, calculate_stats, mean_of_present, percentile, wilson_interval,
+    InitialCondition, InitialConditionBucket, MetricOutliers, OutlierRun, TrimmedStats, BOOTSTRAP_RESAMPLES,
+};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::fuzzy_system::CENTROID_STEPS;
+use crate::map::{HeadingDistribution, Map};
+use crate::resource_usage::{MemorySampler, ResourceUsage};
+use crate::simulation::benchmark::BenchmarkConfig;
+use crate::simulation::convergence::{ConvergenceReport, GroupPrecision, StopReason};
+#[cfg(feature = "storage")]
+use crate::storage::{RunStore, StoredRun};
+use crate::simulation::reachability::ReachabilityConfig;
+use crate::navigation::{build_observation, DistanceScale, NavigationController, SharedControllers};
+use crate::simulation::kinematics::{self, ControlOutput};
+use crate::simulation::stability;
+use crate::simulation::{
+    self, CancelToken, MultiVehicleSimulationResult, Scenario, Simulation, SimulationMetrics, TrajectoryPoint, VehicleResult,
+    NO_RULE_FIRED_WARNING_PERCENT, SCHEMA_VERSION,
+};
+use crate::trajectory_export::{self, FrameOptions, GeoRef};
+use crate::units::{Degrees, Radians};
+use crate::vehicle::create_vehicle_preset;
+use super::models::*;
+
+/// Metrics of a finished simulation as reported by the API
+///
+/// The angle error is measured against 90° and the distance is summed over the
+/// recorded trajectory.
+fn api_metrics(metrics: SimulationMetrics, trajectory: &[TrajectoryPoint]) -> SimulationMetrics {
+    // Without trajectory points the metrics already describe the current vehicle state
+    let Some(final_point) = trajectory.last() else {
+        return metrics;
+    };
+
+    let distance_traveled = trajectory
+        .windows(2)
+        .map(|pair| ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt())
+        .sum();
+
+    SimulationMetrics {
+        distance_traveled,
+        final_angle_error: (Degrees(90.0) - final_point.angle).abs().0,
+        final_distance_to_target: final_point.distance_to_target,
+        ..metrics
+    }
+}
+
+// ============================================================================
+// SIMULATION
+// ============================================================================
+
+/// Run every requested vehicle in lock-step, validation errors are returned as `Err`
+///
+/// Trajectories and targets are returned in the request `frame`, metrics are
+/// frame independent.
+pub fn simulate(request: &SimulationRequest) -> Result<SimulationResponse, FuzzyNavError> {
+    simulate_cancellable(request, CancelToken::new())
+}
+
+/// `simulate`, vehicles still running when `cancel` is tripped stop with `TerminationReason::Cancelled`
+pub fn simulate_cancellable(request: &SimulationRequest, cancel: CancelToken) -> Result<SimulationResponse, FuzzyNavError> {
+    simulate_shared(request, &Arc::new(SharedControllers::default()), cancel)
+}
+
+/// `simulate_cancellable`, taking the controllers from `controllers` so repeated requests reuse them
+pub fn simulate_shared(
+    request: &SimulationRequest,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<SimulationResponse, FuzzyNavError> {
+    simulate_in_frame(request, request.frame.as_ref(), request.playback()?, controllers, cancel)
+}
+
+fn simulate_in_frame(
+    request: &SimulationRequest,
+    frame: Option<&FrameOptions>,
+    playback: Option<Playback>,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<SimulationResponse, FuzzyNavError> {
+    Ok(repeated_response(run_repeats(request, controllers, cancel)?, frame, playback))
+}
+
+/// Scenario and result of every repeat of the request, in repeat order
+///
+/// The repeats run in parallel, each from its own seed, so the results do not
+/// depend on the thread count.
+fn run_repeats(
+    request: &SimulationRequest,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<Vec<(Scenario, MultiVehicleSimulationResult)>, FuzzyNavError> {
+    let scenario = request.to_scenario()?;
+    request
+        .repeat_seeds()?
+        .into_par_iter()
+        .map(|seed| {
+            let scenario = Scenario { seed, ..scenario.clone() };
+            let result = scenario.run_shared(controllers, cancel.clone())?;
+            Ok((scenario, result))
+        })
+        .collect()
+}
+
+/// Response of the first repeat, with the spread over all of them when there are several
+fn repeated_response(
+    runs: Vec<(Scenario, MultiVehicleSimulationResult)>,
+    frame: Option<&FrameOptions>,
+    playback: Option<Playback>,
+) -> SimulationResponse {
+    let repeat_stats = (runs.len() > 1).then(|| repeat_stats(&runs));
+    let (_, first) = runs.into_iter().next().expect("a simulation request has at least one repeat");
+    SimulationResponse { repeat_stats, ..simulation_response(first, frame, playback) }
+}
+
+/// Successes and arrival times of every vehicle over the repeats, vehicles in request order
+fn repeat_stats(runs: &[(Scenario, MultiVehicleSimulationResult)]) -> RepeatStats {
+    let (_, first) = &runs[0];
+    let vehicles = first
+        .vehicles
+        .iter()
+        .enumerate()
+        .map(|(index, vehicle)| {
+            let metrics: Vec<&SimulationMetrics> = runs.iter().map(|(_, result)| &result.vehicles[index].metrics).collect();
+            let arrival_times: Vec<f64> =
+                metrics.iter().filter(|metrics| metrics.success).filter_map(|metrics| metrics.arrival_time).collect();
+            let (avg_arrival_time, std_arrival_time, min_arrival_time, max_arrival_time) = calculate_stats(&arrival_times);
+            VehicleRepeatStats {
+                id: vehicle.id.clone(),
+                vehicle_type: vehicle.vehicle_type.clone(),
+                successes: metrics.iter().filter(|metrics| metrics.success).count(),
+                avg_arrival_time,
+                std_arrival_time,
+                min_arrival_time,
+                max_arrival_time,
+            }
+        })
+        .collect();
+
+    RepeatStats {
+        repeats: runs.len(),
+        seeds: runs.iter().filter_map(|(scenario, _)| scenario.seed).collect(),
+        vehicles,
+    }
+}
+
+/// `simulate_shared`, also storing the scenario and the result in `store` unless cancelled
+///
+/// The response carries the id of the stored run, only the first repeat is stored.
+#[cfg(feature = "storage")]
+pub fn simulate_stored(
+    request: &SimulationRequest,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+    store: &mut RunStore,
+) -> Result<SimulationResponse, FuzzyNavError> {
+    let playback = request.playback()?;
+    let runs = run_repeats(request, controllers, cancel.clone())?;
+    let run_id = match cancel.is_cancelled() {
+        true => None,
+        false => Some(store.insert_simulation(&runs[0].0, &runs[0].1)?),
+    };
+    Ok(SimulationResponse { run_id, ..repeated_response(runs, request.frame.as_ref(), playback) })
+}
+
+/// Response of one run, trajectories in `frame` and also resampled for `playback` when given
+fn simulation_response(
+    result: MultiVehicleSimulationResult,
+    frame: Option<&FrameOptions>,
+    playback: Option<Playback>,
+) -> SimulationResponse {
+    let meta = result.meta.clone().unwrap_or_else(|| BuildMeta::current(1));
+    let approach_geometry = result.approach_geometry.map(|geometry| match frame {
+        Some(frame) => geometry.transformed(frame),
+        None => geometry,
+    });
+
+    let vehicles: Vec<VehicleSimulationResult> = result
+        .vehicles
+        .into_iter()
+        .map(|vehicle| {
+            let metrics = api_metrics(vehicle.metrics.clone(), &vehicle.trajectory);
+            // Resampled in the map frame, where the headings are the ones the seam handling expects
+            let playback_trajectory = playback.map(|playback| {
+                let frames = vehicle.resampled_for_playback(playback.fps, playback.speed);
+                match frame {
+                    Some(frame) => frames.iter().map(|point| frame.point_to_frame(point, vehicle.target.as_ref())).collect(),
+                    None => frames,
+                }
+            });
+            let vehicle = match frame {
+                Some(frame) => vehicle.transformed(frame),
+                None => vehicle,
+            };
+            VehicleSimulationResult {
+                metrics,
+                id: vehicle.id,
+                vehicle_type: vehicle.vehicle_type,
+                trajectory: if playback.is_some_and(|playback| playback.only) { Vec::new() } else { vehicle.trajectory },
+                playback_trajectory,
+                target: vehicle.target,
+            }
+        })
+        .collect();
+
+    let success_count = vehicles.iter().filter(|v| v.metrics.success).count();
+    let mut message = format!(
+        "Simulation completed: {}/{} vehicles arrived successfully",
+        success_count,
+        vehicles.len()
+    );
+    // A vehicle going straight because no rule fired looks like a broken service, say so
+    for vehicle in vehicles.iter().filter(|v| v.metrics.no_rule_fired_percent > NO_RULE_FIRED_WARNING_PERCENT) {
+        message.push_str(&format!(
+            ". Warning: no controller rule fired in {} steps ({:.1}%) of {}, it went straight ahead there",
+            vehicle.metrics.steps_with_no_rule_fired, vehicle.metrics.no_rule_fired_percent, vehicle.id
+        ));
+    }
+
+    SimulationResponse {
+        success: true,
+        vehicles,
+        total_simulation_time: result.total_simulation_time,
+        approach_geometry,
+        meta,
+        run_id: None,
+        repeat_stats: None,
+        message,
+    }
+}
+
+/// Run both simulations and report how their vehicles differ, in metrics and in path
+pub fn compare(request: &CompareRequest) -> Result<CompareResponse, FuzzyNavError> {
+    let run_a = request.a.to_scenario()?.run()?;
+    let run_b = request.b.to_scenario()?.run()?;
+    let vehicles = analysis::compare_runs(&run_a.vehicles, &run_b.vehicles);
+
+    let matched = vehicles.iter().filter(|delta| delta.similarity.is_some()).count();
+    let message = format!("Comparison completed: {} of {} vehicles matched between A and B", matched, vehicles.len());
+
+    Ok(CompareResponse { success: true, vehicles, meta: BuildMeta::current(1), message })
+}
+
+/// Run `simulate_shared` and return the trajectories and map as a GeoJSON FeatureCollection
+///
+/// GeoJSON is placed by `georef` in map coordinates, the request `frame` is ignored.
+pub fn simulate_geojson(
+    request: &SimulationRequest,
+    georef: GeoRef,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<String, FuzzyNavError> {
+    let response = simulate_in_frame(request, None, None, controllers, cancel)?;
+    let map = Map::try_new(request.map_width, request.map_height, request.target_x, request.target_y)?;
+
+    Ok(trajectory_export::to_geojson(&simulation_result(response), &map, georef))
+}
+
+/// Run the request and return the trajectories and metrics as an xlsx workbook
+#[cfg(feature = "xlsx")]
+pub fn simulate_xlsx(
+    request: &SimulationRequest,
+    controllers: &Arc<SharedControllers>,
+    cancel: CancelToken,
+) -> Result<Vec<u8>, FuzzyNavError> {
+    let response = simulate_in_frame(request, None, None, controllers, cancel)?;
+    Ok(crate::xlsx_export::to_xlsx_buffer(&simulation_result(response))?)
+}
+
+/// The response back in the shape of the exporters' input
+fn simulation_result(response: SimulationResponse) -> MultiVehicleSimulationResult {
+    MultiVehicleSimulationResult {
+        schema_version: SCHEMA_VERSION,
+        vehicles: response
+            .vehicles
+            .into_iter()
+            .map(|v| VehicleResult {
+                id: v.id,
+                vehicle_type: v.vehicle_type,
+                trajectory: v.trajectory,
+                metrics: v.metrics,
+                target: v.target,
+            })
+            .collect(),
+        total_simulation_time: response.total_simulation_time,
+        approach_geometry: response.approach_geometry,
+        meta: Some(response.meta),
+    }
+}
+
+// ============================================================================
+// BENCHMARK
+// ============================================================================
+
+// The start state is only exported by the Parquet download and the run database
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(not(any(feature = "arrow", feature = "storage")), allow(dead_code))]
+pub(super) struct VehicleMetrics {
+    success: bool,
+    arrival_time: Option<f64>,
+    degenerate_start: bool,
+    distance_traveled: f64,
+    final_distance: f64,
+    final_angle_error: f64,
+    steering_reversals_per_minute: f64,
+    heading_jerk: f64,
+    avg_velocity: Option<f64>,
+    time_above_high_velocity: Option<f64>,
+    arrival_velocity: Option<f64>,
+    acceleration_reversals: Option<usize>,
+    max_approach_bias_deg: Option<f64>,
+    mean_approach_bias_deg_inside_corridor: Option<f64>,
+    time_optimality_ratio: Option<f64>,
+    transit_time: Option<f64>,
+    alignment_time: Option<f64>,
+    first_aligned_time: Option<f64>,
+    alignment_attempts: usize,
+    initial_x: f64,
+    initial_y: f64,
+    /// Degrees
+    initial_angle: f64,
+    initial_condition: InitialCondition,
+    /// Wall time of the run (seconds)
+    wall_time: f64,
+}
+
+/// Run one benchmark simulation to the end
+fn run_vehicle(mut sim: Simulation) -> VehicleMetrics {
+    let started = Instant::now();
+    let initial = sim.vehicle.state.clone();
+
+    while !sim.is_finished() {
+        sim.step();
+    }
+
+    let metrics = api_metrics(sim.metrics(), &sim.trajectory);
+    VehicleMetrics {
+        success: metrics.success,
+        arrival_time: metrics.arrival_time,
+        degenerate_start: metrics.degenerate_start,
+        distance_traveled: metrics.distance_traveled,
+        final_distance: metrics.final_distance_to_target,
+        final_angle_error: metrics.final_angle_error,
+        steering_reversals_per_minute: metrics.steering_reversals_per_minute,
+        heading_jerk: metrics.heading_jerk,
+        avg_velocity: metrics.avg_velocity,
+        time_above_high_velocity: metrics.time_above_high_velocity,
+        arrival_velocity: metrics.arrival_velocity,
+        acceleration_reversals: metrics.acceleration_reversals,
+        max_approach_bias_deg: metrics.max_approach_bias_deg,
+        mean_approach_bias_deg_inside_corridor: metrics.mean_approach_bias_deg_inside_corridor,
+        time_optimality_ratio: metrics.time_optimality_ratio,
+        transit_time: metrics.transit_time,
+        alignment_time: metrics.alignment_time,
+        first_aligned_time: metrics.first_aligned_time,
+        alignment_attempts: metrics.alignment_attempts,
+        initial_x: initial.position.x,
+        initial_y: initial.position.y,
+        initial_angle: initial.angle.to_degrees().0,
+        initial_condition: InitialCondition::new(&initial.position, initial.angle, &sim.map.target.position),
+        wall_time: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// Metrics of every iteration, the resources they took and, with `convergence`, why they stopped
+type IterationsOutcome = (Vec<Vec<VehicleMetrics>>, ResourceUsage, Option<ConvergenceReport>);
+
+/// Validate the request and run the iterations in parallel
+///
+/// Returns, per iteration, the metrics of every (heading, vehicle) pair in the
+/// order of `BenchmarkConfig::groups`, with the timing and memory of the whole
+/// run. Iteration `i` draws the start states of every heading mode from
+/// `seed + i`, so the results do not depend on the thread count. With a
+/// `duration_seconds` budget the workers take iteration indices until the
+/// deadline passes, the completed ones are always `0..n` with `n >= 1`.
+fn run_iterations(config: &BenchmarkConfig, controllers: &Arc<SharedControllers>) -> Result<IterationsOutcome, FuzzyNavError> {
+    config.validate()?;
+    configure_thread_pool(config);
+    let map = config.map()?;
+
+    let completed = Arc::new(AtomicUsize::new(0));
+    let completed_clone = Arc::clone(&completed);
+
+    let sampler = MemorySampler::start();
+    let started = Instant::now();
+
+    let run_iteration = |iteration: usize| {
+        let result = run_iteration_of(config, &map, controllers, iteration);
+        completed_clone.fetch_add(1, Ordering::Relaxed);
+        result
+    };
+
+    // Run iterations in parallel, a fixed count, as many as the budget allows or batches until they converge
+    let mut convergence_report = None;
+    let timed_results: Vec<(Vec<VehicleMetrics>, f64)> = match (config.duration_seconds, config.convergence) {
+        (None, None) => (0..config.iterations).into_par_iter().map(run_iteration).collect(),
+        (None, Some(convergence)) => {
+            let mut timed_results = Vec::new();
+            while convergence_report.is_none() {
+                let start = timed_results.len();
+                let end = (start + convergence.batch_size).min(config.iterations);
+                timed_results.par_extend((start..end).into_par_iter().map(run_iteration));
+                let results: Vec<&Vec<VehicleMetrics>> = timed_results.iter().map(|(vehicles, _)| vehicles).collect();
+                convergence_report = convergence.check(end, config.iterations, group_precision(config, &results));
+            }
+            timed_results
+        }
+        (Some(seconds), _) => {
+            let deadline = started + Duration::from_secs_f64(seconds);
+            let next_iteration = AtomicUsize::new(0);
+            let mut indexed: Vec<(usize, (Vec<VehicleMetrics>, f64))> = (0..rayon::current_num_threads())
+                .into_par_iter()
+                .flat_map_iter(|_| {
+                    // Every index taken is run, so the completed ones are 0..n whatever the thread timing
+                    let mut completed = Vec::new();
+                    while next_iteration.load(Ordering::Relaxed) == 0 || Instant::now() < deadline {
+                        let iteration = next_iteration.fetch_add(1, Ordering::Relaxed);
+                        completed.push((iteration, run_iteration(iteration)));
+                    }
+                    completed
+                })
+                .collect();
+            indexed.sort_unstable_by_key(|(iteration, _)| *iteration);
+            indexed.into_iter().map(|(_, result)| result).collect()
+        }
+    };
+
+    let total = started.elapsed();
+    let (all_results, iteration_times): (Vec<_>, Vec<_>) = timed_results.into_iter().unzip();
+    let usage = ResourceUsage::new(&iteration_times, total, rayon::current_num_threads(), sampler.finish());
+    Ok((all_results, usage, convergence_report))
+}
+
+/// Size the global rayon pool from `config.threads`, half the cores by default
+///
+/// The pool is built once per process, later calls keep the first size.
+fn configure_thread_pool(config: &BenchmarkConfig) {
+    let available_threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+
+    let threads_to_use = config.threads.unwrap_or(available_threads / 2);
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads_to_use)
+        .build_global()
+        .ok();
+}
+
+/// Metrics of every (heading, vehicle) pair of iteration `iteration` and its wall time (seconds)
+fn run_iteration_of(
+    config: &BenchmarkConfig,
+    map: &Map,
+    controllers: &Arc<SharedControllers>,
+    iteration: usize,
+) -> (Vec<VehicleMetrics>, f64) {
+    let iteration_started = Instant::now();
+    let iteration_vehicles: Vec<VehicleMetrics> = config.headings
+        .iter()
+        .flat_map(|&heading| {
+            let mut rng = config.iteration_rng(iteration);
+            config.vehicles
+                .iter()
+                .map(|vehicle| {
+                    run_vehicle(config.build_shared_simulation(map, vehicle, heading, iteration, &mut rng, controllers))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    (iteration_vehicles, iteration_started.elapsed().as_secs_f64())
+}
+
+/// Run the iterations `iterations` of `config` in parallel, in index order
+///
+/// Iteration `i` draws from `seed + i` as in `run_iterations`, so a range run
+/// later or in another process gives the same metrics.
+#[cfg(feature = "api")]
+pub(super) fn run_iteration_range(
+    config: &BenchmarkConfig,
+    controllers: &Arc<SharedControllers>,
+    iterations: Range<usize>,
+) -> Result<Vec<(Vec<VehicleMetrics>, f64)>, FuzzyNavError> {
+    configure_thread_pool(config);
+    let map = config.map()?;
+    Ok(iterations.into_par_iter().map(|iteration| run_iteration_of(config, &map, controllers, iteration)).collect())
+}
+
+/// Response of a fixed count benchmark whose iterations ran in one or several sessions
+///
+/// `total` is the wall time of the session that finished it.
+#[cfg(feature = "api")]
+pub(super) fn iterations_response(
+    config: &BenchmarkConfig,
+    all_results: &[Vec<VehicleMetrics>],
+    iteration_times: &[f64],
+    total: Duration,
+) -> BenchmarkResponse {
+    let usage = ResourceUsage::new(iteration_times, total, rayon::current_num_threads(), None);
+    benchmark_response(config, all_results, usage, None)
+}
+
+/// Precision of the success rate and mean arrival time of every group over the iterations so far
+fn group_precision(config: &BenchmarkConfig, results: &[&Vec<VehicleMetrics>]) -> Vec<GroupPrecision> {
+    config.groups()
+        .iter()
+        .enumerate()
+        .map(|(idx, (heading, vehicle))| {
+            let runs: Vec<&VehicleMetrics> = results.iter().map(|vehicles| &vehicles[idx]).collect();
+            let arrival_times: Vec<f64> = runs.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .filter_map(|m| m.arrival_time)
+                .collect();
+            let successes = runs.iter().filter(|m| m.success).count();
+            GroupPrecision::new(vehicle.vehicle_type.id(), *heading, successes, runs.len(), &arrival_times, config.confidence)
+        })
+        .collect()
+}
+
+/// Largest work of a simulation request: every vehicle of every repeat running to `max_time`
+pub fn simulation_cost(request: &SimulationRequest) -> Result<QuotaCost, FuzzyNavError> {
+    let scenario = request.to_scenario()?;
+    scenario.validate()?;
+    let repeats = request.repeat_seeds()?.len() as u64;
+    let runs = repeats.saturating_mul(scenario.vehicles.len() as u64);
+    Ok(QuotaCost { steps: projected_steps(runs, scenario.max_time, scenario.time_step()), iterations: 0 })
+}
+
+/// Largest work of a comparison: every vehicle of A and of B running to `max_time`
+pub fn compare_cost(request: &CompareRequest) -> Result<QuotaCost, FuzzyNavError> {
+    let mut steps = 0u64;
+    for side in [&request.a, &request.b] {
+        let scenario = side.to_scenario()?;
+        scenario.validate()?;
+        steps = steps.saturating_add(projected_steps(scenario.vehicles.len() as u64, scenario.max_time, scenario.time_step()));
+    }
+    Ok(QuotaCost { steps, iterations: 0 })
+}
+
+/// Steps of `runs` runs to `max_time` at `dt`, each capped at `simulation::step_limit`
+///
+/// Saturates instead of wrapping, a projection past `u64::MAX` is over any quota.
+fn projected_steps(runs: u64, max_time: f64, dt: f64) -> u64 {
+    runs.saturating_mul(simulation::step_limit(max_time, dt) as u64)
+}
+
+/// Largest work of a benchmark request: every vehicle of every heading mode running to `max_time` in every iteration
+///
+/// A `duration_seconds` run is projected as one iteration, the count is only
+/// known once it completes.
+pub fn benchmark_cost(request: &BenchmarkRequest) -> Result<QuotaCost, FuzzyNavError> {
+    let config = request.to_config()?;
+    config.validate()?;
+    Ok(config_cost(&config, config.iterations.max(1)))
+}
+
+/// Work a `duration_seconds` run did past the one iteration `benchmark_cost` charged, `None` for a fixed iteration count
+pub fn budget_overrun(config: &BenchmarkConfig, completed: usize) -> Option<QuotaCost> {
+    config.duration_seconds.map(|_| config_cost(config, completed.saturating_sub(1)))
+}
+
+/// Work of `iterations` iterations of `config`
+pub fn config_cost(config: &BenchmarkConfig, iterations: usize) -> QuotaCost {
+    let iterations = iterations as u64;
+    let runs = (config.groups().len() as u64).saturating_mul(iterations);
+    QuotaCost { steps: projected_steps(runs, config.max_time, config.dt), iterations }
+}
+
+/// Configuration of the request, refused when the controller preflight fails unless `force` is set
+fn checked_config(request: &BenchmarkRequest) -> Result<BenchmarkConfig, FuzzyNavError> {
+    let config = request.to_config()?;
+    if !request.force {
+        let report = config.preflight()?;
+        if !report.passed() {
+            return Err(SimulationError::PreflightFailed(report).into());
+        }
+    }
+    Ok(config)
+}
+
+/// Configuration of a benchmark job, checked like a benchmark request
+///
+/// Jobs run a fixed iteration count, a time budget or a convergence rule would
+/// stop a resumed job at a different iteration than an uninterrupted one.
+#[cfg(feature = "api")]
+pub fn benchmark_job_config(request: &BenchmarkRequest) -> Result<BenchmarkConfig, FuzzyNavError> {
+    let config = checked_config(request)?;
+    config.validate()?;
+    if config.duration_seconds.is_some() || config.convergence.is_some() {
+        return Err(SimulationError::JobNeedsIterationCount.into());
+    }
+    Ok(config)
+}
+
+/// Run the requested iterations in parallel and aggregate the metrics per heading mode and vehicle type
+pub fn benchmark(request: &BenchmarkRequest) -> Result<BenchmarkResponse, FuzzyNavError> {
+    benchmark_shared(request, &Arc::new(SharedControllers::default()))
+}
+
+/// `benchmark`, taking the controllers from `controllers` so repeated requests reuse them
+pub fn benchmark_shared(request: &BenchmarkRequest, controllers: &Arc<SharedControllers>) -> Result<BenchmarkResponse, FuzzyNavError> {
+    run_benchmark_shared(&checked_config(request)?, controllers)
+}
+
+/// Run a full benchmark configuration, such as the `config` of an earlier response
+///
+/// Every iteration takes its controllers from one `SharedControllers`, so each
+/// rule base is built once per run rather than once per simulation.
+pub fn run_benchmark(config: &BenchmarkConfig) -> Result<BenchmarkResponse, FuzzyNavError> {
+    run_benchmark_shared(config, &Arc::new(SharedControllers::default()))
+}
+
+/// `run_benchmark`, taking the controllers from `controllers`
+pub fn run_benchmark_shared(
+    config: &BenchmarkConfig,
+    controllers: &Arc<SharedControllers>,
+) -> Result<BenchmarkResponse, FuzzyNavError> {
+    let (all_results, resource_usage, convergence) = run_iterations(config, controllers)?;
+    Ok(benchmark_response(config, &all_results, resource_usage, convergence))
+}
+
+/// `benchmark_shared`, also storing the configuration and every vehicle run in `store`
+///
+/// The response carries the id of the stored run.
+#[cfg(feature = "storage")]
+pub fn benchmark_stored(
+    request: &BenchmarkRequest,
+    controllers: &Arc<SharedControllers>,
+    store: &mut RunStore,
+) -> Result<BenchmarkResponse, FuzzyNavError> {
+    let config = checked_config(request)?;
+    let (all_results, resource_usage, convergence) = run_iterations(&config, controllers)?;
+    let response = benchmark_response(&config, &all_results, resource_usage, convergence);
+    let run_id = store.insert_benchmark(&response.config, &stored_runs(&config, &all_results))?;
+    Ok(BenchmarkResponse { run_id: Some(run_id), ..response })
+}
+
+/// Vehicle runs of every iteration in the order of `BenchmarkConfig::groups`, without trajectories
+#[cfg(feature = "storage")]
+fn stored_runs(config: &BenchmarkConfig, all_results: &[Vec<VehicleMetrics>]) -> Vec<StoredRun> {
+    let groups = config.groups();
+    // "<type>-<n>" for the n-th vehicle of its type, as in a simulation
+    let ids: Vec<String> = config.vehicles
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| {
+            let ordinal = config.vehicles[..i].iter().filter(|other| other.vehicle_type == spec.vehicle_type).count() + 1;
+            spec.vehicle_type.default_id(ordinal)
+        })
+        .collect();
+    all_results
+        .iter()
+        .enumerate()
+        .flat_map(|(iteration, vehicles)| {
+            vehicles.iter().zip(groups.iter().zip(ids.iter().cycle())).map(move |(m, ((heading, vehicle), id))| StoredRun {
+                iteration: iteration as u64 + 1,
+                vehicle_id: id.clone(),
+                vehicle_type: vehicle.vehicle_type.id().to_string(),
+                heading: Some(heading.to_string()),
+                initial_x: m.initial_x,
+                initial_y: m.initial_y,
+                initial_angle: m.initial_angle,
+                success: m.success,
+                arrival_time: m.arrival_time,
+                degenerate_start: m.degenerate_start,
+                distance_traveled: m.distance_traveled,
+                final_distance: m.final_distance,
+                final_angle_error: m.final_angle_error,
+                steering_reversals_per_minute: m.steering_reversals_per_minute,
+                heading_jerk: m.heading_jerk,
+                time_optimality_ratio: m.time_optimality_ratio,
+                wall_time: Some(m.wall_time),
+                trajectory: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Aggregates of the metrics of every iteration, per heading mode and vehicle type
+fn benchmark_response(
+    config: &BenchmarkConfig,
+    all_results: &[Vec<VehicleMetrics>],
+    resource_usage: ResourceUsage,
+    convergence: Option<ConvergenceReport>,
+) -> BenchmarkResponse {
+    let groups = config.groups();
+    let completed = config.with_completed_iterations(all_results.len());
+
+    // Reorganize results by (heading, vehicle type)
+    let mut all_metrics: Vec<Vec<VehicleMetrics>> = vec![Vec::new(); groups.len()];
+    for iteration_result in all_results {
+        for (idx, metrics) in iteration_result.iter().enumerate() {
+            all_metrics[idx].push(metrics.clone());
+        }
+    }
+
+    // Calculate aggregate statistics, overall and per initial condition bucket
+    let mut aggregate_stats: Vec<AggregateStats> = Vec::new();
+    let mut bucket_stats: Vec<AggregateStats> = Vec::new();
+
+    for (idx, (heading, vehicle)) in groups.iter().enumerate() {
+        let vehicle_type = vehicle.vehicle_type.id();
+        let bootstrap_seed = config.seed.wrapping_add(idx as u64);
+        let metrics: Vec<&VehicleMetrics> = all_metrics[idx].iter().collect();
+        aggregate_stats.push(aggregate(vehicle_type, *heading, None, &metrics, config, bootstrap_seed));
+
+        if let Some(buckets) = &config.buckets {
+            for (bucket, metrics) in buckets.group(&all_metrics[idx], |m| m.initial_condition) {
+                bucket_stats.push(aggregate(vehicle_type, *heading, Some(bucket), &metrics, config, bootstrap_seed));
+            }
+        }
+    }
+
+    let mut message = format!("Benchmark completed: {} iterations across {} vehicle types and {} heading modes",
+        completed.iterations,
+        config.vehicles.len(),
+        config.headings.len()
+    );
+    match convergence.as_ref().map(|report| report.stop_reason) {
+        Some(StopReason::Converged) => message.push_str(", stopped early once the estimates converged"),
+        Some(StopReason::IterationCap) => message.push_str(", the estimates had not converged at the iteration cap"),
+        None => {}
+    }
+
+    BenchmarkResponse {
+        schema_version: SCHEMA_VERSION,
+        success: true,
+        num_iterations: completed.iterations,
+        duration_seconds: config.duration_seconds,
+        convergence,
+        config: completed,
+        aggregate_stats,
+        bucket_stats,
+        meta: BuildMeta::current(resource_usage.threads),
+        resource_usage,
+        run_id: None,
+        message,
+    }
+}
+
+/// Statistics of the runs of one (heading, vehicle type) group, or of one of its buckets
+///
+/// Degenerate starts count as successes, their arrival at t=0 only enters
+/// the arrival times with `include_degenerate_arrivals`.
+fn aggregate(
+    vehicle_type: &str,
+    heading: HeadingDistribution,
+    bucket: Option<InitialConditionBucket>,
+    metrics: &[&VehicleMetrics],
+    config: &BenchmarkConfig,
+    bootstrap_seed: u64,
+) -> AggregateStats {
+    let confidence = config.confidence;
+    let total_runs = metrics.len();
+    let successes = metrics.iter().filter(|m| m.success).count();
+    let degenerate_starts = metrics.iter().filter(|m| m.degenerate_start).count();
+    let success_rate = successes as f64 / total_runs as f64 * 100.0;
+    let (success_ci_low, success_ci_high) = wilson_interval(successes, total_runs, confidence);
+
+    let arrival_times: Vec<f64> = metrics.iter()
+        .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+        .filter_map(|m| m.arrival_time)
+        .collect();
+    let (avg_time, std_time, min_time, max_time) = calculate_stats(&arrival_times);
+    let (time_ci_low, time_ci_high) =
+        bootstrap_mean_interval(&arrival_times, confidence, BOOTSTRAP_RESAMPLES, bootstrap_seed);
+
+    let distances: Vec<f64> = metrics.iter().map(|m| m.distance_traveled).collect();
+    let (avg_dist, std_dist, _, _) = calculate_stats(&distances);
+
+    // Only the overall statistics hold one run per iteration, in iteration order
+    let outlier_runs = match bucket {
+        None => {
+            let counted_arrivals: Vec<Option<f64>> = metrics.iter()
+                .map(|m| m.arrival_time.filter(|_| config.include_degenerate_arrivals || !m.degenerate_start))
+                .collect();
+            OutlierRun::list(&MetricOutliers::of_runs(&counted_arrivals, &distances), |i| config.iteration_seed(i))
+        }
+        Some(_) => Vec::new(),
+    };
+
+    let final_dists: Vec<f64> = metrics.iter().map(|m| m.final_distance).collect();
+    let (avg_final_dist, _, _, _) = calculate_stats(&final_dists);
+
+    let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
+    let (avg_angle_error, _, _, _) = calculate_stats(&angle_errors);
+
+    let reversals: Vec<f64> = metrics.iter().map(|m| m.steering_reversals_per_minute).collect();
+    let (avg_reversals, _, _, _) = calculate_stats(&reversals);
+
+    let jerks: Vec<f64> = metrics.iter().map(|m| m.heading_jerk).collect();
+    let (avg_jerk, _, _, _) = calculate_stats(&jerks);
+
+    let attempts: Vec<f64> = metrics.iter().map(|m| m.alignment_attempts as f64).collect();
+    let (avg_attempts, _, _, _) = calculate_stats(&attempts);
+
+    let wall_times: Vec<f64> = metrics.iter().map(|m| m.wall_time).collect();
+    let (avg_wall_time, _, _, _) = calculate_stats(&wall_times);
+
+    AggregateStats {
+        vehicle_type: vehicle_type.to_string(),
+        heading,
+        bucket,
+        total_runs,
+        successes,
+        degenerate_starts,
+        success_rate,
+        success_rate_ci_low: success_ci_low * 100.0,
+        success_rate_ci_high: success_ci_high * 100.0,
+        avg_arrival_time: avg_time,
+        avg_arrival_time_ci_low: time_ci_low,
+        avg_arrival_time_ci_high: time_ci_high,
+        std_arrival_time: std_time,
+        min_arrival_time: min_time,
+        max_arrival_time: max_time,
+        avg_distance_traveled: avg_dist,
+        std_distance_traveled: std_dist,
+        trimmed_arrival_time: TrimmedStats::new(&arrival_times),
+        trimmed_distance_traveled: TrimmedStats::new(&distances),
+        avg_final_distance: avg_final_dist,
+        avg_final_angle_error: avg_angle_error,
+        avg_steering_reversals_per_minute: avg_reversals,
+        avg_heading_jerk: avg_jerk,
+        avg_velocity: mean_of_present(metrics.iter().map(|m| m.avg_velocity)),
+        avg_time_above_high_velocity: mean_of_present(metrics.iter().map(|m| m.time_above_high_velocity)),
+        avg_arrival_velocity: mean_of_present(metrics.iter().map(|m| m.arrival_velocity)),
+        avg_acceleration_reversals: mean_of_present(metrics.iter().map(|m| m.acceleration_reversals.map(|n| n as f64))),
+        avg_max_approach_bias_deg: mean_of_present(metrics.iter().map(|m| m.max_approach_bias_deg)),
+        avg_max_approach_bias_deg_failed: mean_of_present(metrics.iter().filter(|m| !m.success).map(|m| m.max_approach_bias_deg)),
+        avg_approach_bias_deg_inside_corridor: mean_of_present(metrics.iter().map(|m| m.mean_approach_bias_deg_inside_corridor)),
+        avg_time_optimality_ratio: mean_of_present(
+            metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.time_optimality_ratio),
+        ),
+        avg_transit_time: mean_of_present(metrics.iter().map(|m| m.transit_time)),
+        avg_alignment_time: mean_of_present(
+            metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.alignment_time),
+        ),
+        avg_first_aligned_time: mean_of_present(metrics.iter().map(|m| m.first_aligned_time)),
+        avg_alignment_attempts: avg_attempts,
+        arrival_funnel: config.arrival_funnel_bins.filter(|_| bucket.is_none()).map(|bins| {
+            let arrival_times: Vec<Option<f64>> = metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.arrival_time)
+                .collect();
+            arrival_funnel(&arrival_times, config.max_time, bins)
+        }),
+        outlier_runs,
+        avg_wall_time,
+        p95_wall_time: percentile(&wall_times, 0.95),
+    }
+}
+
+/// Run the iterations and return the raw `runs` table as a Parquet file, with the `budget_overrun` of the run
+#[cfg(feature = "arrow")]
+pub fn benchmark_parquet(request: &BenchmarkRequest, controllers: &Arc<SharedControllers>) -> Result<(Vec<u8>, Option<QuotaCost>), FuzzyNavError> {
+    use crate::arrow_export::{write_runs_parquet, RunRecord};
+
+    let config = checked_config(request)?;
+    let (all_results, _, _) = run_iterations(&config, controllers)?;
+    let groups = config.groups();
+    let runs: Vec<RunRecord> = all_results
+        .iter()
+        .enumerate()
+        .flat_map(|(iteration, vehicles)| {
+            vehicles.iter().zip(&groups).map(move |(m, (heading, vehicle))| RunRecord {
+                iteration: iteration as u64 + 1,
+                vehicle_type: vehicle.vehicle_type.id().to_string(),
+                success: m.success,
+                arrival_time: m.arrival_time,
+                distance_traveled: m.distance_traveled,
+                final_distance: m.final_distance,
+                final_angle_error: m.final_angle_error,
+                initial_x: m.initial_x,
+                initial_y: m.initial_y,
+                initial_angle: m.initial_angle,
+                heading: Some(heading.to_string()),
+                wall_time: m.wall_time,
+            })
+        })
+        .collect();
+
+    let mut parquet = Vec::new();
+    write_runs_parquet(&runs, &mut parquet)?;
+    Ok((parquet, budget_overrun(&config, all_results.len())))
+}
+
+/// Run the benchmark and return its statistics as an xlsx workbook, a row per group and bucket, with the `budget_overrun` of the run
+#[cfg(feature = "xlsx")]
+pub fn benchmark_xlsx(request: &BenchmarkRequest, controllers: &Arc<SharedControllers>) -> Result<(Vec<u8>, Option<QuotaCost>), FuzzyNavError> {
+    use crate::xlsx_export::{benchmark_to_xlsx_buffer, BenchmarkSummary, SummaryRecord};
+
+    let response = benchmark_shared(request, controllers)?;
+    let groups = response.aggregate_stats.iter().chain(&response.bucket_stats)
+        .map(|stats| SummaryRecord {
+            vehicle_type: stats.vehicle_type.clone(),
+            heading: stats.heading.to_string(),
+            bucket: stats.bucket.map(|bucket| bucket.to_string()),
+            total_runs: stats.total_runs,
+            successes: stats.successes,
+            success_rate: stats.success_rate,
+            success_rate_ci_low: stats.success_rate_ci_low,
+            success_rate_ci_high: stats.success_rate_ci_high,
+            avg_arrival_time: stats.avg_arrival_time,
+            std_arrival_time: stats.std_arrival_time,
+            min_arrival_time: stats.min_arrival_time,
+            max_arrival_time: stats.max_arrival_time,
+            avg_distance_traveled: stats.avg_distance_traveled,
+            std_distance_traveled: stats.std_distance_traveled,
+            avg_final_distance: stats.avg_final_distance,
+            avg_final_angle_error: stats.avg_final_angle_error,
+            avg_steering_reversals_per_minute: stats.avg_steering_reversals_per_minute,
+            avg_heading_jerk: stats.avg_heading_jerk,
+            avg_time_optimality_ratio: stats.avg_time_optimality_ratio,
+            avg_transit_time: stats.avg_transit_time,
+            avg_alignment_time: stats.avg_alignment_time,
+            avg_alignment_attempts: stats.avg_alignment_attempts,
+            avg_wall_time: stats.avg_wall_time,
+        })
+        .collect();
+
+    let xlsx = benchmark_to_xlsx_buffer(&BenchmarkSummary { config: &response.config, meta: &response.meta, groups })?;
+    Ok((xlsx, budget_overrun(&response.config, response.num_iterations)))
+}
+
+// ============================================================================
+// ROBUSTNESS
+// ============================================================================
+
+/// Largest work of a robustness request: every run going to `max_time`
+pub fn robustness_cost(request: &RobustnessRequest) -> Result<QuotaCost, FuzzyNavError> {
+    let runs = request.runs()? as u64;
+    let scenario = request.to_scenario()?;
+    Ok(QuotaCost { steps: projected_steps(runs, scenario.max_time, scenario.time_step()), iterations: 0 })
+}
+
+/// Sweep the requested disturbance levels for every vehicle type
+pub fn robustness(request: &RobustnessRequest) -> Result<RobustnessResponse, FuzzyNavError> {
+    request.runs()?;
+    let scenario = request.to_scenario()?;
+    let vehicle_types = request.parse_vehicle_types()?;
+    let seed = request.seed.unwrap_or_else(rand::random);
+    let report = analysis::robustness_report(&scenario, &vehicle_types, request.axis, &request.levels, request.iterations, seed)?;
+
+    let message = format!("Robustness sweep completed: {} levels of {} for {} vehicle types, {} runs each",
+        request.levels.len(),
+        request.axis,
+        vehicle_types.len(),
+        request.iterations
+    );
+
+    Ok(RobustnessResponse { success: true, report, meta: BuildMeta::current(1), message })
+}
+
+// ============================================================================
+// REACHABILITY
+// ============================================================================
+
+/// Run one simulation per grid cell of the exam map and report which cells arrive
+/// Largest work of a reachability request: the run of every cell going to `max_time`
+pub fn reachability_cost(request: &ReachabilityRequest) -> Result<QuotaCost, FuzzyNavError> {
+    let config = reachability_config(request)?;
+    Ok(QuotaCost { steps: projected_steps(config.cells()? as u64, config.max_time, config.dt), iterations: 0 })
+}
+
+fn reachability_config(request: &ReachabilityRequest) -> Result<ReachabilityConfig, FuzzyNavError> {
+    Ok(ReachabilityConfig {
+        vehicle_type: request.parse_vehicle_type()?,
+        columns: request.columns,
+        rows: request.rows,
+        dt: request.dt,
+        max_time: request.max_time,
+        seed: request.seed,
+        ..Default::default()
+    })
+}
+
+pub fn reachability(request: &ReachabilityRequest) -> Result<ReachabilityResponse, FuzzyNavError> {
+    let config = reachability_config(request)?;
+    let grid = simulation::reachability::reachability(&config)?;
+
+    let reachable_cells = grid.reachable_cells();
+    let message = format!("Reachability completed: {}/{} cells reachable by {}",
+        reachable_cells,
+        grid.columns * grid.rows,
+        config.vehicle_type.id()
+    );
+
+    Ok(ReachabilityResponse {
+        success: true,
+        vehicle_type: config.vehicle_type.id().to_string(),
+        columns: grid.columns,
+        rows: grid.rows,
+        cell_width: grid.cell_width,
+        cell_height: grid.cell_height,
+        reachable: grid.reachable,
+        arrival_times: grid.arrival_times,
+        reachable_cells,
+        message,
+    })
+}
+
+// ============================================================================
+// SINGLE STEP
+// ============================================================================
+
+/// Advance the requested state by one fuzzy-controlled step, as `Simulation::step` does
+pub fn step(request: &StepRequest) -> Result<StepResponse, FuzzyNavError> {
+    let vehicle_type = request.parse_vehicle_type()?;
+    let criteria = request.parse_criteria()?;
+
+    if !(request.dt.is_finite() && request.dt > 0.0) {
+        return Err(SimulationError::InvalidTimeStep(request.dt).into());
+    }
+
+    let state = request.state.to_vehicle_state();
+    let finite = [state.position.x, state.position.y, state.angle.0, state.velocity]
+        .iter()
+        .all(|v| v.is_finite());
+    if !finite {
+        return Err(SimulationError::NonFiniteState.into());
+    }
+
+    let characteristics = request
+        .characteristics
+        .clone()
+        .unwrap_or_else(|| create_vehicle_preset(vehicle_type));
+    characteristics.validate()?;
+    stability::check_time_step(request.dt, [(vehicle_type.id(), &characteristics)], criteria.arrival_radius())?;
+    let map = request.to_map()?;
+    let target = &map.target;
+    let inputs = build_observation(&state, &map, &characteristics);
+    let step_inputs = StepInputs {
+        distance_to_target: inputs.distance_to_target,
+        angular_error: inputs.angular_error.to_degrees().0,
+        velocity_relative: inputs.velocity_relative,
+        approach_x: inputs.approach_point.x,
+        approach_y: inputs.approach_point.y,
+        desired_heading: inputs.desired_heading.to_degrees().0,
+    };
+
+    let angle_error = target.angle_error(state.angle);
+    if criteria.is_met(&state.position, target, angle_error, state.velocity) {
+        return Ok(StepResponse {
+            success: true,
+            vehicle_type: vehicle_type.id().to_string(),
+            arrived: true,
+            inputs: step_inputs,
+            activated_sets: Vec::new(),
+            commanded_adjustment: 0.0,
+            angular_adjustment: 0.0,
+            next_state: request.state.clone(),
+            message: "Vehicle already meets the arrival criteria".to_string(),
+        });
+    }
+
+    let controller = NavigationController::with_distance_scale(&characteristics, DistanceScale::for_map(&map));
+    let trace = controller.compute_control_traced(inputs.distance_to_target, inputs.angular_error, inputs.velocity_relative);
+    let activated_sets = controller
+        .fuzzy_system()
+        .output_variable
+        .fuzzy_sets
+        .iter()
+        .filter_map(|set| {
+            let degree = trace.set_activations.get(&set.name).copied().unwrap_or(0.0);
+            (degree > 0.0).then(|| ActivatedSet { name: set.name.clone(), degree })
+        })
+        .collect();
+
+    // Constant velocity, as in the simulations
+    let commanded = Radians(trace.defuzzified_value);
+    let control = ControlOutput { angular_adjustment: commanded, acceleration: None };
+    let next = kinematics::advance_state(&state, &control, &characteristics, request.dt);
+
+    Ok(StepResponse {
+        success: true,
+        vehicle_type: vehicle_type.id().to_string(),
+        arrived: false,
+        inputs: step_inputs,
+        activated_sets,
+        commanded_adjustment: commanded.to_degrees().0,
+        angular_adjustment: control.clamped(&characteristics).angular_adjustment.to_degrees().0,
+        next_state: StepState::from_vehicle_state(&next),
+        message: format!("Advanced {} by {}s", vehicle_type.id(), request.dt),
+    })
+}
+
+/// Defaults applied to omitted request fields
+pub fn config() -> ConfigResponse {
+    ConfigResponse::current()
+}
+
+/// Describe the fuzzy controller built for the requested vehicle preset on the requested map size
+pub fn controller(query: &ControllerQuery) -> Result<ControllerResponse, FuzzyNavError> {
+    let vehicle_type = query.parse_vehicle_type()?;
+    let controller = NavigationController::with_distance_scale(&create_vehicle_preset(vehicle_type), query.distance_scale()?);
+    let system = controller.fuzzy_system();
+
+    Ok(ControllerResponse {
+        success: true,
+        vehicle_type: vehicle_type.id().to_string(),
+        name: system.name.clone(),
+        inputs: system.input_variables.iter().map(VariableDescription::from_variable).collect(),
+        output: VariableDescription::from_variable(&system.output_variable),
+        rules: system
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| RuleDescription::from_rule(i + 1, rule))
+            .collect(),
+        defuzzification: DefuzzificationDescription {
+            method: format!("{:?}", system.defuzzification_method),
+            resolution: CENTROID_STEPS,
+        },
+        description: system.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{ArrivalGate, Point, StartDistribution};
+    use crate::simulation::{ArrivalCriteria, PreflightFinding, Scenario, ScenarioVehicle, SimulationBuilder};
+    use crate::vehicle::VehicleType;
+
+    fn request(json: &str) -> SimulationRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_simulate_is_reproducible_with_seed() {
+        let seeded = request(r#"{"vehicle_types": ["Agile"], "max_time": 2.0, "seed": 5}"#);
+        let a = serde_json::to_string(&simulate(&seeded).unwrap()).unwrap();
+        let b = serde_json::to_string(&simulate(&seeded).unwrap()).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_playback_trajectory_ends_on_the_raw_one() {
+        let response = simulate(&request(r#"{"vehicle_types": ["Agile"], "max_time": 3.0, "seed": 5, "playback_fps": 60, "playback_speed": 2}"#)).unwrap();
+        let vehicle = &response.vehicles[0];
+        let playback = vehicle.playback_trajectory.as_ref().unwrap();
+        // 2.95 s recorded from the first step at 30 simulated frames per second, plus the final point
+        assert_eq!(vehicle.trajectory[0].t, 0.05);
+        assert_eq!(playback.len(), 90);
+        assert_eq!(serde_json::to_value(playback.last()).unwrap(), serde_json::to_value(vehicle.trajectory.last()).unwrap());
+
+        let only = simulate(&request(r#"{"vehicle_types": ["Agile"], "max_time": 3.0, "seed": 5, "playback_fps": 60, "playback_speed": 2, "playback_only": true}"#)).unwrap();
+        assert!(only.vehicles[0].trajectory.is_empty());
+        assert_eq!(serde_json::to_value(&only.vehicles[0].playback_trajectory).unwrap(), serde_json::to_value(&vehicle.playback_trajectory).unwrap());
+        assert!(simulate(&request(r#"{"max_time": 3.0}"#)).unwrap().vehicles[0].playback_trajectory.is_none());
+
+        for invalid in [r#""playback_fps": 0"#, r#""playback_fps": 1000"#, r#""playback_fps": 60, "playback_speed": -1"#] {
+            assert!(matches!(
+                simulate(&request(&format!(r#"{{"max_time": 3.0, {}}}"#, invalid))),
+                Err(FuzzyNavError::Simulation(SimulationError::InvalidPlayback { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_playback_frames_are_bounded_before_running() {
+        // 1.4e11 frames over the default 600 s, rejected without building one
+        let slow_motion = request(r#"{"playback_fps": 240, "playback_speed": 1e-6}"#);
+        assert!(matches!(
+            simulate(&slow_motion),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidPlayback { max_frames: MAX_PLAYBACK_FRAMES, .. }))
+        ));
+
+        let fastest = request(r#"{"playback_fps": 240}"#).playback().unwrap().unwrap();
+        assert_eq!((fastest.fps, fastest.speed), (MAX_PLAYBACK_FPS, 1.0));
+        assert!(request(r#"{"max_time": 60.0, "playback_fps": 240, "playback_speed": 0.1}"#).playback().is_ok());
+    }
+
+    #[test]
+    fn test_repeated_simulation_reports_the_spread_of_every_vehicle() {
+        let repeated = request(r#"{"vehicle_types": ["Heavy", "Agile"], "max_time": 120.0, "seed": 7, "repeats": 3, "criteria": "loose"}"#);
+        let response = simulate(&repeated).unwrap();
+        assert_eq!(
+            serde_json::to_string(&response.vehicles).unwrap(),
+            serde_json::to_string(&simulate(&repeated).unwrap().vehicles).unwrap()
+        );
+
+        // One trajectory per vehicle, that of the first repeat, which keeps the request seed
+        let single = simulate(&request(r#"{"vehicle_types": ["Heavy", "Agile"], "max_time": 120.0, "seed": 7, "criteria": "loose"}"#)).unwrap();
+        assert!(single.repeat_stats.is_none());
+        assert_eq!(response.vehicles.len(), 2);
+        assert_eq!(serde_json::to_value(&response.vehicles).unwrap(), serde_json::to_value(&single.vehicles).unwrap());
+
+        let stats = response.repeat_stats.unwrap();
+        assert_eq!((stats.repeats, stats.seeds), (3, vec![7, 8, 9]));
+        let arrivals: Vec<Vec<f64>> = (7..10)
+            .map(|seed| {
+                let run = Scenario { seed: Some(seed), ..repeated.to_scenario().unwrap() }.run().unwrap();
+                run.vehicles.iter().map(|vehicle| vehicle.metrics.arrival_time.unwrap_or(f64::NAN)).collect()
+            })
+            .collect();
+        for (index, vehicle) in stats.vehicles.iter().enumerate() {
+            let times: Vec<f64> = arrivals.iter().map(|run| run[index]).filter(|time| !time.is_nan()).collect();
+            assert_eq!(vehicle.id, response.vehicles[index].id);
+            assert_eq!(vehicle.successes, times.len());
+            assert_eq!(
+                (vehicle.avg_arrival_time, vehicle.std_arrival_time, vehicle.min_arrival_time, vehicle.max_arrival_time),
+                calculate_stats(&times)
+            );
+        }
+        assert!(stats.vehicles.iter().any(|vehicle| vehicle.successes == 3 && vehicle.max_arrival_time > vehicle.min_arrival_time));
+    }
+
+    #[test]
+    fn test_repeats_are_validated_and_charged() {
+        for repeats in [0, MAX_REPEATS + 1] {
+            let invalid = request(&format!(r#"{{"vehicle_types": ["Agile"], "repeats": {}}}"#, repeats));
+            assert!(matches!(
+                simulate(&invalid),
+                Err(FuzzyNavError::Simulation(SimulationError::InvalidRepeats { max: MAX_REPEATS, .. }))
+            ));
+        }
+
+        let single = simulation_cost(&request(r#"{"vehicle_types": ["Agile"], "max_time": 1.0}"#)).unwrap();
+        let repeated = simulation_cost(&request(r#"{"vehicle_types": ["Agile"], "max_time": 1.0, "repeats": 4}"#)).unwrap();
+        assert_eq!(repeated.steps, 4 * single.steps);
+    }
+
+    #[test]
+    fn test_costs_are_projected_with_the_step_limit_and_saturate() {
+        // Every run stops at MAX_STEPS whatever max_time asks for
+        let endless = request(r#"{"vehicle_types": ["Heavy", "Agile"], "max_time": 1e300}"#);
+        assert_eq!(simulation_cost(&endless).unwrap().steps, 2 * simulation::MAX_STEPS as u64);
+        assert_eq!(projected_steps(u64::MAX, 600.0, 0.05), u64::MAX);
+
+        let compared: CompareRequest = serde_json::from_str(
+            r#"{"a": {"vehicle_types": ["Heavy", "Agile"], "max_time": 5.0}, "b": {"vehicle_types": ["Agile"], "max_time": 1.0}}"#,
+        ).unwrap();
+        assert_eq!(compare_cost(&compared).unwrap().steps, 2 * (100 + 16) + (20 + 16));
+
+        let grid: ReachabilityRequest = serde_json::from_str(r#"{"columns": 3, "rows": 2, "max_time": 1.0}"#).unwrap();
+        assert_eq!(reachability_cost(&grid).unwrap().steps, 6 * (20 + 16));
+        let oversized: ReachabilityRequest = serde_json::from_str(r#"{"columns": 100, "rows": 100}"#).unwrap();
+        assert!(matches!(
+            reachability_cost(&oversized),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidGridSize { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_steps_without_any_rule_fired_are_counted_and_reported() {
+        let gutted = request(&format!(
+            r#"{{"vehicle_types": ["Standard"], "max_time": 10.0, "seed": 3, "disabled_rules": {:?}}}"#,
+            (0..crate::navigation::RULE_COUNT).collect::<Vec<_>>()
+        ));
+        let response = simulate(&gutted).unwrap();
+        let metrics = &response.vehicles[0].metrics;
+        assert_eq!(metrics.steps_with_no_rule_fired, 200);
+        assert_eq!(metrics.no_rule_fired_percent, 100.0);
+        assert!(response.message.contains("no controller rule fired in 200 steps (100.0%) of standard-1"), "{}", response.message);
+
+        let default = simulate(&request(r#"{"seed": 3}"#)).unwrap();
+        assert!(default.vehicles.iter().all(|vehicle| vehicle.metrics.steps_with_no_rule_fired == 0));
+        assert!(!default.message.contains("Warning"), "{}", default.message);
+    }
+
+    #[test]
+    fn test_simulate_returns_trajectories_and_target_in_the_requested_frame() {
+        let map_frame = simulate(&request(r#"{"vehicle_types": ["Agile"], "max_time": 2.0, "seed": 5}"#)).unwrap();
+        let target_frame = simulate(&request(
+            r#"{"vehicle_types": ["Agile"], "max_time": 2.0, "seed": 5, "include_geometry": true,
+                "frame": {"origin": "target", "flip_y": true, "angles": "compass"}}"#,
+        ))
+        .unwrap();
+
+        let (plain, framed) = (&map_frame.vehicles[0], &target_frame.vehicles[0]);
+        assert_eq!(plain.target, Some(Point::new(500.0, 700.0)));
+        assert_eq!(framed.target, Some(Point::new(0.0, 0.0)));
+        let (first, framed_first) = (&plain.trajectory[0], &framed.trajectory[0]);
+        assert_eq!((framed_first.x, framed_first.y), (first.x - 500.0, 700.0 - first.y));
+        let heading_error = (framed_first.angle - (Degrees(90.0) + first.angle)).0.rem_euclid(360.0);
+        assert!(heading_error.min(360.0 - heading_error) < 1e-9);
+        assert_eq!(serde_json::to_value(&plain.metrics).unwrap(), serde_json::to_value(&framed.metrics).unwrap());
+
+        // The corridor is below the target on the map, above it with y pointing down
+        assert!(map_frame.approach_geometry.is_none());
+        let geometry = target_frame.approach_geometry.unwrap();
+        assert_eq!(geometry.target, Point::new(0.0, 0.0));
+        assert!(geometry.axis[0].x.abs() < 1e-9 && (geometry.axis[0].y - geometry.approach_radius).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_results_and_responses_carry_the_build_meta() {
+        let seeded = request(r#"{"vehicle_types": ["Agile"], "max_time": 2.0, "seed": 5}"#);
+        let trajectory_json = serde_json::to_value(seeded.to_scenario().unwrap().run().unwrap()).unwrap();
+        let response_json = serde_json::to_value(simulate(&seeded).unwrap()).unwrap();
+
+        for meta in [&trajectory_json["meta"], &response_json["meta"]] {
+            assert_eq!(meta["crate_version"], env!("CARGO_PKG_VERSION"));
+            assert!(!meta["git_hash"].as_str().unwrap().is_empty());
+            assert_eq!(meta["threads"], 1);
+        }
+    }
+
+    #[test]
+    fn test_config_reports_the_request_defaults() {
+        let config = config();
+        let defaults = request("{}");
+        assert_eq!(config.dt, defaults.dt);
+        assert_eq!(config.max_time, defaults.max_time);
+        assert_eq!((config.map.width, config.map.height), (defaults.map_width, defaults.map_height));
+        assert_eq!((config.map.target_x, config.map.target_y), (defaults.target_x, defaults.target_y));
+        assert_eq!(config.default_vehicle_types, defaults.vehicle_types);
+        assert_eq!(ArrivalCriteria::parse(&config.default_criteria), Some(defaults.parse_criteria().unwrap()));
+        assert!(config.vehicle_types.iter().all(|id| VehicleType::parse(id).is_some()));
+        assert_eq!(config.vehicle_types.len(), VehicleType::ALL.len());
+
+        let scenario = Scenario::default_exam();
+        assert_eq!((config.dt, config.max_time), (scenario.dt, scenario.max_time));
+    }
+
+    #[test]
+    fn test_request_converts_into_a_scenario() {
+        let scenario = request(r#"{"vehicle_types": ["UltraAgile"], "seed": 9, "criteria": "loose", "heading": "uniform", "disabled_rules": [7]}"#)
+            .to_scenario()
+            .unwrap();
+        assert_eq!(scenario, Scenario {
+            vehicles: vec![ScenarioVehicle::random(VehicleType::UltraAgile)],
+            seed: Some(9),
+            criteria: ArrivalCriteria::loose(),
+            heading: HeadingDistribution::Uniform,
+            disabled_rules: vec![7],
+            ..Scenario::default_exam()
+        });
+        assert_eq!(request("{}").to_scenario().unwrap(), Scenario::default_exam());
+    }
+
+    #[test]
+    fn test_simulate_rejects_invalid_requests() {
+        use crate::error::MapError;
+
+        assert!(matches!(
+            simulate(&request(r#"{"vehicle_types": ["Submarine"]}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::UnknownVehicleType(_)))
+        ));
+        assert!(matches!(
+            simulate(&request(r#"{"vehicle_types": []}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::NoVehicles))
+        ));
+        assert!(matches!(
+            simulate(&request(r#"{"disabled_rules": [12]}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::UnknownRule { index: 12, .. }))
+        ));
+        assert!(matches!(
+            simulate(&request(r#"{"map_width": -1.0}"#)),
+            Err(FuzzyNavError::Map(MapError::InvalidDimensions { .. }))
+        ));
+        assert!(matches!(
+            simulate(&request(r#"{"max_time": 0.0}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidMaxTime(_)))
+        ));
+    }
+
+    #[test]
+    fn test_simulate_geojson_contains_every_vehicle() {
+        let seeded = request(r#"{"vehicle_types": ["Heavy", "Agile"], "max_time": 2.0, "seed": 5}"#);
+        let geojson: serde_json::Value =
+            serde_json::from_str(&simulate_geojson(&seeded, GeoRef::new(-71.6, -33.0, 2.0), &Arc::default(), CancelToken::new()).unwrap()).unwrap();
+
+        let lines = geojson["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["geometry"]["type"] == "LineString")
+            .count();
+        assert_eq!(lines, 2);
+    }
+
+    #[test]
+    fn test_compare_reports_how_far_apart_the_controllers_drove() {
+        let compare_json = |b: &str| {
+            let json = format!(
+                r#"{{"a": {{"vehicle_types": ["Standard"], "seed": 11, "max_time": 150.0, "velocity_fraction": 0.1}}, "b": {{"vehicle_types": ["Standard"], "seed": 11, "max_time": 150.0, "velocity_fraction": 0.1{}}}}}"#,
+                b
+            );
+            serde_json::from_str::<CompareRequest>(&json).unwrap()
+        };
+
+        let same = compare(&compare_json("")).unwrap();
+        let similarity = same.vehicles[0].similarity.unwrap();
+        assert_eq!((similarity.frechet_distance, similarity.mean_deviation), (0.0, Some(0.0)));
+
+        // Without the emergency turns the same start takes another path
+        let ablated = compare(&compare_json(r#", "disabled_rules": [7, 8, 9]"#)).unwrap();
+        let similarity = ablated.vehicles[0].similarity.unwrap();
+        assert!(similarity.frechet_distance > 1.0, "{:?}", similarity);
+        assert!(similarity.mean_deviation.unwrap() > 0.0);
+
+        assert!(matches!(
+            compare(&compare_json(r#", "dt": 0.0"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidTimeStep(_)))
+        ));
+    }
+
+    #[test]
+    fn test_benchmark_rejects_zero_iterations() {
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"iterations": 0}"#).unwrap();
+        assert!(matches!(
+            benchmark(&request),
+            Err(FuzzyNavError::Simulation(SimulationError::NoIterations))
+        ));
+    }
+
+    #[test]
+    fn test_benchmark_request_takes_either_iterations_or_a_duration() {
+        let request: BenchmarkRequest = serde_json::from_str(r#"{}"#).unwrap();
+        assert_eq!(request.to_config().unwrap().iterations, 30);
+
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"duration_seconds": 1.5}"#).unwrap();
+        let config = request.to_config().unwrap();
+        assert_eq!((config.iterations, config.duration_seconds), (0, Some(1.5)));
+
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"iterations": 5, "duration_seconds": 1.5}"#).unwrap();
+        assert!(matches!(request.to_config(), Err(SimulationError::IterationsAndDuration)));
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"duration_seconds": -1.0}"#).unwrap();
+        assert!(matches!(benchmark(&request), Err(FuzzyNavError::Simulation(SimulationError::InvalidDuration { .. }))));
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"duration_seconds": 1e12}"#).unwrap();
+        assert!(matches!(benchmark_cost(&request), Err(FuzzyNavError::Simulation(SimulationError::InvalidDuration { .. }))));
+    }
+
+    #[test]
+    fn test_converging_benchmark_stops_at_the_minimum_batch_count_or_the_cap() {
+        let request = |convergence: &str| -> BenchmarkRequest {
+            serde_json::from_str(&format!(
+                r#"{{"iterations": 12, "convergence": {}, "vehicle_types": ["Agile", "Heavy"], "max_time": 1.0, "seed": 4}}"#,
+                convergence
+            ))
+            .unwrap()
+        };
+
+        // Nobody arrives within a second, 10 failures pin the success rate within ±50 points
+        let converged = benchmark(&request(r#"{"batch_size": 5, "min_batches": 2, "success_rate_half_width": 50.0}"#)).unwrap();
+        let report = converged.convergence.as_ref().unwrap();
+        assert!(report.converged);
+        assert_eq!(report.stop_reason, StopReason::Converged);
+        assert_eq!((report.batches, report.iterations, converged.num_iterations), (2, 10, 10));
+        assert_eq!(report.groups.len(), 2);
+        assert!(report.groups.iter().all(|group| group.runs == 10 && group.arrival_time_std_error.is_none()));
+        // The recorded configuration repeats the iterations that ran
+        assert_eq!((converged.config.iterations, converged.config.convergence), (10, None));
+
+        let capped = benchmark(&request(r#"{"batch_size": 5, "success_rate_half_width": 0.1}"#)).unwrap();
+        let report = capped.convergence.unwrap();
+        assert!(!report.converged);
+        assert_eq!(report.stop_reason, StopReason::IterationCap);
+        assert_eq!((report.batches, capped.num_iterations), (3, 12));
+        assert!(report.groups[0].success_rate_half_width > 0.1);
+
+        let fixed = benchmark(&request("null")).unwrap();
+        assert!(fixed.convergence.is_none());
+        assert_eq!(fixed.num_iterations, 12);
+    }
+
+    #[test]
+    fn test_convergence_is_validated() {
+        let request: BenchmarkRequest =
+            serde_json::from_str(r#"{"duration_seconds": 1.5, "convergence": {}}"#).unwrap();
+        assert!(matches!(benchmark(&request), Err(FuzzyNavError::Simulation(SimulationError::ConvergenceAndDuration))));
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"convergence": {"batch_size": 0}}"#).unwrap();
+        assert!(matches!(benchmark(&request), Err(FuzzyNavError::Simulation(SimulationError::InvalidConvergence(_)))));
+    }
+
+    #[test]
+    fn test_budgeted_benchmark_matches_the_same_iterations_of_a_fixed_count_run() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"duration_seconds": 2.0, "vehicle_types": ["Agile"], "max_time": 1.0, "seed": 12}"#,
+        )
+        .unwrap();
+        let config = request.to_config().unwrap();
+        let started = Instant::now();
+        let (budgeted, usage, _) = run_iterations(&config, &Arc::default()).unwrap();
+        let elapsed = started.elapsed().as_secs_f64();
+
+        assert!(!budgeted.is_empty());
+        // The deadline only stops new iterations, the running ones finish
+        assert!(elapsed < 3.0, "took {elapsed}s");
+        assert!(usage.iterations_per_second > 0.0);
+
+        let (fixed, _, _) = run_iterations(&config.with_completed_iterations(budgeted.len()), &Arc::default()).unwrap();
+        assert_eq!(fixed.len(), budgeted.len());
+        for (a, b) in budgeted.iter().zip(&fixed) {
+            assert_eq!((a[0].initial_x, a[0].initial_y, a[0].initial_angle), (b[0].initial_x, b[0].initial_y, b[0].initial_angle));
+            assert_eq!((a[0].final_distance, a[0].distance_traveled), (b[0].final_distance, b[0].distance_traveled));
+        }
+
+        let response = benchmark(&request).unwrap();
+        assert_eq!(response.duration_seconds, Some(2.0));
+        assert_eq!(response.config.iterations, response.num_iterations);
+        assert_eq!(response.config.duration_seconds, None);
+    }
+
+    #[test]
+    fn test_benchmark_refuses_a_failed_preflight_unless_forced() {
+        let every_rule = r#""disabled_rules": [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]"#;
+        let request: BenchmarkRequest =
+            serde_json::from_str(&format!(r#"{{"iterations": 1, "vehicle_types": ["Agile"], "max_time": 5.0, {}}}"#, every_rule))
+                .unwrap();
+        match benchmark(&request) {
+            Err(FuzzyNavError::Simulation(SimulationError::PreflightFailed(report))) => {
+                assert!(report.findings.iter().any(|f| matches!(f, PreflightFinding::NoRulesFired { .. })));
+            }
+            other => panic!("expected a failed preflight, got {:?}", other.map(|r| r.message)),
+        }
+
+        let forced = BenchmarkRequest { force: true, ..request };
+        assert_eq!(benchmark(&forced).unwrap().aggregate_stats[0].successes, 0);
+    }
+
+    #[test]
+    fn test_benchmark_request_selects_the_start_distribution() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 2, "start_distribution": {"stratified": {"bins": 4}}, "seed": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(request.to_config().unwrap().start_distribution, StartDistribution::Stratified { bins: 4 });
+
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"start_distribution": {"stratified": {"bins": 0}}}"#).unwrap();
+        assert!(matches!(benchmark(&request), Err(FuzzyNavError::Simulation(SimulationError::NoStartBins))));
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_benchmark_parquet_has_one_row_per_run() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 4, "vehicle_types": ["Heavy", "Agile"], "max_time": 1.0, "seed": 3}"#,
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("benchmark_runs_{}.parquet", std::process::id()));
+        let (parquet, overrun) = benchmark_parquet(&request, &Arc::default()).unwrap();
+        assert_eq!(overrun, None);
+        std::fs::write(&path, parquet).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 8);
+    }
+
+    #[cfg(feature = "xlsx")]
+    #[test]
+    fn test_benchmark_xlsx_has_a_row_per_group() {
+        use calamine::{open_workbook_from_rs, Reader, Xlsx};
+
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 4, "vehicle_types": ["Heavy", "Agile"], "max_time": 1.0, "seed": 3}"#,
+        )
+        .unwrap();
+        let (bytes, overrun) = benchmark_xlsx(&request, &Arc::default()).unwrap();
+        assert_eq!(overrun, None);
+
+        let mut workbook: Xlsx<_> = open_workbook_from_rs(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(workbook.worksheet_range("Summary").unwrap().height(), 3);
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_stored_benchmark_aggregates_like_the_response() {
+        use crate::storage::RunFilter;
+
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 3, "vehicle_types": ["Heavy", "Agile"], "max_time": 20.0, "criteria": "loose", "seed": 2}"#,
+        )
+        .unwrap();
+        let mut store = RunStore::open_in_memory().unwrap();
+        let response = benchmark_stored(&request, &Arc::default(), &mut store).unwrap();
+        let run_id = response.run_id.unwrap();
+
+        let details = store.get_run(run_id).unwrap().unwrap();
+        assert_eq!(details.benchmark_config().unwrap(), Some(response.config.clone()));
+        let aggregates = store.aggregate_across_runs(&RunFilter { run_ids: vec![run_id], ..RunFilter::default() }).unwrap();
+        assert_eq!(aggregates.len(), response.aggregate_stats.len());
+        for (stored, stats) in aggregates.iter().zip(&response.aggregate_stats) {
+            assert_eq!(stored.vehicle_type, stats.vehicle_type);
+            assert_eq!((stored.total_runs, stored.successes), (stats.total_runs, stats.successes));
+            assert_eq!(stored.success_rate, stats.success_rate);
+            assert_eq!(stored.avg_arrival_time, stats.avg_arrival_time);
+            assert_eq!(stored.std_arrival_time, stats.std_arrival_time);
+            assert_eq!(stored.avg_distance_traveled, stats.avg_distance_traveled);
+            assert_eq!(stored.avg_final_distance, stats.avg_final_distance);
+        }
+    }
+
+    #[test]
+    fn test_seeded_benchmark_is_reproducible() {
+        let request: BenchmarkRequest =
+            serde_json::from_str(r#"{"iterations": 3, "vehicle_types": ["Agile"], "max_time": 2.0, "seed": 9}"#).unwrap();
+        let a = benchmark(&request).unwrap();
+        let b = benchmark(&request).unwrap();
+
+        assert_eq!(a.aggregate_stats[0].total_runs, 3);
+        assert!(a.aggregate_stats[0].arrival_funnel.is_none());
+        assert_eq!(a.aggregate_stats[0].avg_distance_traveled, b.aggregate_stats[0].avg_distance_traveled);
+        assert_eq!(a.aggregate_stats[0].avg_arrival_time_ci_low, b.aggregate_stats[0].avg_arrival_time_ci_low);
+
+        // Two seconds from the start zone never reach the approach corridor
+        assert_eq!(a.aggregate_stats[0].avg_max_approach_bias_deg, Some(0.0));
+        assert_eq!(a.aggregate_stats[0].avg_max_approach_bias_deg_failed, Some(0.0));
+        assert_eq!(a.aggregate_stats[0].avg_approach_bias_deg_inside_corridor, None);
+    }
+
+    #[test]
+    fn test_seeded_benchmark_serializes_identically_across_runs() {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 4, "vehicle_types": ["Agile", "Heavy"], "max_time": 4.0, "seed": 21,
+                "heading": ["uniform", "away_from_target"]}"#,
+        )
+        .unwrap();
+        // The whole response as serialized, minus the timings
+        let digest = || {
+            let response = benchmark(&request).unwrap();
+            let mut value = serde_json::to_value(&response).unwrap();
+            value["resource_usage"] = serde_json::Value::Null;
+            value["aggregate_stats"] = without_wall_times(&response.aggregate_stats);
+            let mut hasher = DefaultHasher::new();
+            value.to_string().hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(digest(), digest());
+    }
+
+    #[test]
+    fn test_unseeded_benchmark_reruns_from_its_own_output() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 3, "vehicle_types": ["Agile", "Heavy"], "max_time": 20.0, "criteria": "loose"}"#,
+        )
+        .unwrap();
+        let first = benchmark(&request).unwrap();
+        let output = serde_json::to_string(&first).unwrap();
+
+        let config = BenchmarkConfig::from_results_json(&output).unwrap();
+        assert_eq!(config, first.config);
+        assert_eq!(config.version_warning(), None);
+        let rerun = run_benchmark(&config).unwrap();
+        assert_eq!(without_wall_times(&rerun.aggregate_stats), without_wall_times(&first.aggregate_stats));
+    }
+
+    /// Aggregate stats minus the wall times, which differ between runs
+    fn without_wall_times(stats: &[AggregateStats]) -> serde_json::Value {
+        let mut value = serde_json::to_value(stats).unwrap();
+        for row in value.as_array_mut().unwrap() {
+            let row = row.as_object_mut().unwrap();
+            row.remove("avg_wall_time");
+            row.remove("p95_wall_time");
+        }
+        value
+    }
+
+    #[test]
+    fn test_benchmark_reports_consistent_resource_usage() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 4, "vehicle_types": ["Heavy", "Agile"], "max_time": 2.0, "seed": 5}"#,
+        )
+        .unwrap();
+        let response = benchmark(&request).unwrap();
+        let usage = &response.resource_usage;
+
+        assert!(usage.total_wall_time > 0.0 && usage.iterations_per_second > 0.0);
+        assert!(usage.avg_iteration_time > 0.0 && usage.p95_iteration_time >= usage.avg_iteration_time);
+        assert!(usage.threads >= 1);
+        assert_eq!(response.meta.threads, usage.threads);
+        // Iterations of the same benchmark cannot overlap on more than `threads` workers
+        let iteration_sum = usage.avg_iteration_time * response.num_iterations as f64;
+        assert!(iteration_sum <= usage.total_wall_time * usage.threads as f64);
+
+        // Runs are timed inside their iteration
+        let run_sum: f64 = response.aggregate_stats.iter().map(|stats| stats.avg_wall_time * stats.total_runs as f64).sum();
+        assert!(run_sum > 0.0 && run_sum <= iteration_sum);
+        assert!(response.aggregate_stats.iter().all(|stats| stats.p95_wall_time > 0.0));
+        #[cfg(feature = "memory")]
+        assert!(usage.peak_memory_bytes.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_benchmark_reports_success_rate_interval() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 2, "vehicle_types": ["Agile"], "max_time": 1.0, "seed": 4, "confidence": 0.9}"#,
+        )
+        .unwrap();
+        let stats = &benchmark(&request).unwrap().aggregate_stats[0];
+
+        // Nobody arrives in one second, the interval of 0/2 at 90%
+        assert_eq!(stats.successes, 0);
+        assert_eq!(stats.success_rate_ci_low, 0.0);
+        assert!((stats.success_rate_ci_high - wilson_interval(0, 2, 0.9).1 * 100.0).abs() < 1e-9);
+        assert_eq!((stats.avg_arrival_time_ci_low, stats.avg_arrival_time_ci_high), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_heading_sweep_reports_each_mode_and_away_starts_arrive_later() {
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 3, "vehicle_types": ["Agile"], "max_time": 120.0, "seed": 1, "velocity_fraction": 0.1,
+                "heading": ["toward_target", "away_from_target"]}"#,
+        )
+        .unwrap();
+        let response = benchmark(&request).unwrap();
+
+        let headings: Vec<_> = response.aggregate_stats.iter().map(|s| s.heading).collect();
+        assert_eq!(headings, [HeadingDistribution::TowardTarget, HeadingDistribution::AwayFromTarget]);
+        let (toward, away) = (&response.aggregate_stats[0], &response.aggregate_stats[1]);
+        assert_eq!((toward.successes, away.successes), (3, 3));
+        // Turning around first costs time on every run
+        assert!(
+            away.avg_arrival_time > toward.avg_arrival_time,
+            "{}s vs {}s",
+            away.avg_arrival_time,
+            toward.avg_arrival_time
+        );
+        assert!(toward.avg_time_optimality_ratio.is_some() && away.avg_time_optimality_ratio.is_some());
+    }
+
+    #[test]
+    fn test_distance_buckets_split_the_runs_and_far_starts_arrive_later() {
+        // One start per fifth of the width: the middle ones below the target, the outer ones in the corners
+        let request: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 5, "vehicle_types": ["Agile"], "max_time": 120.0, "seed": 3, "velocity_fraction": 0.1,
+                "start_distribution": {"stratified": {"bins": 5}},
+                "buckets": {"distance_edges": [720.0]}, "arrival_funnel_bins": 12}"#,
+        )
+        .unwrap();
+        let response = benchmark(&request).unwrap();
+
+        let overall = &response.aggregate_stats[0];
+        assert!(overall.bucket.is_none());
+        let [near, far] = &response.bucket_stats[..] else {
+            panic!("expected two buckets, got {}", response.bucket_stats.len());
+        };
+        assert_eq!(near.bucket.unwrap().distance_to_target.unwrap().max, Some(720.0));
+        assert_eq!(far.bucket.unwrap().distance_to_target.unwrap().min, Some(720.0));
+        assert_eq!(near.total_runs + far.total_runs, overall.total_runs);
+        assert_eq!(near.successes + far.successes, overall.successes);
+        assert!(
+            far.avg_arrival_time > near.avg_arrival_time,
+            "{}s vs {}s",
+            far.avg_arrival_time,
+            near.avg_arrival_time
+        );
+
+        // The funnel ends at the success rate and is only reported overall
+        let funnel = overall.arrival_funnel.as_ref().unwrap();
+        assert_eq!((funnel.len(), funnel[0], funnel[12].0), (13, (0.0, 0.0), 120.0));
+        assert_eq!(funnel[12].1 * 100.0, overall.success_rate);
+        assert!(funnel.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(near.arrival_funnel.is_none() && far.arrival_funnel.is_none());
+    }
+
+    #[test]
+    fn test_degenerate_start_arrivals_are_left_out_of_the_arrival_times() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let start = |y: f64| {
+            SimulationBuilder::new(map.clone(), VehicleType::Agile)
+                .start_position(crate::map::Point::new(500.0, y))
+                .start_angle(map.target.required_angle)
+                .build()
+        };
+        // On the target, and facing it from 60 units below
+        let on_target = run_vehicle(start(700.0));
+        let below = run_vehicle(start(640.0));
+        assert!(on_target.degenerate_start && !below.degenerate_start);
+        let arrival = below.arrival_time.unwrap();
+        assert!(arrival > 0.0);
+
+        let runs = [&on_target, &below];
+        let heading = HeadingDistribution::TowardTarget;
+        let excluded = aggregate("Agile", heading, None, &runs, &BenchmarkConfig::default(), 0);
+        assert_eq!((excluded.total_runs, excluded.successes, excluded.degenerate_starts), (2, 2, 1));
+        assert_eq!((excluded.avg_arrival_time, excluded.min_arrival_time), (arrival, arrival));
+        // The start on the target has a zero optimal time, so no ratio either way
+        assert_eq!(on_target.time_optimality_ratio, None);
+        assert_eq!(excluded.avg_time_optimality_ratio, below.time_optimality_ratio);
+
+        let config = BenchmarkConfig { include_degenerate_arrivals: true, ..Default::default() };
+        let included = aggregate("Agile", heading, None, &runs, &config, 0);
+        assert_eq!(included.min_arrival_time, 0.0);
+        assert_eq!(included.avg_arrival_time, arrival / 2.0);
+    }
+
+    #[test]
+    fn test_reachability_rejects_unknown_vehicle_and_oversized_grid() {
+        let unknown: ReachabilityRequest = serde_json::from_str(r#"{"vehicle_type": "Submarine"}"#).unwrap();
+        assert!(matches!(
+            reachability(&unknown),
+            Err(FuzzyNavError::Simulation(SimulationError::UnknownVehicleType(_)))
+        ));
+
+        let oversized: ReachabilityRequest = serde_json::from_str(r#"{"columns": 1000, "rows": 1000}"#).unwrap();
+        assert!(matches!(
+            reachability(&oversized),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidGridSize { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_robustness_has_a_curve_per_vehicle_type_and_is_capped() {
+        let request: RobustnessRequest = serde_json::from_str(
+            r#"{"axis": "dropout", "levels": [0, 30], "iterations": 2, "vehicle_types": ["Heavy", "agile"], "max_time": 5.0, "seed": 4}"#,
+        ).unwrap();
+        let response = robustness(&request).unwrap();
+        assert_eq!(response.report.seed, 4);
+        let curves: Vec<(&str, usize)> = response.report.curves.iter().map(|curve| (curve.vehicle_type.as_str(), curve.levels.len())).collect();
+        assert_eq!(curves, [("heavy", 2), ("agile", 2)]);
+        assert!(response.report.curves.iter().flat_map(|curve| &curve.levels).all(|level| level.runs == 2));
+        assert_eq!(robustness_cost(&request).unwrap(), QuotaCost { steps: 8 * (100 + 16), iterations: 0 });
+
+        let oversized: RobustnessRequest = serde_json::from_str(r#"{"axis": "dropout", "levels": [0, 1, 2, 3], "iterations": 500}"#).unwrap();
+        assert!(matches!(
+            robustness(&oversized),
+            Err(FuzzyNavError::Simulation(SimulationError::TooManyRobustnessRuns { runs: 6000, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_criteria_are_echoed_in_results() {
+        let loose = request(r#"{"vehicle_types": ["Heavy"], "max_time": 1.0, "seed": 5, "criteria": "loose"}"#);
+        let json = serde_json::to_string(&simulate(&loose).unwrap()).unwrap();
+        assert!(json.contains(r#""criteria":{"distance_threshold":50.0,"angle_tolerance":15.0}"#), "{}", json);
+
+        let explicit: BenchmarkRequest = serde_json::from_str(
+            r#"{"iterations": 1, "vehicle_types": ["Heavy"], "max_time": 1.0, "seed": 5,
+                "criteria": {"distance_threshold": 30.0, "angle_tolerance": 4.0, "require_velocity_below": 9.0, "arrival_hold_steps": 2}}"#,
+        )
+        .unwrap();
+        let response = benchmark(&explicit).unwrap();
+        assert_eq!(response.config.criteria, ArrivalCriteria {
+            distance_threshold: 30.0,
+            gate: ArrivalGate::Circle,
+            angle_tolerance: Degrees(4.0),
+            require_velocity_below: Some(9.0),
+            arrival_hold_steps: 2,
+        });
+        assert!(serde_json::to_string(&response).unwrap().contains(r#""require_velocity_below":9.0,"arrival_hold_steps":2"#));
+    }
+
+    #[test]
+    fn test_vehicles_keep_the_requested_order_and_labels() {
+        let labeled = request(r#"{"vehicle_types": ["Agile", "Heavy", "Agile"], "vehicle_ids": ["left", "hauler", "right"], "max_time": 1.0, "seed": 5}"#);
+        let response = simulate(&labeled).unwrap();
+        let vehicles: Vec<(&str, &str)> = response.vehicles.iter().map(|v| (v.id.as_str(), v.vehicle_type.as_str())).collect();
+        assert_eq!(vehicles, [("left", "agile"), ("hauler", "heavy"), ("right", "agile")]);
+
+        let generated = simulate(&request(r#"{"vehicle_types": ["Agile", "Agile"], "max_time": 1.0, "seed": 5}"#)).unwrap();
+        let ids: Vec<&str> = generated.vehicles.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, ["agile-1", "agile-2"]);
+
+        assert!(matches!(
+            simulate(&request(r#"{"vehicle_types": ["Agile", "Agile"], "vehicle_ids": ["left"]}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::VehicleIdCount { ids: 1, vehicles: 2 }))
+        ));
+        assert!(matches!(
+            simulate(&request(r#"{"vehicle_types": ["Agile", "Agile"], "vehicle_ids": ["left", "left"]}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidVehicleId(_)))
+        ));
+    }
+
+    #[test]
+    fn test_simulate_checks_the_time_step_stability() {
+        assert!(matches!(
+            simulate(&request(r#"{"vehicle_types": ["UltraAgile"], "dt": 0.5}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::UnstableTimeStep { .. }))
+        ));
+        let response = simulate(&request(r#"{"vehicle_types": ["UltraAgile"], "dt": 0.5, "auto_dt": true, "max_time": 1.0, "seed": 2}"#));
+        assert!(response.is_ok());
+    }
+
+    #[test]
+    fn test_repeated_requests_reuse_the_shared_controllers() {
+        let built = || crate::navigation::RULE_BASES_BUILT.with(|built| built.get());
+        let controllers = Arc::new(SharedControllers::default());
+        let seeded = request(r#"{"vehicle_types": ["Agile", "Heavy", "Agile"], "max_time": 5.0, "seed": 9}"#);
+
+        let before = built();
+        let first = simulate_shared(&seeded, &controllers, CancelToken::new()).unwrap();
+        assert_eq!(built() - before, 2);
+        let second = simulate_shared(&seeded, &controllers, CancelToken::new()).unwrap();
+        assert_eq!(built() - before, 2);
+
+        let fresh = simulate(&seeded).unwrap();
+        let trajectories = |response: &SimulationResponse| serde_json::to_value(&response.vehicles).unwrap();
+        assert_eq!(trajectories(&first), trajectories(&fresh));
+        assert_eq!(trajectories(&second), trajectories(&fresh));
+    }
+
+    #[test]
+    fn test_simulate_rejects_unknown_criteria() {
+        assert!(matches!(
+            simulate(&request(r#"{"criteria": "lenient"}"#)),
+            Err(FuzzyNavError::Simulation(SimulationError::UnknownCriteria(_)))
+        ));
+    }
+
+    #[test]
+    fn test_benchmark_rejects_invalid_confidence() {
+        let request: BenchmarkRequest = serde_json::from_str(r#"{"iterations": 1, "confidence": 1.0}"#).unwrap();
+        assert!(matches!(
+            benchmark(&request),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidConfidence(_)))
+        ));
+    }
+}
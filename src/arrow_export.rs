@@ -0,0 +1,255 @@
+// Columnar export of benchmark data as Arrow record batches and Parquet files
+//
+// Two tables, their schemas are part of the output format and only grow by
+// appending nullable columns:
+//
+// `runs`, one row per vehicle and iteration
+//   iteration          UInt64   not null  1-based benchmark iteration
+//   vehicle_type       Utf8     not null  stable id (heavy, standard, agile, ultra_agile)
+//   success            Boolean  not null  arrived within max_time
+//   arrival_time       Float64  nullable  seconds, null when it did not arrive
+//   distance_traveled  Float64  not null  map units
+//   final_distance     Float64  not null  map units to the target
+//   final_angle_error  Float64  not null  degrees from the required 90°
+//   initial_x          Float64  not null  map units
+//   initial_y          Float64  not null  map units
+//   initial_angle      Float64  not null  degrees
+//   heading            Utf8     nullable  start heading mode (toward_target, uniform, away_from_target, fixed(deg))
+//
+// `trajectories`, one row per recorded trajectory point
+//   iteration           UInt64  not null
+//   vehicle_type        Utf8    not null
+//   t                   Float64 not null  seconds
+//   x                   Float64 not null  map units
+//   y                   Float64 not null  map units
+//   angle               Float64 not null  degrees
+//   velocity            Float64 not null  units/s
+//   distance_to_target  Float64 not null  map units
+//   heading             Utf8    nullable  start heading mode
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+
+use crate::simulation::VehicleResult;
+
+/// One row of the `runs` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub iteration: u64,
+    pub vehicle_type: String,
+    pub success: bool,
+    pub arrival_time: Option<f64>,
+    pub distance_traveled: f64,
+    pub final_distance: f64,
+    pub final_angle_error: f64,
+    pub initial_x: f64,
+    pub initial_y: f64,
+    pub initial_angle: f64,
+    pub heading: Option<String>,
+    /// Wall time of the run (seconds)
+    pub wall_time: f64,
+}
+
+pub fn runs_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("iteration", DataType::UInt64, false),
+        Field::new("vehicle_type", DataType::Utf8, false),
+        Field::new("success", DataType::Boolean, false),
+        Field::new("arrival_time", DataType::Float64, true),
+        Field::new("distance_traveled", DataType::Float64, false),
+        Field::new("final_distance", DataType::Float64, false),
+        Field::new("final_angle_error", DataType::Float64, false),
+        Field::new("initial_x", DataType::Float64, false),
+        Field::new("initial_y", DataType::Float64, false),
+        Field::new("initial_angle", DataType::Float64, false),
+        Field::new("heading", DataType::Utf8, true),
+        Field::new("wall_time", DataType::Float64, false),
+    ]))
+}
+
+pub fn trajectories_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("iteration", DataType::UInt64, false),
+        Field::new("vehicle_type", DataType::Utf8, false),
+        Field::new("t", DataType::Float64, false),
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+        Field::new("angle", DataType::Float64, false),
+        Field::new("velocity", DataType::Float64, false),
+        Field::new("distance_to_target", DataType::Float64, false),
+        Field::new("heading", DataType::Utf8, true),
+    ]))
+}
+
+fn float_column<T>(rows: &[T], value: impl Fn(&T) -> f64) -> ArrayRef {
+    Arc::new(rows.iter().map(value).collect::<Float64Array>())
+}
+
+/// `runs` batch of the given rows
+pub fn runs_batch(runs: &[RunRecord]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(runs.iter().map(|r| r.iteration).collect::<UInt64Array>()),
+        Arc::new(runs.iter().map(|r| Some(r.vehicle_type.as_str())).collect::<StringArray>()),
+        Arc::new(runs.iter().map(|r| Some(r.success)).collect::<BooleanArray>()),
+        Arc::new(runs.iter().map(|r| r.arrival_time).collect::<Float64Array>()),
+        float_column(runs, |r| r.distance_traveled),
+        float_column(runs, |r| r.final_distance),
+        float_column(runs, |r| r.final_angle_error),
+        float_column(runs, |r| r.initial_x),
+        float_column(runs, |r| r.initial_y),
+        float_column(runs, |r| r.initial_angle),
+        Arc::new(runs.iter().map(|r| r.heading.as_deref()).collect::<StringArray>()),
+        float_column(runs, |r| r.wall_time),
+    ];
+    RecordBatch::try_new(runs_schema(), columns)
+}
+
+/// `trajectories` batch of every point of the vehicles of one iteration, started with the `heading` mode
+pub fn trajectories_batch(
+    iteration: u64,
+    heading: Option<&str>,
+    vehicles: &[VehicleResult],
+) -> Result<RecordBatch, ArrowError> {
+    let points: Vec<(&str, &crate::simulation::TrajectoryPoint)> = vehicles
+        .iter()
+        .flat_map(|v| v.trajectory.iter().map(move |p| (v.vehicle_type.as_str(), p)))
+        .collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from(vec![iteration; points.len()])),
+        Arc::new(points.iter().map(|(name, _)| Some(*name)).collect::<StringArray>()),
+        float_column(&points, |(_, p)| p.t),
+        float_column(&points, |(_, p)| p.x),
+        float_column(&points, |(_, p)| p.y),
+        float_column(&points, |(_, p)| p.angle.0),
+        float_column(&points, |(_, p)| p.velocity),
+        float_column(&points, |(_, p)| p.distance_to_target),
+        Arc::new(StringArray::from(vec![heading; points.len()])),
+    ];
+    RecordBatch::try_new(trajectories_schema(), columns)
+}
+
+/// Snappy-compressed Parquet writer, write batches then call `close`
+pub fn parquet_writer<W: Write + Send>(writer: W, schema: SchemaRef) -> Result<ArrowWriter<W>, ParquetError> {
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    ArrowWriter::try_new(writer, schema, Some(properties))
+}
+
+/// Write the `runs` table as a complete Parquet file
+pub fn write_runs_parquet<W: Write + Send>(runs: &[RunRecord], writer: W) -> Result<(), ParquetError> {
+    let mut writer = parquet_writer(writer, runs_schema())?;
+    writer.write(&runs_batch(runs)?)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationMetrics, TrajectoryPoint};
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Float64Type, UInt64Type};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn sample_runs() -> Vec<RunRecord> {
+        (0..250)
+            .map(|i| RunRecord {
+                iteration: i / 2 + 1,
+                vehicle_type: if i % 2 == 0 { "heavy" } else { "agile" }.to_string(),
+                success: i % 3 != 0,
+                arrival_time: (i % 3 != 0).then_some(60.0 + i as f64),
+                distance_traveled: 700.0 + i as f64,
+                final_distance: 20.0,
+                final_angle_error: 1.5,
+                initial_x: i as f64,
+                initial_y: 30.0,
+                initial_angle: 45.0,
+                heading: (i % 5 != 0).then(|| "uniform".to_string()),
+                wall_time: 0.01,
+            })
+            .collect()
+    }
+
+    fn read_back(path: &std::path::Path) -> Vec<RecordBatch> {
+        let file = std::fs::File::open(path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap();
+        reader.collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn test_runs_roundtrip_through_parquet() {
+        let runs = sample_runs();
+        let path = std::env::temp_dir().join(format!("runs_{}.parquet", std::process::id()));
+        write_runs_parquet(&runs, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let batches = read_back(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(batches[0].schema(), runs_schema());
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), runs.len());
+
+        let batch = arrow::compute::concat_batches(&runs_schema(), &batches).unwrap();
+        let iteration = batch.column(0).as_primitive::<UInt64Type>();
+        let vehicle_type = batch.column(1).as_string::<i32>();
+        let arrival_time = batch.column(3).as_primitive::<Float64Type>();
+        let initial_x = batch.column(7).as_primitive::<Float64Type>();
+        let heading = batch.column(10).as_string::<i32>();
+        for (row, run) in runs.iter().enumerate().step_by(37) {
+            assert_eq!(iteration.value(row), run.iteration);
+            assert_eq!(vehicle_type.value(row), run.vehicle_type);
+            assert_eq!(arrival_time.is_null(row), run.arrival_time.is_none());
+            assert_eq!(initial_x.value(row), run.initial_x);
+            assert_eq!(heading.is_null(row), run.heading.is_none());
+        }
+    }
+
+    #[test]
+    fn test_trajectories_streamed_per_iteration() {
+        let vehicle = |name: &str, points: usize| VehicleResult {
+            id: format!("{}-1", name.to_lowercase()),
+            vehicle_type: name.to_string(),
+            trajectory: (0..points)
+                .map(|i| TrajectoryPoint { t: i as f64 * 0.05, x: i as f64, y: 2.0 * i as f64, ..Default::default() })
+                .collect(),
+            metrics: SimulationMetrics {
+                success: false,
+                arrival_time: None,
+                distance_traveled: 0.0,
+                final_distance_to_target: 0.0,
+                final_angle_error: 0.0,
+                termination: None,
+                ..Default::default()
+            },
+            target: None,
+        };
+        let path = std::env::temp_dir().join(format!("trajectories_{}.parquet", std::process::id()));
+
+        let mut writer = parquet_writer(std::fs::File::create(&path).unwrap(), trajectories_schema()).unwrap();
+        for iteration in 1..=3 {
+            let vehicles = [vehicle("heavy", 40), vehicle("standard", 25)];
+            writer.write(&trajectories_batch(iteration, Some("away_from_target"), &vehicles).unwrap()).unwrap();
+        }
+        writer.close().unwrap();
+
+        let batches = read_back(&path);
+        std::fs::remove_file(&path).ok();
+
+        let batch = arrow::compute::concat_batches(&trajectories_schema(), &batches).unwrap();
+        assert_eq!(batch.num_rows(), 3 * 65);
+        assert_eq!(batch.column(0).as_primitive::<UInt64Type>().value(65), 2);
+        assert_eq!(batch.column(1).as_string::<i32>().value(40), "standard");
+        assert_eq!(batch.column(4).as_primitive::<Float64Type>().value(39), 78.0);
+        assert_eq!(batch.column(8).as_string::<i32>().value(100), "away_from_target");
+    }
+}
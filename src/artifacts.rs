@@ -0,0 +1,279 @@
+// Index of the files the CLI bins write: `manifest.json` in the output
+// directory lists every artifact with the bin, arguments, configuration and
+// seed that produced it
+//
+// Every update takes an exclusive lock on `manifest.lock` and rereads the
+// manifest before writing it, so bins running at the same time add to it
+// rather than overwrite each other.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::build_info::CRATE_VERSION;
+use crate::error::{FuzzyNavError, SerializationError};
+
+/// Name of the manifest in the output directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Lock file taken while the manifest is updated
+const LOCK_FILE: &str = "manifest.lock";
+
+/// What an artifact holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// Trajectories of a simulation
+    Simulation,
+    /// Scenario a simulation can be replayed from
+    Scenario,
+    /// Benchmark results, raw or aggregated
+    Benchmark,
+    /// Image or animation
+    Plot,
+    /// Analysis report, such as a fuzz or robustness run
+    Report,
+}
+
+impl ArtifactKind {
+    pub const ALL: [ArtifactKind; 5] = [
+        ArtifactKind::Simulation,
+        ArtifactKind::Scenario,
+        ArtifactKind::Benchmark,
+        ArtifactKind::Plot,
+        ArtifactKind::Report,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            ArtifactKind::Simulation => "simulation",
+            ArtifactKind::Scenario => "scenario",
+            ArtifactKind::Benchmark => "benchmark",
+            ArtifactKind::Plot => "plot",
+            ArtifactKind::Report => "report",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == s)
+    }
+}
+
+impl std::fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// One file of the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+    /// Relative to the manifest directory when the file is inside it
+    pub path: String,
+    pub kind: ArtifactKind,
+    /// Name of the bin that wrote it
+    pub producer: String,
+    /// Arguments of the bin, without its name
+    pub args: Vec<String>,
+    /// `config_hash` of the scenario or configuration it was produced from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Unix time it was recorded (seconds)
+    pub created_at: u64,
+    pub crate_version: String,
+}
+
+impl ArtifactEntry {
+    /// Entry of `path` written by the running bin, with its name and arguments
+    pub fn new(path: impl Into<String>, kind: ArtifactKind) -> Self {
+        let mut args = std::env::args();
+        let producer = args
+            .next()
+            .and_then(|bin| Path::new(&bin).file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        Self {
+            path: path.into(),
+            kind,
+            producer,
+            args: args.collect(),
+            config_hash: None,
+            seed: None,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()),
+            crate_version: CRATE_VERSION.to_string(),
+        }
+    }
+
+    /// Hash the scenario or configuration the artifact was produced from
+    pub fn config(self, config: &impl Serialize) -> Self {
+        Self { config_hash: config_hash(config).ok(), ..self }
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed: Some(seed), ..self }
+    }
+}
+
+/// Stable hash of the JSON form of `config`, 16 hex digits
+///
+/// FNV-1a, the same configuration hashes the same across builds and platforms.
+pub fn config_hash(config: &impl Serialize) -> Result<String, FuzzyNavError> {
+    let json = serde_json::to_vec(config)?;
+    let hash = json.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    Ok(format!("{:016x}", hash))
+}
+
+/// Artifacts of one output directory
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(skip)]
+    dir: PathBuf,
+    pub artifacts: Vec<ArtifactEntry>,
+}
+
+impl Manifest {
+    /// Manifest of `dir`, empty when the directory has none yet
+    ///
+    /// The directory is created when missing.
+    pub fn load_or_create(dir: impl AsRef<Path>) -> Result<Self, FuzzyNavError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| file_error(&dir, e))?;
+        let artifacts = read_artifacts(&dir.join(MANIFEST_FILE))?;
+        Ok(Self { dir, artifacts })
+    }
+
+    /// Add `entry`, replacing the entry of the same path, and write the manifest
+    ///
+    /// Entries other processes recorded since this manifest was loaded are kept.
+    pub fn record(&mut self, mut entry: ArtifactEntry) -> Result<(), FuzzyNavError> {
+        if let Ok(relative) = Path::new(&entry.path).strip_prefix(&self.dir) {
+            entry.path = relative.to_string_lossy().into_owned();
+        }
+
+        let lock_path = self.dir.join(LOCK_FILE);
+        let lock = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path).map_err(|e| file_error(&lock_path, e))?;
+        lock.lock().map_err(|e| file_error(&lock_path, e))?;
+
+        let path = self.dir.join(MANIFEST_FILE);
+        self.artifacts = read_artifacts(&path)?;
+        self.artifacts.retain(|artifact| artifact.path != entry.path);
+        self.artifacts.push(entry);
+
+        // Written aside and renamed, a reader never sees half a manifest
+        let partial = self.dir.join(format!("{}.partial", MANIFEST_FILE));
+        let json = serde_json::to_vec_pretty(self)?;
+        let mut file = File::create(&partial).map_err(|e| file_error(&partial, e))?;
+        file.write_all(&json).map_err(|e| file_error(&partial, e))?;
+        fs::rename(&partial, &path).map_err(|e| file_error(&path, e))
+        // The lock is released when `lock` is dropped
+    }
+
+    /// Artifacts of `kind`, or all of them, in the order they were recorded
+    pub fn filtered(&self, kind: Option<ArtifactKind>) -> impl Iterator<Item = &ArtifactEntry> {
+        self.artifacts.iter().filter(move |artifact| kind.is_none_or(|kind| artifact.kind == kind))
+    }
+}
+
+/// Record `entry` in the manifest of the directory of its path, warning instead of failing
+///
+/// A bin has written its output by then, a manifest it cannot update should not stop it.
+pub fn record(entry: ArtifactEntry) {
+    let dir = Path::new(&entry.path).parent().map(Path::to_path_buf).unwrap_or_default();
+    let path = entry.path.clone();
+    if let Err(e) = Manifest::load_or_create(&dir).and_then(|mut manifest| manifest.record(entry)) {
+        tracing::warn!("{} was not added to the artifact manifest: {}", path, e);
+    }
+}
+
+fn read_artifacts(path: &Path) -> Result<Vec<ArtifactEntry>, FuzzyNavError> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str::<Manifest>(&json)?.artifacts),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(file_error(path, e)),
+    }
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> FuzzyNavError {
+    SerializationError::File { path: path.display().to_string(), message: error.to_string() }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Scenario;
+
+    /// Empty directory of its own for every test
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("artifacts_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_two_exports_append_two_entries_with_their_hashes() {
+        let dir = output_dir("exports");
+        let first = Scenario::default_exam();
+        let second = Scenario { seed: Some(9), ..Scenario::default_exam() };
+
+        let mut manifest = Manifest::load_or_create(&dir).unwrap();
+        let trajectory = dir.join("trajectory_multi.json").to_string_lossy().into_owned();
+        manifest.record(ArtifactEntry::new(trajectory, ArtifactKind::Simulation).config(&first).seed(1)).unwrap();
+        manifest.record(ArtifactEntry::new("benchmark.csv", ArtifactKind::Benchmark).config(&second)).unwrap();
+
+        let reloaded = Manifest::load_or_create(&dir).unwrap();
+        assert_eq!(reloaded, manifest);
+        let [simulation, benchmark] = reloaded.artifacts.as_slice() else { panic!("{:?}", reloaded.artifacts) };
+        assert_eq!((simulation.path.as_str(), simulation.seed), ("trajectory_multi.json", Some(1)));
+        assert_eq!(simulation.config_hash, Some(config_hash(&first).unwrap()));
+        assert_eq!(benchmark.config_hash, Some(config_hash(&second).unwrap()));
+        assert_ne!(simulation.config_hash, benchmark.config_hash);
+        assert_eq!(simulation.crate_version, CRATE_VERSION);
+        assert_eq!(reloaded.filtered(Some(ArtifactKind::Benchmark)).count(), 1);
+
+        // Writing a file again updates its entry
+        manifest.record(ArtifactEntry::new("benchmark.csv", ArtifactKind::Benchmark).seed(4)).unwrap();
+        let reloaded = Manifest::load_or_create(&dir).unwrap();
+        assert_eq!(reloaded.artifacts.len(), 2);
+        assert_eq!(reloaded.artifacts[1].seed, Some(4));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_every_entry() {
+        let dir = output_dir("concurrent");
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    // Each writer loaded the manifest before the other wrote to it
+                    let mut manifest = Manifest::load_or_create(&dir).unwrap();
+                    for i in 0..20 {
+                        manifest.record(ArtifactEntry::new(format!("plot_{}_{}.png", writer, i), ArtifactKind::Plot)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let manifest = Manifest::load_or_create(&dir).unwrap();
+        assert_eq!(manifest.artifacts.len(), 40);
+        assert_eq!(manifest.filtered(Some(ArtifactKind::Plot)).count(), 40);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_hash_is_stable() {
+        // FNV-1a of "{}", pinned so a change of the hash shows up here
+        assert_eq!(config_hash(&serde_json::json!({})).unwrap(), "08f44b07b5901a25");
+        assert_eq!(ArtifactKind::parse("plot"), Some(ArtifactKind::Plot));
+        assert_eq!(ArtifactKind::parse("gif"), None);
+    }
+}
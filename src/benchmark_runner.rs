@@ -1,8 +1,12 @@
 // Benchmark: Run multiple simulations to collect metrics for research
 // Extracted from bin/benchmark.rs
 
+use examen_parcial::analysis::stats::calculate_stats;
 use examen_parcial::map::Map;
-use examen_parcial::simulation::Simulation;
+use examen_parcial::membership_export::ImageFormat;
+use examen_parcial::build_info::BuildMeta;
+use examen_parcial::simulation::{Language, MultiVehicleSimulationResult, Simulation, VehicleResult, SCHEMA_VERSION};
+use examen_parcial::trajectory_export::{self, PlotOptions};
 use examen_parcial::vehicle::VehicleType;
 use rayon::prelude::*;
 use serde::Serialize;
@@ -59,28 +63,16 @@ struct BenchmarkResult {
     aggregate: Vec<AggregateStats>,
 }
 
-fn calculate_stats(values: &[f64]) -> (f64, f64, f64, f64) {
-    if values.is_empty() {
-        return (0.0, 0.0, 0.0, 0.0);
-    }
-    let n = values.len() as f64;
-    let mean = values.iter().sum::<f64>() / n;
-    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
-    let std = variance.sqrt();
-    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    (mean, std, min, max)
-}
-
-fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time: f64) -> VehicleMetrics {
+/// Run one simulation, returning its benchmark metrics and the full result (for plotting)
+fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time: f64) -> (VehicleMetrics, VehicleResult) {
     let mut sim = Simulation::new(map.clone(), vehicle_type, dt, max_time);
 
     let initial_x = sim.vehicle.state.position.x;
     let initial_y = sim.vehicle.state.position.y;
-    let initial_angle = sim.vehicle.state.angle.to_degrees();
+    let initial_angle = sim.vehicle.state.angle.to_degrees().0;
 
     // Run simulation
-    while sim.time < max_time && !sim.vehicle.has_arrived {
+    while !sim.is_finished() {
         sim.step();
     }
 
@@ -89,7 +81,7 @@ fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time
 
     let final_point = sim.trajectory.last().unwrap();
     let final_distance = final_point.distance_to_target;
-    let final_angle_error = (90.0 - final_point.angle).abs();
+    let final_angle_error = (90.0 - final_point.angle.0).abs();
 
     // Calculate distance traveled
     let mut distance_traveled = 0.0;
@@ -101,8 +93,8 @@ fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time
         distance_traveled += (dx * dx + dy * dy).sqrt();
     }
 
-    VehicleMetrics {
-        vehicle_type: vehicle_type.name().to_string(),
+    let metrics = VehicleMetrics {
+        vehicle_type: vehicle_type.id().to_string(),
         success,
         arrival_time,
         distance_traveled,
@@ -111,10 +103,19 @@ fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time
         initial_x,
         initial_y,
         initial_angle,
-    }
+    };
+
+    let result = VehicleResult {
+        id: sim.id,
+        vehicle_type: vehicle_type.id().to_string(),
+        metrics: sim.metrics(),
+        trajectory: sim.trajectory,
+    };
+
+    (metrics, result)
 }
 
-pub fn run(num_iterations: usize, num_threads: Option<usize>) {
+pub fn run(num_iterations: usize, num_threads: Option<usize>, plot_format: Option<ImageFormat>) {
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║   FUZZY NAVIGATION BENCHMARK                         ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
@@ -155,27 +156,32 @@ pub fn run(num_iterations: usize, num_threads: Option<usize>) {
     let completed = Arc::new(AtomicUsize::new(0));
     let completed_clone = Arc::clone(&completed);
 
-    // Run iterations in parallel using rayon
-    let all_iterations: Vec<IterationResult> = (0..num_iterations)
+    // Run iterations in parallel using rayon, keeping only the trajectories of the first one
+    let (all_iterations, kept_trajectories): (Vec<IterationResult>, Vec<Vec<VehicleResult>>) = (0..num_iterations)
         .into_par_iter()
         .map(|i| {
             // Run simulations for all vehicle types in this iteration
-            let iteration_vehicles: Vec<VehicleMetrics> = vehicle_types
+            let (iteration_vehicles, vehicle_results): (Vec<VehicleMetrics>, Vec<VehicleResult>) = vehicle_types
                 .iter()
                 .map(|&vtype| run_single_simulation(&map, vtype, dt, max_time))
-                .collect();
+                .unzip();
 
             // Update progress counter
             let current = completed_clone.fetch_add(1, Ordering::Relaxed) + 1;
             print!("\rCompleted iterations: {}/{}...", current, num_iterations);
             std::io::stdout().flush().unwrap();
 
-            IterationResult {
-                iteration: i + 1,
-                vehicles: iteration_vehicles,
-            }
+            let trajectories = if i == 0 { vehicle_results } else { Vec::new() };
+            (
+                IterationResult {
+                    iteration: i + 1,
+                    vehicles: iteration_vehicles,
+                },
+                trajectories,
+            )
         })
-        .collect();
+        .unzip();
+    let first_iteration = kept_trajectories.into_iter().next().unwrap_or_default();
 
     println!(); // New line after progress
 
@@ -213,7 +219,7 @@ pub fn run(num_iterations: usize, num_threads: Option<usize>) {
         let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
         let (avg_angle_error, _, _, _) = calculate_stats(&angle_errors);
 
-        println!("{}:", vtype.name());
+        println!("{}:", vtype.display_name(Language::Spanish));
         println!("  Success Rate: {:.1}% ({}/{})", success_rate, successes, num_iterations);
         println!("  Arrival Time: {:.2}s avg (std: {:.2}, min: {:.2}, max: {:.2})",
             avg_time, std_time, min_time, max_time);
@@ -222,7 +228,7 @@ pub fn run(num_iterations: usize, num_threads: Option<usize>) {
         println!("  Final Angle Error: {:.2} deg avg\n", avg_angle_error);
 
         aggregate_stats.push(AggregateStats {
-            vehicle_type: vtype.name().to_string(),
+            vehicle_type: vtype.id().to_string(),
             total_runs: num_iterations,
             successes,
             success_rate,
@@ -306,4 +312,27 @@ pub fn run(num_iterations: usize, num_threads: Option<usize>) {
     println!("  - {} (JSON)", filename);
     println!("  - {} (CSV raw data)", csv_filename);
     println!("  - {} (CSV summary)", agg_csv_filename);
+
+    if let Some(format) = plot_format {
+        let plot_filename = format!("output/benchmark_{}iterations_iter1.{}", num_iterations, format.extension());
+        let total_simulation_time = first_iteration.iter()
+            .filter_map(|v| v.trajectory.last().map(|p| p.t))
+            .fold(0.0, f64::max);
+        let first = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: first_iteration,
+            total_simulation_time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        };
+        let options = PlotOptions {
+            format,
+            time_label_interval: Some(10.0),
+            ..Default::default()
+        };
+        match trajectory_export::plot_trajectories(&first, &map, options, &plot_filename) {
+            Ok(()) => println!("  - {} (trajectories of iteration 1)", plot_filename),
+            Err(e) => eprintln!("Warning: could not export trajectory plot: {}", e),
+        }
+    }
 }
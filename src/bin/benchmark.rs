@@ -1,86 +1,322 @@
 // Benchmark: Run multiple simulations to collect metrics for research
 //
-// Run with: cargo run --bin benchmark -- [num_iterations]
+// Run with: cargo run --bin benchmark -- [num_iterations] [--plot[=png|svg]] [--format csv|parquet|xlsx] [--confidence=LEVEL] [--criteria strict|normal|loose] [--hold-steps=N] [--gate=ALONG,ACROSS] [--heading toward|uniform|away|DEG[,..]] [--start uniform|gaussian[:SIGMA]|stratified[:BINS]] [--disable-rules I[,..]] [--cache[=DIST,DEG,VEL]] [--distance-buckets D[,..]] [--heading-buckets DEG[,..]] [--reroll-degenerate] [--include-degenerate] [--force] [--map NAME] [--mean-path[=GRID_DT]] [--arrival-funnel[=BINS]] [--duration=SECONDS] [--converge[=HALF_WIDTH,STD_ERROR[,BATCH[,MIN_BATCHES]]]] [--robustness AXIS=LEVEL[,..]] [--store FILE [--store-trajectories] [--list | --show ID]] [--list-artifacts[=KIND]] [--seed=N] [--log-level=LEVEL]
+// Repeat an earlier run from its JSON output: cargo run --bin benchmark -- --rerun output/benchmark_100iterations.json
+// Benchmark a saved scenario (see the navigation bin): cargo run --bin benchmark -- 30 --scenario output/scenario.json
 // Example: cargo run --bin benchmark -- 100
+// Heading sweep, reported per start mode: cargo run --bin benchmark -- 30 --heading toward,away
+// Target near a map corner: cargo run --bin benchmark -- 30 --map corner_top_right
+// Even coverage of the width, one start per tenth of it every 10 iterations: cargo run --bin benchmark -- 30 --start stratified:10
+// Ablation without the alignment rules R1, R4 and R7: cargo run --bin benchmark -- 30 --seed=42 --disable-rules 0,3,6
+// Cached controller evaluations, inputs rounded to 1 unit, 0.5° and 0.01: cargo run --bin benchmark -- 100 --cache
+// Results also grouped by start distance to the target, below 700 units and beyond: cargo run --bin benchmark -- 30 --start stratified:10 --distance-buckets 700
+// Starts already inside the arrival criteria arrive at t=0 and are left out of the arrival times,
+// --reroll-degenerate draws them again and --include-degenerate counts their arrivals anyway
+// Arrival held for 3 consecutive steps, so a vehicle turning through the tolerances does not count: cargo run --bin benchmark -- 30 --hold-steps=3
+// Elliptical arrival gate, 60 units along the required angle and 25 across it: cargo run --bin benchmark -- 30 --gate=60,25
+// Mean path and its spread per vehicle type on a 1 s grid (output/benchmark_30iterations_mean_paths.json): cargo run --bin benchmark -- 30 --mean-path
+// Fraction of runs arrived over time per vehicle type, in 60 bins up to max_time (output/benchmark_30iterations_arrival_funnel.png): cargo run --bin benchmark -- 30 --arrival-funnel
+// As many iterations as fit in 60 s instead of a fixed count (output/benchmark_60s.json): cargo run --bin benchmark -- --duration=60 --seed=42
+// Up to 1000 iterations, stopping once every success rate is known within ±2 points and every mean arrival time within a 0.5 s
+// standard error, checked every 100 iterations from the third batch: cargo run --bin benchmark -- 1000 --converge=2,0.5,100,3
+// Success rate against sensor dropouts per simulated minute, 30 runs per level and vehicle type
+// (output/robustness_dropout.json and .png): cargo run --bin benchmark -- 30 --robustness dropout=0,2,5,10 --seed=42
+// Every run starts with a short controller preflight and stops if it fails, --force skips the stop
+// Parquet output needs the arrow feature: cargo run --features cli,arrow --bin benchmark -- 100 --format parquet
+// Statistics also as an Excel workbook (output/benchmark_100iterations.xlsx): cargo run --features cli,xlsx --bin benchmark -- 100 --format xlsx
+// Keep the runs in a SQLite database across sessions (storage feature): cargo run --features cli,storage --bin benchmark -- 30 --store output/runs.db
+// --store-trajectories also keeps every trajectory, compressed; --list shows the stored runs and --show ID the statistics of one:
+// cargo run --features cli,storage --bin benchmark -- --store output/runs.db --list
+// Every output is listed in output/manifest.json with the bin, arguments, config hash and seed that produced it,
+// --list-artifacts prints it, of one kind with --list-artifacts=benchmark|plot|report|simulation|scenario
 
-use examen_parcial::map::Map;
-use examen_parcial::simulation::Simulation;
-use examen_parcial::vehicle::VehicleType;
+#[cfg(feature = "arrow")]
+use examen_parcial::arrow_export;
+#[cfg(feature = "storage")]
+use examen_parcial::storage::{RunFilter, RunStore, StoredRun};
+#[cfg(feature = "xlsx")]
+use examen_parcial::xlsx_export::{self, BenchmarkSummary, SummaryRecord};
+use examen_parcial::artifacts::{self, ArtifactEntry, ArtifactKind, Manifest};
+use examen_parcial::analysis::{mean_trajectory, resample, robustness_report, RobustnessAxis};
+use examen_parcial::analysis::stats::{
+    self, arrival_funnel, calculate_stats, mean_of_present, percentile, InitialCondition, InitialConditionBucket,
+    InitialConditionBuckets, MetricOutliers, OutlierRun, TrimmedStats, BOOTSTRAP_RESAMPLES,
+};
+use examen_parcial::build_info::BuildMeta;
+use examen_parcial::logging;
+use examen_parcial::map::{ArrivalGate, HeadingDistribution, Map, MapPreset, StartDistribution};
+use examen_parcial::membership_export::ImageFormat;
+use examen_parcial::navigation::Quantization;
+use examen_parcial::progress::Progress;
+use examen_parcial::resource_usage::{MemorySampler, ResourceUsage};
+use examen_parcial::simulation::benchmark::{BenchmarkConfig, VehicleSpec};
+use examen_parcial::simulation::convergence::{Convergence, ConvergenceReport, GroupPrecision, StopReason};
+use examen_parcial::simulation::{
+    ArrivalCriteria, Language, MultiVehicleSimulationResult, Scenario, TrajectoryPoint, VehicleResult, SCHEMA_VERSION,
+};
+use examen_parcial::trajectory_export::{self, FunnelCurve, PlotOptions};
+use examen_parcial::units::Degrees;
+use examen_parcial::vehicle::{display_label, VehicleType};
+use rand::Rng;
 use serde::Serialize;
 use std::env;
 use std::fs;
 use std::io::Write;
+use std::time::{Duration, Instant};
 
 #[derive(Serialize, Clone)]
 struct VehicleMetrics {
     vehicle_type: String,
+    heading: HeadingDistribution,
     success: bool,
     arrival_time: Option<f64>,
+    /// Arrived before the first step, the start already met the arrival criteria
+    degenerate_start: bool,
     distance_traveled: f64,
     final_distance: f64,
     final_angle_error: f64,
+    steering_reversals_per_minute: f64,
+    heading_jerk: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_above_high_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arrival_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    acceleration_reversals: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_approach_bias_deg: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mean_approach_bias_deg_inside_corridor: Option<f64>,
+    /// Dubins path time at cruise velocity and arrival time over it, see `SimulationMetrics`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    optimal_time_lower_bound: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_optimality_ratio: Option<f64>,
+    /// Approach phases, see `SimulationMetrics`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transit_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alignment_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_aligned_time: Option<f64>,
+    alignment_attempts: usize,
+    /// Fraction of the controller evaluations answered by the cache, only with --cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_hit_rate: Option<f64>,
     initial_x: f64,
     initial_y: f64,
     initial_angle: f64,
+    initial_condition: InitialCondition,
+    /// Metrics that are outliers of the runs of this (heading, vehicle type), set once every iteration ran
+    is_outlier: MetricOutliers,
+    /// Wall time of the run (seconds)
+    wall_time: f64,
 }
 
 #[derive(Serialize)]
 struct IterationResult {
     iteration: usize,
+    /// Wall time of every run of the iteration (seconds)
+    wall_time: f64,
     vehicles: Vec<VehicleMetrics>,
 }
 
 #[derive(Serialize)]
 struct AggregateStats {
     vehicle_type: String,
+    heading: HeadingDistribution,
+    /// Initial conditions of these runs, absent for the overall statistics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket: Option<InitialConditionBucket>,
     total_runs: usize,
     successes: usize,
+    /// Runs that arrived before the first step, left out of the arrival times unless --include-degenerate
+    degenerate_starts: usize,
     success_rate: f64,
+    success_rate_ci_low: f64,
+    success_rate_ci_high: f64,
     avg_arrival_time: f64,
+    avg_arrival_time_ci_low: f64,
+    avg_arrival_time_ci_high: f64,
     std_arrival_time: f64,
     min_arrival_time: f64,
     max_arrival_time: f64,
     avg_distance_traveled: f64,
     std_distance_traveled: f64,
+    /// Arrival time and distance traveled without their outlier runs, the figures above stay the headline
+    trimmed_arrival_time: TrimmedStats,
+    trimmed_distance_traveled: TrimmedStats,
     avg_final_distance: f64,
     avg_final_angle_error: f64,
+    avg_steering_reversals_per_minute: f64,
+    avg_heading_jerk: f64,
+    // Velocity profile, only for runs with velocity control
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_time_above_high_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_arrival_velocity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_acceleration_reversals: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_cache_hit_rate: Option<f64>,
+    /// Mean largest approach bias (degrees), over every run and over the failed ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_max_approach_bias_deg: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_max_approach_bias_deg_failed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_approach_bias_deg_inside_corridor: Option<f64>,
+    /// Mean arrival time over the Dubins lower bound of the arrived runs, 1 is optimal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_time_optimality_ratio: Option<f64>,
+    /// Mean approach phases: time to get within the approach start, from there to the arrival, to first align
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_transit_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_alignment_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_first_aligned_time: Option<f64>,
+    avg_alignment_attempts: f64,
+    /// (t, fraction arrived) from 0 to max_time, only for the overall statistics with --arrival-funnel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arrival_funnel: Option<Vec<(f64, f64)>>,
+    /// Runs with an outlier arrival time or distance and their seeds, to replay them, only for the overall statistics
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outlier_runs: Vec<OutlierRun>,
+    avg_wall_time: f64,
+    p95_wall_time: f64,
 }
 
+/// Precision of the success rate and mean arrival time of every group over the iterations so far
+fn group_precision(config: &BenchmarkConfig, all_metrics: &[Vec<VehicleMetrics>]) -> Vec<GroupPrecision> {
+    config.groups()
+        .iter()
+        .zip(all_metrics)
+        .map(|((heading, vehicle), metrics)| {
+            let arrival_times: Vec<f64> = metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .filter_map(|m| m.arrival_time)
+                .collect();
+            let successes = metrics.iter().filter(|m| m.success).count();
+            GroupPrecision::new(vehicle.vehicle_type.id(), *heading, successes, metrics.len(), &arrival_times, config.confidence)
+        })
+        .collect()
+}
+
+/// Row of the Excel summary sheet
+#[cfg(feature = "xlsx")]
+fn summary_record(stat: &AggregateStats) -> SummaryRecord {
+    SummaryRecord {
+        vehicle_type: stat.vehicle_type.clone(),
+        heading: stat.heading.to_string(),
+        bucket: stat.bucket.map(|bucket| bucket.to_string()),
+        total_runs: stat.total_runs,
+        successes: stat.successes,
+        success_rate: stat.success_rate,
+        success_rate_ci_low: stat.success_rate_ci_low,
+        success_rate_ci_high: stat.success_rate_ci_high,
+        avg_arrival_time: stat.avg_arrival_time,
+        std_arrival_time: stat.std_arrival_time,
+        min_arrival_time: stat.min_arrival_time,
+        max_arrival_time: stat.max_arrival_time,
+        avg_distance_traveled: stat.avg_distance_traveled,
+        std_distance_traveled: stat.std_distance_traveled,
+        avg_final_distance: stat.avg_final_distance,
+        avg_final_angle_error: stat.avg_final_angle_error,
+        avg_steering_reversals_per_minute: stat.avg_steering_reversals_per_minute,
+        avg_heading_jerk: stat.avg_heading_jerk,
+        avg_time_optimality_ratio: stat.avg_time_optimality_ratio,
+        avg_transit_time: stat.avg_transit_time,
+        avg_alignment_time: stat.avg_alignment_time,
+        avg_alignment_attempts: stat.avg_alignment_attempts,
+        avg_wall_time: stat.avg_wall_time,
+    }
+}
+
+/// Mean path of the runs of one (heading, vehicle type) group and its standard deviation, see `analysis::mean_trajectory`
+#[derive(Serialize)]
+struct MeanPath {
+    vehicle_type: String,
+    heading: HeadingDistribution,
+    runs: usize,
+    grid_dt: f64,
+    mean: Vec<TrajectoryPoint>,
+    std: Vec<TrajectoryPoint>,
+}
+
+/// Grid of the mean paths when --mean-path has no value (seconds)
+const DEFAULT_MEAN_PATH_GRID_DT: f64 = 1.0;
+
+/// Time bins of the arrival funnel when --arrival-funnel has no value
+const DEFAULT_ARRIVAL_FUNNEL_BINS: usize = 60;
+
 #[derive(Serialize)]
 struct BenchmarkResult {
-    num_iterations: usize,
-    dt: f64,
-    max_time: f64,
-    map_width: f64,
-    map_height: f64,
-    target_x: f64,
-    target_y: f64,
+    /// Layout version of the output, --rerun upgrades older ones
+    schema_version: u32,
+    /// Every input of the run, read back by --rerun
+    config: BenchmarkConfig,
     iterations: Vec<IterationResult>,
     aggregate: Vec<AggregateStats>,
+    /// Every non-empty initial condition bucket of every group, only with --distance-buckets or --heading-buckets
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bucket_stats: Vec<AggregateStats>,
+    /// Why the run stopped and the precision it reached, only with --converge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    convergence: Option<ConvergenceReport>,
+    resource_usage: ResourceUsage,
+    /// Build that ran the benchmark
+    meta: BuildMeta,
 }
 
-fn calculate_stats(values: &[f64]) -> (f64, f64, f64, f64) {
-    if values.is_empty() {
-        return (0.0, 0.0, 0.0, 0.0);
-    }
-    let n = values.len() as f64;
-    let mean = values.iter().sum::<f64>() / n;
-    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
-    let std = variance.sqrt();
-    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    (mean, std, min, max)
+/// Rows of the Parquet `runs` table
+#[cfg(feature = "arrow")]
+fn run_records(iterations: &[IterationResult]) -> Vec<arrow_export::RunRecord> {
+    iterations
+        .iter()
+        .flat_map(|iter| {
+            iter.vehicles.iter().map(|v| arrow_export::RunRecord {
+                iteration: iter.iteration as u64,
+                vehicle_type: v.vehicle_type.clone(),
+                heading: Some(v.heading.to_string()),
+                success: v.success,
+                arrival_time: v.arrival_time,
+                distance_traveled: v.distance_traveled,
+                final_distance: v.final_distance,
+                final_angle_error: v.final_angle_error,
+                initial_x: v.initial_x,
+                initial_y: v.initial_y,
+                initial_angle: v.initial_angle,
+                wall_time: v.wall_time,
+            })
+        })
+        .collect()
 }
 
-fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time: f64) -> VehicleMetrics {
-    let mut sim = Simulation::new(map.clone(), vehicle_type, dt, max_time);
+/// Run one simulation, returning its benchmark metrics and the full result (for plotting)
+fn run_single_simulation<R: Rng>(
+    config: &BenchmarkConfig,
+    map: &Map,
+    vehicle: &VehicleSpec,
+    heading: HeadingDistribution,
+    iteration: usize,
+    rng: &mut R,
+) -> (VehicleMetrics, VehicleResult) {
+    let started = Instant::now();
+    let vehicle_type = vehicle.vehicle_type;
+    let mut sim = config.build_simulation(map, vehicle, heading, iteration, rng);
 
     let initial_x = sim.vehicle.state.position.x;
     let initial_y = sim.vehicle.state.position.y;
-    let initial_angle = sim.vehicle.state.angle.to_degrees();
+    let initial_angle = sim.vehicle.state.angle.to_degrees().0;
+    let initial_condition = InitialCondition::new(&sim.vehicle.state.position, sim.vehicle.state.angle, &sim.map.target.position);
 
     // Run simulation
-    while sim.time < max_time && !sim.vehicle.has_arrived {
+    while !sim.is_finished() {
         sim.step();
     }
 
@@ -89,7 +325,7 @@ fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time
 
     let final_point = sim.trajectory.last().unwrap();
     let final_distance = final_point.distance_to_target;
-    let final_angle_error = (90.0 - final_point.angle).abs();
+    let final_angle_error = (Degrees(90.0) - final_point.angle).abs().0;
 
     // Calculate distance traveled
     let mut distance_traveled = 0.0;
@@ -101,183 +337,1239 @@ fn run_single_simulation(map: &Map, vehicle_type: VehicleType, dt: f64, max_time
         distance_traveled += (dx * dx + dy * dy).sqrt();
     }
 
-    VehicleMetrics {
-        vehicle_type: vehicle_type.name().to_string(),
+    let sim_metrics = sim.metrics();
+    let metrics = VehicleMetrics {
+        vehicle_type: vehicle_type.id().to_string(),
+        heading,
         success,
         arrival_time,
+        degenerate_start: sim_metrics.degenerate_start,
         distance_traveled,
         final_distance,
         final_angle_error,
+        steering_reversals_per_minute: sim_metrics.steering_reversals_per_minute,
+        heading_jerk: sim_metrics.heading_jerk,
+        avg_velocity: sim_metrics.avg_velocity,
+        time_above_high_velocity: sim_metrics.time_above_high_velocity,
+        arrival_velocity: sim_metrics.arrival_velocity,
+        acceleration_reversals: sim_metrics.acceleration_reversals,
+        max_approach_bias_deg: sim_metrics.max_approach_bias_deg,
+        mean_approach_bias_deg_inside_corridor: sim_metrics.mean_approach_bias_deg_inside_corridor,
+        optimal_time_lower_bound: sim_metrics.optimal_time_lower_bound,
+        time_optimality_ratio: sim_metrics.time_optimality_ratio,
+        transit_time: sim_metrics.transit_time,
+        alignment_time: sim_metrics.alignment_time,
+        first_aligned_time: sim_metrics.first_aligned_time,
+        alignment_attempts: sim_metrics.alignment_attempts,
+        cache_hit_rate: sim.controller.cache_stats().map(|stats| stats.hit_rate()),
         initial_x,
         initial_y,
         initial_angle,
+        initial_condition,
+        is_outlier: MetricOutliers::default(),
+        wall_time: started.elapsed().as_secs_f64(),
+    };
+
+    let result = VehicleResult {
+        id: sim.id,
+        vehicle_type: vehicle_type.id().to_string(),
+        metrics: sim_metrics,
+        trajectory: sim.trajectory,
+        target: Some(sim.map.target.position),
+    };
+
+    (metrics, result)
+}
+
+/// Statistics of the runs of one (heading, vehicle type) group, or of one of its buckets
+///
+/// Degenerate starts count as successes, their arrival at t=0 only enters
+/// the arrival times with `include_degenerate_arrivals`.
+fn aggregate(
+    vehicle_type: &str,
+    heading: HeadingDistribution,
+    bucket: Option<InitialConditionBucket>,
+    metrics: &[&VehicleMetrics],
+    config: &BenchmarkConfig,
+    bootstrap_seed: u64,
+) -> AggregateStats {
+    let confidence = config.confidence;
+    let total_runs = metrics.len();
+    let successes = metrics.iter().filter(|m| m.success).count();
+    let degenerate_starts = metrics.iter().filter(|m| m.degenerate_start).count();
+    let success_rate = successes as f64 / total_runs as f64 * 100.0;
+    let (success_ci_low, success_ci_high) = stats::wilson_interval(successes, total_runs, confidence);
+
+    let arrival_times: Vec<f64> = metrics.iter()
+        .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+        .filter_map(|m| m.arrival_time)
+        .collect();
+    let (avg_time, std_time, min_time, max_time) = calculate_stats(&arrival_times);
+    let (time_ci_low, time_ci_high) =
+        stats::bootstrap_mean_interval(&arrival_times, confidence, BOOTSTRAP_RESAMPLES, bootstrap_seed);
+
+    let distances: Vec<f64> = metrics.iter().map(|m| m.distance_traveled).collect();
+    let (avg_dist, std_dist, _, _) = calculate_stats(&distances);
+
+    // Only the overall statistics hold one run per iteration, in iteration order
+    let outlier_runs = match bucket {
+        None => {
+            let flags: Vec<MetricOutliers> = metrics.iter().map(|m| m.is_outlier).collect();
+            OutlierRun::list(&flags, |i| config.iteration_seed(i))
+        }
+        Some(_) => Vec::new(),
+    };
+
+    let final_dists: Vec<f64> = metrics.iter().map(|m| m.final_distance).collect();
+    let (avg_final_dist, _, _, _) = calculate_stats(&final_dists);
+
+    let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
+    let (avg_angle_error, _, _, _) = calculate_stats(&angle_errors);
+
+    let reversals: Vec<f64> = metrics.iter().map(|m| m.steering_reversals_per_minute).collect();
+    let (avg_reversals, _, _, _) = calculate_stats(&reversals);
+
+    let jerks: Vec<f64> = metrics.iter().map(|m| m.heading_jerk).collect();
+    let (avg_jerk, _, _, _) = calculate_stats(&jerks);
+
+    let attempts: Vec<f64> = metrics.iter().map(|m| m.alignment_attempts as f64).collect();
+    let (avg_attempts, _, _, _) = calculate_stats(&attempts);
+
+    let wall_times: Vec<f64> = metrics.iter().map(|m| m.wall_time).collect();
+    let (avg_wall_time, _, _, _) = calculate_stats(&wall_times);
+
+    AggregateStats {
+        vehicle_type: vehicle_type.to_string(),
+        heading,
+        bucket,
+        total_runs,
+        successes,
+        degenerate_starts,
+        success_rate,
+        success_rate_ci_low: success_ci_low * 100.0,
+        success_rate_ci_high: success_ci_high * 100.0,
+        avg_arrival_time: avg_time,
+        avg_arrival_time_ci_low: time_ci_low,
+        avg_arrival_time_ci_high: time_ci_high,
+        std_arrival_time: std_time,
+        min_arrival_time: min_time,
+        max_arrival_time: max_time,
+        avg_distance_traveled: avg_dist,
+        std_distance_traveled: std_dist,
+        trimmed_arrival_time: TrimmedStats::new(&arrival_times),
+        trimmed_distance_traveled: TrimmedStats::new(&distances),
+        avg_final_distance: avg_final_dist,
+        avg_final_angle_error: avg_angle_error,
+        avg_steering_reversals_per_minute: avg_reversals,
+        avg_heading_jerk: avg_jerk,
+        avg_velocity: mean_of_present(metrics.iter().map(|m| m.avg_velocity)),
+        avg_time_above_high_velocity: mean_of_present(metrics.iter().map(|m| m.time_above_high_velocity)),
+        avg_arrival_velocity: mean_of_present(metrics.iter().map(|m| m.arrival_velocity)),
+        avg_acceleration_reversals: mean_of_present(metrics.iter().map(|m| m.acceleration_reversals.map(|n| n as f64))),
+        avg_cache_hit_rate: mean_of_present(metrics.iter().map(|m| m.cache_hit_rate)),
+        avg_max_approach_bias_deg: mean_of_present(metrics.iter().map(|m| m.max_approach_bias_deg)),
+        avg_max_approach_bias_deg_failed: mean_of_present(metrics.iter().filter(|m| !m.success).map(|m| m.max_approach_bias_deg)),
+        avg_approach_bias_deg_inside_corridor: mean_of_present(metrics.iter().map(|m| m.mean_approach_bias_deg_inside_corridor)),
+        avg_time_optimality_ratio: mean_of_present(
+            metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.time_optimality_ratio),
+        ),
+        avg_transit_time: mean_of_present(metrics.iter().map(|m| m.transit_time)),
+        avg_alignment_time: mean_of_present(
+            metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.alignment_time),
+        ),
+        avg_first_aligned_time: mean_of_present(metrics.iter().map(|m| m.first_aligned_time)),
+        avg_alignment_attempts: avg_attempts,
+        arrival_funnel: config.arrival_funnel_bins.filter(|_| bucket.is_none()).map(|bins| {
+            let arrival_times: Vec<Option<f64>> = metrics.iter()
+                .filter(|m| config.include_degenerate_arrivals || !m.degenerate_start)
+                .map(|m| m.arrival_time)
+                .collect();
+            arrival_funnel(&arrival_times, config.max_time, bins)
+        }),
+        outlier_runs,
+        avg_wall_time,
+        p95_wall_time: percentile(&wall_times, 0.95),
+    }
+}
+
+/// Arrival region of the criteria, "25 units" or "60x25 units ellipse"
+fn arrival_region(criteria: &ArrivalCriteria) -> String {
+    match criteria.gate {
+        ArrivalGate::Circle => format!("{} units", criteria.distance_threshold),
+        ArrivalGate::Ellipse { along, across } => format!("{}x{} units ellipse", along, across),
+    }
+}
+
+/// Vehicle run of the run database, with its trajectory when `keep_trajectory`
+#[cfg(feature = "storage")]
+fn stored_run(iteration: usize, metrics: &VehicleMetrics, result: &VehicleResult, keep_trajectory: bool) -> StoredRun {
+    StoredRun {
+        iteration: iteration as u64,
+        vehicle_id: result.id.clone(),
+        vehicle_type: metrics.vehicle_type.clone(),
+        heading: Some(metrics.heading.to_string()),
+        initial_x: metrics.initial_x,
+        initial_y: metrics.initial_y,
+        initial_angle: metrics.initial_angle,
+        success: metrics.success,
+        arrival_time: metrics.arrival_time,
+        degenerate_start: metrics.degenerate_start,
+        distance_traveled: metrics.distance_traveled,
+        final_distance: metrics.final_distance,
+        final_angle_error: metrics.final_angle_error,
+        steering_reversals_per_minute: metrics.steering_reversals_per_minute,
+        heading_jerk: metrics.heading_jerk,
+        time_optimality_ratio: metrics.time_optimality_ratio,
+        wall_time: Some(metrics.wall_time),
+        trajectory: if keep_trajectory { result.trajectory.clone() } else { Vec::new() },
+    }
+}
+
+/// UTC date and time of unix seconds, "2026-10-17 08:30"
+fn utc_timestamp(seconds: i64) -> String {
+    // Civil date of a day count, see https://howardhinnant.github.io/date_algorithms.html
+    let days = seconds.div_euclid(86_400);
+    let minutes = seconds.rem_euclid(86_400) / 60;
+    let era_day = days + 719_468;
+    let era = era_day.div_euclid(146_097);
+    let day_of_era = era_day.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+}
+
+/// --robustness: success rate and final angle error of every vehicle type alone at each disturbance level, without the benchmark
+fn run_robustness(
+    scenario: &Scenario,
+    axis: RobustnessAxis,
+    levels: &[f64],
+    iterations: usize,
+    seed: u64,
+    plot_format: Option<ImageFormat>,
+) {
+    let mut vehicle_types: Vec<VehicleType> = Vec::new();
+    for vehicle in &scenario.vehicles {
+        if !vehicle_types.contains(&vehicle.vehicle_type) {
+            vehicle_types.push(vehicle.vehicle_type);
+        }
+    }
+    println!("\nRobustness sweep: {} at {:?}, {} runs per level, seed {}", axis, levels, iterations, seed);
+
+    let report = robustness_report(scenario, &vehicle_types, axis, levels, iterations, seed).unwrap_or_else(|e| {
+        eprintln!("\n❌ Error: Barrido de robustez inválido: {}", e);
+        std::process::exit(1);
+    });
+
+    for curve in &report.curves {
+        println!("\n{}", display_label(&curve.vehicle_type, Language::Spanish));
+        println!("  {:>10} {:>12} {:>18}", axis.id(), "success %", "final angle err°");
+        for level in &curve.levels {
+            println!("  {:>10} {:>11.1}% {:>18.2}", level.level, level.success_rate, level.mean_final_angle_error);
+        }
+    }
+
+    fs::create_dir_all("output").expect("Failed to create output directory");
+    let json_filename = format!("output/robustness_{}.json", axis);
+    let json = serde_json::to_string_pretty(&report).expect("Failed to serialize robustness report");
+    fs::write(&json_filename, json).expect("Failed to write robustness report");
+    println!("\nResults saved to:\n  - {}", json_filename);
+    let record = |path: &str, kind| artifacts::record(ArtifactEntry::new(path, kind).config(&report).seed(seed));
+    record(&json_filename, ArtifactKind::Report);
+
+    let plot_filename = format!("output/robustness_{}.{}", axis, plot_format.unwrap_or(ImageFormat::Png).extension());
+    match trajectory_export::export_robustness_plot(&report, &plot_filename) {
+        Ok(()) => {
+            println!("  - {} (success rate per level and vehicle type)", plot_filename);
+            record(&plot_filename, ArtifactKind::Plot);
+        }
+        Err(e) => eprintln!("Warning: could not export robustness plot: {}", e),
+    }
+}
+
+/// --list and --show: print the stored runs, or the statistics of one, without running
+#[cfg(feature = "storage")]
+fn query_store(path: &str, show_run: Option<&str>) {
+    let store = RunStore::open(path).unwrap_or_else(|e| {
+        eprintln!("\n❌ Error: No se pudo abrir la base de corridas '{}': {}", path, e);
+        std::process::exit(1);
+    });
+    let fail = |e: examen_parcial::error::FuzzyNavError| -> ! {
+        eprintln!("\n❌ Error: No se pudo leer la base de corridas '{}': {}", path, e);
+        std::process::exit(1);
+    };
+
+    let Some(id) = show_run else {
+        let runs = store.list_runs().unwrap_or_else(|e| fail(e));
+        println!("Runs in {}:", path);
+        for run in &runs {
+            println!("  #{:<4} {:<10} {} UTC  v{}  {} iterations  {}  {}/{} arrived",
+                run.id, run.kind.as_str(), utc_timestamp(run.created_at), run.crate_version,
+                run.iterations, run.vehicle_types.join(","), run.successes, run.total_runs);
+        }
+        if runs.is_empty() {
+            println!("  (none)");
+        }
+        return;
+    };
+
+    let id: i64 = id.parse().unwrap_or_else(|_| {
+        eprintln!("\n❌ Error: --show espera el número de una corrida, recibido '{}'", id);
+        std::process::exit(1);
+    });
+    let Some(details) = store.get_run(id).unwrap_or_else(|e| fail(e)) else {
+        eprintln!("\n❌ Error: No hay una corrida #{} en '{}' (vea --list)", id, path);
+        std::process::exit(1);
+    };
+    let summary = &details.summary;
+    println!("Run #{}: {} of {} UTC, crate v{}", summary.id, summary.kind.as_str(), utc_timestamp(summary.created_at), summary.crate_version);
+    if let Ok(Some(config)) = details.benchmark_config() {
+        let headings: Vec<String> = config.headings.iter().map(|h| h.to_string()).collect();
+        println!("  Iterations: {}, seed {}, dt {}s, max_time {}s", config.iterations, config.seed, config.dt, config.max_time);
+        println!("  Start headings: {}, arrival criteria ±{}°, {}",
+            headings.join(", "), config.criteria.angle_tolerance, arrival_region(&config.criteria));
+    }
+    let trajectories = details.runs.iter().filter(|run| !run.trajectory.is_empty()).count();
+    println!("  Vehicle runs: {} ({} with trajectory)\n", details.runs.len(), trajectories);
+
+    let filter = RunFilter { run_ids: vec![id], ..Default::default() };
+    for stat in store.aggregate_across_runs(&filter).unwrap_or_else(|e| fail(e)) {
+        println!("{} ({}):", stat.vehicle_type, stat.heading.as_deref().unwrap_or("-"));
+        println!("  Success Rate: {:.1}% ({}/{})", stat.success_rate, stat.successes, stat.total_runs);
+        println!("  Arrival Time: {:.2}s avg (std: {:.2}, min: {:.2}, max: {:.2})",
+            stat.avg_arrival_time, stat.std_arrival_time, stat.min_arrival_time, stat.max_arrival_time);
+        println!("  Distance Traveled: {:.2} avg (std: {:.2})", stat.avg_distance_traveled, stat.std_distance_traveled);
+    }
+}
+
+/// --list-artifacts: print the files of output/manifest.json, of one kind or all, without running
+fn list_artifacts(kind: Option<ArtifactKind>) {
+    let manifest = Manifest::load_or_create("output").unwrap_or_else(|e| {
+        eprintln!("\n❌ Error: No se pudo leer output/{}: {}", artifacts::MANIFEST_FILE, e);
+        std::process::exit(1);
+    });
+    println!("Artifacts in output/{}{}:", artifacts::MANIFEST_FILE, kind.map(|kind| format!(" ({})", kind)).unwrap_or_default());
+    let mut listed = 0;
+    for artifact in manifest.filtered(kind) {
+        println!("  {:<48} {:<10} {} UTC  v{}", artifact.path, artifact.kind, utc_timestamp(artifact.created_at as i64), artifact.crate_version);
+        println!("      {} {}", artifact.producer, artifact.args.join(" "));
+        if let Some(hash) = &artifact.config_hash {
+            println!("      config {}{}", hash, artifact.seed.map(|seed| format!(", seed {}", seed)).unwrap_or_default());
+        }
+        listed += 1;
+    }
+    if listed == 0 {
+        println!("  (none)");
     }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let num_iterations: usize = args.get(1)
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(30);
+    if let Err(e) = logging::init_cli(logging::log_level_arg(&args)) {
+        eprintln!("\n❌ Error: --log-level inválido: {}", e);
+        std::process::exit(1);
+    }
+
+    // --list-artifacts[=KIND] prints the manifest of the output directory and exits
+    if let Some(kind) = args.iter().find_map(|arg| arg.strip_prefix("--list-artifacts")) {
+        let kind = kind.strip_prefix('=').map(|name| {
+            ArtifactKind::parse(name).unwrap_or_else(|| {
+                let kinds: Vec<&str> = ArtifactKind::ALL.iter().map(|kind| kind.id()).collect();
+                eprintln!("\n❌ Error: Tipo de artefacto desconocido '{}' (use {})", name, kinds.join(", "));
+                std::process::exit(1);
+            })
+        });
+        list_artifacts(kind);
+        return;
+    }
+    // The values of `--heading 45`, `--start NAME`, `--disable-rules 0,3`, `--distance-buckets 700`, `--heading-buckets 90`, `--map NAME`,
+    // `--scenario FILE`, `--store FILE`, `--show ID` and `--robustness dropout=0,5` are not iteration counts
+    let requested_iterations: Option<usize> = args.iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(i, arg)| !arg.starts_with("--") && !["--heading", "--start", "--disable-rules", "--distance-buckets", "--heading-buckets", "--map", "--scenario", "--store", "--show", "--robustness"].contains(&args[i - 1].as_str()))
+        .find_map(|(_, s)| s.parse().ok());
+
+    // --duration=60 runs iterations until 60 s have passed instead of a fixed count, iteration i still uses seed + i
+    let duration_seconds = args.iter().find_map(|arg| arg.strip_prefix("--duration=")).map(|value| {
+        value.parse::<f64>().ok().filter(|seconds| *seconds > 0.0 && seconds.is_finite()).unwrap_or_else(|| {
+            eprintln!("\n❌ Error: --duration debe ser un número de segundos positivo, recibido '{}'", value);
+            std::process::exit(1);
+        })
+    });
+    // --converge stops before the iteration count once the estimates of every group are precise enough, checked after
+    // every batch: --converge=2,0.5 for a ±2 point success rate interval and a 0.5 s standard error of the mean arrival
+    // time, --converge=2,0.5,100,3 also sets the batch size and the batches run before stopping
+    let convergence = args.iter().find_map(|arg| match arg.as_str() {
+        "--converge" => Some(Convergence::default()),
+        _ => arg.strip_prefix("--converge=").map(|values| {
+            let values: Vec<f64> = values.split(',').filter_map(|value| value.trim().parse().ok()).collect();
+            let defaults = Convergence::default();
+            let count = |value: Option<&f64>, default: usize| value.map_or(default, |&value| value as usize);
+            match values[..] {
+                [success_rate_half_width, arrival_time_std_error, ref rest @ ..] if rest.len() <= 2 => Convergence {
+                    success_rate_half_width,
+                    arrival_time_std_error,
+                    batch_size: count(rest.first(), defaults.batch_size),
+                    min_batches: count(rest.get(1), defaults.min_batches),
+                },
+                _ => {
+                    eprintln!("\n❌ Error: --converge espera HALF_WIDTH,STD_ERROR[,BATCH[,MIN_BATCHES]], recibido '{}'", arg);
+                    std::process::exit(1);
+                }
+            }
+        }),
+    });
+    // A count given together with --duration is refused by the validation below
+    let num_iterations = match duration_seconds {
+        Some(_) => requested_iterations.unwrap_or(0),
+        None => requested_iterations.unwrap_or(30),
+    };
+
+    // --rerun results.json repeats the run recorded in an earlier output, ignoring the other run options
+    let rerun = args.iter()
+        .position(|arg| arg == "--rerun")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--rerun=")));
+
+    // --format parquet writes the raw runs and every trajectory point as Parquet instead of JSON/CSV,
+    // --format xlsx the statistics as an Excel workbook next to them
+    let format = args.iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--format=")))
+        .unwrap_or("csv");
+    let (parquet, xlsx) = match format {
+        "csv" => (false, false),
+        "parquet" => (true, false),
+        "xlsx" => (false, true),
+        other => {
+            eprintln!("\n❌ Error: Formato desconocido '{}' (use csv, parquet o xlsx)", other);
+            std::process::exit(1);
+        }
+    };
+    if parquet && cfg!(not(feature = "arrow")) {
+        eprintln!("\n❌ Error: --format parquet requiere compilar con --features arrow");
+        std::process::exit(1);
+    }
+    if xlsx && cfg!(not(feature = "xlsx")) {
+        eprintln!("\n❌ Error: --format xlsx requiere compilar con --features xlsx");
+        std::process::exit(1);
+    }
+
+    // --store runs.db adds the run to a SQLite database, --list and --show ID read it instead of running
+    let store_path = args.iter()
+        .position(|arg| arg == "--store")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--store=")));
+    let store_trajectories = args.iter().any(|arg| arg == "--store-trajectories");
+    let show_run = args.iter()
+        .position(|arg| arg == "--show")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--show=")));
+    let list_runs = args.iter().any(|arg| arg == "--list");
+    if (store_trajectories || list_runs || show_run.is_some()) && store_path.is_none() {
+        eprintln!("\n❌ Error: --store-trajectories, --list y --show requieren --store FILE");
+        std::process::exit(1);
+    }
+    if store_path.is_some() && cfg!(not(feature = "storage")) {
+        eprintln!("\n❌ Error: --store requiere compilar con --features storage");
+        std::process::exit(1);
+    }
+    #[cfg(feature = "storage")]
+    if let (Some(path), true) = (store_path, list_runs || show_run.is_some()) {
+        query_store(path, show_run);
+        return;
+    }
+
+    // --confidence=0.9 sets the level of the success rate and arrival time intervals
+    let confidence = match args.iter().find_map(|arg| arg.strip_prefix("--confidence=")) {
+        Some(value) => match value.parse::<f64>() {
+            Ok(level) if stats::is_valid_confidence(level) => level,
+            _ => {
+                eprintln!("\n❌ Error: --confidence debe estar entre 0 y 1, recibido '{}'", value);
+                std::process::exit(1);
+            }
+        },
+        None => stats::DEFAULT_CONFIDENCE,
+    };
+
+    // --criteria loose judges arrival by a looser tolerance profile than the exam ±2°
+    let criteria_name = args.iter()
+        .position(|arg| arg == "--criteria")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--criteria=")))
+        .unwrap_or("strict");
+    let criteria = ArrivalCriteria::parse(criteria_name).unwrap_or_else(|| {
+        eprintln!("\n❌ Error: Criterio de llegada desconocido '{}' (use strict, normal o loose)", criteria_name);
+        std::process::exit(1);
+    });
+    let criteria = match args.iter().find_map(|arg| arg.strip_prefix("--hold-steps=")) {
+        Some(value) => criteria.with_hold_steps(value.parse::<usize>().ok().filter(|&steps| steps > 0).unwrap_or_else(|| {
+            eprintln!("\n❌ Error: --hold-steps debe ser un entero positivo, recibido '{}'", value);
+            std::process::exit(1);
+        })),
+        None => criteria,
+    };
+    // --gate=60,25 accepts arrival inside an ellipse of semi-axes 60 along the required angle and 25 across it
+    let criteria = match args.iter().find_map(|arg| arg.strip_prefix("--gate=")) {
+        Some(value) => {
+            let axes: Vec<f64> = value.split(',').filter_map(|axis| axis.trim().parse().ok()).collect();
+            match axes[..] {
+                [along, across] => criteria.with_gate(ArrivalGate::Ellipse { along, across }),
+                _ => {
+                    eprintln!("\n❌ Error: --gate espera dos semiejes ALONG,ACROSS, recibido '{}'", value);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => criteria,
+    };
+
+    // --heading away starts every vehicle facing away from the target, a comma list runs a sweep
+    let heading_names = args.iter()
+        .position(|arg| arg == "--heading")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--heading=")))
+        .unwrap_or("toward");
+    let headings: Vec<HeadingDistribution> = heading_names
+        .split(',')
+        .map(|name| {
+            HeadingDistribution::parse(name.trim()).unwrap_or_else(|| {
+                eprintln!("\n❌ Error: Rumbo inicial desconocido '{}' (use toward, uniform, away o grados)", name);
+                std::process::exit(1);
+            })
+        })
+        .collect();
+
+    // --start gaussian:0.15 draws the start x around the middle of the width, stratified:10 spreads the iterations over 10 x-bins
+    let start_name = args.iter()
+        .position(|arg| arg == "--start")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--start=")))
+        .unwrap_or("uniform");
+    let start_distribution = StartDistribution::parse(start_name).unwrap_or_else(|| {
+        eprintln!("\n❌ Error: Distribución de inicio desconocida '{}' (use uniform, gaussian[:SIGMA] o stratified[:BINS])", start_name);
+        std::process::exit(1);
+    });
+
+    // --disable-rules 0,3,6 skips those controller rules (0-based, rule number - 1) in every run, for ablation studies
+    let disabled_rules: Option<Vec<usize>> = args.iter()
+        .position(|arg| arg == "--disable-rules")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--disable-rules=")))
+        .map(|list| {
+            list.split(',')
+                .map(|index| {
+                    index.trim().parse().unwrap_or_else(|_| {
+                        eprintln!("\n❌ Error: Índice de regla inválido '{}' (use enteros desde 0 separados por comas)", index);
+                        std::process::exit(1);
+                    })
+                })
+                .collect()
+        });
+
+    // --cache memoizes the controller on the default input grid, --cache=2,1,0.02 on a coarser one (distance, degrees, velocity)
+    let control_cache = args.iter().find_map(|arg| match arg.as_str() {
+        "--cache" => Some(Quantization::default()),
+        _ => arg.strip_prefix("--cache=").map(|steps| {
+            let steps: Vec<f64> = steps.split(',').filter_map(|step| step.trim().parse().ok()).collect();
+            match steps[..] {
+                [distance, angle_degrees, velocity] => Quantization { distance, angle_degrees, velocity },
+                _ => {
+                    eprintln!("\n❌ Error: --cache espera tres pasos DIST,GRADOS,VEL, recibido '{}'", arg);
+                    std::process::exit(1);
+                }
+            }
+        }),
+    });
+
+    // --distance-buckets 400,700 also reports the runs starting below 400, 400 to 700 and beyond 700 units from the target,
+    // --heading-buckets 45,90 by the angle between the start heading and the target bearing; both together cross them
+    let bucket_edges = |flag: &str| -> Vec<f64> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1).map(String::as_str))
+            .or_else(|| args.iter().find_map(|arg| arg.strip_prefix(flag)?.strip_prefix('=')))
+            .map(|list| {
+                list.split(',')
+                    .map(|edge| {
+                        edge.trim().parse().unwrap_or_else(|_| {
+                            eprintln!("\n❌ Error: Límite de grupo inválido '{}' en {} (use números separados por comas)", edge, flag);
+                            std::process::exit(1);
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let buckets = InitialConditionBuckets {
+        distance_edges: bucket_edges("--distance-buckets"),
+        heading_error_edges: bucket_edges("--heading-buckets"),
+    };
+    let buckets = (buckets != InitialConditionBuckets::default()).then_some(buckets);
+
+    // --arrival-funnel reports the fraction of runs arrived over time in 60 bins up to max_time and plots it, --arrival-funnel=120 in finer ones
+    let arrival_funnel_bins = args.iter().find_map(|arg| match arg.as_str() {
+        "--arrival-funnel" => Some(DEFAULT_ARRIVAL_FUNNEL_BINS),
+        _ => arg.strip_prefix("--arrival-funnel=").map(|value| {
+            value.parse::<usize>().ok().filter(|bins| *bins > 0).unwrap_or_else(|| {
+                eprintln!("\n❌ Error: --arrival-funnel debe ser un número de intervalos positivo, recibido '{}'", value);
+                std::process::exit(1);
+            })
+        }),
+    });
+
+    // --reroll-degenerate draws again the random starts that would arrive at t=0,
+    // --include-degenerate keeps the arrivals of those that still do in the arrival times
+    let reroll_degenerate = args.iter().any(|arg| arg == "--reroll-degenerate");
+    let include_degenerate_arrivals = args.iter().any(|arg| arg == "--include-degenerate");
+
+    // --map corner_top_right moves the target to one of the edge placements of MapPreset
+    let map_name = args.iter()
+        .position(|arg| arg == "--map")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--map=")))
+        .unwrap_or("exam");
+    let preset = MapPreset::parse(map_name).unwrap_or_else(|| {
+        let names: Vec<&str> = MapPreset::ALL.iter().map(|preset| preset.name()).collect();
+        eprintln!("\n❌ Error: Mapa desconocido '{}' (use {})", map_name, names.join(", "));
+        std::process::exit(1);
+    });
+    let target = preset.target();
+
+    // --seed=42 fixes the start states, iteration i uses seed + i; a random seed is drawn and recorded otherwise
+    let seed = match args.iter().find_map(|arg| arg.strip_prefix("--seed=")) {
+        Some(value) => value.parse::<u64>().unwrap_or_else(|_| {
+            eprintln!("\n❌ Error: --seed debe ser un entero no negativo, recibido '{}'", value);
+            std::process::exit(1);
+        }),
+        None => rand::random(),
+    };
+
+    // --scenario FILE benchmarks the vehicles, map, timing, heading and criteria of a saved scenario, over --map, --heading and --criteria
+    // (--start still applies, scenarios have no start distribution, and --disable-rules replaces the scenario ones)
+    let scenario: Option<Scenario> = args.iter()
+        .position(|arg| arg == "--scenario")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--scenario=")))
+        .map(|path| {
+            let json = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: No se pudo leer '{}': {}", path, e);
+                std::process::exit(1);
+            });
+            serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: '{}' no es un escenario válido: {}", path, e);
+                std::process::exit(1);
+            })
+        });
+
+    // --plot exports the trajectories of the first iteration, --plot=svg as a vector figure
+    let plot_format = args.iter().find_map(|arg| match arg.as_str() {
+        "--plot" => Some(ImageFormat::Png),
+        _ => arg.strip_prefix("--plot=").and_then(ImageFormat::parse),
+    });
+
+    // --robustness dropout=0,2,5,10 sweeps a disturbance instead of running the benchmark: every vehicle type alone,
+    // the iteration count of runs per level, over --scenario or the --map, --heading and --criteria of a benchmark
+    let robustness = args.iter()
+        .position(|arg| arg == "--robustness")
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix("--robustness=")));
+    if let Some(sweep) = robustness {
+        let parsed = sweep.split_once('=').and_then(|(axis, levels)| {
+            let levels: Option<Vec<f64>> = levels.split(',').map(|level| level.trim().parse().ok()).collect();
+            Some((RobustnessAxis::parse(axis.trim())?, levels?))
+        });
+        let Some((axis, levels)) = parsed else {
+            let axes: Vec<&str> = RobustnessAxis::ALL.iter().map(|axis| axis.id()).collect();
+            eprintln!("\n❌ Error: --robustness espera EJE=NIVEL[,..] con eje {}, recibido '{}'", axes.join(", "), sweep);
+            std::process::exit(1);
+        };
+        let scenario = scenario.clone().unwrap_or_else(|| Scenario {
+            target_x: target.x,
+            target_y: target.y,
+            criteria,
+            heading: headings[0],
+            ..Scenario::default_exam()
+        });
+        run_robustness(&scenario, axis, &levels, num_iterations, scenario.seed.unwrap_or(seed), plot_format);
+        return;
+    }
+
+    let config = match (rerun, &scenario) {
+        (Some(path), _) => {
+            let json = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: No se pudo leer '{}': {}", path, e);
+                std::process::exit(1);
+            });
+            let config = BenchmarkConfig::from_results_json(&json).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: '{}' no contiene una configuración de benchmark: {}", path, e);
+                std::process::exit(1);
+            });
+            if let Some(warning) = config.version_warning() {
+                eprintln!("\n⚠️  {}", warning);
+            }
+            config
+        }
+        (None, Some(scenario)) => BenchmarkConfig {
+            seed: scenario.seed.unwrap_or(seed),
+            threads: Some(1),
+            confidence,
+            start_distribution,
+            disabled_rules: disabled_rules.unwrap_or_else(|| scenario.disabled_rules.clone()),
+            control_cache,
+            buckets,
+            arrival_funnel_bins,
+            reroll_degenerate,
+            include_degenerate_arrivals,
+            duration_seconds,
+            convergence,
+            ..BenchmarkConfig::from_scenario(scenario, num_iterations)
+        },
+        (None, None) => BenchmarkConfig {
+            iterations: num_iterations,
+            duration_seconds,
+            convergence,
+            criteria,
+            headings,
+            start_distribution,
+            disabled_rules: disabled_rules.unwrap_or_default(),
+            control_cache,
+            buckets,
+            arrival_funnel_bins,
+            reroll_degenerate,
+            include_degenerate_arrivals,
+            target_x: target.x,
+            target_y: target.y,
+            seed,
+            // Runs are sequential
+            threads: Some(1),
+            confidence,
+            ..Default::default()
+        },
+    };
+    if let Err(e) = config.validate() {
+        eprintln!("\n❌ Error: Configuración inválida: {}", e);
+        std::process::exit(1);
+    }
+
+    // A few seconds of every vehicle from fixed starts catch a controller that cannot steer, --force runs anyway
+    let preflight = config.preflight().expect("validated configuration");
+    if !preflight.passed() {
+        eprintln!("\n❌ Error: El controlador no pasó el preflight:\n{}", preflight);
+        if !args.iter().any(|arg| arg == "--force") {
+            eprintln!("   Use --force para correr el benchmark de todos modos");
+            std::process::exit(1);
+        }
+        eprintln!("   --force: se corre de todos modos");
+    }
+    let confidence = config.confidence;
+    // Output files of a budgeted run are named after the budget, the iteration count is only known at the end
+    let run_label = match config.duration_seconds {
+        Some(seconds) => format!("{}s", seconds),
+        None => format!("{}iterations", config.iterations),
+    };
+
+    // --mean-path exports the mean path of every group on a 1 s grid, --mean-path=0.5 on a finer one
+    let mean_path_grid_dt = args.iter().find_map(|arg| match arg.as_str() {
+        "--mean-path" => Some(DEFAULT_MEAN_PATH_GRID_DT),
+        _ => arg.strip_prefix("--mean-path=").map(|value| {
+            value.parse::<f64>().ok().filter(|dt| *dt > 0.0 && dt.is_finite()).unwrap_or_else(|| {
+                eprintln!("\n❌ Error: --mean-path debe ser un paso de tiempo positivo, recibido '{}'", value);
+                std::process::exit(1);
+            })
+        }),
+    });
 
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║   FUZZY NAVIGATION BENCHMARK                         ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
 
-    let map = Map::new(1000.0, 800.0, 500.0, 700.0);
-    let dt = 0.05;
-    let max_time = 600.0;
-
-    let vehicle_types = vec![
-        VehicleType::Heavy,
-        VehicleType::Standard,
-        VehicleType::Agile,
-    ];
+    let map = config.map().expect("validated map");
+    let vehicle_names: Vec<String> = config.vehicle_types().iter().map(|v| format!("{:?}", v)).collect();
+    let groups = config.groups();
 
     println!("Configuration:");
-    println!("  Iterations: {}", num_iterations);
-    println!("  Vehicles: Heavy, Standard, Agile");
-    println!("  dt: {}s, max_time: {}s", dt, max_time);
-    println!("  Target: (500, 700) @ 90 deg\n");
+    if let Some(path) = rerun {
+        println!("  Rerun of: {}", path);
+    }
+    match config.duration_seconds {
+        Some(seconds) => println!("  Duration budget: {}s", seconds),
+        None => println!("  Iterations: {}", config.iterations),
+    }
+    if let Some(convergence) = &config.convergence {
+        println!("  Early stopping: every {} iterations from batch {}, success rate ±{} points, arrival time std error {}s",
+            convergence.batch_size, convergence.min_batches, convergence.success_rate_half_width, convergence.arrival_time_std_error);
+    }
+    println!("  Vehicles: {}", vehicle_names.join(", "));
+    println!("  dt: {}s, max_time: {}s", config.dt, config.max_time);
+    println!("  Seed: {}", config.seed);
+    println!("  Confidence intervals: {:.0}%", confidence * 100.0);
+    let heading_labels: Vec<String> = config.headings.iter().map(|h| h.to_string()).collect();
+    println!("  Start headings: {}", heading_labels.join(", "));
+    println!("  Start positions: {}", config.start_distribution);
+    if !config.disabled_rules.is_empty() {
+        let indices: Vec<String> = config.disabled_rules.iter().map(|index| index.to_string()).collect();
+        println!("  Disabled rules: {}", indices.join(", "));
+    }
+    if let Some(quantization) = &config.control_cache {
+        println!("  Control cache: {} units, {}°, {} velocity steps",
+            quantization.distance, quantization.angle_degrees, quantization.velocity);
+    }
+    if let Some(buckets) = &config.buckets {
+        let bucket_labels: Vec<String> = buckets.buckets().iter().map(|bucket| bucket.to_string()).collect();
+        println!("  Initial condition buckets: {}", bucket_labels.join("; "));
+    }
+    if config.reroll_degenerate {
+        println!("  Degenerate starts: drawn again");
+    }
+    println!("  Arrival criteria: ±{}°, {}", config.criteria.angle_tolerance, arrival_region(&config.criteria));
+    if config.criteria.hold_steps() > 1 {
+        println!("  Arrival held for: {} steps", config.criteria.hold_steps());
+    }
+    println!("  Target: ({}, {}) @ 90 deg\n", config.target_x, config.target_y);
 
     let mut all_iterations: Vec<IterationResult> = Vec::new();
-    let mut all_metrics: Vec<Vec<VehicleMetrics>> = vec![Vec::new(); vehicle_types.len()];
+    let mut all_metrics: Vec<Vec<VehicleMetrics>> = vec![Vec::new(); groups.len()];
+    let mut first_iteration: Vec<VehicleResult> = Vec::new();
+    // Runs already on the mean path grid, far fewer points than the recorded ones
+    let mut mean_path_runs: Vec<Vec<Vec<TrajectoryPoint>>> = vec![Vec::new(); groups.len()];
+    #[cfg(feature = "storage")]
+    let mut stored_runs: Vec<StoredRun> = Vec::new();
 
-    for i in 0..num_iterations {
-        print!("\rRunning iteration {}/{}...", i + 1, num_iterations);
+    fs::create_dir_all("output").expect("Failed to create output directory");
+
+    // Trajectories are streamed one iteration at a time, they do not fit in memory for large sweeps
+    #[cfg(feature = "arrow")]
+    let trajectories_filename = format!("output/benchmark_{}_trajectories.parquet", run_label);
+    #[cfg(feature = "arrow")]
+    let mut trajectory_writer = parquet.then(|| {
+        let file = fs::File::create(&trajectories_filename).expect("Failed to create trajectories Parquet file");
+        arrow_export::parquet_writer(file, arrow_export::trajectories_schema()).expect("Failed to start Parquet writer")
+    });
+
+    let sampler = MemorySampler::start();
+    let started = Instant::now();
+    let mut progress = Progress::new();
+
+    let deadline = config.duration_seconds.map(|seconds| started + Duration::from_secs_f64(seconds));
+    let mut convergence_report: Option<ConvergenceReport> = None;
+    // A budgeted run always completes its first iteration
+    for i in (0..).take_while(|&i| match deadline {
+        Some(deadline) => i == 0 || Instant::now() < deadline,
+        None => i < config.iterations,
+    }) {
+        let iteration_started = Instant::now();
+        let label = match config.duration_seconds {
+            Some(seconds) => format!("Running iteration {} ({:.0}s budget)", i + 1, seconds),
+            None => format!("Running iteration {}/{}", i + 1, config.iterations),
+        };
+        print!("\r{:<60}", progress.render(&label));
         std::io::stdout().flush().unwrap();
 
         let mut iteration_vehicles = Vec::new();
 
-        for (idx, &vtype) in vehicle_types.iter().enumerate() {
-            let metrics = run_single_simulation(&map, vtype, dt, max_time);
-            all_metrics[idx].push(metrics.clone());
-            iteration_vehicles.push(metrics);
+        // Every heading mode replays the same start positions
+        for (mode, &heading) in config.headings.iter().enumerate() {
+            let mut iteration_results = Vec::new();
+            let mut rng = config.iteration_rng(i);
+
+            for (idx, vehicle) in config.vehicles.iter().enumerate() {
+                let (metrics, vehicle_result) = run_single_simulation(&config, &map, vehicle, heading, i, &mut rng);
+                if let Some(grid_dt) = mean_path_grid_dt {
+                    mean_path_runs[mode * config.vehicles.len() + idx].push(resample(&vehicle_result.trajectory, grid_dt));
+                }
+                #[cfg(feature = "storage")]
+                if store_path.is_some() {
+                    stored_runs.push(stored_run(i + 1, &metrics, &vehicle_result, store_trajectories));
+                }
+                iteration_results.push(vehicle_result);
+                all_metrics[mode * config.vehicles.len() + idx].push(metrics.clone());
+                iteration_vehicles.push(metrics);
+            }
+
+            #[cfg(feature = "arrow")]
+            if let Some(writer) = trajectory_writer.as_mut() {
+                let batch = arrow_export::trajectories_batch(i as u64 + 1, Some(&heading.to_string()), &iteration_results)
+                    .expect("Failed to build trajectories batch");
+                writer.write(&batch).expect("Failed to write trajectories Parquet file");
+            }
+
+            // The plot shows the first heading mode only
+            if i == 0 && mode == 0 {
+                first_iteration = iteration_results;
+            }
         }
 
         all_iterations.push(IterationResult {
             iteration: i + 1,
+            wall_time: iteration_started.elapsed().as_secs_f64(),
             vehicles: iteration_vehicles,
         });
+        let done = match config.duration_seconds {
+            Some(seconds) => started.elapsed().as_secs_f64() / seconds,
+            None => (i + 1) as f64 / config.iterations as f64,
+        };
+        progress.update(done.min(1.0), started.elapsed());
+
+        if let Some(convergence) = &config.convergence {
+            if convergence.check_due(i + 1, config.iterations) {
+                convergence_report = convergence.check(i + 1, config.iterations, group_precision(&config, &all_metrics));
+                if convergence_report.is_some() {
+                    break;
+                }
+            }
+        }
     }
+    let num_iterations = all_iterations.len();
+    print!("\r{:<60}", progress.render(&format!("Completed {} iterations", num_iterations)));
+    let iteration_times: Vec<f64> = all_iterations.iter().map(|iter| iter.wall_time).collect();
+    let resource_usage = ResourceUsage::new(&iteration_times, started.elapsed(), 1, sampler.finish());
 
     println!("\r\n\n╔══════════════════════════════════════════════════════╗");
     println!("║            BENCHMARK RESULTS                          ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
 
-    // Calculate aggregate statistics
-    let mut aggregate_stats: Vec<AggregateStats> = Vec::new();
+    // Flag the outlier runs of every group, for the aggregates and in the iteration records
+    for (idx, group_metrics) in all_metrics.iter_mut().enumerate() {
+        let counted_arrivals: Vec<Option<f64>> = group_metrics.iter()
+            .map(|m| m.arrival_time.filter(|_| config.include_degenerate_arrivals || !m.degenerate_start))
+            .collect();
+        let distances: Vec<f64> = group_metrics.iter().map(|m| m.distance_traveled).collect();
+        let flags = MetricOutliers::of_runs(&counted_arrivals, &distances);
+        for ((metrics, iteration), flags) in group_metrics.iter_mut().zip(all_iterations.iter_mut()).zip(flags) {
+            metrics.is_outlier = flags;
+            iteration.vehicles[idx].is_outlier = flags;
+        }
+    }
 
-    for (idx, vtype) in vehicle_types.iter().enumerate() {
-        let metrics = &all_metrics[idx];
-        let successes = metrics.iter().filter(|m| m.success).count();
-        let success_rate = successes as f64 / num_iterations as f64 * 100.0;
+    // Calculate aggregate statistics, overall and per initial condition bucket
+    let mut aggregate_stats: Vec<AggregateStats> = Vec::new();
+    let mut bucket_stats: Vec<AggregateStats> = Vec::new();
 
-        let arrival_times: Vec<f64> = metrics.iter()
-            .filter_map(|m| m.arrival_time)
-            .collect();
-        let (avg_time, std_time, min_time, max_time) = calculate_stats(&arrival_times);
+    for (idx, (heading, vehicle)) in groups.iter().enumerate() {
+        let vtype = vehicle.vehicle_type;
+        let bootstrap_seed = config.seed.wrapping_add(idx as u64);
+        let metrics: Vec<&VehicleMetrics> = all_metrics[idx].iter().collect();
+        let stat = aggregate(vtype.id(), *heading, None, &metrics, &config, bootstrap_seed);
 
-        let distances: Vec<f64> = metrics.iter().map(|m| m.distance_traveled).collect();
-        let (avg_dist, std_dist, _, _) = calculate_stats(&distances);
+        if config.headings.len() > 1 {
+            println!("{} ({}):", vtype.display_name(Language::Spanish), heading);
+        } else {
+            println!("{}:", vtype.display_name(Language::Spanish));
+        }
+        println!("  Success Rate: {:.1}% ({}/{}), {:.0}% CI [{:.1}%, {:.1}%]",
+            stat.success_rate, stat.successes, stat.total_runs, confidence * 100.0, stat.success_rate_ci_low, stat.success_rate_ci_high);
+        println!("  Arrival Time: {:.2}s avg (std: {:.2}, min: {:.2}, max: {:.2}), {:.0}% CI [{:.2}s, {:.2}s]",
+            stat.avg_arrival_time, stat.std_arrival_time, stat.min_arrival_time, stat.max_arrival_time,
+            confidence * 100.0, stat.avg_arrival_time_ci_low, stat.avg_arrival_time_ci_high);
+        if stat.degenerate_starts > 0 {
+            let arrivals = if config.include_degenerate_arrivals { "included in" } else { "left out of" };
+            println!("  Degenerate Starts: {} arrived at t=0, {} the arrival time", stat.degenerate_starts, arrivals);
+        }
+        println!("  Distance Traveled: {:.2} avg (std: {:.2})", stat.avg_distance_traveled, stat.std_distance_traveled);
+        if !stat.outlier_runs.is_empty() {
+            let (arrival, distance) = (stat.trimmed_arrival_time, stat.trimmed_distance_traveled);
+            println!("  Outliers: {} runs ({} arrival times, {} distances), without them {:.2}s avg (std: {:.2}) and {:.2} avg (std: {:.2})",
+                stat.outlier_runs.len(), arrival.outliers, distance.outliers, arrival.mean, arrival.std, distance.mean, distance.std);
+        }
+        println!("  Final Distance: {:.2} avg", stat.avg_final_distance);
+        println!("  Final Angle Error: {:.2} deg avg", stat.avg_final_angle_error);
+        println!("  Steering Chatter: {:.1} reversals/min avg, heading jerk {:.4} deg avg",
+            stat.avg_steering_reversals_per_minute, stat.avg_heading_jerk);
+        if let Some(max_bias) = stat.avg_max_approach_bias_deg {
+            let failed = stat.avg_max_approach_bias_deg_failed.map_or("no failures".to_string(), |bias| format!("{:.1} deg in failed runs", bias));
+            let corridor = stat.avg_approach_bias_deg_inside_corridor.map_or("-".to_string(), |bias| format!("{:.1} deg", bias));
+            println!("  Approach Bias: {:.1} deg max avg ({}), {} avg inside the corridor", max_bias, failed, corridor);
+        }
+        if let Some(ratio) = stat.avg_time_optimality_ratio {
+            println!("  Time Optimality: {:.2}x the Dubins lower bound avg ({:+.0}%)", ratio, (ratio - 1.0) * 100.0);
+        }
+        let phase = |time: Option<f64>| time.map_or("-".to_string(), |time| format!("{:.2}s", time));
+        println!("  Phases: transit {} avg, alignment {} avg, first aligned {} avg, {:.1} corridor entries avg",
+            phase(stat.avg_transit_time), phase(stat.avg_alignment_time), phase(stat.avg_first_aligned_time), stat.avg_alignment_attempts);
+        if let Some(hit_rate) = stat.avg_cache_hit_rate {
+            println!("  Control Cache: {:.1}% hits avg", hit_rate * 100.0);
+        }
+        println!("  Wall Time: {:.1} ms avg, p95 {:.1} ms per run", stat.avg_wall_time * 1000.0, stat.p95_wall_time * 1000.0);
 
-        let final_dists: Vec<f64> = metrics.iter().map(|m| m.final_distance).collect();
-        let (avg_final_dist, _, _, _) = calculate_stats(&final_dists);
+        if let Some(buckets) = &config.buckets {
+            println!("  By initial condition:");
+            for (bucket, metrics) in buckets.group(&all_metrics[idx], |m| m.initial_condition) {
+                let stat = aggregate(vtype.id(), *heading, Some(bucket), &metrics, &config, bootstrap_seed);
+                println!("    {}: {:.1}% ({}/{}), arrival {:.2}s avg",
+                    bucket, stat.success_rate, stat.successes, stat.total_runs, stat.avg_arrival_time);
+                bucket_stats.push(stat);
+            }
+        }
+        println!();
+        aggregate_stats.push(stat);
+    }
 
-        let angle_errors: Vec<f64> = metrics.iter().map(|m| m.final_angle_error).collect();
-        let (avg_angle_error, _, _, _) = calculate_stats(&angle_errors);
+    if let Some(report) = &convergence_report {
+        match report.stop_reason {
+            StopReason::Converged => println!("Early Stopping: converged after {} iterations ({} batches)", report.iterations, report.batches),
+            StopReason::IterationCap => println!("Early Stopping: not converged at the cap of {} iterations", report.iterations),
+        }
+        for group in &report.groups {
+            let std_error = group.arrival_time_std_error.map(|se| format!("{:.3}s", se)).unwrap_or_else(|| "-".to_string());
+            println!("  {} ({}): success rate ±{:.2} points, arrival time std error {}",
+                group.vehicle_type, group.heading, group.success_rate_half_width, std_error);
+        }
+        println!();
+    }
 
-        println!("{}:", vtype.name());
-        println!("  Success Rate: {:.1}% ({}/{})", success_rate, successes, num_iterations);
-        println!("  Arrival Time: {:.2}s avg (std: {:.2}, min: {:.2}, max: {:.2})",
-            avg_time, std_time, min_time, max_time);
-        println!("  Distance Traveled: {:.2} avg (std: {:.2})", avg_dist, std_dist);
-        println!("  Final Distance: {:.2} avg", avg_final_dist);
-        println!("  Final Angle Error: {:.2} deg avg\n", avg_angle_error);
-
-        aggregate_stats.push(AggregateStats {
-            vehicle_type: vtype.name().to_string(),
-            total_runs: num_iterations,
-            successes,
-            success_rate,
-            avg_arrival_time: avg_time,
-            std_arrival_time: std_time,
-            min_arrival_time: min_time,
-            max_arrival_time: max_time,
-            avg_distance_traveled: avg_dist,
-            std_distance_traveled: std_dist,
-            avg_final_distance: avg_final_dist,
-            avg_final_angle_error: avg_angle_error,
-        });
+    println!("Resource Usage:");
+    println!("  Total Wall Time: {:.2}s ({:.2} iterations/s)", resource_usage.total_wall_time, resource_usage.iterations_per_second);
+    println!("  Iteration Time: {:.1} ms avg, p95 {:.1} ms",
+        resource_usage.avg_iteration_time * 1000.0, resource_usage.p95_iteration_time * 1000.0);
+    match resource_usage.peak_memory_mb() {
+        Some(peak) => println!("  Peak Memory: {:.1} MB\n", peak),
+        None => println!("  Peak Memory: not sampled (build with --features memory)\n"),
     }
+    let meta = BuildMeta::current(resource_usage.threads);
+    println!("Build: {}\n", meta.summary());
+
+    // Mean paths per group, before the configuration moves into the result
+    let mean_paths: Option<Vec<MeanPath>> = mean_path_grid_dt.map(|grid_dt| {
+        groups
+            .iter()
+            .zip(&mean_path_runs)
+            .map(|((heading, vehicle), runs)| {
+                let runs: Vec<&[TrajectoryPoint]> = runs.iter().map(Vec::as_slice).collect();
+                let (mean, std) = mean_trajectory(&runs, grid_dt);
+                MeanPath {
+                    vehicle_type: vehicle.vehicle_type.id().to_string(),
+                    heading: *heading,
+                    runs: runs.len(),
+                    grid_dt,
+                    mean,
+                    std,
+                }
+            })
+            .collect()
+    });
 
     // Export results
     let result = BenchmarkResult {
-        num_iterations,
-        dt,
-        max_time,
-        map_width: 1000.0,
-        map_height: 800.0,
-        target_x: 500.0,
-        target_y: 700.0,
+        schema_version: SCHEMA_VERSION,
+        // A rerun repeats the iterations that completed
+        config: config.with_completed_iterations(num_iterations),
         iterations: all_iterations,
         aggregate: aggregate_stats,
+        bucket_stats,
+        convergence: convergence_report,
+        resource_usage,
+        meta,
     };
 
-    fs::create_dir_all("output").expect("Failed to create output directory");
+    println!("Results exported to:");
+    // Every file is listed in output/manifest.json with the configuration that produced it
+    let record = |path: &str, kind| {
+        artifacts::record(ArtifactEntry::new(path, kind).config(&result.config).seed(result.config.seed))
+    };
 
-    let json = serde_json::to_string_pretty(&result).unwrap();
-    let filename = format!("output/benchmark_{}iterations.json", num_iterations);
-    fs::write(&filename, &json).expect("Failed to write benchmark results");
-
-    // Export CSV for easy analysis
-    let csv_filename = format!("output/benchmark_{}iterations.csv", num_iterations);
-    let mut csv = String::from("iteration,vehicle_type,success,arrival_time,distance_traveled,final_distance,final_angle_error,initial_x,initial_y,initial_angle\n");
-
-    for iter in &result.iterations {
-        for v in &iter.vehicles {
-            csv.push_str(&format!(
-                "{},{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2}\n",
-                iter.iteration,
-                v.vehicle_type,
-                v.success,
-                v.arrival_time.map(|t| format!("{:.2}", t)).unwrap_or_default(),
-                v.distance_traveled,
-                v.final_distance,
-                v.final_angle_error,
-                v.initial_x,
-                v.initial_y,
-                v.initial_angle
-            ));
+    #[cfg(feature = "arrow")]
+    if let Some(writer) = trajectory_writer {
+        let runs_filename = format!("output/benchmark_{}.parquet", run_label);
+        let file = fs::File::create(&runs_filename).expect("Failed to create runs Parquet file");
+        arrow_export::write_runs_parquet(&run_records(&result.iterations), file).expect("Failed to write runs Parquet file");
+        writer.close().expect("Failed to finish trajectories Parquet file");
+
+        println!("  - {} (Parquet raw data)", runs_filename);
+        println!("  - {} (Parquet trajectory points)", trajectories_filename);
+        record(&runs_filename, ArtifactKind::Benchmark);
+        record(&trajectories_filename, ArtifactKind::Benchmark);
+    }
+
+    if !parquet {
+        let json = serde_json::to_string_pretty(&result).unwrap();
+        let filename = format!("output/benchmark_{}.json", run_label);
+        fs::write(&filename, &json).expect("Failed to write benchmark results");
+
+        // Export CSV for easy analysis
+        let csv_filename = format!("output/benchmark_{}.csv", run_label);
+        let mut csv = String::from("iteration,vehicle_type,heading,success,degenerate_start,arrival_time,distance_traveled,final_distance,final_angle_error,max_approach_bias_deg,optimal_time_lower_bound,time_optimality_ratio,initial_x,initial_y,initial_angle,wall_time\n");
+
+        for iter in &result.iterations {
+            for v in &iter.vehicles {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{:.2},{:.2},{:.2},{},{},{},{:.2},{:.2},{:.2},{:.6}\n",
+                    iter.iteration,
+                    v.vehicle_type,
+                    v.heading,
+                    v.success,
+                    v.degenerate_start,
+                    v.arrival_time.map(|t| format!("{:.2}", t)).unwrap_or_default(),
+                    v.distance_traveled,
+                    v.final_distance,
+                    v.final_angle_error,
+                    v.max_approach_bias_deg.map(|bias| format!("{:.2}", bias)).unwrap_or_default(),
+                    v.optimal_time_lower_bound.map(|t| format!("{:.2}", t)).unwrap_or_default(),
+                    v.time_optimality_ratio.map(|ratio| format!("{:.4}", ratio)).unwrap_or_default(),
+                    v.initial_x,
+                    v.initial_y,
+                    v.initial_angle,
+                    v.wall_time
+                ));
+            }
+        }
+        fs::write(&csv_filename, &csv).expect("Failed to write CSV");
+
+        println!("  - {} (JSON)", filename);
+        println!("  - {} (CSV raw data)", csv_filename);
+        record(&filename, ArtifactKind::Benchmark);
+        record(&csv_filename, ArtifactKind::Benchmark);
+    }
+
+    #[cfg(feature = "storage")]
+    if let Some(path) = store_path {
+        let run_id = RunStore::open(path).and_then(|mut store| store.insert_benchmark(&result.config, &stored_runs));
+        match run_id {
+            Ok(run_id) => println!("  - {} (run #{} of the run database)", path, run_id),
+            Err(e) => eprintln!("Warning: could not store the run in '{}': {}", path, e),
         }
     }
-    fs::write(&csv_filename, &csv).expect("Failed to write CSV");
 
     // Export aggregate stats CSV
-    let agg_csv_filename = format!("output/benchmark_{}iterations_summary.csv", num_iterations);
-    let mut agg_csv = String::from("vehicle_type,total_runs,successes,success_rate,avg_arrival_time,std_arrival_time,min_arrival_time,max_arrival_time,avg_distance_traveled,std_distance_traveled,avg_final_distance,avg_final_angle_error\n");
+    let agg_csv_filename = format!("output/benchmark_{}_summary.csv", run_label);
+    let mut agg_csv = String::from("vehicle_type,heading,total_runs,successes,success_rate,success_rate_ci_low,success_rate_ci_high,avg_arrival_time,avg_arrival_time_ci_low,avg_arrival_time_ci_high,std_arrival_time,min_arrival_time,max_arrival_time,avg_distance_traveled,std_distance_traveled,avg_final_distance,avg_final_angle_error,avg_steering_reversals_per_minute,avg_heading_jerk,avg_max_approach_bias_deg,avg_max_approach_bias_deg_failed,avg_approach_bias_deg_inside_corridor,avg_time_optimality_ratio,avg_transit_time,avg_alignment_time,avg_first_aligned_time,avg_alignment_attempts,avg_wall_time,p95_wall_time\n");
 
     for stat in &result.aggregate {
         agg_csv.push_str(&format!(
-            "{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2}\n",
+            "{},{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.4},{},{},{},{},{},{},{},{:.2},{:.6},{:.6}\n",
             stat.vehicle_type,
+            stat.heading,
             stat.total_runs,
             stat.successes,
             stat.success_rate,
+            stat.success_rate_ci_low,
+            stat.success_rate_ci_high,
             stat.avg_arrival_time,
+            stat.avg_arrival_time_ci_low,
+            stat.avg_arrival_time_ci_high,
             stat.std_arrival_time,
             stat.min_arrival_time,
             stat.max_arrival_time,
             stat.avg_distance_traveled,
             stat.std_distance_traveled,
             stat.avg_final_distance,
-            stat.avg_final_angle_error
+            stat.avg_final_angle_error,
+            stat.avg_steering_reversals_per_minute,
+            stat.avg_heading_jerk,
+            stat.avg_max_approach_bias_deg.map(|bias| format!("{:.2}", bias)).unwrap_or_default(),
+            stat.avg_max_approach_bias_deg_failed.map(|bias| format!("{:.2}", bias)).unwrap_or_default(),
+            stat.avg_approach_bias_deg_inside_corridor.map(|bias| format!("{:.2}", bias)).unwrap_or_default(),
+            stat.avg_time_optimality_ratio.map(|ratio| format!("{:.4}", ratio)).unwrap_or_default(),
+            stat.avg_transit_time.map(|time| format!("{:.2}", time)).unwrap_or_default(),
+            stat.avg_alignment_time.map(|time| format!("{:.2}", time)).unwrap_or_default(),
+            stat.avg_first_aligned_time.map(|time| format!("{:.2}", time)).unwrap_or_default(),
+            stat.avg_alignment_attempts,
+            stat.avg_wall_time,
+            stat.p95_wall_time
         ));
     }
     fs::write(&agg_csv_filename, &agg_csv).expect("Failed to write summary CSV");
-
-    println!("Results exported to:");
-    println!("  - {} (JSON)", filename);
-    println!("  - {} (CSV raw data)", csv_filename);
     println!("  - {} (CSV summary)", agg_csv_filename);
+    record(&agg_csv_filename, ArtifactKind::Benchmark);
+
+    if !result.bucket_stats.is_empty() {
+        let buckets_csv_filename = format!("output/benchmark_{}_buckets.csv", run_label);
+        let mut buckets_csv = String::from("vehicle_type,heading,bucket,total_runs,successes,success_rate,avg_arrival_time,std_arrival_time,avg_distance_traveled,avg_final_distance,avg_final_angle_error\n");
+        for stat in &result.bucket_stats {
+            buckets_csv.push_str(&format!(
+                "{},{},\"{}\",{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2}\n",
+                stat.vehicle_type,
+                stat.heading,
+                stat.bucket.map(|bucket| bucket.to_string()).unwrap_or_default(),
+                stat.total_runs,
+                stat.successes,
+                stat.success_rate,
+                stat.avg_arrival_time,
+                stat.std_arrival_time,
+                stat.avg_distance_traveled,
+                stat.avg_final_distance,
+                stat.avg_final_angle_error
+            ));
+        }
+        fs::write(&buckets_csv_filename, &buckets_csv).expect("Failed to write buckets CSV");
+        println!("  - {} (CSV per initial condition bucket)", buckets_csv_filename);
+        record(&buckets_csv_filename, ArtifactKind::Benchmark);
+    }
+
+    #[cfg(feature = "xlsx")]
+    if xlsx {
+        let xlsx_filename = format!("output/benchmark_{}.xlsx", run_label);
+        let summary = BenchmarkSummary {
+            config: &result.config,
+            meta: &result.meta,
+            groups: result.aggregate.iter().chain(&result.bucket_stats).map(summary_record).collect(),
+        };
+        xlsx_export::benchmark_to_xlsx(&summary, &xlsx_filename).expect("Failed to write Excel workbook");
+        println!("  - {} (Excel summary)", xlsx_filename);
+        record(&xlsx_filename, ArtifactKind::Benchmark);
+    }
+
+    // Arrival funnels in the --plot format, PNG by default
+    if result.aggregate.iter().any(|stat| stat.arrival_funnel.is_some()) {
+        let format = plot_format.unwrap_or(ImageFormat::Png);
+        let funnel_filename = format!("output/benchmark_{}_arrival_funnel.{}", run_label, format.extension());
+        let curves: Vec<FunnelCurve> = result.aggregate.iter()
+            .filter_map(|stat| {
+                Some(FunnelCurve {
+                    vehicle_type: &stat.vehicle_type,
+                    heading: (result.config.headings.len() > 1).then_some(stat.heading),
+                    points: stat.arrival_funnel.as_deref()?,
+                })
+            })
+            .collect();
+        match trajectory_export::export_arrival_funnel_plot(&curves, &funnel_filename) {
+            Ok(()) => {
+                println!("  - {} (arrival funnel per vehicle type)", funnel_filename);
+                record(&funnel_filename, ArtifactKind::Plot);
+            }
+            Err(e) => eprintln!("Warning: could not export arrival funnel plot: {}", e),
+        }
+    }
+
+    if let Some(mean_paths) = &mean_paths {
+        let mean_paths_filename = format!("output/benchmark_{}_mean_paths.json", run_label);
+        fs::write(&mean_paths_filename, serde_json::to_string_pretty(mean_paths).unwrap()).expect("Failed to write mean paths");
+        println!("  - {} (mean path per vehicle type)", mean_paths_filename);
+        record(&mean_paths_filename, ArtifactKind::Report);
+    }
+
+    if let Some(format) = plot_format {
+        let plot_filename = format!("output/benchmark_{}_iter1.{}", run_label, format.extension());
+        let total_simulation_time = first_iteration.iter()
+            .filter_map(|v| v.trajectory.last().map(|p| p.t))
+            .fold(0.0, f64::max);
+        let first = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: first_iteration,
+            total_simulation_time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        };
+        let options = PlotOptions {
+            format,
+            time_label_interval: Some(10.0),
+            ..Default::default()
+        };
+        match trajectory_export::plot_trajectories(&first, &map, options, &plot_filename) {
+            Ok(()) => {
+                println!("  - {} (trajectories of iteration 1)", plot_filename);
+                record(&plot_filename, ArtifactKind::Plot);
+            }
+            Err(e) => eprintln!("Warning: could not export trajectory plot: {}", e),
+        }
+    }
 }
@@ -4,10 +4,12 @@
 //   cargo run -- --mode navigation [--iterations N]
 //   cargo run -- --mode benchmark [--iterations N]
 //   cargo run -- --mode visualizer
-//   cargo run -- --mode export-memberships [--output-dir DIR]
+//   cargo run -- --mode export-memberships [--output-dir DIR] [--format png|svg|csv]
 
 use clap::Parser;
-use examen_parcial::membership_export;
+use examen_parcial::logging;
+use examen_parcial::membership_export::{self, ImageFormat};
+use examen_parcial::vehicle::VehicleType;
 use std::process;
 
 mod navigation_runner;
@@ -29,27 +31,55 @@ struct Args {
     #[arg(help = "Directorio de salida para exportar funciones de pertenencia")]
     output_dir: String,
 
+    #[arg(long, default_value = "png")]
+    #[arg(help = "Formato para exportar funciones de pertenencia: png, svg, csv")]
+    format: String,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = "png", value_name = "FORMAT")]
+    #[arg(help = "Exportar figura de trayectorias (png o svg) en navigation y benchmark")]
+    plot: Option<String>,
+
     #[arg(short = 't', long)]
     #[arg(help = "Número de threads para benchmark paralelo (por defecto: mitad de los cores disponibles)")]
     threads: Option<usize>,
+
+    #[arg(long, value_name = "LEVEL")]
+    #[arg(help = "Filtro de logs (error, warn, info, debug, trace o directivas RUST_LOG), por defecto RUST_LOG o info")]
+    log_level: Option<String>,
 }
 
 fn main() {
     let args = Args::parse();
 
+    if let Err(e) = logging::init_cli(args.log_level.as_deref()) {
+        eprintln!("\n❌ Error: --log-level inválido: {}", e);
+        process::exit(1);
+    }
+
+    let plot_format = match args.plot.as_deref() {
+        None => None,
+        Some(name) => match ImageFormat::parse(name) {
+            Some(format) => Some(format),
+            None => {
+                eprintln!("\n❌ Error: Formato de figura desconocido '{}' (use png o svg)", name);
+                process::exit(1);
+            }
+        },
+    };
+
     match args.mode.to_lowercase().as_str() {
         "navigation" | "nav" => {
             println!("\n╔══════════════════════════════════════════════════════╗");
             println!("║   MODO: NAVEGACIÓN MULTI-VEHÍCULO                    ║");
             println!("╚══════════════════════════════════════════════════════╝\n");
-            navigation_runner::run();
+            navigation_runner::run(plot_format);
         }
 
         "benchmark" | "bench" => {
             println!("\n╔══════════════════════════════════════════════════════╗");
             println!("║   MODO: BENCHMARK                                    ║");
             println!("╚══════════════════════════════════════════════════════╝\n");
-            benchmark_runner::run(args.iterations, args.threads);
+            benchmark_runner::run(args.iterations, args.threads, plot_format);
         }
 
         "visualizer" | "viz" | "visual" => {
@@ -64,7 +94,19 @@ fn main() {
             println!("║   MODO: EXPORTAR FUNCIONES DE PERTENENCIA           ║");
             println!("╚══════════════════════════════════════════════════════╝\n");
 
-            if let Err(e) = membership_export::export_all_vehicle_types(&args.output_dir) {
+            let result = if args.format.eq_ignore_ascii_case("csv") {
+                // Raw sampled values for external plotting
+                VehicleType::ALL.iter()
+                    .try_for_each(|&vt| membership_export::export_navigation_csvs(vt, &args.output_dir).map(|_| ()))
+            } else {
+                let Some(format) = ImageFormat::parse(&args.format) else {
+                    eprintln!("\n❌ Error: Formato desconocido '{}' (use png, svg o csv)", args.format);
+                    process::exit(1);
+                };
+                membership_export::export_all_vehicle_types(&args.output_dir, format)
+            };
+
+            if let Err(e) = result {
                 eprintln!("\nError al exportar funciones de pertenencia: {}", e);
                 process::exit(1);
             }
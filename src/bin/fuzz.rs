@@ -0,0 +1,82 @@
+// Scenario fuzzer: search for start states where the controller does not arrive
+//
+// Run with: cargo run --release --features cli --bin fuzz -- [num_cases] [--vehicle=TYPE] [--seed=N] [--whole-map] [--max-time=S] [--no-shrink] [--log-level=LEVEL]
+// Example: cargo run --release --features cli --bin fuzz -- 1000 --vehicle=Heavy --whole-map
+
+use examen_parcial::artifacts::{self, ArtifactEntry, ArtifactKind};
+use examen_parcial::logging;
+use examen_parcial::simulation::fuzz::{self, FuzzConfig, StartRegion};
+use examen_parcial::simulation::Language;
+use examen_parcial::vehicle::VehicleType;
+use std::env;
+use std::fs;
+
+/// Value of a `--name=value` argument
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let prefix = format!("--{}=", name);
+    args.iter().find_map(|arg| arg.strip_prefix(prefix.as_str()))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    // Warn by default, every arrival is an info event
+    if let Err(e) = logging::init_cli(logging::log_level_arg(&args).or(Some("warn"))) {
+        eprintln!("\n❌ Error: --log-level inválido: {}", e);
+        std::process::exit(1);
+    }
+
+    let defaults = FuzzConfig::default();
+    let vehicle_type = match arg_value(&args, "vehicle") {
+        Some(name) => VehicleType::parse(name).unwrap_or_else(|| {
+            eprintln!("\n❌ Error: Tipo de vehículo desconocido '{}' (Heavy, Standard, Agile, UltraAgile)", name);
+            std::process::exit(1);
+        }),
+        None => defaults.vehicle_type,
+    };
+    let config = FuzzConfig {
+        vehicle_type,
+        cases: args.iter()
+            .skip(1)
+            .filter(|arg| !arg.starts_with("--"))
+            .find_map(|s| s.parse().ok())
+            .unwrap_or(defaults.cases),
+        seed: arg_value(&args, "seed").and_then(|s| s.parse().ok()).unwrap_or(defaults.seed),
+        region: if args.iter().any(|arg| arg == "--whole-map") { StartRegion::WholeMap } else { StartRegion::StartZone },
+        max_time: arg_value(&args, "max-time").and_then(|s| s.parse().ok()).unwrap_or(defaults.max_time),
+        shrink: !args.iter().any(|arg| arg == "--no-shrink"),
+        ..defaults
+    };
+
+    println!("\n╔══════════════════════════════════════════════════════╗");
+    println!("║   SCENARIO FUZZER                                    ║");
+    println!("╚══════════════════════════════════════════════════════╝\n");
+    println!("Vehicle: {}", config.vehicle_type.display_name(Language::Spanish));
+    println!("Cases: {} (seeds {}..{})", config.cases, config.seed, config.seed.wrapping_add(config.cases as u64));
+    println!("Region: {:?}, max_time={:.0}s, shrink={}\n", config.region, config.max_time, config.shrink);
+
+    let report = match fuzz::run(&config) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("\n❌ Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Failures: {}/{}", report.failures.len(), config.cases);
+    for failure in &report.failures {
+        let s = &failure.scenario;
+        print!("  seed {:>6}: ({:7.1}, {:6.1}) @ {:6.1}° -> {:?}, {:.1} units left",
+            s.seed, s.start_x, s.start_y, s.start_angle, failure.termination, failure.final_distance_to_target);
+        match &failure.shrunk {
+            Some(m) => println!(", minimal ({:.1}, {:.1}) @ {:.1}°", m.start_x, m.start_y, m.start_angle),
+            None => println!(),
+        }
+    }
+
+    fs::create_dir_all("output").expect("Failed to create output directory");
+    let filename = format!("output/fuzz_{}_{}cases.json", config.vehicle_type.id(), config.cases);
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    fs::write(&filename, &json).expect("Failed to write fuzz report");
+    println!("\nReport exported to: {}", filename);
+    artifacts::record(ArtifactEntry::new(filename, ArtifactKind::Report).config(&config).seed(config.seed));
+}
@@ -1,143 +1,206 @@
 // Multi-Vehicle Navigation Simulation - Fuzzy logic-based navigation for 3 vehicles
 //
-// Run with: cargo run --bin navigation
-
-use examen_parcial::map::Map;
-use examen_parcial::simulation::{Simulation, MultiVehicleSimulationResult, VehicleResult};
-use examen_parcial::vehicle::VehicleType;
+// Run with: cargo run --bin navigation [-- --plot[=png|svg]] [--scenario FILE] [--save-scenario FILE] [--stream FILE] [--animate FILE] [--log-level=LEVEL]
+// Repeat a run: cargo run --bin navigation -- --save-scenario output/scenario.json, then --scenario output/scenario.json
+// Long runs: --stream output/trajectory_multi.jsonl writes the trajectories as they are produced instead of keeping them
+// Animation: --animate output/trajectory_multi.gif (or .png for an APNG) renders the run frame by frame
+
+use examen_parcial::artifacts::{self, ArtifactEntry, ArtifactKind};
+use examen_parcial::logging;
+use examen_parcial::membership_export::ImageFormat;
+use examen_parcial::progress::Progress;
+use examen_parcial::simulation::{Language, MultiVehicleSimulation, MultiVehicleSimulationResult, Scenario, SummaryOptions, TrajectoryStream};
+use examen_parcial::trajectory_export::{self, AnimationOptions, JsonLinesSink, PlotOptions};
+use std::env;
 use std::fs;
 use std::io::Write;
+use std::time::Instant;
+
+/// Value of `--name VALUE` or `--name=VALUE`
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1).map(String::as_str))
+        .or_else(|| args.iter().find_map(|arg| arg.strip_prefix(name)?.strip_prefix('=')))
+}
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(e) = logging::init_cli(logging::log_level_arg(&args)) {
+        eprintln!("\n❌ Error: --log-level inválido: {}", e);
+        std::process::exit(1);
+    }
+
+    // --plot exports a PNG figure of the trajectories, --plot=svg a vector one
+    let plot_format = args.iter().find_map(|arg| match arg.as_str() {
+        "--plot" => Some(ImageFormat::Png),
+        _ => arg.strip_prefix("--plot=").and_then(ImageFormat::parse),
+    });
+
+    // --animate FILE renders the run as a GIF, or an APNG for a .png file
+    let animate_path = arg_value(&args, "--animate");
+
+    // --scenario FILE runs a saved scenario instead of the exam one
+    let scenario_path = arg_value(&args, "--scenario");
+    let mut scenario = match scenario_path {
+        Some(path) => {
+            let json = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: No se pudo leer '{}': {}", path, e);
+                std::process::exit(1);
+            });
+            serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: '{}' no es un escenario válido: {}", path, e);
+                std::process::exit(1);
+            })
+        }
+        None => Scenario::default_exam(),
+    };
+    // The drawn seed is recorded so a saved scenario repeats this run
+    scenario.seed.get_or_insert_with(rand::random);
+    // Every file is listed in the manifest of its directory with the scenario that produced it
+    let record = |path: &str, kind| {
+        let entry = ArtifactEntry::new(path, kind).config(&scenario);
+        artifacts::record(match scenario.seed {
+            Some(seed) => entry.seed(seed),
+            None => entry,
+        })
+    };
+
+    if let Err(e) = scenario.validate() {
+        eprintln!("\n❌ Error: Escenario inválido: {}", e);
+        std::process::exit(1);
+    }
+
+    // --save-scenario FILE writes the scenario of this run, seed included
+    if let Some(path) = arg_value(&args, "--save-scenario") {
+        let json = serde_json::to_string_pretty(&scenario).expect("Failed to serialize scenario");
+        fs::write(path, json).unwrap_or_else(|e| {
+            eprintln!("\n❌ Error: No se pudo escribir '{}': {}", path, e);
+            std::process::exit(1);
+        });
+        println!("✓ Scenario saved to: {}", path);
+        record(path, ArtifactKind::Scenario);
+    }
+
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║   MULTI-VEHICLE FUZZY NAVIGATION SIMULATION          ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
 
-    // Create map (1000x800, target at top center: 500,700)
-    let map = Map::new(1000.0, 800.0, 500.0, 700.0);
-
-    let dt = 0.05; // 50ms time step
-    let max_time = 600.0;
-
-    // Create 3 vehicles of different types
-    let vehicle_types = vec![
-        VehicleType::Heavy,
-        VehicleType::Standard,
-        VehicleType::Agile,
-    ];
-
-    let mut simulations: Vec<Simulation> = vehicle_types.iter()
-        .map(|&vtype| Simulation::new(map.clone(), vtype, dt, max_time))
-        .collect();
-
-    println!("Simulating {} vehicles:", simulations.len());
-    for (i, sim) in simulations.iter().enumerate() {
-        println!("  {}. {} - Start: ({:.1}, {:.1}) @ {:.1}°",
-            i + 1,
-            sim.vehicle.vehicle_type.name(),
-            sim.vehicle.state.position.x,
-            sim.vehicle.state.position.y,
-            sim.vehicle.state.angle.to_degrees()
-        );
-    }
-    println!("\nTarget: (500.0, 700.0) @ 90°\n");
-    println!("Running simulation (dt={:.3}s, max_time={:.1}s)...\n", dt, max_time);
-
-    // Run all simulations in parallel
-    let mut time = 0.0;
-    let mut all_arrived = false;
+    // Run all simulations in lock-step
     let mut step_count = 0;
-
-    while time < max_time && !all_arrived {
-        // Update each vehicle
-        for sim in &mut simulations {
-            if !sim.vehicle.has_arrived {
-                sim.step();
+    let started = Instant::now();
+    let mut progress = Progress::new();
+
+    let observe = |multi: &MultiVehicleSimulation| {
+        if step_count == 0 {
+            println!("Simulating {} vehicles:", multi.simulations.len());
+            for (i, sim) in multi.simulations.iter().enumerate() {
+                println!("  {}. {} - Start: ({:.1}, {:.1}) @ {:.1}°",
+                    i + 1,
+                    sim.vehicle.vehicle_type.display_name(Language::Spanish),
+                    sim.vehicle.state.position.x,
+                    sim.vehicle.state.position.y,
+                    sim.vehicle.state.angle.to_degrees()
+                );
             }
+            println!("\nTarget: ({:.1}, {:.1}) @ 90°", scenario.target_x, scenario.target_y);
+            println!("Seed: {}\n", scenario.seed.unwrap_or_default());
+            println!("Running simulation (dt={:.3}s, max_time={:.1}s)...\n", multi.dt, multi.max_time);
+        } else if step_count % 100 == 0 {
+            // Print progress every 5 seconds, by simulated time against max_time
+            progress.update(multi.time / multi.max_time, started.elapsed());
+            println!("{} | {}/{} vehicles arrived",
+                progress.render(&format!("[t={:6.2}s]", multi.time)), multi.arrived_count(), multi.simulations.len());
         }
-
-        time += dt;
         step_count += 1;
+    };
 
-        // Check if all have arrived
-        all_arrived = simulations.iter().all(|s| s.vehicle.has_arrived);
-
-        // Print progress every 5 seconds
-        if step_count % 100 == 0 {
-            let arrived_count = simulations.iter().filter(|s| s.vehicle.has_arrived).count();
-            println!("[t={:6.2}s] {}/{} vehicles arrived", time, arrived_count, simulations.len());
+    // --stream FILE appends the points to a JSON Lines file every 10 simulated
+    // seconds, memory stays bounded and a crash keeps what was written
+    let stream_path = arg_value(&args, "--stream");
+    fs::create_dir_all("output").expect("Failed to create output directory");
+    let run = match stream_path {
+        Some(path) => {
+            let sink = JsonLinesSink::create(path).unwrap_or_else(|e| {
+                eprintln!("\n❌ Error: {}", e);
+                std::process::exit(1);
+            });
+            scenario.run_streamed(TrajectoryStream::new(sink), observe)
         }
-    }
+        None => scenario.run_with(observe),
+    };
+    let multi_result = run.unwrap_or_else(|e| {
+        eprintln!("\n❌ Error: {}", e);
+        std::process::exit(1);
+    });
+
+    // Every vehicle may arrive well before max_time
+    let time = multi_result.total_simulation_time;
+    progress.finish(started.elapsed());
+    println!("{} | {:.2}s wall time", progress.render(&format!("[t={:6.2}s]", time)), started.elapsed().as_secs_f64());
 
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║            SIMULATION COMPLETED                       ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
 
-    // Collect results
-    let mut vehicle_results = Vec::new();
-
-    for (i, sim) in simulations.into_iter().enumerate() {
-        println!("Vehicle {}: {}", i + 1, sim.vehicle.vehicle_type.name());
-
-        // Calculate metrics
-        let success = sim.vehicle.has_arrived;
-        let arrival_time = if success { Some(sim.vehicle.time_elapsed) } else { None };
-
-        let final_point = sim.trajectory.last().unwrap();
-        let final_distance = final_point.distance_to_target;
-        let final_angle_error = (90.0 - final_point.angle).abs();
+    println!("{}", multi_result.summary_with(SummaryOptions::default().verbose()));
 
-        // Calculate distance traveled
-        let mut distance_traveled = 0.0;
-        for j in 1..sim.trajectory.len() {
-            let p1 = &sim.trajectory[j - 1];
-            let p2 = &sim.trajectory[j];
-            let dx = p2.x - p1.x;
-            let dy = p2.y - p1.y;
-            distance_traveled += (dx * dx + dy * dy).sqrt();
+    // A streamed run is already on disk, the figure and the animation read it back
+    let trajectories = match stream_path {
+        Some(path) => {
+            println!("✓ Multi-vehicle trajectory streamed to: {}", path);
+            record(path, ArtifactKind::Simulation);
+            (plot_format.is_some() || animate_path.is_some())
+                .then(|| MultiVehicleSimulationResult::from_json_file(path).expect("Failed to read the streamed trajectory"))
         }
+        None => {
+            // Export to JSON
+            let json_output = serde_json::to_string_pretty(&multi_result)
+                .expect("Failed to serialize simulation result");
+
+            let filename = "output/trajectory_multi.json";
+            let mut file = fs::File::create(filename).expect("Failed to create output file");
+            file.write_all(json_output.as_bytes())
+                .expect("Failed to write to file");
+
+            println!("✓ Multi-vehicle trajectory exported to: {}", filename);
+            record(filename, ArtifactKind::Simulation);
+            Some(multi_result)
+        }
+    };
 
-        let metrics = examen_parcial::simulation::SimulationMetrics {
-            success,
-            arrival_time,
-            distance_traveled,
-            final_angle_error,
-            final_distance_to_target: final_distance,
+    if let (Some(format), Some(trajectories)) = (plot_format, &trajectories) {
+        let plot_filename = format!("output/trajectory_multi.{}", format.extension());
+        let options = PlotOptions {
+            format,
+            time_label_interval: Some(10.0),
+            ..Default::default()
         };
-
-        println!("  Success: {}", if success { "YES ✓" } else { "NO ✗" });
-        if let Some(t) = arrival_time {
-            println!("  Arrival Time: {:.2}s", t);
+        let map = scenario.map().expect("scenario validated by build");
+        match trajectory_export::plot_trajectories(trajectories, &map, options, &plot_filename) {
+            Ok(()) => {
+                println!("✓ Trajectory plot exported to: {}", plot_filename);
+                record(&plot_filename, ArtifactKind::Plot);
+            }
+            Err(e) => eprintln!("Warning: could not export trajectory plot: {}", e),
         }
-        println!("  Distance Traveled: {:.2} units", distance_traveled);
-        println!("  Final Distance: {:.2} units", final_distance);
-        println!("  Final Angle Error: {:.2}°", final_angle_error);
-        println!();
-
-        vehicle_results.push(VehicleResult {
-            vehicle_type: sim.vehicle.vehicle_type.name().to_string(),
-            trajectory: sim.trajectory.clone(),
-            metrics,
-        });
+    } else {
+        println!("  (add --plot or --plot=svg to export a trajectory figure)");
     }
 
-    // Create multi-vehicle result
-    let multi_result = MultiVehicleSimulationResult {
-        vehicles: vehicle_results,
-        total_simulation_time: time,
-    };
-
-    // Export to JSON
-    let json_output = serde_json::to_string_pretty(&multi_result)
-        .expect("Failed to serialize simulation result");
-
-    fs::create_dir_all("output").expect("Failed to create output directory");
-
-    let filename = "output/trajectory_multi.json";
-    let mut file = fs::File::create(filename).expect("Failed to create output file");
-    file.write_all(json_output.as_bytes())
-        .expect("Failed to write to file");
-
-    println!("✓ Multi-vehicle trajectory exported to: {}", filename);
+    if let (Some(path), Some(trajectories)) = (animate_path, &trajectories) {
+        let options = AnimationOptions {
+            time_label_interval: Some(10.0),
+            ..Default::default()
+        };
+        let map = scenario.map().expect("scenario validated by build");
+        match trajectory_export::render_animation(trajectories, &map, options, path) {
+            Ok(()) => {
+                println!("✓ Trajectory animation exported to: {}", path);
+                record(path, ArtifactKind::Plot);
+            }
+            Err(e) => eprintln!("Warning: could not export trajectory animation: {}", e),
+        }
+    }
     println!("\nVisualize with: cargo run --bin visualizer");
 }
@@ -0,0 +1,395 @@
+// Smoke test: exercise the library end to end and exit nonzero if any check fails
+//
+// Run with: cargo run --release --features cli --bin smoke -- [--url=URL] [--seed=N] [--min-success-rate=PCT] [--max-sim-seconds=S] [--max-benchmark-seconds=S] [--min-png-bytes=N] [--tolerance=REL] [--log-level=LEVEL]
+// Local checks: the default scenario, one seeded simulation per vehicle type, a 3 iteration benchmark
+// and one membership PNG written to a temporary directory
+// After a deploy: cargo run --release --features cli --bin smoke -- --url=https://<app>.shuttle.app
+// also checks /health, /api/simulate and a 2 iteration /api/benchmark against the same requests run here
+
+use examen_parcial::api::service;
+use examen_parcial::api::{BenchmarkRequest, SimulationRequest};
+use examen_parcial::logging;
+use examen_parcial::membership_export::{self, ImageFormat};
+use examen_parcial::navigation::NavigationController;
+use examen_parcial::simulation::Scenario;
+use examen_parcial::vehicle::{create_vehicle_preset, VehicleType};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Limits the checks must stay within, each one overridable by its flag
+#[derive(Debug, Clone, PartialEq)]
+struct Thresholds {
+    /// Lowest benchmark success rate of every vehicle type (percent), `--min-success-rate`
+    min_success_rate: f64,
+    /// Longest wall time of one seeded simulation (seconds), `--max-sim-seconds`
+    max_simulation_seconds: f64,
+    /// Longest wall time of the whole benchmark (seconds), `--max-benchmark-seconds`
+    max_benchmark_seconds: f64,
+    /// Smallest acceptable membership PNG (bytes), `--min-png-bytes`
+    min_png_bytes: u64,
+    /// Relative difference allowed between the local and the remote numbers, `--tolerance`
+    tolerance: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            min_success_rate: 50.0,
+            max_simulation_seconds: 10.0,
+            max_benchmark_seconds: 60.0,
+            min_png_bytes: 5_000,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl Thresholds {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let defaults = Self::default();
+        Ok(Self {
+            min_success_rate: parse_arg(args, "min-success-rate")?.unwrap_or(defaults.min_success_rate),
+            max_simulation_seconds: parse_arg(args, "max-sim-seconds")?.unwrap_or(defaults.max_simulation_seconds),
+            max_benchmark_seconds: parse_arg(args, "max-benchmark-seconds")?.unwrap_or(defaults.max_benchmark_seconds),
+            min_png_bytes: parse_arg(args, "min-png-bytes")?.unwrap_or(defaults.min_png_bytes),
+            tolerance: parse_arg(args, "tolerance")?.unwrap_or(defaults.tolerance),
+        })
+    }
+}
+
+/// Value of a `--name=value` argument
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let prefix = format!("--{}=", name);
+    args.iter().find_map(|arg| arg.strip_prefix(prefix.as_str()))
+}
+
+/// Parsed value of a `--name=value` argument, an error naming the flag when it does not parse
+fn parse_arg<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    arg_value(args, name)
+        .map(|value| value.parse().map_err(|_| format!("--{} inválido: '{}'", name, value)))
+        .transpose()
+}
+
+/// Outcome of one check, `detail` says what was measured
+#[derive(Debug, Clone, PartialEq)]
+struct Check {
+    name: String,
+    passed: bool,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, passed: bool, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), passed, detail: detail.into() }
+    }
+
+    /// Failed check for a step that could not run at all
+    fn error(name: impl Into<String>, error: impl std::fmt::Display) -> Self {
+        Self::new(name, false, error.to_string())
+    }
+}
+
+fn check_at_least(name: impl Into<String>, value: f64, min: f64, unit: &str) -> Check {
+    Check::new(name, value >= min, format!("{:.2}{} (mín {:.2}{})", value, unit, min, unit))
+}
+
+fn check_at_most(name: impl Into<String>, value: f64, max: f64, unit: &str) -> Check {
+    Check::new(name, value <= max, format!("{:.3}{} (máx {:.3}{})", value, unit, max, unit))
+}
+
+/// Values at `pointer` of a local and a remote response, numbers may differ by `tolerance` relative to the local one
+fn check_matches(name: impl Into<String>, local: &Value, remote: &Value, pointer: &str, tolerance: f64) -> Check {
+    let name = format!("{} {}", name.into(), pointer);
+    let (local, remote) = (local.pointer(pointer), remote.pointer(pointer));
+    let passed = match (local.and_then(Value::as_f64), remote.and_then(Value::as_f64)) {
+        (Some(a), Some(b)) => (a - b).abs() <= tolerance * a.abs().max(1.0),
+        _ => local.is_some() && local == remote,
+    };
+    let show = |value: Option<&Value>| value.map_or("ausente".to_string(), Value::to_string);
+    Check::new(name, passed, format!("local {} / remoto {}", show(local), show(remote)))
+}
+
+/// Failed checks and a readable report of every check
+fn summarize(checks: &[Check]) -> (usize, String) {
+    let mut report = String::new();
+    for check in checks {
+        let mark = if check.passed { "✓" } else { "✗" };
+        report.push_str(&format!("  {} {}: {}\n", mark, check.name, check.detail));
+    }
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    report.push_str(&format!("\n{}/{} checks correctos", checks.len() - failed, checks.len()));
+    (failed, report)
+}
+
+/// Seeded request simulating one vehicle type, shared by the local and the remote runs
+fn simulation_json(vehicle_type: VehicleType, seed: u64) -> Value {
+    json!({ "vehicle_types": [vehicle_type.id()], "seed": seed })
+}
+
+/// Seeded benchmark request over every vehicle type
+fn benchmark_json(iterations: usize, seed: u64) -> Value {
+    let vehicle_types: Vec<&str> = VehicleType::ALL.iter().map(|vehicle_type| vehicle_type.id()).collect();
+    json!({ "iterations": iterations, "vehicle_types": vehicle_types, "seed": seed })
+}
+
+/// Run a simulation request in process, as the API would answer it
+fn simulate_locally(request: &Value) -> Result<(Value, Duration), String> {
+    let request: SimulationRequest = serde_json::from_value(request.clone()).map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    let response = service::simulate(&request).map_err(|e| e.to_string())?;
+    Ok((serde_json::to_value(response).map_err(|e| e.to_string())?, start.elapsed()))
+}
+
+/// Run a benchmark request in process, as the API would answer it
+fn benchmark_locally(request: &Value) -> Result<(Value, Duration), String> {
+    let request: BenchmarkRequest = serde_json::from_value(request.clone()).map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    let response = service::benchmark(&request).map_err(|e| e.to_string())?;
+    Ok((serde_json::to_value(response).map_err(|e| e.to_string())?, start.elapsed()))
+}
+
+/// Success rate of every vehicle type of a benchmark response, as (vehicle type, percent)
+fn success_rates(response: &Value) -> Vec<(String, f64)> {
+    response["aggregate_stats"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|stats| {
+            let vehicle_type = stats["vehicle_type"].as_str().unwrap_or("?").to_string();
+            (vehicle_type, stats["success_rate"].as_f64().unwrap_or(0.0))
+        })
+        .collect()
+}
+
+fn local_checks(seed: u64, thresholds: &Thresholds) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let scenario = Scenario::default_exam();
+    checks.push(match scenario.validate() {
+        Ok(()) => Check::new("escenario por defecto", true, format!("{} vehículos", scenario.vehicles.len())),
+        Err(e) => Check::error("escenario por defecto", e),
+    });
+
+    for vehicle_type in VehicleType::ALL {
+        let name = format!("simulación {}", vehicle_type.id());
+        match simulate_locally(&simulation_json(vehicle_type, seed)) {
+            Ok((response, elapsed)) => {
+                // Timeouts are fine for one start, the benchmark success rates judge arrivals
+                let termination = &response["vehicles"][0]["metrics"]["termination"];
+                let finished = termination == "Arrived" || termination == "Timeout";
+                checks.push(Check::new(format!("{} termina", name), finished, termination.to_string()));
+                checks.push(check_at_most(format!("{} tiempo", name), elapsed.as_secs_f64(), thresholds.max_simulation_seconds, "s"));
+            }
+            Err(e) => checks.push(Check::error(name, e)),
+        }
+    }
+
+    match benchmark_locally(&benchmark_json(3, seed)) {
+        Ok((response, elapsed)) => {
+            for (vehicle_type, rate) in success_rates(&response) {
+                checks.push(check_at_least(format!("benchmark {} éxito", vehicle_type), rate, thresholds.min_success_rate, "%"));
+            }
+            checks.push(check_at_most("benchmark tiempo", elapsed.as_secs_f64(), thresholds.max_benchmark_seconds, "s"));
+        }
+        Err(e) => checks.push(Check::error("benchmark", e)),
+    }
+
+    checks.push(membership_png_check(thresholds.min_png_bytes));
+    checks
+}
+
+/// Export the first input of the Standard controller as PNG into a temporary directory, removed afterwards
+fn membership_png_check(min_bytes: u64) -> Check {
+    let dir = env::temp_dir().join(format!("fuzzy_nav_smoke_{}", std::process::id()));
+    let path = dir.join("membership.png");
+    let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+    let variable = &controller.fuzzy_system().input_variables[0];
+
+    let written = fs::create_dir_all(&dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            membership_export::export_variable_memberships(variable, &path.to_string_lossy(), ImageFormat::Png)
+                .map_err(|e| e.to_string())
+        })
+        .and_then(|_| fs::metadata(&path).map_err(|e| e.to_string()));
+    let _ = fs::remove_dir_all(&dir);
+
+    match written {
+        Ok(metadata) => Check::new(
+            "PNG de pertenencia",
+            metadata.len() >= min_bytes,
+            format!("{} bytes (mín {} bytes)", metadata.len(), min_bytes),
+        ),
+        Err(e) => Check::error("PNG de pertenencia", e),
+    }
+}
+
+/// JSON answer of a request to a running API, an error for transport failures and non-2xx statuses
+fn fetch(request: reqwest::blocking::RequestBuilder) -> Result<Value, String> {
+    let response = request.send().map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, response.text().unwrap_or_default()));
+    }
+    response.json().map_err(|e| e.to_string())
+}
+
+fn remote_checks(url: &str, seed: u64, thresholds: &Thresholds) -> Vec<Check> {
+    let url = url.trim_end_matches('/');
+    let client = match reqwest::blocking::Client::builder().timeout(Duration::from_secs(300)).build() {
+        Ok(client) => client,
+        Err(e) => return vec![Check::error("cliente HTTP", e)],
+    };
+    let mut checks = Vec::new();
+
+    checks.push(match fetch(client.get(format!("{}/health", url))) {
+        Ok(health) => Check::new("remoto /health", health["status"] == "healthy", health["version"].to_string()),
+        Err(e) => Check::error("remoto /health", e),
+    });
+
+    for vehicle_type in VehicleType::ALL {
+        let name = format!("remoto simulación {}", vehicle_type.id());
+        let request = simulation_json(vehicle_type, seed);
+        let start = Instant::now();
+        let remote = match fetch(client.post(format!("{}/api/simulate", url)).json(&request)) {
+            Ok(remote) => remote,
+            Err(e) => {
+                checks.push(Check::error(name, e));
+                continue;
+            }
+        };
+        checks.push(check_at_most(format!("{} tiempo", name), start.elapsed().as_secs_f64(), thresholds.max_simulation_seconds, "s"));
+        match simulate_locally(&request) {
+            Ok((local, _)) => {
+                for pointer in ["/vehicles/0/metrics/success", "/vehicles/0/metrics/arrival_time", "/vehicles/0/metrics/distance_traveled"] {
+                    checks.push(check_matches(&name, &local, &remote, pointer, thresholds.tolerance));
+                }
+            }
+            Err(e) => checks.push(Check::error(format!("{} referencia local", name), e)),
+        }
+    }
+
+    let request = benchmark_json(2, seed);
+    let start = Instant::now();
+    match fetch(client.post(format!("{}/api/benchmark", url)).json(&request)) {
+        Ok(remote) => {
+            checks.push(check_at_most("remoto benchmark tiempo", start.elapsed().as_secs_f64(), thresholds.max_benchmark_seconds, "s"));
+            match benchmark_locally(&request) {
+                Ok((local, _)) => {
+                    for index in 0..success_rates(&local).len() {
+                        for field in ["vehicle_type", "successes", "avg_arrival_time"] {
+                            let pointer = format!("/aggregate_stats/{}/{}", index, field);
+                            checks.push(check_matches("remoto benchmark", &local, &remote, &pointer, thresholds.tolerance));
+                        }
+                    }
+                }
+                Err(e) => checks.push(Check::error("remoto benchmark referencia local", e)),
+            }
+        }
+        Err(e) => checks.push(Check::error("remoto benchmark", e)),
+    }
+
+    checks
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    // Warn by default, every arrival is an info event
+    if let Err(e) = logging::init_cli(logging::log_level_arg(&args).or(Some("warn"))) {
+        eprintln!("\n❌ Error: --log-level inválido: {}", e);
+        std::process::exit(1);
+    }
+    let (thresholds, seed) = match Thresholds::from_args(&args).and_then(|t| Ok((t, parse_arg(&args, "seed")?.unwrap_or(42)))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("\n❌ Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("\n╔══════════════════════════════════════════════════════╗");
+    println!("║   SMOKE TEST                                         ║");
+    println!("╚══════════════════════════════════════════════════════╝\n");
+    println!("Seed: {}, {:?}\n", seed, thresholds);
+
+    let mut checks = local_checks(seed, &thresholds);
+    if let Some(url) = arg_value(&args, "url") {
+        println!("API: {}\n", url);
+        checks.extend(remote_checks(url, seed, &thresholds));
+    }
+
+    let (failed, report) = summarize(&checks);
+    println!("{}", report);
+    if failed > 0 {
+        eprintln!("\n❌ {} checks fallaron", failed);
+        std::process::exit(1);
+    }
+    println!("\n✓ Smoke test correcto");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("smoke").chain(list.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_thresholds_read_their_flags() {
+        assert_eq!(Thresholds::from_args(&args(&[])).unwrap(), Thresholds::default());
+
+        let thresholds = Thresholds::from_args(&args(&["--min-success-rate=90", "--min-png-bytes=100"])).unwrap();
+        assert_eq!(thresholds.min_success_rate, 90.0);
+        assert_eq!(thresholds.min_png_bytes, 100);
+        assert_eq!(thresholds.max_benchmark_seconds, Thresholds::default().max_benchmark_seconds);
+
+        let error = Thresholds::from_args(&args(&["--max-sim-seconds=rápido"])).unwrap_err();
+        assert!(error.contains("--max-sim-seconds"), "{}", error);
+    }
+
+    #[test]
+    fn test_bounds_include_their_limit() {
+        assert!(check_at_least("éxito", 50.0, 50.0, "%").passed);
+        assert!(!check_at_least("éxito", 49.9, 50.0, "%").passed);
+        assert!(check_at_most("tiempo", 2.0, 2.0, "s").passed);
+        assert!(!check_at_most("tiempo", 2.1, 2.0, "s").passed);
+    }
+
+    #[test]
+    fn test_matches_compare_numbers_within_tolerance_and_other_values_exactly() {
+        let local = json!({ "time": 100.0, "success": true, "type": "Heavy" });
+        let close = json!({ "time": 100.00001, "success": true, "type": "Heavy" });
+        let far = json!({ "time": 101.0, "success": false, "type": "Agile" });
+
+        assert!(check_matches("sim", &local, &close, "/time", 1e-6).passed);
+        assert!(!check_matches("sim", &local, &far, "/time", 1e-6).passed);
+        assert!(check_matches("sim", &local, &far, "/time", 0.02).passed);
+        assert!(check_matches("sim", &local, &close, "/success", 0.0).passed);
+        assert!(!check_matches("sim", &local, &far, "/success", 0.0).passed);
+        assert!(!check_matches("sim", &local, &far, "/type", 0.0).passed);
+        // A field missing on both sides is not a match
+        assert!(!check_matches("sim", &local, &close, "/missing", 0.0).passed);
+    }
+
+    #[test]
+    fn test_summary_counts_failures_and_names_them() {
+        let checks = [
+            Check::new("simulación Heavy", true, "Arrived"),
+            Check::new("benchmark Barco éxito", false, "33.33% (mín 50.00%)"),
+        ];
+        let (failed, report) = summarize(&checks);
+        assert_eq!(failed, 1);
+        assert!(report.contains("✗ benchmark Barco éxito: 33.33% (mín 50.00%)"), "{}", report);
+        assert!(report.contains("1/2 checks correctos"), "{}", report);
+    }
+
+    #[test]
+    fn test_success_rates_of_a_local_benchmark() {
+        let (response, _) = benchmark_locally(&json!({ "iterations": 1, "vehicle_types": ["Agile"], "seed": 7, "max_time": 5.0, "force": true })).unwrap();
+        let rates = success_rates(&response);
+        assert_eq!(rates.len(), 1);
+        assert!((0.0..=100.0).contains(&rates[0].1));
+    }
+}
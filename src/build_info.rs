@@ -0,0 +1,90 @@
+// Build metadata embedded in exported results, to tell which code produced which numbers
+
+use serde::{Deserialize, Serialize};
+
+/// Crate version of this build
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit this build was compiled from, set by build.rs
+pub const GIT_HASH: &str = env!("FUZZY_NAV_GIT_HASH");
+
+/// Cargo profile of this build ("debug" or "release"), set by build.rs
+pub const BUILD_PROFILE: &str = env!("FUZZY_NAV_BUILD_PROFILE");
+
+/// Every cargo feature of the crate, and whether this build has it
+const FEATURES: [(&str, bool); 8] = [
+    ("api", cfg!(feature = "api")),
+    ("cli", cfg!(feature = "cli")),
+    ("wasm", cfg!(feature = "wasm")),
+    ("python", cfg!(feature = "python")),
+    ("arrow", cfg!(feature = "arrow")),
+    ("memory", cfg!(feature = "memory")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("storage", cfg!(feature = "storage")),
+];
+
+/// Code and machine that produced a result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildMeta {
+    pub crate_version: String,
+    /// Short commit hash, `-dirty` with uncommitted changes, "unknown" when built outside a git checkout
+    pub git_hash: String,
+    pub build_profile: String,
+    /// Enabled cargo features
+    pub features: Vec<String>,
+    /// Threads the result was computed on
+    pub threads: usize,
+    /// Operating system and CPU architecture, as in `std::env::consts`
+    pub os: String,
+    pub arch: String,
+}
+
+impl BuildMeta {
+    /// Metadata of this build, for a result computed on `threads` threads
+    pub fn current(threads: usize) -> Self {
+        Self {
+            crate_version: CRATE_VERSION.to_string(),
+            git_hash: GIT_HASH.to_string(),
+            build_profile: BUILD_PROFILE.to_string(),
+            features: FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.to_string()).collect(),
+            threads,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+
+    /// One line for console summaries
+    pub fn summary(&self) -> String {
+        let features = if self.features.is_empty() { "-".to_string() } else { self.features.join(",") };
+        format!(
+            "v{} ({}, {}, features {}) on {}/{}, {} thread{}",
+            self.crate_version,
+            self.git_hash,
+            self.build_profile,
+            features,
+            self.os,
+            self.arch,
+            self.threads,
+            if self.threads == 1 { "" } else { "s" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_meta_describes_this_build() {
+        let meta = BuildMeta::current(4);
+        assert_eq!(meta.crate_version, env!("CARGO_PKG_VERSION"));
+        assert!(!meta.git_hash.is_empty());
+        assert_eq!(meta.build_profile, if cfg!(debug_assertions) { "debug" } else { "release" });
+        assert_eq!(meta.features.contains(&"api".to_string()), cfg!(feature = "api"));
+        assert_eq!(meta.os, std::env::consts::OS);
+
+        let summary = meta.summary();
+        assert!(summary.starts_with(&format!("v{} ({}", CRATE_VERSION, meta.git_hash)), "{}", summary);
+        assert!(summary.ends_with("4 threads"), "{}", summary);
+    }
+}
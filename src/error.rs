@@ -0,0 +1,183 @@
+// Error types of the library
+
+use thiserror::Error;
+
+use crate::simulation::migrations::{OLDEST_SCHEMA_VERSION, SCHEMA_VERSION};
+use crate::simulation::{Convergence, PreflightReport};
+
+/// Any error returned by the library
+#[derive(Debug, Error)]
+pub enum FuzzyNavError {
+    #[error(transparent)]
+    Fuzzy(#[from] FuzzyError),
+    #[error(transparent)]
+    Map(#[from] MapError),
+    #[error(transparent)]
+    Simulation(#[from] SimulationError),
+    #[error(transparent)]
+    Serialization(#[from] SerializationError),
+}
+
+impl From<serde_json::Error> for FuzzyNavError {
+    fn from(error: serde_json::Error) -> Self {
+        FuzzyNavError::Serialization(SerializationError::Json(error))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<parquet::errors::ParquetError> for FuzzyNavError {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        FuzzyNavError::Serialization(SerializationError::Parquet(error))
+    }
+}
+
+#[cfg(feature = "xlsx")]
+impl From<rust_xlsxwriter::XlsxError> for FuzzyNavError {
+    fn from(error: rust_xlsxwriter::XlsxError) -> Self {
+        FuzzyNavError::Serialization(SerializationError::Xlsx(error))
+    }
+}
+
+#[cfg(feature = "storage")]
+impl From<rusqlite::Error> for FuzzyNavError {
+    fn from(error: rusqlite::Error) -> Self {
+        FuzzyNavError::Serialization(SerializationError::Database(error))
+    }
+}
+
+/// Invalid fuzzy system definitions or queries
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FuzzyError {
+    #[error("{function} membership function requires {requirement}")]
+    InvalidMembershipParameters {
+        function: &'static str,
+        requirement: &'static str,
+    },
+    #[error("Input variable '{variable}' not found in '{system}'")]
+    UnknownInputVariable { variable: String, system: String },
+    #[error("Both surface axes use '{0}'")]
+    SameSurfaceAxis(String),
+    #[error("Surface resolution must be at least 1")]
+    ZeroResolution,
+    #[error("Rule index {index} out of range, the system has {rules} rules")]
+    UnknownRule { index: usize, rules: usize },
+}
+
+/// Invalid map geometry
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MapError {
+    #[error("Map dimensions must be positive, got {width}x{height}")]
+    InvalidDimensions { width: f64, height: f64 },
+    #[error("Target ({x}, {y}) is outside the {width}x{height} map")]
+    TargetOutOfBounds { x: f64, y: f64, width: f64, height: f64 },
+}
+
+/// Invalid simulation or benchmark parameters
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SimulationError {
+    #[error("Unknown vehicle type: {0}. Valid types: heavy, standard, agile, ultra_agile")]
+    UnknownVehicleType(String),
+    #[error("At least one vehicle type must be specified")]
+    NoVehicles,
+    #[error("dt must be positive, got {0}")]
+    InvalidTimeStep(f64),
+    #[error("dt {dt} is above the stability limit of {vehicle}, use dt <= {suggested} or auto_dt")]
+    UnstableTimeStep { dt: f64, vehicle: String, suggested: f64 },
+    #[error("Number of iterations must be greater than 0")]
+    NoIterations,
+    #[error("Give either iterations or duration_seconds, not both")]
+    IterationsAndDuration,
+    #[error("Benchmark jobs run a fixed number of iterations, drop duration_seconds and convergence")]
+    JobNeedsIterationCount,
+    #[error("Benchmark duration must be positive and at most {max} s, got {seconds}")]
+    InvalidDuration { seconds: f64, max: f64 },
+    #[error("Confidence level must be between 0 and 1, got {0}")]
+    InvalidConfidence(f64),
+    #[error("Unknown arrival criteria: {0}. Valid presets: strict, normal, loose")]
+    UnknownCriteria(String),
+    #[error("Grid must have between 1 and {max} cells, got {columns}x{rows}")]
+    InvalidGridSize { columns: usize, rows: usize, max: usize },
+    #[error("Vehicle state must be finite")]
+    NonFiniteState,
+    #[error("Vehicle {field} must be positive and finite, got {value}")]
+    InvalidCharacteristic { field: &'static str, value: f64 },
+    #[error("At least one start heading distribution must be specified")]
+    NoHeadings,
+    #[error("Fixed start heading must be finite, got {0}")]
+    InvalidHeading(f64),
+    #[error("Real-time speed must be positive and finite, got {0}")]
+    InvalidSpeed(f64),
+    #[error("Formation spacing must be positive and finite, got {0}")]
+    InvalidFormationSpacing(f64),
+    #[error("max_time must be positive, got {0}")]
+    InvalidMaxTime(f64),
+    #[error("Velocity fraction must be in (0, 1], got {0}")]
+    InvalidVelocityFraction(f64),
+    #[error("Dropout rate must be finite and non-negative and durations positive and ordered, got {probability_per_minute}/min for {shortest}..{longest} s")]
+    InvalidDropout { probability_per_minute: f64, shortest: f64, longest: f64 },
+    #[error("Vehicle id '{0}' is empty or used by another vehicle")]
+    InvalidVehicleId(String),
+    #[error("Got {ids} vehicle ids for {vehicles} vehicles")]
+    VehicleIdCount { ids: usize, vehicles: usize },
+    #[error("Start position ({x}, {y}) is outside the map")]
+    StartOutOfBounds { x: f64, y: f64 },
+    #[error("Gaussian start sigma fraction must be positive and finite, got {0}")]
+    InvalidStartSigma(f64),
+    #[error("Stratified start needs at least one bin")]
+    NoStartBins,
+    #[error("Disabled rule index {index} out of range, the controller has {rules} rules")]
+    UnknownRule { index: usize, rules: usize },
+    #[error("Control cache steps must be positive and finite, got {0}")]
+    InvalidQuantization(f64),
+    #[error("Relative distance scale needs a positive and finite map diagonal, got {0}")]
+    InvalidDistanceScale(f64),
+    #[error("Elliptical arrival gate semi-axes must be positive and finite, got {along} along and {across} across")]
+    InvalidArrivalGate { along: f64, across: f64 },
+    #[error("Initial condition buckets need distance or heading error edges")]
+    NoBucketEdges,
+    #[error("Bucket edges must be finite and strictly increasing, got {0:?}")]
+    InvalidBucketEdges(Vec<f64>),
+    #[error("Arrival funnel needs at least one time bin")]
+    NoFunnelBins,
+    #[error("Convergence needs a positive batch size and minimum batch count and positive finite thresholds, got {0:?}")]
+    InvalidConvergence(Convergence),
+    #[error("Give either convergence or duration_seconds, not both")]
+    ConvergenceAndDuration,
+    #[error("A robustness sweep needs at least one disturbance level")]
+    NoRobustnessLevels,
+    #[error("A robustness request may run at most {max} simulations, got {runs}")]
+    TooManyRobustnessRuns { runs: usize, max: usize },
+    #[error("Playback needs a frame rate in (0, {max_fps}], a positive finite speed and at most {max_frames} frames over max_time, got {fps} fps at {speed}x")]
+    InvalidPlayback { fps: f64, speed: f64, max_fps: f64, max_frames: usize },
+    #[error("Repeats must be between 1 and {max}, got {repeats}")]
+    InvalidRepeats { repeats: usize, max: usize },
+    #[error("{0}\nThe controller looks broken, force the run to start it anyway")]
+    PreflightFailed(PreflightReport),
+}
+
+/// Scenarios or results that could not be read or written
+#[derive(Debug, Error)]
+pub enum SerializationError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Missing field '{0}'")]
+    MissingField(&'static str),
+    /// File access failed, the message of the underlying error
+    #[error("Could not access '{path}': {message}")]
+    File { path: String, message: String },
+    #[error("Line {line} of the trajectory stream is invalid: {message}")]
+    InvalidStreamLine { line: usize, message: String },
+    /// A file newer than this build, or that does not match its version once upgraded
+    #[error("Could not read a schema version {version} file (this build reads versions {oldest} to {current}): {message}",
+        oldest = OLDEST_SCHEMA_VERSION, current = SCHEMA_VERSION)]
+    Schema { version: u32, message: String },
+    #[cfg(feature = "arrow")]
+    #[error("Parquet export failed: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "storage")]
+    #[error("Run database failed: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[cfg(feature = "xlsx")]
+    #[error("Excel export failed: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+}
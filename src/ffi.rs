@@ -0,0 +1,331 @@
+//! C ABI of the navigation controller, enabled by the `ffi` feature
+//!
+//! Lets a simulator written in another language call only the fuzzy
+//! controller. The declarations are in `include/fuzzy_nav.h`, regenerated by
+//! build.rs with cbindgen on every `--features ffi` build; link against the
+//! `cdylib` (`libexamen_parcial.so`, `.dylib` or `.dll`). No panic unwinds
+//! across the boundary, it is reported as `FUZZY_NAV_STATUS_PANIC` instead.
+//!
+//! The same calls a C caller makes:
+//!
+//! ```
+//! use examen_parcial::ffi::*;
+//!
+//! let ctrl = fuzzy_nav_controller_create(FuzzyNavVehicleType::Standard as u32);
+//! assert!(!ctrl.is_null());
+//!
+//! let (mut angular, mut velocity) = (0.0, 0.0);
+//! // 500 units away, target 0.5 rad counterclockwise, at 10% of the max velocity
+//! let status = unsafe {
+//!     fuzzy_nav_controller_compute(ctrl, 500.0, 0.5, 0.1, &mut angular, &mut velocity)
+//! };
+//! assert_eq!(status, FuzzyNavStatus::Ok as i32);
+//! assert!(angular > 0.0);
+//!
+//! unsafe { fuzzy_nav_controller_destroy(ctrl) };
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::map::{Map, Point};
+use crate::navigation::{build_observation, NavigationController};
+use crate::units::Radians;
+use crate::vehicle::{create_vehicle_preset, VehicleState, VehicleType};
+
+/// Opaque controller handle, owned by the caller until `fuzzy_nav_controller_destroy`
+pub struct Controller {
+    inner: NavigationController,
+}
+
+/// Vehicle type codes accepted by `fuzzy_nav_controller_create`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzyNavVehicleType {
+    Heavy = 0,
+    Standard = 1,
+    Agile = 2,
+    UltraAgile = 3,
+}
+
+/// Status codes returned by `fuzzy_nav_controller_compute` and `fuzzy_nav_controller_observe`
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzyNavStatus {
+    Ok = 0,
+    /// The controller or an output pointer is null
+    NullPointer = -1,
+    /// An input is NaN or infinite
+    NonFiniteInput = -2,
+    /// The controller panicked, the handle should be destroyed
+    Panic = -3,
+    /// The map has non-positive dimensions or its target is outside it
+    InvalidMap = -4,
+}
+
+/// Map a vehicle navigates on, the target of `Map::new` (arrival heading 90°)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuzzyNavMap {
+    pub width: f64,
+    pub height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+}
+
+/// Vehicle state, `angle` in radians counterclockwise from +x
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuzzyNavState {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+    pub velocity: f64,
+}
+
+/// Controller inputs of a vehicle state, the arguments of `fuzzy_nav_controller_compute`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuzzyNavObservation {
+    pub distance: f64,
+    /// Radians towards the approach point
+    pub angular_error: f64,
+    pub velocity_rel: f64,
+    /// Point the controller steers to, the target itself when far away
+    pub approach_x: f64,
+    pub approach_y: f64,
+}
+
+/// Create the controller of a vehicle type (`FuzzyNavVehicleType`)
+///
+/// Returns null for an unknown vehicle type.
+#[no_mangle]
+pub extern "C" fn fuzzy_nav_controller_create(vehicle_type: u32) -> *mut Controller {
+    let Some(&vehicle_type) = VehicleType::ALL.get(vehicle_type as usize) else {
+        return ptr::null_mut();
+    };
+    panic::catch_unwind(|| {
+        let inner = NavigationController::new(&create_vehicle_preset(vehicle_type));
+        Box::into_raw(Box::new(Controller { inner }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Evaluate the controller
+///
+/// `distance` in map units, `angular_error` in radians (positive when the
+/// target is counterclockwise from the heading), `velocity_rel` as a fraction
+/// of the max velocity. On `FUZZY_NAV_STATUS_OK` writes the angular adjustment
+/// (rad/s) and the velocity adjustment (always 0, velocity is constant) to the
+/// outputs, on any other status leaves them untouched.
+///
+/// # Safety
+///
+/// `ctrl` must be null or a live handle from `fuzzy_nav_controller_create`,
+/// the outputs null or valid for writing an `f64`.
+#[no_mangle]
+pub unsafe extern "C" fn fuzzy_nav_controller_compute(
+    ctrl: *const Controller,
+    distance: f64,
+    angular_error: f64,
+    velocity_rel: f64,
+    out_angular: *mut f64,
+    out_velocity: *mut f64,
+) -> i32 {
+    if ctrl.is_null() || out_angular.is_null() || out_velocity.is_null() {
+        return FuzzyNavStatus::NullPointer as i32;
+    }
+    if !(distance.is_finite() && angular_error.is_finite() && velocity_rel.is_finite()) {
+        return FuzzyNavStatus::NonFiniteInput as i32;
+    }
+
+    // SAFETY: non-null and, per the contract, a live handle
+    let controller = unsafe { &(*ctrl).inner };
+    match panic::catch_unwind(AssertUnwindSafe(|| controller.compute_control(distance, Radians(angular_error), velocity_rel))) {
+        Ok((angular, velocity)) => {
+            // SAFETY: non-null and, per the contract, valid for writes
+            unsafe {
+                out_angular.write(angular.0);
+                out_velocity.write(velocity);
+            }
+            FuzzyNavStatus::Ok as i32
+        }
+        Err(_) => FuzzyNavStatus::Panic as i32,
+    }
+}
+
+/// Observe a vehicle state as the simulation does before evaluating the controller
+///
+/// Uses `navigation::build_observation` with the characteristics of the
+/// controller, so passing the observation to `fuzzy_nav_controller_compute`
+/// steers exactly like a simulation step. On `FUZZY_NAV_STATUS_OK` writes the
+/// observation to `out`, on any other status leaves it untouched.
+///
+/// # Safety
+///
+/// `ctrl` must be null or a live handle from `fuzzy_nav_controller_create`,
+/// `out` null or valid for writing a `FuzzyNavObservation`.
+#[no_mangle]
+pub unsafe extern "C" fn fuzzy_nav_controller_observe(
+    ctrl: *const Controller,
+    map: FuzzyNavMap,
+    state: FuzzyNavState,
+    out: *mut FuzzyNavObservation,
+) -> i32 {
+    if ctrl.is_null() || out.is_null() {
+        return FuzzyNavStatus::NullPointer as i32;
+    }
+    let inputs = [map.width, map.height, map.target_x, map.target_y, state.x, state.y, state.angle, state.velocity];
+    if !inputs.iter().all(|v| v.is_finite()) {
+        return FuzzyNavStatus::NonFiniteInput as i32;
+    }
+    let Ok(map) = Map::try_new(map.width, map.height, map.target_x, map.target_y) else {
+        return FuzzyNavStatus::InvalidMap as i32;
+    };
+
+    // SAFETY: non-null and, per the contract, a live handle
+    let controller = unsafe { &(*ctrl).inner };
+    let observe = || {
+        let state = VehicleState { position: Point::new(state.x, state.y), angle: Radians(state.angle), velocity: state.velocity };
+        build_observation(&state, &map, controller.characteristics())
+    };
+    match panic::catch_unwind(AssertUnwindSafe(observe)) {
+        Ok(inputs) => {
+            let observation = FuzzyNavObservation {
+                distance: inputs.distance_to_target,
+                angular_error: inputs.angular_error.0,
+                velocity_rel: inputs.velocity_relative,
+                approach_x: inputs.approach_point.x,
+                approach_y: inputs.approach_point.y,
+            };
+            // SAFETY: non-null and, per the contract, valid for writes
+            unsafe { out.write(observation) };
+            FuzzyNavStatus::Ok as i32
+        }
+        Err(_) => FuzzyNavStatus::Panic as i32,
+    }
+}
+
+/// Free a controller, null is a no-op
+///
+/// # Safety
+///
+/// `ctrl` must be null or a handle from `fuzzy_nav_controller_create` not
+/// destroyed yet; it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fuzzy_nav_controller_destroy(ctrl: *mut Controller) {
+    if ctrl.is_null() {
+        return;
+    }
+    // SAFETY: per the contract, the handle came from Box::into_raw and is freed once
+    let controller = unsafe { Box::from_raw(ctrl) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(controller)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plain heap handles and stack outputs only, so `cargo miri test --features ffi ffi::` runs them
+
+    #[test]
+    fn test_create_every_vehicle_type() {
+        let codes = [
+            FuzzyNavVehicleType::Heavy,
+            FuzzyNavVehicleType::Standard,
+            FuzzyNavVehicleType::Agile,
+            FuzzyNavVehicleType::UltraAgile,
+        ];
+        for (code, vehicle_type) in codes.into_iter().zip(VehicleType::ALL) {
+            let ctrl = fuzzy_nav_controller_create(code as u32);
+            assert!(!ctrl.is_null(), "{:?}", vehicle_type);
+            unsafe { fuzzy_nav_controller_destroy(ctrl) };
+        }
+        assert!(fuzzy_nav_controller_create(4).is_null());
+        assert!(fuzzy_nav_controller_create(u32::MAX).is_null());
+    }
+
+    #[test]
+    fn test_compute_matches_rust_controller() {
+        let ctrl = fuzzy_nav_controller_create(FuzzyNavVehicleType::Agile as u32);
+        let rust = NavigationController::new(&create_vehicle_preset(VehicleType::Agile));
+
+        for (distance, angular_error) in [(800.0, -1.2), (300.0, 0.1), (40.0, 2.5)] {
+            let (mut angular, mut velocity) = (f64::NAN, f64::NAN);
+            let status = unsafe { fuzzy_nav_controller_compute(ctrl, distance, angular_error, 0.1, &mut angular, &mut velocity) };
+            assert_eq!(status, FuzzyNavStatus::Ok as i32);
+            assert_eq!((Radians(angular), velocity), rust.compute_control(distance, Radians(angular_error), 0.1));
+        }
+        unsafe { fuzzy_nav_controller_destroy(ctrl) };
+    }
+
+    #[test]
+    fn test_compute_errors_leave_outputs_untouched() {
+        let ctrl = fuzzy_nav_controller_create(FuzzyNavVehicleType::Heavy as u32);
+        let (mut angular, mut velocity) = (7.0, 7.0);
+
+        let status = unsafe { fuzzy_nav_controller_compute(ptr::null(), 500.0, 0.0, 0.1, &mut angular, &mut velocity) };
+        assert_eq!(status, FuzzyNavStatus::NullPointer as i32);
+        let status = unsafe { fuzzy_nav_controller_compute(ctrl, 500.0, 0.0, 0.1, ptr::null_mut(), &mut velocity) };
+        assert_eq!(status, FuzzyNavStatus::NullPointer as i32);
+        let status = unsafe { fuzzy_nav_controller_compute(ctrl, 500.0, f64::NAN, 0.1, &mut angular, &mut velocity) };
+        assert_eq!(status, FuzzyNavStatus::NonFiniteInput as i32);
+        let status = unsafe { fuzzy_nav_controller_compute(ctrl, f64::INFINITY, 0.0, 0.1, &mut angular, &mut velocity) };
+        assert_eq!(status, FuzzyNavStatus::NonFiniteInput as i32);
+        assert_eq!((angular, velocity), (7.0, 7.0));
+
+        unsafe {
+            fuzzy_nav_controller_destroy(ctrl);
+            fuzzy_nav_controller_destroy(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_observe_then_compute_steers_like_a_simulation_step() {
+        use crate::simulation::SimulationBuilder;
+
+        let map = FuzzyNavMap { width: 1000.0, height: 800.0, target_x: 500.0, target_y: 700.0 };
+        let mut sim = SimulationBuilder::new(Map::new(map.width, map.height, map.target_x, map.target_y), VehicleType::Agile)
+            .start_position(Point::new(480.0, 600.0))
+            .start_angle(Radians(2.0))
+            .build();
+        let vehicle = &sim.vehicle.state;
+        let state = FuzzyNavState { x: vehicle.position.x, y: vehicle.position.y, angle: vehicle.angle.0, velocity: vehicle.velocity };
+
+        let ctrl = fuzzy_nav_controller_create(FuzzyNavVehicleType::Agile as u32);
+        let mut observation = FuzzyNavObservation { distance: 0.0, angular_error: 0.0, velocity_rel: 0.0, approach_x: 0.0, approach_y: 0.0 };
+        assert_eq!(unsafe { fuzzy_nav_controller_observe(ctrl, map, state, &mut observation) }, FuzzyNavStatus::Ok as i32);
+        let (mut angular, mut velocity) = (f64::NAN, f64::NAN);
+        let status = unsafe {
+            fuzzy_nav_controller_compute(ctrl, observation.distance, observation.angular_error, observation.velocity_rel, &mut angular, &mut velocity)
+        };
+        assert_eq!(status, FuzzyNavStatus::Ok as i32);
+
+        // Inside the approach corridor, so the approach point is not the target
+        sim.step();
+        let point = sim.trajectory.last().unwrap();
+        assert_eq!((Some(observation.approach_x), Some(observation.approach_y)), (point.approach_x, point.approach_y));
+        assert_ne!((observation.approach_x, observation.approach_y), (map.target_x, map.target_y));
+        assert_eq!(point.commanded_adjustment, Some(Radians(angular).to_degrees().0));
+
+        let status = unsafe { fuzzy_nav_controller_observe(ctrl, map, FuzzyNavState { angle: f64::NAN, ..state }, &mut observation) };
+        assert_eq!(status, FuzzyNavStatus::NonFiniteInput as i32);
+        for bad_map in [FuzzyNavMap { width: -1.0, ..map }, FuzzyNavMap { target_y: 900.0, ..map }] {
+            let status = unsafe { fuzzy_nav_controller_observe(ctrl, bad_map, state, &mut observation) };
+            assert_eq!(status, FuzzyNavStatus::InvalidMap as i32);
+        }
+        let status = unsafe { fuzzy_nav_controller_observe(ptr::null(), map, state, &mut observation) };
+        assert_eq!(status, FuzzyNavStatus::NullPointer as i32);
+        unsafe { fuzzy_nav_controller_destroy(ctrl) };
+    }
+
+    #[test]
+    fn test_header_declares_every_function() {
+        let header = include_str!("../include/fuzzy_nav.h");
+        for name in ["fuzzy_nav_controller_create", "fuzzy_nav_controller_compute", "fuzzy_nav_controller_observe", "fuzzy_nav_controller_destroy"] {
+            assert!(header.contains(&format!("{}(", name)), "{} missing, rebuild with --features ffi", name);
+        }
+        assert!(header.contains("FUZZY_NAV_STATUS_NON_FINITE_INPUT = -2"));
+        assert!(header.contains("FUZZY_NAV_STATUS_INVALID_MAP = -4"));
+    }
+}
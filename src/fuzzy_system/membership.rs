@@ -1,6 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FuzzyError;
 
 pub trait MembershipFunction {
     fn evaluate(&self, input: f64) -> f64;
+
+    /// `evaluate` of every input of `xs` into `out`, one call for a whole sweep
+    ///
+    /// Gives exactly the values of `evaluate`. Panics when the slices differ in length.
+    fn evaluate_slice(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len(), "membership sweep needs an output per input");
+        for (&x, y) in xs.iter().zip(out.iter_mut()) {
+            *y = self.evaluate(x);
+        }
+    }
+
+    /// Shape and parameters, `None` for functions without a serializable form
+    fn spec(&self) -> Option<MembershipSpec> {
+        None
+    }
+}
+
+/// Shape and parameters of a built-in membership function
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MembershipSpec {
+    Triangular { a: f64, b: f64, c: f64 },
+    Trapezoidal { a: f64, b: f64, c: f64, d: f64 },
+    Gaussian { mean: f64, sigma: f64 },
+    Sigmoidal { a: f64, c: f64 },
 }
 
 pub struct TriangularMembershipFunction {
@@ -16,19 +44,63 @@ impl MembershipFunction for TriangularMembershipFunction {
         } else if (input - self.b).abs() < f64::EPSILON {
             1.0
         } else if input < self.b {
-            let denominator = self.b - self.a;
-            if denominator.abs() < f64::EPSILON {
-                0.0
-            } else {
-                (input - self.a) / denominator
-            }
+            ramp(input, self.a, self.b)
+        } else {
+            ramp(input, self.c, self.b)
+        }
+    }
+
+    /// Both slopes are set up once for the whole slice, each input then takes the cases of `evaluate`
+    fn evaluate_slice(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len(), "membership sweep needs an output per input");
+        let rise = Ramp::new(self.a, self.b);
+        let fall = Ramp::new(self.c, self.b);
+        for (&x, y) in xs.iter().zip(out.iter_mut()) {
+            let slope = if x < self.b { rise.at(x) } else { fall.at(x) };
+            let peak = if (x - self.b).abs() < f64::EPSILON { 1.0 } else { slope };
+            *y = if x < self.a || x > self.c { 0.0 } else { peak };
+        }
+    }
+
+    fn spec(&self) -> Option<MembershipSpec> {
+        Some(MembershipSpec::Triangular { a: self.a, b: self.b, c: self.c })
+    }
+}
+
+/// Linear slope rising from 0 at `zero` to 1 at `one`, 0 for a vertical slope
+///
+/// Parameters are halved first so the spans of huge finite parameters do not
+/// overflow into `inf / inf`.
+fn ramp(input: f64, zero: f64, one: f64) -> f64 {
+    let span = one / 2.0 - zero / 2.0;
+    if span.abs() < f64::EPSILON / 2.0 {
+        0.0
+    } else {
+        ((input / 2.0 - zero / 2.0) / span).clamp(0.0, 1.0)
+    }
+}
+
+/// `ramp` with the span computed once, for the slice evaluations
+///
+/// Same operations in the same order as `ramp`, so the values are identical.
+struct Ramp {
+    half_zero: f64,
+    span: f64,
+    vertical: bool,
+}
+
+impl Ramp {
+    fn new(zero: f64, one: f64) -> Self {
+        let span = one / 2.0 - zero / 2.0;
+        Self { half_zero: zero / 2.0, span, vertical: span.abs() < f64::EPSILON / 2.0 }
+    }
+
+    #[inline(always)]
+    fn at(&self, input: f64) -> f64 {
+        if self.vertical {
+            0.0
         } else {
-            let denominator = self.c - self.b;
-            if denominator.abs() < f64::EPSILON {
-                0.0
-            } else {
-                (self.c - input) / denominator
-            }
+            ((input / 2.0 - self.half_zero) / self.span).clamp(0.0, 1.0)
         }
     }
 }
@@ -47,21 +119,26 @@ impl MembershipFunction for TrapezoidalMembershipFunction {
         } else if input >= self.b && input <= self.c {
             1.0
         } else if input < self.b {
-            let denominator = self.b - self.a;
-            if denominator.abs() < f64::EPSILON {
-                0.0
-            } else {
-                (input - self.a) / denominator
-            }
+            ramp(input, self.a, self.b)
         } else {
-            let denominator = self.d - self.c;
-            if denominator.abs() < f64::EPSILON {
-                0.0
-            } else {
-                (self.d - input) / denominator
-            }
+            ramp(input, self.d, self.c)
         }
     }
+
+    fn evaluate_slice(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len(), "membership sweep needs an output per input");
+        let rise = Ramp::new(self.a, self.b);
+        let fall = Ramp::new(self.d, self.c);
+        for (&x, y) in xs.iter().zip(out.iter_mut()) {
+            let slope = if x < self.b { rise.at(x) } else { fall.at(x) };
+            let plateau = if x >= self.b && x <= self.c { 1.0 } else { slope };
+            *y = if x < self.a || x > self.d { 0.0 } else { plateau };
+        }
+    }
+
+    fn spec(&self) -> Option<MembershipSpec> {
+        Some(MembershipSpec::Trapezoidal { a: self.a, b: self.b, c: self.c, d: self.d })
+    }
 }
 
 pub struct GaussianMembershipFunction {
@@ -71,8 +148,21 @@ pub struct GaussianMembershipFunction {
 
 impl MembershipFunction for GaussianMembershipFunction {
     fn evaluate(&self, input: f64) -> f64 {
-        let exponent = -((input - self.mean).powi(2)) / (2.0 * self.sigma.powi(2));
-        exponent.exp()
+        // Scaled before squaring, a tiny sigma would underflow to 0 / 0 at the mean
+        let z = (input - self.mean) / self.sigma;
+        (-0.5 * z * z).exp()
+    }
+
+    fn evaluate_slice(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len(), "membership sweep needs an output per input");
+        for (&x, y) in xs.iter().zip(out.iter_mut()) {
+            let z = (x - self.mean) / self.sigma;
+            *y = (-0.5 * z * z).exp();
+        }
+    }
+
+    fn spec(&self) -> Option<MembershipSpec> {
+        Some(MembershipSpec::Gaussian { mean: self.mean, sigma: self.sigma })
     }
 }
 
@@ -85,26 +175,60 @@ impl MembershipFunction for SigmoidalMembershipFunction {
     fn evaluate(&self, input: f64) -> f64 {
         1.0 / (1.0 + (-self.a * (input - self.c)).exp())
     }
+
+    fn spec(&self) -> Option<MembershipSpec> {
+        Some(MembershipSpec::Sigmoidal { a: self.a, c: self.c })
+    }
 }
 
 //helpers
 
+pub fn try_triangular(a: f64, b: f64, c: f64) -> Result<Box<TriangularMembershipFunction>, FuzzyError> {
+    let valid = a <= b && b <= c && a.is_finite() && c.is_finite();
+    if !valid {
+        return Err(FuzzyError::InvalidMembershipParameters { function: "Triangular", requirement: "finite a <= b <= c" });
+    }
+    Ok(Box::new(TriangularMembershipFunction { a, b, c }))
+}
+
+pub fn try_trapezoidal(a: f64, b: f64, c: f64, d: f64) -> Result<Box<TrapezoidalMembershipFunction>, FuzzyError> {
+    let valid = a <= b && b <= c && c <= d && a.is_finite() && d.is_finite();
+    if !valid {
+        return Err(FuzzyError::InvalidMembershipParameters { function: "Trapezoidal", requirement: "finite a <= b <= c <= d" });
+    }
+    Ok(Box::new(TrapezoidalMembershipFunction { a, b, c, d }))
+}
+
+pub fn try_gaussian(mean: f64, sigma: f64) -> Result<Box<GaussianMembershipFunction>, FuzzyError> {
+    let valid = sigma > 0.0 && sigma.is_finite() && mean.is_finite();
+    if !valid {
+        return Err(FuzzyError::InvalidMembershipParameters { function: "Gaussian", requirement: "finite mean and sigma > 0" });
+    }
+    Ok(Box::new(GaussianMembershipFunction { mean, sigma }))
+}
+
+pub fn try_sigmoidal(a: f64, c: f64) -> Result<Box<SigmoidalMembershipFunction>, FuzzyError> {
+    let valid = a.abs() > f64::EPSILON && a.is_finite() && c.is_finite();
+    if !valid {
+        return Err(FuzzyError::InvalidMembershipParameters { function: "Sigmoidal", requirement: "a != 0, both finite" });
+    }
+    Ok(Box::new(SigmoidalMembershipFunction { a, c }))
+}
+
+// Panicking shorthands for parameters known to be valid (controller definitions)
+
 pub fn triangular(a: f64, b: f64, c: f64) -> Box<TriangularMembershipFunction> {
-    assert!(a <= b && b <= c, "Triangular membership function requires a <= b <= c");
-    Box::new(TriangularMembershipFunction { a, b, c })
+    try_triangular(a, b, c).unwrap_or_else(|e| panic!("{}", e))
 }
 
 pub fn trapezoidal(a: f64, b: f64, c: f64, d: f64) -> Box<TrapezoidalMembershipFunction> {
-    assert!(a <= b && b <= c && c <= d, "Trapezoidal membership function requires a <= b <= c <= d");
-    Box::new(TrapezoidalMembershipFunction { a, b, c, d })
+    try_trapezoidal(a, b, c, d).unwrap_or_else(|e| panic!("{}", e))
 }
 
 pub fn gaussian(mean: f64, sigma: f64) -> Box<GaussianMembershipFunction> {
-    assert!(sigma > 0.0, "Gaussian membership function requires sigma > 0");
-    Box::new(GaussianMembershipFunction { mean, sigma })
+    try_gaussian(mean, sigma).unwrap_or_else(|e| panic!("{}", e))
 }
 
 pub fn sigmoidal(a: f64, c: f64) -> Box<SigmoidalMembershipFunction> {
-    assert!(a.abs() > f64::EPSILON, "Sigmoidal membership function requires a != 0");
-    Box::new(SigmoidalMembershipFunction { a, c })
-}
\ No newline at end of file
+    try_sigmoidal(a, c).unwrap_or_else(|e| panic!("{}", e))
+}
@@ -3,12 +3,17 @@ mod sets;
 mod variables;
 mod rules;
 mod system;
+mod surface;
 
 #[cfg(test)]
 mod tests;
 
-pub use membership::{gaussian, sigmoidal, trapezoidal, triangular, MembershipFunction};
+pub use membership::{
+    gaussian, sigmoidal, trapezoidal, triangular, try_gaussian, try_sigmoidal, try_trapezoidal, try_triangular,
+    MembershipFunction, MembershipSpec,
+};
 pub use sets::{FuzzySet, FuzzyOperation};
-pub use variables::{DefuzzificationMethod, Defuzzifier, LinguisticVariable};
+pub use variables::{DefuzzificationMethod, Defuzzifier, LinguisticVariable, CENTROID_STEPS};
 pub use rules::{Antecedent, Consequent, FuzzyRule, RuleOperator};
-pub use system::FuzzySystem;
\ No newline at end of file
+pub use system::{EvaluationTrace, FuzzySystem};
+pub use surface::{compute_control_surface, ControlSurface};
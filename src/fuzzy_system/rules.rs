@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
 
 
 
@@ -39,6 +40,8 @@ pub struct FuzzyRule {
     pub antecedents: Vec<Antecedent>,
     pub consequents: Vec<Consequent>,
     pub operator: RuleOperator,
+    /// Disabled rules stay in the system but never fire, for ablation studies
+    pub enabled: bool,
 }
 
 impl FuzzyRule {
@@ -51,19 +54,24 @@ impl FuzzyRule {
             antecedents,
             consequents,
             operator,
+            enabled: true,
         }
     }
 
-    pub fn evaluate(&self, inputs: &HashMap<String, HashMap<String, f64>>) -> f64 {
-        let mut degrees = Vec::new();
-
-        for antecedent in &self.antecedents {
-            if let Some(var_membership) = inputs.get(&antecedent.variable) {
-                if let Some(degree) = var_membership.get(&antecedent.set) {
-                    degrees.push(*degree);
-                }
-            }
-        }
+    pub fn evaluate(&self, inputs: &BTreeMap<String, BTreeMap<String, f64>>) -> f64 {
+        // An antecedent missing from the inputs is not satisfied at all, so it
+        // blocks an AND instead of being skipped
+        let degrees: Vec<f64> = self
+            .antecedents
+            .iter()
+            .map(|antecedent| {
+                inputs
+                    .get(&antecedent.variable)
+                    .and_then(|var_membership| var_membership.get(&antecedent.set))
+                    .copied()
+                    .unwrap_or(0.0)
+            })
+            .collect();
 
         if degrees.is_empty() {
             return 0.0;
@@ -80,4 +88,25 @@ impl FuzzyRule {
             }
         }
     }   
-}
\ No newline at end of file
+}
+
+impl fmt::Display for FuzzyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.operator {
+            RuleOperator::And => " AND ",
+            RuleOperator::Or => " OR ",
+        };
+        let antecedents: Vec<String> = self
+            .antecedents
+            .iter()
+            .map(|a| format!("{} is {}", a.variable, a.set))
+            .collect();
+        let consequents: Vec<String> = self
+            .consequents
+            .iter()
+            .map(|c| format!("{} is {}", c.variable, c.set))
+            .collect();
+
+        write!(f, "if {} then {}", antecedents.join(op), consequents.join(", "))
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use crate::fuzzy_system::MembershipFunction;
+use crate::fuzzy_system::{MembershipFunction, MembershipSpec};
 
 
 pub struct FuzzySet{
@@ -28,6 +28,16 @@ impl FuzzySet {
     pub fn evaluate(&self, input: f64) -> f64 {
         self.membership_function.evaluate(input)
     }
+
+    /// Membership of every input of `xs` into `out`, see `MembershipFunction::evaluate_slice`
+    pub fn evaluate_slice(&self, xs: &[f64], out: &mut [f64]) {
+        self.membership_function.evaluate_slice(xs, out)
+    }
+
+    /// Shape and parameters of the membership function, `None` for custom functions
+    pub fn spec(&self) -> Option<MembershipSpec> {
+        self.membership_function.spec()
+    }
 }
 
 pub struct FuzzyOperation;
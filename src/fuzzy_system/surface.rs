@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::error::FuzzyError;
+use crate::fuzzy_system::FuzzySystem;
+
+/// Defuzzified output of a system over a grid of two of its inputs
+#[derive(Debug, Clone)]
+pub struct ControlSurface {
+    pub x_variable: String,
+    pub y_variable: String,
+    pub output_variable: String,
+    /// Value of every other input during the sweep, in system order
+    pub fixed_inputs: Vec<(String, f64)>,
+    /// Cell centers along each axis, `resolution` cells covering the variable range
+    pub x_values: Vec<f64>,
+    pub y_values: Vec<f64>,
+    /// `values[j][i]` is the output at (`x_values[i]`, `y_values[j]`)
+    pub values: Vec<Vec<f64>>,
+}
+
+impl ControlSurface {
+    /// Minimum and maximum output over the grid
+    pub fn value_range(&self) -> (f64, f64) {
+        self.values.iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)))
+    }
+}
+
+/// Sweep two inputs of `system` over their ranges with the other inputs fixed
+///
+/// Inputs missing from `fixed_inputs` are held at the middle of their range.
+pub fn compute_control_surface(
+    system: &FuzzySystem,
+    x_var: &str,
+    y_var: &str,
+    fixed_inputs: &HashMap<String, f64>,
+    resolution: usize,
+) -> Result<ControlSurface, FuzzyError> {
+    let find = |name: &str| {
+        system.input_variables.iter()
+            .find(|var| var.name == name)
+            .ok_or_else(|| FuzzyError::UnknownInputVariable {
+                variable: name.to_string(),
+                system: system.name.clone(),
+            })
+    };
+    let x_variable = find(x_var)?;
+    let y_variable = find(y_var)?;
+    if x_var == y_var {
+        return Err(FuzzyError::SameSurfaceAxis(x_var.to_string()));
+    }
+    if resolution == 0 {
+        return Err(FuzzyError::ZeroResolution);
+    }
+
+    let cell_centers = |(min, max): (f64, f64)| -> Vec<f64> {
+        let step = (max - min) / resolution as f64;
+        (0..resolution).map(|i| min + (i as f64 + 0.5) * step).collect()
+    };
+    let x_values = cell_centers(x_variable.range);
+    let y_values = cell_centers(y_variable.range);
+
+    let mut inputs = HashMap::new();
+    let mut fixed = Vec::new();
+    for var in &system.input_variables {
+        if var.name == x_var || var.name == y_var {
+            continue;
+        }
+        let value = fixed_inputs.get(&var.name).copied().unwrap_or((var.range.0 + var.range.1) / 2.0);
+        inputs.insert(var.name.clone(), value);
+        fixed.push((var.name.clone(), value));
+    }
+
+    let values = y_values.iter()
+        .map(|&y| {
+            x_values.iter()
+                .map(|&x| {
+                    inputs.insert(x_var.to_string(), x);
+                    inputs.insert(y_var.to_string(), y);
+                    system.evaluate(&inputs).1
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(ControlSurface {
+        x_variable: x_var.to_string(),
+        y_variable: y_var.to_string(),
+        output_variable: system.output_variable.name.clone(),
+        fixed_inputs: fixed,
+        x_values,
+        y_values,
+        values,
+    })
+}
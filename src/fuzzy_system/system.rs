@@ -1,19 +1,21 @@
-use std::{collections::HashMap, fmt::Display};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
 use std::fmt;
 
-use crate::fuzzy_system::{DefuzzificationMethod, FuzzyRule, LinguisticVariable};
+use tracing::warn;
 
-// Conditional printing macro - only prints when CLI feature is enabled
-#[cfg(feature = "cli")]
-macro_rules! fuzzy_eprintln {
-    ($($arg:tt)*) => {
-        eprintln!($($arg)*)
-    };
-}
+use crate::error::FuzzyError;
+use crate::fuzzy_system::{DefuzzificationMethod, FuzzyRule, LinguisticVariable};
 
-#[cfg(not(feature = "cli"))]
-macro_rules! fuzzy_eprintln {
-    ($($arg:tt)*) => {};
+/// Intermediate results of one evaluation, used to explain the inference
+#[derive(Debug, Clone)]
+pub struct EvaluationTrace {
+    pub output_variable: String,
+    pub defuzzified_value: f64,
+    /// Firing strength of every rule, in rule order, 0 for disabled rules
+    pub rule_activations: Vec<f64>,
+    /// Activation level of every output set after aggregation (max over the rules that imply it), in name order
+    pub set_activations: BTreeMap<String, f64>,
 }
 
 pub struct FuzzySystem{
@@ -49,41 +51,68 @@ impl FuzzySystem {
         self.output_variable = variable;
     }
 
+    /// Enable or disable the rule at `index` (0-based, evaluation order)
+    ///
+    /// A disabled rule is skipped by the evaluation, its activation is 0, and
+    /// is still listed by `Display` with a `[disabled]` marker.
+    pub fn set_rule_enabled(&mut self, index: usize, enabled: bool) -> Result<(), FuzzyError> {
+        let rules = self.rules.len();
+        let rule = self.rules.get_mut(index).ok_or(FuzzyError::UnknownRule { index, rules })?;
+        rule.enabled = enabled;
+        Ok(())
+    }
+
     pub fn evaluate(&self, inputs: &HashMap<String, f64>) -> (String, f64){
+        let (name, value, _) = self.evaluate_with_activations(inputs);
+        (name, value)
+    }
+
+    /// Evaluate the system and also return the firing strength of every rule, in rule order
+    pub fn evaluate_with_activations(&self, inputs: &HashMap<String, f64>) -> (String, f64, Vec<f64>) {
+        let trace = self.evaluate_traced(inputs);
+        (trace.output_variable, trace.defuzzified_value, trace.rule_activations)
+    }
+
+    /// Evaluate the system keeping the rule and output set activations
+    pub fn evaluate_traced(&self, inputs: &HashMap<String, f64>) -> EvaluationTrace {
         // Validate that all required input variables are present
         for var in &self.input_variables {
             if !inputs.contains_key(&var.name) {
-                fuzzy_eprintln!("Warning: Input variable '{}' not found in inputs. Using default value 0.0", var.name);
+                warn!(system = %self.name, variable = %var.name, "Input variable not found in inputs, it will not fire any rule");
             }
         }
 
-        // Fuzzification phase
-        let mut fuzzyfied_inputs = HashMap::new();
+        // Fuzzification phase, ordered maps so nothing depends on the hasher
+        let mut fuzzyfied_inputs = BTreeMap::new();
         for var in &self.input_variables {
             if let Some(&value) = inputs.get(&var.name) {
-                // Validate input is within expected range
-                if value < var.range.0 || value > var.range.1 {
-                    fuzzy_eprintln!("Warning: Input '{}' = {} is outside expected range {:?}",
-                             var.name, value, var.range);
+                // Validate input is within expected range, circular inputs wrap into it
+                if !var.circular && (value < var.range.0 || value > var.range.1) {
+                    warn!(system = %self.name, variable = %var.name, value, range = ?var.range, "Input outside expected range");
                 }
                 fuzzyfied_inputs.insert(var.name.clone(), var.fuzzify(value));
             }
         }
 
         // Rule evaluation and aggregation phase
-        let mut activated_outputs: HashMap<String, f64> = HashMap::new();
+        let mut activated_outputs: BTreeMap<String, f64> = BTreeMap::new();
+        let mut rule_activations = Vec::with_capacity(self.rules.len());
         let mut any_rule_fired = false;
 
         for rule in &self.rules {
+            if !rule.enabled {
+                rule_activations.push(0.0);
+                continue;
+            }
             let degree = rule.evaluate(&fuzzyfied_inputs);
+            rule_activations.push(degree);
             if degree > f64::EPSILON {
                 any_rule_fired = true;
             }
             for consequent in &rule.consequents {
                 // Validate consequent references valid output set
                 if !self.output_variable.fuzzy_sets.iter().any(|s| s.name == consequent.set) {
-                    fuzzy_eprintln!("Warning: Consequent set '{}' not found in output variable '{}'",
-                             consequent.set, self.output_variable.name);
+                    warn!(system = %self.name, set = %consequent.set, output = %self.output_variable.name, "Consequent set not found in output variable");
                     continue;
                 }
                 let entry = activated_outputs.entry(consequent.set.clone()).or_insert(0.0);
@@ -92,7 +121,9 @@ impl FuzzySystem {
         }
 
         if !any_rule_fired {
-            fuzzy_eprintln!("Warning: No rules were activated for inputs {:?}", inputs);
+            // Inputs in name order, so the trace reads the same from run to run
+            let inputs: BTreeMap<_, _> = inputs.iter().collect();
+            warn!(system = %self.name, ?inputs, "No rules were activated");
         }
 
         // Defuzzification phase
@@ -101,7 +132,12 @@ impl FuzzySystem {
                 crate::fuzzy_system::Defuzzifier::centroid(&self.output_variable, &activated_outputs)
             }
         };
-        (self.output_variable.name.clone(), defuzzified_value)
+        EvaluationTrace {
+            output_variable: self.output_variable.name.clone(),
+            defuzzified_value,
+            rule_activations,
+            set_activations: activated_outputs,
+        }
     }
 }
 
@@ -111,7 +147,8 @@ impl Display for FuzzySystem {
 
         writeln!(f, "Input variables:")?;
         for var in &self.input_variables {
-            writeln!(f, "  - {} (range: {:?})", var.name, var.range)?;
+            let circular = if var.circular { ", circular" } else { "" };
+            writeln!(f, "  - {} (range: {:?}{})", var.name, var.range, circular)?;
             for set in &var.fuzzy_sets {
                 writeln!(f, "      · {}", set.name)?;
             }
@@ -125,22 +162,8 @@ impl Display for FuzzySystem {
 
         writeln!(f, "Rules:")?;
         for (i, rule) in self.rules.iter().enumerate() {
-            let antecedents: Vec<String> = rule
-                .antecedents
-                .iter()
-                .map(|a| format!("{} is {}", a.variable, a.set))
-                .collect();
-            let consequents: Vec<String> = rule
-                .consequents
-                .iter()
-                .map(|c| format!("{} is {}", c.variable, c.set))
-                .collect();
-            let op = match rule.operator {
-                crate::fuzzy_system::RuleOperator::And => "AND",
-                crate::fuzzy_system::RuleOperator::Or => "OR",
-            };
-
-            writeln!(f, "  {}: if {} {} then {}", i + 1, antecedents.join(" "), op, consequents.join(", "))?;
+            let marker = if rule.enabled { "" } else { "[disabled] " };
+            writeln!(f, "  {}: {}{}", i + 1, marker, rule)?;
         }
 
         writeln!(f, "Defuzzification: {:?}", self.defuzzification_method)
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use super::super::*;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     #[test]
     fn test_triangular_membership() {
@@ -117,12 +117,12 @@ mod tests {
 
     #[test]
     fn test_rule_evaluation_and() {
-        let mut inputs = HashMap::new();
-        let mut var1_membership = HashMap::new();
+        let mut inputs = BTreeMap::new();
+        let mut var1_membership = BTreeMap::new();
         var1_membership.insert("low".to_string(), 0.7);
         var1_membership.insert("high".to_string(), 0.3);
 
-        let mut var2_membership = HashMap::new();
+        let mut var2_membership = BTreeMap::new();
         var2_membership.insert("cold".to_string(), 0.8);
         var2_membership.insert("hot".to_string(), 0.2);
 
@@ -144,12 +144,12 @@ mod tests {
 
     #[test]
     fn test_rule_evaluation_or() {
-        let mut inputs = HashMap::new();
-        let mut var1_membership = HashMap::new();
+        let mut inputs = BTreeMap::new();
+        let mut var1_membership = BTreeMap::new();
         var1_membership.insert("low".to_string(), 0.3);
         var1_membership.insert("high".to_string(), 0.7);
 
-        let mut var2_membership = HashMap::new();
+        let mut var2_membership = BTreeMap::new();
         var2_membership.insert("cold".to_string(), 0.2);
         var2_membership.insert("hot".to_string(), 0.8);
 
@@ -181,6 +181,13 @@ mod tests {
         assert!(memberships.contains_key("hot"));
         assert!(memberships["cold"] > 0.4 && memberships["cold"] < 0.6);
         assert!(memberships["hot"] < 0.1);
+
+        // Name order, not insertion or hash order
+        assert_eq!(memberships.keys().collect::<Vec<_>>(), ["cold", "hot"]);
+        let mut reversed = LinguisticVariable::new("temperature", (0.0, 100.0));
+        reversed.add_set(FuzzySet::new("hot", triangular(50.0, 100.0, 100.0)));
+        reversed.add_set(FuzzySet::new("cold", triangular(0.0, 0.0, 50.0)));
+        assert_eq!(reversed.fuzzify(25.0), memberships);
     }
 
     #[test]
@@ -228,13 +235,203 @@ mod tests {
         assert!(output_value > 50.0); // Should be in high range
     }
 
+    #[test]
+    fn test_evaluate_with_activations() {
+        let mut system = FuzzySystem::new("Test System");
+
+        let mut temp_var = LinguisticVariable::new("temperature", (0.0, 100.0));
+        temp_var.add_set(FuzzySet::new("cold", triangular(0.0, 0.0, 50.0)));
+        temp_var.add_set(FuzzySet::new("hot", triangular(50.0, 100.0, 100.0)));
+        system.add_input(temp_var);
+
+        let mut fan_var = LinguisticVariable::new("fan_speed", (0.0, 100.0));
+        fan_var.add_set(FuzzySet::new("low", triangular(0.0, 0.0, 50.0)));
+        fan_var.add_set(FuzzySet::new("high", triangular(50.0, 100.0, 100.0)));
+        system.set_output(fan_var);
+
+        system.add_rule(FuzzyRule::new(
+            vec![Antecedent::new("cold", "temperature")],
+            vec![Consequent::new("low", "fan_speed")],
+            RuleOperator::And,
+        ));
+        system.add_rule(FuzzyRule::new(
+            vec![Antecedent::new("hot", "temperature")],
+            vec![Consequent::new("high", "fan_speed")],
+            RuleOperator::And,
+        ));
+
+        let mut inputs = HashMap::new();
+        inputs.insert("temperature".to_string(), 25.0);
+        let (_, value, activations) = system.evaluate_with_activations(&inputs);
+
+        assert_eq!(activations.len(), 2);
+        assert!((activations[0] - 0.5).abs() < 1e-9);
+        assert_eq!(activations[1], 0.0);
+        assert_eq!(value, system.evaluate(&inputs).1);
+
+        let trace = system.evaluate_traced(&inputs);
+        assert_eq!(trace.output_variable, "fan_speed");
+        assert_eq!(trace.defuzzified_value, value);
+        assert!((trace.set_activations["low"] - 0.5).abs() < 1e-9);
+        assert_eq!(trace.set_activations["high"], 0.0);
+    }
+
+    #[test]
+    fn test_disabled_rule_is_skipped_but_listed() {
+        let mut system = FuzzySystem::new("Test System");
+
+        let mut temp_var = LinguisticVariable::new("temperature", (0.0, 100.0));
+        temp_var.add_set(FuzzySet::new("cold", triangular(0.0, 0.0, 50.0)));
+        temp_var.add_set(FuzzySet::new("warm", triangular(0.0, 50.0, 100.0)));
+        system.add_input(temp_var);
+
+        let mut fan_var = LinguisticVariable::new("fan_speed", (0.0, 100.0));
+        fan_var.add_set(FuzzySet::new("low", triangular(0.0, 0.0, 50.0)));
+        fan_var.add_set(FuzzySet::new("high", triangular(50.0, 100.0, 100.0)));
+        system.set_output(fan_var);
+
+        system.add_rule(FuzzyRule::new(
+            vec![Antecedent::new("cold", "temperature")],
+            vec![Consequent::new("low", "fan_speed")],
+            RuleOperator::And,
+        ));
+        system.add_rule(FuzzyRule::new(
+            vec![Antecedent::new("warm", "temperature")],
+            vec![Consequent::new("high", "fan_speed")],
+            RuleOperator::And,
+        ));
+
+        let mut inputs = HashMap::new();
+        inputs.insert("temperature".to_string(), 25.0);
+        let both = system.evaluate(&inputs).1;
+
+        system.set_rule_enabled(0, false).unwrap();
+        let trace = system.evaluate_traced(&inputs);
+        assert_eq!(trace.rule_activations, vec![0.0, 0.5]);
+        assert!(!trace.set_activations.contains_key("low"));
+        assert!(trace.defuzzified_value > both);
+        assert!(system.to_string().contains("  1: [disabled] if temperature is cold"));
+        assert!(system.to_string().contains("  2: if temperature is warm"));
+
+        system.set_rule_enabled(0, true).unwrap();
+        assert_eq!(system.evaluate(&inputs).1, both);
+        assert_eq!(
+            system.set_rule_enabled(2, false),
+            Err(crate::error::FuzzyError::UnknownRule { index: 2, rules: 2 })
+        );
+    }
+
+    #[test]
+    fn test_variable_sample() {
+        let mut var = LinguisticVariable::new("temperature", (0.0, 100.0));
+        var.add_set(FuzzySet::new("cold", triangular(0.0, 0.0, 50.0)));
+        var.add_set(FuzzySet::new("hot", triangular(50.0, 100.0, 100.0)));
+
+        let samples = var.sample(5);
+        let xs: Vec<f64> = samples.iter().map(|(x, _)| *x).collect();
+        assert_eq!(xs, vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+        assert_eq!(samples[1].1, vec![0.5, 0.0]);
+        assert_eq!(samples[4].1, vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_circular_variable_sets_span_the_seam() {
+        let mut var = LinguisticVariable::circular("heading", (-180.0, 180.0));
+        var.add_set(FuzzySet::new("back", trapezoidal(150.0, 170.0, 190.0, 210.0)));
+        var.add_set(FuzzySet::new("front", triangular(-90.0, 0.0, 90.0)));
+
+        // Both ends of the range and any turn away are the same direction
+        assert_eq!(var.fuzzify(180.0), var.fuzzify(-180.0));
+        assert_eq!(var.fuzzify(-180.0), var.fuzzify(540.0));
+        assert_eq!(var.fuzzify(180.0)["back"], 1.0);
+        assert!((var.fuzzify(-160.0)["back"] - 0.5).abs() < 1e-12);
+        assert!((var.fuzzify(405.0)["front"] - 0.5).abs() < 1e-12);
+        assert_eq!(var.wrap(180.0), -180.0);
+
+        // Sampling follows the wrapped membership
+        let samples = var.sample(3);
+        assert_eq!(samples[0].1, vec![1.0, 0.0]);
+        assert_eq!(samples[2].1, vec![1.0, 0.0]);
+
+        // A plain variable does not wrap
+        let mut plain = LinguisticVariable::new("heading", (-180.0, 180.0));
+        plain.add_set(FuzzySet::new("back", trapezoidal(150.0, 170.0, 190.0, 210.0)));
+        assert_eq!(plain.fuzzify(-180.0)["back"], 0.0);
+    }
+
+    #[test]
+    fn test_slice_evaluation_equals_the_scalar_one() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(17);
+        for _ in 0..200 {
+            let mut params: Vec<f64> = (0..4).map(|_| rng.gen_range(-100.0..100.0)).collect();
+            params.sort_by(f64::total_cmp);
+            let [a, b, c, d] = [params[0], params[1], params[2], params[3]];
+            let functions: Vec<Box<dyn MembershipFunction>> = vec![
+                triangular(a, b, c),
+                triangular(a, a, c),
+                trapezoidal(a, b, c, d),
+                trapezoidal(a, a, d, d),
+                gaussian(b, rng.gen_range(0.1..50.0)),
+                sigmoidal(rng.gen_range(-2.0..2.0), b),
+            ];
+
+            // Random inputs around the shape plus its corners, where the branches change
+            let mut xs: Vec<f64> = (0..64).map(|_| rng.gen_range(-150.0..150.0)).collect();
+            xs.extend(params.iter().flat_map(|&p| [p, p - 1e-9, p + 1e-9]));
+            let mut out = vec![f64::NAN; xs.len()];
+            for function in &functions {
+                function.evaluate_slice(&xs, &mut out);
+                let scalar: Vec<f64> = xs.iter().map(|&x| function.evaluate(x)).collect();
+                assert_eq!(out, scalar, "{:?}", function.spec());
+            }
+        }
+
+        // Variables too, on the circle as well
+        let mut var = LinguisticVariable::circular("heading", (-180.0, 180.0));
+        var.add_set(FuzzySet::new("back", trapezoidal(150.0, 170.0, 190.0, 210.0)));
+        var.add_set(FuzzySet::new("front", gaussian(0.0, 40.0)));
+        let xs: Vec<f64> = (0..100).map(|_| rng.gen_range(-720.0..720.0)).collect();
+        let memberships = var.fuzzify_slice(&xs);
+        for (set, values) in var.fuzzy_sets.iter().zip(&memberships) {
+            let scalar: Vec<f64> = xs.iter().map(|&x| var.membership(set, x)).collect();
+            assert_eq!(values, &scalar, "{}", set.name);
+        }
+    }
+
+    #[test]
+    fn test_rule_display() {
+        let rule = FuzzyRule::new(
+            vec![
+                Antecedent::new("lejos", "distancia"),
+                Antecedent::new("alineado", "error"),
+            ],
+            vec![Consequent::new("mantener", "ajuste")],
+            RuleOperator::And,
+        );
+        assert_eq!(rule.to_string(), "if distancia is lejos AND error is alineado then ajuste is mantener");
+    }
+
+    #[test]
+    fn test_membership_spec() {
+        let set = FuzzySet::new("media", triangular(80.0, 200.0, 400.0));
+        let spec = set.spec().unwrap();
+        assert_eq!(spec, MembershipSpec::Triangular { a: 80.0, b: 200.0, c: 400.0 });
+        assert_eq!(serde_json::to_string(&spec).unwrap(), r#"{"type":"triangular","a":80.0,"b":200.0,"c":400.0}"#);
+
+        assert_eq!(trapezoidal(0.0, 1.0, 2.0, 3.0).spec(), Some(MembershipSpec::Trapezoidal { a: 0.0, b: 1.0, c: 2.0, d: 3.0 }));
+        assert_eq!(gaussian(1.0, 2.0).spec(), Some(MembershipSpec::Gaussian { mean: 1.0, sigma: 2.0 }));
+        assert_eq!(sigmoidal(3.0, 4.0).spec(), Some(MembershipSpec::Sigmoidal { a: 3.0, c: 4.0 }));
+    }
+
     #[test]
     fn test_defuzzification_centroid() {
         let mut output_var = LinguisticVariable::new("output", (0.0, 100.0));
         output_var.add_set(FuzzySet::new("low", triangular(0.0, 25.0, 50.0)));
         output_var.add_set(FuzzySet::new("high", triangular(50.0, 75.0, 100.0)));
 
-        let mut activated = HashMap::new();
+        let mut activated = BTreeMap::new();
         activated.insert("low".to_string(), 0.5);
         activated.insert("high".to_string(), 0.5);
 
@@ -249,11 +446,158 @@ mod tests {
         let mut output_var = LinguisticVariable::new("output", (0.0, 100.0));
         output_var.add_set(FuzzySet::new("low", triangular(0.0, 25.0, 50.0)));
 
-        let activated = HashMap::new(); // No activation
+        let activated = BTreeMap::new(); // No activation
 
         let result = Defuzzifier::centroid(&output_var, &activated);
 
         // Should return midpoint
         assert!((result - 50.0).abs() < f64::EPSILON);
     }
+
+    #[test]
+    fn test_try_membership_constructors_report_invalid_parameters() {
+        use crate::error::FuzzyError;
+
+        assert!(try_triangular(0.0, 5.0, 10.0).is_ok());
+        assert_eq!(
+            try_triangular(0.0, 10.0, 5.0).err(),
+            Some(FuzzyError::InvalidMembershipParameters { function: "Triangular", requirement: "finite a <= b <= c" })
+        );
+        assert!(matches!(
+            try_trapezoidal(0.0, 3.0, 2.0, 10.0),
+            Err(FuzzyError::InvalidMembershipParameters { function: "Trapezoidal", .. })
+        ));
+        assert!(matches!(
+            try_gaussian(0.0, 0.0),
+            Err(FuzzyError::InvalidMembershipParameters { function: "Gaussian", .. })
+        ));
+        assert!(matches!(
+            try_gaussian(0.0, f64::NAN),
+            Err(FuzzyError::InvalidMembershipParameters { function: "Gaussian", .. })
+        ));
+        assert!(matches!(
+            try_sigmoidal(0.0, 1.0),
+            Err(FuzzyError::InvalidMembershipParameters { function: "Sigmoidal", .. })
+        ));
+    }
+
+    #[test]
+    fn test_membership_edge_cases_stay_in_unit_interval() {
+        // Spans of huge parameters used to overflow into inf / inf
+        assert!((0.0..=1.0).contains(&triangular(-1.7e308, 1.7e308, 1.75e308).evaluate(1e308)));
+        assert!((0.0..=1.0).contains(&trapezoidal(-1.7e308, 1.7e308, 1.7e308, 1.75e308).evaluate(1e308)));
+        // A sigma whose square underflows used to give 0 / 0 at the mean
+        assert_eq!(gaussian(0.0, 1e-200).evaluate(0.0), 1.0);
+        assert!(try_triangular(f64::NEG_INFINITY, 0.0, 1.0).is_err());
+        assert!(try_gaussian(f64::INFINITY, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_and_rule_with_missing_antecedent_does_not_fire() {
+        let rule = FuzzyRule::new(
+            vec![Antecedent::new("cerca", "distancia"), Antecedent::new("alto", "velocidad")],
+            vec![Consequent::new("bajo", "salida")],
+            RuleOperator::And,
+        );
+        let mut inputs = BTreeMap::new();
+        inputs.insert("distancia".to_string(), BTreeMap::from([("cerca".to_string(), 0.8)]));
+
+        assert_eq!(rule.evaluate(&inputs), 0.0);
+    }
+
+    mod properties {
+        use super::super::super::*;
+        use proptest::prelude::*;
+        use std::collections::BTreeMap;
+
+        fn finite() -> impl Strategy<Value = f64> {
+            proptest::num::f64::NORMAL | proptest::num::f64::SUBNORMAL | proptest::num::f64::ZERO
+        }
+
+        fn sorted_finite(len: usize) -> impl Strategy<Value = Vec<f64>> {
+            prop::collection::vec(finite(), len).prop_map(|mut values| {
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                values
+            })
+        }
+
+        fn assert_membership(value: f64) -> Result<(), TestCaseError> {
+            prop_assert!((0.0..=1.0).contains(&value), "membership {} outside [0, 1]", value);
+            Ok(())
+        }
+
+        proptest! {
+            #[test]
+            fn triangular_stays_in_unit_interval(p in sorted_finite(3), input in finite()) {
+                assert_membership(triangular(p[0], p[1], p[2]).evaluate(input))?;
+            }
+
+            #[test]
+            fn degenerate_triangular_stays_in_unit_interval(a in finite(), width in 0.0f64..1e-12, input in finite()) {
+                assert_membership(triangular(a, a, a).evaluate(input))?;
+                assert_membership(triangular(a, a, a + width).evaluate(input))?;
+                assert_membership(triangular(a, a + width, a + width).evaluate(input))?;
+            }
+
+            #[test]
+            fn trapezoidal_stays_in_unit_interval(p in sorted_finite(4), input in finite()) {
+                assert_membership(trapezoidal(p[0], p[1], p[2], p[3]).evaluate(input))?;
+            }
+
+            #[test]
+            fn gaussian_stays_in_unit_interval(
+                mean in finite(),
+                sigma in proptest::num::f64::POSITIVE | proptest::num::f64::NORMAL | proptest::num::f64::SUBNORMAL,
+                input in finite(),
+            ) {
+                prop_assume!(sigma > 0.0);
+                assert_membership(gaussian(mean, sigma).evaluate(input))?;
+            }
+
+            #[test]
+            fn sigmoidal_stays_in_unit_interval(a in finite(), c in finite(), input in finite()) {
+                prop_assume!(a.abs() > f64::EPSILON);
+                assert_membership(sigmoidal(a, c).evaluate(input))?;
+            }
+
+            #[test]
+            fn centroid_stays_in_output_range(
+                min in -1e6f64..1e6,
+                width in 1e-6f64..1e6,
+                sets in prop::collection::vec((sorted_finite(3), 0.0f64..=1.0), 1..5),
+            ) {
+                let max = min + width;
+                let mut output = LinguisticVariable::new("output", (min, max));
+                let mut activated = BTreeMap::new();
+                for (idx, (points, activation)) in sets.iter().enumerate() {
+                    // Squash the generated points into the range, keeping their order
+                    let scaled: Vec<f64> = points.iter().map(|p| min + width * (0.5 + p.atan() / std::f64::consts::PI)).collect();
+                    let name = format!("set{}", idx);
+                    output.add_set(FuzzySet::new(name.clone(), triangular(scaled[0], scaled[1], scaled[2])));
+                    activated.insert(name, *activation);
+                }
+
+                let result = Defuzzifier::centroid(&output, &activated);
+                prop_assert!(result >= min && result <= max, "centroid {} outside [{}, {}]", result, min, max);
+            }
+
+            #[test]
+            fn and_degree_is_at_most_min_antecedent(degrees in prop::collection::vec(prop::option::of(0.0f64..=1.0), 1..5)) {
+                // Missing entries leave the variable out of the fuzzified inputs
+                let mut inputs: BTreeMap<String, BTreeMap<String, f64>> = BTreeMap::new();
+                let mut antecedents = Vec::new();
+                for (idx, degree) in degrees.iter().enumerate() {
+                    let variable = format!("var{}", idx);
+                    antecedents.push(Antecedent::new("set", &variable));
+                    if let Some(degree) = degree {
+                        inputs.insert(variable, BTreeMap::from([("set".to_string(), *degree)]));
+                    }
+                }
+                let rule = FuzzyRule::new(antecedents, vec![Consequent::new("out", "output")], RuleOperator::And);
+
+                let min_degree = degrees.iter().map(|d| d.unwrap_or(0.0)).fold(1.0, f64::min);
+                prop_assert!(rule.evaluate(&inputs) <= min_degree);
+            }
+        }
+    }
 }
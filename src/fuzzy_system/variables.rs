@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug};
 
 use crate::fuzzy_system::FuzzySet;
 
@@ -7,6 +7,8 @@ pub struct LinguisticVariable {
     pub name: String,
     pub fuzzy_sets: Vec<FuzzySet>,
     pub range: (f64, f64),
+    /// The range is one period of a circle (an angle), its two ends are the same value
+    pub circular: bool,
 }
 
 impl LinguisticVariable {
@@ -15,15 +17,94 @@ impl LinguisticVariable {
             name: name.to_string(),
             fuzzy_sets: Vec::new(),
             range,
+            circular: false,
         }
     }
 
+    /// Variable on a circle whose period is the range
+    ///
+    /// Inputs wrap into `[min, max)` and sets may span the seam, a set defined past
+    /// `max` continues from `min` (e.g. a trapezoid over 150°..210° covers ±180°).
+    pub fn circular(name: &str, range: (f64, f64)) -> Self {
+        Self { circular: true, ..Self::new(name, range) }
+    }
+
     pub fn add_set(&mut self, fuzzy_set: FuzzySet) {
         self.fuzzy_sets.push(fuzzy_set);
     }
 
-    pub fn fuzzify(&self, value: f64) -> HashMap<String, f64> {
-        self.fuzzy_sets.iter().map(|set| (set.name.clone(), set.evaluate(value))).collect()
+    /// Membership of `value` in every set, keyed by set name in name order
+    pub fn fuzzify(&self, value: f64) -> BTreeMap<String, f64> {
+        self.fuzzy_sets.iter().map(|set| (set.name.clone(), self.membership(set, value))).collect()
+    }
+
+    /// Input wrapped into `[min, max)` for circular variables, unchanged otherwise
+    pub fn wrap(&self, value: f64) -> f64 {
+        let (min, max) = self.range;
+        if !self.circular {
+            return value;
+        }
+        min + (value - min).rem_euclid(max - min)
+    }
+
+    /// Membership of `value` in `set`, on the circle for circular variables
+    ///
+    /// Both ends of a circular range evaluate to the same membership, so a value
+    /// crossing the seam does not jump between sets.
+    pub fn membership(&self, set: &FuzzySet, value: f64) -> f64 {
+        if !self.circular {
+            return set.evaluate(value);
+        }
+        let x = self.wrap(value);
+        let period = self.range.1 - self.range.0;
+        set.evaluate(x).max(set.evaluate(x - period)).max(set.evaluate(x + period))
+    }
+
+    /// `membership` of every value of `xs` in every set, one vector per set in set order
+    pub fn fuzzify_slice(&self, xs: &[f64]) -> Vec<Vec<f64>> {
+        if !self.circular {
+            return self.fuzzy_sets
+                .iter()
+                .map(|set| {
+                    let mut out = vec![0.0; xs.len()];
+                    set.evaluate_slice(xs, &mut out);
+                    out
+                })
+                .collect();
+        }
+
+        let period = self.range.1 - self.range.0;
+        let wrapped: Vec<f64> = xs.iter().map(|&x| self.wrap(x)).collect();
+        let below: Vec<f64> = wrapped.iter().map(|&x| x - period).collect();
+        let above: Vec<f64> = wrapped.iter().map(|&x| x + period).collect();
+        let mut shifted = vec![0.0; xs.len()];
+        self.fuzzy_sets
+            .iter()
+            .map(|set| {
+                let mut out = vec![0.0; xs.len()];
+                set.evaluate_slice(&wrapped, &mut out);
+                for copy in [&below, &above] {
+                    set.evaluate_slice(copy, &mut shifted);
+                    out.iter_mut().zip(&shifted).for_each(|(y, &other)| *y = y.max(other));
+                }
+                out
+            })
+            .collect()
+    }
+
+    /// Sample every set at `points` evenly spaced values over the range, both ends included
+    ///
+    /// Each entry is `(x, memberships)` with one membership per set, in set order.
+    pub fn sample(&self, points: usize) -> Vec<(f64, Vec<f64>)> {
+        let (min, max) = self.range;
+        let step = (max - min) / points.saturating_sub(1).max(1) as f64;
+        let xs: Vec<f64> = (0..points).map(|i| min + i as f64 * step).collect();
+        let memberships = self.fuzzify_slice(&xs);
+
+        xs.iter()
+            .enumerate()
+            .map(|(i, &x)| (x, memberships.iter().map(|set| set[i]).collect()))
+            .collect()
     }
 }
 
@@ -39,33 +120,37 @@ impl Debug for DefuzzificationMethod {
     }
 }
 
+/// Intervals of the numerical integration of the centroid over the output range
+pub const CENTROID_STEPS: usize = 1000;
+
 pub struct Defuzzifier;
 
 impl Defuzzifier {
     /// Centroid defuzzification method using numerical integration
     /// Computes: ∫ x·μ(x) dx / ∫ μ(x) dx
     /// where μ(x) is the aggregated membership function (max of all activated sets)
-    pub fn centroid(output_var: &LinguisticVariable, activated: &HashMap<String, f64>) -> f64 {
-        let steps = 1000; // Increased resolution for better accuracy
+    pub fn centroid(output_var: &LinguisticVariable, activated: &BTreeMap<String, f64>) -> f64 {
+        let steps = CENTROID_STEPS;
         let step_size = (output_var.range.1 - output_var.range.0) / steps as f64;
-        let mut numerator = 0.0;
-        let mut denominator = 0.0;
-
-        // Numerical integration using trapezoidal rule
-        for i in 0..=steps {
-            let x = output_var.range.0 + i as f64 * step_size;
-
-            // Compute aggregated membership at point x (max over all activated sets)
-            let mut aggregated_membership: f64 = 0.0;
-            for set in &output_var.fuzzy_sets {
-                if let Some(&activation_degree) = activated.get(&set.name) {
-                    // Apply implication (min between activation degree and membership)
-                    let membership_at_x = set.evaluate(x);
-                    let clipped_membership = membership_at_x.min(activation_degree);
-                    aggregated_membership = aggregated_membership.max(clipped_membership);
+        let xs: Vec<f64> = (0..=steps).map(|i| output_var.range.0 + i as f64 * step_size).collect();
+
+        // Aggregated membership at every point (max over all activated sets)
+        let mut aggregated = vec![0.0_f64; xs.len()];
+        let mut membership = vec![0.0; xs.len()];
+        for set in &output_var.fuzzy_sets {
+            if let Some(&activation_degree) = activated.get(&set.name) {
+                // Apply implication (min between activation degree and membership)
+                set.evaluate_slice(&xs, &mut membership);
+                for (aggregated, &membership_at_x) in aggregated.iter_mut().zip(&membership) {
+                    *aggregated = aggregated.max(membership_at_x.min(activation_degree));
                 }
             }
+        }
 
+        // Numerical integration using trapezoidal rule
+        let mut numerator = 0.0;
+        let mut denominator = 0.0;
+        for (&x, &aggregated_membership) in xs.iter().zip(&aggregated) {
             numerator += x * aggregated_membership;
             denominator += aggregated_membership;
         }
@@ -75,7 +160,8 @@ impl Defuzzifier {
             return (output_var.range.0 + output_var.range.1) / 2.0;
         }
 
-        numerator / denominator
+        // Rounding of the weighted mean may step an ulp past the range ends
+        (numerator / denominator).clamp(output_var.range.0, output_var.range.1)
     }
 
 }
\ No newline at end of file
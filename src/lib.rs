@@ -1,11 +1,108 @@
+pub mod build_info;
+pub mod error;
 pub mod fuzzy_system;
 pub mod map;
 pub mod vehicle;
 pub mod navigation;
 pub mod simulation;
+pub mod analysis;
+pub mod units;
+
+#[cfg(feature = "cli")]
+pub mod artifacts;
+
+#[cfg(feature = "cli")]
+pub mod logging;
 
 #[cfg(feature = "cli")]
 pub mod membership_export;
 
-#[cfg(feature = "api")]
+#[cfg(feature = "cli")]
+pub mod progress;
+
+#[cfg(feature = "arrow")]
+pub mod arrow_export;
+
+#[cfg(feature = "storage")]
+pub mod storage;
+
+#[cfg(feature = "xlsx")]
+pub mod xlsx_export;
+
+pub mod trajectory_export;
+
+#[cfg(any(feature = "api", feature = "cli", feature = "python"))]
+pub mod resource_usage;
+
+#[cfg(any(feature = "api", feature = "python"))]
 pub mod api;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(test)]
+mod tests {
+    /// Sources of the modules that must also build for wasm32-unknown-unknown
+    const CORE_SOURCES: [(&str, &str); 31] = [
+        ("build_info.rs", include_str!("build_info.rs")),
+        ("error.rs", include_str!("error.rs")),
+        ("fuzzy_system/membership.rs", include_str!("fuzzy_system/membership.rs")),
+        ("fuzzy_system/sets.rs", include_str!("fuzzy_system/sets.rs")),
+        ("fuzzy_system/variables.rs", include_str!("fuzzy_system/variables.rs")),
+        ("fuzzy_system/rules.rs", include_str!("fuzzy_system/rules.rs")),
+        ("fuzzy_system/system.rs", include_str!("fuzzy_system/system.rs")),
+        ("fuzzy_system/surface.rs", include_str!("fuzzy_system/surface.rs")),
+        ("map/mod.rs", include_str!("map/mod.rs")),
+        ("vehicle/mod.rs", include_str!("vehicle/mod.rs")),
+        ("navigation/mod.rs", include_str!("navigation/mod.rs")),
+        ("navigation/cache.rs", include_str!("navigation/cache.rs")),
+        ("navigation/observation.rs", include_str!("navigation/observation.rs")),
+        ("simulation/mod.rs", include_str!("simulation/mod.rs")),
+        ("simulation/benchmark.rs", include_str!("simulation/benchmark.rs")),
+        ("simulation/criteria.rs", include_str!("simulation/criteria.rs")),
+        ("simulation/dubins.rs", include_str!("simulation/dubins.rs")),
+        ("simulation/formation.rs", include_str!("simulation/formation.rs")),
+        ("simulation/invariants.rs", include_str!("simulation/invariants.rs")),
+        ("simulation/kinematics.rs", include_str!("simulation/kinematics.rs")),
+        ("simulation/preflight.rs", include_str!("simulation/preflight.rs")),
+        ("simulation/scenario.rs", include_str!("simulation/scenario.rs")),
+        ("simulation/stream.rs", include_str!("simulation/stream.rs")),
+        ("analysis/mod.rs", include_str!("analysis/mod.rs")),
+        ("analysis/resampling.rs", include_str!("analysis/resampling.rs")),
+        ("analysis/robustness.rs", include_str!("analysis/robustness.rs")),
+        ("analysis/similarity.rs", include_str!("analysis/similarity.rs")),
+        ("analysis/stats.rs", include_str!("analysis/stats.rs")),
+        ("trajectory_export/geojson.rs", include_str!("trajectory_export/geojson.rs")),
+        ("units.rs", include_str!("units.rs")),
+        ("wasm.rs", include_str!("wasm.rs")),
+    ];
+
+    #[test]
+    fn test_core_modules_do_no_file_io() {
+        for (path, source) in CORE_SOURCES {
+            for forbidden in ["std::fs", "File::", "std::io", "plotters", "rayon", "tokio"] {
+                assert!(!source.contains(forbidden), "{} uses {}", path, forbidden);
+            }
+        }
+    }
+
+    /// Needs `rustup target add wasm32-unknown-unknown`, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_core_checks_for_wasm32() {
+        let status = std::process::Command::new(env!("CARGO"))
+            .args(["check", "--lib", "--target", "wasm32-unknown-unknown"])
+            .args(["--no-default-features", "--features", "wasm"])
+            .args(["--target-dir", "target/wasm-check"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("failed to run cargo");
+        assert!(status.success());
+    }
+}
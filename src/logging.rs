@@ -0,0 +1,50 @@
+// Console output of the library tracing events for the CLI bins
+
+use tracing_subscriber::filter::{EnvFilter, ParseError};
+
+/// Default filter: simulation milestones and fuzzy-system warnings
+const DEFAULT_LEVEL: &str = "info";
+
+/// Install a fmt subscriber printing to the console
+///
+/// `level` takes an `EnvFilter` directive such as `debug` or
+/// `examen_parcial::simulation=debug`. Without it `RUST_LOG` is used, then
+/// `info`. Installing twice keeps the first subscriber, so a runner may call
+/// this again after the entry point did.
+pub fn init_cli(level: Option<&str>) -> Result<(), ParseError> {
+    let filter = match level {
+        Some(directives) => EnvFilter::try_new(directives)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL)),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .without_time()
+        .try_init()
+        .ok();
+
+    Ok(())
+}
+
+/// Value of a `--log-level=LEVEL` argument, for the bins without clap
+pub fn log_level_arg(args: &[String]) -> Option<&str> {
+    args.iter().find_map(|arg| arg.strip_prefix("--log-level="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_arg() {
+        let args: Vec<String> = ["navigation", "--plot", "--log-level=debug"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(log_level_arg(&args), Some("debug"));
+        assert_eq!(log_level_arg(&args[..2]), None);
+    }
+
+    #[test]
+    fn test_init_cli_rejects_invalid_directives() {
+        assert!(init_cli(Some("examen_parcial=loud")).is_err());
+    }
+}
@@ -1,14 +1,8 @@
 // Fuzzy Navigation System API
 // Powered by Shuttle and Axum
-use shuttle_axum::axum::{
-    routing::{get, post},
-    Router,
-};
-use tower_http::cors::{CorsLayer, Any};
-use tower_http::trace::TraceLayer;
 use std::panic;
 
-use examen_parcial::api::handlers;
+use examen_parcial::api::{build_router, ApiState};
 
 #[shuttle_runtime::main]
 async fn main() -> shuttle_axum::ShuttleAxum {
@@ -19,25 +13,9 @@ async fn main() -> shuttle_axum::ShuttleAxum {
         // In production, you'd want to log this to a proper logging service
         let _ = std::fs::write("/tmp/fuzzy_nav_panic.log", format!("{:?}", _panic_info));
     }));
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    // Build router with all endpoints
-    let router = Router::new()
-        // Health check
-        .route("/", get(handlers::health_check))
-        .route("/health", get(handlers::health_check))
-
-        // Simulation endpoints
-        .route("/api/simulate", post(handlers::run_simulation))
-        .route("/api/benchmark", post(handlers::run_benchmark))
-
-        // Add middleware
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
+    // RUN_STORE_PATH=runs.db stores every JSON simulation and benchmark, in builds with the storage feature
+    // BENCHMARK_JOBS_DIR=jobs keeps benchmark jobs across restarts
+    let router = build_router(ApiState::from_env());
 
     Ok(router.into())
 }
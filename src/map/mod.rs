@@ -1,9 +1,12 @@
 // Map module - Environment configuration for vehicle navigation
 
 use std::f64::consts::PI;
+use rand::Rng;
+use crate::error::{MapError, SimulationError};
+use crate::units::{Degrees, Radians};
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -23,7 +26,18 @@ pub struct StartZone {
 #[derive(Debug, Clone)]
 pub struct Target {
     pub position: Point,
-    pub required_angle: f64,  // Required arrival angle in radians (π/2 for 90°)
+    pub required_angle: Radians,  // Required arrival angle (π/2 for 90°)
+}
+
+impl Target {
+    /// Unsigned difference between `heading` and the required angle, in [0, π]
+    ///
+    /// The arrival angle error of the simulation, the single-step API and the
+    /// metrics: normalized, so headings across the ±π seam or a full turn
+    /// apart compare as the same direction.
+    pub fn angle_error(&self, heading: Radians) -> Radians {
+        normalize_angle(self.required_angle - heading).abs()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +49,19 @@ pub struct Map {
 }
 
 impl Map {
+    /// Same as `new`, rejecting non-positive dimensions and targets outside the map
+    pub fn try_new(width: f64, height: f64, target_x: f64, target_y: f64) -> Result<Self, MapError> {
+        let positive = width > 0.0 && height > 0.0;
+        if !positive {
+            return Err(MapError::InvalidDimensions { width, height });
+        }
+        let inside = (0.0..=width).contains(&target_x) && (0.0..=height).contains(&target_y);
+        if !inside {
+            return Err(MapError::TargetOutOfBounds { x: target_x, y: target_y, width, height });
+        }
+        Ok(Self::new(width, height, target_x, target_y))
+    }
+
     pub fn new(width: f64, height: f64, target_x: f64, target_y: f64) -> Self {
         Self {
             width,
@@ -44,16 +71,30 @@ impl Map {
             },
             target: Target {
                 position: Point::new(target_x, target_y),
-                required_angle: PI / 2.0,  // 90 degrees
+                required_angle: TARGET_REQUIRED_ANGLE,
             },
         }
     }
 
+    /// True for points on the map, edges included
+    pub fn contains(&self, point: &Point) -> bool {
+        (0.0..=self.width).contains(&point.x) && (0.0..=self.height).contains(&point.y)
+    }
+
+    /// True when the circle of `radius` around `point` lies on the map, the room a
+    /// vehicle turning with that radius needs to swing back after overshooting it
+    pub fn turn_fits(&self, point: &Point, radius: f64) -> bool {
+        self.contains(&Point::new(point.x - radius, point.y - radius))
+            && self.contains(&Point::new(point.x + radius, point.y + radius))
+    }
+
     /// Generate a random starting position within the start zone
     pub fn random_start_position(&self) -> Point {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
+        self.random_start_position_with(&mut rand::thread_rng())
+    }
 
+    /// Same as `random_start_position`, drawing from the given generator
+    pub fn random_start_position_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Point {
         let x = rng.gen_range(0.0..self.width);
         let y = rng.gen_range(0.0..(self.height * self.start_zone.height_percentage));
 
@@ -61,24 +102,246 @@ impl Map {
     }
 
     /// Generate a random initial angle (generally pointing upward)
-    pub fn random_start_angle(&self) -> f64 {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
+    pub fn random_start_angle(&self) -> Radians {
+        self.random_start_angle_with(&mut rand::thread_rng())
+    }
 
+    /// Same as `random_start_angle`, drawing from the given generator
+    pub fn random_start_angle_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Radians {
         // Random angle between 30° and 150° (biased upward)
-        rng.gen_range(30f64.to_radians()..150f64.to_radians())
+        Radians(rng.gen_range(30f64.to_radians()..150f64.to_radians()))
     }
 
     /// Generate a random initial velocity percentage (5% to 15% of max velocity)
     pub fn random_start_velocity_percentage(&self) -> f64 {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
+        self.random_start_velocity_percentage_with(&mut rand::thread_rng())
+    }
 
+    /// Same as `random_start_velocity_percentage`, drawing from the given generator
+    pub fn random_start_velocity_percentage_with<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
         // Random percentage between 5% and 15%
         rng.gen_range(0.05..0.15)
     }
 }
 
+/// Width and height of the exam map, shared by every preset
+pub const EXAM_MAP_SIZE: (f64, f64) = (1000.0, 800.0);
+
+/// Heading every target is reached with, 90° (radians)
+pub const TARGET_REQUIRED_ANGLE: Radians = Radians(PI / 2.0);
+
+/// Named target placements on the exam-sized map
+///
+/// The exam target is centered at the top. The others sit near corners and
+/// edges, where the approach and the turns around the target run close to the
+/// map boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapPreset {
+    Exam,
+    CornerTopLeft,
+    CornerTopRight,
+    EdgeLeft,
+    EdgeRight,
+}
+
+impl MapPreset {
+    pub const ALL: [MapPreset; 5] = [
+        MapPreset::Exam,
+        MapPreset::CornerTopLeft,
+        MapPreset::CornerTopRight,
+        MapPreset::EdgeLeft,
+        MapPreset::EdgeRight,
+    ];
+
+    /// Parse a CLI name such as "corner_top_right", case insensitive
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapPreset::Exam => "exam",
+            MapPreset::CornerTopLeft => "corner_top_left",
+            MapPreset::CornerTopRight => "corner_top_right",
+            MapPreset::EdgeLeft => "edge_left",
+            MapPreset::EdgeRight => "edge_right",
+        }
+    }
+
+    /// Target position, always reached heading 90°
+    pub fn target(&self) -> Point {
+        match self {
+            MapPreset::Exam => Point::new(500.0, 700.0),
+            MapPreset::CornerTopLeft => Point::new(50.0, 750.0),
+            MapPreset::CornerTopRight => Point::new(950.0, 750.0),
+            MapPreset::EdgeLeft => Point::new(20.0, 400.0),
+            MapPreset::EdgeRight => Point::new(980.0, 400.0),
+        }
+    }
+
+    pub fn map(&self) -> Map {
+        let target = self.target();
+        Map::new(EXAM_MAP_SIZE.0, EXAM_MAP_SIZE.1, target.x, target.y)
+    }
+}
+
+/// Half-width of the `AwayFromTarget` heading cone around the anti-target direction
+pub const AWAY_FROM_TARGET_SPREAD: f64 = 30.0;
+
+/// How the random start heading is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeadingDistribution {
+    /// 30° to 150°, roughly towards a target at the top of the map (`Map::random_start_angle`)
+    #[default]
+    TowardTarget,
+    /// Any heading
+    Uniform,
+    /// Within ±`AWAY_FROM_TARGET_SPREAD`° of the direction opposite to the target, a recovery scenario
+    AwayFromTarget,
+    /// Always this heading
+    Fixed(Degrees),
+}
+
+impl HeadingDistribution {
+    /// Parse a CLI name: "toward", "uniform", "away" or a fixed heading in degrees
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "toward" | "toward_target" => Some(HeadingDistribution::TowardTarget),
+            "uniform" => Some(HeadingDistribution::Uniform),
+            "away" | "away_from_target" => Some(HeadingDistribution::AwayFromTarget),
+            other => other.parse::<f64>().ok().filter(|d| d.is_finite()).map(|d| HeadingDistribution::Fixed(Degrees(d))),
+        }
+    }
+
+    /// Heading for a vehicle starting at `position` on `map`
+    pub fn sample<R: Rng + ?Sized>(&self, map: &Map, position: &Point, rng: &mut R) -> Radians {
+        match *self {
+            HeadingDistribution::TowardTarget => map.random_start_angle_with(rng),
+            HeadingDistribution::Uniform => Radians(rng.gen_range(-PI..PI)),
+            HeadingDistribution::AwayFromTarget => {
+                let dx = map.target.position.x - position.x;
+                let dy = map.target.position.y - position.y;
+                let spread = AWAY_FROM_TARGET_SPREAD.to_radians();
+                normalize_angle(Radians(dy.atan2(dx) + PI + rng.gen_range(-spread..=spread)))
+            }
+            HeadingDistribution::Fixed(degrees) => normalize_angle(degrees.to_radians()),
+        }
+    }
+}
+
+impl std::fmt::Display for HeadingDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadingDistribution::TowardTarget => write!(f, "toward_target"),
+            HeadingDistribution::Uniform => write!(f, "uniform"),
+            HeadingDistribution::AwayFromTarget => write!(f, "away_from_target"),
+            HeadingDistribution::Fixed(degrees) => write!(f, "fixed({})", degrees),
+        }
+    }
+}
+
+/// Default spread of `StartDistribution::GaussianAroundCenter`, as a fraction of the map width
+pub const DEFAULT_START_SIGMA_FRACTION: f64 = 0.15;
+
+/// Default number of x-bins of `StartDistribution::Stratified`
+pub const DEFAULT_START_BINS: usize = 10;
+
+/// Draws of a Gaussian start outside the map before falling back to clamping
+const GAUSSIAN_START_ATTEMPTS: usize = 100;
+
+/// How the random start x is drawn, y is always uniform over the start zone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartDistribution {
+    /// Anywhere across the width (`Map::random_start_position`)
+    #[default]
+    Uniform,
+    /// Normal around the middle of the width, standard deviation `sigma_fraction`
+    /// of the width, redrawn when it falls off the map
+    GaussianAroundCenter { sigma_fraction: f64 },
+    /// Iteration `i` starts in x-bin `i % bins` of equal width, so every run of
+    /// `bins` consecutive iterations covers the width evenly
+    Stratified { bins: usize },
+}
+
+impl StartDistribution {
+    /// Parse a CLI name: "uniform", "gaussian[:SIGMA_FRACTION]" or "stratified[:BINS]"
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let (kind, parameter) = match name.split_once(':') {
+            Some((kind, parameter)) => (kind, Some(parameter)),
+            None => (name.as_str(), None),
+        };
+        match kind {
+            "uniform" if parameter.is_none() => Some(StartDistribution::Uniform),
+            "gaussian" => {
+                let sigma_fraction = parameter.map_or(Some(DEFAULT_START_SIGMA_FRACTION), |p| p.parse().ok())?;
+                Some(StartDistribution::GaussianAroundCenter { sigma_fraction })
+            }
+            "stratified" => {
+                let bins = parameter.map_or(Some(DEFAULT_START_BINS), |p| p.parse().ok())?;
+                Some(StartDistribution::Stratified { bins })
+            }
+            _ => None,
+        }
+    }
+
+    /// Reject spreads that are not positive and finite and stratifications without bins
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        match *self {
+            StartDistribution::GaussianAroundCenter { sigma_fraction } if !(sigma_fraction > 0.0 && sigma_fraction.is_finite()) => {
+                Err(SimulationError::InvalidStartSigma(sigma_fraction))
+            }
+            StartDistribution::Stratified { bins: 0 } => Err(SimulationError::NoStartBins),
+            _ => Ok(()),
+        }
+    }
+
+    /// Start position of the run at index `iteration` of a series on `map`
+    ///
+    /// Only `Stratified` depends on the iteration, the others draw the same way every time.
+    pub fn sample<R: Rng + ?Sized>(&self, map: &Map, iteration: usize, rng: &mut R) -> Point {
+        let x = match *self {
+            StartDistribution::Uniform => return map.random_start_position_with(rng),
+            StartDistribution::GaussianAroundCenter { sigma_fraction } => {
+                let sigma = sigma_fraction * map.width;
+                let center = map.width / 2.0;
+                (0..GAUSSIAN_START_ATTEMPTS)
+                    .map(|_| center + sigma * standard_normal(rng))
+                    .find(|x| (0.0..map.width).contains(x))
+                    .unwrap_or_else(|| (center + sigma * standard_normal(rng)).clamp(0.0, map.width))
+            }
+            StartDistribution::Stratified { bins } => {
+                let bins = bins.max(1);
+                let bin_width = map.width / bins as f64;
+                bin_width * ((iteration % bins) as f64 + rng.gen_range(0.0..1.0))
+            }
+        };
+        let y = rng.gen_range(0.0..(map.height * map.start_zone.height_percentage));
+        Point::new(x, y)
+    }
+}
+
+impl std::fmt::Display for StartDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartDistribution::Uniform => write!(f, "uniform"),
+            StartDistribution::GaussianAroundCenter { sigma_fraction } => write!(f, "gaussian({})", sigma_fraction),
+            StartDistribution::Stratified { bins } => write!(f, "stratified({})", bins),
+        }
+    }
+}
+
+/// Standard normal sample by the Box-Muller transform
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // 1 - [0, 1) is in (0, 1], the logarithm stays finite
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
 // Geometry utility functions
 
 /// Calculate Euclidean distance between two points
@@ -89,65 +352,211 @@ pub fn euclidean_distance(p1: &Point, p2: &Point) -> f64 {
 }
 
 /// Normalize angle to range [-π, π]
-pub fn normalize_angle(angle: f64) -> f64 {
-    let mut normalized = angle;
-    while normalized > PI {
-        normalized -= 2.0 * PI;
-    }
-    while normalized < -PI {
-        normalized += 2.0 * PI;
-    }
-    normalized
+///
+/// Takes `Radians` so a heading in degrees has to be converted first.
+pub fn normalize_angle(angle: Radians) -> Radians {
+    angle.normalized()
 }
 
 /// Calculate angular error between current orientation and target direction
-/// Returns angle in radians [-π, π]
-pub fn compute_angular_error(current_pos: &Point, current_angle: f64, target_pos: &Point) -> f64 {
+/// Returns angle in radians [-π, π], desired − current heading
+///
+/// Positive when the target is to the left of the vehicle, see `turn_direction`.
+pub fn compute_angular_error(current_pos: &Point, current_angle: Radians, target_pos: &Point) -> Radians {
     let dx = target_pos.x - current_pos.x;
     let dy = target_pos.y - current_pos.y;
 
-    let desired_angle = dy.atan2(dx);
+    let desired_angle = Radians(dy.atan2(dx));
     normalize_angle(desired_angle - current_angle)
 }
 
-/// Calculate angular error with arrival angle consideration
-/// Uses a virtual approach point that converges to target as vehicle gets closer
-///
-/// Strategy for high-precision 90° arrival (±2°):
-/// - When far (>120 units): Navigates directly to target
-/// - When close (<120 units): Navigates to dynamic approach point below target
-/// - Offset decreases with cubic curve for smoother final approach
+/// Side of a turn as seen on the map, drawn with y up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnDirection {
+    /// Counterclockwise, the heading grows
+    Left,
+    /// Clockwise, the heading shrinks
+    Right,
+    Straight,
+}
+
+/// Side of a signed angle: an angular error, an angular adjustment or a heading change
 ///
-/// Returns angle in radians [-π, π]
-pub fn compute_angular_error_with_arrival(
-    current_pos: &Point,
-    current_angle: f64,
-    target: &Target,
-    distance_to_target: f64,
-) -> f64 {
-    const APPROACH_START: f64 = 120.0;    // When to start using approach point (increased for smoother approach)
-    const MAX_OFFSET: f64 = 100.0;         // Maximum offset at APPROACH_START distance
+/// Headings are measured counterclockwise from +x on a map with y up, so the
+/// same sign convention holds everywhere: a positive angular error means the
+/// target is to the left, and the positive angular adjustment that removes it
+/// turns the vehicle left. The visualizer flips y only to draw, so left on the
+/// map is left on screen. A target straight behind (±π) keeps the side of its sign.
+pub fn turn_direction(angle: Radians) -> TurnDirection {
+    if angle > Radians::ZERO {
+        TurnDirection::Left
+    } else if angle < Radians::ZERO {
+        TurnDirection::Right
+    } else {
+        TurnDirection::Straight
+    }
+}
 
+/// Distance to target at which the controller starts steering to the approach point
+pub const APPROACH_START: f64 = 120.0;    // Increased for smoother approach
+/// Maximum approach point offset below the target (at APPROACH_START distance)
+pub const APPROACH_MAX_OFFSET: f64 = 100.0;
+
+/// Point the controller steers to for a given distance to target
+///
+/// - When far (>APPROACH_START): the target itself
+/// - When close: a virtual point below the target that converges to it
+pub fn compute_approach_point(target: &Target, distance_to_target: f64) -> Point {
     if distance_to_target > APPROACH_START {
         // Far away: navigate directly to target
-        compute_angular_error(current_pos, current_angle, &target.position)
+        target.position.clone()
     } else {
         // Close: navigate to dynamic approach point that converges to target
         // Use cubic curve for smoother final approach: offset = MAX_OFFSET * (distance/START)^1.5
         let t = distance_to_target / APPROACH_START;
-        let offset = MAX_OFFSET * t.powf(1.5);  // Cubic-like curve: approaches faster, then slows
+        let offset = APPROACH_MAX_OFFSET * t.powf(1.5);  // Cubic-like curve: approaches faster, then slows
 
-        let approach_point = Point::new(
+        Point::new(
             target.position.x,
             target.position.y - offset  // Point below target (lower Y), vehicle approaches upward to arrive at 90°
+        )
+    }
+}
+
+/// Approach corridor of a target, for drawing what the controller steers by
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApproachGeometry {
+    pub target: Point,
+    /// Distance to target at which the controller starts steering to the approach point (`APPROACH_START`)
+    pub approach_radius: f64,
+    /// Required arrival direction, from `approach_radius` behind the target to the target
+    pub axis: [Point; 2],
+    /// Distance to target below which a vehicle may arrive
+    pub arrival_radius: f64,
+    /// Region where a vehicle may arrive, the `arrival_radius` disc unless elliptical
+    #[serde(default, skip_serializing_if = "ArrivalGate::is_circle")]
+    pub arrival_gate: ArrivalGate,
+}
+
+impl ApproachGeometry {
+    pub fn new(target: &Target, arrival_radius: f64, arrival_gate: ArrivalGate) -> Self {
+        let (sin, cos) = target.required_angle.sin_cos();
+        let behind = Point::new(
+            target.position.x - APPROACH_START * cos,
+            target.position.y - APPROACH_START * sin,
         );
+        Self {
+            target: target.position.clone(),
+            approach_radius: APPROACH_START,
+            axis: [behind, target.position.clone()],
+            arrival_radius,
+            arrival_gate,
+        }
+    }
+}
+
+/// Region around the target where a vehicle may arrive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrivalGate {
+    /// Disc of the arrival radius
+    #[default]
+    Circle,
+    /// Ellipse centered on the target with semi-axis `along` the required angle
+    /// and `across` it, a berth more forgiving along the approach than beside it
+    Ellipse { along: f64, across: f64 },
+}
 
-        compute_angular_error(current_pos, current_angle, &approach_point)
+impl ArrivalGate {
+    pub fn is_circle(&self) -> bool {
+        matches!(self, Self::Circle)
+    }
+
+    /// True if `position` lies strictly inside the gate of `target`, `radius` being the circle radius
+    pub fn contains(&self, position: &Point, target: &Target, radius: f64) -> bool {
+        let (dx, dy) = (position.x - target.position.x, position.y - target.position.y);
+        match *self {
+            Self::Circle => dx.hypot(dy) < radius,
+            Self::Ellipse { along, across } => {
+                // Offset in the frame of the approach axis
+                let (sin, cos) = target.required_angle.sin_cos();
+                let forward = dx * cos + dy * sin;
+                let lateral = -dx * sin + dy * cos;
+                (forward / along).powi(2) + (lateral / across).powi(2) < 1.0
+            }
+        }
+    }
+
+    /// Radius of the largest disc inside the gate, what one step may travel without jumping over it
+    pub fn inner_radius(&self, radius: f64) -> f64 {
+        match *self {
+            Self::Circle => radius,
+            Self::Ellipse { along, across } => along.min(across),
+        }
     }
+
+    /// Reject an ellipse with a semi-axis that is not positive and finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        match *self {
+            Self::Ellipse { along, across } if ![along, across].iter().all(|axis| axis.is_finite() && *axis > 0.0) => {
+                Err(SimulationError::InvalidArrivalGate { along, across })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Radius of the tightest turn at `velocity` with turn rate `maneuverability` (rad/s)
+pub fn turn_radius(velocity: f64, maneuverability: f64) -> f64 {
+    if maneuverability > 0.0 {
+        velocity.abs() / maneuverability
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Approach point re-aimed so the turns around it stay on `map`
+///
+/// Same as `compute_approach_point` when a turn of `turn_radius` around the
+/// point fits on the map. Otherwise the point is pulled inwards to leave one
+/// turning radius to every edge, by less as the vehicle closes in so it still
+/// converges to a target placed at the edge.
+pub fn compute_approach_point_on_map(map: &Map, distance_to_target: f64, turn_radius: f64) -> Point {
+    let point = compute_approach_point(&map.target, distance_to_target);
+    if map.turn_fits(&point, turn_radius) {
+        return point;
+    }
+
+    let closing = (distance_to_target / APPROACH_START).min(1.0);
+    let margin = turn_radius.min(map.width / 2.0).min(map.height / 2.0) * closing;
+    Point::new(
+        clamp(point.x, margin, map.width - margin),
+        clamp(point.y, margin, map.height - margin),
+    )
+}
+
+/// Calculate angular error with arrival angle consideration
+/// Uses a virtual approach point that converges to target as vehicle gets closer
+///
+/// Strategy for high-precision 90° arrival (±2°):
+/// - When far (>120 units): Navigates directly to target
+/// - When close (<120 units): Navigates to dynamic approach point below target
+/// - Offset decreases with cubic curve for smoother final approach
+///
+/// Returns angle in radians [-π, π]
+pub fn compute_angular_error_with_arrival(
+    current_pos: &Point,
+    current_angle: Radians,
+    target: &Target,
+    distance_to_target: f64,
+) -> Radians {
+    let approach_point = compute_approach_point(target, distance_to_target);
+    compute_angular_error(current_pos, current_angle, &approach_point)
 }
 
 /// Clamp a value between min and max
-pub fn clamp(value: f64, min: f64, max: f64) -> f64 {
+pub fn clamp<T: PartialOrd>(value: T, min: T, max: T) -> T {
     if value < min {
         min
     } else if value > max {
@@ -161,6 +570,34 @@ pub fn clamp(value: f64, min: f64, max: f64) -> f64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_approach_axis_runs_along_the_required_angle_into_the_target() {
+        for degrees in [90.0f64, 30.0, -135.0] {
+            let mut map = Map::new(1000.0, 800.0, 500.0, 400.0);
+            map.target.required_angle = Degrees(degrees).to_radians();
+            let geometry = ApproachGeometry::new(&map.target, 25.0, ArrivalGate::Circle);
+
+            let [start, end] = &geometry.axis;
+            assert_eq!(*end, map.target.position);
+            assert!((euclidean_distance(start, end) - APPROACH_START).abs() < 1e-9);
+            let heading = Radians((end.y - start.y).atan2(end.x - start.x));
+            assert!(normalize_angle(heading - map.target.required_angle).abs().0 < 1e-9, "{}", degrees);
+            assert_eq!((geometry.approach_radius, geometry.arrival_radius), (APPROACH_START, 25.0));
+        }
+    }
+
+    #[test]
+    fn test_arrival_angle_error_wraps_around_the_circle() {
+        let mut target = Target { position: Point::new(0.0, 0.0), required_angle: Degrees(180.0).to_radians() };
+        let error = |target: &Target, degrees: f64| target.angle_error(Degrees(degrees).to_radians()).to_degrees().0;
+        assert!((error(&target, -179.5) - 0.5).abs() < 1e-9);
+        assert!((error(&target, 170.0) - 10.0).abs() < 1e-9);
+
+        target.required_angle = Degrees(90.0).to_radians();
+        assert!(error(&target, 450.0).abs() < 1e-9);
+        assert!((error(&target, -90.0) - 180.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_euclidean_distance() {
         let p1 = Point::new(0.0, 0.0);
@@ -170,9 +607,192 @@ mod tests {
 
     #[test]
     fn test_normalize_angle() {
-        assert!((normalize_angle(7.0) - (7.0 - 2.0 * PI)).abs() < 0.001);
-        assert!((normalize_angle(-7.0) - (-7.0 + 2.0 * PI)).abs() < 0.001);
-        assert!((normalize_angle(PI) - PI).abs() < 0.001);
+        assert!((normalize_angle(Radians(7.0)).0 - (7.0 - 2.0 * PI)).abs() < 0.001);
+        assert!((normalize_angle(Radians(-7.0)).0 - (-7.0 + 2.0 * PI)).abs() < 0.001);
+        assert!((normalize_angle(Radians(PI)).0 - PI).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_turn_direction_in_every_quadrant() {
+        use TurnDirection::{Left, Right};
+
+        let origin = Point::new(0.0, 0.0);
+        let (ne, nw, se, sw) = (Point::new(1.0, 1.0), Point::new(-1.0, 1.0), Point::new(1.0, -1.0), Point::new(-1.0, -1.0));
+        // Heading (degrees) and the turn towards a target to the NE, NW, SE and SW
+        let cases = [
+            (0.0, [Left, Left, Right, Right]),
+            (90.0, [Right, Left, Right, Left]),
+            (180.0, [Right, Right, Left, Left]),
+            (-90.0, [Left, Right, Left, Right]),
+            (30.0, [Left, Left, Right, Right]),
+        ];
+        for (heading, expected) in cases {
+            let heading = Degrees(heading).to_radians();
+            let turns = [&ne, &nw, &se, &sw].map(|target| turn_direction(compute_angular_error(&origin, heading, target)));
+            assert_eq!(turns, expected, "heading {}°", heading.to_degrees());
+        }
+
+        assert_eq!(turn_direction(Radians::ZERO), TurnDirection::Straight);
+        assert_eq!(turn_direction(Radians(PI)), Left);
+        assert_eq!(turn_direction(Radians(-PI)), Right);
+    }
+
+    #[test]
+    fn test_approach_point_converges_to_target() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+
+        let far = compute_approach_point(&map.target, APPROACH_START + 1.0);
+        assert_eq!((far.x, far.y), (500.0, 700.0));
+
+        let start = compute_approach_point(&map.target, APPROACH_START);
+        assert!((start.y - (700.0 - APPROACH_MAX_OFFSET)).abs() < 1e-9);
+
+        let at_target = compute_approach_point(&map.target, 0.0);
+        assert!((at_target.y - 700.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_seeded_start_is_reproducible_and_in_start_zone() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let a = map.random_start_position_with(&mut StdRng::seed_from_u64(7));
+        let b = map.random_start_position_with(&mut StdRng::seed_from_u64(7));
+
+        assert_eq!((a.x, a.y), (b.x, b.y));
+        assert!(a.y <= map.height * map.start_zone.height_percentage);
+    }
+
+    #[test]
+    fn test_away_from_target_headings_face_away() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..500 {
+            let position = map.random_start_position_with(&mut rng);
+            let heading = HeadingDistribution::AwayFromTarget.sample(&map, &position, &mut rng);
+            let anti_target = compute_angular_error(&position, Radians::ZERO, &map.target.position) + Radians(PI);
+
+            let offset = normalize_angle(heading - anti_target).abs().to_degrees();
+            assert!(offset.0 <= AWAY_FROM_TARGET_SPREAD + 1e-9, "{}° from the anti-target direction", offset);
+        }
+    }
+
+    #[test]
+    fn test_heading_distribution_parse() {
+        assert_eq!(HeadingDistribution::parse("Uniform"), Some(HeadingDistribution::Uniform));
+        assert_eq!(HeadingDistribution::parse("away"), Some(HeadingDistribution::AwayFromTarget));
+        assert_eq!(HeadingDistribution::parse("-45"), Some(HeadingDistribution::Fixed(Degrees(-45.0))));
+        assert_eq!(HeadingDistribution::parse("sideways"), None);
+        assert_eq!(HeadingDistribution::parse("inf"), None);
+    }
+
+    #[test]
+    fn test_stratified_starts_put_one_iteration_in_every_bin() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let stratified = StartDistribution::Stratified { bins: 10 };
+        let mut counts = [0; 10];
+        for iteration in 0..10 {
+            let start = stratified.sample(&map, iteration, &mut StdRng::seed_from_u64(iteration as u64));
+            assert!(start.y <= map.height * map.start_zone.height_percentage);
+            counts[(start.x / 100.0) as usize] += 1;
+        }
+        assert_eq!(counts, [1; 10]);
+    }
+
+    #[test]
+    fn test_gaussian_starts_cluster_near_the_center() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let gaussian = StartDistribution::GaussianAroundCenter { sigma_fraction: 0.1 };
+        let mut rng = StdRng::seed_from_u64(3);
+        let xs: Vec<f64> = (0..2000).map(|i| gaussian.sample(&map, i, &mut rng).x).collect();
+
+        assert!(xs.iter().all(|x| (0.0..map.width).contains(x)));
+        let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+        let sigma = (xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / xs.len() as f64).sqrt();
+        assert!((mean - 500.0).abs() < 10.0, "mean {}", mean);
+        assert!((sigma - 100.0).abs() < 10.0, "sigma {}", sigma);
+        // Within 2 sigma of the center: about 95% against 40% for uniform starts
+        let near = xs.iter().filter(|x| (*x - 500.0).abs() < 200.0).count() as f64 / xs.len() as f64;
+        assert!(near > 0.9, "{}", near);
+    }
+
+    #[test]
+    fn test_start_distribution_parse_and_validate() {
+        assert_eq!(StartDistribution::parse("Uniform"), Some(StartDistribution::Uniform));
+        assert_eq!(StartDistribution::parse("gaussian"), Some(StartDistribution::GaussianAroundCenter { sigma_fraction: DEFAULT_START_SIGMA_FRACTION }));
+        assert_eq!(StartDistribution::parse("gaussian:0.2"), Some(StartDistribution::GaussianAroundCenter { sigma_fraction: 0.2 }));
+        assert_eq!(StartDistribution::parse("stratified:5"), Some(StartDistribution::Stratified { bins: 5 }));
+        assert_eq!(StartDistribution::parse("stratified:x"), None);
+        assert_eq!(StartDistribution::parse("uniform:3"), None);
+
+        assert!(StartDistribution::Stratified { bins: 0 }.validate().is_err());
+        assert!(StartDistribution::GaussianAroundCenter { sigma_fraction: f64::NAN }.validate().is_err());
+        assert!(StartDistribution::GaussianAroundCenter { sigma_fraction: -0.1 }.validate().is_err());
+        assert!(StartDistribution::parse("stratified").unwrap().validate().is_ok());
+
+        let json = serde_json::to_value(StartDistribution::Stratified { bins: 4 }).unwrap();
+        assert_eq!(json, serde_json::json!({ "stratified": { "bins": 4 } }));
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_geometry() {
+        assert!(Map::try_new(1000.0, 800.0, 500.0, 700.0).is_ok());
+        assert!(matches!(
+            Map::try_new(0.0, 800.0, 0.0, 700.0),
+            Err(MapError::InvalidDimensions { .. })
+        ));
+        assert!(matches!(
+            Map::try_new(1000.0, 800.0, 500.0, 900.0),
+            Err(MapError::TargetOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn test_turn_fits_needs_a_radius_to_every_edge() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        assert!(map.contains(&Point::new(0.0, 800.0)));
+        assert!(!map.contains(&Point::new(-0.1, 400.0)));
+
+        assert!(map.turn_fits(&Point::new(500.0, 400.0), 100.0));
+        assert!(!map.turn_fits(&Point::new(50.0, 400.0), 100.0));
+        assert!(!map.turn_fits(&Point::new(500.0, 750.0), 100.0));
+        assert!(!map.turn_fits(&Point::new(500.0, 400.0), f64::INFINITY));
+    }
+
+    #[test]
+    fn test_approach_point_on_map_is_pulled_off_the_edges() {
+        let exam = MapPreset::Exam.map();
+        let unchanged = compute_approach_point_on_map(&exam, 500.0, 30.0);
+        assert_eq!(unchanged.x, exam.target.position.x);
+        assert_eq!(unchanged.y, exam.target.position.y);
+
+        let corner = MapPreset::CornerTopLeft.map();
+        let far = compute_approach_point_on_map(&corner, 500.0, 100.0);
+        assert_eq!((far.x, far.y), (100.0, 700.0));
+
+        let near = compute_approach_point_on_map(&corner, 60.0, 100.0);
+        assert!(near.x < far.x && near.y > far.y);
+
+        let arrived = compute_approach_point_on_map(&corner, 0.0, 100.0);
+        assert_eq!((arrived.x, arrived.y), (50.0, 750.0));
+    }
+
+    #[test]
+    fn test_map_presets() {
+        for preset in MapPreset::ALL {
+            assert_eq!(MapPreset::parse(preset.name()), Some(preset));
+            let map = preset.map();
+            assert!(map.contains(&map.target.position), "{}", preset.name());
+            assert_eq!((map.width, map.height), EXAM_MAP_SIZE);
+        }
+        assert_eq!(MapPreset::parse("Corner_Top_Right"), Some(MapPreset::CornerTopRight));
+        assert_eq!(MapPreset::parse("center"), None);
     }
 
     #[test]
@@ -181,4 +801,56 @@ mod tests {
         assert_eq!(clamp(-5.0, 0.0, 10.0), 0.0);
         assert_eq!(clamp(15.0, 0.0, 10.0), 10.0);
     }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn mirrored(p: &Point, target: &Point) -> Point {
+            Point::new(2.0 * target.x - p.x, p.y)
+        }
+
+        proptest! {
+            #[test]
+            fn normalize_angle_lands_in_range(angle in -1e300f64..1e300) {
+                let normalized = normalize_angle(Radians(angle));
+                prop_assert!((-PI..=PI).contains(&normalized.0), "{} -> {}", angle, normalized);
+            }
+
+            #[test]
+            fn normalize_angle_is_idempotent(angle in proptest::num::f64::NORMAL | proptest::num::f64::ZERO) {
+                let once = normalize_angle(Radians(angle));
+                prop_assert_eq!(normalize_angle(once), once);
+            }
+
+            #[test]
+            fn angular_error_is_antisymmetric_across_the_target(
+                x in 0.0f64..1000.0,
+                y in 0.0f64..800.0,
+                angle in -10.0f64..10.0,
+                target_x in 0.0f64..1000.0,
+                target_y in 0.0f64..800.0,
+            ) {
+                let position = Point::new(x, y);
+                let target = Point::new(target_x, target_y);
+                prop_assume!(euclidean_distance(&position, &target) > 1e-6);
+
+                let error = compute_angular_error(&position, Radians(angle), &target);
+                // ±π are the same direction, the sign is arbitrary there
+                prop_assume!(PI - error.abs().0 > 1e-9);
+
+                let mirrored_error = compute_angular_error(&mirrored(&position, &target), Radians(PI - angle), &target);
+                prop_assert!((error + mirrored_error).abs().0 < 1e-9, "{} vs {}", error, mirrored_error);
+            }
+        }
+
+        #[test]
+        fn normalize_angle_terminates_for_huge_and_infinite_angles() {
+            assert!((-PI..=PI).contains(&normalize_angle(Radians(1e12)).0));
+            assert!((-PI..=PI).contains(&normalize_angle(Radians(1e300)).0));
+            assert!((-PI..=PI).contains(&normalize_angle(Radians(-1e20)).0));
+            assert!(normalize_angle(Radians(f64::INFINITY)).is_nan());
+            assert!(normalize_angle(Radians(f64::NAN)).is_nan());
+        }
+    }
 }
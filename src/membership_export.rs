@@ -1,27 +1,104 @@
 // Module for exporting membership function visualizations
 
-use crate::fuzzy_system::LinguisticVariable;
+use crate::fuzzy_system::{compute_control_surface, ControlSurface, FuzzySet, FuzzySystem, LinguisticVariable};
+use crate::navigation::NavigationController;
+use crate::simulation::Language;
 use crate::vehicle::{create_vehicle_preset, VehicleType};
+use plotters::coord::Shift;
 use plotters::prelude::*;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::Write;
 
 const IMAGE_WIDTH: u32 = 800;
 const IMAGE_HEIGHT: u32 = 600;
 
+/// Color palette for the fuzzy sets of a variable, in set order
+const SET_COLORS: [RGBColor; 8] = [
+    RED,
+    BLUE,
+    GREEN,
+    MAGENTA,
+    CYAN,
+    RGBColor(255, 165, 0),   // Orange
+    RGBColor(128, 0, 128),   // Purple
+    RGBColor(255, 192, 203), // Pink
+];
+
+/// Number of points used to sample a membership function over its variable range
+const CURVE_SAMPLES: usize = 201;
+
+/// Cells per axis of the control surfaces exported for every vehicle
+const SURFACE_RESOLUTION: usize = 60;
+
+/// Output format of the exported figures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// Vector output, suitable for the paper
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+
+    /// Parse a format name ("png" or "svg", case insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
 /// Export all membership functions for a given linguistic variable
 pub fn export_variable_memberships(
     variable: &LinguisticVariable,
     output_path: &str,
+    format: ImageFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let root = BitMapBackend::new(output_path, (IMAGE_WIDTH, IMAGE_HEIGHT))
-        .into_drawing_area();
-    root.fill(&WHITE)?;
+    let caption = format!("Funciones de Pertenencia: {}", variable.name);
+
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(output_path, (IMAGE_WIDTH, IMAGE_HEIGHT)).into_drawing_area();
+            root.fill(&WHITE)?;
+            draw_variable_memberships(&root, variable, &caption, 40)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(output_path, (IMAGE_WIDTH, IMAGE_HEIGHT)).into_drawing_area();
+            root.fill(&WHITE)?;
+            draw_variable_memberships(&root, variable, &caption, 40)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
 
+/// Draw the membership functions of a variable into a provided drawing area
+///
+/// The area is not cleared nor presented, so it can be a panel of a larger figure.
+fn draw_variable_memberships<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    variable: &LinguisticVariable,
+    caption: &str,
+    caption_size: u32,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
     let (min, max) = variable.range;
-    let name = &variable.name;
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption(format!("Funciones de Pertenencia: {}", name), ("sans-serif", 40))
+    let mut chart = ChartBuilder::on(area)
+        .caption(caption, ("sans-serif", caption_size))
         .margin(15)
         .x_label_area_size(40)
         .y_label_area_size(50)
@@ -33,31 +110,11 @@ pub fn export_variable_memberships(
         .y_desc("Grado de Pertenencia")
         .draw()?;
 
-    // Color palette for different sets
-    let colors = vec![
-        &RED,
-        &BLUE,
-        &GREEN,
-        &MAGENTA,
-        &CYAN,
-        &RGBColor(255, 165, 0), // Orange
-        &RGBColor(128, 0, 128), // Purple
-        &RGBColor(255, 192, 203), // Pink
-    ];
-
     // Plot each fuzzy set
+    let samples = variable.sample(CURVE_SAMPLES);
     for (idx, set) in variable.fuzzy_sets.iter().enumerate() {
-        let color = colors[idx % colors.len()];
-        let num_points = 200;
-        let step = (max - min) / num_points as f64;
-
-        let points: Vec<(f64, f64)> = (0..=num_points)
-            .map(|i| {
-                let x = min + i as f64 * step;
-                let y = set.membership_function.evaluate(x);
-                (x, y)
-            })
-            .collect();
+        let color = SET_COLORS[idx % SET_COLORS.len()];
+        let points: Vec<(f64, f64)> = samples.iter().map(|(x, memberships)| (*x, memberships[idx])).collect();
 
         chart
             .draw_series(LineSeries::new(points, color.stroke_width(2)))?
@@ -67,123 +124,492 @@ pub fn export_variable_memberships(
 
     chart
         .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+/// Export every variable of the controller of a vehicle type as one grid figure
+///
+/// Inputs first, then the output, two panels per row (2×2 for the navigation
+/// controller), with the vehicle name in the super-title.
+pub fn export_navigation_overview(
+    vehicle_type: VehicleType,
+    output_path: &str,
+    format: ImageFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let controller = NavigationController::new(&create_vehicle_preset(vehicle_type));
+    let title = format!("Controlador de Navegación Difuso: {}", vehicle_type.display_name(Language::Spanish));
+    let size = (2 * IMAGE_WIDTH, 2 * IMAGE_HEIGHT);
+
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(output_path, size).into_drawing_area();
+            draw_navigation_overview(&root, &controller, &title)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(output_path, size).into_drawing_area();
+            draw_navigation_overview(&root, &controller, &title)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_navigation_overview<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    controller: &NavigationController,
+    title: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let body = root.titled(title, ("sans-serif", 48))?;
+
+    let system = controller.fuzzy_system();
+    let variables: Vec<&LinguisticVariable> = system.input_variables.iter()
+        .chain(std::iter::once(&system.output_variable))
+        .collect();
+
+    let rows = variables.len().div_ceil(2);
+    let panels = body.split_evenly((rows, 2));
+
+    for (panel, variable) in panels.iter().zip(&variables) {
+        draw_variable_memberships(panel, variable, &variable.name, 32)?;
+    }
+
+    Ok(())
+}
+
+/// Grid of [min, max] the curves are sampled on, the same as `LinguisticVariable::sample`
+fn curve_xs(min: f64, max: f64) -> Vec<f64> {
+    let step = (max - min) / (CURVE_SAMPLES - 1) as f64;
+    (0..CURVE_SAMPLES).map(|i| min + i as f64 * step).collect()
+}
+
+/// Membership of `set` over `xs`, clipped at `ceiling`
+fn set_curve(set: &FuzzySet, xs: &[f64], ceiling: f64) -> Vec<(f64, f64)> {
+    let mut memberships = vec![0.0; xs.len()];
+    set.evaluate_slice(xs, &mut memberships);
+    xs.iter().zip(memberships).map(|(&x, membership)| (x, membership.min(ceiling))).collect()
+}
+
+/// Plot the Mamdani inference of `system` for one input point
+///
+/// The output sets are drawn faintly in full, clipped at their activation level,
+/// the aggregated (max) envelope is shaded and a vertical line marks the
+/// defuzzified value. The legend lists every activated set with its degree.
+/// Returns the defuzzified value.
+pub fn export_defuzzification_snapshot(
+    system: &FuzzySystem,
+    inputs: &HashMap<String, f64>,
+    output_path: &str,
+    format: ImageFormat,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let trace = system.evaluate_traced(inputs);
+
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(output_path, (IMAGE_WIDTH, IMAGE_HEIGHT)).into_drawing_area();
+            draw_defuzzification_snapshot(&root, system, inputs, &trace.set_activations, trace.defuzzified_value)?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(output_path, (IMAGE_WIDTH, IMAGE_HEIGHT)).into_drawing_area();
+            draw_defuzzification_snapshot(&root, system, inputs, &trace.set_activations, trace.defuzzified_value)?;
+        }
+    }
+
+    Ok(trace.defuzzified_value)
+}
+
+fn draw_defuzzification_snapshot<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    system: &FuzzySystem,
+    inputs: &HashMap<String, f64>,
+    set_activations: &BTreeMap<String, f64>,
+    defuzzified_value: f64,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let output = &system.output_variable;
+    let (min, max) = output.range;
+
+    // Inputs in the order the system declares them
+    let input_text = system.input_variables.iter()
+        .filter_map(|var| inputs.get(&var.name).map(|value| format!("{} = {:.3}", var.name, value)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(format!("Inferencia: {}", output.name), ("sans-serif", 32))
+        .margin(15)
+        .x_label_area_size(60)
+        .y_label_area_size(50)
+        .build_cartesian_2d(min..max, 0.0..1.1)?;
+
+    chart
+        .configure_mesh()
+        .x_desc(input_text)
+        .y_desc("Grado de Pertenencia")
+        .draw()?;
+
+    // Aggregated envelope (max of the clipped sets), as computed by the centroid defuzzifier
+    let xs = curve_xs(min, max);
+    let mut envelope: Vec<(f64, f64)> = xs.iter().map(|&x| (x, 0.0)).collect();
+    for set in &output.fuzzy_sets {
+        if let Some(&activation) = set_activations.get(&set.name) {
+            for ((_, aggregated), (_, clipped)) in envelope.iter_mut().zip(set_curve(set, &xs, activation)) {
+                *aggregated = aggregated.max(clipped);
+            }
+        }
+    }
+    chart
+        .draw_series(AreaSeries::new(envelope, 0.0, RGBColor(120, 120, 120).mix(0.35)))?
+        .label("Agregación (max)")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], RGBColor(120, 120, 120).mix(0.35).filled()));
+
+    for (idx, set) in output.fuzzy_sets.iter().enumerate() {
+        let color = SET_COLORS[idx % SET_COLORS.len()];
+
+        // Full set, faint
+        chart.draw_series(LineSeries::new(
+            set_curve(set, &xs, 1.0),
+            color.mix(0.3).stroke_width(1),
+        ))?;
+
+        // Clipped at its activation level
+        let activation = set_activations.get(&set.name).copied().unwrap_or(0.0);
+        if activation > f64::EPSILON {
+            chart
+                .draw_series(LineSeries::new(
+                    set_curve(set, &xs, activation),
+                    color.stroke_width(2),
+                ))?
+                .label(format!("{}: {:.3}", set.name, activation))
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(3)));
+        }
+    }
+
+    chart
+        .draw_series(std::iter::once(PathElement::new(
+            vec![(defuzzified_value, 0.0), (defuzzified_value, 1.1)],
+            BLACK.stroke_width(2),
+        )))?
+        .label(format!("Valor defuzzificado: {:.4}", defuzzified_value))
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK.stroke_width(2)));
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
 
     root.present()?;
     Ok(())
 }
 
+/// Scale and unit used to display a navigation variable, angles are shown in degrees
+fn display_units(variable: &str) -> (f64, &'static str) {
+    match variable {
+        "error_angular" => (180.0 / std::f64::consts::PI, "°"),
+        "ajuste_angular" => (180.0 / std::f64::consts::PI, "°/s"),
+        _ => (1.0, ""),
+    }
+}
+
+fn axis_label(variable: &str) -> String {
+    match display_units(variable) {
+        (_, "") => variable.to_string(),
+        (_, unit) => format!("{} [{}]", variable, unit),
+    }
+}
+
+/// Heatmap color of a normalized value, blue (0) to red (1)
+fn heat_color(t: f64) -> HSLColor {
+    HSLColor(0.66 * (1.0 - t.clamp(0.0, 1.0)), 0.85, 0.5)
+}
+
+/// Render the defuzzified output over two inputs as a heatmap with a colorbar
+///
+/// `resolution` is the number of cells per axis. Returns the computed surface.
+pub fn export_control_surface_plot(
+    system: &FuzzySystem,
+    x_var: &str,
+    y_var: &str,
+    fixed_inputs: &HashMap<String, f64>,
+    resolution: usize,
+    output_path: &str,
+    format: ImageFormat,
+) -> Result<ControlSurface, Box<dyn std::error::Error>> {
+    let surface = compute_control_surface(system, x_var, y_var, fixed_inputs, resolution)?;
+    let title = format!("Superficie de Control: {}", system.name);
+    write_control_surface(&surface, &title, output_path, format)?;
+    Ok(surface)
+}
+
+fn write_control_surface(
+    surface: &ControlSurface,
+    title: &str,
+    output_path: &str,
+    format: ImageFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let size = (IMAGE_WIDTH + 160, IMAGE_HEIGHT);
+
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(output_path, size).into_drawing_area();
+            draw_control_surface(&root, surface, title)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(output_path, size).into_drawing_area();
+            draw_control_surface(&root, surface, title)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_control_surface<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    surface: &ControlSurface,
+    title: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let fixed_text = surface.fixed_inputs.iter()
+        .map(|(name, value)| {
+            let (scale, unit) = display_units(name);
+            format!("{} = {:.2}{}", name, value * scale, unit)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let body = root.titled(title, ("sans-serif", 32))?;
+    let (map_area, bar_area) = body.split_horizontally(IMAGE_WIDTH);
+
+    let (x_scale, _) = display_units(&surface.x_variable);
+    let (y_scale, _) = display_units(&surface.y_variable);
+    let (z_scale, _) = display_units(&surface.output_variable);
+
+    // Cells tile the variable ranges, so the axes span from the first to the last cell edge
+    let half_step = |values: &[f64]| match values {
+        [a, b, ..] => (b - a) / 2.0,
+        _ => 0.5,
+    };
+    let x_half = half_step(&surface.x_values);
+    let y_half = half_step(&surface.y_values);
+    let x_min = (surface.x_values[0] - x_half) * x_scale;
+    let x_max = (surface.x_values[surface.x_values.len() - 1] + x_half) * x_scale;
+    let y_min = (surface.y_values[0] - y_half) * y_scale;
+    let y_max = (surface.y_values[surface.y_values.len() - 1] + y_half) * y_scale;
+
+    let (z_lo, z_hi) = surface.value_range();
+    let (z_lo, z_hi) = (z_lo * z_scale, z_hi * z_scale);
+    // Avoid a zero-height colorbar when the output is constant
+    let (z_lo, z_hi) = if z_hi - z_lo < 1e-9 { (z_lo - 0.5, z_hi + 0.5) } else { (z_lo, z_hi) };
+    let normalize = |z: f64| (z * z_scale - z_lo) / (z_hi - z_lo);
+
+    let mut chart = ChartBuilder::on(&map_area)
+        .caption(fixed_text, ("sans-serif", 18))
+        .margin(15)
+        .x_label_area_size(45)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_min..x_max, y_min..y_max)?;
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc(axis_label(&surface.x_variable))
+        .y_desc(axis_label(&surface.y_variable))
+        .draw()?;
+
+    chart.draw_series(surface.y_values.iter().enumerate().flat_map(|(j, &y)| {
+        surface.x_values.iter().enumerate().map(move |(i, &x)| {
+            Rectangle::new(
+                [
+                    ((x - x_half) * x_scale, (y - y_half) * y_scale),
+                    ((x + x_half) * x_scale, (y + y_half) * y_scale),
+                ],
+                heat_color(normalize(surface.values[j][i])).filled(),
+            )
+        })
+    }))?;
+
+    // Colorbar
+    let mut bar = ChartBuilder::on(&bar_area)
+        .margin_top(50)
+        .margin_bottom(60)
+        .margin_right(15)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..1.0, z_lo..z_hi)?;
+
+    bar.configure_mesh()
+        .disable_mesh()
+        .disable_x_axis()
+        .y_desc(axis_label(&surface.output_variable))
+        .draw()?;
+
+    const BAR_STEPS: usize = 100;
+    let step = (z_hi - z_lo) / BAR_STEPS as f64;
+    bar.draw_series((0..BAR_STEPS).map(|k| {
+        let z = z_lo + k as f64 * step;
+        Rectangle::new([(0.0, z), (1.0, z + step)], heat_color((k as f64 + 0.5) / BAR_STEPS as f64).filled())
+    }))?;
+
+    Ok(())
+}
+
+/// Heatmap of `ajuste_angular` over angular error and distance for every vehicle preset
+///
+/// The relative velocity is fixed at 0.5. Files are written to
+/// `<dir>/<vehicle>/control_surface.png`. Returns the written paths.
+pub fn export_all_control_surfaces(dir: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut fixed_inputs = HashMap::new();
+    fixed_inputs.insert("velocidad_relativa".to_string(), 0.5);
+
+    let mut paths = Vec::new();
+    for vehicle_type in VehicleType::ALL {
+        let controller = NavigationController::new(&create_vehicle_preset(vehicle_type));
+        let surface = compute_control_surface(
+            controller.fuzzy_system(),
+            "error_angular",
+            "distancia_al_objetivo",
+            &fixed_inputs,
+            SURFACE_RESOLUTION,
+        )?;
+
+        let vehicle_dir = format!("{}/{}", dir, vehicle_type.id());
+        fs::create_dir_all(&vehicle_dir)?;
+        let path = format!("{}/control_surface.png", vehicle_dir);
+        let title = format!("Superficie de Control: {}", vehicle_type.display_name(Language::Spanish));
+        write_control_surface(&surface, &title, &path, ImageFormat::Png)?;
+        println!("  ✓ {}", path);
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Write the sampled membership functions of a variable as CSV
+///
+/// Columns are `x` followed by one column per set, `points` rows over the variable range.
+pub fn export_variable_csv(
+    variable: &LinguisticVariable,
+    points: usize,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = fs::File::create(path)?;
+
+    let header: Vec<&str> = std::iter::once("x")
+        .chain(variable.fuzzy_sets.iter().map(|set| set.name.as_str()))
+        .collect();
+    writeln!(file, "{}", header.join(","))?;
+
+    for (x, memberships) in variable.sample(points) {
+        let row: Vec<String> = std::iter::once(x)
+            .chain(memberships)
+            .map(|value| value.to_string())
+            .collect();
+        writeln!(file, "{}", row.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// CSV counterpart of `export_navigation_memberships`: `<dir>/<vehicle>/input_<variable>.csv`
+/// and `output_<variable>.csv`, sampled like the plots. Returns the written paths.
+pub fn export_navigation_csvs(
+    vehicle_type: VehicleType,
+    dir: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let controller = NavigationController::new(&create_vehicle_preset(vehicle_type));
+    let system = controller.fuzzy_system();
+
+    let vehicle_dir = format!("{}/{}", dir, vehicle_type.id());
+    fs::create_dir_all(&vehicle_dir)?;
+
+    let variables = system.input_variables.iter()
+        .map(|variable| ("input", variable))
+        .chain(std::iter::once(("output", &system.output_variable)));
+
+    let mut paths = Vec::new();
+    for (kind, variable) in variables {
+        let path = format!("{}/{}_{}.csv", vehicle_dir, kind, variable.name);
+        export_variable_csv(variable, CURVE_SAMPLES, &path)?;
+        println!("  ✓ {}", path);
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
 /// Export all membership functions from the navigation controller
 pub fn export_navigation_memberships(
     vehicle_type: VehicleType,
     output_dir: &str,
+    format: ImageFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Create output directory if it doesn't exist
     fs::create_dir_all(output_dir)?;
 
     // Export all navigation variables for this vehicle type
-    export_all_navigation_variables(vehicle_type, output_dir)?;
+    let controller = NavigationController::new(&create_vehicle_preset(vehicle_type));
+    export_all_navigation_variables(&controller, vehicle_type.id(), output_dir, format)?;
+
+    // All variables in one figure
+    let overview_path = format!("{}/{}/overview.{}", output_dir, vehicle_type.id(), format.extension());
+    export_navigation_overview(vehicle_type, &overview_path, format)?;
+    println!("  ✓ {}", overview_path);
 
     Ok(())
 }
 
-/// Export all navigation system variables for all vehicle types
+/// Export every input and output variable of a controller to `<output_dir>/<vehicle_name>/`
+///
+/// Files are named `input_<variable>.<ext>` and `output_<variable>.<ext>`.
+/// Returns the written paths.
 pub fn export_all_navigation_variables(
-    vehicle_type: VehicleType,
+    controller: &NavigationController,
+    vehicle_name: &str,
     output_dir: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::fuzzy_system::{triangular, trapezoidal, FuzzySet, LinguisticVariable};
-    use std::f64::consts::PI;
-
-    let characteristics = create_vehicle_preset(vehicle_type);
-    let maneuverability = characteristics.maneuverability;
+    format: ImageFormat,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let system = controller.fuzzy_system();
 
-    let vehicle_dir = format!("{}/{}", output_dir, vehicle_type.name());
+    let vehicle_dir = format!("{}/{}", output_dir, vehicle_name);
     fs::create_dir_all(&vehicle_dir)?;
 
-    println!("\nExportando funciones de pertenencia para {}...", vehicle_type.name());
-
-    // INPUT 1: distancia_al_objetivo
-    let mut dist_var = LinguisticVariable::new("distancia_al_objetivo", (0.0, 1000.0));
-    dist_var.add_set(FuzzySet::new("muy_cerca", trapezoidal(0.0, 0.0, 50.0, 100.0)));
-    dist_var.add_set(FuzzySet::new("media", triangular(80.0, 200.0, 400.0)));
-    dist_var.add_set(FuzzySet::new("lejos", trapezoidal(350.0, 500.0, 1000.0, 1000.0)));
-
-    let path = format!("{}/input_distancia_al_objetivo.png", vehicle_dir);
-    export_variable_memberships(&dist_var, &path)?;
-    println!("  ✓ {}", path);
-
-    // INPUT 2: error_angular
-    let mut error_var = LinguisticVariable::new("error_angular", (-PI, PI));
-    error_var.add_set(FuzzySet::new(
-        "alineado",
-        trapezoidal(-10f64.to_radians(), -5f64.to_radians(), 5f64.to_radians(), 10f64.to_radians()),
-    ));
-    error_var.add_set(FuzzySet::new(
-        "desviado_izq",
-        triangular(-90f64.to_radians(), -45f64.to_radians(), -10f64.to_radians()),
-    ));
-    error_var.add_set(FuzzySet::new(
-        "desviado_der",
-        triangular(10f64.to_radians(), 45f64.to_radians(), 90f64.to_radians()),
-    ));
-    error_var.add_set(FuzzySet::new(
-        "muy_desviado_izq",
-        trapezoidal(-PI, -150f64.to_radians(), -120f64.to_radians(), -70f64.to_radians()),
-    ));
-    error_var.add_set(FuzzySet::new(
-        "muy_desviado_der",
-        trapezoidal(70f64.to_radians(), 120f64.to_radians(), 150f64.to_radians(), PI),
-    ));
-
-    let path = format!("{}/input_error_angular.png", vehicle_dir);
-    export_variable_memberships(&error_var, &path)?;
-    println!("  ✓ {}", path);
-
-    // INPUT 3: velocidad_relativa
-    let mut vel_var = LinguisticVariable::new("velocidad_relativa", (0.0, 1.0));
-    vel_var.add_set(FuzzySet::new("lenta", triangular(0.0, 0.0, 0.3)));
-    vel_var.add_set(FuzzySet::new("media", triangular(0.2, 0.5, 0.8)));
-    vel_var.add_set(FuzzySet::new("rapida", trapezoidal(0.7, 1.0, 1.0, 1.0)));
-
-    let path = format!("{}/input_velocidad_relativa.png", vehicle_dir);
-    export_variable_memberships(&vel_var, &path)?;
-    println!("  ✓ {}", path);
-
-    // OUTPUT 1: ajuste_angular
-    let mut ang_out_var = LinguisticVariable::new("ajuste_angular", (-maneuverability, maneuverability));
-    ang_out_var.add_set(FuzzySet::new(
-        "girar_izq",
-        triangular(-maneuverability, -0.7 * maneuverability, -0.3 * maneuverability),
-    ));
-    ang_out_var.add_set(FuzzySet::new(
-        "leve_izq",
-        triangular(-0.4 * maneuverability, -0.2 * maneuverability, 0.0),
-    ));
-    ang_out_var.add_set(FuzzySet::new(
-        "mantener",
-        triangular(-0.1 * maneuverability, 0.0, 0.1 * maneuverability),
-    ));
-    ang_out_var.add_set(FuzzySet::new(
-        "leve_der",
-        triangular(0.0, 0.2 * maneuverability, 0.4 * maneuverability),
-    ));
-    ang_out_var.add_set(FuzzySet::new(
-        "girar_der",
-        triangular(0.3 * maneuverability, 0.7 * maneuverability, maneuverability),
-    ));
-
-    let path = format!("{}/output_ajuste_angular.png", vehicle_dir);
-    export_variable_memberships(&ang_out_var, &path)?;
-    println!("  ✓ {}", path);
+    println!("\nExportando funciones de pertenencia para {}...", vehicle_name);
 
-    Ok(())
+    let variables = system.input_variables.iter()
+        .map(|variable| ("input", variable))
+        .chain(std::iter::once(("output", &system.output_variable)));
+
+    let mut paths = Vec::new();
+    for (kind, variable) in variables {
+        let path = format!("{}/{}_{}.{}", vehicle_dir, kind, variable.name, format.extension());
+        export_variable_memberships(variable, &path, format)?;
+        println!("  ✓ {}", path);
+        paths.push(path);
+    }
+
+    Ok(paths)
 }
 
 /// Export membership functions for all vehicle types
-pub fn export_all_vehicle_types(output_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub fn export_all_vehicle_types(output_dir: &str, format: ImageFormat) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║   EXPORTANDO FUNCIONES DE PERTENENCIA                ║");
     println!("╚══════════════════════════════════════════════════════╝");
@@ -195,9 +621,239 @@ pub fn export_all_vehicle_types(output_dir: &str) -> Result<(), Box<dyn std::err
     ];
 
     for vehicle_type in vehicle_types {
-        export_all_navigation_variables(vehicle_type, output_dir)?;
+        export_navigation_memberships(vehicle_type, output_dir, format)?;
     }
 
     println!("\n✓ Todas las funciones de pertenencia exportadas a: {}/", output_dir);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FuzzyError;
+
+    fn temp_output_dir(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("memberships_{}_{}", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_export_writes_one_file_per_controller_variable() {
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Heavy));
+        let system = controller.fuzzy_system();
+        let expected = system.input_variables.len() + 1;
+
+        let output_dir = temp_output_dir("png");
+        let paths = export_all_navigation_variables(&controller, VehicleType::Heavy.id(), &output_dir, ImageFormat::Png).unwrap();
+
+        let written = fs::read_dir(format!("{}/{}", output_dir, VehicleType::Heavy.id())).unwrap().count();
+        fs::remove_dir_all(&output_dir).ok();
+
+        assert_eq!(paths.len(), expected);
+        assert_eq!(written, expected);
+        assert!(paths.iter().all(|p| p.ends_with(".png")));
+    }
+
+    #[test]
+    fn test_svg_export_contains_set_names() {
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Heavy));
+        let output_dir = temp_output_dir("svg");
+        let paths = export_all_navigation_variables(&controller, VehicleType::Heavy.id(), &output_dir, ImageFormat::Svg).unwrap();
+
+        let output_path = paths.iter().find(|p| p.ends_with("output_ajuste_angular.svg")).unwrap();
+        let svg = fs::read_to_string(output_path).unwrap();
+        fs::remove_dir_all(&output_dir).ok();
+
+        for set in &controller.fuzzy_system().output_variable.fuzzy_sets {
+            assert!(svg.contains(&set.name), "missing set name {}", set.name);
+        }
+    }
+
+    #[test]
+    fn test_defuzzification_snapshot_matches_evaluate() {
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+        let system = controller.fuzzy_system();
+
+        let mut inputs = HashMap::new();
+        inputs.insert("distancia_al_objetivo".to_string(), 150.0);
+        inputs.insert("error_angular".to_string(), 30f64.to_radians());
+        inputs.insert("velocidad_relativa".to_string(), 0.1);
+
+        let output_dir = temp_output_dir("snapshot");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        for format in [ImageFormat::Png, ImageFormat::Svg] {
+            let path = format!("{}/snapshot.{}", output_dir, format.extension());
+            let value = export_defuzzification_snapshot(system, &inputs, &path, format).unwrap();
+
+            assert_eq!(value, system.evaluate(&inputs).1);
+            assert!(fs::metadata(&path).unwrap().len() > 0);
+        }
+
+        fs::remove_dir_all(&output_dir).ok();
+    }
+
+    #[test]
+    fn test_overview_for_each_vehicle_type() {
+        let output_dir = temp_output_dir("overview");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        for vehicle_type in VehicleType::ALL {
+            let overview_path = format!("{}/overview_{}.png", output_dir, vehicle_type.id());
+            export_navigation_overview(vehicle_type, &overview_path, ImageFormat::Png).unwrap();
+
+            let paths = export_all_navigation_variables(
+                &NavigationController::new(&create_vehicle_preset(vehicle_type)),
+                vehicle_type.id(),
+                &output_dir,
+                ImageFormat::Png,
+            ).unwrap();
+
+            let overview_size = fs::metadata(&overview_path).unwrap().len();
+            for path in &paths {
+                let single_size = fs::metadata(path).unwrap().len();
+                assert!(overview_size > single_size, "{} not larger than {}", overview_path, path);
+            }
+        }
+
+        fs::remove_dir_all(&output_dir).ok();
+    }
+
+    #[test]
+    fn test_navigation_csv_header_and_alineado_peak() {
+        let output_dir = temp_output_dir("csv");
+        let paths = export_navigation_csvs(VehicleType::Heavy, &output_dir).unwrap();
+
+        let error_path = paths.iter().find(|p| p.ends_with("input_error_angular.csv")).unwrap();
+        let csv = fs::read_to_string(error_path).unwrap();
+        fs::remove_dir_all(&output_dir).ok();
+
+        let mut lines = csv.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Heavy));
+        let error_var = controller.fuzzy_system().input_variables.iter()
+            .find(|v| v.name == "error_angular")
+            .unwrap();
+        let expected: Vec<&str> = std::iter::once("x")
+            .chain(error_var.fuzzy_sets.iter().map(|s| s.name.as_str()))
+            .collect();
+        assert_eq!(header, expected);
+
+        let rows: Vec<Vec<f64>> = lines
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), CURVE_SAMPLES);
+
+        let alineado = header.iter().position(|&h| h == "alineado").unwrap();
+        let closest_to_zero = rows.iter()
+            .min_by(|a, b| a[0].abs().partial_cmp(&b[0].abs()).unwrap())
+            .unwrap();
+        assert!(closest_to_zero[0].abs() < 0.05);
+        assert_eq!(closest_to_zero[alineado], 1.0);
+        assert!(rows.iter().all(|row| row[alineado] <= 1.0));
+    }
+
+    /// Three inputs in [0, 1]; the output follows `a` for low `c` and is pushed up for high `c`
+    fn three_input_system() -> FuzzySystem {
+        use crate::fuzzy_system::{triangular, Antecedent, Consequent, FuzzyRule, FuzzySet, RuleOperator};
+
+        let low_high = |name: &str| {
+            let mut var = LinguisticVariable::new(name, (0.0, 1.0));
+            var.add_set(FuzzySet::new("bajo", triangular(-1.0, 0.0, 1.0)));
+            var.add_set(FuzzySet::new("alto", triangular(0.0, 1.0, 2.0)));
+            var
+        };
+
+        let mut system = FuzzySystem::new("Prueba");
+        system.add_input(low_high("a"));
+        system.add_input(low_high("b"));
+        system.add_input(low_high("c"));
+        system.set_output(low_high("salida"));
+
+        let rule = |antecedents: Vec<Antecedent>, set: &str| {
+            FuzzyRule::new(antecedents, vec![Consequent::new(set, "salida")], RuleOperator::And)
+        };
+        system.add_rule(rule(vec![Antecedent::new("bajo", "a"), Antecedent::new("bajo", "c")], "bajo"));
+        system.add_rule(rule(vec![Antecedent::new("alto", "a")], "alto"));
+        system.add_rule(rule(vec![Antecedent::new("alto", "c")], "alto"));
+        system
+    }
+
+    fn file_checksum(path: &str) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        fs::read(path).unwrap().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_control_surface_grid_and_errors() {
+        let system = three_input_system();
+        let surface = compute_control_surface(&system, "a", "b", &HashMap::new(), 10).unwrap();
+
+        assert_eq!(surface.x_values.len(), 10);
+        assert_eq!(surface.values.len(), 10);
+        assert!((surface.x_values[0] - 0.05).abs() < 1e-12);
+        // Unspecified inputs are held at the middle of their range
+        assert_eq!(surface.fixed_inputs, vec![("c".to_string(), 0.5)]);
+
+        assert!(matches!(
+            compute_control_surface(&system, "a", "z", &HashMap::new(), 10),
+            Err(FuzzyError::UnknownInputVariable { variable, .. }) if variable == "z"
+        ));
+        assert_eq!(
+            compute_control_surface(&system, "a", "a", &HashMap::new(), 10).unwrap_err(),
+            FuzzyError::SameSurfaceAxis("a".to_string())
+        );
+        assert_eq!(
+            compute_control_surface(&system, "a", "b", &HashMap::new(), 0).unwrap_err(),
+            FuzzyError::ZeroResolution
+        );
+    }
+
+    #[test]
+    fn test_control_surface_plot_changes_with_fixed_input() {
+        let system = three_input_system();
+        let output_dir = temp_output_dir("surface");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let mut checksums = Vec::new();
+        let mut surfaces = Vec::new();
+        for c in [0.1, 0.9] {
+            let mut fixed_inputs = HashMap::new();
+            fixed_inputs.insert("c".to_string(), c);
+            let path = format!("{}/surface_{}.png", output_dir, c);
+
+            surfaces.push(export_control_surface_plot(&system, "a", "b", &fixed_inputs, 20, &path, ImageFormat::Png).unwrap());
+            checksums.push(file_checksum(&path));
+        }
+
+        fs::remove_dir_all(&output_dir).ok();
+
+        assert_ne!(checksums[0], checksums[1]);
+        assert!(surfaces[1].value_range().0 > surfaces[0].value_range().0);
+    }
+
+    #[test]
+    fn test_export_all_control_surfaces() {
+        let output_dir = temp_output_dir("all_surfaces");
+        let paths = export_all_control_surfaces(&output_dir).unwrap();
+
+        let all_written = paths.iter().all(|p| fs::metadata(p).map(|m| m.len() > 0).unwrap_or(false));
+        fs::remove_dir_all(&output_dir).ok();
+
+        assert_eq!(paths.len(), VehicleType::ALL.len());
+        assert!(all_written);
+    }
+
+    #[test]
+    fn test_image_format_parse() {
+        assert_eq!(ImageFormat::parse("SVG"), Some(ImageFormat::Svg));
+        assert_eq!(ImageFormat::parse("png"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::parse("pdf"), None);
+    }
+}
@@ -0,0 +1,190 @@
+// Memoization of the controller output: inputs rounded to a grid share one
+// fuzzy evaluation, evaluated at the center of their cell
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimulationError;
+use crate::units::{Degrees, Radians};
+
+/// Cells kept before the cache is emptied, a long run visits far fewer
+pub const DEFAULT_CACHE_CAPACITY: usize = 65_536;
+
+/// Grid the controller inputs are rounded to before a cached evaluation
+///
+/// The output of a cached evaluation is the exact one at the center of the
+/// cell, so it differs from the exact output at the input by at most the
+/// variation of the control surface over half a step of every input. With
+/// the default grid that stays under 1e-3 rad/s on the presets, except within
+/// half a step of the ±10° edge of `alineado`: there the surface jumps from
+/// `mantener` to a turn and the cell may take either side. A seeded run
+/// stays within a couple of map units of the exact one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quantization {
+    /// Step of `distancia_al_objetivo`, map units
+    pub distance: f64,
+    /// Step of `error_angular`, degrees
+    pub angle_degrees: f64,
+    /// Step of `velocidad_relativa`
+    pub velocity: f64,
+}
+
+impl Default for Quantization {
+    /// 1 unit, 0.5° and 0.01 of the max velocity
+    fn default() -> Self {
+        Self { distance: 1.0, angle_degrees: 0.5, velocity: 0.01 }
+    }
+}
+
+impl Quantization {
+    /// Reject steps that are not positive and finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        for step in [self.distance, self.angle_degrees, self.velocity] {
+            if !(step.is_finite() && step > 0.0) {
+                return Err(SimulationError::InvalidQuantization(step));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cell of the inputs
+    fn cell(&self, distance: f64, angular_error: Radians, velocity_relative: f64) -> (i64, i64, i64) {
+        (
+            (distance / self.distance).round() as i64,
+            (angular_error.to_degrees().0 / self.angle_degrees).round() as i64,
+            (velocity_relative / self.velocity).round() as i64,
+        )
+    }
+
+    /// Inputs at the center of `cell`
+    fn center(&self, cell: (i64, i64, i64)) -> (f64, Radians, f64) {
+        (
+            cell.0 as f64 * self.distance,
+            Degrees(cell.1 as f64 * self.angle_degrees).to_radians(),
+            cell.2 as f64 * self.velocity,
+        )
+    }
+}
+
+/// Lookups answered by a `ControlCache`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Fraction of the lookups answered without evaluating, 0 before the first one
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Angular adjustment and rule activations of every visited cell
+#[derive(Debug, Clone)]
+pub struct ControlCache {
+    quantization: Quantization,
+    capacity: usize,
+    entries: HashMap<(i64, i64, i64), (Radians, Vec<f64>)>,
+    stats: CacheStats,
+}
+
+impl ControlCache {
+    pub fn new(quantization: Quantization) -> Self {
+        Self::with_capacity(quantization, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Cache emptied whenever it holds `capacity` cells
+    pub fn with_capacity(quantization: Quantization, capacity: usize) -> Self {
+        Self { quantization, capacity: capacity.max(1), entries: HashMap::new(), stats: CacheStats::default() }
+    }
+
+    pub fn quantization(&self) -> Quantization {
+        self.quantization
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Drop the cached cells after the controller changed, the statistics are kept
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Cached output of the cell of the inputs, `evaluate` at its center on a miss
+    pub fn get_or_evaluate(
+        &mut self,
+        distance: f64,
+        angular_error: Radians,
+        velocity_relative: f64,
+        evaluate: impl FnOnce(f64, Radians, f64) -> (Radians, Vec<f64>),
+    ) -> (Radians, Vec<f64>) {
+        let cell = self.quantization.cell(distance, angular_error, velocity_relative);
+        if let Some(output) = self.entries.get(&cell) {
+            self.stats.hits += 1;
+            return output.clone();
+        }
+
+        self.stats.misses += 1;
+        let (distance, angular_error, velocity_relative) = self.quantization.center(cell);
+        let output = evaluate(distance, angular_error, velocity_relative);
+        if self.entries.len() >= self.capacity {
+            self.entries.clear();
+        }
+        self.entries.insert(cell, output.clone());
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inputs_of_one_cell_share_the_center_evaluation() {
+        let mut cache = ControlCache::new(Quantization::default());
+        let mut evaluated = Vec::new();
+        let mut evaluate = |d: f64, e: Radians, v: f64| {
+            evaluated.push((d, e, v));
+            (Radians(d + v) + e, vec![1.0])
+        };
+
+        let first = cache.get_or_evaluate(100.2, Degrees(10.1).to_radians(), 0.101, &mut evaluate);
+        let second = cache.get_or_evaluate(99.8, Degrees(9.9).to_radians(), 0.099, &mut evaluate);
+        assert_eq!(first, second);
+        cache.get_or_evaluate(101.0, Degrees(10.0).to_radians(), 0.1, &mut evaluate);
+
+        assert_eq!(evaluated.len(), 2);
+        let (d, e, v) = evaluated[0];
+        assert_eq!(d, 100.0);
+        assert!((e.to_degrees().0 - 10.0).abs() < 1e-12 && (v - 0.1).abs() < 1e-12);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+        assert!((cache.stats().hit_rate() - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_full_cache_starts_over() {
+        let mut cache = ControlCache::with_capacity(Quantization::default(), 2);
+        for distance in [1.0, 2.0, 3.0, 1.0] {
+            cache.get_or_evaluate(distance, Radians::ZERO, 0.0, |d, _, _| (Radians(d), Vec::new()));
+        }
+        // The third cell emptied the cache, so the first one was evaluated again
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 4 });
+        assert_eq!(CacheStats::default().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_quantization_rejects_non_positive_steps() {
+        assert!(Quantization::default().validate().is_ok());
+        let flat = Quantization { angle_degrees: 0.0, ..Default::default() };
+        assert_eq!(flat.validate(), Err(SimulationError::InvalidQuantization(0.0)));
+        assert!(Quantization { velocity: f64::NAN, ..Default::default() }.validate().is_err());
+    }
+}
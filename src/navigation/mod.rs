@@ -1,25 +1,115 @@
 // Navigation module - Fuzzy logic controller for vehicle navigation
 
+mod cache;
+mod observation;
+mod shared;
+
+pub use cache::{CacheStats, ControlCache, Quantization, DEFAULT_CACHE_CAPACITY};
+pub use observation::{build_observation, NavigationInputs};
+pub use shared::{ControllerKey, SharedControllers, DEFAULT_SHARED_CONTROLLERS};
+
+use crate::error::{FuzzyError, SimulationError};
 use crate::fuzzy_system::{
-    triangular, trapezoidal, Antecedent, Consequent, FuzzyRule, FuzzySet, FuzzySystem,
-    LinguisticVariable, RuleOperator,
+    triangular, trapezoidal, Antecedent, Consequent, EvaluationTrace, FuzzyRule, FuzzySet,
+    FuzzySystem, LinguisticVariable, RuleOperator,
 };
+use crate::map::{Map, EXAM_MAP_SIZE};
+use crate::units::Radians;
 use crate::vehicle::VehicleCharacteristics;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Upper end of the absolute `distancia_al_objetivo` range, farther distances count as this far
+pub const MAX_DISTANCE: f64 = 1000.0;
+
+/// Rules of the controller built by `NavigationController::new`, valid rule indices are below it
+pub const RULE_COUNT: usize = 12;
+
+/// Half width of the `alineado` set of `error_angular` (degrees), its support is ±this
+pub const ALIGNED_HALF_WIDTH_DEG: f64 = 10.0;
+
+/// Placement of the `distancia_al_objetivo` range and sets
+///
+/// The breakpoints (50/100/200/400/500 units on [0, 1000]) were chosen for
+/// the 1000×800 exam map. On a much smaller map every distance is `media` or
+/// closer, on a much larger one everything is `lejos`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceScale {
+    /// The exam map breakpoints in map units, whatever the map
+    #[default]
+    Absolute,
+    /// The exam map layout scaled to a map of this diagonal, every breakpoint
+    /// stays the same fraction of the diagonal
+    Relative { diagonal: f64 },
+}
+
+impl DistanceScale {
+    /// Absolute on the exam-sized map, relative to the diagonal of any other
+    pub fn for_map(map: &Map) -> Self {
+        Self::for_size(map.width, map.height)
+    }
+
+    /// `for_map` of a map of this size
+    pub fn for_size(width: f64, height: f64) -> Self {
+        if (width, height) == EXAM_MAP_SIZE {
+            DistanceScale::Absolute
+        } else {
+            DistanceScale::Relative { diagonal: width.hypot(height) }
+        }
+    }
+
+    /// Factor applied to the exam map breakpoints
+    pub fn factor(&self) -> f64 {
+        match self {
+            DistanceScale::Absolute => 1.0,
+            DistanceScale::Relative { diagonal } => diagonal / EXAM_MAP_SIZE.0.hypot(EXAM_MAP_SIZE.1),
+        }
+    }
+
+    /// Upper end of the range, farther distances count as this far
+    pub fn max_distance(&self) -> f64 {
+        MAX_DISTANCE * self.factor()
+    }
+
+    /// Reject a relative diagonal that is not positive and finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        match *self {
+            DistanceScale::Relative { diagonal } if !(diagonal > 0.0 && diagonal.is_finite()) => {
+                Err(SimulationError::InvalidDistanceScale(diagonal))
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
 /// Navigation controller using fuzzy logic
+///
+/// Clones share the rule base, which is read-only once built, and keep their
+/// own output cache (see `SharedControllers`).
+#[derive(Clone)]
 pub struct NavigationController {
-    fuzzy_system: FuzzySystem,
-    _maneuverability: f64,  // Reserved for future use
-    _max_acceleration: f64,  // Reserved for future use
+    fuzzy_system: Arc<FuzzySystem>,
+    /// Memoized outputs of `Controller::control`, off unless enabled
+    cache: Option<ControlCache>,
+    /// Characteristics the rule base was built for, to rebuild a private copy
+    characteristics: VehicleCharacteristics,
+    distance_scale: DistanceScale,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Rule bases built on this thread, to check that shared controllers are built once
+    pub(crate) static RULE_BASES_BUILT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
 }
 
 impl NavigationController {
     /// Create a simplified navigation controller for a vehicle
     ///
     /// Inputs:
-    /// - distancia_al_objetivo: [0, 1000]
+    /// - distancia_al_objetivo: [0, 1000] (see `with_distance_scale` for other maps)
     /// - error_angular: [-180°, 180°]
     /// - velocidad_relativa: [0, 1]
     ///
@@ -27,44 +117,86 @@ impl NavigationController {
     /// - ajuste_angular: [-maneuverability, +maneuverability]
     /// - ajuste_velocidad: [-max_accel, +max_accel] (not used - constant velocity)
     ///
-    /// Rules: 10 rules covering all distance-angle combinations
+    /// Rules: `RULE_COUNT` (12) rules covering all distance-angle combinations, a target behind always turns left
     pub fn new(characteristics: &VehicleCharacteristics) -> Self {
+        Self::with_distance_scale(characteristics, DistanceScale::Absolute)
+    }
+
+    /// Controller with the `distancia_al_objetivo` sets placed by `distance_scale`,
+    /// `DistanceScale::for_map` of the map it steers on
+    pub fn with_distance_scale(characteristics: &VehicleCharacteristics, distance_scale: DistanceScale) -> Self {
+        Self {
+            fuzzy_system: Arc::new(Self::rule_base(characteristics, distance_scale)),
+            cache: None,
+            characteristics: characteristics.clone(),
+            distance_scale,
+        }
+    }
+
+    /// Placement of the `distancia_al_objetivo` sets
+    pub fn distance_scale(&self) -> DistanceScale {
+        self.distance_scale
+    }
+
+    /// Characteristics the rule base was built for
+    pub fn characteristics(&self) -> &VehicleCharacteristics {
+        &self.characteristics
+    }
+
+    /// Variables, sets and rules of the controller of `characteristics`
+    fn rule_base(characteristics: &VehicleCharacteristics, distance_scale: DistanceScale) -> FuzzySystem {
+        #[cfg(test)]
+        RULE_BASES_BUILT.with(|built| built.set(built.get() + 1));
+
         let mut system = FuzzySystem::new("Navigation Controller");
 
         let maneuverability = characteristics.maneuverability;
-        let max_accel = characteristics.max_acceleration;
 
-        // INPUT 1: distancia_al_objetivo [0, 1000]
-        let mut dist_var = LinguisticVariable::new("distancia_al_objetivo", (0.0, 1000.0));
-        dist_var.add_set(FuzzySet::new("muy_cerca", trapezoidal(0.0, 0.0, 50.0, 100.0)));
-        dist_var.add_set(FuzzySet::new("media", triangular(80.0, 200.0, 400.0)));
-        dist_var.add_set(FuzzySet::new("lejos", trapezoidal(350.0, 500.0, 1000.0, 1000.0)));
+        // INPUT 1: distancia_al_objetivo [0, 1000] on the exam map, scaled with its diagonal otherwise
+        let k = distance_scale.factor();
+        let max_distance = distance_scale.max_distance();
+        let mut dist_var = LinguisticVariable::new("distancia_al_objetivo", (0.0, max_distance));
+        dist_var.add_set(FuzzySet::new("muy_cerca", trapezoidal(0.0, 0.0, 50.0 * k, 100.0 * k)));
+        dist_var.add_set(FuzzySet::new("media", triangular(80.0 * k, 200.0 * k, 400.0 * k)));
+        dist_var.add_set(FuzzySet::new("lejos", trapezoidal(350.0 * k, 500.0 * k, max_distance, max_distance)));
         system.add_input(dist_var);
 
-        // INPUT 2: error_angular [-180°, 180°]
-        // Negative angles = target is to the left, need to turn left
-        // Positive angles = target is to the right, need to turn right
-        let mut error_var = LinguisticVariable::new("error_angular", (-PI, PI));
+        // INPUT 2: error_angular [-180°, 180°], desired − current heading (see `map::turn_direction`)
+        // Positive angles = target is to the left (counterclockwise), need to turn left
+        // Negative angles = target is to the right (clockwise), need to turn right
+        // Circular: -180° and 180° are the same direction and get the same memberships
+        let mut error_var = LinguisticVariable::circular("error_angular", (-PI, PI));
         error_var.add_set(FuzzySet::new(
             "alineado",
-            trapezoidal(-10f64.to_radians(), -5f64.to_radians(), 5f64.to_radians(), 10f64.to_radians()),
+            trapezoidal(
+                -ALIGNED_HALF_WIDTH_DEG.to_radians(),
+                -5f64.to_radians(),
+                5f64.to_radians(),
+                ALIGNED_HALF_WIDTH_DEG.to_radians(),
+            ),
         ));
         error_var.add_set(FuzzySet::new(
-            "desviado_izq",
+            "desviado_der",
             triangular(-90f64.to_radians(), -45f64.to_radians(), -10f64.to_radians()),
         ));
         error_var.add_set(FuzzySet::new(
-            "desviado_der",
+            "desviado_izq",
             triangular(10f64.to_radians(), 45f64.to_radians(), 90f64.to_radians()),
         ));
-        // Very deviated: covers angles beyond ±90°
+        // Very deviated: covers angles beyond ±90°, handing over to `opuesto` near ±180°
+        error_var.add_set(FuzzySet::new(
+            "muy_desviado_der",
+            trapezoidal(-170f64.to_radians(), -150f64.to_radians(), -120f64.to_radians(), -70f64.to_radians()),
+        ));
         error_var.add_set(FuzzySet::new(
             "muy_desviado_izq",
-            trapezoidal(-PI, -150f64.to_radians(), -120f64.to_radians(), -70f64.to_radians()),
+            trapezoidal(70f64.to_radians(), 120f64.to_radians(), 150f64.to_radians(), 170f64.to_radians()),
         ));
+        // Target behind: spans the ±180° seam (150°..210°), so the noise of a vehicle
+        // pointed straight away cannot flip the turn between sides
         error_var.add_set(FuzzySet::new(
-            "muy_desviado_der",
-            trapezoidal(70f64.to_radians(), 120f64.to_radians(), 150f64.to_radians(), PI),
+            "opuesto",
+            trapezoidal(150f64.to_radians(), 170f64.to_radians(), 190f64.to_radians(), 210f64.to_radians()),
         ));
         system.add_input(error_var);
 
@@ -76,13 +208,14 @@ impl NavigationController {
         system.add_input(vel_var);
 
         // OUTPUT 1: ajuste_angular [-maneuverability, +maneuverability]
+        // Positive adjustments turn left (counterclockwise), negative ones turn right
         let mut ang_out_var = LinguisticVariable::new("ajuste_angular", (-maneuverability, maneuverability));
         ang_out_var.add_set(FuzzySet::new(
-            "girar_izq",
+            "girar_der",
             triangular(-maneuverability, -0.7 * maneuverability, -0.3 * maneuverability),
         ));
         ang_out_var.add_set(FuzzySet::new(
-            "leve_izq",
+            "leve_der",
             triangular(-0.4 * maneuverability, -0.2 * maneuverability, 0.0),
         ));
         ang_out_var.add_set(FuzzySet::new(
@@ -90,11 +223,11 @@ impl NavigationController {
             triangular(-0.1 * maneuverability, 0.0, 0.1 * maneuverability),
         ));
         ang_out_var.add_set(FuzzySet::new(
-            "leve_der",
+            "leve_izq",
             triangular(0.0, 0.2 * maneuverability, 0.4 * maneuverability),
         ));
         ang_out_var.add_set(FuzzySet::new(
-            "girar_der",
+            "girar_izq",
             triangular(0.3 * maneuverability, 0.7 * maneuverability, maneuverability),
         ));
         system.set_output(ang_out_var);
@@ -189,6 +322,14 @@ impl NavigationController {
             RuleOperator::And,
         ));
 
+        // R8c: SI opuesto ENTONCES girar fuerte izquierda
+        // Deterministic tie-break for a target behind, always counterclockwise
+        system.add_rule(FuzzyRule::new(
+            vec![Antecedent::new("opuesto", "error_angular")],
+            vec![Consequent::new("girar_izq", "ajuste_angular")],
+            RuleOperator::And,
+        ));
+
         // R9: SI muy_cerca Y desviado_izq ENTONCES leve_izq
         system.add_rule(FuzzyRule::new(
             vec![
@@ -209,11 +350,7 @@ impl NavigationController {
             RuleOperator::And,
         ));
 
-        Self {
-            fuzzy_system: system,
-            _maneuverability: maneuverability,
-            _max_acceleration: max_accel,
-        }
+        system
     }
 
     /// Compute control output for angular adjustment
@@ -222,20 +359,229 @@ impl NavigationController {
     pub fn compute_control(
         &self,
         distance_to_target: f64,
-        angular_error: f64,
+        angular_error: Radians,
         velocity_relative: f64,
-    ) -> (f64, f64) {
-        // Evaluate fuzzy system for angular adjustment
-        let mut inputs = HashMap::new();
-        inputs.insert("distancia_al_objetivo".to_string(), distance_to_target);
-        inputs.insert("error_angular".to_string(), angular_error);
-        inputs.insert("velocidad_relativa".to_string(), velocity_relative);
+    ) -> (Radians, f64) {
+        let (angular_adjustment, velocity_adjustment, _) =
+            self.compute_control_with_activations(distance_to_target, angular_error, velocity_relative);
+        (angular_adjustment, velocity_adjustment)
+    }
 
-        let (_, angular_adjustment) = self.fuzzy_system.evaluate(&inputs);
+    /// Same as `compute_control`, also returning the firing strength of every rule (see `rule_labels`)
+    pub fn compute_control_with_activations(
+        &self,
+        distance_to_target: f64,
+        angular_error: Radians,
+        velocity_relative: f64,
+    ) -> (Radians, f64, Vec<f64>) {
+        // Evaluate fuzzy system for angular adjustment
+        let trace = self.compute_control_traced(distance_to_target, angular_error, velocity_relative);
 
         // Velocity is constant - no adjustment
         let velocity_adjustment = 0.0;
 
-        (angular_adjustment, velocity_adjustment)
+        (Radians(trace.defuzzified_value), velocity_adjustment, trace.rule_activations)
+    }
+
+    /// Evaluate the angular adjustment keeping the rule and output set activations
+    ///
+    /// Distances beyond the end of the range (targets across a large map)
+    /// saturate to it, `lejos` would not fire past it. The trace holds the
+    /// fuzzy values, `defuzzified_value` is the adjustment in rad/s.
+    pub fn compute_control_traced(
+        &self,
+        distance_to_target: f64,
+        angular_error: Radians,
+        velocity_relative: f64,
+    ) -> EvaluationTrace {
+        evaluate_traced(&self.fuzzy_system, distance_to_target.min(self.distance_scale.max_distance()), angular_error, velocity_relative)
+    }
+
+    /// Memoize the output of `Controller::control` on the `quantization` grid
+    ///
+    /// Speeds up long runs at the cost of the quantization error (see
+    /// `Quantization`). The `compute_control*` methods stay exact.
+    pub fn with_cache(mut self, quantization: Quantization) -> Self {
+        self.set_cache(Some(quantization));
+        self
+    }
+
+    /// Turn the cache on with a new grid, or off for accuracy-sensitive runs
+    pub fn set_cache(&mut self, quantization: Option<Quantization>) {
+        self.cache = quantization.map(ControlCache::new);
+    }
+
+    /// Lookups of the cache since it was turned on, `None` when off
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(ControlCache::stats)
+    }
+
+    /// Skip the rules at `indices` (0-based, the order of `rule_labels`), for ablation studies
+    pub fn disable_rules(&mut self, indices: &[usize]) -> Result<(), FuzzyError> {
+        if let Some(cache) = &mut self.cache {
+            cache.clear();
+        }
+        let system = self.rule_base_mut();
+        for &index in indices {
+            system.set_rule_enabled(index, false)?;
+        }
+        Ok(())
+    }
+
+    /// Rule base to change, first rebuilt as a private copy when shared with other controllers
+    fn rule_base_mut(&mut self) -> &mut FuzzySystem {
+        if Arc::get_mut(&mut self.fuzzy_system).is_none() {
+            let mut own = Self::rule_base(&self.characteristics, self.distance_scale);
+            for (rule, shared) in own.rules.iter_mut().zip(&self.fuzzy_system.rules) {
+                rule.enabled = shared.enabled;
+            }
+            self.fuzzy_system = Arc::new(own);
+        }
+        Arc::get_mut(&mut self.fuzzy_system).expect("private rule base")
+    }
+
+    /// Underlying fuzzy system (variables, sets and rules)
+    pub fn fuzzy_system(&self) -> &FuzzySystem {
+        &self.fuzzy_system
+    }
+
+    /// Rendered IF/THEN text of every rule, in evaluation order
+    pub fn rule_labels(&self) -> Vec<String> {
+        self.fuzzy_system.rules.iter().map(|rule| rule.to_string()).collect()
+    }
+}
+
+/// Steering law stepped by `Simulation`, the fuzzy controller or a scripted one in tests
+pub trait Controller {
+    /// Angular adjustment (radians per second), velocity adjustment and the firing
+    /// strength of every rule, empty for controllers without rules
+    fn control(&mut self, distance_to_target: f64, angular_error: Radians, velocity_relative: f64) -> (Radians, f64, Vec<f64>);
+}
+
+impl Controller for NavigationController {
+    /// Exact evaluation, or the cached one of the input cell when the cache is on
+    fn control(&mut self, distance_to_target: f64, angular_error: Radians, velocity_relative: f64) -> (Radians, f64, Vec<f64>) {
+        let Some(cache) = &mut self.cache else {
+            return self.compute_control_with_activations(distance_to_target, angular_error, velocity_relative);
+        };
+
+        let system = &self.fuzzy_system;
+        let max_distance = self.distance_scale.max_distance();
+        let (angular_adjustment, activations) =
+            cache.get_or_evaluate(distance_to_target.min(max_distance), angular_error, velocity_relative, |d, e, v| {
+                let trace = evaluate_traced(system, d, e, v);
+                (Radians(trace.defuzzified_value), trace.rule_activations)
+            });
+        (angular_adjustment, 0.0, activations)
+    }
+}
+
+/// Evaluate `system` with the navigation inputs, the distance already saturated to its range
+///
+/// The fuzzy variables are plain numbers, `error_angular` in radians.
+fn evaluate_traced(system: &FuzzySystem, distance_to_target: f64, angular_error: Radians, velocity_relative: f64) -> EvaluationTrace {
+    let mut inputs = HashMap::new();
+    inputs.insert("distancia_al_objetivo".to_string(), distance_to_target);
+    inputs.insert("error_angular".to_string(), angular_error.0);
+    inputs.insert("velocidad_relativa".to_string(), velocity_relative);
+
+    system.evaluate_traced(&inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::turn_direction;
+    use crate::units::Degrees;
+    use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+    #[test]
+    fn test_adjustment_turns_towards_the_side_of_the_error() {
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+
+        // Beyond ±160° the target counts as behind, see `test_target_behind_always_turns_left`
+        for degrees in [-155.0, -135.0, -90.0, -45.0, -15.0, 15.0, 45.0, 90.0, 135.0, 155.0] {
+            let error = Degrees(degrees).to_radians();
+            for distance in [30.0, 200.0, 800.0] {
+                let (adjustment, _) = controller.compute_control(distance, error, 0.1);
+                assert_eq!(turn_direction(adjustment), turn_direction(error), "{}° at {} units", degrees, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_target_behind_always_turns_left() {
+        let controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+        let maneuverability = create_vehicle_preset(VehicleType::Standard).maneuverability;
+
+        // Both sides of the seam, including the noise of a vehicle pointed straight away
+        for error in [PI, -PI, PI - 1e-12, -PI + 1e-12, f64::to_radians(175.0), f64::to_radians(-175.0)].map(Radians) {
+            let (adjustment, _) = controller.compute_control(500.0, error, 0.1);
+            assert!(adjustment.0 > 0.5 * maneuverability, "{} rad: {}", error, adjustment);
+        }
+        let (at_pi, _) = controller.compute_control(500.0, Radians(PI), 0.1);
+        let (at_minus_pi, _) = controller.compute_control(500.0, Radians(-PI), 0.1);
+        assert_eq!(at_pi, at_minus_pi);
+
+        // Only the tie-break rule fires at ±π
+        for error in [Radians(PI), Radians(-PI)] {
+            let trace = controller.compute_control_traced(500.0, error, 0.1);
+            let fired: Vec<String> = controller.fuzzy_system().rules.iter()
+                .zip(&trace.rule_activations)
+                .filter(|(_, &activation)| activation > 0.0)
+                .map(|(rule, _)| rule.antecedents[0].set.clone())
+                .collect();
+            assert_eq!(fired, vec!["opuesto"]);
+        }
+    }
+
+    #[test]
+    fn test_disabled_rules_contribute_no_activation() {
+        let mut controller = NavigationController::new(&create_vehicle_preset(VehicleType::Standard));
+        assert_eq!(controller.fuzzy_system().rules.len(), RULE_COUNT);
+
+        // Far and aligned: R1 (lejos, alineado) drives the output
+        let before = controller.compute_control_traced(600.0, Radians(0.02), 0.1);
+        assert!(before.rule_activations[0] > 0.0);
+
+        controller.disable_rules(&[0, 3, 6]).unwrap();
+        let after = controller.compute_control_traced(600.0, Radians(0.02), 0.1);
+        assert_eq!(after.rule_activations.len(), RULE_COUNT);
+        for index in [0, 3, 6] {
+            assert_eq!(after.rule_activations[index], 0.0);
+        }
+        assert!(!after.set_activations.contains_key("mantener"));
+        assert!(controller.fuzzy_system().to_string().contains("  1: [disabled] if"));
+
+        assert!(controller.disable_rules(&[RULE_COUNT]).is_err());
+    }
+
+    #[test]
+    fn test_relative_scale_reaches_lejos_on_a_small_map() {
+        let characteristics = create_vehicle_preset(VehicleType::Standard);
+        let map = Map::new(200.0, 200.0, 100.0, 175.0);
+        let far_corner = map.width.hypot(map.height);
+
+        // R1 (lejos, alineado) fires only if the distance is somewhat lejos
+        let absolute = NavigationController::new(&characteristics);
+        assert_eq!(absolute.compute_control_traced(far_corner, Radians::ZERO, 0.1).rule_activations[0], 0.0);
+
+        let relative = NavigationController::with_distance_scale(&characteristics, DistanceScale::for_map(&map));
+        assert!(relative.compute_control_traced(far_corner, Radians::ZERO, 0.1).rule_activations[0] > 0.0);
+        let distance = &relative.fuzzy_system().input_variables[0];
+        assert_eq!(distance.range, (0.0, relative.distance_scale().max_distance()));
+    }
+
+    #[test]
+    fn test_distance_scale_of_the_exam_map_is_absolute() {
+        assert_eq!(DistanceScale::for_size(EXAM_MAP_SIZE.0, EXAM_MAP_SIZE.1), DistanceScale::Absolute);
+
+        let exam_diagonal = EXAM_MAP_SIZE.0.hypot(EXAM_MAP_SIZE.1);
+        assert!((DistanceScale::Relative { diagonal: exam_diagonal }.factor() - 1.0).abs() < 1e-12);
+        assert!((DistanceScale::for_size(500.0, 400.0).max_distance() - 0.5 * MAX_DISTANCE).abs() < 1e-9);
+
+        assert!(DistanceScale::Relative { diagonal: 0.0 }.validate().is_err());
+        assert!(DistanceScale::Relative { diagonal: f64::NAN }.validate().is_err());
+        assert!(DistanceScale::Absolute.validate().is_ok());
     }
 }
@@ -0,0 +1,59 @@
+// Observation: the fuzzy controller inputs of a vehicle state, shared by the
+// simulation, the single-step API, the FFI layer and the Python bindings
+
+use serde::Serialize;
+
+use crate::map::{compute_angular_error, compute_approach_point_on_map, euclidean_distance, normalize_angle, turn_radius, Map, Point};
+use crate::units::Radians;
+use crate::vehicle::{VehicleCharacteristics, VehicleState};
+
+/// Fuzzy controller inputs of a vehicle state, as `Simulation::step` feeds them
+#[derive(Debug, Clone, Serialize)]
+pub struct NavigationInputs {
+    pub distance_to_target: f64,
+    /// Virtual point the controller steers to, the target itself when far away,
+    /// re-aimed away from the map edges the vehicle could not turn in
+    pub approach_point: Point,
+    /// Interpolated angular error towards the approach point
+    pub angular_error: Radians,
+    /// Angular error towards the target itself
+    pub raw_angular_error: Radians,
+    /// Heading towards the approach point
+    pub desired_heading: Radians,
+    /// Velocity as a fraction of the max velocity
+    pub velocity_relative: f64,
+}
+
+impl NavigationInputs {
+    /// How much the approach point bends the bearing the controller steers by, in [0, π]
+    ///
+    /// Zero while the approach point is the target itself, far from it on a map with room to turn.
+    pub fn approach_bias(&self) -> Radians {
+        normalize_angle(self.angular_error - self.raw_angular_error).abs()
+    }
+}
+
+/// Controller inputs of `state` navigating to the target of `map`
+///
+/// The only place they are derived: `Simulation::step`, `POST /api/step`, the
+/// dropout hold, the FFI layer and the Python bindings all observe through it,
+/// so callers outside a simulation see exactly what the controller would. The
+/// whole map is needed because the approach point keeps a turning radius of
+/// `characteristics` away from its edges.
+pub fn build_observation(state: &VehicleState, map: &Map, characteristics: &VehicleCharacteristics) -> NavigationInputs {
+    let distance_to_target = euclidean_distance(&state.position, &map.target.position);
+    let radius = turn_radius(state.velocity, characteristics.maneuverability);
+    let approach_point = compute_approach_point_on_map(map, distance_to_target, radius);
+    let angular_error = compute_angular_error(&state.position, state.angle, &approach_point);
+    let raw_angular_error = compute_angular_error(&state.position, state.angle, &map.target.position);
+    let desired_heading = Radians((approach_point.y - state.position.y).atan2(approach_point.x - state.position.x));
+
+    NavigationInputs {
+        distance_to_target,
+        approach_point,
+        angular_error,
+        raw_angular_error,
+        desired_heading,
+        velocity_relative: state.velocity / characteristics.max_velocity,
+    }
+}
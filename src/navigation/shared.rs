@@ -0,0 +1,184 @@
+// Controllers shared across simulations, threads and requests: the rule base
+// of a vehicle is built once per key and handed out as cheap clones
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::{DistanceScale, NavigationController};
+use crate::error::FuzzyError;
+use crate::vehicle::VehicleCharacteristics;
+
+/// Controllers kept before the cache is emptied, far more than the presets and their ablations
+pub const DEFAULT_SHARED_CONTROLLERS: usize = 256;
+
+/// Characteristics (as bits), disabled rules and distance scale a shared controller was built with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControllerKey {
+    characteristics: [u64; 5],
+    disabled_rules: Vec<usize>,
+    /// Factor of the distance breakpoints, as bits
+    distance_factor: u64,
+}
+
+impl ControllerKey {
+    /// Key of `characteristics` with `disabled_rules` skipped, in any order, on `distance_scale`
+    pub fn new(characteristics: &VehicleCharacteristics, disabled_rules: &[usize], distance_scale: DistanceScale) -> Self {
+        let mut disabled_rules = disabled_rules.to_vec();
+        disabled_rules.sort_unstable();
+        disabled_rules.dedup();
+        Self {
+            characteristics: [
+                characteristics.size.to_bits(),
+                characteristics.maneuverability.to_bits(),
+                characteristics.max_velocity.to_bits(),
+                characteristics.max_acceleration.to_bits(),
+                characteristics.cruise_fraction.to_bits(),
+            ],
+            disabled_rules,
+            distance_factor: distance_scale.factor().to_bits(),
+        }
+    }
+}
+
+/// Controllers built once per `ControllerKey` and shared read-only
+///
+/// `get` hands out the same controller to every caller of a key, clone it
+/// for an owned one that shares the rule base. Emptied whenever it holds
+/// `capacity` controllers, so custom characteristics cannot grow it without
+/// bound.
+pub struct SharedControllers {
+    capacity: usize,
+    controllers: RwLock<HashMap<ControllerKey, Arc<NavigationController>>>,
+}
+
+impl Default for SharedControllers {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHARED_CONTROLLERS)
+    }
+}
+
+impl SharedControllers {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), controllers: RwLock::new(HashMap::new()) }
+    }
+
+    /// Controllers currently kept
+    pub fn len(&self) -> usize {
+        self.controllers.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Controller of `characteristics` with `disabled_rules` skipped on `distance_scale`, built on the first request of its key
+    pub fn get(
+        &self,
+        characteristics: &VehicleCharacteristics,
+        disabled_rules: &[usize],
+        distance_scale: DistanceScale,
+    ) -> Result<Arc<NavigationController>, FuzzyError> {
+        let key = ControllerKey::new(characteristics, disabled_rules, distance_scale);
+        if let Some(controller) = self.controllers.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(Arc::clone(controller));
+        }
+
+        // Built under the write lock, so concurrent first requests of a key build it once
+        let mut controllers = self.controllers.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(controller) = controllers.get(&key) {
+            return Ok(Arc::clone(controller));
+        }
+        let mut controller = NavigationController::with_distance_scale(characteristics, distance_scale);
+        controller.disable_rules(&key.disabled_rules)?;
+        let controller = Arc::new(controller);
+        if controllers.len() >= self.capacity {
+            controllers.clear();
+        }
+        controllers.insert(key, Arc::clone(&controller));
+        Ok(controller)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{Controller, RULE_BASES_BUILT};
+    use crate::units::Radians;
+    use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+    fn built() -> usize {
+        RULE_BASES_BUILT.with(|built| built.get())
+    }
+
+    #[test]
+    fn test_controllers_are_built_once_per_key() {
+        let shared = SharedControllers::default();
+        let agile = create_vehicle_preset(VehicleType::Agile);
+        let before = built();
+
+        let first = shared.get(&agile, &[3, 1], DistanceScale::Absolute).unwrap();
+        let second = shared.get(&agile, &[1, 3, 3], DistanceScale::Absolute).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(built() - before, 1);
+
+        shared.get(&agile, &[], DistanceScale::Absolute).unwrap();
+        shared.get(&create_vehicle_preset(VehicleType::Heavy), &[1, 3], DistanceScale::Absolute).unwrap();
+        assert_eq!((built() - before, shared.len()), (3, 3));
+        let small = shared.get(&agile, &[], DistanceScale::Relative { diagonal: 300.0 }).unwrap();
+        assert_eq!(small.distance_scale(), DistanceScale::Relative { diagonal: 300.0 });
+        assert_eq!((built() - before, shared.len()), (4, 4));
+        assert!(shared.get(&agile, &[40], DistanceScale::Absolute).is_err());
+    }
+
+    #[test]
+    fn test_full_cache_starts_over() {
+        let shared = SharedControllers::new(2);
+        let agile = create_vehicle_preset(VehicleType::Agile);
+        for maneuverability in [1.0, 2.0, 3.0] {
+            shared.get(&VehicleCharacteristics { maneuverability, ..agile.clone() }, &[], DistanceScale::Absolute).unwrap();
+        }
+        assert_eq!(shared.len(), 1);
+    }
+
+    #[test]
+    fn test_clones_share_the_rule_base_and_match_a_fresh_controller() {
+        let shared = SharedControllers::default();
+        let standard = create_vehicle_preset(VehicleType::Standard);
+        let prototype = shared.get(&standard, &[2], DistanceScale::Absolute).unwrap();
+        let mut fresh = NavigationController::new(&standard);
+        fresh.disable_rules(&[2]).unwrap();
+
+        let mut owned = NavigationController::clone(&prototype);
+        let before = built();
+        for (distance, error, velocity) in [(600.0, 0.3, 0.1), (150.0, -1.2, 0.5), (30.0, 2.9, 0.9)] {
+            assert_eq!(owned.control(distance, Radians(error), velocity), fresh.control(distance, Radians(error), velocity));
+        }
+        assert_eq!(built(), before);
+
+        // Disabling more rules on a clone leaves the shared controller untouched
+        owned.disable_rules(&[0]).unwrap();
+        assert_eq!(built() - before, 1);
+        assert!(!owned.fuzzy_system().rules[0].enabled && owned.fuzzy_system().rules[1].enabled);
+        assert!(prototype.fuzzy_system().rules[0].enabled && !prototype.fuzzy_system().rules[2].enabled);
+        assert!(!owned.fuzzy_system().rules[2].enabled);
+    }
+
+    #[test]
+    fn test_controllers_are_shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<NavigationController>();
+        assert_send_sync::<SharedControllers>();
+
+        let shared = Arc::new(SharedControllers::default());
+        let agile = create_vehicle_preset(VehicleType::Agile);
+        let expected = NavigationController::new(&agile).compute_control(200.0, Radians(0.5), 0.2);
+        let outputs: Vec<(Radians, f64)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| shared.get(&agile, &[], DistanceScale::Absolute).unwrap().compute_control(200.0, Radians(0.5), 0.2)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(outputs.iter().all(|&output| output == expected));
+        assert_eq!(shared.len(), 1);
+    }
+}
@@ -2,12 +2,15 @@
 // Extracted from bin/navigation.rs
 
 use examen_parcial::map::Map;
-use examen_parcial::simulation::{Simulation, MultiVehicleSimulationResult, VehicleResult};
+use examen_parcial::membership_export::ImageFormat;
+use examen_parcial::build_info::BuildMeta;
+use examen_parcial::simulation::{Language, Simulation, MultiVehicleSimulationResult, VehicleResult, SCHEMA_VERSION};
+use examen_parcial::trajectory_export::{self, PlotOptions};
 use examen_parcial::vehicle::VehicleType;
 use std::fs;
 use std::io::Write;
 
-pub fn run() {
+pub fn run(plot_format: Option<ImageFormat>) {
     println!("\n╔══════════════════════════════════════════════════════╗");
     println!("║   MULTI-VEHICLE FUZZY NAVIGATION SIMULATION          ║");
     println!("╚══════════════════════════════════════════════════════╝\n");
@@ -33,7 +36,7 @@ pub fn run() {
     for (i, sim) in simulations.iter().enumerate() {
         println!("  {}. {} - Start: ({:.1}, {:.1}) @ {:.1}°",
             i + 1,
-            sim.vehicle.vehicle_type.name(),
+            sim.vehicle.vehicle_type.display_name(Language::Spanish),
             sim.vehicle.state.position.x,
             sim.vehicle.state.position.y,
             sim.vehicle.state.angle.to_degrees()
@@ -76,7 +79,7 @@ pub fn run() {
     let mut vehicle_results = Vec::new();
 
     for (i, sim) in simulations.into_iter().enumerate() {
-        println!("Vehicle {}: {}", i + 1, sim.vehicle.vehicle_type.name());
+        println!("Vehicle {}: {}", i + 1, sim.vehicle.vehicle_type.display_name(Language::Spanish));
 
         // Calculate metrics
         let success = sim.vehicle.has_arrived;
@@ -84,7 +87,7 @@ pub fn run() {
 
         let final_point = sim.trajectory.last().unwrap();
         let final_distance = final_point.distance_to_target;
-        let final_angle_error = (90.0 - final_point.angle).abs();
+        let final_angle_error = (90.0 - final_point.angle.0).abs();
 
         // Calculate distance traveled
         let mut distance_traveled = 0.0;
@@ -102,6 +105,7 @@ pub fn run() {
             distance_traveled,
             final_angle_error,
             final_distance_to_target: final_distance,
+            ..sim.metrics()
         };
 
         println!("  Success: {}", if success { "YES ✓" } else { "NO ✗" });
@@ -114,7 +118,8 @@ pub fn run() {
         println!();
 
         vehicle_results.push(VehicleResult {
-            vehicle_type: sim.vehicle.vehicle_type.name().to_string(),
+            id: sim.id.clone(),
+            vehicle_type: sim.vehicle.vehicle_type.id().to_string(),
             trajectory: sim.trajectory.clone(),
             metrics,
         });
@@ -122,8 +127,11 @@ pub fn run() {
 
     // Create multi-vehicle result
     let multi_result = MultiVehicleSimulationResult {
+        schema_version: SCHEMA_VERSION,
         vehicles: vehicle_results,
         total_simulation_time: time,
+        approach_geometry: None,
+        meta: Some(BuildMeta::current(1)),
     };
 
     // Export to JSON
@@ -138,5 +146,20 @@ pub fn run() {
         .expect("Failed to write to file");
 
     println!("✓ Multi-vehicle trajectory exported to: {}", filename);
+
+    if let Some(format) = plot_format {
+        let plot_filename = format!("output/trajectory_multi.{}", format.extension());
+        let options = PlotOptions {
+            format,
+            time_label_interval: Some(10.0),
+            ..Default::default()
+        };
+        match trajectory_export::plot_trajectories(&multi_result, &map, options, &plot_filename) {
+            Ok(()) => println!("✓ Trajectory plot exported to: {}", plot_filename),
+            Err(e) => eprintln!("Warning: could not export trajectory plot: {}", e),
+        }
+    } else {
+        println!("  (add --plot or --plot=svg to export a trajectory figure)");
+    }
     println!("\nVisualize with: cargo run -- --mode visualizer");
 }
@@ -0,0 +1,145 @@
+// Single-line progress with percentage and ETA for the CLI bins
+
+use std::time::Duration;
+
+/// Weight of the newest speed sample in the moving average
+const SPEED_SMOOTHING: f64 = 0.3;
+
+/// Progress of a long run, fed with the completed fraction (by simulated time
+/// or by iterations) and the wall time elapsed since the start
+///
+/// The ETA divides the remaining fraction by an exponentially weighted moving
+/// average of the recent speed, so it follows slowdowns without jumping on
+/// every sample.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    fraction: f64,
+    elapsed: Duration,
+    /// Smoothed completed fraction per wall second
+    speed: Option<f64>,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `fraction` of the work (clamped to [0, 1]) is done after `elapsed`
+    pub fn update(&mut self, fraction: f64, elapsed: Duration) {
+        let fraction = if fraction.is_nan() { self.fraction } else { fraction.clamp(0.0, 1.0) };
+        let interval = elapsed.saturating_sub(self.elapsed).as_secs_f64();
+
+        if interval > 0.0 {
+            let sample = (fraction - self.fraction).max(0.0) / interval;
+            self.speed = Some(match self.speed {
+                Some(speed) => SPEED_SMOOTHING * sample + (1.0 - SPEED_SMOOTHING) * speed,
+                None => sample,
+            });
+        }
+
+        self.fraction = fraction;
+        self.elapsed = self.elapsed.max(elapsed);
+    }
+
+    /// Jump to 100%, for runs that end early such as every vehicle arriving before max_time
+    pub fn finish(&mut self, elapsed: Duration) {
+        self.fraction = 1.0;
+        self.elapsed = self.elapsed.max(elapsed);
+    }
+
+    /// Completed fraction in [0, 1]
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Remaining wall time at the smoothed speed, `None` while no progress has been measured
+    pub fn eta(&self) -> Option<Duration> {
+        if self.fraction >= 1.0 {
+            return Some(Duration::ZERO);
+        }
+        let speed = self.speed.filter(|&speed| speed > 0.0)?;
+        Duration::try_from_secs_f64((1.0 - self.fraction) / speed).ok()
+    }
+
+    /// `label [ 42.0%] ETA 01:13`, `ETA --:--` before the speed is known
+    pub fn render(&self, label: &str) -> String {
+        let eta = match self.eta() {
+            Some(eta) => format_duration(eta),
+            None => "--:--".to_string(),
+        };
+        format!("{} [{:5.1}%] ETA {}", label, self.fraction * 100.0, eta)
+    }
+}
+
+/// `mm:ss`, or `h:mm:ss` from one hour on
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    #[test]
+    fn test_eta_at_constant_speed() {
+        let mut progress = Progress::new();
+        progress.update(0.25, secs(1.0));
+        progress.update(0.5, secs(2.0));
+
+        assert!((progress.eta().unwrap().as_secs_f64() - 2.0).abs() < 1e-6);
+        assert_eq!(progress.render("Iteration 2/4"), "Iteration 2/4 [ 50.0%] ETA 00:02");
+    }
+
+    #[test]
+    fn test_eta_follows_a_slowdown_smoothly() {
+        let mut progress = Progress::new();
+        progress.update(0.1, secs(1.0));
+        // Ten times slower: the smoothed speed is 0.3 * 0.01 + 0.7 * 0.1 = 0.073 per second
+        progress.update(0.2, secs(11.0));
+
+        let eta = progress.eta().unwrap().as_secs_f64();
+        assert!((eta - 0.8 / 0.073).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zero_progress_has_no_eta() {
+        let mut progress = Progress::new();
+        assert_eq!(progress.eta(), None);
+
+        progress.update(0.0, secs(3.0));
+        assert_eq!(progress.eta(), None);
+        assert_eq!(progress.render("t=0.00s"), "t=0.00s [  0.0%] ETA --:--");
+
+        // No wall time between samples gives no speed either
+        let mut instant = Progress::new();
+        instant.update(0.5, Duration::ZERO);
+        assert_eq!(instant.eta(), None);
+    }
+
+    #[test]
+    fn test_finish_jumps_to_complete() {
+        let mut progress = Progress::new();
+        progress.update(0.3, secs(1.0));
+        progress.finish(secs(1.5));
+
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+        assert_eq!(progress.render("done"), "done [100.0%] ETA 00:00");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(secs(73.4)), "01:13");
+        assert_eq!(format_duration(secs(3725.0)), "1:02:05");
+    }
+}
@@ -0,0 +1,198 @@
+// Python bindings (PyO3), enabled by the `python` feature and packaged with maturin
+
+// The #[pyfunction] expansion of pyo3 0.22 converts PyErr into itself
+#![allow(clippy::useless_conversion)]
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::api::{service, BenchmarkRequest, SimulationRequest};
+use crate::error::FuzzyNavError;
+use crate::fuzzy_system::compute_control_surface;
+use crate::map::{Map, Point};
+use crate::navigation::{build_observation as observe, NavigationController};
+use crate::units::Radians;
+use crate::vehicle::{create_vehicle_preset, VehicleState, VehicleType};
+
+/// Convert a Python dict into a request through its JSON form, `None` gives the defaults
+fn from_dict<T: DeserializeOwned>(py: Python<'_>, dict: Option<&Bound<'_, PyDict>>) -> PyResult<T> {
+    let json: String = match dict {
+        Some(dict) => py.import_bound("json")?.call_method1("dumps", (dict,))?.extract()?,
+        None => "{}".to_string(),
+    };
+    serde_json::from_str(&json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Convert a response into plain Python dicts and lists through its JSON form
+fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import_bound("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+impl From<FuzzyNavError> for PyErr {
+    fn from(error: FuzzyNavError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+fn parse_vehicle_type(name: &str) -> PyResult<VehicleType> {
+    VehicleType::parse(name).ok_or_else(|| {
+        PyValueError::new_err(format!("Unknown vehicle type: {}. Valid types: heavy, standard, agile, ultra_agile", name))
+    })
+}
+
+/// Run a multi-vehicle simulation, same schema as `POST /api/simulate`
+#[pyfunction]
+#[pyo3(signature = (scenario=None))]
+fn simulate(py: Python<'_>, scenario: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let request: SimulationRequest = from_dict(py, scenario)?;
+    let response = py.allow_threads(|| service::simulate(&request))?;
+    to_py(py, &response)
+}
+
+/// Run a parallel benchmark, same schema as `POST /api/benchmark`
+#[pyfunction]
+#[pyo3(signature = (config=None))]
+fn benchmark(py: Python<'_>, config: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let request: BenchmarkRequest = from_dict(py, config)?;
+    let response = py.allow_threads(|| service::benchmark(&request))?;
+    to_py(py, &response)
+}
+
+/// Angular adjustment (rad/s) of the controller of a vehicle type, angular error in radians
+#[pyfunction]
+fn evaluate_controller(vehicle_type: &str, distance: f64, angular_error: f64, velocity: f64) -> PyResult<f64> {
+    let controller = NavigationController::new(&create_vehicle_preset(parse_vehicle_type(vehicle_type)?));
+    let (adjustment, _) = controller.compute_control(distance, Radians(angular_error), velocity);
+    Ok(adjustment.0)
+}
+
+/// Controller inputs of a vehicle state as `Simulation::step` observes them, angles in radians
+///
+/// Returns the fields of `NavigationInputs`: pass `distance_to_target`,
+/// `angular_error` and `velocity_relative` to `evaluate_controller` to steer
+/// like a simulation step. The map and target default to the exam map, a map
+/// with non-positive dimensions or a target outside it raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (vehicle_type, x, y, angle, velocity, target_x=500.0, target_y=700.0, map_width=1000.0, map_height=800.0))]
+#[allow(clippy::too_many_arguments)]
+fn build_observation(
+    py: Python<'_>,
+    vehicle_type: &str,
+    x: f64,
+    y: f64,
+    angle: f64,
+    velocity: f64,
+    target_x: f64,
+    target_y: f64,
+    map_width: f64,
+    map_height: f64,
+) -> PyResult<PyObject> {
+    let characteristics = create_vehicle_preset(parse_vehicle_type(vehicle_type)?);
+    let state = VehicleState { position: Point::new(x, y), angle: Radians(angle), velocity };
+    let map = Map::try_new(map_width, map_height, target_x, target_y).map_err(FuzzyNavError::from)?;
+    let inputs = observe(&state, &map, &characteristics);
+    to_py(py, &inputs)
+}
+
+/// `(x_values, y_values, values)` of a control surface
+type SurfaceGrid = (Vec<f64>, Vec<f64>, Vec<Vec<f64>>);
+
+/// Controller output over two inputs as `(x_values, y_values, values)`
+///
+/// `values[j][i]` is the output at `(x_values[i], y_values[j])`, so
+/// `numpy.asarray(values)` has shape `(len(y_values), len(x_values))`.
+#[pyfunction]
+#[pyo3(signature = (vehicle_type, x_var="error_angular", y_var="distancia_al_objetivo", fixed_inputs=None, resolution=50))]
+fn control_surface(
+    py: Python<'_>,
+    vehicle_type: &str,
+    x_var: &str,
+    y_var: &str,
+    fixed_inputs: Option<HashMap<String, f64>>,
+    resolution: usize,
+) -> PyResult<SurfaceGrid> {
+    let controller = NavigationController::new(&create_vehicle_preset(parse_vehicle_type(vehicle_type)?));
+    let fixed_inputs = fixed_inputs.unwrap_or_default();
+
+    let surface = py
+        .allow_threads(|| compute_control_surface(controller.fuzzy_system(), x_var, y_var, &fixed_inputs, resolution))
+        .map_err(FuzzyNavError::from)?;
+    Ok((surface.x_values, surface.y_values, surface.values))
+}
+
+#[pymodule]
+fn fuzzy_nav(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(benchmark, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_controller, m)?)?;
+    m.add_function(wrap_pyfunction!(build_observation, m)?)?;
+    m.add_function(wrap_pyfunction!(control_surface, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDED_SCENARIO: &str = r#"{"vehicle_types": ["Heavy", "Agile"], "max_time": 5.0, "seed": 42}"#;
+
+    #[test]
+    fn test_python_simulate_matches_rust_for_seeded_scenario() {
+        pyo3::prepare_freethreaded_python();
+
+        let request: SimulationRequest = serde_json::from_str(SEEDED_SCENARIO).unwrap();
+        let rust_json = serde_json::to_string(&service::simulate(&request).unwrap()).unwrap();
+
+        // Compared as Python objects, Python parses the shortest-roundtrip floats exactly
+        Python::with_gil(|py| {
+            let json = py.import_bound("json").unwrap();
+            let scenario = json.call_method1("loads", (SEEDED_SCENARIO,)).unwrap();
+            let python = simulate(py, Some(scenario.downcast::<PyDict>().unwrap())).unwrap();
+            let rust = json.call_method1("loads", (rust_json,)).unwrap();
+
+            assert!(python.bind(py).eq(rust).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_evaluate_controller_turns_towards_error() {
+        let left = evaluate_controller("Standard", 500.0, 0.5, 0.1).unwrap();
+        let right = evaluate_controller("Standard", 500.0, -0.5, 0.1).unwrap();
+        assert!(left > 0.0 && right < 0.0);
+        assert!(evaluate_controller("Submarine", 500.0, 0.0, 0.1).is_err());
+    }
+
+    #[test]
+    fn test_build_observation_matches_rust_and_feeds_the_controller() {
+        pyo3::prepare_freethreaded_python();
+
+        // Inside the approach corridor, the approach point is below the target
+        let characteristics = create_vehicle_preset(VehicleType::Heavy);
+        let state = VehicleState { position: Point::new(440.0, 640.0), angle: Radians::ZERO, velocity: 5.0 };
+        let rust = observe(&state, &Map::new(1000.0, 800.0, 500.0, 700.0), &characteristics);
+        let rust_json = serde_json::to_string(&rust).unwrap();
+        let (expected, _) = NavigationController::new(&characteristics).compute_control(rust.distance_to_target, rust.angular_error, rust.velocity_relative);
+
+        Python::with_gil(|py| {
+            let python = build_observation(py, "Heavy", 440.0, 640.0, 0.0, 5.0, 500.0, 700.0, 1000.0, 800.0).unwrap();
+            let python = python.bind(py);
+            let rust = py.import_bound("json").unwrap().call_method1("loads", (rust_json,)).unwrap();
+            assert!(python.eq(rust).unwrap());
+
+            let field = |name: &str| python.get_item(name).unwrap().extract::<f64>().unwrap();
+            let adjustment = evaluate_controller("Heavy", field("distance_to_target"), field("angular_error"), field("velocity_relative")).unwrap();
+            assert_eq!(adjustment, expected.0);
+
+            assert!(build_observation(py, "Submarine", 440.0, 640.0, 0.0, 5.0, 500.0, 700.0, 1000.0, 800.0).is_err());
+            let outside = build_observation(py, "Heavy", 440.0, 640.0, 0.0, 5.0, 500.0, 900.0, 1000.0, 800.0).unwrap_err();
+            assert!(outside.is_instance_of::<PyValueError>(py));
+            assert!(build_observation(py, "Heavy", 440.0, 640.0, 0.0, 5.0, 500.0, 700.0, -1.0, 800.0).is_err());
+        });
+    }
+}
@@ -0,0 +1,137 @@
+// Wall time and memory footprint of a benchmark, for sizing the deployment
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::stats::{calculate_stats, percentile};
+
+/// Interval between two memory samples of `MemorySampler`
+#[cfg(feature = "memory")]
+const MEMORY_SAMPLE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Timing and memory of a whole benchmark
+///
+/// An iteration runs every heading mode and vehicle of one start state, so
+/// with `threads` workers the iteration times add up to at most
+/// `total_wall_time × threads`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Wall time of the whole benchmark (seconds)
+    pub total_wall_time: f64,
+    pub iterations_per_second: f64,
+    /// Wall time of one iteration (seconds)
+    pub avg_iteration_time: f64,
+    pub p95_iteration_time: f64,
+    /// Worker threads the iterations ran on
+    pub threads: usize,
+    /// Peak resident memory of the process during the benchmark, needs the `memory` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory_bytes: Option<u64>,
+}
+
+impl ResourceUsage {
+    pub fn new(iteration_times: &[f64], total: Duration, threads: usize, peak_memory_bytes: Option<u64>) -> Self {
+        let total_wall_time = total.as_secs_f64();
+        let iterations_per_second = if total_wall_time > 0.0 {
+            iteration_times.len() as f64 / total_wall_time
+        } else {
+            0.0
+        };
+        Self {
+            total_wall_time,
+            iterations_per_second,
+            avg_iteration_time: calculate_stats(iteration_times).0,
+            p95_iteration_time: percentile(iteration_times, 0.95),
+            threads,
+            peak_memory_bytes,
+        }
+    }
+
+    pub fn peak_memory_mb(&self) -> Option<f64> {
+        self.peak_memory_bytes.map(|bytes| bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Samples the resident memory of this process on a background thread until `finish`
+///
+/// Without the `memory` feature nothing is sampled and `finish` returns None.
+pub struct MemorySampler {
+    #[cfg(feature = "memory")]
+    sampling: Option<(std::sync::mpsc::Sender<()>, std::thread::JoinHandle<Option<u64>>)>,
+}
+
+impl MemorySampler {
+    #[cfg(feature = "memory")]
+    pub fn start() -> Self {
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || {
+            let pid = sysinfo::get_current_pid().ok()?;
+            let mut system = sysinfo::System::new();
+            let mut peak = None;
+            loop {
+                system.refresh_process_specifics(pid, sysinfo::ProcessRefreshKind::new().with_memory());
+                if let Some(process) = system.process(pid) {
+                    peak = peak.max(Some(process.memory()));
+                }
+                // Sleeping on the channel ends the sampling as soon as `finish` is called
+                if stopped.recv_timeout(MEMORY_SAMPLE_INTERVAL) != Err(std::sync::mpsc::RecvTimeoutError::Timeout) {
+                    return peak;
+                }
+            }
+        });
+        Self { sampling: Some((stop, handle)) }
+    }
+
+    #[cfg(not(feature = "memory"))]
+    pub fn start() -> Self {
+        Self {}
+    }
+
+    /// Stop sampling and return the highest resident memory seen, in bytes
+    #[cfg(feature = "memory")]
+    pub fn finish(mut self) -> Option<u64> {
+        let (stop, handle) = self.sampling.take()?;
+        stop.send(()).ok();
+        handle.join().ok().flatten()
+    }
+
+    #[cfg(not(feature = "memory"))]
+    pub fn finish(self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_usage_summarizes_iteration_times() {
+        let times = [0.2, 0.4, 0.3, 0.1];
+        let usage = ResourceUsage::new(&times, Duration::from_secs(2), 1, None);
+
+        assert_eq!(usage.iterations_per_second, 2.0);
+        assert!((usage.avg_iteration_time - 0.25).abs() < 1e-12);
+        assert_eq!(usage.p95_iteration_time, 0.4);
+        assert_eq!(usage.peak_memory_mb(), None);
+        assert!(!serde_json::to_string(&usage).unwrap().contains("peak_memory_bytes"));
+    }
+
+    #[cfg(feature = "memory")]
+    #[test]
+    fn test_memory_sampler_sees_the_process() {
+        let sampler = MemorySampler::start();
+        let buffer = vec![1u8; 8 << 20];
+        std::thread::sleep(MEMORY_SAMPLE_INTERVAL * 3);
+        let peak = sampler.finish().unwrap();
+
+        assert!(peak >= buffer.len() as u64, "{} bytes", peak);
+    }
+
+    #[cfg(not(feature = "memory"))]
+    #[test]
+    fn test_memory_sampler_is_inert_without_the_feature() {
+        assert_eq!(MemorySampler::start().finish(), None);
+    }
+}
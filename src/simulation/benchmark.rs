@@ -0,0 +1,553 @@
+// Benchmark configuration: every input of a benchmark, recorded with its
+// results so a run can be repeated exactly from its own output
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::migrations;
+use super::preflight::{self, PreflightReport};
+use super::stability;
+use super::{validate_disabled_rules, ArrivalCriteria, Convergence, Scenario, Simulation, SimulationBuilder};
+use crate::analysis::stats::{is_valid_confidence, InitialConditionBuckets, DEFAULT_CONFIDENCE};
+use crate::error::{FuzzyNavError, SerializationError, SimulationError};
+use crate::map::{HeadingDistribution, Map, StartDistribution};
+use crate::navigation::{DistanceScale, Quantization, SharedControllers};
+use crate::vehicle::{create_vehicle_preset, VehicleCharacteristics, VehicleType};
+
+/// Version of the crate that wrote a configuration
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A benchmarked vehicle, the controller fuzzy sets are derived from its characteristics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VehicleSpec {
+    pub vehicle_type: VehicleType,
+    pub characteristics: VehicleCharacteristics,
+}
+
+impl VehicleSpec {
+    /// Spec with the current preset characteristics of `vehicle_type`
+    pub fn preset(vehicle_type: VehicleType) -> Self {
+        Self { vehicle_type, characteristics: create_vehicle_preset(vehicle_type) }
+    }
+}
+
+/// Longest `duration_seconds` budget, an hour of iterations
+pub const MAX_DURATION_SECONDS: f64 = 3600.0;
+
+/// Every input of a benchmark, the default is 30 iterations of Heavy, Standard
+/// and Agile on the exam map under the strict criteria
+///
+/// Iteration `i` of every heading mode draws the start states of its
+/// vehicles, in order, from `seed + i`, so the results do not depend on the
+/// thread count and the modes of a sweep start from the same positions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkConfig {
+    /// Crate version that ran the benchmark, reruns warn when it differs
+    pub crate_version: String,
+    /// Iterations to run, 0 with a `duration_seconds` budget and the cap with `convergence`
+    pub iterations: usize,
+    /// Wall-clock budget: iterations start until it passes, and at least one runs
+    ///
+    /// Results record the count that completed instead, see `with_completed_iterations`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+    /// Stop before `iterations` once the estimates are precise enough, see `convergence`
+    ///
+    /// Results record the count that completed instead, like a budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convergence: Option<Convergence>,
+    pub vehicles: Vec<VehicleSpec>,
+    pub dt: f64,
+    pub max_time: f64,
+    pub map_width: f64,
+    pub map_height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    /// Start velocity as a fraction of each vehicle max velocity, the cruise fraction of each vehicle when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity_fraction: Option<f64>,
+    /// Start heading distributions, several make a sweep reported per mode
+    #[serde(default = "default_headings")]
+    pub headings: Vec<HeadingDistribution>,
+    /// Distribution of the random start positions, uniform in configurations recorded before it existed
+    #[serde(default)]
+    pub start_distribution: StartDistribution,
+    pub criteria: ArrivalCriteria,
+    /// Controller rules skipped in every run (0-based indices), empty in configurations recorded before it existed
+    #[serde(default)]
+    pub disabled_rules: Vec<usize>,
+    /// Grid of the controller output cache, `None` (exact evaluation) in configurations recorded before it existed
+    #[serde(default)]
+    pub control_cache: Option<Quantization>,
+    /// Placement of the controller distance sets, `DistanceScale::for_map` of the benchmark map when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance_scale: Option<DistanceScale>,
+    /// Also report the runs grouped by initial condition, `None` in configurations recorded before it existed
+    #[serde(default)]
+    pub buckets: Option<InitialConditionBuckets>,
+    /// Also report the arrival funnel of every group over this many time bins, see `stats::arrival_funnel`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrival_funnel_bins: Option<usize>,
+    /// Draw again the random starts that already meet the arrival criteria, off in configurations recorded before it existed
+    #[serde(default)]
+    pub reroll_degenerate: bool,
+    /// Count the arrivals of degenerate starts (at t=0) in the arrival time statistics, off by default
+    #[serde(default)]
+    pub include_degenerate_arrivals: bool,
+    pub seed: u64,
+    /// Worker threads, `None` for the runner default
+    pub threads: Option<usize>,
+    /// Confidence level of the reported intervals
+    pub confidence: f64,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            crate_version: CRATE_VERSION.to_string(),
+            iterations: 30,
+            duration_seconds: None,
+            convergence: None,
+            vehicles: [VehicleType::Heavy, VehicleType::Standard, VehicleType::Agile]
+                .into_iter()
+                .map(VehicleSpec::preset)
+                .collect(),
+            dt: 0.05,
+            max_time: 600.0,
+            map_width: 1000.0,
+            map_height: 800.0,
+            target_x: 500.0,
+            target_y: 700.0,
+            velocity_fraction: None,
+            headings: default_headings(),
+            start_distribution: StartDistribution::Uniform,
+            criteria: ArrivalCriteria::strict(),
+            disabled_rules: Vec::new(),
+            control_cache: None,
+            distance_scale: None,
+            buckets: None,
+            arrival_funnel_bins: None,
+            reroll_degenerate: false,
+            include_degenerate_arrivals: false,
+            seed: 0,
+            threads: None,
+            confidence: DEFAULT_CONFIDENCE,
+        }
+    }
+}
+
+fn default_headings() -> Vec<HeadingDistribution> {
+    vec![HeadingDistribution::TowardTarget]
+}
+
+impl BenchmarkConfig {
+    /// Configuration embedded under `config` in a benchmark output JSON, upgraded
+    /// from the version the output was recorded with
+    pub fn from_results_json(json: &str) -> Result<Self, FuzzyNavError> {
+        let mut results = migrations::upgrade_benchmark(serde_json::from_str(json)?)?;
+        match results.get_mut("config") {
+            Some(config) => Ok(serde_json::from_value(config.take())?),
+            None => Err(SerializationError::MissingField("config").into()),
+        }
+    }
+
+    /// Benchmark of the vehicles, map, timing, start heading and criteria of `scenario`
+    ///
+    /// Every iteration draws its own start states, the fixed ones of the
+    /// scenario vehicles are not used. An unseeded scenario gets seed 0.
+    pub fn from_scenario(scenario: &Scenario, iterations: usize) -> Self {
+        Self {
+            iterations,
+            vehicles: scenario.vehicles.iter().map(|v| VehicleSpec::preset(v.vehicle_type)).collect(),
+            dt: scenario.time_step(),
+            max_time: scenario.max_time,
+            map_width: scenario.map_width,
+            map_height: scenario.map_height,
+            target_x: scenario.target_x,
+            target_y: scenario.target_y,
+            velocity_fraction: scenario.velocity_fraction,
+            headings: vec![scenario.heading],
+            criteria: scenario.criteria,
+            disabled_rules: scenario.disabled_rules.clone(),
+            distance_scale: scenario.distance_scale,
+            seed: scenario.seed.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Warning for a configuration written by another crate version, whose results may differ
+    pub fn version_warning(&self) -> Option<String> {
+        (self.crate_version != CRATE_VERSION).then(|| {
+            format!(
+                "Configuration written by version {}, running {}: results may differ",
+                self.crate_version, CRATE_VERSION
+            )
+        })
+    }
+
+    /// Reject configurations that cannot run
+    pub fn validate(&self) -> Result<(), FuzzyNavError> {
+        if self.vehicles.is_empty() {
+            return Err(SimulationError::NoVehicles.into());
+        }
+        match (self.iterations, self.duration_seconds) {
+            (0, None) => return Err(SimulationError::NoIterations.into()),
+            (_, Some(seconds)) if !(seconds > 0.0 && seconds <= MAX_DURATION_SECONDS) => {
+                return Err(SimulationError::InvalidDuration { seconds, max: MAX_DURATION_SECONDS }.into());
+            }
+            (1.., Some(_)) => return Err(SimulationError::IterationsAndDuration.into()),
+            _ => {}
+        }
+        if let Some(convergence) = &self.convergence {
+            if self.duration_seconds.is_some() {
+                return Err(SimulationError::ConvergenceAndDuration.into());
+            }
+            convergence.validate()?;
+        }
+        if self.dt <= 0.0 {
+            return Err(SimulationError::InvalidTimeStep(self.dt).into());
+        }
+        stability::check_time_step(
+            self.dt,
+            self.vehicles.iter().map(|spec| (spec.vehicle_type.id(), &spec.characteristics)),
+            self.criteria.arrival_radius(),
+        )?;
+        self.criteria.gate.validate()?;
+        if !is_valid_confidence(self.confidence) {
+            return Err(SimulationError::InvalidConfidence(self.confidence).into());
+        }
+        if self.headings.is_empty() {
+            return Err(SimulationError::NoHeadings.into());
+        }
+        for heading in &self.headings {
+            if let HeadingDistribution::Fixed(degrees) = heading {
+                if !degrees.is_finite() {
+                    return Err(SimulationError::InvalidHeading(degrees.0).into());
+                }
+            }
+        }
+        if let Some(fraction) = self.velocity_fraction {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(SimulationError::InvalidVelocityFraction(fraction).into());
+            }
+        }
+        self.start_distribution.validate()?;
+        validate_disabled_rules(&self.disabled_rules)?;
+        if let Some(quantization) = &self.control_cache {
+            quantization.validate()?;
+        }
+        if let Some(distance_scale) = &self.distance_scale {
+            distance_scale.validate()?;
+        }
+        if let Some(buckets) = &self.buckets {
+            buckets.validate()?;
+        }
+        if self.arrival_funnel_bins == Some(0) {
+            return Err(SimulationError::NoFunnelBins.into());
+        }
+        self.map().map(|_| ())
+    }
+
+    /// Fixed-count configuration of the `iterations` a run completed, what budgeted and early stopped runs record
+    ///
+    /// Iteration `i` is seeded by its index, so a rerun repeats exactly the
+    /// iterations a budget happened to complete.
+    pub fn with_completed_iterations(&self, iterations: usize) -> Self {
+        Self { iterations, duration_seconds: None, convergence: None, ..self.clone() }
+    }
+
+    pub fn map(&self) -> Result<Map, FuzzyNavError> {
+        Ok(Map::try_new(self.map_width, self.map_height, self.target_x, self.target_y)?)
+    }
+
+    pub fn vehicle_types(&self) -> Vec<VehicleType> {
+        self.vehicles.iter().map(|spec| spec.vehicle_type).collect()
+    }
+
+    /// Benchmarked (heading, vehicle) pairs, heading-major: the order of the aggregate rows
+    pub fn groups(&self) -> Vec<(HeadingDistribution, &VehicleSpec)> {
+        self.headings
+            .iter()
+            .flat_map(|&heading| self.vehicles.iter().map(move |vehicle| (heading, vehicle)))
+            .collect()
+    }
+
+    /// Seed of the start states of iteration `iteration` (0-based), `seed + iteration`
+    pub fn iteration_seed(&self, iteration: usize) -> u64 {
+        self.seed.wrapping_add(iteration as u64)
+    }
+
+    /// Generator of the start states of iteration `iteration` (0-based)
+    pub fn iteration_rng(&self, iteration: usize) -> StdRng {
+        StdRng::seed_from_u64(self.iteration_seed(iteration))
+    }
+
+    /// Simulation of `vehicle` on `map` in iteration `iteration`, drawing its random start from `rng`
+    pub fn build_simulation<R: Rng + ?Sized>(
+        &self,
+        map: &Map,
+        vehicle: &VehicleSpec,
+        heading: HeadingDistribution,
+        iteration: usize,
+        rng: &mut R,
+    ) -> Simulation {
+        self.start(self.builder(map, vehicle), heading, iteration, rng)
+    }
+
+    /// `build_simulation`, taking the controller from `controllers` instead of building its rule base
+    pub fn build_shared_simulation<R: Rng + ?Sized>(
+        &self,
+        map: &Map,
+        vehicle: &VehicleSpec,
+        heading: HeadingDistribution,
+        iteration: usize,
+        rng: &mut R,
+        controllers: &Arc<SharedControllers>,
+    ) -> Simulation {
+        let builder = self.builder(map, vehicle).shared_controllers(Arc::clone(controllers));
+        self.start(builder, heading, iteration, rng)
+    }
+
+    /// `builder` with the start of iteration `iteration`, drawn from `rng`
+    fn start<R: Rng + ?Sized>(
+        &self,
+        builder: SimulationBuilder,
+        heading: HeadingDistribution,
+        iteration: usize,
+        rng: &mut R,
+    ) -> Simulation {
+        builder
+            .heading(heading)
+            .start_distribution(self.start_distribution)
+            .iteration(iteration)
+            .build_with_rng(rng)
+    }
+
+    /// Builder of `vehicle` with everything but the start state
+    fn builder(&self, map: &Map, vehicle: &VehicleSpec) -> SimulationBuilder {
+        let builder = SimulationBuilder::new(map.clone(), vehicle.vehicle_type)
+            .characteristics(vehicle.characteristics.clone())
+            .dt(self.dt)
+            .max_time(self.max_time)
+            .criteria(self.criteria)
+            .disabled_rules(self.disabled_rules.clone())
+            .control_cache(self.control_cache)
+            .reroll_degenerate(self.reroll_degenerate);
+        let builder = match self.distance_scale {
+            Some(distance_scale) => builder.distance_scale(distance_scale),
+            None => builder,
+        };
+        match self.velocity_fraction {
+            Some(fraction) => builder.velocity_fraction(fraction),
+            None => builder,
+        }
+    }
+
+    /// Check the controller of every benchmarked vehicle before the
+    /// iterations, see `Scenario::preflight`
+    pub fn preflight(&self) -> Result<PreflightReport, FuzzyNavError> {
+        self.validate()?;
+        let map = self.map()?;
+        let templates = self.vehicles.iter().map(|vehicle| self.builder(&map, vehicle)).collect();
+        Ok(preflight::run_probes(templates, |simulation| simulation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::ArrivalGate;
+    use crate::units::Degrees;
+
+    #[test]
+    fn test_config_roundtrips_through_results_json() {
+        let config = BenchmarkConfig { iterations: 4, seed: 17, threads: Some(2), ..Default::default() };
+        let results = serde_json::json!({ "config": config, "aggregate": [] }).to_string();
+
+        assert_eq!(BenchmarkConfig::from_results_json(&results).unwrap(), config);
+        assert!(matches!(
+            BenchmarkConfig::from_results_json(r#"{"aggregate": []}"#),
+            Err(FuzzyNavError::Serialization(SerializationError::MissingField("config")))
+        ));
+    }
+
+    #[test]
+    fn test_heading_sweep_roundtrips_and_old_configs_face_the_target() {
+        let sweep = BenchmarkConfig {
+            headings: vec![HeadingDistribution::Uniform, HeadingDistribution::Fixed(Degrees(45.0))],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&sweep).unwrap();
+        assert_eq!(json["headings"], serde_json::json!(["uniform", { "fixed": 45.0 }]));
+        assert_eq!(serde_json::from_value::<BenchmarkConfig>(json.clone()).unwrap(), sweep);
+        assert_eq!(sweep.groups().len(), 2 * sweep.vehicles.len());
+
+        let mut old = json;
+        old.as_object_mut().unwrap().remove("headings");
+        let old: BenchmarkConfig = serde_json::from_value(old).unwrap();
+        assert_eq!(old.headings, vec![HeadingDistribution::TowardTarget]);
+    }
+
+    #[test]
+    fn test_scenario_sets_everything_but_the_run_options() {
+        let scenario = Scenario {
+            vehicles: vec![crate::simulation::ScenarioVehicle::random(VehicleType::UltraAgile)],
+            criteria: ArrivalCriteria::loose(),
+            heading: HeadingDistribution::AwayFromTarget,
+            target_x: 950.0,
+            seed: Some(21),
+            ..Scenario::default_exam()
+        };
+        let config = BenchmarkConfig::from_scenario(&scenario, 5);
+
+        assert_eq!(config.vehicle_types(), vec![VehicleType::UltraAgile]);
+        assert_eq!(config.headings, vec![HeadingDistribution::AwayFromTarget]);
+        assert_eq!((config.criteria, config.target_x, config.seed), (ArrivalCriteria::loose(), 950.0, 21));
+        assert_eq!((config.iterations, config.confidence), (5, DEFAULT_CONFIDENCE));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_version_warning_only_on_mismatch() {
+        assert_eq!(BenchmarkConfig::default().version_warning(), None);
+        let old = BenchmarkConfig { crate_version: "0.0.1".to_string(), ..Default::default() };
+        assert!(old.version_warning().unwrap().contains("0.0.1"));
+    }
+
+    #[test]
+    fn test_recorded_characteristics_override_the_preset() {
+        let config = BenchmarkConfig::default();
+        let mut slow = VehicleSpec::preset(VehicleType::Agile);
+        slow.characteristics.max_velocity = 10.0;
+
+        let sim = config.build_simulation(&config.map().unwrap(), &slow, HeadingDistribution::TowardTarget, 0, &mut config.iteration_rng(0));
+        assert_eq!(sim.vehicle.characteristics, slow.characteristics);
+        assert!((sim.vehicle.state.velocity - 10.0 * slow.characteristics.cruise_fraction).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_validate_rejects_unrunnable_configs() {
+        assert!(BenchmarkConfig::default().validate().is_ok());
+        assert!(BenchmarkConfig { vehicles: Vec::new(), ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { iterations: 0, ..Default::default() }.validate().is_err());
+        let budgeted = BenchmarkConfig { iterations: 0, duration_seconds: Some(2.0), ..Default::default() };
+        assert!(budgeted.validate().is_ok());
+        assert!(budgeted.with_completed_iterations(7).validate().is_ok());
+        assert!(BenchmarkConfig { duration_seconds: Some(2.0), ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { duration_seconds: Some(0.0), ..budgeted.clone() }.validate().is_err());
+        assert!(BenchmarkConfig { duration_seconds: Some(f64::INFINITY), ..budgeted }.validate().is_err());
+        assert!(BenchmarkConfig { confidence: 1.5, ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { target_x: 5000.0, ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { headings: Vec::new(), ..Default::default() }.validate().is_err());
+        let nan_heading = vec![HeadingDistribution::Fixed(Degrees(f64::NAN))];
+        assert!(BenchmarkConfig { headings: nan_heading, ..Default::default() }.validate().is_err());
+        let no_bins = StartDistribution::Stratified { bins: 0 };
+        assert!(BenchmarkConfig { start_distribution: no_bins, ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { disabled_rules: vec![0, 12], ..Default::default() }.validate().is_err());
+        let flat = Some(Quantization { distance: -1.0, ..Default::default() });
+        assert!(BenchmarkConfig { control_cache: flat, ..Default::default() }.validate().is_err());
+        let unordered = Some(InitialConditionBuckets { distance_edges: vec![700.0, 650.0], ..Default::default() });
+        assert!(BenchmarkConfig { buckets: unordered, ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { arrival_funnel_bins: Some(0), ..Default::default() }.validate().is_err());
+        assert!(BenchmarkConfig { arrival_funnel_bins: Some(20), ..Default::default() }.validate().is_ok());
+        let flat_gate = ArrivalCriteria::strict().with_gate(ArrivalGate::Ellipse { along: 60.0, across: 0.0 });
+        assert!(BenchmarkConfig { criteria: flat_gate, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_stratified_iterations_cover_the_width() {
+        let config = BenchmarkConfig {
+            iterations: 5,
+            start_distribution: StartDistribution::Stratified { bins: 5 },
+            ..Default::default()
+        };
+        let map = config.map().unwrap();
+        let vehicle = VehicleSpec::preset(VehicleType::Standard);
+
+        let bins: Vec<usize> = (0..config.iterations)
+            .map(|i| {
+                let sim = config.build_simulation(&map, &vehicle, HeadingDistribution::TowardTarget, i, &mut config.iteration_rng(i));
+                (sim.vehicle.state.position.x / (map.width / 5.0)) as usize
+            })
+            .collect();
+        assert_eq!(bins, vec![0, 1, 2, 3, 4]);
+
+        // Configurations recorded before the option start uniformly
+        let mut old = serde_json::to_value(&config).unwrap();
+        old.as_object_mut().unwrap().remove("start_distribution");
+        let old: BenchmarkConfig = serde_json::from_value(old).unwrap();
+        assert_eq!(old.start_distribution, StartDistribution::Uniform);
+    }
+
+    /// Final angle errors (degrees) of seeded Standard runs without `disabled_rules`
+    fn final_angle_errors(disabled_rules: Vec<usize>) -> Vec<f64> {
+        let config = BenchmarkConfig { seed: 11, max_time: 150.0, velocity_fraction: Some(0.1), disabled_rules, ..Default::default() };
+        let map = config.map().unwrap();
+        let vehicle = VehicleSpec::preset(VehicleType::Standard);
+        // Iterations whose start needs a sharp turn on the final approach
+        [5, 7]
+            .into_iter()
+            .map(|i| {
+                let mut sim = config.build_simulation(&map, &vehicle, HeadingDistribution::TowardTarget, i, &mut config.iteration_rng(i));
+                sim.run();
+                sim.metrics().final_angle_error
+            })
+            .collect()
+    }
+
+    /// Arrival times of seeded Heavy or Agile runs from any heading judged by the exam tolerances inside `gate`
+    fn arrival_times(vehicle_type: VehicleType, gate: ArrivalGate) -> Vec<Option<f64>> {
+        let config = BenchmarkConfig { seed: 3, max_time: 150.0, criteria: ArrivalCriteria::strict().with_gate(gate), ..Default::default() };
+        let map = config.map().unwrap();
+        let vehicle = VehicleSpec::preset(vehicle_type);
+        // Iterations where Heavy ends its approach short of the 25-unit circle and loops again
+        [28, 35]
+            .into_iter()
+            .map(|i| {
+                let mut sim = config.build_simulation(&map, &vehicle, HeadingDistribution::Uniform, i, &mut config.iteration_rng(i));
+                sim.run();
+                sim.metrics().arrival_time
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_elongated_gate_saves_heavy_the_extra_loop() {
+        let berth = ArrivalGate::Ellipse { along: 60.0, across: 25.0 };
+        assert_eq!(arrival_times(VehicleType::Heavy, ArrivalGate::Circle), [None, None]);
+        let heavy = arrival_times(VehicleType::Heavy, berth);
+        assert!(heavy.iter().all(Option::is_some), "{:?}", heavy);
+
+        // Agile arrives from the same starts either way, only sooner inside the longer gate
+        let circle = arrival_times(VehicleType::Agile, ArrivalGate::Circle);
+        let ellipse = arrival_times(VehicleType::Agile, berth);
+        assert!(circle.iter().all(Option::is_some), "{:?}", circle);
+        for (circle, ellipse) in circle.iter().zip(&ellipse) {
+            assert!(ellipse.unwrap() <= circle.unwrap(), "{:?} vs {:?}", ellipse, circle);
+        }
+    }
+
+    #[test]
+    fn test_disabled_rules_ablation_on_seeded_runs() {
+        let full = final_angle_errors(Vec::new());
+        assert!(full.iter().all(|&error| error <= 2.0), "{:?}", full);
+
+        // Without the muy_desviado and opuesto emergency turns (R8a-c) the approach overshoots
+        let no_emergency = final_angle_errors(vec![7, 8, 9]);
+        for (ablated, baseline) in no_emergency.iter().zip(&full) {
+            assert!(*ablated > baseline + 5.0, "{:?} vs {:?}", no_emergency, full);
+        }
+
+        // The alignment rules (R1, R4, R7) only imply `mantener`, centered on 0 like
+        // the output of an evaluation where no rule fires, so the runs do not change
+        assert_eq!(final_angle_errors(vec![0, 3, 6]), full);
+
+        // The skipped rules are recorded with the configuration
+        let config = BenchmarkConfig { disabled_rules: vec![7, 8, 9], ..Default::default() };
+        let results = serde_json::json!({ "config": config }).to_string();
+        assert_eq!(BenchmarkConfig::from_results_json(&results).unwrap().disabled_rules, vec![7, 8, 9]);
+    }
+}
@@ -0,0 +1,58 @@
+// Cooperative cancellation: a flag shared between a running simulation and
+// whoever may want it stopped (an API timeout, a client that went away)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared stop flag, checked by the simulations before every step
+///
+/// Clones share the flag. A tripped simulation stops with
+/// `TerminationReason::Cancelled` and keeps the trajectory recorded so far.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every simulation holding this token to stop at its next step
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Guard that cancels the token when dropped, e.g. with the request future of a client that disconnected
+    pub fn drop_guard(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// Cancels its token when dropped, see `CancelToken::drop_guard`
+#[derive(Debug)]
+pub struct CancelOnDrop(CancelToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_flag_and_guards_cancel_on_drop() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        drop(token.drop_guard());
+
+        assert!(token.is_cancelled() && clone.is_cancelled());
+    }
+}
@@ -0,0 +1,200 @@
+// Benchmark early stopping: iterations run in batches, and after each one the
+// precision of the success rate and mean arrival time of every group is
+// checked against the requested thresholds
+//
+// Iteration `i` is seeded by its index, so a run stopped after n iterations
+// is the first n iterations of any longer run of the same configuration.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::stats::{standard_error, wilson_interval};
+use crate::error::SimulationError;
+use crate::map::HeadingDistribution;
+
+/// When a benchmark is precise enough to stop, `iterations` stays the cap
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Convergence {
+    /// Iterations between two checks
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Batches run before the first check can stop the benchmark
+    #[serde(default = "default_min_batches")]
+    pub min_batches: usize,
+    /// Largest half-width of the success rate interval (percentage points)
+    #[serde(default = "default_success_rate_half_width")]
+    pub success_rate_half_width: f64,
+    /// Largest standard error of the mean arrival time (seconds)
+    #[serde(default = "default_arrival_time_std_error")]
+    pub arrival_time_std_error: f64,
+}
+
+fn default_batch_size() -> usize { 50 }
+fn default_min_batches() -> usize { 2 }
+fn default_success_rate_half_width() -> f64 { 3.0 }
+fn default_arrival_time_std_error() -> f64 { 1.0 }
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Self {
+            batch_size: default_batch_size(),
+            min_batches: default_min_batches(),
+            success_rate_half_width: default_success_rate_half_width(),
+            arrival_time_std_error: default_arrival_time_std_error(),
+        }
+    }
+}
+
+impl Convergence {
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let positive = |value: f64| value > 0.0 && value.is_finite();
+        if self.batch_size == 0
+            || self.min_batches == 0
+            || !positive(self.success_rate_half_width)
+            || !positive(self.arrival_time_std_error)
+        {
+            return Err(SimulationError::InvalidConvergence(*self));
+        }
+        Ok(())
+    }
+
+    /// True when the precision is checked after `iterations`: at the end of every batch and at the cap
+    pub fn check_due(&self, iterations: usize, cap: usize) -> bool {
+        iterations.is_multiple_of(self.batch_size) || iterations >= cap
+    }
+
+    /// Report of a check after `iterations`, None while the benchmark should go on
+    ///
+    /// It stops once `min_batches` have run and every group meets both
+    /// thresholds, or at the `cap` whatever the precision.
+    pub fn check(&self, iterations: usize, cap: usize, groups: Vec<GroupPrecision>) -> Option<ConvergenceReport> {
+        let batches = iterations.div_ceil(self.batch_size);
+        let converged = batches >= self.min_batches && groups.iter().all(|group| group.meets(self));
+        let stop_reason = match (converged, iterations >= cap) {
+            (true, _) => StopReason::Converged,
+            (false, true) => StopReason::IterationCap,
+            (false, false) => return None,
+        };
+        Some(ConvergenceReport { converged, stop_reason, batches, iterations, groups })
+    }
+}
+
+/// Why an early stopping benchmark stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Every group met both thresholds
+    Converged,
+    /// The iteration cap came first
+    IterationCap,
+}
+
+/// Precision of the estimates of one (heading, vehicle type) group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupPrecision {
+    pub vehicle_type: String,
+    pub heading: HeadingDistribution,
+    pub runs: usize,
+    /// Arrival times counted in the mean
+    pub arrivals: usize,
+    /// Half-width of the success rate interval at the benchmark confidence (percentage points)
+    pub success_rate_half_width: f64,
+    /// Standard error of the mean arrival time (seconds), None below two arrivals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_time_std_error: Option<f64>,
+}
+
+impl GroupPrecision {
+    /// Precision of the runs of a group, `arrival_times` holding the ones the aggregates count
+    pub fn new(
+        vehicle_type: &str,
+        heading: HeadingDistribution,
+        successes: usize,
+        runs: usize,
+        arrival_times: &[f64],
+        confidence: f64,
+    ) -> Self {
+        let (low, high) = wilson_interval(successes, runs, confidence);
+        Self {
+            vehicle_type: vehicle_type.to_string(),
+            heading,
+            runs,
+            arrivals: arrival_times.len(),
+            success_rate_half_width: (high - low) / 2.0 * 100.0,
+            arrival_time_std_error: standard_error(arrival_times),
+        }
+    }
+
+    /// A group without arrivals has no mean arrival time to pin down, one with a single arrival has not pinned it yet
+    fn meets(&self, convergence: &Convergence) -> bool {
+        let arrival_time = match self.arrival_time_std_error {
+            Some(std_error) => std_error <= convergence.arrival_time_std_error,
+            None => self.arrivals == 0,
+        };
+        self.success_rate_half_width <= convergence.success_rate_half_width && arrival_time
+    }
+}
+
+/// Outcome of an early stopping benchmark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvergenceReport {
+    pub converged: bool,
+    pub stop_reason: StopReason,
+    pub batches: usize,
+    pub iterations: usize,
+    /// Precision of every group when it stopped, in the order of `BenchmarkConfig::groups`
+    pub groups: Vec<GroupPrecision>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convergence() -> Convergence {
+        Convergence { batch_size: 20, min_batches: 2, success_rate_half_width: 10.0, arrival_time_std_error: 1.0 }
+    }
+
+    /// A controller that always arrives, always at the same time
+    fn always_arrives(runs: usize) -> GroupPrecision {
+        GroupPrecision::new("standard", HeadingDistribution::TowardTarget, runs, runs, &vec![42.0; runs], 0.95)
+    }
+
+    #[test]
+    fn test_always_arriving_controller_stops_at_the_minimum_batch_count() {
+        let convergence = convergence();
+        assert!(convergence.check(20, 200, vec![always_arrives(20)]).is_none());
+
+        let report = convergence.check(40, 200, vec![always_arrives(40)]).unwrap();
+        assert!(report.converged);
+        assert_eq!(report.stop_reason, StopReason::Converged);
+        assert_eq!((report.batches, report.iterations), (2, 40));
+        assert_eq!(report.groups[0].arrival_time_std_error, Some(0.0));
+        assert!(report.groups[0].success_rate_half_width < 10.0);
+    }
+
+    #[test]
+    fn test_imprecise_groups_run_to_the_cap() {
+        let convergence = convergence();
+        // Half of the runs arrive, the interval stays wider than 10 points at 60 runs
+        let coin = |runs: usize| {
+            let times: Vec<f64> = (0..runs / 2).map(|i| 30.0 + (i % 7) as f64 * 10.0).collect();
+            GroupPrecision::new("heavy", HeadingDistribution::Uniform, runs / 2, runs, &times, 0.95)
+        };
+        assert!(convergence.check(40, 60, vec![always_arrives(40), coin(40)]).is_none());
+
+        let report = convergence.check(60, 60, vec![always_arrives(60), coin(60)]).unwrap();
+        assert!(!report.converged);
+        assert_eq!(report.stop_reason, StopReason::IterationCap);
+        assert!(report.groups[1].success_rate_half_width > 10.0);
+        assert!(convergence.check_due(60, 60) && convergence.check_due(55, 55) && !convergence.check_due(55, 60));
+    }
+
+    #[test]
+    fn test_a_single_arrival_does_not_pin_the_mean_down() {
+        let never = GroupPrecision::new("agile", HeadingDistribution::Uniform, 0, 1000, &[], 0.95);
+        let once = GroupPrecision::new("agile", HeadingDistribution::Uniform, 1, 1000, &[55.0], 0.95);
+        assert!(never.meets(&convergence()));
+        assert!(!once.meets(&convergence()));
+        assert!(Convergence { batch_size: 0, ..convergence() }.validate().is_err());
+        assert!(Convergence { arrival_time_std_error: f64::NAN, ..convergence() }.validate().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+// Arrival criteria: when a vehicle counts as arrived at the target
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{ArrivalGate, Point, Target};
+use crate::units::{Degrees, Radians};
+
+/// Tolerances of the arrival check, all of them must hold at once
+///
+/// `strict` is the exam requirement and the default, the looser presets are
+/// meant for experiments outside the exam constraints.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ArrivalCriteria {
+    /// Distance to the target below which the vehicle may arrive, with the circular gate
+    pub distance_threshold: f64,
+    /// Region where the vehicle may arrive, the `distance_threshold` disc in criteria recorded before it existed
+    #[serde(default, skip_serializing_if = "ArrivalGate::is_circle")]
+    pub gate: ArrivalGate,
+    /// Largest heading error from the required angle
+    pub angle_tolerance: Degrees,
+    /// Velocity must also be below this value when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_velocity_below: Option<f64>,
+    /// Consecutive steps the tolerances must hold before the vehicle arrives,
+    /// 1 arrives on the first step that meets them and 0 counts as 1
+    #[serde(default = "default_hold_steps", skip_serializing_if = "is_single_step")]
+    pub arrival_hold_steps: usize,
+}
+
+fn default_hold_steps() -> usize {
+    1
+}
+
+fn is_single_step(steps: &usize) -> bool {
+    *steps == 1
+}
+
+impl Default for ArrivalCriteria {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+impl ArrivalCriteria {
+    /// Exam requirement, ±2° within 25 units
+    pub fn strict() -> Self {
+        Self::circle(25.0, 2.0)
+    }
+
+    /// ±5° within 35 units
+    pub fn normal() -> Self {
+        Self::circle(35.0, 5.0)
+    }
+
+    /// ±15° within 50 units
+    pub fn loose() -> Self {
+        Self::circle(50.0, 15.0)
+    }
+
+    fn circle(distance_threshold: f64, angle_tolerance: f64) -> Self {
+        Self {
+            distance_threshold,
+            gate: ArrivalGate::Circle,
+            angle_tolerance: Degrees(angle_tolerance),
+            require_velocity_below: None,
+            arrival_hold_steps: 1,
+        }
+    }
+
+    /// Every preset with its name, strictest first
+    pub fn presets() -> [(&'static str, Self); 3] {
+        [("strict", Self::strict()), ("normal", Self::normal()), ("loose", Self::loose())]
+    }
+
+    /// Preset by name (`strict`, `normal`, `loose`), case insensitive
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::presets()
+            .into_iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, criteria)| criteria)
+    }
+
+    /// Same tolerances held for `steps` consecutive steps
+    pub fn with_hold_steps(self, steps: usize) -> Self {
+        Self { arrival_hold_steps: steps, ..self }
+    }
+
+    /// Same tolerances inside `gate`
+    pub fn with_gate(self, gate: ArrivalGate) -> Self {
+        Self { gate, ..self }
+    }
+
+    /// Radius of the largest disc inside the gate, the arrival radius of the time step stability bounds
+    pub fn arrival_radius(&self) -> f64 {
+        self.gate.inner_radius(self.distance_threshold)
+    }
+
+    /// Steps of `arrival_hold_steps`, at least one
+    pub fn hold_steps(&self) -> usize {
+        self.arrival_hold_steps.max(1)
+    }
+
+    /// True if a vehicle at `position` with `angle_error` and `velocity` has arrived at `target`
+    pub fn is_met(&self, position: &Point, target: &Target, angle_error: Radians, velocity: f64) -> bool {
+        self.gate.contains(position, target, self.distance_threshold)
+            && angle_error < self.angle_tolerance.to_radians()
+            && self.require_velocity_below.is_none_or(|limit| velocity < limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_by_name() {
+        assert_eq!(ArrivalCriteria::parse("strict"), Some(ArrivalCriteria::strict()));
+        assert_eq!(ArrivalCriteria::parse("Loose"), Some(ArrivalCriteria::loose()));
+        assert_eq!(ArrivalCriteria::parse("normal").unwrap().angle_tolerance, Degrees(5.0));
+        assert_eq!(ArrivalCriteria::parse("lenient"), None);
+        assert_eq!(ArrivalCriteria::default(), ArrivalCriteria::strict());
+    }
+
+    /// Exam target, arrival heading 90°
+    fn target() -> Target {
+        Target { position: Point::new(500.0, 700.0), required_angle: Radians(std::f64::consts::FRAC_PI_2) }
+    }
+
+    /// Point `distance` below the target
+    fn below(distance: f64) -> Point {
+        Point::new(500.0, 700.0 - distance)
+    }
+
+    #[test]
+    fn test_is_met_checks_every_tolerance() {
+        let strict = ArrivalCriteria::strict();
+        let target = target();
+        assert!(strict.is_met(&below(20.0), &target, Degrees(1.0).to_radians(), 10.0));
+        assert!(!strict.is_met(&below(30.0), &target, Degrees(1.0).to_radians(), 10.0));
+        assert!(!strict.is_met(&below(20.0), &target, Degrees(3.0).to_radians(), 10.0));
+        assert!(ArrivalCriteria::loose().is_met(&below(45.0), &target, Degrees(10.0).to_radians(), 10.0));
+
+        let slow = ArrivalCriteria { require_velocity_below: Some(8.0), ..strict };
+        assert!(slow.is_met(&below(20.0), &target, Degrees(1.0).to_radians(), 5.0));
+        assert!(!slow.is_met(&below(20.0), &target, Degrees(1.0).to_radians(), 10.0));
+    }
+
+    #[test]
+    fn test_ellipse_gate_is_measured_in_the_approach_frame() {
+        let gate = ArrivalGate::Ellipse { along: 60.0, across: 20.0 };
+        for degrees in [90.0f64, 0.0, 30.0, -135.0] {
+            let target = Target { required_angle: Radians(degrees.to_radians()), ..target() };
+            let (sin, cos) = target.required_angle.sin_cos();
+            let at = |forward: f64, lateral: f64| {
+                Point::new(500.0 + forward * cos - lateral * sin, 700.0 + forward * sin + lateral * cos)
+            };
+
+            // Just inside and just outside the four axis endpoints
+            for (forward, lateral) in [(60.0, 0.0), (-60.0, 0.0), (0.0, 20.0), (0.0, -20.0)] {
+                assert!(gate.contains(&at(forward * 0.99, lateral * 0.99), &target, 25.0), "{} {} {}", degrees, forward, lateral);
+                assert!(!gate.contains(&at(forward * 1.01, lateral * 1.01), &target, 25.0), "{} {} {}", degrees, forward, lateral);
+            }
+            assert!(!gate.contains(&at(30.0, 18.0), &target, 25.0));
+        }
+
+        let criteria = ArrivalCriteria::strict().with_gate(gate);
+        assert!(criteria.is_met(&below(50.0), &target(), Degrees(1.0).to_radians(), 10.0));
+        assert!(!criteria.is_met(&Point::new(522.0, 700.0), &target(), Degrees(1.0).to_radians(), 10.0));
+        assert_eq!(criteria.arrival_radius(), 20.0);
+        assert_eq!(ArrivalCriteria::strict().arrival_radius(), 25.0);
+    }
+
+    #[test]
+    fn test_gate_round_trips_and_defaults_to_the_circle() {
+        let json = serde_json::to_string(&ArrivalCriteria::strict()).unwrap();
+        assert!(!json.contains("gate"), "{}", json);
+
+        let ellipse = ArrivalCriteria::strict().with_gate(ArrivalGate::Ellipse { along: 60.0, across: 20.0 });
+        let json = serde_json::to_string(&ellipse).unwrap();
+        assert!(json.contains(r#""gate":{"ellipse":{"along":60.0,"across":20.0}}"#), "{}", json);
+        assert_eq!(serde_json::from_str::<ArrivalCriteria>(&json).unwrap(), ellipse);
+        assert!(ArrivalGate::Ellipse { along: 0.0, across: 20.0 }.validate().is_err());
+        assert!(ArrivalGate::Ellipse { along: 60.0, across: f64::NAN }.validate().is_err());
+        assert!(ellipse.gate.validate().is_ok());
+    }
+
+    #[test]
+    fn test_hold_steps_default_to_one_and_round_trip() {
+        let strict = ArrivalCriteria::strict();
+        assert_eq!(strict.hold_steps(), 1);
+        assert_eq!(strict.with_hold_steps(0).hold_steps(), 1);
+
+        let json = serde_json::to_string(&strict).unwrap();
+        assert!(!json.contains("arrival_hold_steps"), "{}", json);
+        let recorded: ArrivalCriteria = serde_json::from_str(r#"{"distance_threshold": 25.0, "angle_tolerance": 2.0}"#).unwrap();
+        assert_eq!(recorded, strict);
+
+        let held = strict.with_hold_steps(3);
+        let json = serde_json::to_string(&held).unwrap();
+        assert_eq!(serde_json::from_str::<ArrivalCriteria>(&json).unwrap(), held);
+    }
+}
@@ -0,0 +1,176 @@
+// Sensor dropouts: random windows of simulated time during which the
+// controller keeps seeing its last observation while the vehicle moves on
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimulationError;
+use crate::navigation::NavigationInputs;
+
+/// How often the observations drop out and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DropoutConfig {
+    /// Expected dropout starts per simulated minute outside a dropout
+    pub probability_per_minute: f64,
+    /// Shortest and longest dropout (seconds), durations are uniform in between
+    pub duration_range: (f64, f64),
+    /// Seed of the dropout draws, vehicle `i` of a scenario uses `seed + i`
+    pub seed: u64,
+}
+
+impl DropoutConfig {
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let (shortest, longest) = self.duration_range;
+        let valid_rate = self.probability_per_minute.is_finite() && self.probability_per_minute >= 0.0;
+        let valid_range = shortest.is_finite() && longest.is_finite() && shortest > 0.0 && shortest <= longest;
+        if valid_rate && valid_range {
+            Ok(())
+        } else {
+            Err(SimulationError::InvalidDropout { probability_per_minute: self.probability_per_minute, shortest, longest })
+        }
+    }
+
+    /// Same config drawing from the seed of vehicle `index`
+    pub fn for_vehicle(&self, index: usize) -> Self {
+        Self { seed: self.seed.wrapping_add(index as u64), ..*self }
+    }
+}
+
+/// Simulated time without fresh observations (seconds)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DropoutWindow {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Dropout state of one simulation, consulted once per step
+#[derive(Debug, Clone)]
+pub struct Dropout {
+    config: DropoutConfig,
+    rng: StdRng,
+    /// Every window so far, the last one possibly still open
+    windows: Vec<DropoutWindow>,
+    /// Inputs of the last fresh observation, held through the current window
+    last_observed: Option<NavigationInputs>,
+}
+
+impl Dropout {
+    pub fn new(config: DropoutConfig) -> Self {
+        Self { config, rng: StdRng::seed_from_u64(config.seed), windows: Vec::new(), last_observed: None }
+    }
+
+    /// Inputs the controller sees in the step starting at `time`
+    ///
+    /// Outside a window these are `fresh`, and a window may open holding them.
+    /// Inside one, the distance and the angular error (with the approach point
+    /// and heading derived from them) are those observed when it opened; the
+    /// vehicle own velocity is always fresh.
+    pub fn observe(&mut self, time: f64, dt: f64, fresh: NavigationInputs) -> NavigationInputs {
+        let in_window = self.windows.last().is_some_and(|window| time < window.end);
+        if let (true, Some(observed)) = (in_window, &self.last_observed) {
+            return NavigationInputs { velocity_relative: fresh.velocity_relative, ..observed.clone() };
+        }
+        self.last_observed = Some(fresh.clone());
+        self.start_window(time, dt);
+        fresh
+    }
+
+    /// Draw whether a window opens at `time`, recording it when it does
+    fn start_window(&mut self, time: f64, dt: f64) {
+        let probability = (self.config.probability_per_minute * dt / 60.0).min(1.0);
+        if self.rng.gen_bool(probability) {
+            let (shortest, longest) = self.config.duration_range;
+            let duration = if longest > shortest { self.rng.gen_range(shortest..=longest) } else { shortest };
+            self.windows.push(DropoutWindow { start: time, end: time + duration });
+        }
+    }
+
+    /// Windows up to `elapsed` seconds, an open one cut at `elapsed`
+    pub fn windows(&self, elapsed: f64) -> Vec<DropoutWindow> {
+        self.windows
+            .iter()
+            .filter(|window| window.start < elapsed)
+            .map(|window| DropoutWindow { end: window.end.min(elapsed), ..*window })
+            .collect()
+    }
+
+    /// Seconds without fresh observations over the first `elapsed` seconds
+    pub fn seconds_total(&self, elapsed: f64) -> f64 {
+        self.windows(elapsed).iter().map(|window| window.end - window.start).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, Point};
+    use crate::simulation::{Scenario, ScenarioVehicle, SimulationBuilder};
+    use crate::units::Radians;
+    use crate::vehicle::VehicleType;
+
+    #[test]
+    fn test_inputs_hold_still_through_a_forced_window() {
+        // One window opens at every step outside a window, back to back one second each
+        let forced = DropoutConfig { probability_per_minute: 1200.0, duration_range: (1.0, 1.0), seed: 1 };
+        let mut sim = SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), VehicleType::Agile)
+            .start_position(Point::new(200.0, 60.0))
+            .start_angle(Radians::ZERO)
+            .max_time(4.0)
+            .dropout(Some(forced))
+            .build();
+        while !sim.is_finished() {
+            sim.step();
+        }
+
+        let metrics = sim.metrics();
+        assert_eq!(metrics.dropout_windows.len(), 4);
+        assert!((metrics.dropout_seconds_total.unwrap() - 4.0).abs() < 1e-9);
+        for window in &metrics.dropout_windows {
+            // Points are stamped at the end of their step
+            let held: Vec<_> = sim.trajectory.iter()
+                .filter(|p| p.t - sim.dt >= window.start - 1e-9 && p.t - sim.dt < window.end - 1e-9)
+                .map(|p| (p.desired_heading, p.approach_x, p.approach_y, p.commanded_adjustment))
+                .collect();
+            assert_eq!(held.len(), 20);
+            assert!(held.iter().all(|inputs| *inputs == held[0]), "{:?}", window);
+        }
+        let first = sim.trajectory.first().unwrap().desired_heading;
+        assert!(sim.trajectory.iter().any(|p| p.desired_heading != first));
+    }
+
+    #[test]
+    fn test_random_dropouts_are_reproducible_under_the_seed() {
+        let scenario = Scenario {
+            vehicles: vec![ScenarioVehicle::random(VehicleType::Agile); 2],
+            seed: Some(4),
+            max_time: 200.0,
+            dropout: Some(DropoutConfig { probability_per_minute: 12.0, duration_range: (0.5, 2.0), seed: 9 }),
+            ..Scenario::default_exam()
+        };
+        let run = || serde_json::to_value(scenario.run().unwrap()).unwrap();
+        let result = run();
+        assert_eq!(result, run());
+
+        let windows: Vec<&serde_json::Value> = result["vehicles"].as_array().unwrap().iter().map(|v| &v["metrics"]["dropout_windows"]).collect();
+        assert!(windows.iter().all(|w| !w.as_array().unwrap().is_empty()));
+        assert_ne!(windows[0], windows[1]);
+    }
+
+    #[test]
+    fn test_invalid_configs_are_rejected() {
+        let valid = DropoutConfig { probability_per_minute: 2.0, duration_range: (1.0, 3.0), seed: 0 };
+        assert!(valid.validate().is_ok());
+        for invalid in [
+            DropoutConfig { probability_per_minute: -1.0, ..valid },
+            DropoutConfig { probability_per_minute: f64::INFINITY, ..valid },
+            DropoutConfig { duration_range: (0.0, 1.0), ..valid },
+            DropoutConfig { duration_range: (3.0, 1.0), ..valid },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+        }
+        assert!(Scenario { dropout: Some(DropoutConfig { duration_range: (2.0, 1.0), ..valid }), ..Scenario::default_exam() }
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,310 @@
+// Dubins paths: the shortest path between two poses for a vehicle moving
+// forward with a bounded turning radius, the yardstick of the arrival times
+//
+// Follows Shkel & Lumelsky, "Classification of the Dubins set" (2001): the
+// problem is scaled to a unit radius and rotated so the goal lies on the x
+// axis, then each of the six words (LSL, RSR, LSR, RSL, RLR, LRL) is solved
+// in closed form and the shortest feasible one is kept.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{euclidean_distance, Point};
+use crate::units::Radians;
+
+/// Position and heading (0 = east, π/2 = north)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pose {
+    pub position: Point,
+    pub angle: Radians,
+}
+
+impl Pose {
+    pub fn new(x: f64, y: f64, angle: Radians) -> Self {
+        Self { position: Point::new(x, y), angle }
+    }
+}
+
+/// Segments of a Dubins path: L turns left (counter-clockwise), R right, S goes straight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DubinsWord {
+    Lsl,
+    Rsr,
+    Lsr,
+    Rsl,
+    Rlr,
+    Lrl,
+}
+
+impl DubinsWord {
+    pub const ALL: [DubinsWord; 6] =
+        [DubinsWord::Lsl, DubinsWord::Rsr, DubinsWord::Lsr, DubinsWord::Rsl, DubinsWord::Rlr, DubinsWord::Lrl];
+
+    /// Turn direction of every segment: 1 left, -1 right, 0 straight
+    fn turns(self) -> [f64; 3] {
+        match self {
+            DubinsWord::Lsl => [1.0, 0.0, 1.0],
+            DubinsWord::Rsr => [-1.0, 0.0, -1.0],
+            DubinsWord::Lsr => [1.0, 0.0, -1.0],
+            DubinsWord::Rsl => [-1.0, 0.0, 1.0],
+            DubinsWord::Rlr => [-1.0, 1.0, -1.0],
+            DubinsWord::Lrl => [1.0, -1.0, 1.0],
+        }
+    }
+
+    /// Segment lengths at unit radius, None when the word cannot join the poses
+    ///
+    /// `d` is the distance between the poses over the radius, `alpha` and
+    /// `beta` the start and goal headings relative to the line joining them.
+    fn segments(self, d: f64, alpha: f64, beta: f64) -> Option<[f64; 3]> {
+        let (sa, ca) = alpha.sin_cos();
+        let (sb, cb) = beta.sin_cos();
+        let cos_ab = (alpha - beta).cos();
+        match self {
+            DubinsWord::Lsl => {
+                let p_sq = 2.0 + d * d - 2.0 * cos_ab + 2.0 * d * (sa - sb);
+                let theta = (cb - ca).atan2(d + sa - sb);
+                straight(p_sq).map(|p| [mod2pi(theta - alpha), p, mod2pi(beta - theta)])
+            }
+            DubinsWord::Rsr => {
+                let p_sq = 2.0 + d * d - 2.0 * cos_ab + 2.0 * d * (sb - sa);
+                let theta = (ca - cb).atan2(d - sa + sb);
+                straight(p_sq).map(|p| [mod2pi(alpha - theta), p, mod2pi(theta - beta)])
+            }
+            DubinsWord::Lsr => {
+                let p_sq = -2.0 + d * d + 2.0 * cos_ab + 2.0 * d * (sa + sb);
+                straight(p_sq).map(|p| {
+                    let theta = (-ca - cb).atan2(d + sa + sb) - (-2.0f64).atan2(p);
+                    [mod2pi(theta - alpha), p, mod2pi(theta - beta)]
+                })
+            }
+            DubinsWord::Rsl => {
+                let p_sq = -2.0 + d * d + 2.0 * cos_ab - 2.0 * d * (sa + sb);
+                straight(p_sq).map(|p| {
+                    let theta = (ca + cb).atan2(d - sa - sb) - 2.0f64.atan2(p);
+                    [mod2pi(alpha - theta), p, mod2pi(beta - theta)]
+                })
+            }
+            DubinsWord::Rlr => {
+                let cos_p = (6.0 - d * d + 2.0 * cos_ab + 2.0 * d * (sa - sb)) / 8.0;
+                let phi = (ca - cb).atan2(d - sa + sb);
+                middle_turn(cos_p).map(|p| {
+                    let t = mod2pi(alpha - phi + p / 2.0);
+                    [t, p, mod2pi(alpha - beta - t + p)]
+                })
+            }
+            DubinsWord::Lrl => {
+                let cos_p = (6.0 - d * d + 2.0 * cos_ab + 2.0 * d * (sb - sa)) / 8.0;
+                let phi = (ca - cb).atan2(d + sa - sb);
+                middle_turn(cos_p).map(|p| {
+                    let t = mod2pi(-alpha - phi + p / 2.0);
+                    [t, p, mod2pi(beta - alpha - t + p)]
+                })
+            }
+        }
+    }
+}
+
+/// Straight segment of a CSC word, None when the tangent does not exist
+fn straight(p_sq: f64) -> Option<f64> {
+    // Rounding can leave a tangent of length zero slightly negative
+    (p_sq >= -1e-12).then(|| p_sq.max(0.0).sqrt())
+}
+
+/// Middle turn of a CCC word, longer than π, None when the circles are too far apart
+fn middle_turn(cos_p: f64) -> Option<f64> {
+    (cos_p.abs() <= 1.0).then(|| mod2pi(2.0 * PI - cos_p.acos()))
+}
+
+/// Angle in [0, 2π)
+fn mod2pi(angle: f64) -> f64 {
+    let wrapped = angle.rem_euclid(2.0 * PI);
+    // rem_euclid rounds tiny negative angles up to 2π
+    if wrapped >= 2.0 * PI { 0.0 } else { wrapped }
+}
+
+/// One Dubins path: a word, its three segment lengths and the turning radius
+#[derive(Debug, Clone, PartialEq)]
+pub struct DubinsPath {
+    pub word: DubinsWord,
+    /// Lengths of the segments at unit radius, turns in radians
+    pub segments: [f64; 3],
+    pub radius: f64,
+}
+
+impl DubinsPath {
+    pub fn length(&self) -> f64 {
+        self.segments.iter().sum::<f64>() * self.radius
+    }
+
+    /// Pose reached after `distance` along the path from `start`, clamped to the path
+    pub fn pose_at(&self, start: &Pose, distance: f64) -> Pose {
+        let mut remaining = distance.clamp(0.0, self.length()) / self.radius;
+        let (mut x, mut y, mut angle) = (0.0, 0.0, start.angle.0);
+        for (segment, turn) in self.segments.iter().zip(self.word.turns()) {
+            let step = remaining.min(*segment);
+            if turn == 0.0 {
+                x += step * angle.cos();
+                y += step * angle.sin();
+            } else {
+                let next = angle + turn * step;
+                x += turn * (next.sin() - angle.sin());
+                y += turn * (angle.cos() - next.cos());
+                angle = next;
+            }
+            remaining -= step;
+        }
+        Pose::new(start.position.x + x * self.radius, start.position.y + y * self.radius, Radians(angle))
+    }
+}
+
+/// Every feasible path from `start` to `goal` with turning radius `radius`
+///
+/// Empty unless the radius is positive and finite and the poses are finite.
+pub fn paths(start: &Pose, goal: &Pose, radius: f64) -> Vec<DubinsPath> {
+    let finite = [start.position.x, start.position.y, start.angle.0, goal.position.x, goal.position.y, goal.angle.0]
+        .iter()
+        .all(|value| value.is_finite());
+    if !(finite && radius > 0.0 && radius.is_finite()) {
+        return Vec::new();
+    }
+
+    let dx = goal.position.x - start.position.x;
+    let dy = goal.position.y - start.position.y;
+    let d = euclidean_distance(&start.position, &goal.position) / radius;
+    let theta = if d > 0.0 { mod2pi(dy.atan2(dx)) } else { 0.0 };
+    let alpha = mod2pi(start.angle.0 - theta);
+    let beta = mod2pi(goal.angle.0 - theta);
+
+    DubinsWord::ALL
+        .into_iter()
+        .filter_map(|word| word.segments(d, alpha, beta).map(|segments| DubinsPath { word, segments, radius }))
+        .collect()
+}
+
+/// Shortest path from `start` to `goal` with turning radius `radius`, None without one
+pub fn shortest_path(start: &Pose, goal: &Pose, radius: f64) -> Option<DubinsPath> {
+    paths(start, goal, radius).into_iter().min_by(|a, b| a.length().total_cmp(&b.length()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn pose(x: f64, y: f64, angle: f64) -> Pose {
+        Pose::new(x, y, Radians(angle))
+    }
+
+    fn length(start: Pose, goal: Pose, radius: f64) -> f64 {
+        shortest_path(&start, &goal, radius).unwrap().length()
+    }
+
+    fn assert_reaches(path: &DubinsPath, start: &Pose, goal: &Pose) {
+        let end = path.pose_at(start, path.length());
+        let tolerance = 1e-6 * path.radius.max(1.0);
+        assert!(euclidean_distance(&end.position, &goal.position) < tolerance, "{:?} ends at {:?}, not {:?}", path.word, end, goal);
+        let turn = mod2pi((end.angle - goal.angle).0);
+        assert!(turn.min(2.0 * PI - turn) < 1e-6, "{:?} heading", path.word);
+    }
+
+    #[test]
+    fn test_start_equal_to_goal_is_a_zero_length_path() {
+        for angle in [0.0, 1.0, -2.5, PI] {
+            let pose = pose(3.0, -7.0, angle);
+            assert!(length(pose.clone(), pose, 2.0) < EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_goal_directly_ahead_is_a_straight_line() {
+        let path = shortest_path(&pose(0.0, 0.0, 0.0), &pose(4.0, 0.0, 0.0), 1.0).unwrap();
+        assert!((path.length() - 4.0).abs() < EPSILON);
+        assert!(path.segments[0] < EPSILON && path.segments[2] < EPSILON);
+
+        // Same along any heading and radius
+        let angle: f64 = 2.0;
+        let goal = pose(10.0 + 50.0 * angle.cos(), 20.0 + 50.0 * angle.sin(), angle);
+        assert!((length(pose(10.0, 20.0, angle), goal, 30.0) - 50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pure_turns_follow_the_circle() {
+        // Quarter circle to the left and half circles either way, radius 1
+        let start = pose(0.0, 0.0, 0.0);
+        assert!((length(start.clone(), pose(1.0, 1.0, PI / 2.0), 1.0) - PI / 2.0).abs() < EPSILON);
+
+        let left = shortest_path(&start, &pose(0.0, 2.0, PI), 1.0).unwrap();
+        assert!((left.length() - PI).abs() < EPSILON);
+        assert_eq!(left.word.turns()[0], 1.0);
+
+        let right = shortest_path(&start, &pose(0.0, -2.0, PI), 1.0).unwrap();
+        assert!((right.length() - PI).abs() < EPSILON);
+        assert_eq!(right.word.turns()[0], -1.0);
+
+        // Scales with the radius
+        assert!((length(start, pose(0.0, 20.0, PI), 10.0) - 10.0 * PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_textbook_cases() {
+        // Heading north to a goal four radii east heading south: two quarter turns
+        // to the right around a straight of four radii
+        let path = shortest_path(&pose(0.0, 0.0, PI / 2.0), &pose(6.0, 0.0, -PI / 2.0), 1.0).unwrap();
+        assert_eq!(path.word, DubinsWord::Rsr);
+        assert!((path.length() - (PI + 4.0)).abs() < EPSILON);
+
+        // Turning around on the spot: a CCC loop of π/3, 5π/3 and π/3, shorter than any CSC word
+        let start = pose(0.0, 0.0, 0.0);
+        let goal = pose(0.0, 0.0, PI);
+        let path = shortest_path(&start, &goal, 1.0).unwrap();
+        assert!(matches!(path.word, DubinsWord::Lrl | DubinsWord::Rlr));
+        assert!((path.length() - 7.0 * PI / 3.0).abs() < EPSILON);
+        for (segment, expected) in path.segments.iter().zip([PI / 3.0, 5.0 * PI / 3.0, PI / 3.0]) {
+            assert!((segment - expected).abs() < EPSILON);
+        }
+        let csc = paths(&start, &goal, 1.0)
+            .into_iter()
+            .filter(|p| p.word.turns()[1] == 0.0)
+            .map(|p| p.length())
+            .fold(f64::INFINITY, f64::min);
+        assert!(path.length() < csc);
+
+        // Parallel offset of exactly two radii: a left turn and back, CSC with a zero straight
+        let path = shortest_path(&pose(0.0, 0.0, PI / 2.0), &pose(-2.0, 0.0, -PI / 2.0), 1.0).unwrap();
+        assert!((path.length() - PI).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_every_word_reaches_the_goal() {
+        let poses = [
+            (pose(0.0, 0.0, 0.0), pose(5.0, 3.0, 2.0)),
+            (pose(-3.0, 4.0, 1.2), pose(0.5, -0.5, -2.8)),
+            (pose(10.0, 10.0, -1.0), pose(11.0, 10.5, 2.5)),
+            (pose(0.0, 0.0, 0.0), pose(-1.0, 0.0, 0.0)),
+            (pose(200.0, 50.0, 0.7), pose(500.0, 500.0, PI / 2.0)),
+        ];
+        for (start, goal) in &poses {
+            let all = paths(start, goal, 1.5);
+            assert!(all.len() >= 4, "CSC words LSL and RSR always exist, got {:?}", all);
+            for path in &all {
+                assert_reaches(path, start, goal);
+            }
+            let shortest = shortest_path(start, goal, 1.5).unwrap();
+            assert!(shortest.length() >= euclidean_distance(&start.position, &goal.position) - EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_unusable_inputs_have_no_path() {
+        let start = pose(0.0, 0.0, 0.0);
+        let goal = pose(4.0, 0.0, 0.0);
+        for radius in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert!(shortest_path(&start, &goal, radius).is_none());
+        }
+        assert!(shortest_path(&pose(f64::NAN, 0.0, 0.0), &goal, 1.0).is_none());
+    }
+}
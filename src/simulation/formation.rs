@@ -0,0 +1,158 @@
+// Fleet formations: every vehicle of a multi-vehicle run arrives at its own
+// berth, offset from the map target
+
+use serde::{Deserialize, Serialize};
+
+use super::{MultiVehicleSimulation, SimulationBuilder};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::map::{Map, Point, Target};
+use crate::vehicle::VehicleType;
+
+/// Placement of the berths around the map target, all reached at its required angle
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formation {
+    /// Side by side across the arrival direction, centered on the target and
+    /// numbered from the left of a vehicle arriving
+    LineAbreast { spacing: f64 },
+    /// One behind the other along the arrival direction, the first vehicle at the target
+    Column { spacing: f64 },
+}
+
+impl Formation {
+    pub fn spacing(&self) -> f64 {
+        match *self {
+            Formation::LineAbreast { spacing } | Formation::Column { spacing } => spacing,
+        }
+    }
+
+    /// Berth of each of `count` vehicles
+    pub fn berths(&self, target: &Target, count: usize) -> Vec<Point> {
+        let (sin, cos) = target.required_angle.sin_cos();
+        (0..count)
+            .map(|i| match *self {
+                Formation::LineAbreast { spacing } => {
+                    // Right of the arrival direction
+                    let offset = (i as f64 - (count as f64 - 1.0) / 2.0) * spacing;
+                    Point::new(target.position.x + offset * sin, target.position.y - offset * cos)
+                }
+                Formation::Column { spacing } => {
+                    let offset = i as f64 * spacing;
+                    Point::new(target.position.x - offset * cos, target.position.y - offset * sin)
+                }
+            })
+            .collect()
+    }
+
+    /// Copy of `map` per vehicle with the target moved to its berth
+    ///
+    /// Fails when the spacing is not positive or a berth falls off the map.
+    pub fn berth_maps(&self, map: &Map, count: usize) -> Result<Vec<Map>, FuzzyNavError> {
+        let spacing = self.spacing();
+        if !(spacing.is_finite() && spacing > 0.0) {
+            return Err(SimulationError::InvalidFormationSpacing(spacing).into());
+        }
+
+        self.berths(&map.target, count)
+            .into_iter()
+            .map(|berth| {
+                let mut berth_map = Map::try_new(map.width, map.height, berth.x, berth.y)?;
+                berth_map.target.required_angle = map.target.required_angle;
+                Ok(berth_map)
+            })
+            .collect()
+    }
+}
+
+impl MultiVehicleSimulation {
+    /// One vehicle per type, each with a random start and its own berth of `formation`
+    /// and the id a scenario would generate for it
+    ///
+    /// Runs with the builder defaults, dt 0.05 s and 600 s of simulated time.
+    pub fn with_formation(map: Map, vehicle_types: &[VehicleType], formation: Formation) -> Result<Self, FuzzyNavError> {
+        if vehicle_types.is_empty() {
+            return Err(SimulationError::NoVehicles.into());
+        }
+
+        let simulations: Vec<_> = formation
+            .berth_maps(&map, vehicle_types.len())?
+            .into_iter()
+            .zip(vehicle_types)
+            .enumerate()
+            .map(|(index, (berth_map, &vehicle_type))| {
+                let ordinal = vehicle_types[..=index].iter().filter(|&&t| t == vehicle_type).count();
+                SimulationBuilder::new(berth_map, vehicle_type).id(vehicle_type.default_id(ordinal)).build()
+            })
+            .collect();
+        let (dt, max_time) = (simulations[0].dt, simulations[0].max_time);
+        Ok(Self::new(simulations, dt, max_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::MapError;
+    use crate::units::Radians;
+
+    fn exam_map() -> Map {
+        Map::new(1000.0, 800.0, 500.0, 700.0)
+    }
+
+    fn coordinates(points: &[Point]) -> Vec<(f64, f64)> {
+        points.iter().map(|p| ((p.x * 1e9).round() / 1e9, (p.y * 1e9).round() / 1e9)).collect()
+    }
+
+    #[test]
+    fn test_berths_for_a_90_degree_arrival() {
+        let target = exam_map().target;
+
+        let line = Formation::LineAbreast { spacing: 60.0 }.berths(&target, 3);
+        assert_eq!(coordinates(&line), [(440.0, 700.0), (500.0, 700.0), (560.0, 700.0)]);
+
+        let pair = Formation::LineAbreast { spacing: 60.0 }.berths(&target, 2);
+        assert_eq!(coordinates(&pair), [(470.0, 700.0), (530.0, 700.0)]);
+
+        let column = Formation::Column { spacing: 60.0 }.berths(&target, 3);
+        assert_eq!(coordinates(&column), [(500.0, 700.0), (500.0, 640.0), (500.0, 580.0)]);
+    }
+
+    #[test]
+    fn test_berths_turn_with_the_arrival_direction() {
+        let mut target = exam_map().target;
+        target.required_angle = Radians::ZERO;
+
+        // Arriving eastwards, abreast spreads along y and the column trails to the west
+        let line = Formation::LineAbreast { spacing: 60.0 }.berths(&target, 3);
+        assert_eq!(coordinates(&line), [(500.0, 760.0), (500.0, 700.0), (500.0, 640.0)]);
+        let column = Formation::Column { spacing: 60.0 }.berths(&target, 2);
+        assert_eq!(coordinates(&column), [(500.0, 700.0), (440.0, 700.0)]);
+    }
+
+    #[test]
+    fn test_berth_maps_reject_bad_spacing_and_berths_off_the_map() {
+        let map = exam_map();
+        for spacing in [0.0, -60.0, f64::NAN] {
+            assert!(matches!(
+                Formation::Column { spacing }.berth_maps(&map, 3),
+                Err(FuzzyNavError::Simulation(SimulationError::InvalidFormationSpacing(_)))
+            ));
+        }
+        assert!(matches!(
+            Formation::LineAbreast { spacing: 600.0 }.berth_maps(&map, 3),
+            Err(FuzzyNavError::Map(MapError::TargetOutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_with_formation_gives_each_vehicle_its_berth() {
+        let vehicle_types = [VehicleType::Heavy, VehicleType::Standard, VehicleType::Agile];
+        let multi = MultiVehicleSimulation::with_formation(exam_map(), &vehicle_types, Formation::LineAbreast { spacing: 60.0 })
+            .unwrap();
+
+        let targets: Vec<Point> = multi.simulations.iter().map(|sim| sim.map.target.position.clone()).collect();
+        assert_eq!(coordinates(&targets), [(440.0, 700.0), (500.0, 700.0), (560.0, 700.0)]);
+        assert!(multi.simulations.iter().all(|sim| sim.map.target.required_angle == exam_map().target.required_angle));
+        assert!(MultiVehicleSimulation::with_formation(exam_map(), &[], Formation::Column { spacing: 60.0 }).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+// Scenario fuzzing: seeded random start states run in parallel (sequentially
+// without rayon), the failing ones collected with their seeds and shrunk
+// towards a nominal start
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{Simulation, SimulationBuilder, SimulationMetrics, TerminationReason};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::map::{normalize_angle, Map, Point};
+use crate::units::Degrees;
+use crate::vehicle::VehicleType;
+
+/// Bisection steps per coordinate, the shrunk start is within 1/1024 of the failure boundary
+const SHRINK_STEPS: usize = 10;
+
+/// Where the fuzzer samples start states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartRegion {
+    /// Start zone positions with 30°-150° headings, like regular runs
+    StartZone,
+    /// Any position on the map with any heading
+    WholeMap,
+}
+
+/// One fully determined run: map, vehicle and start state
+///
+/// Self-contained, so a failing case can be stored as JSON and replayed as a
+/// regression scenario with `run`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub vehicle_type: VehicleType,
+    /// Seed the start state was drawn from, kept by shrinking
+    pub seed: u64,
+    pub map_width: f64,
+    pub map_height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub start_x: f64,
+    pub start_y: f64,
+    pub start_angle: Degrees,
+    pub dt: f64,
+    pub max_time: f64,
+    /// Replaces the preset maneuverability (rad/s) that bounds the controller output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maneuverability: Option<f64>,
+}
+
+impl Scenario {
+    pub fn build(&self) -> Result<Simulation, FuzzyNavError> {
+        if self.dt <= 0.0 {
+            return Err(SimulationError::InvalidTimeStep(self.dt).into());
+        }
+        let map = Map::try_new(self.map_width, self.map_height, self.target_x, self.target_y)?;
+
+        let mut sim = SimulationBuilder::new(map, self.vehicle_type)
+            .dt(self.dt)
+            .max_time(self.max_time)
+            .start_position(Point::new(self.start_x, self.start_y))
+            .start_angle(self.start_angle.to_radians())
+            .build();
+        if let Some(maneuverability) = self.maneuverability {
+            sim.vehicle.characteristics.maneuverability = maneuverability;
+        }
+        Ok(sim)
+    }
+
+    /// Run to the end, `termination` of the metrics is always set
+    pub fn run(&self) -> Result<SimulationMetrics, FuzzyNavError> {
+        let mut sim = self.build()?;
+        while !sim.is_finished() {
+            sim.step();
+        }
+        Ok(sim.metrics())
+    }
+
+    fn fails_with(&self, reason: TerminationReason) -> bool {
+        self.run().is_ok_and(|metrics| metrics.termination == Some(reason))
+    }
+}
+
+/// Search parameters, `FuzzConfig::default()` fuzzes 100 Standard starts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuzzConfig {
+    pub vehicle_type: VehicleType,
+    pub cases: usize,
+    /// Case `i` draws its start state from `seed + i`
+    pub seed: u64,
+    pub region: StartRegion,
+    pub map_width: f64,
+    pub map_height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub dt: f64,
+    /// Generous by default, a failure is a controller problem rather than a slow vehicle
+    pub max_time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maneuverability: Option<f64>,
+    /// Shrink every failure into a minimal reproducer
+    pub shrink: bool,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            vehicle_type: VehicleType::Standard,
+            cases: 100,
+            seed: 0,
+            region: StartRegion::StartZone,
+            map_width: 1000.0,
+            map_height: 800.0,
+            target_x: 500.0,
+            target_y: 700.0,
+            dt: 0.05,
+            max_time: 600.0,
+            maneuverability: None,
+            shrink: true,
+        }
+    }
+}
+
+impl FuzzConfig {
+    /// Scenario of case `index`
+    pub fn scenario(&self, index: usize) -> Scenario {
+        let seed = self.seed.wrapping_add(index as u64);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let map = Map::new(self.map_width, self.map_height, self.target_x, self.target_y);
+
+        let (start, angle) = match self.region {
+            StartRegion::StartZone => (map.random_start_position_with(&mut rng), map.random_start_angle_with(&mut rng)),
+            StartRegion::WholeMap => (
+                Point::new(rng.gen_range(0.0..map.width), rng.gen_range(0.0..map.height)),
+                Degrees(rng.gen_range(-180f64..180.0)).to_radians(),
+            ),
+        };
+
+        Scenario {
+            vehicle_type: self.vehicle_type,
+            seed,
+            map_width: self.map_width,
+            map_height: self.map_height,
+            target_x: self.target_x,
+            target_y: self.target_y,
+            start_x: start.x,
+            start_y: start.y,
+            start_angle: angle.to_degrees(),
+            dt: self.dt,
+            max_time: self.max_time,
+            maneuverability: self.maneuverability,
+        }
+    }
+}
+
+/// A case that did not arrive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzFailure {
+    pub scenario: Scenario,
+    pub termination: TerminationReason,
+    pub final_distance_to_target: f64,
+    /// Start closest to the nominal one that still fails with the same termination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shrunk: Option<Scenario>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzReport {
+    pub config: FuzzConfig,
+    pub failures: Vec<FuzzFailure>,
+}
+
+impl FuzzReport {
+    /// One regression scenario per failure, the shrunk reproducer when there is one
+    pub fn regression_scenarios(&self) -> Vec<Scenario> {
+        self.failures
+            .iter()
+            .map(|failure| failure.shrunk.clone().unwrap_or_else(|| failure.scenario.clone()))
+            .collect()
+    }
+}
+
+/// Run every case and collect the failures, in case order
+pub fn run(config: &FuzzConfig) -> Result<FuzzReport, FuzzyNavError> {
+    if config.cases == 0 {
+        return Err(SimulationError::NoIterations.into());
+    }
+    // Surfaces invalid maps and time steps before the parallel run
+    config.scenario(0).build()?;
+
+    #[cfg(feature = "rayon")]
+    let cases = (0..config.cases).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let cases = 0..config.cases;
+
+    let failures = cases
+        .filter_map(|index| {
+            let scenario = config.scenario(index);
+            let metrics = scenario.run().ok()?;
+            let termination = metrics.termination.filter(|&reason| reason != TerminationReason::Arrived)?;
+            let shrunk = config.shrink.then(|| shrink(&scenario, termination));
+            Some(FuzzFailure {
+                scenario,
+                termination,
+                final_distance_to_target: metrics.final_distance_to_target,
+                shrunk,
+            })
+        })
+        .collect();
+
+    Ok(FuzzReport {
+        config: config.clone(),
+        failures,
+    })
+}
+
+/// Smallest fraction of the way from the nominal value to the failing one that still fails
+fn bisect(still_fails: impl Fn(f64) -> bool) -> f64 {
+    let (mut passes, mut fails) = (0.0, 1.0);
+    for _ in 0..SHRINK_STEPS {
+        let mid = (passes + fails) / 2.0;
+        if still_fails(mid) {
+            fails = mid;
+        } else {
+            passes = mid;
+        }
+    }
+    fails
+}
+
+/// Move the heading, then x, then y of a failing scenario towards the nominal
+/// start (below the target, mid start zone, heading at the required angle) as
+/// far as it keeps failing with `reason`
+pub fn shrink(scenario: &Scenario, reason: TerminationReason) -> Scenario {
+    let map = Map::new(scenario.map_width, scenario.map_height, scenario.target_x, scenario.target_y);
+    let nominal_x = map.target.position.x;
+    let nominal_y = map.height * map.start_zone.height_percentage / 2.0;
+    let nominal_angle = map.target.required_angle.to_degrees();
+
+    let mut shrunk = scenario.clone();
+
+    let angle_offset = normalize_angle((shrunk.start_angle - nominal_angle).to_radians()).to_degrees();
+    let t = bisect(|t| Scenario { start_angle: nominal_angle + angle_offset * t, ..shrunk.clone() }.fails_with(reason));
+    shrunk.start_angle = nominal_angle + angle_offset * t;
+
+    let x_offset = shrunk.start_x - nominal_x;
+    let t = bisect(|t| Scenario { start_x: nominal_x + t * x_offset, ..shrunk.clone() }.fails_with(reason));
+    shrunk.start_x = nominal_x + t * x_offset;
+
+    let y_offset = shrunk.start_y - nominal_y;
+    let t = bisect(|t| Scenario { start_y: nominal_y + t * y_offset, ..shrunk.clone() }.fails_with(reason));
+    shrunk.start_y = nominal_y + t * y_offset;
+
+    shrunk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Agile with no steering authority on a small map, only near-nominal starts arrive
+    fn crippled() -> FuzzConfig {
+        FuzzConfig {
+            vehicle_type: VehicleType::Agile,
+            cases: 4,
+            seed: 7,
+            map_width: 400.0,
+            map_height: 300.0,
+            target_x: 200.0,
+            target_y: 250.0,
+            dt: 0.1,
+            max_time: 40.0,
+            maneuverability: Some(0.0),
+            ..Default::default()
+        }
+    }
+
+    fn nominal_distance(scenario: &Scenario) -> (f64, f64) {
+        ((scenario.start_angle - Degrees(90.0)).abs().0, (scenario.start_x - 200.0).abs())
+    }
+
+    #[test]
+    fn test_nominal_start_arrives_with_crippled_controller() {
+        let nominal = Scenario { start_x: 200.0, start_y: 12.0, start_angle: Degrees(90.0), ..crippled().scenario(0) };
+        assert_eq!(nominal.run().unwrap().termination, Some(TerminationReason::Arrived));
+    }
+
+    #[test]
+    fn test_fuzzer_finds_and_shrinks_crippled_controller_failures() {
+        let report = run(&crippled()).unwrap();
+        assert!(!report.failures.is_empty());
+
+        for failure in &report.failures {
+            assert_eq!(failure.termination, TerminationReason::Timeout);
+            let shrunk = failure.shrunk.as_ref().unwrap();
+            assert_eq!(shrunk.seed, failure.scenario.seed);
+            assert!(shrunk.fails_with(failure.termination));
+
+            let (angle, x) = nominal_distance(&failure.scenario);
+            let (shrunk_angle, shrunk_x) = nominal_distance(shrunk);
+            assert!(shrunk_angle <= angle && shrunk_x <= x);
+            assert!(shrunk_angle < angle || shrunk_x < x, "{:?} was not shrunk", failure.scenario);
+        }
+    }
+
+    #[test]
+    fn test_report_replays_as_regression_scenarios() {
+        let config = FuzzConfig { shrink: false, region: StartRegion::WholeMap, ..crippled() };
+        let report = run(&config).unwrap();
+        assert!(!report.failures.is_empty());
+        let json = serde_json::to_string(&report).unwrap();
+        let replayed: FuzzReport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(replayed.config, config);
+        for (scenario, failure) in replayed.regression_scenarios().iter().zip(&report.failures) {
+            // JSON floats may lose the last bit, the replay only needs the same outcome
+            assert_eq!(scenario.seed, failure.scenario.seed);
+            assert!((scenario.start_x - failure.scenario.start_x).abs() < 1e-9);
+            assert_eq!(scenario.run().unwrap().termination, Some(failure.termination));
+        }
+    }
+
+    #[test]
+    fn test_rejects_empty_or_invalid_runs() {
+        assert!(run(&FuzzConfig { cases: 0, ..crippled() }).is_err());
+        assert!(run(&FuzzConfig { dt: 0.0, ..crippled() }).is_err());
+        assert!(run(&FuzzConfig { target_x: 5000.0, ..crippled() }).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// Physics invariants of one simulation step, checked at the end of every
+// `Simulation::step`: a violation panics with the full state in debug builds
+// and is logged as an error in release ones
+
+use std::f64::consts::PI;
+
+use thiserror::Error;
+
+use crate::map::{euclidean_distance, Point};
+use crate::units::Radians;
+use crate::vehicle::{VehicleCharacteristics, VehicleState};
+
+/// Relative slack of the limit and displacement checks, rounding stays far below it
+pub const INVARIANT_EPSILON: f64 = 1e-9;
+
+/// Time, position and odometer of a simulation, taken before and after a step
+#[derive(Debug, Clone)]
+pub struct StepSnapshot {
+    pub step_index: usize,
+    pub time: f64,
+    pub position: Point,
+    pub distance_traveled: f64,
+}
+
+/// Physics invariant broken by a step
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum InvariantViolation {
+    #[error("Angle {0} rad outside [-π, π]")]
+    AngleOutOfRange(Radians),
+
+    #[error("Velocity {velocity} outside [0, {max_velocity}]")]
+    VelocityOutOfRange { velocity: f64, max_velocity: f64 },
+
+    #[error("Non-finite position ({x}, {y})")]
+    NonFinitePosition { x: f64, y: f64 },
+
+    #[error("Applied angular adjustment {applied} rad/s above the maneuverability {maneuverability} rad/s")]
+    AngularAdjustmentAboveManeuverability { applied: f64, maneuverability: f64 },
+
+    #[error("Distance traveled went down from {before} to {after}")]
+    DistanceTraveledDecreased { before: f64, after: f64 },
+
+    #[error("Distance traveled grew {recorded} for a displacement of {displacement}")]
+    DistanceTraveledMismatch { recorded: f64, displacement: f64 },
+
+    #[error("Time went from {before} to {after} over a step")]
+    TimeNotIncreasing { before: f64, after: f64 },
+}
+
+/// Check the state after a step against the one before it
+///
+/// `applied_adjustment` is the angular adjustment (rad/s) the step applied,
+/// None when the step did not move the vehicle; the motion invariants are
+/// only checked for a step that moved it.
+pub fn check_step(
+    before: &StepSnapshot,
+    after: &StepSnapshot,
+    state: &VehicleState,
+    characteristics: &VehicleCharacteristics,
+    applied_adjustment: Option<f64>,
+) -> Result<(), InvariantViolation> {
+    if !(-PI..=PI).contains(&state.angle.0) {
+        return Err(InvariantViolation::AngleOutOfRange(state.angle));
+    }
+    let max_velocity = characteristics.max_velocity;
+    if !(0.0..=max_velocity * (1.0 + INVARIANT_EPSILON)).contains(&state.velocity) {
+        return Err(InvariantViolation::VelocityOutOfRange { velocity: state.velocity, max_velocity });
+    }
+    if !(state.position.x.is_finite() && state.position.y.is_finite()) {
+        return Err(InvariantViolation::NonFinitePosition { x: state.position.x, y: state.position.y });
+    }
+    if after.distance_traveled < before.distance_traveled {
+        return Err(InvariantViolation::DistanceTraveledDecreased {
+            before: before.distance_traveled,
+            after: after.distance_traveled,
+        });
+    }
+
+    let Some(applied) = applied_adjustment else {
+        return Ok(());
+    };
+    let maneuverability = characteristics.maneuverability;
+    if applied.abs() > maneuverability * (1.0 + INVARIANT_EPSILON) {
+        return Err(InvariantViolation::AngularAdjustmentAboveManeuverability { applied, maneuverability });
+    }
+    let recorded = after.distance_traveled - before.distance_traveled;
+    let displacement = euclidean_distance(&before.position, &after.position);
+    if (recorded - displacement).abs() > INVARIANT_EPSILON * displacement.max(1.0) {
+        return Err(InvariantViolation::DistanceTraveledMismatch { recorded, displacement });
+    }
+    if after.time <= before.time {
+        return Err(InvariantViolation::TimeNotIncreasing { before: before.time, after: after.time });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+    use crate::simulation::{Simulation, SimulationBuilder};
+    use crate::vehicle::VehicleType;
+
+    fn sim() -> Simulation {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        SimulationBuilder::new(map, VehicleType::Standard)
+            .start_position(Point::new(300.0, 100.0))
+            .start_angle(Radians(1.0))
+            .max_time(10.0)
+            .build()
+    }
+
+    type Corruption = fn(&mut Simulation);
+
+    /// Panic message of a step corrupted by `corrupt` after it moved the vehicle
+    fn violation(corrupt: Corruption) -> String {
+        let mut sim = sim();
+        sim.step();
+        sim.corrupt_step = Some(corrupt);
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| sim.step())).unwrap_err();
+        panic.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_uncorrupted_runs_keep_every_invariant() {
+        for vehicle_type in VehicleType::ALL {
+            let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+            let mut sim = SimulationBuilder::new(map, vehicle_type).velocity_control(true).max_time(60.0).seed(3).build();
+            sim.run();
+            assert!(sim.step_index > 0);
+        }
+    }
+
+    #[test]
+    fn test_each_corruption_fires_its_invariant() {
+        let cases: [(Corruption, &str); 7] = [
+            (|sim| sim.vehicle.state.angle = Radians(4.0), "outside [-π, π]"),
+            (|sim| sim.vehicle.state.velocity = -1.0, "Velocity -1 outside"),
+            (|sim| sim.vehicle.state.velocity = 1e3, "Velocity 1000 outside"),
+            (|sim| sim.vehicle.state.position.x = f64::INFINITY, "Non-finite position"),
+            (|sim| sim.trajectory.last_mut().unwrap().angular_adjustment = Some(720.0), "above the maneuverability"),
+            (|sim| sim.vehicle.distance_traveled = 0.0, "went down"),
+            (|sim| sim.vehicle.distance_traveled += 5.0, "for a displacement of"),
+        ];
+        for (corrupt, expected) in cases {
+            let message = violation(corrupt);
+            assert!(message.contains(expected), "expected {:?} in {:?}", expected, message);
+            assert!(message.contains("Vehicle {"), "state missing from {:?}", message);
+        }
+        assert!(violation(|sim| sim.time = 0.0).contains("Time went from 0.05 to 0"));
+    }
+
+    #[test]
+    fn test_steps_that_do_not_move_skip_the_motion_invariants() {
+        let before = StepSnapshot { step_index: 3, time: 0.15, position: Point::new(10.0, 10.0), distance_traveled: 2.0 };
+        let state = VehicleState { position: Point::new(10.0, 10.0), angle: Radians(0.5), velocity: 6.0 };
+        let characteristics = crate::vehicle::create_vehicle_preset(VehicleType::Agile);
+
+        assert_eq!(check_step(&before, &before, &state, &characteristics, None), Ok(()));
+        assert_eq!(
+            check_step(&before, &before, &state, &characteristics, Some(0.1)),
+            Err(InvariantViolation::TimeNotIncreasing { before: 0.15, after: 0.15 })
+        );
+    }
+}
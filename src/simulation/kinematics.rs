@@ -0,0 +1,139 @@
+// Kinematics: the vehicle state update of one time step, shared by
+// `Simulation::step` and the single-step API
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{clamp, normalize_angle, Point};
+use crate::units::Radians;
+use crate::vehicle::{VehicleCharacteristics, VehicleState};
+
+/// Controller command applied over one time step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ControlOutput {
+    /// Heading rate, radians per second
+    pub angular_adjustment: Radians,
+    /// Velocity change (units/s²), `None` keeps the velocity constant
+    pub acceleration: Option<f64>,
+}
+
+impl ControlOutput {
+    /// Command limited to the vehicle maneuverability and max acceleration
+    pub fn clamped(&self, characteristics: &VehicleCharacteristics) -> Self {
+        let maneuverability = Radians(characteristics.maneuverability);
+        let max_acceleration = characteristics.max_acceleration;
+        Self {
+            angular_adjustment: clamp(self.angular_adjustment, -maneuverability, maneuverability),
+            acceleration: self.acceleration.map(|a| clamp(a, -max_acceleration, max_acceleration)),
+        }
+    }
+
+    /// Command with its heading rate moved from `previous_rate` toward the commanded
+    /// one by at most `max_angular_acceleration * dt`, unchanged without the limit
+    pub fn slewed(&self, previous_rate: Radians, characteristics: &VehicleCharacteristics, dt: f64) -> Self {
+        let Some(max_angular_acceleration) = characteristics.max_angular_acceleration else {
+            return *self;
+        };
+        let max_change = Radians(max_angular_acceleration * dt);
+        Self {
+            angular_adjustment: previous_rate + clamp(self.angular_adjustment - previous_rate, -max_change, max_change),
+            ..*self
+        }
+    }
+}
+
+/// State after applying `control` for `dt` seconds
+///
+/// The command is clamped to the vehicle limits first. The heading turns, then
+/// the velocity changes within `[0, max_velocity]`, then the vehicle moves
+/// along the new heading at the new velocity.
+pub fn advance_state(
+    state: &VehicleState,
+    control: &ControlOutput,
+    characteristics: &VehicleCharacteristics,
+    dt: f64,
+) -> VehicleState {
+    let control = control.clamped(characteristics);
+
+    let angle = normalize_angle(state.angle + control.angular_adjustment * dt);
+    let velocity = match control.acceleration {
+        Some(acceleration) => clamp(state.velocity + acceleration * dt, 0.0, characteristics.max_velocity),
+        None => state.velocity,
+    };
+    let position = Point::new(
+        state.position.x + velocity * angle.cos() * dt,
+        state.position.y + velocity * angle.sin() * dt,
+    );
+
+    VehicleState { position, angle, velocity }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::euclidean_distance;
+
+    fn characteristics() -> VehicleCharacteristics {
+        VehicleCharacteristics {
+            size: 10.0,
+            maneuverability: 1.0,
+            max_velocity: 100.0,
+            max_acceleration: 10.0,
+            cruise_fraction: 0.1,
+            max_angular_acceleration: None,
+        }
+    }
+
+    fn state(velocity: f64) -> VehicleState {
+        VehicleState { position: Point::new(0.0, 0.0), angle: Radians(0.3), velocity }
+    }
+
+    #[test]
+    fn test_straight_line_moves_velocity_times_dt() {
+        let start = state(20.0);
+        let next = advance_state(&start, &ControlOutput::default(), &characteristics(), 0.05);
+
+        assert!((euclidean_distance(&start.position, &next.position) - 20.0 * 0.05).abs() < 1e-12);
+        assert_eq!(next.angle, start.angle);
+        assert_eq!(next.velocity, start.velocity);
+    }
+
+    #[test]
+    fn test_constant_turn_rate_traces_a_circle() {
+        let (velocity, rate, dt) = (10.0, 0.2, 0.01);
+        let control = ControlOutput { angular_adjustment: Radians(rate), acceleration: None };
+        let steps = (2.0 * std::f64::consts::PI / rate / dt).round() as usize;
+
+        let mut current = state(velocity);
+        let mut points = Vec::with_capacity(steps);
+        for _ in 0..steps {
+            current = advance_state(&current, &control, &characteristics(), dt);
+            points.push(current.position.clone());
+        }
+
+        // One full revolution, the centroid of the points is the center
+        let center = Point::new(
+            points.iter().map(|p| p.x).sum::<f64>() / steps as f64,
+            points.iter().map(|p| p.y).sum::<f64>() / steps as f64,
+        );
+        let radius = velocity / rate;
+        for point in &points {
+            assert!((euclidean_distance(point, &center) - radius).abs() < 0.01 * radius);
+        }
+    }
+
+    #[test]
+    fn test_commands_are_clamped_to_the_vehicle_limits() {
+        let limits = characteristics();
+        let start = state(99.0);
+        let control = ControlOutput { angular_adjustment: Radians(5.0), acceleration: Some(50.0) };
+        let next = advance_state(&start, &control, &limits, 0.1);
+
+        assert!((next.angle - (start.angle + Radians(limits.maneuverability * 0.1))).abs().0 < 1e-12);
+        assert_eq!(next.velocity, limits.max_velocity);
+
+        let braking = ControlOutput { angular_adjustment: Radians(-5.0), acceleration: Some(-50.0) };
+        let next = advance_state(&state(0.5), &braking, &limits, 0.1);
+        assert!((next.angle.0 - (0.3 - limits.maneuverability * 0.1)).abs() < 1e-12);
+        assert_eq!(next.velocity, 0.0);
+    }
+}
@@ -0,0 +1,214 @@
+// Schema versions of saved results: files recorded by older versions are
+// upgraded in JSON form, one step per version, before being read
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::DEFAULT_VELOCITY_FRACTION;
+use crate::error::{FuzzyNavError, SerializationError};
+use crate::vehicle::VehicleType;
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Oldest readable schema version, that of files recorded before `schema_version` existed
+pub const OLDEST_SCHEMA_VERSION: u32 = 0;
+
+/// One upgrade step, from the version at its index to the next
+type Migration = fn(&mut Value);
+
+/// Steps of simulation results, single or multi-vehicle
+const RESULT_MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [result_v0_to_v1, result_v1_to_v2];
+
+/// Steps of benchmark outputs
+const BENCHMARK_MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [benchmark_v0_to_v1, benchmark_v1_to_v2];
+
+fn schema_error(version: u32, message: impl Into<String>) -> FuzzyNavError {
+    SerializationError::Schema { version, message: message.into() }.into()
+}
+
+/// Version a document declares, `OLDEST_SCHEMA_VERSION` when it has none
+pub fn schema_version(document: &Value) -> Result<u32, FuzzyNavError> {
+    match document.get("schema_version") {
+        None => Ok(OLDEST_SCHEMA_VERSION),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                let message = format!("invalid schema_version {}", version);
+                SerializationError::Json(<serde_json::Error as serde::de::Error>::custom(message)).into()
+            }),
+    }
+}
+
+fn upgrade(mut document: Value, migrations: &[Migration]) -> Result<(u32, Value), FuzzyNavError> {
+    let version = schema_version(&document)?;
+    if version > SCHEMA_VERSION {
+        return Err(schema_error(version, "written by a newer version"));
+    }
+    if !document.is_object() {
+        return Err(schema_error(version, "not a JSON object"));
+    }
+    for migration in &migrations[version as usize..] {
+        migration(&mut document);
+    }
+    document["schema_version"] = SCHEMA_VERSION.into();
+    Ok((version, document))
+}
+
+/// Upgrade a simulation result to `SCHEMA_VERSION` and read it as `T`
+///
+/// The upgraded document must match the current structure, errors name the
+/// version the file was recorded with.
+pub fn read_result<T: DeserializeOwned>(document: Value) -> Result<T, FuzzyNavError> {
+    let (version, document) = upgrade(document, &RESULT_MIGRATIONS)?;
+    serde_json::from_value(document).map_err(|e| schema_error(version, e.to_string()))
+}
+
+/// Upgrade a benchmark output to `SCHEMA_VERSION`, still as JSON
+pub fn upgrade_benchmark(document: Value) -> Result<Value, FuzzyNavError> {
+    upgrade(document, &BENCHMARK_MIGRATIONS).map(|(_, document)| document)
+}
+
+/// Insert `key` with `value` when `object` lacks it
+fn fill(object: &mut Value, key: &str, value: Value) {
+    if let Some(object) = object.as_object_mut() {
+        object.entry(key).or_insert(value);
+    }
+}
+
+/// v0 metrics predate the degenerate start flag and the steering smoothness
+/// metrics, they read as a regular start that never chattered
+fn result_v0_to_v1(document: &mut Value) {
+    let mut metrics: Vec<&mut Value> = match document.get_mut("vehicles").and_then(Value::as_array_mut) {
+        Some(vehicles) => vehicles.iter_mut().filter_map(|vehicle| vehicle.get_mut("metrics")).collect(),
+        None => document.get_mut("metrics").into_iter().collect(),
+    };
+    for metrics in metrics.iter_mut() {
+        fill(metrics, "degenerate_start", json!(false));
+        fill(metrics, "steering_reversals_per_minute", json!(0.0));
+        fill(metrics, "heading_jerk", json!(0.0));
+    }
+}
+
+/// v0 configurations ran on a single start heading and a uniform start
+/// distribution, with every rule, and recorded their `velocity_fraction` over
+/// vehicles without a cruise fraction
+fn benchmark_v0_to_v1(document: &mut Value) {
+    let Some(config) = document.get_mut("config") else {
+        return;
+    };
+    fill(config, "headings", json!(["toward_target"]));
+    fill(config, "start_distribution", json!("uniform"));
+    fill(config, "disabled_rules", json!([]));
+    fill(config, "control_cache", Value::Null);
+    fill(config, "buckets", Value::Null);
+    fill(config, "reroll_degenerate", json!(false));
+    fill(config, "include_degenerate_arrivals", json!(false));
+    if let Some(vehicles) = config.get_mut("vehicles").and_then(Value::as_array_mut) {
+        for characteristics in vehicles.iter_mut().filter_map(|vehicle| vehicle.get_mut("characteristics")) {
+            fill(characteristics, "cruise_fraction", json!(DEFAULT_VELOCITY_FRACTION));
+        }
+    }
+}
+
+/// Replace the `vehicle_type` label of `object` by the id of the type it names,
+/// labels that name no type are kept
+fn relabel_vehicle_type(object: &mut Value) {
+    let Some(label) = object.get_mut("vehicle_type") else {
+        return;
+    };
+    if let Some(vehicle_type) = label.as_str().and_then(VehicleType::parse) {
+        *label = json!(vehicle_type.id());
+    }
+}
+
+/// Every element of the array at `key` of `object`
+fn elements<'a>(object: &'a mut Value, key: &str) -> impl Iterator<Item = &'a mut Value> {
+    object.get_mut(key).and_then(Value::as_array_mut).into_iter().flatten()
+}
+
+/// v1 results labeled vehicles by display name ("Barco"), v2 by the type id ("heavy")
+fn result_v1_to_v2(document: &mut Value) {
+    relabel_vehicle_type(document);
+    elements(document, "vehicles").for_each(relabel_vehicle_type);
+}
+
+/// v1 outputs labeled configured vehicles by API identifier ("Heavy") and
+/// runs and statistics by display name ("Barco"), v2 all of them by type id
+fn benchmark_v1_to_v2(document: &mut Value) {
+    if let Some(config) = document.get_mut("config") {
+        elements(config, "vehicles").for_each(relabel_vehicle_type);
+    }
+    for iteration in elements(document, "iterations") {
+        elements(iteration, "vehicles").for_each(relabel_vehicle_type);
+    }
+    elements(document, "aggregate").for_each(relabel_vehicle_type);
+    elements(document, "bucket_stats").for_each(relabel_vehicle_type);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::benchmark::BenchmarkConfig;
+    use crate::simulation::MultiVehicleSimulationResult;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_older_and_current_result_fixtures_load_identically() {
+        let current = MultiVehicleSimulationResult::from_json_file(fixture("result_v2.json")).unwrap();
+        let types: Vec<&str> = current.vehicles.iter().map(|vehicle| vehicle.vehicle_type.as_str()).collect();
+        assert_eq!(types, ["heavy", "agile"]);
+
+        for name in ["result_v0.json", "result_v1.json"] {
+            let older = MultiVehicleSimulationResult::from_json_file(fixture(name)).unwrap();
+            assert_eq!(older.schema_version, SCHEMA_VERSION);
+            assert_eq!(serde_json::to_value(&older).unwrap(), serde_json::to_value(&current).unwrap(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_older_and_current_benchmark_fixtures_load_identically() {
+        let read = |name: &str| BenchmarkConfig::from_results_json(&std::fs::read_to_string(fixture(name)).unwrap()).unwrap();
+        let v0 = read("benchmark_v0.json");
+
+        assert_eq!(v0, read("benchmark_v1.json"));
+        assert_eq!(v0, read("benchmark_v2.json"));
+        assert_eq!(v0.velocity_fraction, Some(DEFAULT_VELOCITY_FRACTION));
+    }
+
+    #[test]
+    fn test_v1_benchmark_labels_become_type_ids() {
+        let v1 = json!({
+            "schema_version": 1,
+            "config": { "vehicles": [{ "vehicle_type": "UltraAgile" }] },
+            "iterations": [{ "vehicles": [{ "vehicle_type": "Barco" }, { "vehicle_type": "Lancha" }] }],
+            "aggregate": [{ "vehicle_type": "Ultra-Agile" }],
+            "bucket_stats": [{ "vehicle_type": "Avión" }, { "vehicle_type": "custom" }],
+        });
+        let v2 = upgrade_benchmark(v1).unwrap();
+
+        assert_eq!(v2["config"]["vehicles"][0]["vehicle_type"], "ultra_agile");
+        assert_eq!(v2["iterations"][0]["vehicles"][0]["vehicle_type"], "heavy");
+        assert_eq!(v2["iterations"][0]["vehicles"][1]["vehicle_type"], "standard");
+        assert_eq!(v2["aggregate"][0]["vehicle_type"], "ultra_agile");
+        assert_eq!(v2["bucket_stats"][0]["vehicle_type"], "agile");
+        assert_eq!(v2["bucket_stats"][1]["vehicle_type"], "custom");
+    }
+
+    #[test]
+    fn test_errors_state_the_file_version_and_the_supported_range() {
+        let supported = format!("versions {} to {}", OLDEST_SCHEMA_VERSION, SCHEMA_VERSION);
+
+        let newer = json!({ "schema_version": SCHEMA_VERSION + 1, "vehicles": [], "total_simulation_time": 0.0 });
+        let error = read_result::<MultiVehicleSimulationResult>(newer).unwrap_err().to_string();
+        assert!(error.contains(&format!("version {}", SCHEMA_VERSION + 1)) && error.contains(&supported), "{}", error);
+
+        let malformed = json!({ "vehicles": [{ "vehicle_type": "Barco" }], "total_simulation_time": 0.0 });
+        let error = read_result::<MultiVehicleSimulationResult>(malformed).unwrap_err().to_string();
+        assert!(error.contains("version 0") && error.contains(&supported), "{}", error);
+    }
+}
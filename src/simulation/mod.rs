@@ -1,140 +1,948 @@
 // Simulation module - Main simulation loop and physics engine
 
-use crate::map::{clamp, compute_angular_error_with_arrival, euclidean_distance, normalize_angle, Map, Point};
-use crate::navigation::NavigationController;
-use crate::vehicle::{create_vehicle_preset, Vehicle, VehicleType};
+use crate::build_info::BuildMeta;
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::map::{ApproachGeometry, APPROACH_START, euclidean_distance, normalize_angle, turn_radius, HeadingDistribution, Map, Point, StartDistribution};
+use crate::navigation::{build_observation, Controller, DistanceScale, NavigationController, Quantization, SharedControllers, RULE_COUNT};
+use crate::units::{Degrees, Radians};
+use crate::vehicle::{create_vehicle_preset, Vehicle, VehicleCharacteristics, VehicleType};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
 
-// Conditional printing macro - only prints when CLI feature is enabled
-#[cfg(feature = "cli")]
-macro_rules! sim_println {
-    ($($arg:tt)*) => {
-        println!($($arg)*)
-    };
-}
+pub mod benchmark;
+pub mod cancel;
+pub mod convergence;
+pub mod criteria;
+pub mod dropout;
+pub mod dubins;
+pub mod formation;
+pub mod fuzz;
+pub mod invariants;
+pub mod kinematics;
+pub mod migrations;
+pub mod preflight;
+pub mod reachability;
+pub mod scenario;
+pub mod stability;
+pub mod stream;
+pub mod summary;
+// Sleeping needs a clock, which wasm32-unknown-unknown does not provide
+#[cfg(not(target_arch = "wasm32"))]
+pub mod realtime;
 
-#[cfg(not(feature = "cli"))]
-macro_rules! sim_println {
-    ($($arg:tt)*) => {};
-}
+pub use cancel::{CancelOnDrop, CancelToken};
+pub use convergence::{Convergence, ConvergenceReport};
+pub use criteria::ArrivalCriteria;
+pub use dropout::{Dropout, DropoutConfig, DropoutWindow};
+pub use dubins::Pose;
+pub use formation::Formation;
+use invariants::StepSnapshot;
+pub use kinematics::ControlOutput;
+pub use migrations::SCHEMA_VERSION;
+pub use preflight::{PreflightFinding, PreflightReport};
+pub use scenario::{Scenario, ScenarioVehicle};
+pub use stream::{StreamRecord, TrajectorySink, TrajectoryStream};
+pub use summary::{Language, SummaryOptions};
 
 /// Snapshot of vehicle state at a given time
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The optional control-signal fields describe what the controller aimed at
+/// during the step that produced this point. They are absent on the arrival
+/// point and in results recorded before they existed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TrajectoryPoint {
     pub t: f64,
     pub x: f64,
     pub y: f64,
-    pub angle: f64,
+    pub angle: Degrees,
     pub velocity: f64,
     pub distance_to_target: f64,
+
+    /// Virtual approach point the controller steered to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approach_x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approach_y: Option<f64>,
+    /// Heading towards the approach point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desired_heading: Option<Degrees>,
+    /// Applied angular adjustment, the controller output clamped to the vehicle maneuverability (degrees/second)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angular_adjustment: Option<f64>,
+    /// Raw controller output before clamping (degrees/second)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commanded_adjustment: Option<f64>,
+    /// Measured heading rate over the step (degrees/second)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angular_velocity: Option<f64>,
+    /// How far the steered angular error is from the raw bearing to the target (degrees), see `NavigationInputs::approach_bias`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approach_bias: Option<Degrees>,
+    /// Firing strength of every controller rule, only when `record_rule_activations` is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_activations: Option<Vec<f64>>,
 }
 
 /// Complete simulation result for export
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationResult {
+    /// Layout version of the saved result, see `migrations`
+    #[serde(default)]
+    pub schema_version: u32,
     pub vehicle_type: String,
     pub trajectory: Vec<TrajectoryPoint>,
     pub metrics: SimulationMetrics,
+    /// Target the vehicle navigated to, absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Point>,
+    /// Build that produced the result, absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<BuildMeta>,
+}
+
+/// Why a simulation stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminationReason {
+    Arrived,
+    Timeout,
+    /// Position, angle or velocity became NaN or infinite
+    NumericalError,
+    /// Hit the step cap before `max_time`, a time step too small to ever reach it
+    StepLimit,
+    /// Stopped early through its `CancelToken`, the result holds the run so far
+    Cancelled,
 }
 
 /// Performance metrics
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Angles here are statistics aggregated over runs, plain numbers in degrees
+/// named as such, unlike the typed angles of `TrajectoryPoint`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SimulationMetrics {
     pub success: bool,
     pub arrival_time: Option<f64>,
     pub distance_traveled: f64,
+    /// Difference between the required and the final heading (degrees)
     pub final_angle_error: f64,
     pub final_distance_to_target: f64,
+    /// Absent while the simulation is still running and in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination: Option<TerminationReason>,
+    /// Arrived before the first step, the start already met the arrival criteria
+    #[serde(default)]
+    pub degenerate_start: bool,
+    /// Sign changes of the applied angular adjustment per simulated minute, high when the steering chatters
+    #[serde(default)]
+    pub steering_reversals_per_minute: f64,
+    /// Mean absolute second difference of the heading between steps (degrees), a jerk proxy
+    #[serde(default)]
+    pub heading_jerk: f64,
+    /// Steps in which no controller rule fired, the adjustment fell back to the output midpoint (straight ahead)
+    #[serde(default)]
+    pub steps_with_no_rule_fired: usize,
+    /// `steps_with_no_rule_fired` as a percentage of the simulated steps
+    #[serde(default)]
+    pub no_rule_fired_percent: f64,
+
+    // Velocity profile, only with velocity control (None at constant velocity)
+    /// Seconds spent above `HIGH_VELOCITY_FRACTION` of the max velocity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_above_high_velocity: Option<f64>,
+    /// Time-weighted mean velocity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_velocity: Option<f64>,
+    /// Also None when the vehicle did not arrive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrival_velocity: Option<f64>,
+    /// Sign changes of the acceleration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acceleration_reversals: Option<usize>,
+
+    /// Arrival criteria the run was judged by, absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criteria: Option<ArrivalCriteria>,
+    /// Step cap the run was held to, absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<usize>,
+    /// Start velocity from the cruise fraction (units/second), absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cruise_velocity: Option<f64>,
+    /// Seconds the controller ran on stale observations, only with a dropout config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dropout_seconds_total: Option<f64>,
+    /// When the controller ran on stale observations, empty without dropouts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dropout_windows: Vec<DropoutWindow>,
+    /// Largest approach bias of the run (degrees), absent without steps and in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_approach_bias_deg: Option<f64>,
+    /// Mean approach bias over the steps within `APPROACH_START` of the target (degrees), absent when never that close
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean_approach_bias_deg_inside_corridor: Option<f64>,
+    /// Time of the shortest (Dubins) path from the start pose to the target pose at cruise velocity, see `dubins`
+    ///
+    /// Absent when the vehicle cannot move and in results recorded before it existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimal_time_lower_bound: Option<f64>,
+    /// `arrival_time` over `optimal_time_lower_bound`, only for arrived runs
+    ///
+    /// Can fall below 1: arrival accepts a tolerance around the target pose
+    /// and velocity control can go faster than cruise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_optimality_ratio: Option<f64>,
+
+    // Approach phases, arrival time split into getting near the target and aligning with it
+    /// First time within `APPROACH_START` of the target, absent when never that close
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transit_time: Option<f64>,
+    /// `arrival_time - transit_time`, only for arrived runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment_time: Option<f64>,
+    /// First time the heading error was below the angle tolerance, wherever the vehicle was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_aligned_time: Option<f64>,
+    /// Entries into `APPROACH_START` of the target, a start already inside counts as one
+    #[serde(default)]
+    pub alignment_attempts: usize,
 }
 
 /// Result for a single vehicle in multi-vehicle simulation
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VehicleResult {
+    /// Stable identifier, the scenario label or `<type>-<n>` (see `VehicleType::default_id`)
+    #[serde(default)]
+    pub id: String,
     pub vehicle_type: String,
     pub trajectory: Vec<TrajectoryPoint>,
     pub metrics: SimulationMetrics,
+    /// Target this vehicle navigated to, its berth in a formation; None when not recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Point>,
 }
 
 /// Complete multi-vehicle simulation result
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultiVehicleSimulationResult {
+    /// Layout version of the saved result, see `migrations`
+    #[serde(default)]
+    pub schema_version: u32,
     pub vehicles: Vec<VehicleResult>,
     pub total_simulation_time: f64,
+    /// Approach corridor of the target, only when the scenario asked for it with `include_geometry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approach_geometry: Option<ApproachGeometry>,
+    /// Build that produced the result, absent in results recorded before it existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<BuildMeta>,
+}
+
+impl MultiVehicleSimulationResult {
+    /// Time between the first and the last arrival of the fleet, None unless every vehicle arrived
+    pub fn max_arrival_time_spread(&self) -> Option<f64> {
+        let arrivals: Option<Vec<f64>> = self.vehicles.iter().map(|v| v.metrics.arrival_time).collect();
+        let arrivals = arrivals.filter(|arrivals| !arrivals.is_empty())?;
+        let first = arrivals.iter().copied().fold(f64::INFINITY, f64::min);
+        let last = arrivals.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Some(last - first)
+    }
+}
+
+/// Applied adjustments this small (rad/s) are numerical noise around zero and have no sign
+const STEERING_DEADBAND: f64 = 1e-6;
+
+/// Velocity changes this small (units/s per step) count as constant velocity
+const ACCELERATION_DEADBAND: f64 = 1e-9;
+
+/// Fraction of the max velocity above which the vehicle counts as running fast
+pub const HIGH_VELOCITY_FRACTION: f64 = 0.8;
+
+/// Percentage of steps without any rule fired above which the rule base is reported as not covering the run
+pub const NO_RULE_FIRED_WARNING_PERCENT: f64 = 5.0;
+
+/// Running chattering measures of the applied steering, updated by every `Simulation::step`
+#[derive(Debug, Clone, Default)]
+pub struct ChatterTracker {
+    /// Sign of the last applied adjustment outside the deadband
+    last_sign: f64,
+    sign_changes: usize,
+    last_heading_change: Option<f64>,
+    jerk_sum: f64,
+    jerk_samples: usize,
+}
+
+impl ChatterTracker {
+    /// Record one step: the applied angular adjustment (per second) and the heading change it produced
+    pub fn record(&mut self, applied_adjustment: Radians, heading_change: Radians) {
+        let (applied_adjustment, heading_change) = (applied_adjustment.0, heading_change.0);
+        if applied_adjustment.abs() > STEERING_DEADBAND {
+            let sign = applied_adjustment.signum();
+            if self.last_sign != 0.0 && sign != self.last_sign {
+                self.sign_changes += 1;
+            }
+            self.last_sign = sign;
+        }
+
+        if let Some(last) = self.last_heading_change {
+            self.jerk_sum += (heading_change - last).abs();
+            self.jerk_samples += 1;
+        }
+        self.last_heading_change = Some(heading_change);
+    }
+
+    pub fn sign_changes(&self) -> usize {
+        self.sign_changes
+    }
+
+    /// Sign changes per minute over `elapsed` seconds, 0 before any time has passed
+    pub fn sign_changes_per_minute(&self, elapsed: f64) -> f64 {
+        if elapsed > 0.0 { self.sign_changes as f64 * 60.0 / elapsed } else { 0.0 }
+    }
+
+    /// Mean absolute second difference of the heading in degrees, 0 with fewer than two steps
+    pub fn heading_jerk(&self) -> f64 {
+        if self.jerk_samples == 0 {
+            return 0.0;
+        }
+        (self.jerk_sum / self.jerk_samples as f64).to_degrees()
+    }
+}
+
+/// Running approach bias measures, updated by every `Simulation::step`
+#[derive(Debug, Clone, Default)]
+pub struct ApproachBiasTracker {
+    max: Option<f64>,
+    corridor_sum: f64,
+    corridor_steps: usize,
+}
+
+impl ApproachBiasTracker {
+    /// Record the bias of one step at `distance_to_target`
+    pub fn record(&mut self, bias: Radians, distance_to_target: f64) {
+        let bias = bias.0;
+        self.max = Some(self.max.map_or(bias, |max| max.max(bias)));
+        if distance_to_target <= APPROACH_START {
+            self.corridor_sum += bias;
+            self.corridor_steps += 1;
+        }
+    }
+
+    /// Largest bias in degrees, None before any step
+    pub fn max_deg(&self) -> Option<f64> {
+        self.max.map(f64::to_degrees)
+    }
+
+    /// Mean bias in degrees over the steps within `APPROACH_START`, None without such steps
+    pub fn mean_inside_corridor_deg(&self) -> Option<f64> {
+        (self.corridor_steps > 0).then(|| (self.corridor_sum / self.corridor_steps as f64).to_degrees())
+    }
+}
+
+/// Running approach phase measures, updated by every `Simulation::step`
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTracker {
+    transit_time: Option<f64>,
+    first_aligned_time: Option<f64>,
+    inside_corridor: bool,
+    corridor_entries: usize,
+}
+
+impl PhaseTracker {
+    /// Record the state checked at `time`, `aligned` when its heading error is below the angle tolerance
+    pub fn record(&mut self, time: f64, distance_to_target: f64, aligned: bool) {
+        let inside = distance_to_target <= APPROACH_START;
+        if inside && !self.inside_corridor {
+            self.corridor_entries += 1;
+            self.transit_time.get_or_insert(time);
+        }
+        self.inside_corridor = inside;
+        if aligned {
+            self.first_aligned_time.get_or_insert(time);
+        }
+    }
+
+    /// First time within `APPROACH_START`, None when never that close
+    pub fn transit_time(&self) -> Option<f64> {
+        self.transit_time
+    }
+
+    /// First time within the angle tolerance, None when never aligned
+    pub fn first_aligned_time(&self) -> Option<f64> {
+        self.first_aligned_time
+    }
+
+    /// Entries into `APPROACH_START`, each a new attempt at the final alignment
+    pub fn corridor_entries(&self) -> usize {
+        self.corridor_entries
+    }
+}
+
+/// Running velocity-profile measures, updated by every `Simulation::step`
+#[derive(Debug, Clone, Default)]
+pub struct VelocityTracker {
+    elapsed: f64,
+    time_above_high: f64,
+    velocity_time_sum: f64,
+    /// Sign of the last velocity change outside the deadband
+    last_sign: f64,
+    reversals: usize,
+}
+
+impl VelocityTracker {
+    /// Record one step of `dt` seconds that took the velocity from `previous` to `current`
+    pub fn record(&mut self, previous: f64, current: f64, dt: f64, max_velocity: f64) {
+        self.elapsed += dt;
+        self.velocity_time_sum += current * dt;
+        if current > HIGH_VELOCITY_FRACTION * max_velocity {
+            self.time_above_high += dt;
+        }
+
+        let change = current - previous;
+        if change.abs() > ACCELERATION_DEADBAND {
+            let sign = change.signum();
+            if self.last_sign != 0.0 && sign != self.last_sign {
+                self.reversals += 1;
+            }
+            self.last_sign = sign;
+        }
+    }
+
+    pub fn time_above_high_velocity(&self) -> f64 {
+        self.time_above_high
+    }
+
+    /// Time-weighted mean velocity, 0 before the first step
+    pub fn avg_velocity(&self) -> f64 {
+        if self.elapsed > 0.0 { self.velocity_time_sum / self.elapsed } else { 0.0 }
+    }
+
+    pub fn acceleration_reversals(&self) -> usize {
+        self.reversals
+    }
 }
 
 /// Main simulation controller
-pub struct Simulation {
+///
+/// Steered by the fuzzy `NavigationController` unless another `Controller` is
+/// swapped in with `with_controller`.
+pub struct Simulation<C: Controller = NavigationController> {
+    /// Identifier of the vehicle in results, see `VehicleResult::id`
+    pub id: String,
     pub map: Map,
     pub vehicle: Vehicle,
-    pub controller: NavigationController,
+    pub controller: C,
+    /// `step_index * dt`, recomputed every step rather than accumulated so it does not drift
     pub time: f64,
     pub dt: f64,
     pub max_time: f64,
+    /// Steps taken so far
+    pub step_index: usize,
+    /// Hard stop independent of the time, see `step_limit`
+    pub max_steps: usize,
     pub trajectory: Vec<TrajectoryPoint>,
 
-    // Arrival criteria
-    pub distance_threshold: f64,
-    pub angle_threshold: f64,
+    pub criteria: ArrivalCriteria,
+    /// Step the current streak of steps meeting the criteria started at, see `ArrivalCriteria::arrival_hold_steps`
+    pub arrival_streak_start: Option<usize>,
+    /// Start velocity, the constant one unless `velocity_control` is on
+    pub cruise_velocity: f64,
     pub velocity_threshold: f64,
+
+    /// Store per-rule firing strengths on every trajectory point
+    pub record_rule_activations: bool,
+
+    /// Apply the controller velocity adjustment, otherwise the velocity stays constant
+    pub velocity_control: bool,
+
+    /// Set when `step` stops the vehicle for good, arrival, numerical error or cancellation
+    pub termination: Option<TerminationReason>,
+
+    pub chatter: ChatterTracker,
+    pub velocity_profile: VelocityTracker,
+    pub approach_bias: ApproachBiasTracker,
+    pub phases: PhaseTracker,
+
+    /// Heading rate applied over the last step (per second), the actuator limit slews from it
+    pub turn_rate: Radians,
+
+    /// Steps in which the controller reported rule activations and none fired
+    pub steps_with_no_rule_fired: usize,
+
+    /// Streams the trajectory out of memory when set, see `with_stream`
+    pub stream: Option<TrajectoryStream>,
+
+    /// Stops the run at the next step once cancelled, see `with_cancel`
+    pub cancel: Option<CancelToken>,
+
+    /// Freezes the controller observations in random windows when set
+    pub dropout: Option<Dropout>,
+
+    /// Pose before the first step, the start of the optimal path, None until the run starts
+    pub start_pose: Option<Pose>,
+
+    /// Breaks the state after every step, to test the invariants check
+    #[cfg(test)]
+    pub(crate) corrupt_step: Option<fn(&mut Simulation<C>)>,
 }
 
-impl Simulation {
-    /// Create a new simulation with a vehicle type
-    pub fn new(
-        map: Map,
-        vehicle_type: VehicleType,
-        dt: f64,
-        max_time: f64,
-    ) -> Self {
-        let characteristics = create_vehicle_preset(vehicle_type);
-        let initial_pos = map.random_start_position();
-        let initial_angle = map.random_start_angle();
+/// Default time step (seconds)
+pub const DEFAULT_DT: f64 = 0.05;
 
-        let mut vehicle = Vehicle::new(
+/// Default limit of simulated time (seconds)
+pub const DEFAULT_MAX_TIME: f64 = 600.0;
+
+/// Constant velocity as a fraction of max speed before it became per vehicle, see `VehicleCharacteristics::cruise_fraction`
+pub const DEFAULT_VELOCITY_FRACTION: f64 = 0.10;
+
+/// Margin above the configured velocity tolerated by the arrival check
+pub const VELOCITY_THRESHOLD_MARGIN: f64 = 5.0;
+
+/// Draws of a random start that already meets the arrival criteria before keeping it, see `reroll_degenerate`
+pub const START_REROLL_ATTEMPTS: usize = 100;
+
+/// Steps allowed past `ceil(max_time / dt)` before the step cap applies
+pub const STEP_LIMIT_MARGIN: usize = 16;
+
+/// Ceiling of the step cap whatever `max_time` and `dt`, about 14 simulated hours at the default dt
+pub const MAX_STEPS: usize = 1_000_000;
+
+/// Step cap of a run: the steps `max_time` takes at `dt` plus `STEP_LIMIT_MARGIN`, at most `MAX_STEPS`
+///
+/// A time step too small (or a ratio that is not finite) gets `MAX_STEPS`, so
+/// the simulation loops stay bounded even when the time never reaches `max_time`.
+pub fn step_limit(max_time: f64, dt: f64) -> usize {
+    let steps = (max_time / dt).ceil();
+    if !steps.is_finite() || steps >= MAX_STEPS as f64 {
+        return MAX_STEPS;
+    }
+    (steps.max(0.0) as usize).saturating_add(STEP_LIMIT_MARGIN).min(MAX_STEPS)
+}
+
+/// Reject disabled rule indices the navigation controller does not have
+pub(crate) fn validate_disabled_rules(indices: &[usize]) -> Result<(), SimulationError> {
+    match indices.iter().find(|&&index| index >= RULE_COUNT) {
+        Some(&index) => Err(SimulationError::UnknownRule { index, rules: RULE_COUNT }),
+        None => Ok(()),
+    }
+}
+
+/// Builder for a `Simulation` with optional start state
+///
+/// Position and angle default to random values from the map start zone, the
+/// angle from the `heading` distribution (towards the target by default), and
+/// the velocity to the `cruise_fraction` of the vehicle max velocity.
+/// The random start is drawn from `seed` when set, from the thread RNG
+/// otherwise, or from any generator through `build_with_rng`.
+#[derive(Clone)]
+pub struct SimulationBuilder {
+    id: Option<String>,
+    map: Map,
+    vehicle_type: VehicleType,
+    characteristics: Option<VehicleCharacteristics>,
+    dt: f64,
+    max_time: f64,
+    start_position: Option<Point>,
+    start_angle: Option<Radians>,
+    heading: HeadingDistribution,
+    start_distribution: StartDistribution,
+    iteration: usize,
+    velocity_fraction: Option<f64>,
+    record_rule_activations: bool,
+    velocity_control: bool,
+    criteria: ArrivalCriteria,
+    disabled_rules: Vec<usize>,
+    control_cache: Option<Quantization>,
+    distance_scale: Option<DistanceScale>,
+    shared_controllers: Option<Arc<SharedControllers>>,
+    max_steps: Option<usize>,
+    reroll_degenerate: bool,
+    dropout: Option<DropoutConfig>,
+    seed: Option<u64>,
+}
+
+impl SimulationBuilder {
+    pub fn new(map: Map, vehicle_type: VehicleType) -> Self {
+        Self {
+            id: None,
+            map,
             vehicle_type,
+            characteristics: None,
+            dt: DEFAULT_DT,
+            max_time: DEFAULT_MAX_TIME,
+            start_position: None,
+            start_angle: None,
+            heading: HeadingDistribution::TowardTarget,
+            start_distribution: StartDistribution::Uniform,
+            iteration: 0,
+            velocity_fraction: None,
+            record_rule_activations: false,
+            velocity_control: false,
+            criteria: ArrivalCriteria::strict(),
+            disabled_rules: Vec::new(),
+            control_cache: None,
+            distance_scale: None,
+            shared_controllers: None,
+            max_steps: None,
+            reroll_degenerate: false,
+            dropout: None,
+            seed: None,
+        }
+    }
+
+    /// Identifier of the vehicle in results, the first of its type (`agile-1`) when unset
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Characteristics used instead of the preset of the vehicle type
+    pub fn characteristics(mut self, characteristics: VehicleCharacteristics) -> Self {
+        self.characteristics = Some(characteristics);
+        self
+    }
+
+    pub fn dt(mut self, dt: f64) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    pub fn max_time(mut self, max_time: f64) -> Self {
+        self.max_time = max_time;
+        self
+    }
+
+    pub fn start_position(mut self, position: Point) -> Self {
+        self.start_position = Some(position);
+        self
+    }
+
+    /// Initial heading
+    pub fn start_angle(mut self, angle: Radians) -> Self {
+        self.start_angle = Some(angle);
+        self
+    }
+
+    /// Distribution of the random start heading, ignored when `start_angle` is set
+    pub fn heading(mut self, heading: HeadingDistribution) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    /// Distribution of the random start position, ignored when `start_position` is set
+    pub fn start_distribution(mut self, distribution: StartDistribution) -> Self {
+        self.start_distribution = distribution;
+        self
+    }
+
+    /// Index of this run in a series (a benchmark iteration), stratified starts
+    /// spread consecutive iterations over their bins
+    pub fn iteration(mut self, iteration: usize) -> Self {
+        self.iteration = iteration;
+        self
+    }
+
+    /// Constant velocity as a fraction of the vehicle max velocity (0.10 = 10%), instead of its `cruise_fraction`
+    pub fn velocity_fraction(mut self, fraction: f64) -> Self {
+        self.velocity_fraction = Some(fraction);
+        self
+    }
+
+    pub fn record_rule_activations(mut self, enabled: bool) -> Self {
+        self.record_rule_activations = enabled;
+        self
+    }
+
+    /// Let the controller accelerate and brake, starting from the velocity fraction
+    pub fn velocity_control(mut self, enabled: bool) -> Self {
+        self.velocity_control = enabled;
+        self
+    }
+
+    /// Arrival tolerances, `ArrivalCriteria::strict()` (the exam ±2°) by default
+    pub fn criteria(mut self, criteria: ArrivalCriteria) -> Self {
+        self.criteria = criteria;
+        self
+    }
+
+    /// Controller rules to skip (0-based indices), for ablation studies
+    ///
+    /// Indices past the rule base are ignored, the scenario and benchmark
+    /// validations reject them.
+    pub fn disabled_rules(mut self, indices: Vec<usize>) -> Self {
+        self.disabled_rules = indices;
+        self
+    }
+
+    /// Memoize the controller output on a quantized input grid, off by default
+    /// for exact runs (see `NavigationController::with_cache`)
+    pub fn control_cache(mut self, quantization: Option<Quantization>) -> Self {
+        self.control_cache = quantization;
+        self
+    }
+
+    /// Placement of the controller distance sets, `DistanceScale::for_map` of the map by default
+    /// (the absolute exam breakpoints on the 1000×800 map, scaled to the diagonal of any other)
+    pub fn distance_scale(mut self, distance_scale: DistanceScale) -> Self {
+        self.distance_scale = Some(distance_scale);
+        self
+    }
+
+    /// Take the controller from `controllers` instead of building its rule base
+    pub fn shared_controllers(mut self, controllers: Arc<SharedControllers>) -> Self {
+        self.shared_controllers = Some(controllers);
+        self
+    }
+
+    /// Step cap instead of the one derived from `max_time` and `dt` (see `step_limit`)
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Draw the random start again while it already meets the arrival criteria,
+    /// up to `START_REROLL_ATTEMPTS` times; off by default
+    ///
+    /// Only the random parts are drawn again, a fixed `start_position` and
+    /// `start_angle` stay where they are.
+    pub fn reroll_degenerate(mut self, enabled: bool) -> Self {
+        self.reroll_degenerate = enabled;
+        self
+    }
+
+    /// Sensor dropouts, off by default (see `Dropout`)
+    pub fn dropout(mut self, config: Option<DropoutConfig>) -> Self {
+        self.dropout = config;
+        self
+    }
+
+    /// Seed for the random start, the same seed gives the same start state
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> Simulation {
+        match self.seed {
+            Some(seed) => self.build_with_rng(&mut StdRng::seed_from_u64(seed)),
+            None => self.build_with_rng(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Start position and heading, the fixed ones or drawn from `rng`
+    fn draw_start<R: Rng + ?Sized>(&self, rng: &mut R) -> (Point, Radians) {
+        let position = self.start_position.clone()
+            .unwrap_or_else(|| self.start_distribution.sample(&self.map, self.iteration, rng));
+        let angle = self.start_angle.unwrap_or_else(|| self.heading.sample(&self.map, &position, rng));
+        (position, angle)
+    }
+
+    /// True when a vehicle starting at `position` heading `angle` would arrive before moving
+    fn is_degenerate_start(&self, position: &Point, angle: Radians, velocity: f64) -> bool {
+        let angle_error = self.map.target.angle_error(angle);
+        self.criteria.is_met(position, &self.map.target, angle_error, velocity)
+    }
+
+    /// Build drawing the random start from `rng`, ignoring `seed`
+    pub fn build_with_rng<R: Rng + ?Sized>(self, rng: &mut R) -> Simulation {
+        let characteristics = self.characteristics.clone().unwrap_or_else(|| create_vehicle_preset(self.vehicle_type));
+        let constant_velocity = characteristics.max_velocity * self.velocity_fraction.unwrap_or(characteristics.cruise_fraction);
+
+        let (mut initial_pos, mut initial_angle) = self.draw_start(rng);
+        let random_start = self.start_position.is_none() || self.start_angle.is_none();
+        if self.reroll_degenerate && random_start {
+            for _ in 0..START_REROLL_ATTEMPTS {
+                if !self.is_degenerate_start(&initial_pos, initial_angle, constant_velocity) {
+                    break;
+                }
+                (initial_pos, initial_angle) = self.draw_start(rng);
+            }
+        }
+
+        let mut vehicle = Vehicle::new(
+            self.vehicle_type,
             characteristics.clone(),
             initial_pos,
             initial_angle,
         );
 
-        // Set constant velocity at 10% of max speed for high precision 90° arrival (±2°)
-        let constant_velocity = characteristics.max_velocity * 0.10;
         vehicle.state.velocity = constant_velocity;
 
-        let controller = NavigationController::new(&characteristics);
+        let disabled: Vec<usize> = self.disabled_rules.into_iter().filter(|&index| index < RULE_COUNT).collect();
+        let distance_scale = self.distance_scale.unwrap_or_else(|| DistanceScale::for_map(&self.map));
+        let mut controller = match &self.shared_controllers {
+            Some(controllers) => NavigationController::clone(
+                &controllers.get(&characteristics, &disabled, distance_scale).expect("indices below RULE_COUNT"),
+            ),
+            None => {
+                let mut controller = NavigationController::with_distance_scale(&characteristics, distance_scale);
+                controller.disable_rules(&disabled).expect("indices below RULE_COUNT");
+                controller
+            }
+        };
+        controller.set_cache(self.control_cache);
 
-        Self {
-            map,
+        Simulation {
+            id: self.id.unwrap_or_else(|| self.vehicle_type.default_id(1)),
+            map: self.map,
             vehicle,
             controller,
             time: 0.0,
-            dt,
-            max_time,
+            dt: self.dt,
+            max_time: self.max_time,
+            step_index: 0,
+            max_steps: self.max_steps.unwrap_or_else(|| step_limit(self.max_time, self.dt)),
             trajectory: Vec::new(),
-            distance_threshold: 25.0,  // 25 units
-            angle_threshold: 2f64.to_radians(),  // ±2° tolerance (88-92°) - STRICT
-            velocity_threshold: constant_velocity + 5.0,  // Allow slightly above constant
+            criteria: self.criteria,
+            arrival_streak_start: None,
+            cruise_velocity: constant_velocity,
+            velocity_threshold: constant_velocity + VELOCITY_THRESHOLD_MARGIN,  // Allow slightly above constant
+            record_rule_activations: self.record_rule_activations,
+            velocity_control: self.velocity_control,
+            termination: None,
+            chatter: ChatterTracker::default(),
+            velocity_profile: VelocityTracker::default(),
+            approach_bias: ApproachBiasTracker::default(),
+            phases: PhaseTracker::default(),
+            turn_rate: Radians::ZERO,
+            steps_with_no_rule_fired: 0,
+            stream: None,
+            cancel: None,
+            dropout: self.dropout.map(Dropout::new),
+            start_pose: None,
+            #[cfg(test)]
+            corrupt_step: None,
+        }
+    }
+}
+
+impl Simulation {
+    /// Create a new simulation with a vehicle type, random start and default velocity
+    pub fn new(
+        map: Map,
+        vehicle_type: VehicleType,
+        dt: f64,
+        max_time: f64,
+    ) -> Self {
+        SimulationBuilder::new(map, vehicle_type)
+            .dt(dt)
+            .max_time(max_time)
+            .build()
+    }
+}
+
+impl<C: Controller> Simulation<C> {
+    /// Same simulation steered by `controller` from now on
+    pub fn with_controller<D: Controller>(self, controller: D) -> Simulation<D> {
+        Simulation {
+            id: self.id,
+            map: self.map,
+            vehicle: self.vehicle,
+            controller,
+            time: self.time,
+            dt: self.dt,
+            max_time: self.max_time,
+            step_index: self.step_index,
+            max_steps: self.max_steps,
+            trajectory: self.trajectory,
+            criteria: self.criteria,
+            arrival_streak_start: self.arrival_streak_start,
+            cruise_velocity: self.cruise_velocity,
+            velocity_threshold: self.velocity_threshold,
+            record_rule_activations: self.record_rule_activations,
+            velocity_control: self.velocity_control,
+            termination: self.termination,
+            chatter: self.chatter,
+            velocity_profile: self.velocity_profile,
+            approach_bias: self.approach_bias,
+            phases: self.phases,
+            turn_rate: self.turn_rate,
+            steps_with_no_rule_fired: self.steps_with_no_rule_fired,
+            stream: self.stream,
+            cancel: self.cancel,
+            dropout: self.dropout,
+            start_pose: self.start_pose,
+            #[cfg(test)]
+            corrupt_step: None,
         }
     }
 
-    /// Execute one simulation step
+    /// Same simulation stopping with `TerminationReason::Cancelled` once `cancel` is tripped
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Same simulation writing its trajectory to `stream` in checkpoints
+    ///
+    /// Finalize the run with `finish` to write the last points and the metrics.
+    pub fn with_stream(mut self, mut stream: TrajectoryStream) -> Self {
+        stream.start(MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![self.summary()],
+            total_simulation_time: self.time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        });
+        self.stream = Some(stream);
+        self
+    }
+
     pub fn step(&mut self) {
-        if self.vehicle.has_arrived {
+        if self.step_index == 0 && self.start_pose.is_none() {
+            self.start_pose = Some(self.pose());
+        }
+        let before = self.snapshot();
+        self.advance();
+        #[cfg(test)]
+        if let Some(corrupt) = self.corrupt_step {
+            corrupt(self);
+        }
+        self.check_invariants(&before);
+        if let Some(stream) = &mut self.stream {
+            stream.step(self.time, std::iter::once(&mut self.trajectory));
+        }
+    }
+
+    fn advance(&mut self) {
+        // A run that already ended keeps its reason, late cancellations are ignored
+        if self.termination_reason().is_none() && self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            self.termination = Some(TerminationReason::Cancelled);
+            info!(t = self.time, vehicle = self.vehicle.vehicle_type.id(), "Simulation cancelled");
+            return;
+        }
+
+        if self.vehicle.has_arrived || self.termination.is_some() || self.step_index >= self.max_steps {
+            return;
+        }
+
+        if !self.state_is_finite() {
+            self.termination = Some(TerminationReason::NumericalError);
+            warn!(t = self.time, vehicle = self.vehicle.vehicle_type.id(), "Non-finite vehicle state, simulation stopped");
             return;
         }
 
         // 1. CALCULATE FUZZY INPUTS
-        let distance_to_target = euclidean_distance(
-            &self.vehicle.state.position,
-            &self.map.target.position,
-        );
+        let fresh = build_observation(&self.vehicle.state, &self.map, &self.vehicle.characteristics);
+        let distance_to_target = fresh.distance_to_target;
 
         // 2. CHECK ARRIVAL CONDITION FIRST (before moving)
-        // Vehicle must satisfy BOTH distance and angle requirements to arrive
-        let angle_error = (self.map.target.required_angle - self.vehicle.state.angle).abs();
+        // Vehicle must satisfy every tolerance of the criteria to arrive
+        let angle_error = self.map.target.angle_error(self.vehicle.state.angle);
+
+        // The tolerances must hold for `arrival_hold_steps` steps in a row, a vehicle
+        // grazing the acceptance region for fewer keeps moving
+        self.phases.record(self.time, distance_to_target, angle_error < self.criteria.angle_tolerance.to_radians());
+        let met = self.criteria.is_met(&self.vehicle.state.position, &self.map.target, angle_error, self.vehicle.state.velocity);
+        let streak_start = if met { *self.arrival_streak_start.get_or_insert(self.step_index) } else { self.step_index };
+        if !met {
+            self.arrival_streak_start = None;
+        }
 
-        if distance_to_target < self.distance_threshold && angle_error < self.angle_threshold {
+        if met && self.step_index + 1 - streak_start >= self.criteria.hold_steps() {
             self.vehicle.has_arrived = true;
+            self.termination = Some(TerminationReason::Arrived);
 
             // Record final position before stopping
             self.trajectory.push(TrajectoryPoint {
@@ -144,58 +952,92 @@ impl Simulation {
                 angle: self.vehicle.state.angle.to_degrees(),
                 velocity: self.vehicle.state.velocity,
                 distance_to_target,
+                ..Default::default()
             });
 
-            sim_println!("\n✓ Vehicle arrived successfully at t={:.2}s", self.time);
-            sim_println!("  Distance: {:.2} units, Angle error: {:.1}°", distance_to_target, angle_error.to_degrees());
+            info!(
+                t = self.time,
+                vehicle = self.vehicle.vehicle_type.id(),
+                distance = distance_to_target,
+                angle_error_deg = angle_error.to_degrees().0,
+                "Vehicle arrived"
+            );
             return;
         }
 
-        // 3. CONTINUE NAVIGATION
-        // Use interpolated angular error (navigates to target when far, aligns to 90° when close)
-        let angular_error = compute_angular_error_with_arrival(
-            &self.vehicle.state.position,
-            self.vehicle.state.angle,
-            &self.map.target,
-            distance_to_target,
-        );
-
-        let velocity_relative = self.vehicle.state.velocity / self.vehicle.characteristics.max_velocity;
+        // What the approach point hides from the controller, measured on the fresh inputs
+        let approach_bias = fresh.approach_bias();
+        self.approach_bias.record(approach_bias, distance_to_target);
 
-        // 4. EVALUATE FUZZY CONTROLLER
-        let (angular_adjustment, _velocity_adjustment) =
-            self.controller.compute_control(
-                distance_to_target,
-                angular_error,
-                velocity_relative,
+        // 3. EVALUATE FUZZY CONTROLLER
+        // Steers by the interpolated angular error (navigates to target when far, aligns to 90° when close),
+        // as last observed during a dropout
+        let inputs = match &mut self.dropout {
+            Some(dropout) => dropout.observe(self.time, self.dt, fresh),
+            None => fresh,
+        };
+        let (angular_adjustment, velocity_adjustment, rule_activations) =
+            self.controller.control(
+                inputs.distance_to_target,
+                inputs.angular_error,
+                inputs.velocity_relative,
             );
 
-        // 5. APPLY PHYSICAL CONSTRAINTS
-        let angular_adjustment_clamped = clamp(
-            angular_adjustment,
-            -self.vehicle.characteristics.maneuverability,
-            self.vehicle.characteristics.maneuverability,
-        );
+        // A rule base that fires nothing leaves the output at the midpoint of its
+        // range, the vehicle goes straight whatever the inputs
+        if !rule_activations.is_empty() && rule_activations.iter().all(|&activation| activation <= f64::EPSILON) {
+            if self.steps_with_no_rule_fired == 0 {
+                warn!(
+                    t = self.time,
+                    vehicle = self.vehicle.vehicle_type.id(),
+                    distance = inputs.distance_to_target,
+                    angular_error_deg = inputs.angular_error.to_degrees().0,
+                    velocity_relative = inputs.velocity_relative,
+                    "No controller rule fired, steering straight ahead (later steps are only counted)"
+                );
+            }
+            self.steps_with_no_rule_fired += 1;
+        }
 
-        // 6. UPDATE VEHICLE STATE
-        // Update angle
-        self.vehicle.state.angle += angular_adjustment_clamped * self.dt;
-        self.vehicle.state.angle = normalize_angle(self.vehicle.state.angle);
+        // 4. UPDATE VEHICLE STATE (kinematic model, clamped to the physical limits)
+        // Velocity remains constant unless velocity control is enabled, the turn
+        // rate follows the command no faster than the actuator allows
+        let control = ControlOutput {
+            angular_adjustment,
+            acceleration: self.velocity_control.then_some(velocity_adjustment),
+        };
+        let characteristics = &self.vehicle.characteristics;
+        let applied = control.clamped(characteristics).slewed(self.turn_rate, characteristics, self.dt);
+        let previous = self.vehicle.state.clone();
+        let next = kinematics::advance_state(&previous, &applied, characteristics, self.dt);
+        self.turn_rate = applied.angular_adjustment;
 
-        // Velocity remains constant (no velocity_adjustment applied)
+        let heading_change = normalize_angle(next.angle - previous.angle);
+        let angular_velocity = heading_change / self.dt;
+        self.chatter.record(applied.angular_adjustment, heading_change);
+        self.velocity_profile.record(
+            previous.velocity,
+            next.velocity,
+            self.dt,
+            self.vehicle.characteristics.max_velocity,
+        );
 
-        // 7. UPDATE POSITION (kinematic model)
-        let old_position = self.vehicle.state.position.clone();
-        let new_x = old_position.x + self.vehicle.state.velocity * self.vehicle.state.angle.cos() * self.dt;
-        let new_y = old_position.y + self.vehicle.state.velocity * self.vehicle.state.angle.sin() * self.dt;
+        self.vehicle.state.angle = next.angle;
+        self.vehicle.state.velocity = next.velocity;
+        self.vehicle.update_position(next.position);
 
-        self.vehicle.update_position(Point::new(new_x, new_y));
+        if !self.state_is_finite() {
+            self.termination = Some(TerminationReason::NumericalError);
+            warn!(t = self.time, vehicle = self.vehicle.vehicle_type.id(), "Non-finite vehicle state, simulation stopped");
+            return;
+        }
 
-        // 8. UPDATE TIME
-        self.time += self.dt;
+        // 5. UPDATE TIME
+        self.step_index += 1;
+        self.time = self.step_index as f64 * self.dt;
         self.vehicle.time_elapsed = self.time;
 
-        // 9. RECORD TRAJECTORY POINT
+        // 6. RECORD TRAJECTORY POINT
         self.trajectory.push(TrajectoryPoint {
             t: self.time,
             x: self.vehicle.state.position.x,
@@ -203,100 +1045,1547 @@ impl Simulation {
             angle: self.vehicle.state.angle.to_degrees(),
             velocity: self.vehicle.state.velocity,
             distance_to_target,
+            approach_x: Some(inputs.approach_point.x),
+            approach_y: Some(inputs.approach_point.y),
+            desired_heading: Some(inputs.desired_heading.to_degrees()),
+            angular_adjustment: Some(applied.angular_adjustment.to_degrees().0),
+            commanded_adjustment: Some(angular_adjustment.to_degrees().0),
+            angular_velocity: Some(angular_velocity.to_degrees().0),
+            approach_bias: Some(approach_bias.to_degrees()),
+            rule_activations: if self.record_rule_activations { Some(rule_activations) } else { None },
         });
     }
 
-    /// Run the complete simulation
-    pub fn run(&mut self) -> SimulationResult {
-        sim_println!("\n╔══════════════════════════════════════════════════════╗");
-        sim_println!("║       FUZZY NAVIGATION SIMULATION STARTED           ║");
-        sim_println!("╚══════════════════════════════════════════════════════╝\n");
-
-        sim_println!("Vehicle Type: {} ", self.vehicle.vehicle_type.name());
-        sim_println!("  - Size: {}", self.vehicle.characteristics.size);
-        sim_println!("  - Max Speed: {:.1} units/s", self.vehicle.characteristics.max_velocity);
-        sim_println!("  - Max Acceleration: {:.1} units/s²", self.vehicle.characteristics.max_acceleration);
-        sim_println!("  - Maneuverability: {:.1}°/s\n", self.vehicle.characteristics.maneuverability.to_degrees());
-
-        sim_println!("Map: {}x{}", self.map.width, self.map.height);
-        sim_println!("Target: ({:.1}, {:.1})", self.map.target.position.x, self.map.target.position.y);
-        sim_println!("Required arrival angle: {:.1}°\n", self.map.target.required_angle.to_degrees());
-
-        sim_println!("Starting Position: ({:.1}, {:.1})",
-            self.vehicle.state.position.x,
-            self.vehicle.state.position.y);
-        sim_println!("Starting Angle: {:.1}°\n", self.vehicle.state.angle.to_degrees());
-
-        let _initial_distance = euclidean_distance(
-            &self.vehicle.state.position,
-            &self.map.target.position,
-        );
-        sim_println!("Initial Distance to Target: {:.1} units\n", _initial_distance);
+    fn snapshot(&self) -> StepSnapshot {
+        StepSnapshot {
+            step_index: self.step_index,
+            time: self.time,
+            position: self.vehicle.state.position.clone(),
+            distance_traveled: self.vehicle.distance_traveled,
+        }
+    }
 
-        sim_println!("Running simulation (dt={:.3}s, max_time={:.1}s)...\n", self.dt, self.max_time);
+    /// Panic on a broken physics invariant in debug builds, log it in release ones
+    ///
+    /// Skipped before the first step, a fixed start heading is not normalized,
+    /// and after a numerical error, already a termination of its own.
+    fn check_invariants(&self, before: &StepSnapshot) {
+        if self.step_index == 0 || self.termination == Some(TerminationReason::NumericalError) {
+            return;
+        }
+        let applied_adjustment = (self.step_index > before.step_index)
+            .then(|| self.trajectory.last().and_then(|point| point.angular_adjustment))
+            .flatten()
+            .map(f64::to_radians);
+        let Err(violation) = invariants::check_step(
+            before,
+            &self.snapshot(),
+            &self.vehicle.state,
+            &self.vehicle.characteristics,
+            applied_adjustment,
+        ) else {
+            return;
+        };
+        if cfg!(debug_assertions) {
+            panic!("{} at step {} (t = {}): {:?}", violation, self.step_index, self.time, self.vehicle);
+        }
+        error!(t = self.time, step = self.step_index, vehicle = ?self.vehicle, "{}", violation);
+    }
 
-        let mut step_count = 0;
-        let print_interval = (5.0 / self.dt) as usize; // Print every 5 seconds
+    fn state_is_finite(&self) -> bool {
+        let state = &self.vehicle.state;
+        state.position.x.is_finite()
+            && state.position.y.is_finite()
+            && state.angle.is_finite()
+            && state.velocity.is_finite()
+    }
 
-        while self.time < self.max_time && !self.vehicle.has_arrived {
-            self.step();
-            step_count += 1;
+    /// Why the simulation stopped, `None` while it can still advance
+    pub fn termination_reason(&self) -> Option<TerminationReason> {
+        self.termination
+            .or_else(|| (self.time >= self.max_time).then_some(TerminationReason::Timeout))
+            .or_else(|| (self.step_index >= self.max_steps).then_some(TerminationReason::StepLimit))
+    }
 
-            if step_count % print_interval == 0 {
-                let _dist = euclidean_distance(
-                    &self.vehicle.state.position,
-                    &self.map.target.position,
-                );
-                sim_println!(
-                    "[t={:6.2}s] pos=({:6.1}, {:6.1}) vel={:5.1} dist={:6.1} angle={:6.1}°",
-                    self.time,
-                    self.vehicle.state.position.x,
-                    self.vehicle.state.position.y,
-                    self.vehicle.state.velocity,
-                    _dist,
-                    self.vehicle.state.angle.to_degrees()
-                );
-            }
-        }
+    /// True once the vehicle arrived, the state broke down or the time or step limit is reached
+    pub fn is_finished(&self) -> bool {
+        self.termination_reason().is_some()
+    }
 
-        let final_distance = euclidean_distance(
-            &self.vehicle.state.position,
-            &self.map.target.position,
-        );
-        let final_angle_error = (self.map.target.required_angle - self.vehicle.state.angle).abs();
-
-        let metrics = SimulationMetrics {
-            success: self.vehicle.has_arrived,
-            arrival_time: if self.vehicle.has_arrived {
-                Some(self.time)
-            } else {
-                None
-            },
-            distance_traveled: self.vehicle.distance_traveled,
-            final_angle_error: final_angle_error.to_degrees(),
-            final_distance_to_target: final_distance,
-        };
+    /// Time of the first step of the streak that made the vehicle arrive, None until it arrives
+    fn arrival_time(&self) -> Option<f64> {
+        let start = self.arrival_streak_start.filter(|_| self.vehicle.has_arrived)?;
+        Some(start as f64 * self.dt)
+    }
 
-        sim_println!("\n╔══════════════════════════════════════════════════════╗");
-        sim_println!("║              SIMULATION COMPLETED                    ║");
-        sim_println!("╚══════════════════════════════════════════════════════╝\n");
+    fn pose(&self) -> Pose {
+        Pose { position: self.vehicle.state.position.clone(), angle: self.vehicle.state.angle }
+    }
 
-        sim_println!("Results:");
-        sim_println!("  Success: {}", if metrics.success { "YES ✓" } else { "NO ✗" });
-        if let Some(_t) = metrics.arrival_time {
-            sim_println!("  Arrival Time: {:.2}s", _t);
-        } else {
-            sim_println!("  Status: Did not arrive (timeout at {:.2}s)", self.max_time);
+    /// Share of the simulated steps in which no rule fired, in percent
+    fn no_rule_fired_percent(&self) -> f64 {
+        match self.step_index {
+            0 => 0.0,
+            steps => 100.0 * self.steps_with_no_rule_fired as f64 / steps as f64,
         }
-        sim_println!("  Distance Traveled: {:.2} units", metrics.distance_traveled);
-        sim_println!("  Final Distance to Target: {:.2} units", metrics.final_distance_to_target);
-        sim_println!("  Final Angle Error: {:.2}°", metrics.final_angle_error);
-        sim_println!("  Total Steps: {}", step_count);
+    }
 
-        SimulationResult {
-            vehicle_type: self.vehicle.vehicle_type.name().to_string(),
-            trajectory: self.trajectory.clone(),
-            metrics,
+    /// Approach phase fields of the metrics
+    fn phase_metrics(&self) -> SimulationMetrics {
+        let transit_time = self.phases.transit_time();
+        SimulationMetrics {
+            transit_time,
+            alignment_time: self.arrival_time().zip(transit_time).map(|(arrival, transit)| arrival - transit),
+            first_aligned_time: self.phases.first_aligned_time(),
+            alignment_attempts: self.phases.corridor_entries(),
+            ..Default::default()
         }
     }
+
+    /// Lower bound, optimality ratio and phase fields of the metrics, from the start pose or the current one before the run
+    fn optimality_metrics(&self) -> SimulationMetrics {
+        let start = self.start_pose.clone().unwrap_or_else(|| self.pose());
+        let goal = Pose { position: self.map.target.position.clone(), angle: self.map.target.required_angle };
+        let radius = turn_radius(self.cruise_velocity, self.vehicle.characteristics.maneuverability);
+        let lower_bound = (self.cruise_velocity > 0.0)
+            .then(|| dubins::shortest_path(&start, &goal, radius))
+            .flatten()
+            .map(|path| path.length() / self.cruise_velocity);
+        let ratio = self.arrival_time().zip(lower_bound).filter(|&(_, bound)| bound > 0.0).map(|(time, bound)| time / bound);
+        SimulationMetrics { optimal_time_lower_bound: lower_bound, time_optimality_ratio: ratio, ..self.phase_metrics() }
+    }
+
+    /// Optimality and velocity-profile fields of the metrics, the latter all None at constant velocity
+    fn velocity_metrics(&self) -> SimulationMetrics {
+        if !self.velocity_control {
+            return self.optimality_metrics();
+        }
+        SimulationMetrics {
+            time_above_high_velocity: Some(self.velocity_profile.time_above_high_velocity()),
+            avg_velocity: Some(self.velocity_profile.avg_velocity()),
+            arrival_velocity: self.vehicle.has_arrived.then_some(self.vehicle.state.velocity),
+            acceleration_reversals: Some(self.velocity_profile.acceleration_reversals()),
+            ..self.optimality_metrics()
+        }
+    }
+
+    /// Optimality, velocity-profile and dropout fields of the metrics
+    fn dropout_metrics(&self) -> SimulationMetrics {
+        let Some(dropout) = &self.dropout else {
+            return self.velocity_metrics();
+        };
+        SimulationMetrics {
+            dropout_seconds_total: Some(dropout.seconds_total(self.time)),
+            dropout_windows: dropout.windows(self.time),
+            ..self.velocity_metrics()
+        }
+    }
+
+    /// Compute performance metrics from the current state of the simulation
+    pub fn metrics(&self) -> SimulationMetrics {
+        let success = self.vehicle.has_arrived;
+        let target = &self.map.target;
+
+        // Handle empty trajectory case
+        let (final_distance, final_angle_error) = if let Some(final_point) = self.trajectory.last() {
+            (final_point.distance_to_target, target.angle_error(final_point.angle.to_radians()).to_degrees().0)
+        } else {
+            // If no trajectory points, calculate from current vehicle state
+            let dist = euclidean_distance(&self.vehicle.state.position, &target.position);
+            (dist, target.angle_error(self.vehicle.state.angle).to_degrees().0)
+        };
+
+        SimulationMetrics {
+            success,
+            arrival_time: self.arrival_time(),
+            distance_traveled: self.vehicle.distance_traveled,
+            final_angle_error,
+            final_distance_to_target: final_distance,
+            termination: self.termination_reason(),
+            degenerate_start: success && self.arrival_streak_start == Some(0),
+            steering_reversals_per_minute: self.chatter.sign_changes_per_minute(self.time),
+            heading_jerk: self.chatter.heading_jerk(),
+            steps_with_no_rule_fired: self.steps_with_no_rule_fired,
+            no_rule_fired_percent: self.no_rule_fired_percent(),
+            max_approach_bias_deg: self.approach_bias.max_deg(),
+            mean_approach_bias_deg_inside_corridor: self.approach_bias.mean_inside_corridor_deg(),
+            criteria: Some(self.criteria),
+            max_steps: Some(self.max_steps),
+            cruise_velocity: Some(self.cruise_velocity),
+            ..self.dropout_metrics()
+        }
+    }
+
+    /// Result of this vehicle without its trajectory
+    fn summary(&self) -> VehicleResult {
+        VehicleResult {
+            id: self.id.clone(),
+            vehicle_type: self.vehicle.vehicle_type.id().to_string(),
+            trajectory: Vec::new(),
+            metrics: self.metrics(),
+            target: Some(self.map.target.position.clone()),
+        }
+    }
+
+    /// Result of the run so far, finalizing the stream if any
+    ///
+    /// A streamed run writes its last points and the `Summary` footer and
+    /// returns an empty trajectory, the points are in the sink.
+    pub fn finish(mut self) -> Result<SimulationResult, FuzzyNavError> {
+        let summary = self.summary();
+        if let Some(stream) = self.stream.take() {
+            let result = MultiVehicleSimulationResult {
+                schema_version: SCHEMA_VERSION,
+                vehicles: vec![summary.clone()],
+                total_simulation_time: self.time,
+                approach_geometry: None,
+                meta: Some(BuildMeta::current(1)),
+            };
+            stream.finish(result, std::iter::once(&mut self.trajectory))?;
+            self.trajectory.clear();
+        }
+        Ok(SimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicle_type: summary.vehicle_type,
+            trajectory: self.trajectory,
+            metrics: summary.metrics,
+            target: summary.target,
+            meta: Some(BuildMeta::current(1)),
+        })
+    }
+
+    /// Run the complete simulation
+    ///
+    /// Logs the start and the results at info level and the state every
+    /// 5 simulated seconds at debug level.
+    pub fn run(&mut self) -> SimulationResult {
+        let characteristics = &self.vehicle.characteristics;
+        info!(
+            vehicle = self.vehicle.vehicle_type.id(),
+            size = characteristics.size,
+            max_velocity = characteristics.max_velocity,
+            max_acceleration = characteristics.max_acceleration,
+            maneuverability_deg = characteristics.maneuverability.to_degrees(),
+            "Fuzzy navigation simulation started"
+        );
+        info!(
+            width = self.map.width,
+            height = self.map.height,
+            target_x = self.map.target.position.x,
+            target_y = self.map.target.position.y,
+            required_angle_deg = self.map.target.required_angle.to_degrees().0,
+            "Map"
+        );
+        info!(
+            x = self.vehicle.state.position.x,
+            y = self.vehicle.state.position.y,
+            angle_deg = self.vehicle.state.angle.to_degrees().0,
+            distance = euclidean_distance(&self.vehicle.state.position, &self.map.target.position),
+            dt = self.dt,
+            max_time = self.max_time,
+            "Starting state"
+        );
+
+        let print_interval = ((5.0 / self.dt) as usize).max(1); // Every 5 simulated seconds
+
+        while !self.is_finished() {
+            self.step();
+
+            if self.step_index.is_multiple_of(print_interval) {
+                debug!(
+                    t = self.time,
+                    x = self.vehicle.state.position.x,
+                    y = self.vehicle.state.position.y,
+                    velocity = self.vehicle.state.velocity,
+                    distance = euclidean_distance(&self.vehicle.state.position, &self.map.target.position),
+                    angle_deg = self.vehicle.state.angle.to_degrees().0,
+                    "Progress"
+                );
+            }
+        }
+
+        let metrics = self.metrics();
+
+        info!(
+            success = metrics.success,
+            termination = ?metrics.termination,
+            arrival_time = ?metrics.arrival_time,
+            distance_traveled = metrics.distance_traveled,
+            final_distance = metrics.final_distance_to_target,
+            final_angle_error_deg = metrics.final_angle_error,
+            steps = self.step_index,
+            max_steps = self.max_steps,
+            "Simulation completed"
+        );
+
+        SimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicle_type: self.vehicle.vehicle_type.id().to_string(),
+            trajectory: self.trajectory.clone(),
+            metrics,
+            target: Some(self.map.target.position.clone()),
+            meta: Some(BuildMeta::current(1)),
+        }
+    }
+}
+
+/// Several simulations advanced in lock-step on a shared clock
+pub struct MultiVehicleSimulation {
+    pub simulations: Vec<Simulation>,
+    /// `step_index * dt` of the shared clock
+    pub time: f64,
+    pub dt: f64,
+    pub max_time: f64,
+    pub step_index: usize,
+    /// Hard stop of the shared clock independent of the time, see `step_limit`
+    pub max_steps: usize,
+    /// Streams every trajectory out of memory when set, see `with_stream`
+    pub stream: Option<TrajectoryStream>,
+    /// Stops every vehicle still running at the next step once cancelled, see `with_cancel`
+    pub cancel: Option<CancelToken>,
+}
+
+impl MultiVehicleSimulation {
+    pub fn new(simulations: Vec<Simulation>, dt: f64, max_time: f64) -> Self {
+        Self {
+            simulations,
+            time: 0.0,
+            dt,
+            max_time,
+            step_index: 0,
+            max_steps: step_limit(max_time, dt),
+            stream: None,
+            cancel: None,
+        }
+    }
+
+    /// Same simulation stopping every running vehicle with `TerminationReason::Cancelled` once `cancel` is tripped
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Same simulation writing the trajectories to `stream` in checkpoints
+    ///
+    /// Finalize the run with `finish` to write the last points and the metrics.
+    pub fn with_stream(mut self, mut stream: TrajectoryStream) -> Self {
+        stream.start(self.summary());
+        self.stream = Some(stream);
+        self
+    }
+
+    /// Advance every vehicle that has not arrived yet by one time step
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            for sim in self.simulations.iter_mut().filter(|sim| sim.termination.is_none()) {
+                sim.termination = Some(TerminationReason::Cancelled);
+            }
+            info!(t = self.time, "Simulation cancelled");
+            return;
+        }
+
+        for sim in &mut self.simulations {
+            if sim.termination.is_none() {
+                sim.step();
+            }
+        }
+
+        self.step_index += 1;
+        self.time = self.step_index as f64 * self.dt;
+        if let Some(stream) = &mut self.stream {
+            stream.step(self.time, self.simulations.iter_mut().map(|sim| &mut sim.trajectory));
+        }
+    }
+
+    /// True once every vehicle has stopped (arrival, numerical error or cancellation) or the time or step limit is reached
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.max_time
+            || self.step_index >= self.max_steps
+            || self.simulations.iter().all(|s| s.termination.is_some())
+    }
+
+    pub fn all_arrived(&self) -> bool {
+        self.simulations.iter().all(|s| s.vehicle.has_arrived)
+    }
+
+    pub fn arrived_count(&self) -> usize {
+        self.simulations.iter().filter(|s| s.vehicle.has_arrived).count()
+    }
+
+    /// Step until finished
+    pub fn run(&mut self) {
+        while !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Consume the simulation and collect trajectories and metrics of every vehicle
+    ///
+    /// A streamed run only holds the points since its last checkpoint, finalize it with `finish`.
+    pub fn into_result(self) -> MultiVehicleSimulationResult {
+        let total_simulation_time = self.time;
+        let vehicles = self.simulations
+            .into_iter()
+            .map(|sim| {
+                let summary = sim.summary();
+                VehicleResult { trajectory: sim.trajectory, ..summary }
+            })
+            .collect();
+
+        MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles,
+            total_simulation_time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        }
+    }
+
+    /// Metrics of every vehicle so far, without the trajectories
+    fn summary(&self) -> MultiVehicleSimulationResult {
+        MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: self.simulations.iter().map(Simulation::summary).collect(),
+            total_simulation_time: self.time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        }
+    }
+
+    /// Collect the result, finalizing the stream if any
+    ///
+    /// A streamed run writes its last points and the `Summary` footer and
+    /// returns the metrics with empty trajectories, the points are in the sink.
+    pub fn finish(mut self) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        let Some(stream) = self.stream.take() else {
+            return Ok(self.into_result());
+        };
+        let summary = self.summary();
+        stream.finish(summary.clone(), self.simulations.iter_mut().map(|sim| &mut sim.trajectory))?;
+        Ok(summary)
+    }
+}
+
+/// JSON description of a multi-vehicle run, accepted by `run_config_json`
+///
+/// Same fields and defaults as the API simulation request, plus an optional
+/// seed for a reproducible start and the constant velocity fraction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    /// Vehicle identifiers (Heavy, Standard, Agile, UltraAgile)
+    #[serde(default = "default_vehicle_types")]
+    pub vehicle_types: Vec<String>,
+    #[serde(default = "default_dt")]
+    pub dt: f64,
+    #[serde(default = "default_max_time")]
+    pub max_time: f64,
+    #[serde(default = "default_map_width")]
+    pub map_width: f64,
+    #[serde(default = "default_map_height")]
+    pub map_height: f64,
+    #[serde(default = "default_target_x")]
+    pub target_x: f64,
+    #[serde(default = "default_target_y")]
+    pub target_y: f64,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Overrides the cruise fraction of every vehicle
+    #[serde(default)]
+    pub velocity_fraction: Option<f64>,
+}
+
+fn default_vehicle_types() -> Vec<String> {
+    vec!["Heavy".to_string(), "Standard".to_string(), "Agile".to_string()]
+}
+
+fn default_dt() -> f64 { 0.05 }
+fn default_max_time() -> f64 { 600.0 }
+fn default_map_width() -> f64 { 1000.0 }
+fn default_map_height() -> f64 { 800.0 }
+fn default_target_x() -> f64 { 500.0 }
+fn default_target_y() -> f64 { 700.0 }
+
+impl SimulationConfig {
+    /// Scenario of this configuration, every vehicle from a random start
+    pub fn to_scenario(&self) -> Result<Scenario, FuzzyNavError> {
+        let vehicles = self.vehicle_types
+            .iter()
+            .map(|name| {
+                VehicleType::parse(name)
+                    .map(ScenarioVehicle::random)
+                    .ok_or_else(|| SimulationError::UnknownVehicleType(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Scenario {
+            map_width: self.map_width,
+            map_height: self.map_height,
+            target_x: self.target_x,
+            target_y: self.target_y,
+            vehicles,
+            dt: self.dt,
+            max_time: self.max_time,
+            seed: self.seed,
+            velocity_fraction: self.velocity_fraction,
+            ..Scenario::default_exam()
+        })
+    }
+
+    /// Build and run every vehicle in lock-step
+    ///
+    /// With a seed, the start states are drawn in vehicle order from one seeded generator.
+    pub fn run(&self) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        self.to_scenario()?.run()
+    }
+}
+
+/// Parse a `SimulationConfig`, run it and serialize the `MultiVehicleSimulationResult`
+///
+/// Pure string in, string out, so it is usable from the wasm wrapper and natively.
+pub fn run_config_json(config_json: &str) -> Result<String, FuzzyNavError> {
+    let config: SimulationConfig = serde_json::from_str(config_json)?;
+    let result = config.run()?;
+    Ok(serde_json::to_string(&result)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{compute_angular_error, turn_direction, TurnDirection};
+    use crate::vehicle::VehicleState;
+
+    fn test_map() -> Map {
+        Map::new(1000.0, 800.0, 500.0, 700.0)
+    }
+
+    #[test]
+    fn test_multi_vehicle_lock_step() {
+        let map = test_map();
+        let simulations = vec![
+            Simulation::new(map.clone(), VehicleType::Heavy, 0.05, 1.0),
+            Simulation::new(map.clone(), VehicleType::Agile, 0.05, 1.0),
+        ];
+        let mut multi = MultiVehicleSimulation::new(simulations, 0.05, 1.0);
+
+        multi.step();
+        multi.step();
+        assert!((multi.time - 0.1).abs() < 1e-9);
+        for sim in &multi.simulations {
+            assert_eq!(sim.trajectory.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_max_arrival_time_spread() {
+        let vehicle = |arrival_time: Option<f64>| VehicleResult {
+            id: "heavy-1".to_string(),
+            vehicle_type: "heavy".to_string(),
+            trajectory: Vec::new(),
+            metrics: SimulationMetrics { success: arrival_time.is_some(), arrival_time, ..Default::default() },
+            target: None,
+        };
+        let fleet = |arrivals: &[Option<f64>]| MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: arrivals.iter().map(|&t| vehicle(t)).collect(),
+            total_simulation_time: 200.0,
+            approach_geometry: None,
+            meta: None,
+        };
+
+        assert_eq!(fleet(&[Some(95.5), Some(140.0), Some(120.25)]).max_arrival_time_spread(), Some(44.5));
+        assert_eq!(fleet(&[Some(80.0)]).max_arrival_time_spread(), Some(0.0));
+        assert_eq!(fleet(&[Some(95.5), None]).max_arrival_time_spread(), None);
+        assert_eq!(fleet(&[]).max_arrival_time_spread(), None);
+    }
+
+    #[test]
+    fn test_multi_vehicle_stops_at_max_time() {
+        let map = test_map();
+        let simulations = vec![Simulation::new(map, VehicleType::Standard, 0.05, 0.5)];
+        let mut multi = MultiVehicleSimulation::new(simulations, 0.05, 0.5);
+
+        multi.run();
+        assert!(multi.is_finished());
+        let steps = multi.simulations[0].trajectory.len();
+
+        multi.step();
+        assert_eq!(multi.simulations[0].trajectory.len(), steps);
+
+        let result = multi.into_result();
+        assert_eq!(result.vehicles.len(), 1);
+        assert_eq!(result.vehicles[0].vehicle_type, VehicleType::Standard.id());
+        assert!(result.total_simulation_time >= 0.5 - 1e-9);
+    }
+
+    #[test]
+    fn test_recorded_approach_point_matches_recomputation() {
+        use crate::map::{compute_approach_point_on_map, turn_radius};
+
+        let mut sim = Simulation::new(test_map(), VehicleType::Agile, 0.05, 600.0);
+        let mut previous = sim.vehicle.state.position.clone();
+
+        while sim.time < sim.max_time && !sim.vehicle.has_arrived {
+            sim.step();
+            let point = sim.trajectory.last().unwrap();
+            if sim.vehicle.has_arrived {
+                assert!(point.approach_x.is_none());
+                break;
+            }
+
+            // distance_to_target is measured before the move, as is the approach point
+            let radius = turn_radius(sim.vehicle.state.velocity, sim.vehicle.characteristics.maneuverability);
+            let expected = compute_approach_point_on_map(&sim.map, point.distance_to_target, radius);
+            assert!((point.approach_x.unwrap() - expected.x).abs() < 1e-9);
+            assert!((point.approach_y.unwrap() - expected.y).abs() < 1e-9);
+
+            let heading = Radians((expected.y - previous.y).atan2(expected.x - previous.x)).to_degrees();
+            assert!((point.desired_heading.unwrap() - heading).abs().0 < 1e-9);
+
+            let max_adjustment = sim.vehicle.characteristics.maneuverability.to_degrees();
+            assert!(point.angular_adjustment.unwrap().abs() <= max_adjustment + 1e-9);
+
+            previous = Point::new(point.x, point.y);
+        }
+    }
+
+    #[test]
+    fn test_approach_bias_is_zero_outside_the_corridor() {
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Heavy)
+            .start_position(Point::new(200.0, 60.0))
+            .start_angle(Radians::ZERO)
+            .max_time(200.0)
+            .build();
+        while !sim.is_finished() {
+            sim.step();
+        }
+
+        let (outside, inside): (Vec<&TrajectoryPoint>, Vec<&TrajectoryPoint>) = sim.trajectory.iter()
+            .filter(|p| p.approach_bias.is_some())
+            .partition(|p| p.distance_to_target > APPROACH_START);
+        assert!(!outside.is_empty() && !inside.is_empty());
+        assert!(outside.iter().all(|p| p.approach_bias == Some(Degrees::ZERO)));
+
+        let metrics = sim.metrics();
+        let max_inside = inside.iter().filter_map(|p| p.approach_bias).map(|bias| bias.0).fold(0.0, f64::max);
+        let mean_inside = inside.iter().filter_map(|p| p.approach_bias).map(|bias| bias.0).sum::<f64>() / inside.len() as f64;
+        assert!((metrics.max_approach_bias_deg.unwrap() - max_inside).abs() < 1e-9);
+        assert!((metrics.mean_approach_bias_deg_inside_corridor.unwrap() - mean_inside).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_approach_bias_at_a_pose_near_the_target() {
+        use crate::map::compute_angular_error_with_arrival;
+
+        // 60 units left of and below the target (500, 700), heading east: the target
+        // bears 45°, the approach point sits 100 * (84.85 / 120)^1.5 = 59.46 below it,
+        // at (500, 640.54), bearing atan2(0.54, 60) = 0.5153°
+        let map = test_map();
+        let characteristics = crate::vehicle::create_vehicle_preset(VehicleType::Agile);
+        let state = VehicleState { position: Point::new(440.0, 640.0), angle: Radians::ZERO, velocity: 6.0 };
+        let inputs = build_observation(&state, &map, &characteristics);
+
+        assert!((inputs.raw_angular_error.to_degrees().0 - 45.0).abs() < 1e-9);
+        let arrival = compute_angular_error_with_arrival(&state.position, state.angle, &map.target, inputs.distance_to_target);
+        assert!((inputs.angular_error - arrival).abs().0 < 1e-12);
+        assert!((inputs.approach_bias().to_degrees().0 - 44.484_691_595).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_build_observation_reproduces_the_recorded_steps() {
+        // Heavy starting away from the target, so the checked steps cover the far
+        // approach, the turn back and the approach corridor
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Heavy)
+            .dt(0.05)
+            .max_time(600.0)
+            .start_position(Point::new(150.0, 40.0))
+            .start_angle(Degrees(-30.0).to_radians())
+            .build();
+        let mut checked = 0;
+
+        while !sim.is_finished() {
+            if !sim.step_index.is_multiple_of(200) {
+                sim.step();
+                continue;
+            }
+            let before = sim.vehicle.state.clone();
+            let inputs = build_observation(&before, &sim.map, &sim.vehicle.characteristics);
+            let (commanded, _, _) = sim.controller.control(inputs.distance_to_target, inputs.angular_error, inputs.velocity_relative);
+            sim.step();
+            if sim.vehicle.has_arrived {
+                break;
+            }
+
+            let point = sim.trajectory.last().unwrap();
+            assert_eq!(point.distance_to_target, inputs.distance_to_target);
+            assert_eq!(point.approach_x, Some(inputs.approach_point.x));
+            assert_eq!(point.approach_y, Some(inputs.approach_point.y));
+            assert_eq!(point.desired_heading, Some(inputs.desired_heading.to_degrees()));
+            assert_eq!(point.approach_bias, Some(inputs.approach_bias().to_degrees()));
+            assert_eq!(point.commanded_adjustment, Some(commanded.to_degrees().0));
+            assert_eq!(inputs.velocity_relative, before.velocity / sim.vehicle.characteristics.max_velocity);
+            checked += 1;
+        }
+        assert!(sim.vehicle.has_arrived);
+        assert!(checked >= 5, "{}", checked);
+    }
+
+    #[test]
+    fn test_applied_adjustment_is_clamped_commanded_and_matches_angular_velocity() {
+        let mut sim = Simulation::new(test_map(), VehicleType::Heavy, 0.05, 5.0);
+        sim.run();
+
+        let max_rate = sim.vehicle.characteristics.maneuverability.to_degrees();
+        for point in sim.trajectory.iter().filter(|p| p.angular_adjustment.is_some()) {
+            let applied = point.angular_adjustment.unwrap();
+            let commanded = point.commanded_adjustment.unwrap();
+            assert!(applied.abs() <= max_rate + 1e-9);
+            assert!((applied - commanded.clamp(-max_rate, max_rate)).abs() < 1e-9);
+            assert!((point.angular_velocity.unwrap() - applied).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_builder_velocity_fraction_is_constant_and_sets_threshold() {
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .dt(0.05)
+            .max_time(5.0)
+            .start_position(Point::new(300.0, 40.0))
+            .start_angle(Degrees(60.0).to_radians())
+            .velocity_fraction(0.15)
+            .build();
+
+        let expected = sim.vehicle.characteristics.max_velocity * 0.15;
+        assert!((sim.velocity_threshold - (expected + VELOCITY_THRESHOLD_MARGIN)).abs() < 1e-9);
+
+        sim.run();
+        assert!(!sim.trajectory.is_empty());
+        assert!(sim.trajectory.iter().all(|p| (p.velocity - expected).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_new_uses_the_vehicle_cruise_fraction() {
+        let sim = Simulation::new(test_map(), VehicleType::Agile, 0.05, 1.0);
+        let expected = sim.vehicle.characteristics.max_velocity * sim.vehicle.characteristics.cruise_fraction;
+        assert_eq!(sim.metrics().cruise_velocity, Some(expected));
+        assert!((sim.vehicle.state.velocity - expected).abs() < 1e-9);
+        assert!((sim.velocity_threshold - (expected + VELOCITY_THRESHOLD_MARGIN)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rule_activations_recorded_only_when_enabled() {
+        let mut sim = Simulation::new(test_map(), VehicleType::Standard, 0.05, 1.0);
+        sim.step();
+        assert!(sim.trajectory[0].rule_activations.is_none());
+
+        sim.record_rule_activations = true;
+        sim.step();
+        let activations = sim.trajectory[1].rule_activations.as_ref().unwrap();
+        assert_eq!(activations.len(), sim.controller.rule_labels().len());
+        assert!(activations.iter().all(|&a| (0.0..=1.0).contains(&a)));
+    }
+
+    #[test]
+    fn test_trajectory_point_without_control_fields_deserializes() {
+        let json = r#"{"t":0.0,"x":1.0,"y":2.0,"angle":90.0,"velocity":5.0,"distance_to_target":10.0}"#;
+        let point: TrajectoryPoint = serde_json::from_str(json).unwrap();
+        assert!(point.approach_x.is_none());
+        assert!(point.angular_adjustment.is_none());
+
+        let roundtrip = serde_json::to_string(&point).unwrap();
+        assert!(!roundtrip.contains("approach_x"));
+    }
+
+    #[test]
+    fn test_metrics_without_trajectory_use_vehicle_state() {
+        let mut sim = Simulation::new(test_map(), VehicleType::Agile, 0.05, 10.0);
+        sim.vehicle.state.position = Point::new(500.0, 600.0);
+        sim.vehicle.state.angle = Degrees(80.0).to_radians();
+
+        let metrics = sim.metrics();
+        assert!(!metrics.success);
+        assert!(metrics.arrival_time.is_none());
+        assert!((metrics.final_distance_to_target - 100.0).abs() < 1e-9);
+        assert!((metrics.final_angle_error - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run_reports_the_metrics_of_the_finished_simulation() {
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Agile).seed(5).max_time(20.0).build();
+        let result = sim.run();
+
+        let json = |metrics: &SimulationMetrics| serde_json::to_value(metrics).unwrap();
+        assert_eq!(json(&result.metrics), json(&sim.metrics()));
+    }
+
+    #[test]
+    fn test_builder_seed_gives_same_start() {
+        let build = |seed| SimulationBuilder::new(test_map(), VehicleType::Heavy).seed(seed).build();
+        let (a, b, c) = (build(3), build(3), build(4));
+
+        assert_eq!(a.vehicle.state.position.x, b.vehicle.state.position.x);
+        assert_eq!(a.vehicle.state.angle, b.vehicle.state.angle);
+        assert_ne!(a.vehicle.state.position.x, c.vehicle.state.position.x);
+    }
+
+    #[test]
+    fn test_run_config_json_is_reproducible_with_seed() {
+        let config = r#"{"vehicle_types": ["Agile", "UltraAgile"], "max_time": 2.0, "seed": 11}"#;
+        let first = run_config_json(config).unwrap();
+        let second = run_config_json(config).unwrap();
+        assert_eq!(first, second);
+
+        let result: MultiVehicleSimulationResult = serde_json::from_str(&first).unwrap();
+        assert_eq!(result.vehicles.len(), 2);
+        assert_eq!(result.vehicles[1].vehicle_type, VehicleType::UltraAgile.id());
+    }
+
+    #[test]
+    fn test_config_converts_into_a_random_start_scenario() {
+        let config: SimulationConfig = serde_json::from_str(r#"{"vehicle_types": ["agile"], "dt": 0.1, "seed": 4}"#).unwrap();
+        let scenario = config.to_scenario().unwrap();
+
+        assert_eq!(scenario.vehicles, vec![ScenarioVehicle::random(VehicleType::Agile)]);
+        assert_eq!((scenario.dt, scenario.seed), (0.1, Some(4)));
+        assert_eq!(scenario.criteria, ArrivalCriteria::strict());
+        assert_eq!((scenario.target_x, scenario.target_y), (500.0, 700.0));
+    }
+
+    #[test]
+    fn test_run_config_json_rejects_bad_input() {
+        use crate::error::{MapError, SerializationError};
+
+        assert!(matches!(
+            run_config_json("not json"),
+            Err(FuzzyNavError::Serialization(SerializationError::Json(_)))
+        ));
+        assert!(matches!(
+            run_config_json(r#"{"vehicle_types": ["Submarine"]}"#),
+            Err(FuzzyNavError::Simulation(SimulationError::UnknownVehicleType(name))) if name == "Submarine"
+        ));
+        assert!(matches!(
+            run_config_json(r#"{"vehicle_types": []}"#),
+            Err(FuzzyNavError::Simulation(SimulationError::NoVehicles))
+        ));
+        assert!(matches!(
+            run_config_json(r#"{"dt": 0.0}"#),
+            Err(FuzzyNavError::Simulation(SimulationError::InvalidTimeStep(_)))
+        ));
+        assert!(matches!(
+            run_config_json(r#"{"target_x": 5000.0}"#),
+            Err(FuzzyNavError::Map(MapError::TargetOutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_target_to_the_left_turns_left() {
+        // Heading east with the target straight north: 90° to the left in the y-up map
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .start_position(Point::new(500.0, 100.0))
+            .start_angle(Radians::ZERO)
+            .build();
+        let error = compute_angular_error(&sim.vehicle.state.position, Radians::ZERO, &Point::new(500.0, 700.0));
+        assert_eq!(turn_direction(error), TurnDirection::Left);
+
+        sim.step();
+        assert_eq!(turn_direction(sim.vehicle.state.angle), TurnDirection::Left);
+        let point = sim.trajectory.last().expect("one step recorded");
+        assert_eq!(point.angular_adjustment.map(|rate| turn_direction(Degrees(rate).to_radians())), Some(TurnDirection::Left));
+    }
+
+    #[test]
+    fn test_target_straight_behind_turns_one_way_for_100_steps() {
+        // Heading south with the target straight north, on and just off the ±180° seam
+        let south = -std::f64::consts::FRAC_PI_2;
+        for start_angle in [south, south + 1e-9, south - 1e-9, south + 2.0 * std::f64::consts::PI] {
+            let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+                .start_position(Point::new(500.0, 100.0))
+                .start_angle(Radians(start_angle))
+                .build();
+            for _ in 0..100 {
+                sim.step();
+            }
+
+            let directions: Vec<TurnDirection> = sim.trajectory.iter()
+                .filter_map(|point| point.angular_adjustment.map(|rate| turn_direction(Degrees(rate).to_radians())))
+                .collect();
+            assert_eq!(directions.len(), 100);
+            assert!(directions.iter().all(|&d| d == TurnDirection::Left), "start {} rad: {:?}", start_angle, directions);
+        }
+    }
+
+    #[test]
+    fn test_nan_start_angle_terminates_with_numerical_error() {
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .max_time(600.0)
+            .start_position(Point::new(300.0, 40.0))
+            .start_angle(Radians(f64::NAN))
+            .build();
+
+        let result = sim.run();
+        assert_eq!(result.metrics.termination, Some(TerminationReason::NumericalError));
+        assert!(!result.metrics.success);
+        assert!(sim.trajectory.is_empty());
+        assert_eq!(sim.time, 0.0);
+    }
+
+    #[test]
+    fn test_multi_vehicle_finishes_when_every_vehicle_failed() {
+        let broken = SimulationBuilder::new(test_map(), VehicleType::Agile)
+            .start_position(Point::new(f64::INFINITY, 40.0))
+            .build();
+        let mut multi = MultiVehicleSimulation::new(vec![broken], 0.05, 600.0);
+
+        multi.run();
+        assert!((multi.time - 0.05).abs() < 1e-9);
+        assert_eq!(multi.into_result().vehicles[0].metrics.termination, Some(TerminationReason::NumericalError));
+    }
+
+    #[test]
+    fn test_termination_reason_reports_timeout() {
+        let mut sim = Simulation::new(test_map(), VehicleType::Heavy, 0.05, 0.5);
+        assert_eq!(sim.termination_reason(), None);
+        sim.run();
+        assert_eq!(sim.termination_reason(), Some(TerminationReason::Timeout));
+    }
+
+    #[test]
+    fn test_cancelling_mid_run_keeps_the_partial_trajectory() {
+        let cancel = CancelToken::new();
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard).seed(3).build().with_cancel(cancel.clone());
+        for _ in 0..10 {
+            sim.step();
+        }
+        cancel.cancel();
+        let result = sim.run();
+        assert_eq!(result.metrics.termination, Some(TerminationReason::Cancelled));
+        assert!(!result.metrics.success);
+        assert_eq!(result.trajectory.len(), 10);
+        assert_eq!(sim.step_index, 10);
+
+        let cancel = CancelToken::new();
+        let simulations = vec![SimulationBuilder::new(test_map(), VehicleType::Agile).seed(4).build()];
+        let mut multi = MultiVehicleSimulation::new(simulations, 0.05, 600.0).with_cancel(cancel.clone());
+        multi.step();
+        cancel.cancel();
+        multi.run();
+        assert_eq!(multi.step_index, 1);
+        let result = multi.into_result();
+        assert_eq!(result.vehicles[0].metrics.termination, Some(TerminationReason::Cancelled));
+        assert_eq!(result.vehicles[0].trajectory.len(), 1);
+    }
+
+    #[test]
+    fn test_finished_run_ignores_late_cancellation() {
+        let cancel = CancelToken::new();
+        let mut sim = Simulation::new(test_map(), VehicleType::Heavy, 0.05, 0.5).with_cancel(cancel.clone());
+        sim.run();
+        cancel.cancel();
+        sim.step();
+        assert_eq!(sim.termination_reason(), Some(TerminationReason::Timeout));
+        assert_eq!(sim.metrics().termination, Some(TerminationReason::Timeout));
+    }
+
+    #[test]
+    fn test_start_on_the_target_is_flagged_degenerate() {
+        let map = test_map();
+        let mut sim = SimulationBuilder::new(map.clone(), VehicleType::Standard)
+            .start_position(map.target.position.clone())
+            .start_angle(map.target.required_angle)
+            .build();
+        let result = sim.run();
+        assert_eq!(result.metrics.arrival_time, Some(0.0));
+        assert!(result.metrics.degenerate_start);
+        assert!(sim.metrics().degenerate_start);
+        assert_eq!(result.trajectory.len(), 1);
+
+        let mut normal = Simulation::new(map, VehicleType::Standard, 0.05, 0.5);
+        assert!(!normal.run().metrics.degenerate_start);
+    }
+
+    #[test]
+    fn test_optimality_ratio_compares_the_arrival_with_the_dubins_bound() {
+        // Facing the target from straight below: the optimal path is the straight line
+        let map = test_map();
+        let start = Point::new(map.target.position.x, map.target.position.y - 400.0);
+        let mut sim = SimulationBuilder::new(map.clone(), VehicleType::Agile)
+            .start_position(start)
+            .start_angle(map.target.required_angle)
+            .build();
+        let before = sim.metrics().optimal_time_lower_bound.unwrap();
+        assert!((before - 400.0 / sim.cruise_velocity).abs() < 1e-9);
+
+        // The bound stays the one of the start pose while the vehicle moves
+        let result = sim.run();
+        let metrics = &result.metrics;
+        assert_eq!(metrics.optimal_time_lower_bound, Some(before));
+        let arrival = metrics.arrival_time.unwrap();
+        assert_eq!(metrics.time_optimality_ratio, Some(arrival / before));
+        // Arrival accepts the arrival radius, so a straight run can beat the bound
+        assert!(metrics.time_optimality_ratio.unwrap() < 1.0);
+
+        // Facing away, the vehicle has to turn around first
+        let mut away = SimulationBuilder::new(map.clone(), VehicleType::Agile)
+            .start_position(Point::new(map.target.position.x, map.target.position.y - 400.0))
+            .start_angle(-map.target.required_angle)
+            .build();
+        let metrics = away.run().metrics;
+        assert!(metrics.optimal_time_lower_bound.unwrap() > before);
+        assert!(metrics.time_optimality_ratio.unwrap() > 0.0);
+
+        // A start on the target has a zero bound and no ratio
+        let mut degenerate = SimulationBuilder::new(map.clone(), VehicleType::Agile)
+            .start_position(map.target.position.clone())
+            .start_angle(map.target.required_angle)
+            .build();
+        let metrics = degenerate.run().metrics;
+        assert_eq!((metrics.optimal_time_lower_bound, metrics.time_optimality_ratio), (Some(0.0), None));
+    }
+
+    #[test]
+    fn test_reroll_draws_starts_outside_the_arrival_criteria() {
+        // The start zone (y below 8) holds the target, the starts left of x=45 face it from within 25 units
+        let map = Map::new(100.0, 100.0, 20.0, 4.0);
+        let build = |seed: u64, reroll: bool| {
+            SimulationBuilder::new(map.clone(), VehicleType::Standard)
+                .heading(HeadingDistribution::Fixed(Degrees(90.0)))
+                .reroll_degenerate(reroll)
+                .seed(seed)
+                .build()
+        };
+        let degenerate = |sim: &mut Simulation| {
+            sim.step();
+            sim.metrics().degenerate_start
+        };
+
+        assert!((0..40).any(|seed| degenerate(&mut build(seed, false))));
+        assert!((0..40).all(|seed| !degenerate(&mut build(seed, true))));
+
+        // A start that does not need it is not drawn again, the seeded start stays the same
+        let kept = (0..40).find(|&seed| !degenerate(&mut build(seed, false))).unwrap();
+        assert_eq!(build(kept, true).vehicle.state.position, build(kept, false).vehicle.state.position);
+
+        // Fixed starts are never moved
+        let on_target = SimulationBuilder::new(map.clone(), VehicleType::Standard)
+            .start_position(map.target.position.clone())
+            .start_angle(map.target.required_angle)
+            .reroll_degenerate(true)
+            .build();
+        assert_eq!(on_target.vehicle.state.position, map.target.position);
+    }
+
+    #[test]
+    fn test_step_limit_stops_a_tiny_time_step() {
+        assert_eq!(step_limit(600.0, 0.05), 12_000 + STEP_LIMIT_MARGIN);
+        assert_eq!(step_limit(600.0, 1e-12), MAX_STEPS);
+        assert_eq!(step_limit(600.0, 0.0), MAX_STEPS);
+        assert_eq!(step_limit(f64::NAN, 0.05), MAX_STEPS);
+
+        let tiny = || SimulationBuilder::new(test_map(), VehicleType::Agile).dt(1e-12).seed(1);
+        assert_eq!(tiny().build().max_steps, MAX_STEPS);
+
+        let mut sim = tiny().max_steps(200).build();
+        let result = sim.run();
+        assert_eq!(result.metrics.termination, Some(TerminationReason::StepLimit));
+        assert_eq!(result.metrics.max_steps, Some(200));
+        assert_eq!(result.trajectory.len(), 200);
+        assert!(sim.time < 1e-9);
+        sim.step();
+        assert_eq!(sim.step_index, 200);
+
+        let mut multi = MultiVehicleSimulation::new(vec![tiny().max_steps(50).build()], 1e-12, 600.0);
+        assert_eq!(multi.max_steps, MAX_STEPS);
+        multi.max_steps = 50;
+        multi.run();
+        let result = multi.into_result();
+        assert_eq!(result.vehicles[0].metrics.termination, Some(TerminationReason::StepLimit));
+        assert_eq!(result.vehicles[0].trajectory.len(), 50);
+    }
+
+    #[test]
+    fn test_time_from_the_step_index_keeps_the_step_counts() {
+        let run = |max_time: f64| {
+            SimulationBuilder::new(test_map(), VehicleType::Agile)
+                .start_position(Point::new(200.0, 150.0))
+                .start_angle(Degrees(60.0).to_radians())
+                .velocity_fraction(DEFAULT_VELOCITY_FRACTION)
+                .max_time(max_time)
+                .build()
+                .run()
+        };
+
+        // Recorded with the accumulated time: 1222 steps and the arrival point
+        let arrived = run(300.0);
+        assert_eq!(arrived.metrics.termination, Some(TerminationReason::Arrived));
+        assert_eq!(arrived.trajectory.len(), 1223);
+        assert_eq!(arrived.metrics.arrival_time, Some(1222.0 * 0.05));
+
+        // Accumulating 0.05 fell short of 5 s after 100 steps and took a 101st
+        let timed_out = run(5.0);
+        assert_eq!(timed_out.metrics.termination, Some(TerminationReason::Timeout));
+        assert_eq!(timed_out.trajectory.len(), 100);
+        assert_eq!(timed_out.trajectory.last().unwrap().t, 5.0);
+    }
+
+    #[test]
+    fn test_converged_run_holds_the_arrival_for_the_extra_steps() {
+        let run = |hold_steps: usize| {
+            SimulationBuilder::new(test_map(), VehicleType::Agile)
+                .start_position(Point::new(200.0, 150.0))
+                .start_angle(Degrees(60.0).to_radians())
+                .velocity_fraction(DEFAULT_VELOCITY_FRACTION)
+                .max_time(300.0)
+                .criteria(ArrivalCriteria::strict().with_hold_steps(hold_steps))
+                .build()
+        };
+        let mut single = run(1);
+        let mut held = run(3);
+        let (single, held_result) = (single.run(), held.run());
+
+        // Same first step inside the tolerances, the held run stops two steps later
+        assert_eq!(held_result.metrics.termination, Some(TerminationReason::Arrived));
+        assert_eq!(held_result.metrics.arrival_time, single.metrics.arrival_time);
+        assert_eq!(held_result.trajectory.len(), single.trajectory.len() + 2);
+        assert_eq!(held.time, (1222.0 + 2.0) * 0.05);
+        assert_eq!(held_result.metrics.criteria.unwrap().arrival_hold_steps, 3);
+        assert!(!held_result.metrics.degenerate_start);
+    }
+
+    /// Turns at full authority in one direction
+    struct Spin;
+
+    impl Controller for Spin {
+        fn control(&mut self, _distance: f64, _angular_error: Radians, _velocity: f64) -> (Radians, f64, Vec<f64>) {
+            (Radians(10.0), 0.0, Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_grazing_the_tolerances_for_one_step_does_not_arrive_when_held() {
+        // The UltraAgile turns 4.5° per step: aligned on the second step only, turning through the ±2°
+        let map = test_map();
+        let run = |hold_steps: usize| {
+            let mut sim = SimulationBuilder::new(map.clone(), VehicleType::UltraAgile)
+                .start_position(Point::new(map.target.position.x, map.target.position.y - 10.0))
+                .start_angle(map.target.required_angle - Degrees(4.5).to_radians())
+                .max_time(1.0)
+                .criteria(ArrivalCriteria::strict().with_hold_steps(hold_steps))
+                .build()
+                .with_controller(Spin);
+            sim.run().metrics
+        };
+
+        let grazed = run(1);
+        assert_eq!(grazed.termination, Some(TerminationReason::Arrived));
+        assert_eq!(grazed.arrival_time, Some(0.05));
+
+        let held = run(3);
+        assert_eq!(held.termination, Some(TerminationReason::Timeout));
+        assert_eq!(held.arrival_time, None);
+        assert!(!held.success);
+    }
+
+    /// Full steering authority, alternating left and right every step
+    struct BangBang {
+        sign: f64,
+    }
+
+    impl Controller for BangBang {
+        fn control(&mut self, _distance: f64, _angular_error: Radians, _velocity: f64) -> (Radians, f64, Vec<f64>) {
+            self.sign = -self.sign;
+            (Radians(self.sign * 10.0), 0.0, Vec::new())
+        }
+    }
+
+    fn straight_run() -> Simulation {
+        SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .max_time(30.0)
+            .start_position(Point::new(500.0, 40.0))
+            .start_angle(Radians(std::f64::consts::FRAC_PI_2))
+            .build()
+    }
+
+    #[test]
+    fn test_bang_bang_controller_chatters() {
+        let mut sim = straight_run().with_controller(BangBang { sign: 1.0 });
+        let metrics = sim.run().metrics;
+
+        // One reversal per step after the first, 20 steps per second
+        assert_eq!(sim.chatter.sign_changes(), 599);
+        assert!((metrics.steering_reversals_per_minute - 1198.0).abs() < 1.0);
+        // Heading changes flip between +m*dt and -m*dt, a second difference of 2*m*dt
+        let expected_jerk = (2.0 * sim.vehicle.characteristics.maneuverability * sim.dt).to_degrees();
+        assert!((metrics.heading_jerk - expected_jerk).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fuzzy_controller_does_not_chatter_on_straight_run() {
+        let mut sim = straight_run();
+        let metrics = sim.run().metrics;
+
+        assert!(metrics.steering_reversals_per_minute < 1.0, "{}", metrics.steering_reversals_per_minute);
+        assert!(metrics.heading_jerk < 1e-3, "{}", metrics.heading_jerk);
+    }
+
+    /// Full left turn during the steps of `turn`, straight ahead otherwise
+    struct TurnWindow {
+        step: usize,
+        turn: std::ops::Range<usize>,
+    }
+
+    impl Controller for TurnWindow {
+        fn control(&mut self, _distance: f64, _angular_error: Radians, _velocity: f64) -> (Radians, f64, Vec<f64>) {
+            let adjustment = if self.turn.contains(&self.step) { Radians(10.0) } else { Radians::ZERO };
+            self.step += 1;
+            (adjustment, 0.0, Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_phases_of_a_straight_approach() {
+        // 0.4 units per step straight up from 300.3 below the target, aligned from the start:
+        // within APPROACH_START after 451 steps, within 25 units after 689
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .start_position(Point::new(500.0, 399.7))
+            .start_angle(Radians(std::f64::consts::FRAC_PI_2))
+            .velocity_fraction(0.1)
+            .max_time(60.0)
+            .build()
+            .with_controller(TurnWindow { step: 0, turn: 0..0 });
+        let metrics = sim.run().metrics;
+
+        assert!((metrics.arrival_time.unwrap() - 689.0 * 0.05).abs() < 1e-9);
+        assert!((metrics.transit_time.unwrap() - 451.0 * 0.05).abs() < 1e-9);
+        assert!((metrics.alignment_time.unwrap() - 238.0 * 0.05).abs() < 1e-9);
+        assert_eq!(metrics.first_aligned_time, Some(0.0));
+        assert_eq!(metrics.alignment_attempts, 1);
+    }
+
+    #[test]
+    fn test_phases_count_every_entry_into_the_corridor() {
+        // Starts inside APPROACH_START heading away, leaves it, turns around at 1.75° per step and comes back
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .start_position(Point::new(500.0, 600.0))
+            .start_angle(Radians(-std::f64::consts::FRAC_PI_2))
+            .velocity_fraction(0.1)
+            .max_time(60.0)
+            .criteria(ArrivalCriteria::loose())
+            .build()
+            .with_controller(TurnWindow { step: 0, turn: 100..203 });
+        let metrics = sim.run().metrics;
+
+        assert_eq!(metrics.termination, Some(TerminationReason::Arrived));
+        assert_eq!(metrics.transit_time, Some(0.0));
+        assert_eq!(metrics.alignment_time, metrics.arrival_time);
+        assert_eq!(metrics.alignment_attempts, 2);
+        // The heading error drops below the 15° tolerance after 95 of the turning steps
+        assert!((metrics.first_aligned_time.unwrap() - 195.0 * 0.05).abs() < 1e-9);
+    }
+
+    /// `vehicle_type` preset with the actuator limit of `preset_max_angular_acceleration`
+    fn slew_limited(vehicle_type: VehicleType) -> VehicleCharacteristics {
+        let limit = crate::vehicle::preset_max_angular_acceleration(vehicle_type);
+        VehicleCharacteristics { max_angular_acceleration: Some(limit), ..create_vehicle_preset(vehicle_type) }
+    }
+
+    #[test]
+    fn test_actuator_limit_slews_a_step_command_to_full_turn() {
+        // The Standard limit reaches full maneuverability in 1 s, 20 steps of 0.05 s
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .characteristics(slew_limited(VehicleType::Standard))
+            .max_time(2.0)
+            .start_position(Point::new(500.0, 40.0))
+            .start_angle(Radians::ZERO)
+            .build()
+            .with_controller(Spin);
+        let result = sim.run();
+
+        let maneuverability = sim.vehicle.characteristics.maneuverability.to_degrees();
+        let applied: Vec<f64> = result.trajectory.iter().filter_map(|point| point.angular_adjustment).collect();
+        for (step, &rate) in applied.iter().enumerate().take(19) {
+            assert!((rate - maneuverability * (step + 1) as f64 / 20.0).abs() < 1e-9, "step {}: {}", step + 1, rate);
+        }
+        assert!(applied[19..].iter().all(|rate| (rate - maneuverability).abs() < 1e-9));
+        // The command is recorded as given
+        assert!(result.trajectory.iter().all(|point| point.commanded_adjustment == Some(10f64.to_degrees())));
+    }
+
+    #[test]
+    fn test_actuator_limit_smooths_the_steering() {
+        let mut free = straight_run().with_controller(BangBang { sign: 1.0 });
+        let mut slewed = SimulationBuilder::new(test_map(), VehicleType::Standard)
+            .characteristics(slew_limited(VehicleType::Standard))
+            .max_time(30.0)
+            .start_position(Point::new(500.0, 40.0))
+            .start_angle(Radians(std::f64::consts::FRAC_PI_2))
+            .build()
+            .with_controller(BangBang { sign: 1.0 });
+        let (free, slewed) = (free.run().metrics, slewed.run().metrics);
+        assert!(slewed.steering_reversals_per_minute < free.steering_reversals_per_minute / 2.0);
+        assert!(slewed.heading_jerk < free.heading_jerk / 10.0);
+
+        // The fuzzy controller on seeded runs
+        let jerk = |characteristics: VehicleCharacteristics| -> f64 {
+            (0..3)
+                .map(|seed| {
+                    SimulationBuilder::new(test_map(), VehicleType::Standard)
+                        .characteristics(characteristics.clone())
+                        .max_time(60.0)
+                        .seed(seed)
+                        .build()
+                        .run()
+                        .metrics
+                        .heading_jerk
+                })
+                .sum()
+        };
+        let (free, slewed) = (jerk(create_vehicle_preset(VehicleType::Standard)), jerk(slew_limited(VehicleType::Standard)));
+        assert!(slewed < free, "{} vs {}", slewed, free);
+    }
+
+    #[test]
+    fn test_metrics_without_chatter_fields_deserialize() {
+        let json = r#"{"success": true, "arrival_time": 12.5, "distance_traveled": 300.0,
+            "final_angle_error": 1.0, "final_distance_to_target": 20.0}"#;
+        let metrics: SimulationMetrics = serde_json::from_str(json).unwrap();
+        assert_eq!(metrics.steering_reversals_per_minute, 0.0);
+        assert_eq!(metrics.heading_jerk, 0.0);
+    }
+
+    #[test]
+    fn test_same_run_fails_strict_and_arrives_loose() {
+        let run = |criteria: ArrivalCriteria| {
+            SimulationBuilder::new(test_map(), VehicleType::Standard)
+                .max_time(3.0)
+                .velocity_fraction(DEFAULT_VELOCITY_FRACTION)
+                .start_position(Point::new(500.0, 630.0))
+                .start_angle(Radians(std::f64::consts::FRAC_PI_2))
+                .criteria(criteria)
+                .seed(7)
+                .build()
+                .run()
+                .metrics
+        };
+
+        let strict = run(ArrivalCriteria::strict());
+        let loose = run(ArrivalCriteria::loose());
+        assert!(!strict.success);
+        assert!(loose.success);
+        assert_eq!(strict.criteria, Some(ArrivalCriteria::strict()));
+        assert_eq!(loose.criteria, Some(ArrivalCriteria::loose()));
+    }
+
+    #[test]
+    fn test_relative_distance_scale_arrives_more_often_on_a_small_map() {
+        let arrivals = |scale: Option<DistanceScale>| {
+            (0..8)
+                .filter(|&seed| {
+                    let mut builder = SimulationBuilder::new(Map::new(200.0, 200.0, 100.0, 175.0), VehicleType::Standard)
+                        .heading(HeadingDistribution::Uniform)
+                        .velocity_fraction(0.3)
+                        .max_time(120.0)
+                        .seed(seed);
+                    if let Some(scale) = scale {
+                        builder = builder.distance_scale(scale);
+                    }
+                    builder.build().run().metrics.success
+                })
+                .count()
+        };
+
+        // The default on a map other than the exam one is relative to its diagonal
+        let absolute = arrivals(Some(DistanceScale::Absolute));
+        let relative = arrivals(None);
+        assert!(relative > absolute, "relative {} vs absolute {}", relative, absolute);
+    }
+
+    /// Straight ahead with a fixed schedule of commanded accelerations
+    struct VelocitySchedule {
+        accelerations: std::vec::IntoIter<f64>,
+    }
+
+    impl Controller for VelocitySchedule {
+        fn control(&mut self, _distance: f64, _angular_error: Radians, _velocity: f64) -> (Radians, f64, Vec<f64>) {
+            (Radians::ZERO, self.accelerations.next().unwrap_or(0.0), Vec::new())
+        }
+    }
+
+    fn heavy_run(velocity_control: bool) -> Simulation {
+        SimulationBuilder::new(test_map(), VehicleType::Heavy)
+            .dt(1.0)
+            .max_time(5.0)
+            .start_position(Point::new(500.0, 40.0))
+            .start_angle(Radians(std::f64::consts::FRAC_PI_2))
+            .velocity_fraction(0.7)
+            .velocity_control(velocity_control)
+            .build()
+    }
+
+    #[test]
+    fn test_velocity_profile_of_scripted_schedule() {
+        let schedule = VelocitySchedule { accelerations: vec![10.0, 10.0, -10.0, 10.0, 20.0].into_iter() };
+        let mut sim = heavy_run(true).with_controller(schedule);
+        let metrics = sim.run().metrics;
+
+        // 35 -> 45 -> 50 (capped) -> 40 -> 50 -> 50 (20 clamped to max acceleration, then capped)
+        let velocities: Vec<f64> = sim.trajectory.iter().map(|p| p.velocity).collect();
+        assert_eq!(velocities, vec![45.0, 50.0, 40.0, 50.0, 50.0]);
+        assert_eq!(metrics.acceleration_reversals, Some(2));
+        // High velocity is above 40 for the Heavy vehicle, every step except the one at 40
+        assert_eq!(metrics.time_above_high_velocity, Some(4.0));
+        assert_eq!(metrics.avg_velocity, Some(47.0));
+        assert_eq!(metrics.arrival_velocity, None);
+    }
+
+    #[test]
+    fn test_constant_velocity_has_no_velocity_profile() {
+        let mut sim = heavy_run(false);
+        let metrics = sim.run().metrics;
+
+        assert!(sim.trajectory.iter().all(|p| p.velocity == 35.0));
+        assert_eq!(metrics.time_above_high_velocity, None);
+        assert_eq!(metrics.avg_velocity, None);
+        assert_eq!(metrics.arrival_velocity, None);
+        assert_eq!(metrics.acceleration_reversals, None);
+        assert!(!serde_json::to_string(&metrics).unwrap().contains("avg_velocity"));
+    }
+
+    /// Records the message of every event
+    #[derive(Clone, Default)]
+    struct MessageCollector(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for MessageCollector {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            struct Message<'a>(&'a mut String);
+            impl tracing::field::Visit for Message<'_> {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        *self.0 = format!("{:?}", value);
+                    }
+                }
+            }
+
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    #[test]
+    fn test_arrival_event_fires_once_per_successful_run() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let collector = MessageCollector::default();
+        let subscriber = tracing_subscriber::registry().with(collector.clone());
+        let mut sim = SimulationBuilder::new(test_map(), VehicleType::Agile)
+            .start_position(Point::new(500.0, 600.0))
+            .start_angle(Degrees(90.0).to_radians())
+            .build();
+
+        tracing::subscriber::with_default(subscriber, || {
+            sim.run();
+            // Steps after the arrival are no-ops and must not report it again
+            sim.step();
+        });
+
+        assert!(sim.vehicle.has_arrived);
+        let messages = collector.0.lock().unwrap();
+        assert_eq!(messages.iter().filter(|m| *m == "Vehicle arrived").count(), 1);
+        assert_eq!(messages.iter().filter(|m| *m == "Simulation completed").count(), 1);
+    }
+
+    /// Every vehicle must reach the targets placed near corners and edges
+    ///
+    /// One seeded run per preset keeps the gate affordable in debug builds;
+    /// release sweeps with `benchmark --map` reach 100% on every preset.
+    #[test]
+    fn test_edge_presets_keep_their_success_rate() {
+        use crate::map::MapPreset;
+
+        let presets: Vec<MapPreset> = MapPreset::ALL.into_iter().filter(|preset| *preset != MapPreset::Exam).collect();
+        for vehicle_type in VehicleType::ALL {
+            let failed: Vec<&str> = presets
+                .iter()
+                .zip(0u64..)
+                .filter(|(preset, seed)| {
+                    !SimulationBuilder::new(preset.map(), vehicle_type)
+                        .build_with_rng(&mut StdRng::seed_from_u64(*seed))
+                        .run()
+                        .metrics
+                        .success
+                })
+                .map(|(preset, _)| preset.name())
+                .collect();
+            assert!(failed.is_empty(), "{} missed {:?}", vehicle_type.id(), failed);
+        }
+    }
+
+    /// Position deviation (map units) tolerated between a cached and an exact seeded run
+    const CACHE_POSITION_TOLERANCE: f64 = 2.0;
+
+    /// Controller that records the inputs it was asked for
+    struct Recording {
+        inner: NavigationController,
+        inputs: Vec<(f64, Radians, f64)>,
+    }
+
+    impl Controller for Recording {
+        fn control(&mut self, distance_to_target: f64, angular_error: Radians, velocity_relative: f64) -> (Radians, f64, Vec<f64>) {
+            self.inputs.push((distance_to_target, angular_error, velocity_relative));
+            self.inner.control(distance_to_target, angular_error, velocity_relative)
+        }
+    }
+
+    #[test]
+    fn test_cached_run_agrees_with_the_exact_one_and_is_faster() {
+        let build = |cache: Option<Quantization>| {
+            SimulationBuilder::new(test_map(), VehicleType::Standard).seed(42).control_cache(cache).build()
+        };
+        let exact = build(None);
+        let inner = NavigationController::new(&exact.vehicle.characteristics);
+        let mut exact = exact.with_controller(Recording { inner, inputs: Vec::new() });
+        let started = std::time::Instant::now();
+        while !exact.is_finished() {
+            exact.step();
+        }
+        let exact_time = started.elapsed();
+
+        let mut cached = build(Some(Quantization::default()));
+        let started = std::time::Instant::now();
+        while !cached.is_finished() {
+            cached.step();
+        }
+        let cached_time = started.elapsed();
+
+        assert!(exact.vehicle.has_arrived && cached.vehicle.has_arrived);
+        let deviation = exact.trajectory.iter()
+            .zip(&cached.trajectory)
+            .map(|(a, b)| (a.x - b.x).hypot(a.y - b.y))
+            .fold(0.0, f64::max);
+        assert!(deviation <= CACHE_POSITION_TOLERANCE, "{} units apart", deviation);
+
+        let stats = cached.controller.cache_stats().unwrap();
+        assert!(stats.hit_rate() > 0.3, "{:?}", stats);
+        assert!(cached_time < exact_time, "cached {:?}, exact {:?}", cached_time, exact_time);
+
+        // Output deviation on the exact inputs, away from the jump of the surface at the ±10° edge of `alineado`
+        let mut controller = NavigationController::new(&exact.vehicle.characteristics).with_cache(Quantization::default());
+        for &(distance, error, velocity) in &exact.controller.inputs {
+            if (error.to_degrees().abs().0 - 10.0).abs() < 0.5 {
+                continue;
+            }
+            let (expected, _) = exact.controller.inner.compute_control(distance, error, velocity);
+            let (adjustment, _, _) = controller.control(distance, error, velocity);
+            assert!((adjustment - expected).abs().0 < 1e-3, "{} vs {} at {}°", adjustment, expected, error.to_degrees());
+        }
+    }
+
+    #[test]
+    fn test_control_cache_is_off_by_default() {
+        let sim = SimulationBuilder::new(test_map(), VehicleType::Agile).seed(3).build();
+        assert!(sim.controller.cache_stats().is_none());
+    }
 }
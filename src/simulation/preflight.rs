@@ -0,0 +1,238 @@
+// Preflight: a few seconds of simulated time from fixed start states, to
+// catch a controller that cannot steer before a long run is launched
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Simulation, SimulationBuilder, TerminationReason};
+use crate::map::{euclidean_distance, Point};
+use crate::navigation::Controller;
+use crate::units::{Degrees, Radians};
+use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+/// Start headings of the probes of every vehicle, relative to the bearing to its target (degrees)
+pub const PROBE_HEADING_OFFSETS: [f64; 3] = [0.0, 90.0, -90.0];
+
+/// Simulated seconds of a probe after the time the vehicle needs to turn 90° at full rate
+const PROBE_SETTLE_TIME: f64 = 5.0;
+
+/// Commands of a vehicle varying less than this fraction of its maneuverability (standard deviation) are degenerate
+const MIN_COMMAND_SPREAD: f64 = 0.01;
+
+/// Relative margin over the maneuverability before a command counts as out of range
+const MANEUVERABILITY_MARGIN: f64 = 0.01;
+
+/// A probe must close at least this fraction of the distance covered at its start velocity
+const MIN_PROGRESS_FRACTION: f64 = 0.1;
+
+/// Something wrong with the controller of a vehicle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreflightFinding {
+    /// The commanded angular adjustment hardly changed across the probes (deg/s)
+    DegenerateCommands { vehicle: usize, vehicle_type: VehicleType, std_dev: f64 },
+    /// Commands beyond the maneuverability, clamped by the kinematics (deg/s)
+    BeyondManeuverability { vehicle: usize, vehicle_type: VehicleType, max_command: f64, maneuverability: f64 },
+    /// The vehicle did not get closer to its target from this start heading
+    NoProgress { vehicle: usize, vehicle_type: VehicleType, heading_offset: f64, progress: f64, required: f64 },
+    /// Steps where the controller has rules but none of them fired
+    NoRulesFired { vehicle: usize, vehicle_type: VehicleType, steps: usize },
+    /// The state or the command became NaN or infinite
+    NumericalError { vehicle: usize, vehicle_type: VehicleType, heading_offset: f64 },
+}
+
+impl fmt::Display for PreflightFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DegenerateCommands { vehicle, vehicle_type, std_dev } => write!(
+                f,
+                "vehicle {} ({:?}): degenerate commands, angular adjustment std dev {:.4} deg/s",
+                vehicle, vehicle_type, std_dev
+            ),
+            Self::BeyondManeuverability { vehicle, vehicle_type, max_command, maneuverability } => write!(
+                f,
+                "vehicle {} ({:?}): commands up to {:.1} deg/s exceed the maneuverability of {:.1} deg/s",
+                vehicle, vehicle_type, max_command, maneuverability
+            ),
+            Self::NoProgress { vehicle, vehicle_type, heading_offset, progress, required } => write!(
+                f,
+                "vehicle {} ({:?}): no progress from a start {:+.0}° off the target bearing, closed {:.1} of {:.1} units",
+                vehicle, vehicle_type, heading_offset, progress, required
+            ),
+            Self::NoRulesFired { vehicle, vehicle_type, steps } => {
+                write!(f, "vehicle {} ({:?}): no rule fired on {} steps", vehicle, vehicle_type, steps)
+            }
+            Self::NumericalError { vehicle, vehicle_type, heading_offset } => write!(
+                f,
+                "vehicle {} ({:?}): non-finite state or command from a start {:+.0}° off the target bearing",
+                vehicle, vehicle_type, heading_offset
+            ),
+        }
+    }
+}
+
+/// Outcome of a preflight, passed when it has no findings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreflightReport {
+    /// Probes run, one per vehicle and start heading
+    pub probes: usize,
+    pub findings: Vec<PreflightFinding>,
+}
+
+impl PreflightReport {
+    pub fn passed(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            return write!(f, "preflight passed ({} probes)", self.probes);
+        }
+        write!(f, "preflight failed ({} probes, {} findings)", self.probes, self.findings.len())?;
+        for finding in &self.findings {
+            write!(f, "\n  - {}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Probe every vehicle of `templates` (builders with everything but the start
+/// state) from the middle of the start zone, or its fixed start position,
+/// heading at each `PROBE_HEADING_OFFSETS` from its target; `steer` swaps in
+/// the controller under test
+pub(crate) fn run_probes<C: Controller>(
+    templates: Vec<SimulationBuilder>,
+    mut steer: impl FnMut(Simulation) -> Simulation<C>,
+) -> PreflightReport {
+    let mut findings = Vec::new();
+    let mut probes = 0;
+
+    for (vehicle, template) in templates.into_iter().enumerate() {
+        let vehicle_type = template.vehicle_type;
+        let characteristics =
+            template.characteristics.clone().unwrap_or_else(|| create_vehicle_preset(vehicle_type));
+        let maneuverability = characteristics.maneuverability;
+        let map = &template.map;
+        let start = template.start_position.clone().unwrap_or_else(|| {
+            Point::new(map.width / 2.0, map.height * map.start_zone.height_percentage / 2.0)
+        });
+        let target = &map.target.position;
+        let bearing = Radians((target.y - start.y).atan2(target.x - start.x));
+        let duration = (std::f64::consts::FRAC_PI_2 / maneuverability + PROBE_SETTLE_TIME).min(template.max_time);
+
+        let mut commands = Vec::new();
+        let mut silent_steps = 0;
+        for offset in PROBE_HEADING_OFFSETS {
+            probes += 1;
+            let probe = template
+                .clone()
+                .start_position(start.clone())
+                .start_angle(bearing + Degrees(offset).to_radians())
+                .max_time(duration)
+                .record_rule_activations(true)
+                .build();
+            let start_velocity = probe.vehicle.state.velocity;
+            let start_distance = euclidean_distance(&start, target);
+            let mut simulation = steer(probe);
+            while !simulation.is_finished() {
+                simulation.step();
+            }
+
+            let trajectory = &simulation.trajectory;
+            let step_commands: Vec<f64> = trajectory.iter().filter_map(|point| point.commanded_adjustment).collect();
+            if simulation.termination == Some(TerminationReason::NumericalError)
+                || step_commands.iter().any(|command| !command.is_finite())
+            {
+                findings.push(PreflightFinding::NumericalError { vehicle, vehicle_type, heading_offset: offset });
+                continue;
+            }
+            commands.extend(step_commands);
+            silent_steps += trajectory
+                .iter()
+                .filter_map(|point| point.rule_activations.as_ref())
+                .filter(|activations| !activations.is_empty() && activations.iter().all(|&a| a <= 0.0))
+                .count();
+
+            let arrived = simulation.termination == Some(TerminationReason::Arrived);
+            let progress = start_distance - euclidean_distance(&simulation.vehicle.state.position, target);
+            let required = MIN_PROGRESS_FRACTION * start_velocity * simulation.time;
+            if !arrived && progress < required {
+                findings.push(PreflightFinding::NoProgress { vehicle, vehicle_type, heading_offset: offset, progress, required });
+            }
+        }
+
+        if commands.len() > 1 {
+            let mean = commands.iter().sum::<f64>() / commands.len() as f64;
+            let variance = commands.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / commands.len() as f64;
+            let std_dev = variance.sqrt();
+            if std_dev < MIN_COMMAND_SPREAD * maneuverability.to_degrees() {
+                findings.push(PreflightFinding::DegenerateCommands { vehicle, vehicle_type, std_dev });
+            }
+        }
+        let max_command = commands.iter().fold(0.0f64, |max, c| max.max(c.abs()));
+        if max_command > (1.0 + MANEUVERABILITY_MARGIN) * maneuverability.to_degrees() {
+            findings.push(PreflightFinding::BeyondManeuverability {
+                vehicle,
+                vehicle_type,
+                max_command,
+                maneuverability: maneuverability.to_degrees(),
+            });
+        }
+        if silent_steps > 0 {
+            findings.push(PreflightFinding::NoRulesFired { vehicle, vehicle_type, steps: silent_steps });
+        }
+    }
+
+    PreflightReport { probes, findings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{Formation, Scenario};
+
+    /// Controller stuck at zero, the misconfiguration the preflight is for
+    struct Idle;
+
+    impl Controller for Idle {
+        fn control(&mut self, _: f64, _: Radians, _: f64) -> (Radians, f64, Vec<f64>) {
+            (Radians::ZERO, 0.0, Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_default_controller_passes() {
+        let scenario = Scenario { formation: Some(Formation::LineAbreast { spacing: 80.0 }), ..Scenario::default_exam() };
+        for scenario in [Scenario::default_exam(), scenario] {
+            let report = scenario.preflight().unwrap();
+            assert!(report.passed(), "{}", report);
+            assert_eq!(report.probes, scenario.vehicles.len() * PROBE_HEADING_OFFSETS.len());
+        }
+    }
+
+    #[test]
+    fn test_zero_output_controller_fails_without_progress() {
+        let report = Scenario::default_exam().preflight_with(|simulation| simulation.with_controller(Idle)).unwrap();
+
+        assert!(!report.passed());
+        // Straight at the target it still closes in, headed 90° off it never does
+        let no_progress: Vec<(usize, f64)> = report
+            .findings
+            .iter()
+            .filter_map(|finding| match finding {
+                PreflightFinding::NoProgress { vehicle, heading_offset, .. } => Some((*vehicle, *heading_offset)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(no_progress.len(), 6);
+        assert!(no_progress.iter().all(|&(_, offset)| offset != 0.0));
+        assert!(report
+            .findings
+            .iter()
+            .any(|finding| matches!(finding, PreflightFinding::DegenerateCommands { vehicle: 0, .. })));
+        assert!(report.to_string().contains("no progress"));
+    }
+}
@@ -0,0 +1,200 @@
+// Reachability analysis: one simulation per map grid cell, started at the cell
+// center heading towards the target, run in parallel (sequentially without rayon)
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::SimulationBuilder;
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::map::{Map, Point};
+use crate::units::Radians;
+use crate::vehicle::VehicleType;
+
+/// Largest grid accepted, every cell is a full simulation
+pub const MAX_REACHABILITY_CELLS: usize = 2500;
+
+/// Grid and run parameters, the default is a 10x8 grid of Standard runs on the exam map
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReachabilityConfig {
+    pub vehicle_type: VehicleType,
+    pub columns: usize,
+    pub rows: usize,
+    pub map_width: f64,
+    pub map_height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub dt: f64,
+    pub max_time: f64,
+    /// Cell `i` (row-major) is built with `seed + i`
+    pub seed: u64,
+}
+
+impl ReachabilityConfig {
+    /// Cells of the grid, one run each, refused when empty or above `MAX_REACHABILITY_CELLS`
+    pub fn cells(&self) -> Result<usize, SimulationError> {
+        let cells = self.columns.saturating_mul(self.rows);
+        if self.columns == 0 || self.rows == 0 || cells > MAX_REACHABILITY_CELLS {
+            return Err(SimulationError::InvalidGridSize { columns: self.columns, rows: self.rows, max: MAX_REACHABILITY_CELLS });
+        }
+        Ok(cells)
+    }
+}
+
+impl Default for ReachabilityConfig {
+    fn default() -> Self {
+        Self {
+            vehicle_type: VehicleType::Standard,
+            columns: 10,
+            rows: 8,
+            map_width: 1000.0,
+            map_height: 800.0,
+            target_x: 500.0,
+            target_y: 700.0,
+            dt: 0.05,
+            max_time: 600.0,
+            seed: 0,
+        }
+    }
+}
+
+impl ReachabilityConfig {
+    pub fn cell_width(&self) -> f64 {
+        self.map_width / self.columns as f64
+    }
+
+    pub fn cell_height(&self) -> f64 {
+        self.map_height / self.rows as f64
+    }
+
+    /// Center of the cell in `row` (0 at y = 0) and `column` (0 at x = 0)
+    pub fn cell_center(&self, row: usize, column: usize) -> Point {
+        Point::new(
+            (column as f64 + 0.5) * self.cell_width(),
+            (row as f64 + 0.5) * self.cell_height(),
+        )
+    }
+}
+
+/// Outcome of every cell, indexed `[row][column]` with row 0 at the bottom of the map
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReachabilityGrid {
+    pub columns: usize,
+    pub rows: usize,
+    pub cell_width: f64,
+    pub cell_height: f64,
+    /// Arrived within the angle tolerance before `max_time`
+    pub reachable: Vec<Vec<bool>>,
+    /// Seconds to arrival, `None` for unreachable cells
+    pub arrival_times: Vec<Vec<Option<f64>>>,
+}
+
+impl ReachabilityGrid {
+    pub fn reachable_cells(&self) -> usize {
+        self.reachable.iter().flatten().filter(|&&reached| reached).count()
+    }
+}
+
+/// Run one simulation per cell of the grid
+pub fn reachability(config: &ReachabilityConfig) -> Result<ReachabilityGrid, FuzzyNavError> {
+    let cells = config.cells()?;
+    if config.dt <= 0.0 {
+        return Err(SimulationError::InvalidTimeStep(config.dt).into());
+    }
+    let map = Map::try_new(config.map_width, config.map_height, config.target_x, config.target_y)?;
+
+    #[cfg(feature = "rayon")]
+    let indices = (0..cells).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let indices = 0..cells;
+
+    let arrivals: Vec<Option<f64>> = indices
+        .map(|index| {
+            let start = config.cell_center(index / config.columns, index % config.columns);
+            let heading = Radians((map.target.position.y - start.y).atan2(map.target.position.x - start.x));
+            let mut sim = SimulationBuilder::new(map.clone(), config.vehicle_type)
+                .dt(config.dt)
+                .max_time(config.max_time)
+                .start_position(start)
+                .start_angle(heading)
+                .seed(config.seed.wrapping_add(index as u64))
+                .build();
+            while !sim.is_finished() {
+                sim.step();
+            }
+            sim.metrics().arrival_time
+        })
+        .collect();
+
+    let arrival_times: Vec<Vec<Option<f64>>> = arrivals.chunks(config.columns).map(<[_]>::to_vec).collect();
+    let reachable = arrival_times
+        .iter()
+        .map(|row| row.iter().map(Option::is_some).collect())
+        .collect();
+
+    Ok(ReachabilityGrid {
+        columns: config.columns,
+        rows: config.rows,
+        cell_width: config.cell_width(),
+        cell_height: config.cell_height(),
+        reachable,
+        arrival_times,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coarse() -> ReachabilityConfig {
+        ReachabilityConfig {
+            vehicle_type: VehicleType::Agile,
+            columns: 4,
+            rows: 3,
+            dt: 0.1,
+            max_time: 60.0,
+            seed: 21,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_coarse_grid_is_full_and_deterministic() {
+        let grid = reachability(&coarse()).unwrap();
+
+        assert_eq!((grid.columns, grid.rows), (4, 3));
+        assert_eq!((grid.cell_width, grid.cell_height), (250.0, 800.0 / 3.0));
+        assert_eq!(grid.reachable.len(), 3);
+        assert_eq!(grid.arrival_times.len(), 3);
+        for (reachable, times) in grid.reachable.iter().zip(&grid.arrival_times) {
+            assert_eq!(reachable.len(), 4);
+            assert_eq!(times.len(), 4);
+            for (&reached, time) in reachable.iter().zip(times) {
+                assert_eq!(reached, time.is_some());
+            }
+        }
+
+        assert_eq!(reachability(&coarse()).unwrap(), grid);
+    }
+
+    #[test]
+    fn test_cell_centers() {
+        let config = coarse();
+        let first = config.cell_center(0, 0);
+        let last = config.cell_center(2, 3);
+        assert_eq!((first.x, first.y), (125.0, 800.0 / 6.0));
+        assert_eq!(last.x, 875.0);
+        assert!((last.y - 800.0 * 5.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_empty_or_oversized_grids() {
+        for (columns, rows) in [(0, 3), (4, 0), (100, 26)] {
+            assert!(matches!(
+                reachability(&ReachabilityConfig { columns, rows, ..coarse() }),
+                Err(FuzzyNavError::Simulation(SimulationError::InvalidGridSize { .. }))
+            ));
+        }
+        assert!(reachability(&ReachabilityConfig { dt: 0.0, ..coarse() }).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+// Soft real-time pacing: the simulation advances in wall-clock time at a
+// multiple of real time, for hardware-in-the-loop demos and live streams
+
+use std::time::{Duration, Instant};
+
+use super::{Simulation, TrajectoryPoint};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::navigation::Controller;
+
+/// Wall-clock schedule of a paced run
+///
+/// Every step is due at `start + sim_time / speed` measured from the first
+/// step, so the time spent computing a step is taken from the following sleep
+/// and late steps never accumulate drift.
+struct Pacer {
+    start: Instant,
+    start_time: f64,
+    speed: f64,
+}
+
+impl Pacer {
+    fn new(start_time: f64, speed: f64) -> Result<Self, SimulationError> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(SimulationError::InvalidSpeed(speed));
+        }
+        Ok(Self { start: Instant::now(), start_time, speed })
+    }
+
+    /// Wait until the step reaching `sim_time` is due, zero when running late
+    fn delay(&self, sim_time: f64) -> Duration {
+        let due = Duration::from_secs_f64(((sim_time - self.start_time) / self.speed).max(0.0));
+        due.saturating_sub(self.start.elapsed())
+    }
+}
+
+impl<C: Controller> Simulation<C> {
+    /// Run to the end with sim time advancing at `speed ×` real time
+    ///
+    /// `sink` receives every trajectory point as soon as its step is due.
+    pub fn run_realtime(&mut self, speed: f64, mut sink: impl FnMut(&TrajectoryPoint)) -> Result<(), FuzzyNavError> {
+        let pacer = Pacer::new(self.time, speed)?;
+
+        while !self.is_finished() {
+            let recorded = self.trajectory.len();
+            self.step();
+            std::thread::sleep(pacer.delay(self.time));
+            if let Some(point) = self.trajectory.get(recorded) {
+                sink(point);
+            }
+        }
+        Ok(())
+    }
+
+    /// `run_realtime` sleeping on the tokio timer, for streaming from async handlers
+    #[cfg(feature = "tokio")]
+    pub async fn run_realtime_async(
+        &mut self,
+        speed: f64,
+        mut sink: impl FnMut(&TrajectoryPoint),
+    ) -> Result<(), FuzzyNavError> {
+        let pacer = Pacer::new(self.time, speed)?;
+
+        while !self.is_finished() {
+            let recorded = self.trajectory.len();
+            self.step();
+            tokio::time::sleep(pacer.delay(self.time)).await;
+            if let Some(point) = self.trajectory.get(recorded) {
+                sink(point);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, Point};
+    use crate::simulation::SimulationBuilder;
+    use crate::units::Degrees;
+    use crate::vehicle::VehicleType;
+
+    /// 5 simulated seconds far from the target, 10 steps that never arrive
+    ///
+    /// At 50× a step has 10 ms of wall time, well above its cost in debug builds.
+    fn short_run() -> Simulation {
+        SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), VehicleType::Standard)
+            .dt(0.5)
+            .max_time(5.0)
+            .start_position(Point::new(500.0, 50.0))
+            .start_angle(Degrees(90.0).to_radians())
+            .build()
+    }
+
+    #[test]
+    fn test_realtime_run_follows_wall_clock() {
+        let mut sim = short_run();
+        let mut calls = 0;
+        let started = Instant::now();
+        sim.run_realtime(50.0, |_| calls += 1).unwrap();
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let expected = sim.time / 50.0;
+        assert!((elapsed - expected).abs() < 0.1 * expected, "{}s for {}s", elapsed, expected);
+        assert_eq!(calls, sim.trajectory.len());
+        assert_eq!(calls, 10);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_realtime_run_follows_wall_clock() {
+        let mut sim = short_run();
+        let mut times = Vec::new();
+        let started = Instant::now();
+        sim.run_realtime_async(50.0, |point| times.push(point.t)).await.unwrap();
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let expected = sim.time / 50.0;
+        assert!((elapsed - expected).abs() < 0.1 * expected, "{}s for {}s", elapsed, expected);
+        assert_eq!(times.len(), sim.trajectory.len());
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_realtime_rejects_invalid_speed() {
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                short_run().run_realtime(speed, |_| {}),
+                Err(FuzzyNavError::Simulation(SimulationError::InvalidSpeed(_)))
+            ));
+        }
+    }
+}
@@ -0,0 +1,593 @@
+// Scenario: everything a multi-vehicle run depends on, in one serializable
+// description shared by the API, the bins and the visualizer
+//
+// Determinism: the same scenario with the same seed, run by the same binary,
+// gives an identical result, and so do benchmarks of it whatever the thread count.
+// Random draws come from the seeded `StdRng` in vehicle order, the fuzzy
+// evaluation keeps its memberships and activations in ordered maps instead of
+// hashed ones, and the benchmark statistics sum their samples in iteration order
+// (`analysis::stats::stable_sum`). Only wall times and resource usage differ.
+// Builds for other targets may still round the math library differently.
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use super::preflight::{self, PreflightReport};
+use super::stability;
+use super::{
+    ArrivalCriteria, CancelToken, DropoutConfig, Formation, MultiVehicleSimulation, MultiVehicleSimulationResult, Simulation,
+    SimulationBuilder, validate_disabled_rules, TrajectoryStream, DEFAULT_DT, DEFAULT_MAX_TIME,
+};
+use crate::error::{FuzzyNavError, SimulationError};
+use crate::map::{ApproachGeometry, HeadingDistribution, Map, MapPreset, Point, EXAM_MAP_SIZE};
+use crate::navigation::{Controller, DistanceScale, SharedControllers};
+use crate::units::Degrees;
+use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+/// A vehicle of a scenario, every unset initial condition is drawn at random
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioVehicle {
+    /// Label of the vehicle in results, `<type>-<n>` when unset (see `Scenario::vehicle_ids`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub vehicle_type: VehicleType,
+    /// Start position, drawn from the map start zone when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_position: Option<Point>,
+    /// Start heading, drawn from the scenario `heading` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_angle: Option<Degrees>,
+    /// Constant velocity as a fraction of the max velocity, the scenario one or the vehicle cruise fraction when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity_fraction: Option<f64>,
+}
+
+impl ScenarioVehicle {
+    /// Vehicle with a random start
+    pub fn random(vehicle_type: VehicleType) -> Self {
+        Self { id: None, vehicle_type, start_position: None, start_angle: None, velocity_fraction: None }
+    }
+}
+
+/// Map, vehicles, timing, arrival criteria and controller options of a run
+///
+/// Missing fields take the `default_exam` values when deserializing. With a
+/// seed, the random start states are drawn in vehicle order from one seeded
+/// generator, so the same scenario always gives the same result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub map_width: f64,
+    pub map_height: f64,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub vehicles: Vec<ScenarioVehicle>,
+    pub dt: f64,
+    /// Run at the largest stable dt of the vehicles instead of `dt` (see `Scenario::time_step`)
+    pub auto_dt: bool,
+    pub max_time: f64,
+    pub criteria: ArrivalCriteria,
+    pub seed: Option<u64>,
+    /// Distribution of the random start headings
+    pub heading: HeadingDistribution,
+    /// Start velocity of the vehicles without their own fraction, the cruise fraction of each vehicle when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub velocity_fraction: Option<f64>,
+    /// Let the controller accelerate and brake
+    pub velocity_control: bool,
+    /// Record the rule activations of every trajectory point
+    pub record_rule_activations: bool,
+    /// Controller rules skipped by every vehicle (0-based indices), for ablation studies
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disabled_rules: Vec<usize>,
+    /// Placement of the controller distance sets, `DistanceScale::for_map` of the scenario map when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_scale: Option<DistanceScale>,
+    /// Own berth per vehicle, None for the shared target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formation: Option<Formation>,
+    /// Add the approach corridor of the scenario target to the result
+    pub include_geometry: bool,
+    /// Sensor dropouts of every vehicle, none when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dropout: Option<DropoutConfig>,
+}
+
+/// Vehicles of a scenario or request that names none
+pub const DEFAULT_VEHICLE_TYPES: [VehicleType; 3] = [VehicleType::Heavy, VehicleType::Standard, VehicleType::Agile];
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::default_exam()
+    }
+}
+
+impl Scenario {
+    /// `DEFAULT_VEHICLE_TYPES` from random starts on the exam map, strict
+    /// criteria, dt 0.05 s and 600 s of simulated time
+    pub fn default_exam() -> Self {
+        let target = MapPreset::Exam.target();
+        Self {
+            map_width: EXAM_MAP_SIZE.0,
+            map_height: EXAM_MAP_SIZE.1,
+            target_x: target.x,
+            target_y: target.y,
+            vehicles: DEFAULT_VEHICLE_TYPES.into_iter().map(ScenarioVehicle::random).collect(),
+            dt: DEFAULT_DT,
+            auto_dt: false,
+            max_time: DEFAULT_MAX_TIME,
+            criteria: ArrivalCriteria::strict(),
+            seed: None,
+            heading: HeadingDistribution::TowardTarget,
+            velocity_fraction: None,
+            velocity_control: false,
+            record_rule_activations: false,
+            disabled_rules: Vec::new(),
+            distance_scale: None,
+            formation: None,
+            include_geometry: false,
+            dropout: None,
+        }
+    }
+
+    pub fn map(&self) -> Result<Map, FuzzyNavError> {
+        Ok(Map::try_new(self.map_width, self.map_height, self.target_x, self.target_y)?)
+    }
+
+    /// Map of every vehicle, its berth in a formation and the scenario map otherwise
+    pub fn vehicle_maps(&self) -> Result<Vec<Map>, FuzzyNavError> {
+        let map = self.map()?;
+        match self.formation {
+            Some(formation) => formation.berth_maps(&map, self.vehicles.len()),
+            None => Ok(vec![map; self.vehicles.len()]),
+        }
+    }
+
+    /// Identifier of every vehicle in order: its label, or `<type>-<n>` for the
+    /// n-th vehicle of its type counting labeled ones
+    pub fn vehicle_ids(&self) -> Vec<String> {
+        self.vehicles
+            .iter()
+            .enumerate()
+            .map(|(index, vehicle)| {
+                vehicle.id.clone().unwrap_or_else(|| {
+                    let ordinal = self.vehicles[..=index].iter().filter(|v| v.vehicle_type == vehicle.vehicle_type).count();
+                    vehicle.vehicle_type.default_id(ordinal)
+                })
+            })
+            .collect()
+    }
+
+    /// Time step the scenario runs at: `dt`, or the largest dt stable for
+    /// every vehicle under the arrival distance with `auto_dt`
+    pub fn time_step(&self) -> f64 {
+        if !self.auto_dt {
+            return self.dt;
+        }
+        let characteristics: Vec<_> = self.vehicles.iter().map(|v| create_vehicle_preset(v.vehicle_type)).collect();
+        stability::auto_dt(&characteristics, self.criteria.arrival_radius()).unwrap_or(self.dt)
+    }
+
+    /// Reject scenarios that cannot run
+    pub fn validate(&self) -> Result<(), FuzzyNavError> {
+        if self.vehicles.is_empty() {
+            return Err(SimulationError::NoVehicles.into());
+        }
+        let dt = self.time_step();
+        if !(dt.is_finite() && dt > 0.0) {
+            return Err(SimulationError::InvalidTimeStep(dt).into());
+        }
+        let characteristics: Vec<_> = self.vehicles.iter().map(|v| create_vehicle_preset(v.vehicle_type)).collect();
+        stability::check_time_step(
+            dt,
+            self.vehicles.iter().zip(&characteristics).map(|(v, c)| (v.vehicle_type.id(), c)),
+            self.criteria.arrival_radius(),
+        )?;
+        self.criteria.gate.validate()?;
+        if !(self.max_time.is_finite() && self.max_time > 0.0) {
+            return Err(SimulationError::InvalidMaxTime(self.max_time).into());
+        }
+        if let HeadingDistribution::Fixed(degrees) = self.heading {
+            if !degrees.is_finite() {
+                return Err(SimulationError::InvalidHeading(degrees.0).into());
+            }
+        }
+
+        let ids = self.vehicle_ids();
+        for (index, id) in ids.iter().enumerate() {
+            if id.trim().is_empty() || ids[..index].contains(id) {
+                return Err(SimulationError::InvalidVehicleId(id.clone()).into());
+            }
+        }
+
+        validate_disabled_rules(&self.disabled_rules)?;
+        if let Some(distance_scale) = &self.distance_scale {
+            distance_scale.validate()?;
+        }
+        if let Some(dropout) = &self.dropout {
+            dropout.validate()?;
+        }
+
+        let map = self.map()?;
+        let fractions = self.vehicles.iter().filter_map(|v| v.velocity_fraction);
+        for fraction in self.velocity_fraction.into_iter().chain(fractions) {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(SimulationError::InvalidVelocityFraction(fraction).into());
+            }
+        }
+        for vehicle in &self.vehicles {
+            if let Some(position) = &vehicle.start_position {
+                if !(position.x.is_finite() && position.y.is_finite()) {
+                    return Err(SimulationError::NonFiniteState.into());
+                }
+                if !map.contains(position) {
+                    return Err(SimulationError::StartOutOfBounds { x: position.x, y: position.y }.into());
+                }
+            }
+            if vehicle.start_angle.is_some_and(|angle| !angle.is_finite()) {
+                return Err(SimulationError::NonFiniteState.into());
+            }
+        }
+
+        self.vehicle_maps().map(|_| ())
+    }
+
+    /// Validate and build the lock-step simulation, for callers that step it themselves
+    pub fn build(&self) -> Result<MultiVehicleSimulation, FuzzyNavError> {
+        self.build_from(None)
+    }
+
+    /// `build`, taking the controllers from `controllers` instead of building their rule bases
+    pub fn build_shared(&self, controllers: &Arc<SharedControllers>) -> Result<MultiVehicleSimulation, FuzzyNavError> {
+        self.build_from(Some(controllers))
+    }
+
+    fn build_from(&self, controllers: Option<&Arc<SharedControllers>>) -> Result<MultiVehicleSimulation, FuzzyNavError> {
+        self.validate()?;
+
+        let mut rng: Box<dyn rand::RngCore> = match self.seed {
+            Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+            None => Box::new(rand::thread_rng()),
+        };
+
+        let simulations = self.builders(controllers)?
+            .into_iter()
+            .map(|builder| builder.build_with_rng(&mut rng))
+            .collect();
+
+        Ok(MultiVehicleSimulation::new(simulations, self.time_step(), self.max_time))
+    }
+
+    /// Builder of every vehicle, with its fixed initial conditions
+    fn builders(&self, controllers: Option<&Arc<SharedControllers>>) -> Result<Vec<SimulationBuilder>, FuzzyNavError> {
+        let dt = self.time_step();
+        Ok(self.vehicles
+            .iter()
+            .zip(self.vehicle_maps()?)
+            .zip(self.vehicle_ids())
+            .enumerate()
+            .map(|(index, ((vehicle, map), id))| {
+                let mut builder = SimulationBuilder::new(map, vehicle.vehicle_type)
+                    .id(id)
+                    .dt(dt)
+                    .max_time(self.max_time)
+                    .heading(self.heading)
+                    .criteria(self.criteria)
+                    .velocity_control(self.velocity_control)
+                    .record_rule_activations(self.record_rule_activations)
+                    .disabled_rules(self.disabled_rules.clone())
+                    .dropout(self.dropout.map(|dropout| dropout.for_vehicle(index)));
+                if let Some(fraction) = vehicle.velocity_fraction.or(self.velocity_fraction) {
+                    builder = builder.velocity_fraction(fraction);
+                }
+                if let Some(distance_scale) = self.distance_scale {
+                    builder = builder.distance_scale(distance_scale);
+                }
+                if let Some(controllers) = controllers {
+                    builder = builder.shared_controllers(Arc::clone(controllers));
+                }
+                if let Some(position) = &vehicle.start_position {
+                    builder = builder.start_position(position.clone());
+                }
+                if let Some(angle) = vehicle.start_angle {
+                    builder = builder.start_angle(angle.to_radians());
+                }
+                builder
+            })
+            .collect())
+    }
+
+    /// Check the controller of every vehicle with a few seconds of simulated
+    /// time from fixed start states before a long run
+    ///
+    /// Each vehicle starts from the middle of the start zone (or its fixed
+    /// position) headed at the target and 90° to either side of it. The report
+    /// lists commands that do not vary or exceed the maneuverability, starts
+    /// without progress towards the target, steps where no rule fired and
+    /// numerical errors.
+    pub fn preflight(&self) -> Result<PreflightReport, FuzzyNavError> {
+        self.preflight_with(|simulation| simulation)
+    }
+
+    /// `preflight` of the controllers `steer` swaps into the probe simulations
+    pub fn preflight_with<C: Controller>(
+        &self,
+        steer: impl FnMut(Simulation) -> Simulation<C>,
+    ) -> Result<PreflightReport, FuzzyNavError> {
+        self.validate()?;
+        Ok(preflight::run_probes(self.builders(None)?, steer))
+    }
+
+    /// Run every vehicle in lock-step until all stopped or `max_time`
+    pub fn run(&self) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        self.run_with(|_| {})
+    }
+
+    /// `run`, showing `observe` the simulation before the first step and after every step
+    pub fn run_with(
+        &self,
+        mut observe: impl FnMut(&MultiVehicleSimulation),
+    ) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        let mut multi = self.build()?;
+        drive(&mut multi, &mut observe);
+        self.with_geometry(multi.into_result())
+    }
+
+    /// `run`, stopping every vehicle still running with `TerminationReason::Cancelled` once `cancel` is tripped
+    pub fn run_cancellable(&self, cancel: CancelToken) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        let mut multi = self.build()?.with_cancel(cancel);
+        drive(&mut multi, &mut |_| {});
+        self.with_geometry(multi.into_result())
+    }
+
+    /// `run_cancellable`, taking the controllers from `controllers` instead of building their rule bases
+    pub fn run_shared(
+        &self,
+        controllers: &Arc<SharedControllers>,
+        cancel: CancelToken,
+    ) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        let mut multi = self.build_shared(controllers)?.with_cancel(cancel);
+        drive(&mut multi, &mut |_| {});
+        self.with_geometry(multi.into_result())
+    }
+
+    /// `run_with`, writing the trajectories to `stream` instead of keeping them in memory
+    ///
+    /// The result holds the metrics with empty trajectories, the points are in the sink.
+    pub fn run_streamed(
+        &self,
+        stream: TrajectoryStream,
+        mut observe: impl FnMut(&MultiVehicleSimulation),
+    ) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        let mut multi = self.build()?.with_stream(stream);
+        drive(&mut multi, &mut observe);
+        self.with_geometry(multi.finish()?)
+    }
+
+    /// `result` with the approach corridor of the scenario target when `include_geometry` is set
+    fn with_geometry(&self, mut result: MultiVehicleSimulationResult) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+        if self.include_geometry {
+            result.approach_geometry = Some(ApproachGeometry::new(&self.map()?.target, self.criteria.distance_threshold, self.criteria.gate));
+        }
+        Ok(result)
+    }
+}
+
+/// Step until finished, showing `observe` the simulation before the first step and after every step
+fn drive(multi: &mut MultiVehicleSimulation, observe: &mut impl FnMut(&MultiVehicleSimulation)) {
+    observe(multi);
+    while !multi.is_finished() {
+        multi.step();
+        observe(multi);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::MapError;
+    use crate::map::ArrivalGate;
+    use crate::navigation::ALIGNED_HALF_WIDTH_DEG;
+
+    fn short(scenario: Scenario) -> Scenario {
+        Scenario { max_time: 2.0, seed: Some(7), ..scenario }
+    }
+
+    #[test]
+    fn test_missing_fields_take_the_exam_defaults() {
+        let scenario: Scenario = serde_json::from_str(r#"{"dt": 0.1, "seed": 3}"#).unwrap();
+        assert_eq!(scenario, Scenario { dt: 0.1, seed: Some(3), ..Scenario::default_exam() });
+
+        let json = serde_json::to_string(&scenario).unwrap();
+        assert!(!json.contains("formation") && !json.contains("start_position"));
+        assert_eq!(serde_json::from_str::<Scenario>(&json).unwrap(), scenario);
+        assert!(Scenario::default_exam().validate().is_ok());
+    }
+
+    #[test]
+    fn test_velocity_fraction_defaults_to_the_cruise_fraction_of_each_vehicle() {
+        let types = r#""vehicles": [{"vehicle_type": "Heavy"}, {"vehicle_type": "Agile"}]"#;
+        let cruise: Scenario = serde_json::from_str(&format!("{{{}}}", types)).unwrap();
+        assert_eq!(cruise.velocity_fraction, None);
+        assert!(!serde_json::to_string(&cruise).unwrap().contains("velocity_fraction"));
+        let multi = cruise.build().unwrap();
+        for sim in &multi.simulations {
+            let characteristics = &sim.vehicle.characteristics;
+            assert!((sim.vehicle.state.velocity - characteristics.max_velocity * characteristics.cruise_fraction).abs() < 1e-12);
+        }
+
+        // Scenarios saved before per-vehicle cruise fractions recorded the shared one
+        let saved: Scenario = serde_json::from_str(&format!(r#"{{{}, "velocity_fraction": 0.1}}"#, types)).unwrap();
+        let multi = saved.build().unwrap();
+        for sim in &multi.simulations {
+            assert!((sim.vehicle.state.velocity - sim.vehicle.characteristics.max_velocity * 0.1).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_unrunnable_scenarios() {
+        let exam = Scenario::default_exam;
+        let with_vehicle = |vehicle: ScenarioVehicle| Scenario { vehicles: vec![vehicle], ..exam() };
+        let heavy = || ScenarioVehicle::random(VehicleType::Heavy);
+
+        let invalid = |scenario: Scenario| match scenario.validate() {
+            Err(FuzzyNavError::Simulation(e)) => e,
+            other => panic!("expected a simulation error, got {:?}", other),
+        };
+        assert_eq!(invalid(Scenario { vehicles: Vec::new(), ..exam() }), SimulationError::NoVehicles);
+        assert_eq!(invalid(Scenario { dt: 0.0, ..exam() }), SimulationError::InvalidTimeStep(0.0));
+        assert_eq!(invalid(Scenario { max_time: -1.0, ..exam() }), SimulationError::InvalidMaxTime(-1.0));
+        assert_eq!(
+            invalid(Scenario { velocity_fraction: Some(1.5), ..exam() }),
+            SimulationError::InvalidVelocityFraction(1.5)
+        );
+        assert_eq!(
+            invalid(with_vehicle(ScenarioVehicle { start_position: Some(Point::new(1200.0, 10.0)), ..heavy() })),
+            SimulationError::StartOutOfBounds { x: 1200.0, y: 10.0 }
+        );
+        assert_eq!(
+            invalid(with_vehicle(ScenarioVehicle { start_angle: Some(Degrees(f64::NAN)), ..heavy() })),
+            SimulationError::NonFiniteState
+        );
+        let labeled = |id: &str| ScenarioVehicle { id: Some(id.to_string()), ..heavy() };
+        assert_eq!(
+            invalid(Scenario { vehicles: vec![heavy(), labeled("heavy-1")], ..exam() }),
+            SimulationError::InvalidVehicleId("heavy-1".to_string())
+        );
+        assert_eq!(invalid(with_vehicle(labeled(" "))), SimulationError::InvalidVehicleId(" ".to_string()));
+        assert_eq!(
+            invalid(Scenario { formation: Some(Formation::Column { spacing: 0.0 }), ..exam() }),
+            SimulationError::InvalidFormationSpacing(0.0)
+        );
+        assert_eq!(
+            invalid(Scenario { disabled_rules: vec![2, 40], ..exam() }),
+            SimulationError::UnknownRule { index: 40, rules: 12 }
+        );
+        assert!(matches!(
+            Scenario { target_x: 5000.0, ..exam() }.validate(),
+            Err(FuzzyNavError::Map(MapError::TargetOutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_time_steps_above_the_stability_bound_are_rejected() {
+        let ultra = Scenario { vehicles: vec![ScenarioVehicle::random(VehicleType::UltraAgile)], ..Scenario::default_exam() };
+        let characteristics = create_vehicle_preset(VehicleType::UltraAgile);
+        let suggested = stability::safe_dt(&characteristics, ultra.criteria.distance_threshold);
+        assert!(suggested < 0.5);
+
+        let error = Scenario { dt: 0.5, ..ultra.clone() }.validate().unwrap_err();
+        assert!(matches!(
+            &error,
+            FuzzyNavError::Simulation(SimulationError::UnstableTimeStep { dt, vehicle, suggested: s })
+                if *dt == 0.5 && vehicle == "ultra_agile" && *s == suggested
+        ));
+        assert!(error.to_string().contains(&suggested.to_string()));
+        assert!(Scenario { dt: suggested, ..ultra }.validate().is_ok());
+    }
+
+    #[test]
+    fn test_auto_dt_picks_the_largest_stable_time_step_of_the_vehicles() {
+        let scenario = short(Scenario { dt: 0.5, auto_dt: true, ..Scenario::default_exam() });
+        let dt = scenario.time_step();
+        for vehicle in &scenario.vehicles {
+            let characteristics = create_vehicle_preset(vehicle.vehicle_type);
+            assert!(characteristics.maneuverability * dt <= ALIGNED_HALF_WIDTH_DEG.to_radians());
+            assert!(characteristics.max_velocity * dt <= scenario.criteria.distance_threshold);
+        }
+        // The tightest vehicle bound limits it, rounded down to the millisecond
+        let bound = scenario.vehicles.iter()
+            .map(|v| stability::max_stable_dt(&create_vehicle_preset(v.vehicle_type), scenario.criteria.distance_threshold))
+            .fold(f64::INFINITY, f64::min);
+        assert!(dt <= bound && bound - dt < 0.001);
+
+        let multi = scenario.build().unwrap();
+        assert_eq!(multi.dt, dt);
+        assert!(multi.simulations.iter().all(|sim| sim.dt == dt));
+        assert!(serde_json::from_str::<Scenario>(r#"{"auto_dt": true}"#).unwrap().auto_dt);
+    }
+
+    #[test]
+    fn test_results_keep_the_vehicle_order_and_ids() {
+        let agile = |id: Option<&str>| ScenarioVehicle { id: id.map(String::from), ..ScenarioVehicle::random(VehicleType::Agile) };
+        let scenario = short(Scenario {
+            vehicles: vec![agile(None), ScenarioVehicle::random(VehicleType::Heavy), agile(Some("scout")), agile(None)],
+            ..Scenario::default_exam()
+        });
+        assert_eq!(scenario.vehicle_ids(), ["agile-1", "heavy-1", "scout", "agile-3"]);
+
+        let result = scenario.run().unwrap();
+        let ids: Vec<&str> = result.vehicles.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, scenario.vehicle_ids());
+        assert_eq!(result.vehicles[1].vehicle_type, VehicleType::Heavy.id());
+    }
+
+    #[test]
+    fn test_build_applies_initial_conditions_and_seed() {
+        let fixed = ScenarioVehicle {
+            id: None,
+            vehicle_type: VehicleType::Agile,
+            start_position: Some(Point::new(300.0, 40.0)),
+            start_angle: Some(Degrees(60.0)),
+            velocity_fraction: Some(0.12),
+        };
+        let scenario = short(Scenario {
+            vehicles: vec![fixed, ScenarioVehicle::random(VehicleType::Heavy)],
+            ..Scenario::default_exam()
+        });
+
+        let multi = scenario.build().unwrap();
+        let agile = &multi.simulations[0].vehicle;
+        assert_eq!(agile.state.position, Point::new(300.0, 40.0));
+        assert!((agile.state.angle - Degrees(60.0).to_radians()).abs().0 < 1e-12);
+        assert!((agile.state.velocity - agile.characteristics.max_velocity * 0.12).abs() < 1e-12);
+        let heavy = &multi.simulations[1].vehicle;
+        assert!((heavy.state.velocity - heavy.characteristics.max_velocity * heavy.characteristics.cruise_fraction).abs() < 1e-12);
+
+        let mut steps = 0;
+        let a = scenario.run_with(|_| steps += 1).unwrap();
+        let b = scenario.run().unwrap();
+        assert_eq!(serde_json::to_value(&a).unwrap(), serde_json::to_value(&b).unwrap());
+        assert_eq!(steps, (a.total_simulation_time / scenario.dt).round() as usize + 1);
+
+        let streamed = scenario.run_streamed(TrajectoryStream::new(Vec::new()), |_| {}).unwrap();
+        assert!(streamed.vehicles.iter().all(|v| v.trajectory.is_empty()));
+        let metrics = |result: &MultiVehicleSimulationResult| -> Vec<_> {
+            result.vehicles.iter().map(|v| serde_json::to_value(&v.metrics).unwrap()).collect()
+        };
+        assert_eq!(metrics(&streamed), metrics(&a));
+    }
+
+    #[test]
+    fn test_formation_gives_every_vehicle_its_berth() {
+        let scenario = short(Scenario {
+            formation: Some(Formation::LineAbreast { spacing: 60.0 }),
+            ..Scenario::default_exam()
+        });
+
+        let targets: Vec<Point> = scenario.run().unwrap().vehicles.into_iter().filter_map(|v| v.target).collect();
+        assert_eq!(targets, vec![Point::new(440.0, 700.0), Point::new(500.0, 700.0), Point::new(560.0, 700.0)]);
+    }
+
+    #[test]
+    fn test_geometry_is_included_on_request_with_the_configured_radii() {
+        let scenario = short(Scenario { criteria: ArrivalCriteria::loose(), ..Scenario::default_exam() });
+        assert!(scenario.run().unwrap().approach_geometry.is_none());
+
+        let geometry = short(Scenario { include_geometry: true, ..scenario.clone() }).run().unwrap().approach_geometry.unwrap();
+        assert_eq!(geometry.target, Point::new(scenario.target_x, scenario.target_y));
+        assert_eq!(geometry.arrival_radius, ArrivalCriteria::loose().distance_threshold);
+        assert_eq!(geometry.approach_radius, crate::map::APPROACH_START);
+        assert_eq!(geometry.arrival_gate, ArrivalGate::Circle);
+
+        let gate = ArrivalGate::Ellipse { along: 70.0, across: 40.0 };
+        let elliptical = Scenario { include_geometry: true, criteria: ArrivalCriteria::loose().with_gate(gate), ..scenario };
+        let result = elliptical.run().unwrap();
+        assert_eq!(result.approach_geometry.unwrap().arrival_gate, gate);
+        let json = serde_json::to_string(&elliptical).unwrap();
+        assert_eq!(serde_json::from_str::<Scenario>(&json).unwrap().criteria.gate, gate);
+    }
+}
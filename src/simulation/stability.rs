@@ -0,0 +1,78 @@
+// Time step stability: above these bounds one step can turn a vehicle across
+// the whole `alineado` band or jump past the arrival radius, and it oscillates
+// around the target instead of arriving
+
+use crate::error::SimulationError;
+use crate::navigation::ALIGNED_HALF_WIDTH_DEG;
+use crate::vehicle::VehicleCharacteristics;
+
+/// Largest stable dt of a vehicle: one step turns at most half the `alineado`
+/// band and travels at most `arrival_radius`, a radius no vehicle can reach
+/// bounds only the turn
+pub fn max_stable_dt(characteristics: &VehicleCharacteristics, arrival_radius: f64) -> f64 {
+    let turn = ALIGNED_HALF_WIDTH_DEG.to_radians() / characteristics.maneuverability;
+    if arrival_radius <= 0.0 {
+        return turn;
+    }
+    turn.min(arrival_radius / characteristics.max_velocity)
+}
+
+/// `max_stable_dt` rounded down to the millisecond, the dt suggested in errors
+pub fn safe_dt(characteristics: &VehicleCharacteristics, arrival_radius: f64) -> f64 {
+    let bound = max_stable_dt(characteristics, arrival_radius);
+    let rounded = (bound * 1000.0).floor() / 1000.0;
+    if rounded > 0.0 { rounded } else { bound }
+}
+
+/// Largest safe dt of all the vehicles, None without vehicles
+pub fn auto_dt<'a>(
+    vehicles: impl IntoIterator<Item = &'a VehicleCharacteristics>,
+    arrival_radius: f64,
+) -> Option<f64> {
+    vehicles.into_iter().map(|c| safe_dt(c, arrival_radius)).reduce(f64::min)
+}
+
+/// Reject a dt above the stability bound of any of the named vehicles
+pub fn check_time_step<'a>(
+    dt: f64,
+    vehicles: impl IntoIterator<Item = (&'a str, &'a VehicleCharacteristics)>,
+    arrival_radius: f64,
+) -> Result<(), SimulationError> {
+    for (vehicle, characteristics) in vehicles {
+        if dt > max_stable_dt(characteristics, arrival_radius) {
+            return Err(SimulationError::UnstableTimeStep {
+                dt,
+                vehicle: vehicle.to_string(),
+                suggested: safe_dt(characteristics, arrival_radius),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vehicle::{create_vehicle_preset, VehicleType};
+
+    #[test]
+    fn test_max_stable_dt_takes_the_tighter_bound() {
+        let heavy = create_vehicle_preset(VehicleType::Heavy);
+        let agile = create_vehicle_preset(VehicleType::Agile);
+
+        // Heavy turns slowly: a tiny arrival radius makes travel the limit
+        assert_eq!(max_stable_dt(&heavy, 1.0), 1.0 / heavy.max_velocity);
+        // Agile turns fast: the turn bound limits it at the strict radius
+        assert_eq!(max_stable_dt(&agile, 25.0), ALIGNED_HALF_WIDTH_DEG.to_radians() / agile.maneuverability);
+    }
+
+    #[test]
+    fn test_safe_dt_never_exceeds_the_bound() {
+        for vehicle_type in VehicleType::ALL {
+            let characteristics = create_vehicle_preset(vehicle_type);
+            let safe = safe_dt(&characteristics, 25.0);
+            assert!(safe > 0.0 && safe <= max_stable_dt(&characteristics, 25.0));
+            assert!(check_time_step(safe, [("v", &characteristics)], 25.0).is_ok());
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// Trajectory streaming: points leave memory in checkpoints while a long run goes
+// on, the sink decides where they end up (a JSON Lines file in `trajectory_export`)
+
+use serde::{Deserialize, Serialize};
+
+use super::{MultiVehicleSimulationResult, TrajectoryPoint};
+use crate::error::FuzzyNavError;
+
+/// Simulated seconds between checkpoints of `TrajectoryStream::new`
+pub const DEFAULT_FLUSH_INTERVAL: f64 = 10.0;
+
+/// One record of a streamed run, a line of the JSON Lines format
+///
+/// A stream is a `Start`, the `Point`s in checkpoint order and, once the run is
+/// finalized, a `Summary`. A stream cut short by a crash has no `Summary`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum StreamRecord {
+    /// Vehicles and start metrics, the result without trajectories
+    Start { result: MultiVehicleSimulationResult },
+    /// Trajectory point of the vehicle at index `vehicle`
+    Point {
+        vehicle: usize,
+        #[serde(flatten)]
+        point: TrajectoryPoint,
+    },
+    /// Final metrics written by the finalize step, the result without trajectories
+    Summary { result: MultiVehicleSimulationResult },
+}
+
+/// Destination of the streamed records
+pub trait TrajectorySink {
+    fn write(&mut self, record: &StreamRecord) -> Result<(), FuzzyNavError>;
+
+    /// Make the records written so far durable, called at every checkpoint
+    fn flush(&mut self) -> Result<(), FuzzyNavError>;
+}
+
+/// In-memory sink, for tests and callers that post-process the records
+impl TrajectorySink for Vec<StreamRecord> {
+    fn write(&mut self, record: &StreamRecord) -> Result<(), FuzzyNavError> {
+        self.push(record.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), FuzzyNavError> {
+        Ok(())
+    }
+}
+
+/// Streams the trajectories of a simulation to a sink in checkpoints
+///
+/// Every `flush_interval` simulated seconds the points not yet written go to
+/// the sink and all but the last `retained` leave memory, so the in-memory
+/// trajectory of a vehicle stays under `retained` plus one interval of steps
+/// whatever the `max_time`. After a sink error streaming stops, the points stay
+/// in memory and the finalize step returns the error.
+pub struct TrajectoryStream {
+    sink: Box<dyn TrajectorySink + Send>,
+    flush_interval: f64,
+    retained: usize,
+    last_checkpoint: f64,
+    /// Leading points of every vehicle trajectory already written
+    written: Vec<usize>,
+    error: Option<FuzzyNavError>,
+}
+
+impl TrajectoryStream {
+    /// Stream to `sink` every `DEFAULT_FLUSH_INTERVAL` simulated seconds
+    pub fn new(sink: impl TrajectorySink + Send + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            retained: 1,
+            last_checkpoint: 0.0,
+            written: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Simulated seconds between checkpoints, every step when not positive
+    pub fn flush_interval(mut self, seconds: f64) -> Self {
+        self.flush_interval = seconds;
+        self
+    }
+
+    /// Points kept in memory after a checkpoint, at least the last one the metrics read
+    pub fn retained(mut self, points: usize) -> Self {
+        self.retained = points.max(1);
+        self
+    }
+
+    /// Most points a vehicle trajectory holds in memory with steps of `dt`
+    pub fn memory_bound(&self, dt: f64) -> usize {
+        self.retained + (self.flush_interval / dt).ceil().max(1.0) as usize + 1
+    }
+
+    pub(super) fn start(&mut self, result: MultiVehicleSimulationResult) {
+        self.write(&StreamRecord::Start { result });
+        self.flush();
+    }
+
+    /// Checkpoint at simulated `time` once `flush_interval` passed since the last one
+    pub(super) fn step<'a>(&mut self, time: f64, trajectories: impl Iterator<Item = &'a mut Vec<TrajectoryPoint>>) {
+        if time - self.last_checkpoint >= self.flush_interval {
+            self.checkpoint(time, trajectories);
+        }
+    }
+
+    /// Write the points not yet written and drop all but the retained ones
+    pub(super) fn checkpoint<'a>(&mut self, time: f64, trajectories: impl Iterator<Item = &'a mut Vec<TrajectoryPoint>>) {
+        self.last_checkpoint = time;
+        if self.error.is_some() {
+            return;
+        }
+
+        for (vehicle, trajectory) in trajectories.enumerate() {
+            if self.written.len() <= vehicle {
+                self.written.resize(vehicle + 1, 0);
+            }
+            for point in &trajectory[self.written[vehicle]..] {
+                self.write(&StreamRecord::Point { vehicle, point: point.clone() });
+            }
+            if self.error.is_some() {
+                return;
+            }
+
+            let dropped = trajectory.len().saturating_sub(self.retained);
+            trajectory.drain(..dropped);
+            self.written[vehicle] = trajectory.len();
+        }
+        self.flush();
+    }
+
+    /// Write the final checkpoint and the `Summary` footer
+    pub(super) fn finish<'a>(
+        mut self,
+        result: MultiVehicleSimulationResult,
+        trajectories: impl Iterator<Item = &'a mut Vec<TrajectoryPoint>>,
+    ) -> Result<(), FuzzyNavError> {
+        self.checkpoint(result.total_simulation_time, trajectories);
+        self.write(&StreamRecord::Summary { result });
+        self.flush();
+        self.error.map_or(Ok(()), Err)
+    }
+
+    fn write(&mut self, record: &StreamRecord) {
+        if self.error.is_none() {
+            self.error = self.sink.write(record).err();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.error.is_none() {
+            self.error = self.sink.flush().err();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, Point};
+    use crate::simulation::{MultiVehicleSimulation, SimulationBuilder, TerminationReason};
+    use crate::units::Radians;
+    use crate::vehicle::VehicleType;
+    use std::sync::{Arc, Mutex};
+
+    /// Sink the test keeps a handle to
+    #[derive(Clone, Default)]
+    struct SharedSink(Arc<Mutex<Vec<StreamRecord>>>);
+
+    impl TrajectorySink for SharedSink {
+        fn write(&mut self, record: &StreamRecord) -> Result<(), FuzzyNavError> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), FuzzyNavError> {
+            Ok(())
+        }
+    }
+
+    /// Heavy vehicle circling far from the target, it cannot arrive before `max_time`
+    fn long_run(max_time: f64) -> MultiVehicleSimulation {
+        let sim = SimulationBuilder::new(Map::new(1000.0, 800.0, 500.0, 700.0), VehicleType::Heavy)
+            .max_time(max_time)
+            .start_position(Point::new(500.0, 60.0))
+            .start_angle(Radians(-std::f64::consts::FRAC_PI_2))
+            .build();
+        MultiVehicleSimulation::new(vec![sim], 0.05, max_time)
+    }
+
+    fn points(records: &[StreamRecord]) -> Vec<TrajectoryPoint> {
+        records.iter()
+            .filter_map(|record| match record {
+                StreamRecord::Point { point, .. } => Some(point.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streamed_trajectory_stays_bounded_in_memory() {
+        let sink = SharedSink::default();
+        let stream = TrajectoryStream::new(sink.clone()).flush_interval(2.0).retained(5);
+        let bound = stream.memory_bound(0.05);
+        let mut multi = long_run(120.0).with_stream(stream);
+
+        let (mut steps, mut longest) = (0, 0);
+        while !multi.is_finished() {
+            multi.step();
+            steps += 1;
+            longest = longest.max(multi.simulations[0].trajectory.len());
+        }
+        assert!(longest <= bound, "{} points in memory, bound {}", longest, bound);
+        assert!(bound < steps / 10);
+
+        let result = multi.finish().unwrap();
+        assert!(result.vehicles[0].trajectory.is_empty());
+        assert_eq!(result.vehicles[0].metrics.termination, Some(TerminationReason::Timeout));
+
+        // Every step reached the sink once, in order, between the start and the summary
+        let records = sink.0.lock().unwrap();
+        assert!(matches!(records.first(), Some(StreamRecord::Start { .. })));
+        assert!(matches!(records.last(), Some(StreamRecord::Summary { .. })));
+        let streamed = points(&records);
+        assert_eq!(streamed.len(), steps);
+        assert!(streamed.windows(2).all(|pair| pair[0].t < pair[1].t));
+    }
+
+    #[test]
+    fn test_streamed_run_matches_the_in_memory_one() {
+        let mut in_memory = long_run(30.0);
+        in_memory.run();
+        let expected = in_memory.into_result();
+
+        let sink = SharedSink::default();
+        let mut streamed = long_run(30.0).with_stream(TrajectoryStream::new(sink.clone()).flush_interval(1.0));
+        streamed.run();
+        let result = streamed.finish().unwrap();
+
+        let records = sink.0.lock().unwrap();
+        let trajectory = points(&records);
+        assert_eq!(trajectory.len(), expected.vehicles[0].trajectory.len());
+        for (a, b) in trajectory.iter().zip(&expected.vehicles[0].trajectory) {
+            assert_eq!((a.t, a.x, a.y, a.angle), (b.t, b.x, b.y, b.angle));
+        }
+        assert_eq!(result.total_simulation_time, expected.total_simulation_time);
+        assert_eq!(result.vehicles[0].metrics.final_distance_to_target, expected.vehicles[0].metrics.final_distance_to_target);
+    }
+
+    #[test]
+    fn test_sink_error_keeps_the_points_and_is_returned() {
+        struct FailingSink;
+        impl TrajectorySink for FailingSink {
+            fn write(&mut self, _record: &StreamRecord) -> Result<(), FuzzyNavError> {
+                Err(crate::error::SerializationError::MissingField("disk").into())
+            }
+            fn flush(&mut self) -> Result<(), FuzzyNavError> {
+                Ok(())
+            }
+        }
+
+        let mut multi = long_run(5.0).with_stream(TrajectoryStream::new(FailingSink).flush_interval(1.0));
+        let mut steps = 0;
+        while !multi.is_finished() {
+            multi.step();
+            steps += 1;
+        }
+        assert_eq!(multi.simulations[0].trajectory.len(), steps);
+        assert!(multi.finish().is_err());
+    }
+
+    #[test]
+    fn test_point_record_is_a_flat_json_line() {
+        let record = StreamRecord::Point { vehicle: 2, point: TrajectoryPoint { t: 1.5, x: 3.0, ..Default::default() } };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(line.starts_with(r#"{"record":"point","vehicle":2,"t":1.5,"x":3.0"#), "{}", line);
+
+        let back: StreamRecord = serde_json::from_str(&line).unwrap();
+        assert!(matches!(back, StreamRecord::Point { vehicle: 2, point } if point.t == 1.5 && point.x == 3.0));
+    }
+}
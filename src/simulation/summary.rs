@@ -0,0 +1,278 @@
+// Text reports of a multi-vehicle result: the console block the bins print and
+// a Markdown table for reports and issues
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use super::{MultiVehicleSimulationResult, VehicleResult};
+use crate::vehicle::display_label;
+
+/// Language of the text reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+/// Labels of one language, in report order
+struct Labels {
+    vehicle: &'static str,
+    success: &'static str,
+    yes: &'static str,
+    no: &'static str,
+    arrival_time: &'static str,
+    distance_traveled: &'static str,
+    final_distance: &'static str,
+    final_angle_error: &'static str,
+    units: &'static str,
+    id: &'static str,
+    vehicle_type: &'static str,
+    arrived: &'static str,
+    vehicles: &'static str,
+    simulated_time: &'static str,
+    spread: &'static str,
+}
+
+impl Language {
+    fn labels(self) -> &'static Labels {
+        match self {
+            Language::English => &Labels {
+                vehicle: "Vehicle",
+                success: "Success",
+                yes: "YES",
+                no: "NO",
+                arrival_time: "Arrival Time",
+                distance_traveled: "Distance Traveled",
+                final_distance: "Final Distance",
+                final_angle_error: "Final Angle Error",
+                units: "units",
+                id: "Id",
+                vehicle_type: "Type",
+                arrived: "Arrived",
+                vehicles: "vehicles",
+                simulated_time: "simulated time",
+                spread: "Fleet arrival spread",
+            },
+            Language::Spanish => &Labels {
+                vehicle: "Vehículo",
+                success: "Éxito",
+                yes: "SÍ",
+                no: "NO",
+                arrival_time: "Tiempo de Llegada",
+                distance_traveled: "Distancia Recorrida",
+                final_distance: "Distancia Final",
+                final_angle_error: "Error Angular Final",
+                units: "unidades",
+                id: "Id",
+                vehicle_type: "Tipo",
+                arrived: "Llegaron",
+                vehicles: "vehículos",
+                simulated_time: "tiempo simulado",
+                spread: "Dispersión de llegadas de la flota",
+            },
+        }
+    }
+}
+
+/// What `MultiVehicleSimulationResult::summary_with` and `markdown_table_with` write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SummaryOptions {
+    pub language: Language,
+    /// Add the fleet arrival spread and the build that produced the result
+    pub verbose: bool,
+}
+
+impl SummaryOptions {
+    pub fn spanish() -> Self {
+        Self { language: Language::Spanish, ..Self::default() }
+    }
+
+    pub fn verbose(self) -> Self {
+        Self { verbose: true, ..self }
+    }
+}
+
+impl MultiVehicleSimulationResult {
+    /// Per-vehicle success, arrival time, distances and angle error, then a totals line, in English
+    pub fn summary(&self) -> String {
+        self.summary_with(SummaryOptions::default())
+    }
+
+    /// `summary` in the language and verbosity of `options`
+    pub fn summary_with(&self, options: SummaryOptions) -> String {
+        let labels = options.language.labels();
+        let mut out = String::new();
+        for (i, vehicle) in self.vehicles.iter().enumerate() {
+            let metrics = &vehicle.metrics;
+            let _ = writeln!(out, "{} {}: {}", labels.vehicle, i + 1, vehicle_label(vehicle, options.language));
+            let success = if metrics.success { (labels.yes, "✓") } else { (labels.no, "✗") };
+            let _ = writeln!(out, "  {}: {} {}", labels.success, success.0, success.1);
+            if let Some(t) = metrics.arrival_time {
+                let _ = writeln!(out, "  {}: {:.2}s", labels.arrival_time, t);
+            }
+            let _ = writeln!(out, "  {}: {:.2} {}", labels.distance_traveled, metrics.distance_traveled, labels.units);
+            let _ = writeln!(out, "  {}: {:.2} {}", labels.final_distance, metrics.final_distance_to_target, labels.units);
+            let _ = writeln!(out, "  {}: {:.2}°", labels.final_angle_error, metrics.final_angle_error);
+            out.push('\n');
+        }
+        let _ = writeln!(out, "{}", self.totals_line(labels));
+        if options.verbose {
+            self.write_details(&mut out, labels);
+        }
+        out
+    }
+
+    /// Markdown table of the `summary` fields, one row per vehicle, then the totals line, in English
+    pub fn markdown_table(&self) -> String {
+        self.markdown_table_with(SummaryOptions::default())
+    }
+
+    /// `markdown_table` in the language and verbosity of `options`
+    pub fn markdown_table_with(&self, options: SummaryOptions) -> String {
+        let labels = options.language.labels();
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} (s) | {} | {} | {} (°) |",
+            labels.id,
+            labels.vehicle_type,
+            labels.success,
+            labels.arrival_time,
+            labels.distance_traveled,
+            labels.final_distance,
+            labels.final_angle_error
+        );
+        out.push_str("|---|---|:---:|---:|---:|---:|---:|\n");
+        for vehicle in &self.vehicles {
+            let metrics = &vehicle.metrics;
+            let arrival = metrics.arrival_time.map_or("—".to_string(), |t| format!("{:.2}", t));
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {:.2} | {:.2} | {:.2} |",
+                escape_cell(&vehicle.id),
+                escape_cell(display_label(&vehicle.vehicle_type, options.language)),
+                if metrics.success { "✓" } else { "✗" },
+                arrival,
+                metrics.distance_traveled,
+                metrics.final_distance_to_target,
+                metrics.final_angle_error
+            );
+        }
+        let _ = writeln!(out, "\n{}", self.totals_line(labels));
+        if options.verbose {
+            self.write_details(&mut out, labels);
+        }
+        out
+    }
+
+    fn totals_line(&self, labels: &Labels) -> String {
+        let arrived = self.vehicles.iter().filter(|v| v.metrics.success).count();
+        format!(
+            "{}: {}/{} {}, {} {:.2}s",
+            labels.arrived,
+            arrived,
+            self.vehicles.len(),
+            labels.vehicles,
+            labels.simulated_time,
+            self.total_simulation_time
+        )
+    }
+
+    /// Spread and build lines of the verbose reports
+    fn write_details(&self, out: &mut String, labels: &Labels) {
+        if let Some(spread) = self.max_arrival_time_spread() {
+            let _ = writeln!(out, "{}: {:.2}s", labels.spread, spread);
+        }
+        if let Some(meta) = &self.meta {
+            let _ = writeln!(out, "Build: {}", meta.summary());
+        }
+    }
+}
+
+/// Type name of the vehicle with its id, results recorded before ids existed have none
+fn vehicle_label(vehicle: &VehicleResult, language: Language) -> String {
+    let name = display_label(&vehicle.vehicle_type, language);
+    if vehicle.id.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} ({})", name, vehicle.id)
+    }
+}
+
+/// Labels are free text, a `|` would split the row
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{Scenario, ScenarioVehicle};
+    use crate::vehicle::VehicleType;
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    /// Seeded run where an Agile and a Heavy both arrive
+    fn seeded_result() -> MultiVehicleSimulationResult {
+        let scenario = Scenario {
+            vehicles: vec![ScenarioVehicle::random(VehicleType::Agile), ScenarioVehicle::random(VehicleType::Heavy)],
+            max_time: 300.0,
+            seed: Some(11),
+            ..Scenario::default_exam()
+        };
+        MultiVehicleSimulationResult { meta: None, ..scenario.run().unwrap() }
+    }
+
+    #[test]
+    fn test_summary_of_a_seeded_run_matches_the_snapshot() {
+        let result = seeded_result();
+        assert_eq!(result.summary(), fixture("summary_en.txt"));
+        assert_eq!(result.summary_with(SummaryOptions::spanish()), fixture("summary_es.txt"));
+        assert_eq!(result.markdown_table(), fixture("summary_en.md"));
+    }
+
+    #[test]
+    fn test_both_languages_render_every_field() {
+        let result = seeded_result();
+        for language in [Language::English, Language::Spanish] {
+            let labels = language.labels();
+            let options = SummaryOptions { language, verbose: true };
+            let summary = result.summary_with(options);
+            let table = result.markdown_table_with(options);
+            for label in [
+                labels.vehicle,
+                labels.success,
+                labels.distance_traveled,
+                labels.final_distance,
+                labels.final_angle_error,
+                labels.arrival_time,
+                labels.arrived,
+                labels.simulated_time,
+                labels.spread,
+            ] {
+                assert!(summary.contains(label), "{:?} summary misses {}", language, label);
+            }
+            for label in [labels.id, labels.vehicle_type, labels.arrival_time, labels.arrived] {
+                assert!(table.contains(label), "{:?} table misses {}", language, label);
+            }
+            for vehicle in &result.vehicles {
+                assert!(summary.contains(&vehicle.id) && table.contains(&vehicle.id));
+            }
+        }
+    }
+
+    #[test]
+    fn test_markdown_table_escapes_labels() {
+        let mut result = seeded_result();
+        result.vehicles[0].id = "left|scout".to_string();
+        let table = result.markdown_table();
+        assert!(table.contains("| left\\|scout |"));
+        assert_eq!(table.lines().nth(2).unwrap().matches(" | ").count(), 6);
+    }
+}
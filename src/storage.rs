@@ -0,0 +1,635 @@
+// SQLite database of benchmark and simulation runs, so results of many sessions
+// can be listed, reopened and aggregated together
+//
+// Three tables, one row per stored run, per vehicle run and per metrics set:
+//
+// `runs`
+//   id             INTEGER  primary key
+//   kind           TEXT     benchmark | simulation
+//   created_at     INTEGER  unix seconds
+//   crate_version  TEXT     crate that ran it
+//   iterations     INTEGER  benchmark iterations, 1 for a simulation
+//   config         TEXT     BenchmarkConfig or Scenario JSON, enough to repeat the run
+//
+// `vehicles`
+//   id             INTEGER  primary key
+//   run_id         INTEGER  runs.id
+//   iteration      INTEGER  1-based benchmark iteration, 1 for a simulation
+//   vehicle_id     TEXT     label of the vehicle in the run ("agile-1")
+//   vehicle_type   TEXT     stable id (heavy, standard, agile, ultra_agile)
+//   heading        TEXT     start heading mode, null when unknown
+//   initial_x      REAL     map units
+//   initial_y      REAL     map units
+//   initial_angle  REAL     degrees
+//   trajectory     BLOB     zlib-compressed JSON of the trajectory points, null when not kept
+//
+// `metrics`, keyed by vehicles.id
+//   success, arrival_time, degenerate_start, distance_traveled, final_distance,
+//   final_angle_error, steering_reversals_per_minute, heading_jerk,
+//   time_optimality_ratio, wall_time (null when not measured)
+
+use std::io::Read;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+
+use crate::analysis::stats::{calculate_stats, mean_of_present};
+use crate::error::FuzzyNavError;
+use crate::simulation::benchmark::{BenchmarkConfig, CRATE_VERSION};
+use crate::simulation::{MultiVehicleSimulationResult, Scenario, TrajectoryPoint, VehicleResult};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        kind TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        crate_version TEXT NOT NULL,
+        iterations INTEGER NOT NULL,
+        config TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS vehicles (
+        id INTEGER PRIMARY KEY,
+        run_id INTEGER NOT NULL REFERENCES runs(id),
+        iteration INTEGER NOT NULL,
+        vehicle_id TEXT NOT NULL,
+        vehicle_type TEXT NOT NULL,
+        heading TEXT,
+        initial_x REAL NOT NULL,
+        initial_y REAL NOT NULL,
+        initial_angle REAL NOT NULL,
+        trajectory BLOB
+    );
+    CREATE INDEX IF NOT EXISTS vehicles_by_run ON vehicles(run_id);
+    CREATE TABLE IF NOT EXISTS metrics (
+        vehicle_row INTEGER PRIMARY KEY REFERENCES vehicles(id),
+        success INTEGER NOT NULL,
+        arrival_time REAL,
+        degenerate_start INTEGER NOT NULL,
+        distance_traveled REAL NOT NULL,
+        final_distance REAL NOT NULL,
+        final_angle_error REAL NOT NULL,
+        steering_reversals_per_minute REAL NOT NULL,
+        heading_jerk REAL NOT NULL,
+        time_optimality_ratio REAL,
+        wall_time REAL
+    );
+";
+
+/// Columns of a vehicle run with its metrics, in `StoredRun::from_row` order
+const RUN_COLUMNS: &str = "v.iteration, v.vehicle_id, v.vehicle_type, v.heading, v.initial_x, v.initial_y, v.initial_angle, \
+    m.success, m.arrival_time, m.degenerate_start, m.distance_traveled, m.final_distance, m.final_angle_error, \
+    m.steering_reversals_per_minute, m.heading_jerk, m.time_optimality_ratio, m.wall_time";
+
+/// Vehicle runs with the id of the stored run they belong to
+type RunRows = Vec<(i64, StoredRun)>;
+
+/// What a stored run is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunKind {
+    Benchmark,
+    Simulation,
+}
+
+impl RunKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunKind::Benchmark => "benchmark",
+            RunKind::Simulation => "simulation",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "benchmark" => Some(RunKind::Benchmark),
+            "simulation" => Some(RunKind::Simulation),
+            _ => None,
+        }
+    }
+}
+
+/// One vehicle run: a vehicle of one benchmark iteration and heading mode, or of a simulation
+#[derive(Debug, Clone)]
+pub struct StoredRun {
+    /// 1-based benchmark iteration, 1 for a simulation
+    pub iteration: u64,
+    pub vehicle_id: String,
+    pub vehicle_type: String,
+    /// Start heading mode (toward_target, uniform, away_from_target, fixed(deg))
+    pub heading: Option<String>,
+    pub initial_x: f64,
+    pub initial_y: f64,
+    /// Degrees
+    pub initial_angle: f64,
+    pub success: bool,
+    pub arrival_time: Option<f64>,
+    pub degenerate_start: bool,
+    pub distance_traveled: f64,
+    pub final_distance: f64,
+    pub final_angle_error: f64,
+    pub steering_reversals_per_minute: f64,
+    pub heading_jerk: f64,
+    pub time_optimality_ratio: Option<f64>,
+    /// Wall time of the run (seconds), None when not measured
+    pub wall_time: Option<f64>,
+    /// Recorded points, stored compressed, empty when not kept
+    pub trajectory: Vec<TrajectoryPoint>,
+}
+
+impl StoredRun {
+    /// Run of a finished vehicle, starting at its first trajectory point, with the trajectory kept
+    pub fn from_vehicle(iteration: u64, heading: Option<String>, vehicle: &VehicleResult) -> Self {
+        let start = vehicle.trajectory.first().cloned().unwrap_or_default();
+        let metrics = &vehicle.metrics;
+        Self {
+            iteration,
+            vehicle_id: vehicle.id.clone(),
+            vehicle_type: vehicle.vehicle_type.clone(),
+            heading,
+            initial_x: start.x,
+            initial_y: start.y,
+            initial_angle: start.angle.0,
+            success: metrics.success,
+            arrival_time: metrics.arrival_time,
+            degenerate_start: metrics.degenerate_start,
+            distance_traveled: metrics.distance_traveled,
+            final_distance: metrics.final_distance_to_target,
+            final_angle_error: metrics.final_angle_error,
+            steering_reversals_per_minute: metrics.steering_reversals_per_minute,
+            heading_jerk: metrics.heading_jerk,
+            time_optimality_ratio: metrics.time_optimality_ratio,
+            wall_time: None,
+            trajectory: vehicle.trajectory.clone(),
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            iteration: row.get::<_, i64>(0)? as u64,
+            vehicle_id: row.get(1)?,
+            vehicle_type: row.get(2)?,
+            heading: row.get(3)?,
+            initial_x: row.get(4)?,
+            initial_y: row.get(5)?,
+            initial_angle: row.get(6)?,
+            success: row.get(7)?,
+            arrival_time: row.get(8)?,
+            degenerate_start: row.get(9)?,
+            distance_traveled: row.get(10)?,
+            final_distance: row.get(11)?,
+            final_angle_error: row.get(12)?,
+            steering_reversals_per_minute: row.get(13)?,
+            heading_jerk: row.get(14)?,
+            time_optimality_ratio: row.get(15)?,
+            wall_time: row.get(16)?,
+            trajectory: Vec::new(),
+        })
+    }
+}
+
+/// A stored run as listed by `RunStore::list_runs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunSummary {
+    pub id: i64,
+    pub kind: RunKind,
+    /// Unix seconds
+    pub created_at: i64,
+    pub crate_version: String,
+    pub iterations: u64,
+    /// Vehicle types in the order they were first run
+    pub vehicle_types: Vec<String>,
+    /// Vehicle runs, every vehicle of every iteration and heading mode
+    pub total_runs: usize,
+    pub successes: usize,
+}
+
+/// A stored run with its configuration and every vehicle run
+#[derive(Debug, Clone)]
+pub struct RunDetails {
+    pub summary: RunSummary,
+    /// `BenchmarkConfig` or `Scenario` JSON, see `benchmark_config` and `scenario`
+    pub config: String,
+    pub runs: Vec<StoredRun>,
+}
+
+impl RunDetails {
+    /// Configuration of a stored benchmark, None for a simulation
+    pub fn benchmark_config(&self) -> Result<Option<BenchmarkConfig>, FuzzyNavError> {
+        match self.summary.kind {
+            RunKind::Benchmark => Ok(Some(serde_json::from_str(&self.config)?)),
+            RunKind::Simulation => Ok(None),
+        }
+    }
+
+    /// Scenario of a stored simulation, None for a benchmark
+    pub fn scenario(&self) -> Result<Option<Scenario>, FuzzyNavError> {
+        match self.summary.kind {
+            RunKind::Simulation => Ok(Some(serde_json::from_str(&self.config)?)),
+            RunKind::Benchmark => Ok(None),
+        }
+    }
+}
+
+/// Vehicle runs taken by `RunStore::aggregate_across_runs`, the default takes every one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunFilter {
+    pub kind: Option<RunKind>,
+    /// Only these runs, every run when empty
+    pub run_ids: Vec<i64>,
+    pub vehicle_type: Option<String>,
+    pub heading: Option<String>,
+    pub crate_version: Option<String>,
+    /// Count the arrivals of degenerate starts (at t=0) in the arrival times
+    pub include_degenerate_arrivals: bool,
+}
+
+/// Statistics of the stored vehicle runs of one (heading, vehicle type) group
+///
+/// Computed like the benchmark aggregates: degenerate starts count as
+/// successes and their arrival only enters the arrival times with
+/// `include_degenerate_arrivals`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunAggregate {
+    pub vehicle_type: String,
+    pub heading: Option<String>,
+    /// Stored runs the vehicle runs come from
+    pub stored_runs: usize,
+    pub total_runs: usize,
+    pub successes: usize,
+    pub degenerate_starts: usize,
+    /// Percent
+    pub success_rate: f64,
+    pub avg_arrival_time: f64,
+    pub std_arrival_time: f64,
+    pub min_arrival_time: f64,
+    pub max_arrival_time: f64,
+    pub avg_distance_traveled: f64,
+    pub std_distance_traveled: f64,
+    pub avg_final_distance: f64,
+    pub avg_final_angle_error: f64,
+    pub avg_time_optimality_ratio: Option<f64>,
+}
+
+impl RunAggregate {
+    fn new(vehicle_type: String, heading: Option<String>, runs: &[(i64, StoredRun)], include_degenerate_arrivals: bool) -> Self {
+        let mut run_ids: Vec<i64> = runs.iter().map(|(id, _)| *id).collect();
+        run_ids.dedup();
+        let total_runs = runs.len();
+        let successes = runs.iter().filter(|(_, r)| r.success).count();
+
+        let timed = || runs.iter().map(|(_, r)| r).filter(|r| include_degenerate_arrivals || !r.degenerate_start);
+        let arrival_times: Vec<f64> = timed().filter_map(|r| r.arrival_time).collect();
+        let (avg_arrival_time, std_arrival_time, min_arrival_time, max_arrival_time) = calculate_stats(&arrival_times);
+        let distances: Vec<f64> = runs.iter().map(|(_, r)| r.distance_traveled).collect();
+        let (avg_distance_traveled, std_distance_traveled, _, _) = calculate_stats(&distances);
+        let final_distances: Vec<f64> = runs.iter().map(|(_, r)| r.final_distance).collect();
+        let angle_errors: Vec<f64> = runs.iter().map(|(_, r)| r.final_angle_error).collect();
+
+        Self {
+            vehicle_type,
+            heading,
+            stored_runs: run_ids.len(),
+            total_runs,
+            successes,
+            degenerate_starts: runs.iter().filter(|(_, r)| r.degenerate_start).count(),
+            success_rate: successes as f64 / total_runs as f64 * 100.0,
+            avg_arrival_time,
+            std_arrival_time,
+            min_arrival_time,
+            max_arrival_time,
+            avg_distance_traveled,
+            std_distance_traveled,
+            avg_final_distance: calculate_stats(&final_distances).0,
+            avg_final_angle_error: calculate_stats(&angle_errors).0,
+            avg_time_optimality_ratio: mean_of_present(timed().map(|r| r.time_optimality_ratio)),
+        }
+    }
+}
+
+/// Database of stored runs, created with its tables on first open
+pub struct RunStore {
+    connection: Connection,
+}
+
+impl RunStore {
+    /// Open the database at `path`, creating it when missing
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FuzzyNavError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Database that lives as long as the store, for tests and one-off aggregation
+    pub fn open_in_memory() -> Result<Self, FuzzyNavError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, FuzzyNavError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Store a benchmark and its vehicle runs, returns the id of the stored run
+    pub fn insert_benchmark(&mut self, config: &BenchmarkConfig, runs: &[StoredRun]) -> Result<i64, FuzzyNavError> {
+        let config_json = serde_json::to_string(config)?;
+        self.insert(RunKind::Benchmark, &config.crate_version, config.iterations as u64, &config_json, runs)
+    }
+
+    /// Store a simulation, with the trajectory of every vehicle, returns the id of the stored run
+    pub fn insert_simulation(&mut self, scenario: &Scenario, result: &MultiVehicleSimulationResult) -> Result<i64, FuzzyNavError> {
+        let heading = Some(scenario.heading.to_string());
+        let runs: Vec<StoredRun> = result.vehicles.iter()
+            .map(|vehicle| StoredRun::from_vehicle(1, heading.clone(), vehicle))
+            .collect();
+        self.insert(RunKind::Simulation, CRATE_VERSION, 1, &serde_json::to_string(scenario)?, &runs)
+    }
+
+    fn insert(&mut self, kind: RunKind, crate_version: &str, iterations: u64, config: &str, runs: &[StoredRun]) -> Result<i64, FuzzyNavError> {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (kind, created_at, crate_version, iterations, config) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![kind.as_str(), created_at, crate_version, iterations as i64, config],
+        )?;
+        let run_id = transaction.last_insert_rowid();
+        {
+            let mut insert_vehicle = transaction.prepare(
+                "INSERT INTO vehicles (run_id, iteration, vehicle_id, vehicle_type, heading, initial_x, initial_y, initial_angle, trajectory)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            let mut insert_metrics = transaction.prepare(
+                "INSERT INTO metrics (vehicle_row, success, arrival_time, degenerate_start, distance_traveled, final_distance,
+                 final_angle_error, steering_reversals_per_minute, heading_jerk, time_optimality_ratio, wall_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for run in runs {
+                let trajectory = match run.trajectory.is_empty() {
+                    true => None,
+                    false => Some(compress_trajectory(&run.trajectory)?),
+                };
+                insert_vehicle.execute(params![
+                    run_id, run.iteration as i64, run.vehicle_id, run.vehicle_type, run.heading,
+                    run.initial_x, run.initial_y, run.initial_angle, trajectory,
+                ])?;
+                insert_metrics.execute(params![
+                    transaction.last_insert_rowid(), run.success, run.arrival_time, run.degenerate_start,
+                    run.distance_traveled, run.final_distance, run.final_angle_error,
+                    run.steering_reversals_per_minute, run.heading_jerk, run.time_optimality_ratio, run.wall_time,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(run_id)
+    }
+
+    /// Every stored run, oldest first
+    pub fn list_runs(&self) -> Result<Vec<RunSummary>, FuzzyNavError> {
+        let mut statement = self.connection.prepare("SELECT id FROM runs ORDER BY id")?;
+        let ids: Vec<i64> = statement.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        ids.into_iter().map(|id| Ok(self.summary(id)?.expect("listed run exists"))).collect()
+    }
+
+    fn summary(&self, id: i64) -> Result<Option<RunSummary>, FuzzyNavError> {
+        let run = self.connection
+            .query_row(
+                "SELECT kind, created_at, crate_version, iterations FROM runs WHERE id = ?1",
+                [id],
+                |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get::<_, i64>(3)?)),
+            )
+            .optional()?;
+        let Some((kind, created_at, crate_version, iterations)) = run else {
+            return Ok(None);
+        };
+
+        let mut statement = self.connection.prepare(
+            "SELECT v.vehicle_type, m.success FROM vehicles v JOIN metrics m ON m.vehicle_row = v.id WHERE v.run_id = ?1 ORDER BY v.id",
+        )?;
+        let vehicles: Vec<(String, bool)> = statement
+            .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        let mut vehicle_types: Vec<String> = Vec::new();
+        for (vehicle_type, _) in &vehicles {
+            if !vehicle_types.contains(vehicle_type) {
+                vehicle_types.push(vehicle_type.clone());
+            }
+        }
+
+        Ok(Some(RunSummary {
+            id,
+            // Only this module writes the kind
+            kind: RunKind::parse(&kind).unwrap_or(RunKind::Benchmark),
+            created_at,
+            crate_version,
+            iterations: iterations as u64,
+            vehicle_types,
+            total_runs: vehicles.len(),
+            successes: vehicles.iter().filter(|(_, success)| *success).count(),
+        }))
+    }
+
+    /// A stored run with every vehicle run and its trajectory, None for an unknown id
+    pub fn get_run(&self, id: i64) -> Result<Option<RunDetails>, FuzzyNavError> {
+        let Some(summary) = self.summary(id)? else {
+            return Ok(None);
+        };
+        let config: String = self.connection.query_row("SELECT config FROM runs WHERE id = ?1", [id], |row| row.get(0))?;
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {}, v.trajectory FROM vehicles v JOIN metrics m ON m.vehicle_row = v.id WHERE v.run_id = ?1 ORDER BY v.id",
+            RUN_COLUMNS
+        ))?;
+        let rows: Vec<(StoredRun, Option<Vec<u8>>)> = statement
+            .query_map([id], |row| Ok((StoredRun::from_row(row)?, row.get(17)?)))?
+            .collect::<Result<_, _>>()?;
+        let runs = rows
+            .into_iter()
+            .map(|(run, trajectory)| {
+                let trajectory = trajectory.map(|blob| decompress_trajectory(&blob)).transpose()?.unwrap_or_default();
+                Ok(StoredRun { trajectory, ..run })
+            })
+            .collect::<Result<_, FuzzyNavError>>()?;
+
+        Ok(Some(RunDetails { summary, config, runs }))
+    }
+
+    /// Statistics of the vehicle runs that pass `filter`, per (heading, vehicle type) in the order first stored
+    pub fn aggregate_across_runs(&self, filter: &RunFilter) -> Result<Vec<RunAggregate>, FuzzyNavError> {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let mut condition = |sql: &str, value: Value| {
+            values.push(value);
+            conditions.push(format!("{} ?{}", sql, values.len()));
+        };
+        if let Some(kind) = filter.kind {
+            condition("r.kind =", kind.as_str().to_string().into());
+        }
+        if let Some(vehicle_type) = &filter.vehicle_type {
+            condition("v.vehicle_type =", vehicle_type.clone().into());
+        }
+        if let Some(heading) = &filter.heading {
+            condition("v.heading =", heading.clone().into());
+        }
+        if let Some(crate_version) = &filter.crate_version {
+            condition("r.crate_version =", crate_version.clone().into());
+        }
+        if !filter.run_ids.is_empty() {
+            let ids: Vec<String> = filter.run_ids.iter().map(i64::to_string).collect();
+            conditions.push(format!("r.id IN ({})", ids.join(", ")));
+        }
+        let where_clause = match conditions.is_empty() {
+            true => String::new(),
+            false => format!("WHERE {}", conditions.join(" AND ")),
+        };
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {}, r.id FROM vehicles v JOIN metrics m ON m.vehicle_row = v.id JOIN runs r ON r.id = v.run_id {} ORDER BY v.id",
+            RUN_COLUMNS, where_clause
+        ))?;
+        let rows: RunRows = statement
+            .query_map(params_from_iter(values), |row| Ok((row.get(17)?, StoredRun::from_row(row)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let mut groups: Vec<((String, Option<String>), RunRows)> = Vec::new();
+        for (id, run) in rows {
+            let key = (run.vehicle_type.clone(), run.heading.clone());
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, runs)) => runs.push((id, run)),
+                None => groups.push((key, vec![(id, run)])),
+            }
+        }
+        Ok(groups
+            .into_iter()
+            .map(|((vehicle_type, heading), runs)| RunAggregate::new(vehicle_type, heading, &runs, filter.include_degenerate_arrivals))
+            .collect())
+    }
+}
+
+fn compress_trajectory(points: &[TrajectoryPoint]) -> Result<Vec<u8>, FuzzyNavError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, points)?;
+    Ok(encoder.finish().map_err(serde_json::Error::io)?)
+}
+
+fn decompress_trajectory(blob: &[u8]) -> Result<Vec<TrajectoryPoint>, FuzzyNavError> {
+    let mut json = Vec::new();
+    ZlibDecoder::new(blob).read_to_end(&mut json).map_err(serde_json::Error::io)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::ArrivalCriteria;
+
+    fn scenario(seed: u64) -> Scenario {
+        Scenario { max_time: 20.0, seed: Some(seed), criteria: ArrivalCriteria::loose(), ..Scenario::default_exam() }
+    }
+
+    /// A two-iteration "benchmark" of the exam scenario, stored without trajectories
+    fn benchmark_runs() -> Vec<StoredRun> {
+        (1..=2)
+            .flat_map(|iteration| {
+                let result = scenario(iteration).run().unwrap();
+                result.vehicles.iter()
+                    .map(|vehicle| StoredRun {
+                        wall_time: Some(0.01),
+                        trajectory: Vec::new(),
+                        ..StoredRun::from_vehicle(iteration, Some("random".to_string()), vehicle)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_runs_round_trip_through_the_database() {
+        let mut store = RunStore::open_in_memory().unwrap();
+        let config = BenchmarkConfig { iterations: 2, seed: 4, ..BenchmarkConfig::default() };
+        let runs = benchmark_runs();
+        let benchmark_id = store.insert_benchmark(&config, &runs).unwrap();
+
+        let scenario = scenario(9);
+        let result = scenario.run().unwrap();
+        let simulation_id = store.insert_simulation(&scenario, &result).unwrap();
+
+        let listed = store.list_runs().unwrap();
+        assert_eq!(listed.iter().map(|run| (run.id, run.kind)).collect::<Vec<_>>(),
+            [(benchmark_id, RunKind::Benchmark), (simulation_id, RunKind::Simulation)]);
+        assert_eq!(listed[0].total_runs, runs.len());
+        assert_eq!(listed[0].successes, runs.iter().filter(|run| run.success).count());
+        assert_eq!(listed[1].iterations, 1);
+
+        let benchmark = store.get_run(benchmark_id).unwrap().unwrap();
+        assert_eq!(benchmark.benchmark_config().unwrap(), Some(config));
+        assert_eq!(benchmark.scenario().unwrap(), None);
+        for (stored, original) in benchmark.runs.iter().zip(&runs) {
+            assert_eq!(stored.vehicle_id, original.vehicle_id);
+            assert_eq!(stored.arrival_time, original.arrival_time);
+            assert_eq!(stored.distance_traveled, original.distance_traveled);
+            assert_eq!(stored.wall_time, Some(0.01));
+            assert!(stored.trajectory.is_empty());
+        }
+
+        let simulation = store.get_run(simulation_id).unwrap().unwrap();
+        assert_eq!(simulation.scenario().unwrap(), Some(scenario));
+        for (stored, vehicle) in simulation.runs.iter().zip(&result.vehicles) {
+            assert_eq!(stored.trajectory.len(), vehicle.trajectory.len());
+            assert_eq!(stored.trajectory.last().map(|p| p.x), vehicle.trajectory.last().map(|p| p.x));
+            assert_eq!(stored.wall_time, None);
+        }
+        assert!(store.get_run(simulation_id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_aggregate_matches_the_in_memory_statistics() {
+        let mut store = RunStore::open_in_memory().unwrap();
+        let runs = benchmark_runs();
+        let (first, second) = runs.split_at(runs.len() / 2);
+        let config = BenchmarkConfig::default();
+        let ids = [store.insert_benchmark(&config, first).unwrap(), store.insert_benchmark(&config, second).unwrap()];
+        store.insert_simulation(&scenario(9), &scenario(9).run().unwrap()).unwrap();
+
+        let filter = RunFilter { kind: Some(RunKind::Benchmark), ..RunFilter::default() };
+        let aggregates = store.aggregate_across_runs(&filter).unwrap();
+        let vehicle_types: Vec<&str> = first.iter().map(|run| run.vehicle_type.as_str()).collect();
+        assert_eq!(aggregates.iter().map(|a| a.vehicle_type.as_str()).collect::<Vec<_>>(), vehicle_types);
+
+        for aggregate in &aggregates {
+            let group: Vec<&StoredRun> = runs.iter().filter(|run| run.vehicle_type == aggregate.vehicle_type).collect();
+            let times: Vec<f64> = group.iter().filter(|run| !run.degenerate_start).filter_map(|run| run.arrival_time).collect();
+            let distances: Vec<f64> = group.iter().map(|run| run.distance_traveled).collect();
+            let (avg_time, std_time, _, _) = calculate_stats(&times);
+            assert_eq!(aggregate.stored_runs, 2);
+            assert_eq!(aggregate.total_runs, 2);
+            assert_eq!(aggregate.successes, group.iter().filter(|run| run.success).count());
+            assert_eq!(aggregate.avg_arrival_time, avg_time);
+            assert_eq!(aggregate.std_arrival_time, std_time);
+            assert_eq!(aggregate.avg_distance_traveled, calculate_stats(&distances).0);
+        }
+
+        let one = RunFilter { run_ids: vec![ids[1]], vehicle_type: Some(vehicle_types[0].to_string()), ..RunFilter::default() };
+        let aggregates = store.aggregate_across_runs(&one).unwrap();
+        assert_eq!(aggregates.len(), 1);
+        assert_eq!((aggregates[0].stored_runs, aggregates[0].total_runs), (1, 1));
+        assert_eq!(aggregates[0].avg_distance_traveled, second[0].distance_traveled);
+
+        let none = RunFilter { crate_version: Some("0.0.0".to_string()), ..RunFilter::default() };
+        assert!(store.aggregate_across_runs(&none).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_file_persists_across_reopens() {
+        let path = std::env::temp_dir().join(format!("run_store_{}.db", std::process::id()));
+        let scenario = scenario(3);
+        let id = RunStore::open(&path).unwrap().insert_simulation(&scenario, &scenario.run().unwrap()).unwrap();
+
+        let reopened = RunStore::open(&path).unwrap();
+        let listed = reopened.list_runs().unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, id);
+        assert_eq!(listed[0].crate_version, CRATE_VERSION);
+    }
+}
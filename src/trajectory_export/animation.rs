@@ -0,0 +1,241 @@
+// Animated GIF / APNG renders of a simulation run
+
+use super::plot::{draw_trajectories, PlotOptions};
+use crate::map::Map;
+use crate::simulation::MultiVehicleSimulationResult;
+use plotters::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Container of an animation, chosen from the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    /// `.gif` or `.png` / `.apng`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+}
+
+/// Options of an animated render
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Frames per second of playback
+    pub fps: u32,
+    /// Simulated seconds between frames
+    pub stride: f64,
+    /// Longer runs get a larger stride so the frame count stays under this
+    pub max_frames: usize,
+    /// Mark every vehicle path with its time every N seconds, None to disable
+    pub time_label_interval: Option<f64>,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 640,
+            fps: 20,
+            stride: 0.5,
+            max_frames: 300,
+            time_label_interval: None,
+        }
+    }
+}
+
+/// Simulated time of every frame: one per stride up to the end of the run,
+/// evenly respaced when that exceeds `max_frames`, the last at the end
+fn frame_times(duration: f64, stride: f64, max_frames: usize) -> Vec<f64> {
+    let duration = duration.max(0.0);
+    let wanted = if stride > 0.0 { (duration / stride).ceil() as usize + 1 } else { 1 };
+    let frames = wanted.clamp(1, max_frames.max(1));
+    if frames == 1 {
+        return vec![duration];
+    }
+    (0..frames).map(|i| duration * i as f64 / (frames - 1) as f64).collect()
+}
+
+/// Render a run as an animation at `output_path`
+///
+/// Every frame is the figure of `plot_trajectories` cut at its time, with
+/// each vehicle at its position then and the time in the caption. Frames are
+/// drawn into an in-memory buffer and encoded one by one, so memory does not
+/// grow with the length of the run.
+pub fn render_animation<P: AsRef<Path>>(
+    result: &MultiVehicleSimulationResult,
+    map: &Map,
+    options: AnimationOptions,
+    output_path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = output_path.as_ref();
+    let format = AnimationFormat::from_path(path)
+        .ok_or_else(|| format!("unsupported animation file {}, use .gif or .png", path.display()))?;
+    if options.width == 0 || options.height == 0 || options.width > u16::MAX as u32 || options.height > u16::MAX as u32 {
+        return Err(format!("invalid animation size {}x{}", options.width, options.height).into());
+    }
+    if options.fps == 0 {
+        return Err("animation fps must be positive".into());
+    }
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let plot_options = PlotOptions {
+        width: options.width,
+        height: options.height,
+        time_label_interval: options.time_label_interval,
+        ..Default::default()
+    };
+    let times = frame_times(result.total_simulation_time, options.stride, options.max_frames);
+    let (width, height) = (options.width, options.height);
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    let render = |t: f64, buffer: &mut [u8]| -> Result<(), Box<dyn std::error::Error>> {
+        let root = BitMapBackend::with_buffer(buffer, (width, height)).into_drawing_area();
+        draw_trajectories(&root, result, map, &plot_options, Some(t))?;
+        root.present()?;
+        Ok(())
+    };
+
+    let writer = BufWriter::new(File::create(path)?);
+    match format {
+        AnimationFormat::Gif => {
+            let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &[])?;
+            encoder.set_repeat(gif::Repeat::Infinite)?;
+            // GIF delays are in hundredths of a second
+            let delay = (100 / options.fps).max(1) as u16;
+            for &t in &times {
+                render(t, &mut buffer)?;
+                let mut frame = gif::Frame::from_rgb_speed(width as u16, height as u16, &buffer, 30);
+                frame.delay = delay;
+                encoder.write_frame(&frame)?;
+            }
+        }
+        AnimationFormat::Apng => {
+            let mut encoder = png::Encoder::new(writer, width, height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(times.len() as u32, 0)?;
+            encoder.set_frame_delay(1, options.fps as u16)?;
+            let mut writer = encoder.write_header()?;
+            for &t in &times {
+                render(t, &mut buffer)?;
+                writer.write_image_data(&buffer)?;
+            }
+            writer.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationMetrics, TrajectoryPoint, VehicleResult, SCHEMA_VERSION};
+    use crate::units::Degrees;
+
+    fn tiny_run() -> MultiVehicleSimulationResult {
+        let trajectory: Vec<TrajectoryPoint> = (0..=40)
+            .map(|i| TrajectoryPoint {
+                t: i as f64 * 0.1,
+                x: 300.0 + i as f64 * 5.0,
+                y: 60.0 + i as f64 * 14.0,
+                angle: Degrees(70.0),
+                velocity: 5.0,
+                distance_to_target: 600.0 - i as f64 * 14.0,
+                ..Default::default()
+            })
+            .collect();
+
+        MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![VehicleResult {
+                id: "standard-1".to_string(),
+                vehicle_type: "standard".to_string(),
+                trajectory,
+                metrics: SimulationMetrics { distance_traveled: 560.0, ..Default::default() },
+                target: None,
+            }],
+            total_simulation_time: 4.0,
+            approach_geometry: None,
+            meta: None,
+        }
+    }
+
+    fn small_options() -> AnimationOptions {
+        AnimationOptions { width: 320, height: 256, stride: 1.0, ..Default::default() }
+    }
+
+    #[test]
+    fn test_frame_times_follow_the_stride_and_respect_the_cap() {
+        assert_eq!(frame_times(4.0, 1.0, 300), vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        // 4.5 s at 1 s per frame needs 6 frames, respaced to end at 4.5
+        assert_eq!(frame_times(4.5, 1.0, 300), vec![0.0, 0.9, 1.8, 2.7, 3.6, 4.5]);
+
+        let capped = frame_times(1000.0, 0.1, 50);
+        assert_eq!(capped.len(), 50);
+        assert_eq!(capped.last(), Some(&1000.0));
+        assert_eq!(frame_times(3.0, 0.5, 1), vec![3.0]);
+    }
+
+    #[test]
+    fn test_gif_has_one_frame_per_stride_and_outgrows_a_single_frame() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let dir = std::env::temp_dir();
+        let animated = dir.join(format!("animation_{}.gif", std::process::id()));
+        let still = dir.join(format!("animation_still_{}.gif", std::process::id()));
+
+        render_animation(&tiny_run(), &map, small_options(), &animated).unwrap();
+        render_animation(&tiny_run(), &map, AnimationOptions { max_frames: 1, ..small_options() }, &still).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&animated).unwrap()).unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        let animated_size = std::fs::metadata(&animated).unwrap().len();
+        let still_size = std::fs::metadata(&still).unwrap().len();
+        std::fs::remove_file(&animated).ok();
+        std::fs::remove_file(&still).ok();
+
+        assert_eq!(frames, 5);
+        assert!(animated_size > still_size, "{} <= {} bytes", animated_size, still_size);
+    }
+
+    #[test]
+    fn test_apng_declares_its_frames() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let path = std::env::temp_dir().join(format!("animation_{}.png", std::process::id()));
+
+        render_animation(&tiny_run(), &map, small_options(), &path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control.expect("missing acTL chunk");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(control.num_frames, 5);
+    }
+
+    #[test]
+    fn test_unknown_extension_is_rejected() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let path = std::env::temp_dir().join("animation.mp4");
+        assert!(render_animation(&tiny_run(), &map, small_options(), &path).is_err());
+        assert!(!path.exists());
+    }
+}
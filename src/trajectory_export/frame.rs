@@ -0,0 +1,276 @@
+// Output coordinate frames: origin translation, y-axis flip and angle convention
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{ApproachGeometry, Point};
+use crate::simulation::{MultiVehicleSimulationResult, SimulationResult, TrajectoryPoint, VehicleResult};
+use crate::units::Degrees;
+
+/// Where the output frame puts `(0, 0)`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameOrigin {
+    /// The map origin, positions are unchanged
+    #[default]
+    Map,
+    /// The target the vehicle navigated to, the map origin when the result has no target
+    Target,
+    /// An explicit point in map coordinates, e.g. `(0, map_height)` for top-left screen coordinates
+    Point { x: f64, y: f64 },
+}
+
+/// How headings are measured in the output frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AngleConvention {
+    /// Degrees counter-clockwise from the +x axis (east), the simulator convention
+    #[default]
+    Math,
+    /// Degrees clockwise from the +y axis (north), in [0, 360)
+    Compass,
+}
+
+/// Coordinate frame of exported trajectories
+///
+/// Positions are translated to `origin` then, with `flip_y`, mirrored so y
+/// grows downwards. Headings follow the same mirror and are then expressed in
+/// `angles`; angular rates change sign whenever the mirror or the compass
+/// convention reverses the direction of rotation. The default frame is the
+/// map frame and leaves results untouched.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FrameOptions {
+    #[serde(default)]
+    pub origin: FrameOrigin,
+    #[serde(default)]
+    pub flip_y: bool,
+    #[serde(default)]
+    pub angles: AngleConvention,
+}
+
+impl FrameOptions {
+    /// Origin of the frame in map coordinates, given the target of the result
+    fn origin_point(&self, target: Option<&Point>) -> (f64, f64) {
+        match (&self.origin, target) {
+            (FrameOrigin::Point { x, y }, _) => (*x, *y),
+            (FrameOrigin::Target, Some(target)) => (target.x, target.y),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    fn y_sign(&self) -> f64 {
+        if self.flip_y { -1.0 } else { 1.0 }
+    }
+
+    /// Sign applied to angular rates, -1 when the output rotates the other way
+    pub fn rate_sign(&self) -> f64 {
+        let compass = if self.angles == AngleConvention::Compass { -1.0 } else { 1.0 };
+        self.y_sign() * compass
+    }
+
+    /// A map position in this frame
+    pub fn to_frame(&self, x: f64, y: f64, target: Option<&Point>) -> (f64, f64) {
+        let (ox, oy) = self.origin_point(target);
+        (x - ox, self.y_sign() * (y - oy))
+    }
+
+    /// A position of this frame back in map coordinates
+    pub fn from_frame(&self, x: f64, y: f64, target: Option<&Point>) -> (f64, f64) {
+        let (ox, oy) = self.origin_point(target);
+        (x + ox, self.y_sign() * y + oy)
+    }
+
+    /// A heading in degrees, math convention, in this frame
+    pub fn heading_to_frame(&self, heading: Degrees) -> Degrees {
+        let mirrored = heading * self.y_sign();
+        match self.angles {
+            AngleConvention::Math => mirrored,
+            AngleConvention::Compass => Degrees((90.0 - mirrored.0).rem_euclid(360.0)),
+        }
+    }
+
+    /// A heading of this frame back in degrees, math convention
+    pub fn heading_from_frame(&self, heading: Degrees) -> Degrees {
+        let mirrored = match self.angles {
+            AngleConvention::Math => heading,
+            AngleConvention::Compass => Degrees(90.0) - heading,
+        };
+        mirrored * self.y_sign()
+    }
+
+    /// A trajectory point in this frame
+    pub fn point_to_frame(&self, point: &TrajectoryPoint, target: Option<&Point>) -> TrajectoryPoint {
+        let (x, y) = self.to_frame(point.x, point.y, target);
+        let approach = match (point.approach_x, point.approach_y) {
+            (Some(ax), Some(ay)) => {
+                let (ax, ay) = self.to_frame(ax, ay, target);
+                (Some(ax), Some(ay))
+            }
+            _ => (point.approach_x, point.approach_y),
+        };
+        let rate_sign = self.rate_sign();
+        TrajectoryPoint {
+            x,
+            y,
+            angle: self.heading_to_frame(point.angle),
+            approach_x: approach.0,
+            approach_y: approach.1,
+            desired_heading: point.desired_heading.map(|h| self.heading_to_frame(h)),
+            angular_adjustment: point.angular_adjustment.map(|r| rate_sign * r),
+            commanded_adjustment: point.commanded_adjustment.map(|r| rate_sign * r),
+            angular_velocity: point.angular_velocity.map(|r| rate_sign * r),
+            ..point.clone()
+        }
+    }
+
+    /// The target itself in this frame, the origin when the frame is centered on it
+    pub fn target_to_frame(&self, target: &Point) -> Point {
+        let (x, y) = self.to_frame(target.x, target.y, Some(target));
+        Point::new(x, y)
+    }
+}
+
+impl SimulationResult {
+    /// The result with positions, headings and target expressed in `frame`
+    pub fn transformed(&self, frame: &FrameOptions) -> SimulationResult {
+        let target = self.target.as_ref();
+        SimulationResult {
+            trajectory: self.trajectory.iter().map(|p| frame.point_to_frame(p, target)).collect(),
+            target: target.map(|t| frame.target_to_frame(t)),
+            ..self.clone()
+        }
+    }
+}
+
+impl VehicleResult {
+    /// The result with positions, headings and target expressed in `frame`
+    pub fn transformed(&self, frame: &FrameOptions) -> VehicleResult {
+        let target = self.target.as_ref();
+        VehicleResult {
+            trajectory: self.trajectory.iter().map(|p| frame.point_to_frame(p, target)).collect(),
+            target: target.map(|t| frame.target_to_frame(t)),
+            ..self.clone()
+        }
+    }
+}
+
+impl MultiVehicleSimulationResult {
+    /// Every vehicle expressed in `frame`, `Target` origins use each vehicle's own target
+    pub fn transformed(&self, frame: &FrameOptions) -> MultiVehicleSimulationResult {
+        MultiVehicleSimulationResult {
+            schema_version: self.schema_version,
+            vehicles: self.vehicles.iter().map(|v| v.transformed(frame)).collect(),
+            total_simulation_time: self.total_simulation_time,
+            approach_geometry: self.approach_geometry.as_ref().map(|geometry| geometry.transformed(frame)),
+            meta: self.meta.clone(),
+        }
+    }
+}
+
+impl ApproachGeometry {
+    /// Target and axis expressed in `frame`, `Target` origins use this target
+    pub fn transformed(&self, frame: &FrameOptions) -> ApproachGeometry {
+        let to_frame = |point: &Point| {
+            let (x, y) = frame.to_frame(point.x, point.y, Some(&self.target));
+            Point::new(x, y)
+        };
+        ApproachGeometry {
+            target: frame.target_to_frame(&self.target),
+            axis: [to_frame(&self.axis[0]), to_frame(&self.axis[1])],
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationMetrics, SCHEMA_VERSION};
+
+    fn angle_difference(a: Degrees, b: Degrees) -> f64 {
+        let diff = (a - b).0.rem_euclid(360.0);
+        diff.min(360.0 - diff)
+    }
+
+    fn frames() -> Vec<FrameOptions> {
+        let mut frames = Vec::new();
+        for origin in [FrameOrigin::Map, FrameOrigin::Target, FrameOrigin::Point { x: 0.0, y: 800.0 }] {
+            for flip_y in [false, true] {
+                for angles in [AngleConvention::Math, AngleConvention::Compass] {
+                    frames.push(FrameOptions { origin: origin.clone(), flip_y, angles });
+                }
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn test_frame_round_trip_is_identity() {
+        let target = Point::new(500.0, 700.0);
+        let point = TrajectoryPoint {
+            t: 1.5,
+            x: 123.4,
+            y: 56.7,
+            angle: Degrees(-135.0),
+            velocity: 4.0,
+            approach_x: Some(480.0),
+            approach_y: Some(650.0),
+            desired_heading: Some(Degrees(72.5)),
+            angular_adjustment: Some(-12.0),
+            commanded_adjustment: Some(-30.0),
+            angular_velocity: Some(-11.5),
+            ..TrajectoryPoint::default()
+        };
+
+        for frame in frames() {
+            let there = frame.point_to_frame(&point, Some(&target));
+            let (x, y) = frame.from_frame(there.x, there.y, Some(&target));
+            let (ax, ay) = frame.from_frame(there.approach_x.unwrap(), there.approach_y.unwrap(), Some(&target));
+            assert!((x - point.x).abs() < 1e-9 && (y - point.y).abs() < 1e-9, "{:?}", frame);
+            assert!((ax - 480.0).abs() < 1e-9 && (ay - 650.0).abs() < 1e-9, "{:?}", frame);
+            assert!(angle_difference(frame.heading_from_frame(there.angle), point.angle) < 1e-9, "{:?}", frame);
+            assert!(angle_difference(frame.heading_from_frame(there.desired_heading.unwrap()), Degrees(72.5)) < 1e-9, "{:?}", frame);
+            assert_eq!(there.angular_velocity.unwrap() * frame.rate_sign(), -11.5, "{:?}", frame);
+            assert_eq!((there.t, there.velocity), (point.t, point.velocity));
+        }
+    }
+
+    #[test]
+    fn test_compass_headings_of_the_cardinal_directions() {
+        let compass = FrameOptions { angles: AngleConvention::Compass, ..FrameOptions::default() };
+        let screen = FrameOptions { flip_y: true, ..compass.clone() };
+        // (math degrees, compass degrees, compass degrees with y pointing down)
+        let table = [
+            (0.0, 90.0, 90.0),
+            (90.0, 0.0, 180.0),
+            (180.0, 270.0, 270.0),
+            (-90.0, 180.0, 0.0),
+            (270.0, 180.0, 0.0),
+        ];
+        for (math, expected, expected_screen) in table {
+            let math = Degrees(math);
+            assert!(angle_difference(compass.heading_to_frame(math), Degrees(expected)) < 1e-9, "{} -> {}", math, compass.heading_to_frame(math));
+            assert!(angle_difference(screen.heading_to_frame(math), Degrees(expected_screen)) < 1e-9, "{} -> {}", math, screen.heading_to_frame(math));
+            assert!((0.0..360.0).contains(&compass.heading_to_frame(math).0));
+        }
+    }
+
+    #[test]
+    fn test_target_origin_moves_the_target_to_zero() {
+        let result = SimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicle_type: "Standard".to_string(),
+            trajectory: vec![TrajectoryPoint { x: 500.0, y: 600.0, angle: Degrees(90.0), ..TrajectoryPoint::default() }],
+            metrics: SimulationMetrics::default(),
+            target: Some(Point::new(500.0, 700.0)),
+            meta: None,
+        };
+        let frame = FrameOptions { origin: FrameOrigin::Target, flip_y: true, angles: AngleConvention::Compass };
+
+        let transformed = result.transformed(&frame);
+
+        assert_eq!(transformed.target, Some(Point::new(0.0, 0.0)));
+        assert_eq!((transformed.trajectory[0].x, transformed.trajectory[0].y), (0.0, 100.0));
+        assert_eq!(transformed.trajectory[0].angle, Degrees(180.0));
+        assert_eq!(result.transformed(&FrameOptions::default()).trajectory[0].angle, Degrees(90.0));
+    }
+}
@@ -0,0 +1,218 @@
+// Trajectories and map geometry as a GeoJSON FeatureCollection (RFC 7946)
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::map::Map;
+use crate::simulation::MultiVehicleSimulationResult;
+
+/// WGS84 equatorial radius in meters
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// Placement of the map on the globe
+///
+/// The map origin `(0, 0)` sits at `(origin_lon, origin_lat)`, x grows east and
+/// y grows north. Positions use an equirectangular approximation around the
+/// origin, accurate enough for maps a few kilometers wide.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoRef {
+    pub origin_lon: f64,
+    pub origin_lat: f64,
+    pub meters_per_unit: f64,
+}
+
+impl Default for GeoRef {
+    fn default() -> Self {
+        Self {
+            origin_lon: 0.0,
+            origin_lat: 0.0,
+            meters_per_unit: 1.0,
+        }
+    }
+}
+
+impl GeoRef {
+    pub fn new(origin_lon: f64, origin_lat: f64, meters_per_unit: f64) -> Self {
+        Self { origin_lon, origin_lat, meters_per_unit }
+    }
+
+    /// `[lon, lat]` in degrees of a map position
+    pub fn to_lon_lat(&self, x: f64, y: f64) -> [f64; 2] {
+        let east = x * self.meters_per_unit;
+        let north = y * self.meters_per_unit;
+        let lat = self.origin_lat + (north / EARTH_RADIUS).to_degrees();
+        let lon = self.origin_lon + (east / (EARTH_RADIUS * self.origin_lat.to_radians().cos())).to_degrees();
+        [lon, lat]
+    }
+}
+
+fn feature(geometry: Value, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+/// Serialize a multi-vehicle result and its map as a GeoJSON FeatureCollection
+///
+/// Features, in order: the start zone polygon, the target point, then for every
+/// vehicle its trajectory LineString (`id`, `vehicle_type`, `success`,
+/// `arrival_time`) and its start point (`id`, `vehicle_type`). Trajectories with fewer than two
+/// points have no LineString and empty ones no start point. Maps carry no
+/// obstacles, so no obstacle polygons are emitted.
+pub fn to_geojson(result: &MultiVehicleSimulationResult, map: &Map, georef: GeoRef) -> String {
+    let mut features = Vec::new();
+
+    let zone_height = map.height * map.start_zone.height_percentage;
+    let ring: Vec<[f64; 2]> = [(0.0, 0.0), (map.width, 0.0), (map.width, zone_height), (0.0, zone_height), (0.0, 0.0)]
+        .iter()
+        .map(|&(x, y)| georef.to_lon_lat(x, y))
+        .collect();
+    features.push(feature(
+        json!({ "type": "Polygon", "coordinates": [ring] }),
+        json!({ "kind": "start_zone" }),
+    ));
+
+    let target = &map.target.position;
+    features.push(feature(
+        json!({ "type": "Point", "coordinates": georef.to_lon_lat(target.x, target.y) }),
+        json!({ "kind": "target", "required_angle": map.target.required_angle.to_degrees() }),
+    ));
+
+    for vehicle in &result.vehicles {
+        let coordinates: Vec<[f64; 2]> = vehicle.trajectory.iter().map(|p| georef.to_lon_lat(p.x, p.y)).collect();
+
+        if coordinates.len() >= 2 {
+            features.push(feature(
+                json!({ "type": "LineString", "coordinates": coordinates }),
+                json!({
+                    "kind": "trajectory",
+                    "id": vehicle.id,
+                    "vehicle_type": vehicle.vehicle_type,
+                    "success": vehicle.metrics.success,
+                    "arrival_time": vehicle.metrics.arrival_time,
+                }),
+            ));
+        }
+
+        if let Some(start) = coordinates.first() {
+            features.push(feature(
+                json!({ "type": "Point", "coordinates": start }),
+                json!({ "kind": "start", "id": vehicle.id, "vehicle_type": vehicle.vehicle_type }),
+            ));
+        }
+    }
+
+    json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationMetrics, TrajectoryPoint, VehicleResult, SCHEMA_VERSION};
+
+    fn vehicle(name: &str, points: usize, arrival_time: Option<f64>) -> VehicleResult {
+        VehicleResult {
+            id: format!("{}-1", name.to_lowercase()),
+            vehicle_type: name.to_string(),
+            trajectory: (0..points)
+                .map(|i| TrajectoryPoint {
+                    t: i as f64 * 0.05,
+                    x: 200.0 + i as f64 * 10.0,
+                    y: 40.0 + i as f64 * 20.0,
+                    ..Default::default()
+                })
+                .collect(),
+            metrics: SimulationMetrics {
+                success: arrival_time.is_some(),
+                arrival_time,
+                distance_traveled: 0.0,
+                final_angle_error: 0.0,
+                final_distance_to_target: 0.0,
+                termination: None,
+                ..Default::default()
+            },
+            target: None,
+        }
+    }
+
+    fn parse(result: &MultiVehicleSimulationResult, georef: GeoRef) -> Value {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        serde_json::from_str(&to_geojson(result, &map, georef)).unwrap()
+    }
+
+    #[test]
+    fn test_geojson_has_one_line_and_start_per_vehicle() {
+        let result = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![vehicle("heavy", 30, Some(1.45)), vehicle("agile", 30, None)],
+            total_simulation_time: 1.5,
+            approach_geometry: None,
+            meta: None,
+        };
+        let geojson = parse(&result, GeoRef::default());
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        // start zone + target + (line + start) per vehicle
+        assert_eq!(features.len(), 6);
+
+        let lines: Vec<&Value> = features.iter().filter(|f| f["geometry"]["type"] == "LineString").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["properties"]["vehicle_type"], "heavy");
+        assert_eq!(lines[0]["properties"]["id"], "heavy-1");
+        assert_eq!(lines[0]["properties"]["arrival_time"], 1.45);
+        assert_eq!(lines[1]["properties"]["success"], false);
+        assert!(lines[1]["properties"]["arrival_time"].is_null());
+
+        let zone = &features[0]["geometry"]["coordinates"][0];
+        assert_eq!(zone[0], zone[4]);
+    }
+
+    #[test]
+    fn test_geojson_skips_degenerate_trajectories() {
+        let result = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![vehicle("heavy", 1, None), vehicle("standard", 0, None)],
+            total_simulation_time: 0.0,
+            approach_geometry: None,
+            meta: None,
+        };
+        let features = parse(&result, GeoRef::default())["features"].as_array().unwrap().len();
+        // start zone + target + the start point of the one-point trajectory
+        assert_eq!(features, 3);
+    }
+
+    #[test]
+    fn test_geojson_coordinates_near_origin() {
+        // Valparaíso, 1 m per map unit: the 1000x800 map spans about 0.01°
+        let georef = GeoRef::new(-71.63, -33.04, 1.0);
+        let result = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![vehicle("standard", 30, None)],
+            total_simulation_time: 1.5,
+            approach_geometry: None,
+            meta: None,
+        };
+        let geojson = parse(&result, georef);
+
+        for feature in geojson["features"].as_array().unwrap() {
+            let geometry = &feature["geometry"];
+            let positions: Vec<&Value> = match geometry["type"].as_str().unwrap() {
+                "Point" => vec![&geometry["coordinates"]],
+                "LineString" => geometry["coordinates"].as_array().unwrap().iter().collect(),
+                _ => geometry["coordinates"][0].as_array().unwrap().iter().collect(),
+            };
+            for position in positions {
+                let (lon, lat) = (position[0].as_f64().unwrap(), position[1].as_f64().unwrap());
+                assert!((lon - -71.63).abs() < 0.02 && (lat - -33.04).abs() < 0.02, "({}, {})", lon, lat);
+                assert!(lon >= -71.63 && lat >= -33.04);
+            }
+        }
+
+        let corner = georef.to_lon_lat(0.0, 800.0);
+        let expected = -33.04 + (800.0 / EARTH_RADIUS).to_degrees();
+        assert!((corner[1] - expected).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,17 @@
+// Module for exporting trajectories as plots, animations, GeoJSON and JSON Lines streams, in a chosen frame, and arrival funnel and robustness plots
+
+#[cfg(feature = "cli")]
+mod animation;
+mod frame;
+mod geojson;
+#[cfg(feature = "cli")]
+mod plot;
+mod stream;
+
+pub use frame::{AngleConvention, FrameOptions, FrameOrigin};
+pub use geojson::{to_geojson, GeoRef};
+pub use stream::{is_stream, parse_stream, JsonLinesSink};
+#[cfg(feature = "cli")]
+pub use animation::{render_animation, AnimationFormat, AnimationOptions};
+#[cfg(feature = "cli")]
+pub use plot::{export_arrival_funnel_plot, export_robustness_plot, plot_trajectories, FunnelCurve, PlotOptions};
@@ -0,0 +1,568 @@
+// Trajectory, arrival funnel and robustness plots as PNG or SVG images
+
+use crate::analysis::RobustnessReport;
+use crate::map::{HeadingDistribution, Map};
+use crate::membership_export::ImageFormat;
+use crate::simulation::{Language, MultiVehicleSimulationResult};
+use crate::vehicle::{display_label, VehicleType};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::path::Path;
+
+const IMAGE_WIDTH: u32 = 1200;
+const IMAGE_HEIGHT: u32 = 1000;
+
+/// Fallback palette for duplicated or unknown vehicle types
+const FALLBACK_PALETTE: [RGBColor; 6] = [
+    RGBColor(220, 60, 150),  // Pink
+    RGBColor(0, 170, 170),   // Teal
+    RGBColor(240, 120, 0),   // Orange
+    RGBColor(130, 70, 200),  // Purple
+    RGBColor(120, 160, 0),   // Olive
+    RGBColor(200, 30, 30),   // Red
+];
+
+/// Plot color of a vehicle type by id, or display name in older results
+/// (darker variants of the visualizer colors for a white background)
+fn base_plot_color(vehicle_type: &str) -> Option<RGBColor> {
+    match VehicleType::parse(vehicle_type)? {
+        VehicleType::Heavy => Some(RGBColor(215, 160, 0)),
+        VehicleType::Standard => Some(RGBColor(40, 170, 40)),
+        VehicleType::Agile => Some(RGBColor(40, 90, 220)),
+        VehicleType::UltraAgile => Some(RGBColor(190, 40, 190)),
+    }
+}
+
+/// Assign one color per vehicle type in order, duplicates fall back to the palette
+fn assign_colors<'a>(vehicle_types: impl IntoIterator<Item = &'a str>) -> Vec<RGBColor> {
+    let mut used: Vec<RGBColor> = Vec::new();
+
+    for (idx, vehicle_type) in vehicle_types.into_iter().enumerate() {
+        let color = base_plot_color(vehicle_type)
+            .filter(|c| !used.contains(c))
+            .or_else(|| FALLBACK_PALETTE.iter().copied().find(|c| !used.contains(c)))
+            .unwrap_or(FALLBACK_PALETTE[idx % FALLBACK_PALETTE.len()]);
+        used.push(color);
+    }
+
+    used
+}
+
+/// One color per vehicle of the result
+fn assign_plot_colors(result: &MultiVehicleSimulationResult) -> Vec<RGBColor> {
+    assign_colors(result.vehicles.iter().map(|vehicle| vehicle.vehicle_type.as_str()))
+}
+
+/// Options of a trajectory figure
+#[derive(Debug, Clone, Copy)]
+pub struct PlotOptions {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    /// Mark every vehicle path with its time every N seconds, None to disable
+    pub time_label_interval: Option<f64>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            width: IMAGE_WIDTH,
+            height: IMAGE_HEIGHT,
+            format: ImageFormat::Png,
+            time_label_interval: None,
+        }
+    }
+}
+
+/// Render every trajectory of a multi-vehicle result into a figure
+///
+/// The figure shows the map boundary, the start zone, the target with its
+/// required arrival angle, each full trajectory with its start and final
+/// position, and a legend with the arrival metrics of each vehicle. It only
+/// depends on the result data, so it can be called from the visualizer and
+/// from the CLI bins alike.
+pub fn plot_trajectories<P: AsRef<Path>>(
+    result: &MultiVehicleSimulationResult,
+    map: &Map,
+    options: PlotOptions,
+    output_path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = output_path.as_ref().parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let size = (options.width, options.height);
+    match options.format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(output_path.as_ref(), size).into_drawing_area();
+            draw_trajectories(&root, result, map, &options, None)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(output_path.as_ref(), size).into_drawing_area();
+            draw_trajectories(&root, result, map, &options, None)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the figure of `plot_trajectories`, or with `until` a frame of the
+/// animation: the paths up to that time, each vehicle at its position then
+/// and the time in the caption
+pub(super) fn draw_trajectories<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    result: &MultiVehicleSimulationResult,
+    map: &Map,
+    options: &PlotOptions,
+    until: Option<f64>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let caption = match until {
+        Some(t) => format!("Trayectorias de Navegación Difusa - t = {:.1} s", t),
+        None => "Trayectorias de Navegación Difusa".to_string(),
+    };
+    let mut chart = ChartBuilder::on(root)
+        .caption(caption, ("sans-serif", 36))
+        .margin(20)
+        .x_label_area_size(45)
+        .y_label_area_size(55)
+        .build_cartesian_2d(0.0..map.width, 0.0..map.height)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("X (unidades)")
+        .y_desc("Y (unidades)")
+        .light_line_style(WHITE.mix(0.0))
+        .draw()?;
+
+    // Map boundary
+    chart.draw_series(std::iter::once(Rectangle::new(
+        [(0.0, 0.0), (map.width, map.height)],
+        BLACK.stroke_width(2),
+    )))?;
+
+    // Start zone
+    let start_zone_height = map.height * map.start_zone.height_percentage;
+    chart.draw_series(std::iter::once(Rectangle::new(
+        [(0.0, 0.0), (map.width, start_zone_height)],
+        RGBColor(50, 160, 50).mix(0.2).filled(),
+    )))?;
+
+    // Target square and required arrival direction
+    let target = &map.target.position;
+    let half = 25.0;
+    chart.draw_series(std::iter::once(Rectangle::new(
+        [(target.x - half, target.y - half), (target.x + half, target.y + half)],
+        RED.mix(0.35).filled(),
+    )))?;
+    chart.draw_series(std::iter::once(Rectangle::new(
+        [(target.x - half, target.y - half), (target.x + half, target.y + half)],
+        RED.stroke_width(2),
+    )))?;
+
+    let arrow_len = 45.0;
+    let angle = map.target.required_angle;
+    chart.draw_series(std::iter::once(PathElement::new(
+        vec![
+            (target.x, target.y),
+            (target.x + arrow_len * angle.cos(), target.y + arrow_len * angle.sin()),
+        ],
+        RGBColor(230, 150, 0).stroke_width(3),
+    )))?;
+
+    // Trajectories, start markers and final positions
+    let colors = assign_plot_colors(result);
+    for (idx, vehicle) in result.vehicles.iter().enumerate() {
+        let color = colors[idx];
+        let shown = match until {
+            Some(t) => vehicle.trajectory.partition_point(|p| p.t <= t + 1e-9),
+            None => vehicle.trajectory.len(),
+        };
+        let trajectory = &vehicle.trajectory[..shown];
+        let points: Vec<(f64, f64)> = trajectory.iter().map(|p| (p.x, p.y)).collect();
+
+        let status = match vehicle.metrics.arrival_time {
+            Some(t) => format!("llegó en {:.1}s", t),
+            None => "no llegó".to_string(),
+        };
+        let label = format!(
+            "{}. {} - {}, Δ ángulo {:.1}°, recorrido {:.0} u",
+            idx + 1,
+            display_label(&vehicle.vehicle_type, Language::Spanish),
+            status,
+            vehicle.metrics.final_angle_error,
+            vehicle.metrics.distance_traveled
+        );
+
+        chart
+            .draw_series(LineSeries::new(points.iter().copied(), color.stroke_width(2)))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(3)));
+
+        if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+            chart.draw_series(std::iter::once(Cross::new(first, 6, color.stroke_width(2))))?;
+            chart.draw_series(std::iter::once(Circle::new(last, 6, color.filled())))?;
+        }
+
+        // Moving vehicle: a larger marker with a tick along its heading
+        if let (Some(_), Some(point)) = (until, trajectory.last()) {
+            let heading = point.angle.to_radians();
+            let tick = 30.0;
+            chart.draw_series(std::iter::once(Circle::new((point.x, point.y), 9, color.stroke_width(3))))?;
+            chart.draw_series(std::iter::once(PathElement::new(
+                vec![(point.x, point.y), (point.x + tick * heading.cos(), point.y + tick * heading.sin())],
+                color.stroke_width(3),
+            )))?;
+        }
+
+        // Time marks along the path
+        if let Some(interval) = options.time_label_interval.filter(|&i| i > 0.0) {
+            let mut next_mark = interval;
+            for point in trajectory {
+                if point.t + 1e-9 >= next_mark {
+                    chart.draw_series(std::iter::once(
+                        EmptyElement::at((point.x, point.y))
+                            + Circle::new((0, 0), 3, color.filled())
+                            + Text::new(format!("{:.0}s", point.t), (5, -12), ("sans-serif", 13).into_font().color(&color)),
+                    ))?;
+                    next_mark += interval;
+                }
+            }
+        }
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.85))
+        .border_style(BLACK)
+        .label_font(("sans-serif", 16))
+        .draw()?;
+
+    Ok(())
+}
+
+/// Arrival funnel of one group of benchmark runs, see `stats::arrival_funnel`
+#[derive(Debug, Clone, Copy)]
+pub struct FunnelCurve<'a> {
+    /// Vehicle type id, picks the color and the legend name
+    pub vehicle_type: &'a str,
+    /// Start heading mode, shown in the legend of a heading sweep
+    pub heading: Option<HeadingDistribution>,
+    /// (t, fraction arrived) points
+    pub points: &'a [(f64, f64)],
+}
+
+/// Render the arrival funnels of a benchmark, one curve per group, as an SVG
+/// for a `.svg` path and a PNG otherwise
+///
+/// The x axis spans the longest curve, the y axis the percentage of runs
+/// arrived from 0 to 100.
+pub fn export_arrival_funnel_plot<P: AsRef<Path>>(
+    curves: &[FunnelCurve],
+    output_path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = output_path.as_ref();
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let format = path.extension().and_then(|ext| ImageFormat::parse(&ext.to_string_lossy())).unwrap_or(ImageFormat::Png);
+    let size = (IMAGE_WIDTH, IMAGE_HEIGHT * 3 / 4);
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(path, size).into_drawing_area();
+            draw_arrival_funnel(&root, curves)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(path, size).into_drawing_area();
+            draw_arrival_funnel(&root, curves)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_arrival_funnel<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    curves: &[FunnelCurve],
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let max_time = curves
+        .iter()
+        .filter_map(|curve| curve.points.last().map(|p| p.0))
+        .fold(0.0, f64::max);
+    let mut chart = ChartBuilder::on(root)
+        .caption("Embudo de llegada", ("sans-serif", 36))
+        .margin(20)
+        .x_label_area_size(45)
+        .y_label_area_size(55)
+        .build_cartesian_2d(0.0..max_time.max(1.0), 0.0..100.0)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Tiempo de simulación (s)")
+        .y_desc("Corridas que llegaron (%)")
+        .draw()?;
+
+    let colors = assign_colors(curves.iter().map(|curve| curve.vehicle_type));
+    for (curve, &color) in curves.iter().zip(&colors) {
+        let name = display_label(curve.vehicle_type, Language::Spanish);
+        let label = match curve.heading {
+            Some(heading) => format!("{} ({})", name, heading),
+            None => name.to_string(),
+        };
+        // Steps, the fraction only changes at the bin times
+        let steps = curve.points.windows(2).flat_map(|pair| [(pair[1].0, pair[0].1 * 100.0), (pair[1].0, pair[1].1 * 100.0)]);
+        let points = curve.points.first().map(|&(t, fraction)| (t, fraction * 100.0)).into_iter().chain(steps);
+        chart
+            .draw_series(LineSeries::new(points, color.stroke_width(3)))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(3)));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .background_style(WHITE.mix(0.85))
+        .border_style(BLACK)
+        .label_font(("sans-serif", 16))
+        .draw()?;
+
+    Ok(())
+}
+
+/// Render the success rate of a robustness sweep against the disturbance
+/// level, one curve per vehicle type, as an SVG for a `.svg` path and a PNG otherwise
+pub fn export_robustness_plot<P: AsRef<Path>>(
+    report: &RobustnessReport,
+    output_path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = output_path.as_ref();
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let format = path.extension().and_then(|ext| ImageFormat::parse(&ext.to_string_lossy())).unwrap_or(ImageFormat::Png);
+    let size = (IMAGE_WIDTH, IMAGE_HEIGHT * 3 / 4);
+    match format {
+        ImageFormat::Png => {
+            let root = BitMapBackend::new(path, size).into_drawing_area();
+            draw_robustness(&root, report)?;
+            root.present()?;
+        }
+        ImageFormat::Svg => {
+            let root = SVGBackend::new(path, size).into_drawing_area();
+            draw_robustness(&root, report)?;
+            root.present()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn draw_robustness<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    report: &RobustnessReport,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let levels = report.curves.iter().flat_map(|curve| curve.levels.iter().map(|level| level.level));
+    let (min_level, max_level) = levels.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), level| (min.min(level), max.max(level)));
+    let (min_level, max_level) = if min_level < max_level { (min_level, max_level) } else { (0.0, max_level.max(0.0) + 1.0) };
+    let mut chart = ChartBuilder::on(root)
+        .caption("Robustez del controlador", ("sans-serif", 36))
+        .margin(20)
+        .x_label_area_size(45)
+        .y_label_area_size(55)
+        .build_cartesian_2d(min_level..max_level, 0.0..100.0)?;
+
+    chart
+        .configure_mesh()
+        .x_desc(report.axis.label())
+        .y_desc("Tasa de éxito (%)")
+        .draw()?;
+
+    let colors = assign_colors(report.curves.iter().map(|curve| curve.vehicle_type.as_str()));
+    for (curve, &color) in report.curves.iter().zip(&colors) {
+        let points: Vec<(f64, f64)> = curve.levels.iter().map(|level| (level.level, level.success_rate)).collect();
+        chart
+            .draw_series(LineSeries::new(points.clone(), color.stroke_width(3)))?
+            .label(display_label(&curve.vehicle_type, Language::Spanish))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(3)));
+        chart.draw_series(points.into_iter().map(|point| Circle::new(point, 5, color.filled())))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerLeft)
+        .background_style(WHITE.mix(0.85))
+        .border_style(BLACK)
+        .label_font(("sans-serif", 16))
+        .draw()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationMetrics, TrajectoryPoint, VehicleResult, SCHEMA_VERSION};
+    use crate::units::Degrees;
+
+    fn sample_result() -> MultiVehicleSimulationResult {
+        let trajectory: Vec<TrajectoryPoint> = (0..50)
+            .map(|i| TrajectoryPoint {
+                t: i as f64 * 0.05,
+                x: 200.0 + i as f64 * 6.0,
+                y: 40.0 + i as f64 * 13.0,
+                angle: Degrees(65.0),
+                velocity: 5.0,
+                distance_to_target: 600.0 - i as f64 * 12.0,
+                ..Default::default()
+            })
+            .collect();
+
+        let vehicle = |name: &str| VehicleResult {
+            id: format!("{}-1", name.to_lowercase()),
+            vehicle_type: name.to_string(),
+            trajectory: trajectory.clone(),
+            metrics: SimulationMetrics {
+                success: false,
+                arrival_time: None,
+                distance_traveled: 700.0,
+                final_angle_error: 25.0,
+                final_distance_to_target: 12.0,
+                termination: None,
+                ..Default::default()
+            },
+            target: None,
+        };
+
+        MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: vec![vehicle("heavy"), vehicle("heavy"), vehicle("agile")],
+            total_simulation_time: 2.5,
+            approach_geometry: None,
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn test_plot_trajectories_writes_png() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let path = std::env::temp_dir().join(format!("trajectory_plot_{}.png", std::process::id()));
+
+        plot_trajectories(&sample_result(), &map, PlotOptions::default(), &path).unwrap();
+
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).ok();
+        assert!(size > 10_000, "PNG too small: {} bytes", size);
+    }
+
+    #[test]
+    fn test_plot_trajectories_svg_contains_vehicle_names() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let path = std::env::temp_dir().join(format!("trajectory_plot_{}.svg", std::process::id()));
+        let options = PlotOptions {
+            format: ImageFormat::Svg,
+            time_label_interval: Some(1.0),
+            ..Default::default()
+        };
+
+        plot_trajectories(&sample_result(), &map, options, &path).unwrap();
+
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(svg.contains("Barco"));
+        assert!(svg.contains("Avión"));
+        // Time marks at 1s and 2s of the 2.45s sample trajectory
+        let lines: Vec<&str> = svg.lines().map(str::trim).collect();
+        assert!(lines.contains(&"1s") && lines.contains(&"2s"));
+    }
+
+    #[test]
+    fn test_arrival_funnel_plot_has_one_curve_per_vehicle_type() {
+        let heavy = [(0.0, 0.0), (50.0, 0.25), (100.0, 0.5)];
+        let agile = [(0.0, 0.0), (50.0, 0.75), (100.0, 1.0)];
+        let curves = [
+            FunnelCurve { vehicle_type: "heavy", heading: None, points: &heavy },
+            FunnelCurve { vehicle_type: "agile", heading: None, points: &agile },
+        ];
+        let path = std::env::temp_dir().join(format!("arrival_funnel_{}.svg", std::process::id()));
+
+        export_arrival_funnel_plot(&curves, &path).unwrap();
+
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(svg.contains("Barco") && svg.contains("Avión"));
+        // Curve and legend mark of each, in the vehicle colors
+        assert_eq!(svg.matches("#D7A000").count(), 2);
+        assert_eq!(svg.matches("#285ADC").count(), 2);
+    }
+
+    #[test]
+    fn test_robustness_plot_has_one_curve_per_vehicle_type() {
+        use crate::analysis::{RobustnessAxis, RobustnessCurve, RobustnessLevel};
+
+        let curve = |vehicle_type: &str, rates: [f64; 2]| RobustnessCurve {
+            vehicle_type: vehicle_type.to_string(),
+            levels: [0.0, 10.0]
+                .into_iter()
+                .zip(rates)
+                .map(|(level, success_rate)| RobustnessLevel {
+                    level,
+                    runs: 4,
+                    successes: (success_rate / 25.0) as usize,
+                    success_rate,
+                    mean_final_angle_error: 3.0,
+                    mean_dropout_seconds: Some(level),
+                })
+                .collect(),
+        };
+        let report = RobustnessReport {
+            axis: RobustnessAxis::Dropout,
+            iterations: 4,
+            seed: 1,
+            curves: vec![curve("heavy", [100.0, 50.0]), curve("agile", [100.0, 75.0])],
+        };
+        let path = std::env::temp_dir().join(format!("robustness_{}.svg", std::process::id()));
+
+        export_robustness_plot(&report, &path).unwrap();
+
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(svg.contains("Barco") && svg.contains("Avión"));
+        assert!(svg.contains(RobustnessAxis::Dropout.label()));
+        assert!(svg.contains("#D7A000") && svg.contains("#285ADC"));
+    }
+
+    #[test]
+    fn test_plot_colors_distinct_for_duplicates() {
+        let colors = assign_plot_colors(&sample_result());
+        assert_eq!(colors.len(), 3);
+        assert_ne!(colors[0], colors[1]);
+        assert_ne!(colors[1], colors[2]);
+    }
+}
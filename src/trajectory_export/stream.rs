@@ -0,0 +1,189 @@
+// JSON Lines files of streamed runs: the sink a `TrajectoryStream` writes to and
+// the reader that turns a stream, finished or cut short, back into a result
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::{FuzzyNavError, SerializationError};
+use crate::simulation::{migrations, MultiVehicleSimulationResult, StreamRecord, TrajectorySink, SCHEMA_VERSION};
+
+fn file_error(path: &str, error: std::io::Error) -> FuzzyNavError {
+    SerializationError::File { path: path.to_string(), message: error.to_string() }.into()
+}
+
+/// Writes one `StreamRecord` per line, synced to disk at every checkpoint
+pub struct JsonLinesSink {
+    path: String,
+    writer: BufWriter<File>,
+}
+
+impl JsonLinesSink {
+    /// Create or truncate the file at `path`
+    pub fn create(path: &str) -> Result<Self, FuzzyNavError> {
+        let file = File::create(path).map_err(|e| file_error(path, e))?;
+        Ok(Self { path: path.to_string(), writer: BufWriter::new(file) })
+    }
+}
+
+impl TrajectorySink for JsonLinesSink {
+    fn write(&mut self, record: &StreamRecord) -> Result<(), FuzzyNavError> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n").map_err(|e| file_error(&self.path, e))
+    }
+
+    fn flush(&mut self) -> Result<(), FuzzyNavError> {
+        self.writer.flush().map_err(|e| file_error(&self.path, e))?;
+        self.writer.get_ref().sync_data().map_err(|e| file_error(&self.path, e))
+    }
+}
+
+/// True when `text` starts with a stream record rather than a whole JSON result
+pub fn is_stream(text: &str) -> bool {
+    let first = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    serde_json::from_str::<StreamRecord>(first).is_ok()
+}
+
+/// Rebuild the result of a streamed run
+///
+/// Without the `Summary` footer (a run that crashed) the metrics are the start
+/// ones, without a termination, and the time is that of the last point. A
+/// truncated last line, the write a crash interrupted, is skipped.
+pub fn parse_stream(text: &str) -> Result<MultiVehicleSimulationResult, FuzzyNavError> {
+    let mut start = None;
+    let mut summary = None;
+    let mut points = Vec::new();
+
+    let lines: Vec<&str> = text.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = match serde_json::from_str::<StreamRecord>(line) {
+            Ok(record) => record,
+            Err(_) if index + 1 == lines.len() && !text.ends_with('\n') => break,
+            Err(e) => return Err(SerializationError::InvalidStreamLine { line: index + 1, message: e.to_string() }.into()),
+        };
+        match record {
+            StreamRecord::Start { result } => start = Some(result),
+            StreamRecord::Summary { result } => summary = Some(result),
+            StreamRecord::Point { vehicle, point } => points.push((index + 1, vehicle, point)),
+        }
+    }
+
+    let finished = summary.is_some();
+    let mut result = summary.or(start).ok_or(SerializationError::MissingField("start"))?;
+    // Records of older streams read with the defaults of their missing fields, the rebuilt result is current
+    result.schema_version = SCHEMA_VERSION;
+    for (line, vehicle, point) in points {
+        let Some(target) = result.vehicles.get_mut(vehicle) else {
+            let message = format!("vehicle {} of {}", vehicle, result.vehicles.len());
+            return Err(SerializationError::InvalidStreamLine { line, message }.into());
+        };
+        if !finished {
+            result.total_simulation_time = result.total_simulation_time.max(point.t);
+        }
+        target.trajectory.push(point);
+    }
+    Ok(result)
+}
+
+impl MultiVehicleSimulationResult {
+    /// Read a result saved as one JSON document or streamed as JSON Lines
+    ///
+    /// Documents recorded by an older version are upgraded to the current
+    /// layout first, see `migrations`.
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, FuzzyNavError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| file_error(&path.display().to_string(), e))?;
+        if is_stream(&text) {
+            parse_stream(&text)
+        } else {
+            migrations::read_result(serde_json::from_str(&text)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, Point};
+    use crate::simulation::{MultiVehicleSimulation, SimulationBuilder, TrajectoryStream};
+    use crate::units::Radians;
+    use crate::vehicle::VehicleType;
+
+    fn run(stream_path: Option<&str>) -> MultiVehicleSimulationResult {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let simulations = [VehicleType::Heavy, VehicleType::Agile]
+            .into_iter()
+            .map(|vehicle_type| {
+                SimulationBuilder::new(map.clone(), vehicle_type)
+                    .start_position(Point::new(300.0, 100.0))
+                    .start_angle(Radians::ZERO)
+                    .build()
+            })
+            .collect();
+        let mut multi = MultiVehicleSimulation::new(simulations, 0.05, 20.0);
+        if let Some(path) = stream_path {
+            let sink = JsonLinesSink::create(path).unwrap();
+            multi = multi.with_stream(TrajectoryStream::new(sink).flush_interval(2.0));
+        }
+        multi.run();
+        multi.finish().unwrap()
+    }
+
+    #[test]
+    fn test_streamed_file_reads_back_as_the_in_memory_result() {
+        let path = std::env::temp_dir().join(format!("trajectory_stream_{}.jsonl", std::process::id()));
+        let streamed = run(Some(path.to_str().unwrap()));
+        assert!(streamed.vehicles.iter().all(|v| v.trajectory.is_empty()));
+
+        let expected = run(None);
+        let read = MultiVehicleSimulationResult::from_json_file(&path).unwrap();
+        assert_eq!(read.total_simulation_time, expected.total_simulation_time);
+        for (a, b) in read.vehicles.iter().zip(&expected.vehicles) {
+            assert_eq!(a.vehicle_type, b.vehicle_type);
+            assert_eq!(a.metrics.termination, b.metrics.termination);
+            assert_eq!(a.trajectory.len(), b.trajectory.len());
+            let (last, expected_last) = (a.trajectory.last().unwrap(), b.trajectory.last().unwrap());
+            assert!((last.x - expected_last.x).abs() < 1e-9 && (last.y - expected_last.y).abs() < 1e-9);
+        }
+
+        // A crash leaves no summary and possibly half a line
+        let text = fs::read_to_string(&path).unwrap();
+        let cut = text.find(r#"{"record":"summary""#).unwrap();
+        let crashed = parse_stream(&text[..cut - 40]).unwrap();
+        assert!(crashed.vehicles.iter().all(|v| v.metrics.termination.is_none()));
+        let points: usize = crashed.vehicles.iter().map(|v| v.trajectory.len()).sum();
+        let total: usize = read.vehicles.iter().map(|v| v.trajectory.len()).sum();
+        assert_eq!(points, total - 1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_json_document_still_reads() {
+        let path = std::env::temp_dir().join(format!("trajectory_document_{}.json", std::process::id()));
+        let expected = run(None);
+        fs::write(&path, serde_json::to_string_pretty(&expected).unwrap()).unwrap();
+
+        let read = MultiVehicleSimulationResult::from_json_file(&path).unwrap();
+        assert_eq!(read.vehicles.len(), 2);
+        assert_eq!(read.vehicles[1].trajectory.len(), expected.vehicles[1].trajectory.len());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_stream_lines_are_reported() {
+        let start = r#"{"record":"start","result":{"vehicles":[],"total_simulation_time":0.0}}"#;
+        let point = r#"{"record":"point","vehicle":3,"t":0.1,"x":1.0,"y":1.0,"angle":0.0,"velocity":1.0,"distance_to_target":5.0}"#;
+        let error = parse_stream(&format!("{}\n{}\n", start, point)).unwrap_err();
+        assert!(error.to_string().contains("Line 2"), "{}", error);
+
+        let error = parse_stream(&format!("{}\nnot json\n{}\n", start, point)).unwrap_err();
+        assert!(error.to_string().contains("Line 2"), "{}", error);
+
+        assert!(parse_stream(&format!("{}\n", point)).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// Units module - Angles typed by their unit
+//
+// Headings are radians inside the simulation and degrees in recorded
+// trajectories, the API and the visualizer. `Radians` and `Degrees` keep the
+// compiler from mixing them: converting is always an explicit `to_degrees` or
+// `to_radians`. Both serialize as the bare number, so JSON keeps its shape.
+
+use std::f64::consts::PI;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// Angle in radians, counterclockwise from +x
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Radians(pub f64);
+
+/// Angle in degrees, counterclockwise from +x
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Degrees(pub f64);
+
+impl Radians {
+    pub const ZERO: Radians = Radians(0.0);
+
+    pub fn to_degrees(self) -> Degrees {
+        Degrees(self.0.to_degrees())
+    }
+
+    /// Same angle in [-π, π], NaN for non-finite angles
+    pub fn normalized(self) -> Radians {
+        if (-PI..=PI).contains(&self.0) {
+            return self;
+        }
+
+        // Constant time for huge angles
+        let wrapped = self.0.rem_euclid(2.0 * PI);
+        Radians(if wrapped > PI { wrapped - 2.0 * PI } else { wrapped })
+    }
+
+    pub fn sin(self) -> f64 {
+        self.0.sin()
+    }
+
+    pub fn cos(self) -> f64 {
+        self.0.cos()
+    }
+
+    pub fn sin_cos(self) -> (f64, f64) {
+        self.0.sin_cos()
+    }
+}
+
+impl Degrees {
+    pub const ZERO: Degrees = Degrees(0.0);
+
+    pub fn to_radians(self) -> Radians {
+        Radians(self.0.to_radians())
+    }
+
+    /// Same angle in [-180, 180]
+    pub fn normalized(self) -> Degrees {
+        self.to_radians().normalized().to_degrees()
+    }
+}
+
+impl From<Degrees> for Radians {
+    fn from(degrees: Degrees) -> Self {
+        degrees.to_radians()
+    }
+}
+
+impl From<Radians> for Degrees {
+    fn from(radians: Radians) -> Self {
+        radians.to_degrees()
+    }
+}
+
+/// Arithmetic within one unit and the helpers of `f64` that keep it
+macro_rules! angle_ops {
+    ($unit:ident) => {
+        impl $unit {
+            pub fn abs(self) -> $unit {
+                $unit(self.0.abs())
+            }
+
+            pub fn is_finite(self) -> bool {
+                self.0.is_finite()
+            }
+
+            pub fn is_nan(self) -> bool {
+                self.0.is_nan()
+            }
+        }
+
+        impl Add for $unit {
+            type Output = $unit;
+            fn add(self, other: $unit) -> $unit {
+                $unit(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = $unit;
+            fn sub(self, other: $unit) -> $unit {
+                $unit(self.0 - other.0)
+            }
+        }
+
+        impl AddAssign for $unit {
+            fn add_assign(&mut self, other: $unit) {
+                self.0 += other.0;
+            }
+        }
+
+        impl SubAssign for $unit {
+            fn sub_assign(&mut self, other: $unit) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = $unit;
+            fn neg(self) -> $unit {
+                $unit(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $unit {
+            type Output = $unit;
+            fn mul(self, factor: f64) -> $unit {
+                $unit(self.0 * factor)
+            }
+        }
+
+        impl Div<f64> for $unit {
+            type Output = $unit;
+            fn div(self, divisor: f64) -> $unit {
+                $unit(self.0 / divisor)
+            }
+        }
+
+        /// The bare number, format options apply to it
+        impl std::fmt::Display for $unit {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+angle_ops!(Radians);
+angle_ops!(Degrees);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::HeadingDistribution;
+    use crate::simulation::{MultiVehicleSimulationResult, Scenario};
+
+    #[test]
+    fn test_conversions_round_trip() {
+        for degrees in [-720.0, -180.0, -45.5, 0.0, 1e-9, 90.0, 179.99, 360.0, 1e6] {
+            let there = Degrees(degrees).to_radians();
+            assert!((there.to_degrees().0 - degrees).abs() <= 1e-9 * degrees.abs().max(1.0), "{}", degrees);
+            assert_eq!(Radians::from(Degrees(degrees)), there);
+        }
+        assert_eq!(Degrees(90.0).to_radians(), Radians(PI / 2.0));
+        assert_eq!(Degrees::from(Radians(PI)), Degrees(180.0));
+    }
+
+    #[test]
+    fn test_normalized_wraps_into_half_turn() {
+        assert_eq!(Radians(PI).normalized(), Radians(PI));
+        assert!((Radians(7.0).normalized().0 - (7.0 - 2.0 * PI)).abs() < 1e-12);
+        assert!((Degrees(270.0).normalized().0 + 90.0).abs() < 1e-9);
+        assert!(Radians(f64::INFINITY).normalized().is_nan());
+    }
+
+    #[test]
+    fn test_serializes_as_the_bare_number() {
+        assert_eq!(serde_json::to_string(&Degrees(75.5)).unwrap(), "75.5");
+        assert_eq!(serde_json::from_str::<Radians>("1.25").unwrap(), Radians(1.25));
+        assert_eq!(format!("{:.1}", Degrees(89.96)), "90.0");
+    }
+
+    #[test]
+    fn test_recorded_results_keep_their_json_shape() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/result_v2.json")).unwrap();
+        let result: MultiVehicleSimulationResult = serde_json::from_value(fixture.clone()).unwrap();
+
+        assert_eq!(result.vehicles[0].trajectory[1].angle, Degrees(75.4));
+        // Metrics gained defaulted fields since v2, the trajectories did not
+        let written = serde_json::to_value(&result).unwrap();
+        for (vehicle, recorded) in written["vehicles"].as_array().unwrap().iter().zip(fixture["vehicles"].as_array().unwrap()) {
+            assert_eq!(vehicle["trajectory"], recorded["trajectory"]);
+        }
+    }
+
+    #[test]
+    fn test_scenarios_keep_their_json_shape_and_convert_once() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/scenario_v2.json")).unwrap();
+        let scenario: Scenario = serde_json::from_value(fixture.clone()).unwrap();
+
+        assert_eq!(scenario.vehicles[0].start_angle, Some(Degrees(60.0)));
+        assert_eq!(scenario.heading, HeadingDistribution::Fixed(Degrees(-45.0)));
+        assert_eq!(scenario.criteria.angle_tolerance, Degrees(5.0));
+        assert_eq!(serde_json::to_value(&scenario).unwrap(), fixture);
+
+        // Degrees in the file, radians in the vehicle state
+        let multi = scenario.build().unwrap();
+        let [fixed, random] = [&multi.simulations[0], &multi.simulations[1]].map(|sim| sim.vehicle.state.angle);
+        assert_eq!(fixed, Degrees(60.0).to_radians());
+        assert_eq!(random, Degrees(-45.0).to_radians());
+    }
+}
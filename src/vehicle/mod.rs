@@ -1,43 +1,144 @@
 // Vehicle module - Vehicle structures, types and configuration presets
 
+use crate::error::SimulationError;
 use crate::map::Point;
+use crate::simulation::{Language, DEFAULT_VELOCITY_FRACTION};
+use crate::units::Radians;
 use serde::{Serialize, Deserialize};
 
 /// Physical and performance characteristics of a vehicle
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VehicleCharacteristics {
     pub size: f64,                    // Radius or characteristic dimension
-    pub maneuverability: f64,         // Maximum turning rate (degrees/second)
+    pub maneuverability: f64,         // Maximum turning rate (radians/second)
     pub max_velocity: f64,            // Maximum speed (units/second)
     pub max_acceleration: f64,        // Maximum acceleration (units/second²)
+    /// Cruise velocity as a fraction of `max_velocity`, `DEFAULT_VELOCITY_FRACTION` in characteristics recorded before it existed
+    #[serde(default = "default_cruise_fraction")]
+    pub cruise_fraction: f64,
+    /// How fast the applied turn rate can change (radians/second²), `None` turns at the commanded rate at once
+    ///
+    /// Models the steering actuator: the applied rate slews toward the command
+    /// at this limit, see `preset_max_angular_acceleration` for plausible values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_angular_acceleration: Option<f64>,
+}
+
+fn default_cruise_fraction() -> f64 { DEFAULT_VELOCITY_FRACTION }
+
+impl VehicleCharacteristics {
+    /// Every field positive and finite, the cruise fraction at most 1
+    ///
+    /// Caller-supplied characteristics go through it before they reach the
+    /// membership functions, which panic on the sets a negative maneuverability builds.
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let fields = [
+            ("size", self.size),
+            ("maneuverability", self.maneuverability),
+            ("max_velocity", self.max_velocity),
+            ("max_acceleration", self.max_acceleration),
+            ("cruise_fraction", self.cruise_fraction),
+        ];
+        let slew = self.max_angular_acceleration.map(|value| ("max_angular_acceleration", value));
+        if let Some((field, value)) = fields.into_iter().chain(slew).find(|(_, value)| !(value.is_finite() && *value > 0.0)) {
+            return Err(SimulationError::InvalidCharacteristic { field, value });
+        }
+        if self.cruise_fraction > 1.0 {
+            return Err(SimulationError::InvalidVelocityFraction(self.cruise_fraction));
+        }
+        Ok(())
+    }
 }
 
 /// Dynamic state of a vehicle
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VehicleState {
     pub position: Point,
-    pub angle: f64,                   // Orientation in radians (0 = east, π/2 = north)
+    pub angle: Radians,               // Orientation (0 = east, π/2 = north)
     pub velocity: f64,                // Current speed (units/second)
 }
 
 /// Vehicle types with predefined characteristics
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+///
+/// Serialized as the stable `id`, files recorded with the API identifiers
+/// ("Heavy", "UltraAgile") still read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VehicleType {
+    #[serde(alias = "Heavy")]
     Heavy,          // Tipo A: Vehículo Pesado
+    #[serde(alias = "Standard")]
     Standard,       // Tipo B: Vehículo Estándar
+    #[serde(alias = "Agile")]
     Agile,          // Tipo C: Vehículo Ágil
+    #[serde(alias = "UltraAgile")]
     UltraAgile,     // Tipo D: Vehículo Ultra-Maniobrable
 }
 
 impl VehicleType {
-    pub fn name(&self) -> &str {
+    /// Every available vehicle type, in preset order
+    pub const ALL: [VehicleType; 4] = [
+        VehicleType::Heavy,
+        VehicleType::Standard,
+        VehicleType::Agile,
+        VehicleType::UltraAgile,
+    ];
+
+    /// Parse an `id`, an API identifier ("Heavy", "UltraAgile") or a display name
+    /// in any language ("Barco", "Ship"), case insensitive
+    ///
+    /// Display names are accepted so results recorded when they were the type
+    /// labels still read.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let by_id = match name.as_str() {
+            "heavy" => Some(VehicleType::Heavy),
+            "standard" => Some(VehicleType::Standard),
+            "agile" => Some(VehicleType::Agile),
+            "ultra_agile" | "ultraagile" | "ultra-agile" => Some(VehicleType::UltraAgile),
+            _ => None,
+        };
+        by_id.or_else(|| {
+            Self::ALL.into_iter().find(|vehicle_type| {
+                [Language::Spanish, Language::English]
+                    .into_iter()
+                    .any(|language| vehicle_type.display_name(language).to_lowercase() == name)
+            })
+        })
+    }
+
+    /// Stable ASCII identifier, the type label of every serialized output and lookup
+    pub fn id(&self) -> &'static str {
         match self {
-            VehicleType::Heavy => "Barco",
-            VehicleType::Standard => "Lancha",
-            VehicleType::Agile => "Avión",
-            VehicleType::UltraAgile => "Ultra-Agile",
+            VehicleType::Heavy => "heavy",
+            VehicleType::Standard => "standard",
+            VehicleType::Agile => "agile",
+            VehicleType::UltraAgile => "ultra_agile",
         }
     }
+
+    /// Identifier of the `ordinal`-th vehicle of this type (1-based) when none is given, "agile-2"
+    pub fn default_id(&self, ordinal: usize) -> String {
+        format!("{}-{}", self.id(), ordinal)
+    }
+
+    /// Name shown to people, never parsed back or matched on
+    pub fn display_name(&self, language: Language) -> &'static str {
+        match (self, language) {
+            (VehicleType::Heavy, Language::Spanish) => "Barco",
+            (VehicleType::Heavy, Language::English) => "Ship",
+            (VehicleType::Standard, Language::Spanish) => "Lancha",
+            (VehicleType::Standard, Language::English) => "Launch",
+            (VehicleType::Agile, Language::Spanish) => "Avión",
+            (VehicleType::Agile, Language::English) => "Airplane",
+            (VehicleType::UltraAgile, _) => "Ultra-Agile",
+        }
+    }
+}
+
+/// Type label of a result as shown to people, labels that are not a vehicle type as they are
+pub fn display_label(label: &str, language: Language) -> &str {
+    VehicleType::parse(label).map_or(label, |vehicle_type| vehicle_type.display_name(language))
 }
 
 /// Complete vehicle structure
@@ -59,7 +160,7 @@ impl Vehicle {
         vehicle_type: VehicleType,
         characteristics: VehicleCharacteristics,
         initial_position: Point,
-        initial_angle: f64,
+        initial_angle: Radians,
     ) -> Self {
         Self {
             vehicle_type,
@@ -87,6 +188,10 @@ impl Vehicle {
 }
 
 /// Factory function to create vehicle presets from the specification
+///
+/// The cruise fractions put every preset at 5 to 6 units/second, so types
+/// differ in how they turn rather than in how fast they go. The Heavy stays
+/// at 5, faster it starts circling some targets with its slow turn.
 pub fn create_vehicle_preset(vehicle_type: VehicleType) -> VehicleCharacteristics {
     match vehicle_type {
         VehicleType::Heavy => VehicleCharacteristics {
@@ -94,28 +199,51 @@ pub fn create_vehicle_preset(vehicle_type: VehicleType) -> VehicleCharacteristic
             maneuverability: 20.0f64.to_radians(),  // Convert degrees to radians/second
             max_velocity: 50.0,
             max_acceleration: 10.0,
+            cruise_fraction: 0.10,
+            max_angular_acceleration: None,
         },
         VehicleType::Standard => VehicleCharacteristics {
             size: 10.0,
             maneuverability: 35.0f64.to_radians(),
             max_velocity: 80.0,
             max_acceleration: 20.0,
+            cruise_fraction: 0.075,
+            max_angular_acceleration: None,
         },
         VehicleType::Agile => VehicleCharacteristics {
             size: 6.0,
             maneuverability: 60.0f64.to_radians(),
             max_velocity: 100.0,
             max_acceleration: 30.0,
+            cruise_fraction: 0.06,
+            max_angular_acceleration: None,
         },
         VehicleType::UltraAgile => VehicleCharacteristics {
             size: 8.0,
             maneuverability: 90.0f64.to_radians(),
             max_velocity: 70.0,
             max_acceleration: 25.0,
+            cruise_fraction: 0.085,
+            max_angular_acceleration: None,
         },
     }
 }
 
+/// Actuator limit of the preset of `vehicle_type` (radians/second²), for `max_angular_acceleration`
+///
+/// Time to reach the full maneuverability from straight ahead: 2 s for Heavy,
+/// 1 s for Standard, 0.5 s for Agile and 0.25 s for UltraAgile. The presets
+/// leave the limit off, set it to model the actuator.
+pub fn preset_max_angular_acceleration(vehicle_type: VehicleType) -> f64 {
+    let seconds_to_full_turn = match vehicle_type {
+        VehicleType::Heavy => 2.0,
+        VehicleType::Standard => 1.0,
+        VehicleType::Agile => 0.5,
+        VehicleType::UltraAgile => 0.25,
+    };
+    create_vehicle_preset(vehicle_type).maneuverability / seconds_to_full_turn
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +257,43 @@ mod tests {
         let agile = create_vehicle_preset(VehicleType::Agile);
         assert_eq!(agile.size, 6.0);
         assert_eq!(agile.max_velocity, 100.0);
+
+        let cruise: Vec<f64> = VehicleType::ALL
+            .iter()
+            .map(|&vehicle_type| {
+                let preset = create_vehicle_preset(vehicle_type);
+                preset.max_velocity * preset.cruise_fraction
+            })
+            .collect();
+        assert!(cruise.iter().all(|velocity| (5.0..=6.0).contains(velocity)), "{:?}", cruise);
+
+        // The actuator limit is off in the presets, the heavier the vehicle the slower it slews
+        assert!(VehicleType::ALL.iter().all(|&vehicle_type| create_vehicle_preset(vehicle_type).max_angular_acceleration.is_none()));
+        let limits: Vec<f64> = VehicleType::ALL.iter().map(|&vehicle_type| preset_max_angular_acceleration(vehicle_type)).collect();
+        assert!(limits.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", limits);
+    }
+
+    #[test]
+    fn test_characteristics_recorded_without_cruise_fraction_keep_the_old_default() {
+        let json = r#"{"size": 15.0, "maneuverability": 0.35, "max_velocity": 50.0, "max_acceleration": 10.0}"#;
+        let characteristics: VehicleCharacteristics = serde_json::from_str(json).unwrap();
+        assert_eq!(characteristics.cruise_fraction, DEFAULT_VELOCITY_FRACTION);
+        assert_eq!(characteristics.max_angular_acceleration, None);
+    }
+
+    #[test]
+    fn test_validate_rejects_non_positive_and_non_finite_characteristics() {
+        assert!(VehicleType::ALL.iter().all(|&vehicle_type| create_vehicle_preset(vehicle_type).validate().is_ok()));
+
+        let preset = create_vehicle_preset(VehicleType::Agile);
+        let negative = VehicleCharacteristics { maneuverability: -1.0, ..preset.clone() };
+        assert_eq!(negative.validate(), Err(SimulationError::InvalidCharacteristic { field: "maneuverability", value: -1.0 }));
+        let infinite = VehicleCharacteristics { max_velocity: f64::INFINITY, ..preset.clone() };
+        assert!(matches!(infinite.validate(), Err(SimulationError::InvalidCharacteristic { field: "max_velocity", .. })));
+        let nan_slew = VehicleCharacteristics { max_angular_acceleration: Some(f64::NAN), ..preset.clone() };
+        assert!(matches!(nan_slew.validate(), Err(SimulationError::InvalidCharacteristic { field: "max_angular_acceleration", .. })));
+        let too_fast = VehicleCharacteristics { cruise_fraction: 1.5, ..preset };
+        assert_eq!(too_fast.validate(), Err(SimulationError::InvalidVelocityFraction(1.5)));
     }
 
     #[test]
@@ -138,11 +303,41 @@ mod tests {
             VehicleType::Standard,
             characteristics,
             Point::new(100.0, 50.0),
-            90f64.to_radians(),
+            Radians(std::f64::consts::FRAC_PI_2),
         );
 
         assert_eq!(vehicle.state.velocity, 0.0);
         assert!(!vehicle.has_arrived);
         assert_eq!(vehicle.distance_traveled, 0.0);
     }
+
+    #[test]
+    fn test_vehicle_type_parse() {
+        assert_eq!(VehicleType::parse("Heavy"), Some(VehicleType::Heavy));
+        assert_eq!(VehicleType::parse("agile"), Some(VehicleType::Agile));
+        assert_eq!(VehicleType::parse("UltraAgile"), Some(VehicleType::UltraAgile));
+        assert_eq!(VehicleType::parse("Submarine"), None);
+    }
+
+    #[test]
+    fn test_ids_and_display_names_parse_back() {
+        for vehicle_type in VehicleType::ALL {
+            assert!(vehicle_type.id().is_ascii());
+            assert_eq!(VehicleType::parse(vehicle_type.id()), Some(vehicle_type));
+            for language in [Language::Spanish, Language::English] {
+                assert_eq!(VehicleType::parse(vehicle_type.display_name(language)), Some(vehicle_type));
+            }
+            let json = serde_json::to_string(&vehicle_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", vehicle_type.id()));
+        }
+        assert_eq!(VehicleType::parse("Barco"), Some(VehicleType::Heavy));
+        assert_eq!(VehicleType::parse("AVIÓN"), Some(VehicleType::Agile));
+        assert_eq!(VehicleType::UltraAgile.default_id(2), "ultra_agile-2");
+        assert_eq!(display_label("Lancha", Language::English), "Launch");
+        assert_eq!(display_label("custom", Language::Spanish), "custom");
+
+        // Scenarios and configurations recorded with the API identifiers
+        let legacy: Vec<VehicleType> = serde_json::from_str(r#"["Heavy", "UltraAgile", "agile"]"#).unwrap();
+        assert_eq!(legacy, [VehicleType::Heavy, VehicleType::UltraAgile, VehicleType::Agile]);
+    }
 }
@@ -1,18 +1,69 @@
 // Multi-Vehicle Navigation Visualizer with egui
 // Runs simulation automatically and displays results
 // Extracted from bin/visualizer.rs
-
-use examen_parcial::map::Map;
-use examen_parcial::simulation::{Simulation, MultiVehicleSimulationResult, VehicleResult};
-use examen_parcial::vehicle::VehicleType;
+// RUST_LOG filters the console log, e.g. RUST_LOG=debug
+
+use examen_parcial::analysis;
+use examen_parcial::map::{Map, Point, APPROACH_START};
+use examen_parcial::navigation::NavigationController;
+use examen_parcial::build_info::BuildMeta;
+use examen_parcial::simulation::{
+    Language, MultiVehicleSimulation, MultiVehicleSimulationResult, Simulation, SimulationBuilder, TrajectoryPoint, VehicleResult,
+    SCHEMA_VERSION,
+};
+use examen_parcial::trajectory_export::{self, PlotOptions};
+use examen_parcial::units::Degrees;
+use examen_parcial::vehicle::{create_vehicle_preset, VehicleType};
 use macroquad::prelude::*;
 use std::fs;
 use std::io::Write;
-
-const WINDOW_WIDTH: f32 = 1800.0;
-const WINDOW_HEIGHT: f32 = 1000.0;
-const SIDEBAR_WIDTH: f32 = 450.0;
+use std::sync::mpsc;
+
+/// Initial window size, the window can be resized afterwards
+const WINDOW_WIDTH: f32 = 1280.0;
+const WINDOW_HEIGHT: f32 = 760.0;
+/// Sidebar width as a fraction of the window width
+const SIDEBAR_FRACTION: f32 = 0.25;
+const MIN_SIDEBAR_WIDTH: f32 = 330.0;
 const MAP_PADDING: f32 = 40.0;
+/// Space kept free below the map for labels and the comparison legend
+const MAP_BOTTOM_MARGIN: f32 = 60.0;
+/// Simulation sub-steps per frame in live mode at 1x playback speed
+const LIVE_STEPS_PER_FRAME: f32 = 4.0;
+
+/// Placement of the sidebar and the map for a given window size
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Layout {
+    sidebar_width: f32,
+    scale: f32,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+impl Layout {
+    /// Fit the map (in world units) into the space right of the sidebar, preserving its
+    /// aspect ratio and centering it horizontally
+    fn compute(screen_w: f32, screen_h: f32, map_w: f32, map_h: f32) -> Self {
+        let sidebar_width = (screen_w * SIDEBAR_FRACTION).max(MIN_SIDEBAR_WIDTH).min(screen_w);
+
+        let available_width = (screen_w - sidebar_width - 2.0 * MAP_PADDING).max(1.0);
+        let available_height = (screen_h - 2.0 * MAP_PADDING - MAP_BOTTOM_MARGIN).max(1.0);
+
+        let scale = (available_width / map_w).min(available_height / map_h);
+
+        Self {
+            sidebar_width,
+            scale,
+            offset_x: sidebar_width + MAP_PADDING + (available_width - map_w * scale) / 2.0,
+            offset_y: MAP_PADDING,
+        }
+    }
+
+    /// Screen rectangle covered by the map
+    fn map_rect(&self, map_w: f32, map_h: f32) -> Rect {
+        Rect::new(self.offset_x, self.offset_y, map_w * self.scale, map_h * self.scale)
+    }
+}
 
 /// Application state
 enum AppState {
@@ -21,6 +72,46 @@ enum AppState {
     Visualization,
 }
 
+/// What the configuration screen asked for this frame
+enum ConfigOutcome {
+    Idle,
+    Simulate,
+    OpenSaved,
+}
+
+/// Saved result files for the load / A-B comparison section of the configuration screen
+struct ResultSlots {
+    primary_path: String,
+    // Empty when no comparison run is wanted
+    comparison_path: String,
+    status: Option<String>,
+}
+
+impl ResultSlots {
+    fn new() -> Self {
+        Self {
+            primary_path: "output/trajectory_multi.json".to_string(),
+            comparison_path: String::new(),
+            status: None,
+        }
+    }
+
+    /// Load the comparison run if a path was given
+    fn load_comparison(&self) -> Result<Option<MultiVehicleSimulationResult>, String> {
+        let path = self.comparison_path.trim();
+        if path.is_empty() {
+            Ok(None)
+        } else {
+            load_result(path).map(Some)
+        }
+    }
+}
+
+/// Read a result file previously written by the visualizer or the navigation bin
+fn load_result(path: &str) -> Result<MultiVehicleSimulationResult, String> {
+    MultiVehicleSimulationResult::from_json_file(path).map_err(|e| format!("{}: {}", path, e))
+}
+
 /// Configuration for a single vehicle before simulation
 #[derive(Clone)]
 struct VehicleConfig {
@@ -28,7 +119,7 @@ struct VehicleConfig {
     position_x: f32,
     position_y: f32,
     angle_degrees: f32,
-    velocity_percentage: f32, // 0.0 to 1.0
+    velocity_percentage: f32, // Percent of max velocity (5.0 to 15.0)
     use_random: bool,
 }
 
@@ -38,7 +129,7 @@ impl VehicleConfig {
             vehicle_type,
             position_x: map.random_start_position().x as f32,
             position_y: map.random_start_position().y as f32,
-            angle_degrees: map.random_start_angle().to_degrees() as f32,
+            angle_degrees: map.random_start_angle().to_degrees().0 as f32,
             velocity_percentage: (map.random_start_velocity_percentage() * 100.0) as f32,
             use_random: true,
         }
@@ -48,160 +139,259 @@ impl VehicleConfig {
         let pos = map.random_start_position();
         self.position_x = pos.x as f32;
         self.position_y = pos.y as f32;
-        self.angle_degrees = map.random_start_angle().to_degrees() as f32;
+        self.angle_degrees = map.random_start_angle().to_degrees().0 as f32;
         self.velocity_percentage = (map.random_start_velocity_percentage() * 100.0) as f32;
         self.use_random = true;
     }
 }
 
-/// Run the multi-vehicle simulation and save results
-fn run_simulation(configs: &[VehicleConfig]) -> MultiVehicleSimulationResult {
-    println!("\n╔══════════════════════════════════════════════════════╗");
-    println!("║   EJECUTANDO SIMULACIÓN DE NAVEGACIÓN DIFUSA         ║");
-    println!("╚══════════════════════════════════════════════════════╝\n");
+/// Append a new randomized vehicle of the given type
+fn add_vehicle_config(configs: &mut Vec<VehicleConfig>, vehicle_type: VehicleType, map: &Map) {
+    configs.push(VehicleConfig::new_random(vehicle_type, map));
+}
 
-    // Create map (1000x800, target at top center: 500,700)
-    let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+/// Remove the vehicle at `index`, returns false if the index is out of range
+fn remove_vehicle_config(configs: &mut Vec<VehicleConfig>, index: usize) -> bool {
+    if index >= configs.len() {
+        return false;
+    }
+    configs.remove(index);
+    true
+}
 
-    let dt = 0.05; // 50ms time step
-    let max_time = 600.0;
+/// Insert a copy of the vehicle at `index` right after it, returns false if the index is out of range
+fn duplicate_vehicle_config(configs: &mut Vec<VehicleConfig>, index: usize) -> bool {
+    if index >= configs.len() {
+        return false;
+    }
+    let copy = configs[index].clone();
+    configs.insert(index + 1, copy);
+    true
+}
 
-    // Create simulations from configs
-    let mut simulations: Vec<Simulation> = configs.iter()
-        .map(|config| {
-            use examen_parcial::vehicle::create_vehicle_preset;
-            use examen_parcial::navigation::NavigationController;
-            use examen_parcial::map::Point;
-            use examen_parcial::vehicle::Vehicle;
-
-            let characteristics = create_vehicle_preset(config.vehicle_type);
-            let initial_pos = Point::new(config.position_x as f64, config.position_y as f64);
-            let initial_angle = config.angle_degrees.to_radians() as f64;
-
-            let mut vehicle = Vehicle::new(
-                config.vehicle_type,
-                characteristics.clone(),
-                initial_pos,
-                initial_angle,
-            );
+/// Pending edit on the configuration list, applied after the UI loop releases its borrow
+enum ConfigAction {
+    Remove(usize),
+    Duplicate(usize),
+}
 
-            // Set velocity from config
-            let velocity_factor = config.velocity_percentage / 100.0;
-            vehicle.state.velocity = characteristics.max_velocity * velocity_factor as f64;
-
-            Simulation {
-                map: map.clone(),
-                vehicle,
-                controller: NavigationController::new(&characteristics),
-                time: 0.0,
-                dt,
-                max_time,
-                trajectory: Vec::new(),
-                distance_threshold: 25.0,
-                angle_threshold: 2f64.to_radians(),
-                velocity_threshold: characteristics.max_velocity + 5.0,
-            }
-        })
-        .collect();
+/// Fallback palette for duplicated or unknown vehicle types
+const FALLBACK_PALETTE: [(u8, u8, u8); 6] = [
+    (255, 120, 200),  // Pink
+    (120, 255, 230),  // Cyan
+    (255, 150, 80),   // Orange
+    (190, 130, 255),  // Purple
+    (230, 255, 120),  // Lime
+    (255, 90, 90),    // Red
+];
+
+/// Base color of a vehicle type, by id or by the display name of older results
+fn base_vehicle_rgb(vehicle_type: &str) -> Option<(u8, u8, u8)> {
+    match VehicleType::parse(vehicle_type)? {
+        VehicleType::Heavy => Some((255, 200, 50)),       // Yellow/Gold - Barco
+        VehicleType::Standard => Some((100, 255, 100)),   // Green - Lancha
+        VehicleType::Agile => Some((100, 150, 255)),      // Blue - Avión
+        VehicleType::UltraAgile => Some((255, 100, 255)), // Magenta - Ultra-Agile
+    }
+}
 
-    println!("Simulando {} vehículos:", simulations.len());
-    for (i, sim) in simulations.iter().enumerate() {
-        println!("  {}. {} - Inicio: ({:.1}, {:.1}) @ {:.1}°",
-            i + 1,
-            sim.vehicle.vehicle_type.name(),
-            sim.vehicle.state.position.x,
-            sim.vehicle.state.position.y,
-            sim.vehicle.state.angle.to_degrees()
-        );
+/// Assign one color per vehicle: the first vehicle of each type keeps its base color,
+/// duplicates and unknown types take the next unused color from the fallback palette
+fn assign_vehicle_colors<S: AsRef<str>>(vehicle_types: &[S]) -> Vec<Color> {
+    let mut used: Vec<(u8, u8, u8)> = Vec::new();
+
+    for (idx, vehicle_type) in vehicle_types.iter().enumerate() {
+        let rgb = base_vehicle_rgb(vehicle_type.as_ref())
+            .filter(|rgb| !used.contains(rgb))
+            .or_else(|| FALLBACK_PALETTE.iter().copied().find(|rgb| !used.contains(rgb)))
+            .unwrap_or(FALLBACK_PALETTE[idx % FALLBACK_PALETTE.len()]);
+        used.push(rgb);
     }
-    println!("\nObjetivo: (500.0, 700.0) @ 90°\n");
-    println!("Ejecutando simulación (dt={:.3}s, tiempo_max={:.1}s)...\n", dt, max_time);
 
-    // Run all simulations in parallel
-    let mut time = 0.0;
-    let mut all_arrived = false;
+    used.into_iter()
+        .map(|(r, g, b)| Color::from_rgba(r, g, b, 255))
+        .collect()
+}
+
+fn to_egui_color(color: Color) -> egui_macroquad::egui::Color32 {
+    egui_macroquad::egui::Color32::from_rgb(
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8
+    )
+}
+
+/// Vehicle type of a result entry from its id (or display name in older results), Standard if unknown
+fn vehicle_type_from_name(name: &str) -> VehicleType {
+    VehicleType::parse(name).unwrap_or(VehicleType::Standard)
+}
 
-    while time < max_time && !all_arrived {
-        // Update each vehicle
-        for sim in &mut simulations {
-            if !sim.vehicle.has_arrived {
-                sim.step();
+/// Rule texts of the controller used by a vehicle type, looked up by its id
+fn rule_labels_for(vehicle_type: &str) -> Vec<String> {
+    NavigationController::new(&create_vehicle_preset(vehicle_type_from_name(vehicle_type))).rule_labels()
+}
+
+/// Per-step series plotted in the sidebar mini graphs, aligned with the trajectory indices
+#[derive(Debug, Default, PartialEq)]
+struct GraphSeries {
+    distance: Vec<f32>,
+    angle_error: Vec<f32>,
+    // Control series are None for results recorded without the corresponding fields
+    angular_velocity: Option<Vec<f32>>,
+    commanded_adjustment: Option<Vec<f32>>,
+    applied_adjustment: Option<Vec<f32>>,
+    // Velocity series are None when the velocity never changes
+    velocity: Option<Vec<f32>>,
+    average_velocity: Option<Vec<f32>>,
+}
+
+/// Series of an optional trajectory field, None when no point has it.
+/// Points missing the value (such as the arrival point) repeat the previous one.
+fn optional_series(trajectory: &[TrajectoryPoint], field: impl Fn(&TrajectoryPoint) -> Option<f64>) -> Option<Vec<f32>> {
+    if !trajectory.iter().any(|p| field(p).is_some()) {
+        return None;
+    }
+
+    let mut last = 0.0;
+    Some(trajectory.iter()
+        .map(|p| {
+            if let Some(value) = field(p) {
+                last = value as f32;
             }
-        }
+            last
+        })
+        .collect())
+}
 
-        time += dt;
+/// Velocity and its running time-weighted mean, None at constant velocity
+fn velocity_series(trajectory: &[TrajectoryPoint]) -> (Option<Vec<f32>>, Option<Vec<f32>>) {
+    let first = match trajectory.first() {
+        Some(point) => point.velocity,
+        None => return (None, None),
+    };
+    if trajectory.iter().all(|p| p.velocity == first) {
+        return (None, None);
+    }
+
+    let (mut previous_t, mut weighted_sum) = (0.0, 0.0);
+    let average = trajectory.iter()
+        .map(|p| {
+            weighted_sum += p.velocity * (p.t - previous_t);
+            previous_t = p.t;
+            if p.t > 0.0 { (weighted_sum / p.t) as f32 } else { p.velocity as f32 }
+        })
+        .collect();
+    (Some(trajectory.iter().map(|p| p.velocity as f32).collect()), Some(average))
+}
 
-        // Check if all have arrived
-        all_arrived = simulations.iter().all(|s| s.vehicle.has_arrived);
+fn build_graph_series(trajectory: &[TrajectoryPoint]) -> GraphSeries {
+    let (velocity, average_velocity) = velocity_series(trajectory);
+    GraphSeries {
+        distance: trajectory.iter().map(|p| p.distance_to_target as f32).collect(),
+        angle_error: trajectory.iter().map(|p| ((90.0 - p.angle.0) as f32).abs()).collect(),
+        angular_velocity: optional_series(trajectory, |p| p.angular_velocity),
+        commanded_adjustment: optional_series(trajectory, |p| p.commanded_adjustment),
+        applied_adjustment: optional_series(trajectory, |p| p.angular_adjustment),
+        velocity,
+        average_velocity,
     }
+}
 
-    println!("\n╔══════════════════════════════════════════════════════╗");
-    println!("║            SIMULACIÓN COMPLETADA                      ║");
-    println!("╚══════════════════════════════════════════════════════╝\n");
+/// Shorten a rule text to fit the sidebar, keeping the full text for the hover tooltip
+fn truncate_label(label: &str, max_chars: usize) -> String {
+    if label.chars().count() <= max_chars {
+        label.to_string()
+    } else {
+        let truncated: String = label.chars().take(max_chars.saturating_sub(1)).collect();
+        format!("{}…", truncated)
+    }
+}
 
-    // Collect results
-    let mut vehicle_results = Vec::new();
+/// Build the lock-step multi-vehicle simulation from the configuration screen
+fn build_simulation(configs: &[VehicleConfig], record_rule_activations: bool) -> MultiVehicleSimulation {
+    // Create map (1000x800, target at top center: 500,700)
+    let map = Map::new(1000.0, 800.0, 500.0, 700.0);
 
-    for (i, sim) in simulations.into_iter().enumerate() {
-        println!("Vehículo {}: {}", i + 1, sim.vehicle.vehicle_type.name());
+    let dt = 0.05; // 50ms time step
+    let max_time = 600.0;
 
-        // Calculate metrics
-        let success = sim.vehicle.has_arrived;
-        let arrival_time = if success { Some(sim.time) } else { None };
-        let distance_traveled = sim.vehicle.distance_traveled;
+    // Create simulations from configs
+    let simulations: Vec<Simulation> = configs.iter()
+        .map(|config| {
+            SimulationBuilder::new(map.clone(), config.vehicle_type)
+                .dt(dt)
+                .max_time(max_time)
+                .start_position(Point::new(config.position_x as f64, config.position_y as f64))
+                .start_angle(Degrees(config.angle_degrees as f64).to_radians())
+                .velocity_fraction(config.velocity_percentage as f64 / 100.0)
+                .record_rule_activations(record_rule_activations)
+                .build()
+        })
+        .collect();
 
-        let final_distance = if !sim.trajectory.is_empty() {
-            sim.trajectory.last().unwrap().distance_to_target
-        } else {
-            f64::MAX
-        };
+    MultiVehicleSimulation::new(simulations, dt, max_time)
+}
 
-        let final_angle_error = if !sim.trajectory.is_empty() {
-            (90.0 - sim.trajectory.last().unwrap().angle).abs()
-        } else {
-            f64::MAX
-        };
+/// Print the final metrics of every vehicle and save the result to output/trajectory_multi.json
+fn report_and_save(multi_result: &MultiVehicleSimulationResult) {
+    println!("\n╔══════════════════════════════════════════════════════╗");
+    println!("║            SIMULACIÓN COMPLETADA                      ║");
+    println!("╚══════════════════════════════════════════════════════╝\n");
 
-        println!("  Éxito: {} {}", if success { "SÍ" } else { "NO" }, if success { "✓" } else { "✗" });
-        if let Some(t) = arrival_time {
+    for (i, vehicle) in multi_result.vehicles.iter().enumerate() {
+        let metrics = &vehicle.metrics;
+        println!("Vehículo {}: {}", i + 1, vehicle.vehicle_type);
+        println!("  Éxito: {} {}", if metrics.success { "SÍ" } else { "NO" }, if metrics.success { "✓" } else { "✗" });
+        if let Some(t) = metrics.arrival_time {
             println!("  Tiempo de Llegada: {:.2}s", t);
         }
-        println!("  Distancia Recorrida: {:.2} unidades", distance_traveled);
-        println!("  Distancia Final: {:.2} unidades", final_distance);
-        println!("  Error Angular Final: {:.2}°\n", final_angle_error);
-
-        let vehicle_result = VehicleResult {
-            vehicle_type: sim.vehicle.vehicle_type.name().to_string(),
-            trajectory: sim.trajectory,
-            metrics: examen_parcial::simulation::SimulationMetrics {
-                success,
-                arrival_time,
-                distance_traveled,
-                final_distance_to_target: final_distance,
-                final_angle_error,
-            },
-        };
-
-        vehicle_results.push(vehicle_result);
+        println!("  Distancia Recorrida: {:.2} unidades", metrics.distance_traveled);
+        println!("  Distancia Final: {:.2} unidades", metrics.final_distance_to_target);
+        println!("  Error Angular Final: {:.2}°\n", metrics.final_angle_error);
     }
 
-    let multi_result = MultiVehicleSimulationResult {
-        vehicles: vehicle_results,
-        total_simulation_time: time,
-    };
-
     // Save to file
-    let json_output = serde_json::to_string_pretty(&multi_result).unwrap();
+    let json_output = serde_json::to_string_pretty(multi_result).unwrap();
     fs::create_dir_all("output").unwrap();
     let mut file = fs::File::create("output/trajectory_multi.json").unwrap();
     file.write_all(json_output.as_bytes()).unwrap();
     println!("✓ Trayectoria multi-vehículo exportada a: output/trajectory_multi.json\n");
+}
+
+/// Run the multi-vehicle simulation and save results
+fn run_simulation(configs: &[VehicleConfig], record_rule_activations: bool) -> MultiVehicleSimulationResult {
+    println!("\n╔══════════════════════════════════════════════════════╗");
+    println!("║   EJECUTANDO SIMULACIÓN DE NAVEGACIÓN DIFUSA         ║");
+    println!("╚══════════════════════════════════════════════════════╝\n");
+
+    let mut multi = build_simulation(configs, record_rule_activations);
+
+    println!("Simulando {} vehículos:", multi.simulations.len());
+    for (i, sim) in multi.simulations.iter().enumerate() {
+        println!("  {}. {} - Inicio: ({:.1}, {:.1}) @ {:.1}°",
+            i + 1,
+            sim.vehicle.vehicle_type.display_name(Language::Spanish),
+            sim.vehicle.state.position.x,
+            sim.vehicle.state.position.y,
+            sim.vehicle.state.angle.to_degrees()
+        );
+    }
+    println!("\nObjetivo: (500.0, 700.0) @ 90°\n");
+    println!("Ejecutando simulación (dt={:.3}s, tiempo_max={:.1}s)...\n", multi.dt, multi.max_time);
+
+    multi.run();
+
+    let multi_result = multi.into_result();
+    report_and_save(&multi_result);
 
     multi_result
 }
 
 struct Visualizer {
     vehicles: Vec<VehicleResult>,
+    // Simulation stepped inside the render loop in live mode, None when replaying
+    live: Option<MultiVehicleSimulation>,
+    colors: Vec<Color>,
     selected_vehicle: usize,
     current_index: usize,
     is_playing: bool,
@@ -209,44 +399,42 @@ struct Visualizer {
     time_accumulator: f32,
     map_width: f32,
     map_height: f32,
-    scale: f32,
-    offset_x: f32,
-    offset_y: f32,
+    layout: Layout,
+    total_simulation_time: f64,
+    export_status: Option<String>,
+    show_debug_overlay: bool,
     // Graph data for selected vehicle
-    distance_history: Vec<f32>,
-    angle_error_history: Vec<f32>,
+    graphs: GraphSeries,
+    // Maneuverability of the selected vehicle (°/s), reference lines of the adjustment graph
+    maneuverability_deg: f32,
+    // Trajectory index under the mouse in any mini graph, shared by all of them
+    graph_hover: Option<usize>,
+    // Second run overlaid with dashed trajectories, played with the same clock
+    comparison: Option<Vec<VehicleResult>>,
+    comparison_colors: Vec<Color>,
+    // Deltas and path similarity against the comparison run, computed once the main run is complete
+    comparison_deltas: Option<Vec<analysis::VehicleDelta>>,
+    // Rule activation data for selected vehicle (empty when not recorded)
+    rule_labels: Vec<String>,
+    rule_activation_max: Vec<f64>,
+    dominant_rules: Vec<Option<usize>>,
 }
 
 impl Visualizer {
     fn new(result: MultiVehicleSimulationResult, map_width: f32, map_height: f32) -> Self {
-        // Calculate scale to fit map in window (accounting for sidebar)
-        let available_width = WINDOW_WIDTH - SIDEBAR_WIDTH - 2.0 * MAP_PADDING;
-        let available_height = WINDOW_HEIGHT - 2.0 * MAP_PADDING - 100.0;
-
-        let scale_x = available_width / map_width;
-        let scale_y = available_height / map_height;
-        let scale = scale_x.min(scale_y);
+        // Recomputed every frame from the actual window size by `relayout`
+        let layout = Layout::compute(WINDOW_WIDTH, WINDOW_HEIGHT, map_width, map_height);
 
-        let offset_x = SIDEBAR_WIDTH + MAP_PADDING + (available_width - map_width * scale) / 2.0;
-        let offset_y = MAP_PADDING;
+        let vehicle_types: Vec<&str> = result.vehicles.iter().map(|v| v.vehicle_type.as_str()).collect();
+        let colors = assign_vehicle_colors(&vehicle_types);
 
-        // Initialize graph data for first vehicle
-        let distance_history = if !result.vehicles.is_empty() {
-            result.vehicles[0].trajectory.iter().map(|p| p.distance_to_target as f32).collect()
-        } else {
-            Vec::new()
-        };
-
-        let angle_error_history: Vec<f32> = if !result.vehicles.is_empty() {
-            result.vehicles[0].trajectory.iter()
-                .map(|p| ((90.0 - p.angle) as f32).abs())
-                .collect()
-        } else {
-            Vec::new()
-        };
-
-        Self {
+        let mut viz = Self {
             vehicles: result.vehicles,
+            live: None,
+            total_simulation_time: result.total_simulation_time,
+            export_status: None,
+            show_debug_overlay: false,
+            colors,
             selected_vehicle: 0,
             current_index: 0,
             is_playing: true,
@@ -254,40 +442,176 @@ impl Visualizer {
             time_accumulator: 0.0,
             map_width,
             map_height,
-            scale,
-            offset_x,
-            offset_y,
-            distance_history,
-            angle_error_history,
+            layout,
+            graphs: GraphSeries::default(),
+            maneuverability_deg: 0.0,
+            graph_hover: None,
+            comparison: None,
+            comparison_colors: Vec::new(),
+            comparison_deltas: None,
+            rule_labels: Vec::new(),
+            rule_activation_max: Vec::new(),
+            dominant_rules: Vec::new(),
+        };
+        viz.update_graph_data();
+        viz
+    }
+
+    /// Create a visualizer that steps the simulation while it is displayed
+    fn new_live(multi: MultiVehicleSimulation, map_width: f32, map_height: f32) -> Self {
+        let result = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: multi.simulations.iter()
+                .map(|sim| VehicleResult {
+                    id: sim.id.clone(),
+                    vehicle_type: sim.vehicle.vehicle_type.id().to_string(),
+                    trajectory: Vec::new(),
+                    metrics: sim.metrics(),
+                })
+                .collect(),
+            total_simulation_time: 0.0,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        };
+
+        let mut viz = Self::new(result, map_width, map_height);
+        viz.live = Some(multi);
+        viz
+    }
+
+    fn is_live(&self) -> bool {
+        self.live.is_some()
+    }
+
+    /// Overlay a second run (B) on top of the main one (A)
+    fn set_comparison(&mut self, result: MultiVehicleSimulationResult) {
+        let vehicle_types: Vec<&str> = result.vehicles.iter().map(|v| v.vehicle_type.as_str()).collect();
+        self.comparison_colors = assign_vehicle_colors(&vehicle_types);
+        self.comparison = Some(result.vehicles);
+        self.update_comparison_deltas();
+    }
+
+    /// The Fréchet distances cost O(nm) per vehicle, so they wait for the live run to finish
+    fn update_comparison_deltas(&mut self) {
+        self.comparison_deltas = match &self.comparison {
+            Some(comparison) if !self.is_live() => Some(analysis::compare_runs(&self.vehicles, comparison)),
+            _ => None,
+        };
+    }
+
+    /// Playback time of the selected vehicle, shared with the comparison run
+    fn shared_time(&self) -> f64 {
+        if let Some(multi) = &self.live {
+            return multi.time;
         }
+        self.vehicles.get(self.selected_vehicle)
+            .and_then(|v| v.trajectory.get(self.current_index))
+            .map(|p| p.t)
+            .unwrap_or(0.0)
+    }
+
+    /// Advance the live simulation, appending new trajectory points as they are produced.
+    /// Once every vehicle has arrived or timed out, the final metrics replace the placeholders
+    /// and the visualizer switches to regular replay.
+    fn step_live(&mut self, substeps: usize) {
+        let Some(multi) = self.live.as_mut() else {
+            return;
+        };
+
+        for _ in 0..substeps {
+            multi.step();
+        }
+
+        for (vehicle, sim) in self.vehicles.iter_mut().zip(&multi.simulations) {
+            let known = vehicle.trajectory.len();
+            vehicle.trajectory.extend_from_slice(&sim.trajectory[known..]);
+            vehicle.metrics = sim.metrics();
+        }
+        self.total_simulation_time = multi.time;
+
+        if multi.is_finished() {
+            if let Some(multi) = self.live.take() {
+                let result = multi.into_result();
+                report_and_save(&result);
+                self.total_simulation_time = result.total_simulation_time;
+                self.vehicles = result.vehicles;
+                self.update_comparison_deltas();
+            }
+        }
+
+        if let Some(selected) = self.vehicles.get(self.selected_vehicle) {
+            self.current_index = selected.trajectory.len().saturating_sub(1);
+        }
+        self.update_graph_data();
     }
 
     fn update_graph_data(&mut self) {
         if self.selected_vehicle < self.vehicles.len() {
             let vehicle = &self.vehicles[self.selected_vehicle];
-            self.distance_history = vehicle.trajectory.iter().map(|p| p.distance_to_target as f32).collect();
-            self.angle_error_history = vehicle.trajectory.iter()
-                .map(|p| ((90.0 - p.angle) as f32).abs())
-                .collect();
+            self.graphs = build_graph_series(&vehicle.trajectory);
+            self.maneuverability_deg = create_vehicle_preset(vehicle_type_from_name(&vehicle.vehicle_type))
+                .maneuverability
+                .to_degrees() as f32;
+
+            self.rule_labels = rule_labels_for(&vehicle.vehicle_type);
+            self.rule_activation_max = analysis::rule_activation_max(&vehicle.trajectory);
+            self.dominant_rules = analysis::dominant_rule_per_step(&vehicle.trajectory);
         }
     }
 
+    /// Render all trajectories to output/plot_<timestamp>.png, returns the written path
+    fn export_image(&self) -> Result<String, String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("output/plot_{}.png", timestamp);
+
+        let result = MultiVehicleSimulationResult {
+            schema_version: SCHEMA_VERSION,
+            vehicles: self.vehicles.clone(),
+            total_simulation_time: self.total_simulation_time,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        };
+        let map = Map::new(self.map_width as f64, self.map_height as f64, 500.0, 700.0);
+
+        trajectory_export::plot_trajectories(&result, &map, PlotOptions::default(), &path)
+            .map(|_| path)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Adapt the map placement to the current window size
+    fn relayout(&mut self, screen_w: f32, screen_h: f32) {
+        self.layout = Layout::compute(screen_w, screen_h, self.map_width, self.map_height);
+    }
+
     fn world_to_screen(&self, x: f32, y: f32) -> (f32, f32) {
         (
-            self.offset_x + x * self.scale,
-            self.offset_y + (self.map_height - y) * self.scale,
+            self.layout.offset_x + x * self.layout.scale,
+            self.layout.offset_y + (self.map_height - y) * self.layout.scale,
         )
     }
 
     fn update(&mut self, dt: f32) {
+        // Live mode: simulate while displaying
+        if self.is_live() {
+            if self.is_playing {
+                let substeps = (LIVE_STEPS_PER_FRAME * self.playback_speed).ceil().max(1.0) as usize;
+                self.step_live(substeps);
+            }
+            return;
+        }
+
         // Update animation for selected vehicle
         if self.selected_vehicle < self.vehicles.len() {
             let trajectory = &self.vehicles[self.selected_vehicle].trajectory;
-            if self.is_playing && self.current_index < trajectory.len() - 1 {
+            let last_index = trajectory.len().saturating_sub(1);
+            if self.is_playing && self.current_index < last_index {
                 self.time_accumulator += dt * self.playback_speed;
 
                 // Advance multiple frames if playback_speed is high
-                while self.current_index < trajectory.len() - 1 {
+                while self.current_index < last_index {
                     let current_point = &trajectory[self.current_index];
                     let next_point = &trajectory[self.current_index + 1];
                     let dt_trajectory = (next_point.t - current_point.t) as f32;
@@ -303,28 +627,23 @@ impl Visualizer {
         }
     }
 
-    fn get_vehicle_color(vehicle_type: &str) -> Color {
-        match vehicle_type {
-            "Barco" => Color::from_rgba(255, 200, 50, 255),      // Yellow/Gold - Barco (Heavy)
-            "Lancha" => Color::from_rgba(100, 255, 100, 255),    // Green - Lancha (Standard)
-            "Avión" => Color::from_rgba(100, 150, 255, 255),     // Blue - Avión (Agile)
-            _ => Color::from_rgba(200, 200, 200, 255),           // Gray
-        }
+    fn get_vehicle_color(&self, idx: usize) -> Color {
+        self.colors.get(idx).copied().unwrap_or(Color::from_rgba(200, 200, 200, 255))
     }
 
     fn draw_map(&self) {
         // Draw map boundary
-        let (x1, y1) = self.world_to_screen(0.0, 0.0);
-        let (x2, y2) = self.world_to_screen(self.map_width, self.map_height);
-        draw_rectangle_lines(x1, y2, x2 - x1, y1 - y2, 2.0, WHITE);
+        let map_rect = self.layout.map_rect(self.map_width, self.map_height);
+        draw_rectangle_lines(map_rect.x, map_rect.y, map_rect.w, map_rect.h, 2.0, WHITE);
 
         // Draw start zone
+        let (x1, y1) = self.world_to_screen(0.0, 0.0);
         let start_zone_height = self.map_height * 0.08;
         let (_, zone_y) = self.world_to_screen(0.0, start_zone_height);
         draw_rectangle(
             x1,
             y1,
-            x2 - x1,
+            map_rect.w,
             zone_y - y1,
             Color::from_rgba(50, 100, 50, 80),
         );
@@ -365,12 +684,13 @@ impl Visualizer {
         // Draw all vehicle trajectories
         for (idx, vehicle) in self.vehicles.iter().enumerate() {
             let is_selected = idx == self.selected_vehicle;
-            let max_idx = if is_selected { self.current_index } else { vehicle.trajectory.len() - 1 };
+            let last_index = vehicle.trajectory.len().saturating_sub(1);
+            let max_idx = if is_selected { self.current_index } else { last_index };
 
-            let base_color = Self::get_vehicle_color(&vehicle.vehicle_type);
+            let base_color = self.get_vehicle_color(idx);
             let alpha_multiplier = if is_selected { 1.0 } else { 0.3 };
 
-            for i in 0..max_idx.min(vehicle.trajectory.len() - 1) {
+            for i in 0..max_idx.min(last_index) {
                 let p1 = &vehicle.trajectory[i];
                 let p2 = &vehicle.trajectory[i + 1];
 
@@ -398,17 +718,18 @@ impl Visualizer {
         // Draw all vehicles at current position
         for (idx, vehicle) in self.vehicles.iter().enumerate() {
             let is_selected = idx == self.selected_vehicle;
+            let last_index = vehicle.trajectory.len().saturating_sub(1);
             let traj_idx = if is_selected {
-                self.current_index.min(vehicle.trajectory.len() - 1)
+                self.current_index.min(last_index)
             } else {
-                vehicle.trajectory.len() - 1  // Show final position for non-selected
+                last_index  // Show final position for non-selected
             };
 
             if traj_idx < vehicle.trajectory.len() {
                 let current = &vehicle.trajectory[traj_idx];
                 let (vx, vy) = self.world_to_screen(current.x as f32, current.y as f32);
 
-                let vehicle_color = Self::get_vehicle_color(&vehicle.vehicle_type);
+                let vehicle_color = self.get_vehicle_color(idx);
 
                 if is_selected {
                     // Vehicle body (pulsing effect for selected) - LARGER
@@ -427,7 +748,7 @@ impl Visualizer {
                 }
 
                 // Direction indicator - LARGER
-                let angle_rad = (current.angle as f32).to_radians();
+                let angle_rad = (current.angle.0 as f32).to_radians();
                 let dir_length = if is_selected { 28.0 } else { 22.0 };
                 let dx = angle_rad.cos() * dir_length;
                 let dy = -angle_rad.sin() * dir_length;
@@ -436,6 +757,120 @@ impl Visualizer {
                 draw_circle(vx + dx, vy + dy, 4.0, arrow_color);
             }
         }
+
+        if self.comparison.is_some() {
+            self.draw_comparison_overlay();
+        }
+
+        if self.show_debug_overlay {
+            self.draw_debug_overlay();
+        }
+    }
+
+    /// Draw run B dashed up to the shared playback time, with a hollow marker at its current position
+    fn draw_comparison_overlay(&self) {
+        let Some(vehicles) = &self.comparison else {
+            return;
+        };
+        let t = self.shared_time();
+
+        for (idx, vehicle) in vehicles.iter().enumerate() {
+            if vehicle.trajectory.is_empty() {
+                continue;
+            }
+            let base_color = self.comparison_colors.get(idx).copied().unwrap_or(WHITE);
+            let line_color = Color::new(base_color.r, base_color.g, base_color.b, 0.8);
+
+            let current_idx = analysis::index_at_time(&vehicle.trajectory, t);
+            let points: Vec<(f32, f32)> = vehicle.trajectory[..=current_idx].iter()
+                .map(|p| self.world_to_screen(p.x as f32, p.y as f32))
+                .collect();
+            draw_dashed_path(&points, 2.5, 10.0, line_color);
+
+            let current = &vehicle.trajectory[current_idx];
+            let (vx, vy) = self.world_to_screen(current.x as f32, current.y as f32);
+            draw_circle_lines(vx, vy, 10.0, 2.5, line_color);
+
+            let angle_rad = (current.angle.0 as f32).to_radians();
+            draw_line(vx, vy, vx + angle_rad.cos() * 22.0, vy - angle_rad.sin() * 22.0, 2.0, line_color);
+        }
+
+        let (legend_x, legend_y) = self.world_to_screen(0.0, 0.0);
+        draw_text("── A: ejecución principal    - - B: comparación", legend_x, legend_y + 25.0, 20.0, LIGHTGRAY);
+    }
+
+    /// Draw what the controller was aiming at for the selected vehicle at the current index:
+    /// the APPROACH_START radius, the virtual approach point, the desired heading ray and the
+    /// commanded angular adjustment (arc swept in one second). Uses recorded data only.
+    fn draw_debug_overlay(&self) {
+        let overlay_color = Color::from_rgba(0, 255, 255, 200);
+
+        // Radius where the approach point starts to move below the target
+        let (target_x, target_y) = self.world_to_screen(500.0, 700.0);
+        draw_circle_lines(target_x, target_y, APPROACH_START as f32 * self.layout.scale, 1.5,
+            Color::from_rgba(0, 255, 255, 90));
+
+        let Some(vehicle) = self.vehicles.get(self.selected_vehicle) else {
+            return;
+        };
+        let Some(current) = vehicle.trajectory.get(self.current_index) else {
+            return;
+        };
+        let (vx, vy) = self.world_to_screen(current.x as f32, current.y as f32);
+
+        // Virtual approach point
+        if let (Some(ax), Some(ay)) = (current.approach_x, current.approach_y) {
+            let (px, py) = self.world_to_screen(ax as f32, ay as f32);
+            draw_line(vx, vy, px, py, 1.0, Color::from_rgba(0, 255, 255, 80));
+            draw_poly_lines(px, py, 4, 8.0, 45.0, 2.0, overlay_color);
+        }
+
+        // Desired heading ray
+        if let Some(heading) = current.desired_heading {
+            let heading_rad = (heading.0 as f32).to_radians();
+            let ray_length = 60.0;
+            draw_line(vx, vy, vx + heading_rad.cos() * ray_length, vy - heading_rad.sin() * ray_length,
+                2.0, overlay_color);
+        }
+
+        // Commanded adjustment: positive turns counter-clockwise (left), negative clockwise (right)
+        if let Some(adjustment) = current.angular_adjustment {
+            let arc_color = if adjustment >= 0.0 {
+                Color::from_rgba(100, 255, 100, 220)
+            } else {
+                Color::from_rgba(255, 100, 200, 220)
+            };
+            let start = (current.angle.0 as f32).to_radians();
+            let sweep = (adjustment as f32).to_radians();
+            let radius = 40.0;
+            let segments = 24;
+
+            let mut previous = (vx + start.cos() * radius, vy - start.sin() * radius);
+            for i in 1..=segments {
+                let a = start + sweep * i as f32 / segments as f32;
+                let point = (vx + a.cos() * radius, vy - a.sin() * radius);
+                draw_line(previous.0, previous.1, point.0, point.1, 3.0, arc_color);
+                previous = point;
+            }
+
+            draw_text(&format!("{:+.1}°/s", adjustment), previous.0 + 6.0, previous.1, 18.0, arc_color);
+        }
+    }
+}
+
+/// Polyline drawn with alternating dashes of `dash_length` pixels measured along the path,
+/// so the pattern survives trajectories made of many tiny segments
+fn draw_dashed_path(points: &[(f32, f32)], thickness: f32, dash_length: f32, color: Color) {
+    let mut travelled = 0.0;
+
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+
+        if (travelled / dash_length) % 2.0 < 1.0 {
+            draw_line(x1, y1, x2, y2, thickness, color);
+        }
+        travelled += length;
     }
 }
 
@@ -444,7 +879,7 @@ fn window_conf() -> Conf {
         window_title: "Simulador de Navegación Difusa - Barco, Lancha y Avión".to_owned(),
         window_width: WINDOW_WIDTH as i32,
         window_height: WINDOW_HEIGHT as i32,
-        window_resizable: false,
+        window_resizable: true,
         ..Default::default()
     }
 }
@@ -455,7 +890,7 @@ fn draw_loading_screen(egui_ctx: &egui_macroquad::egui::Context, time: f32) {
 
     egui::CentralPanel::default().show(egui_ctx, |ui| {
         ui.vertical_centered(|ui| {
-            ui.add_space(WINDOW_HEIGHT / 3.0);
+            ui.add_space(screen_height() / 3.0);
 
             // Animated spinner
             let spinner_size = 80.0;
@@ -483,10 +918,21 @@ fn draw_loading_screen(egui_ctx: &egui_macroquad::egui::Context, time: f32) {
 }
 
 /// Draw configuration screen - returns true if simulation should start
-fn draw_config_screen(egui_ctx: &egui_macroquad::egui::Context, configs: &mut [VehicleConfig], map: &Map) -> bool {
+fn draw_config_screen(
+    egui_ctx: &egui_macroquad::egui::Context,
+    configs: &mut Vec<VehicleConfig>,
+    new_vehicle_type: &mut VehicleType,
+    live_mode: &mut bool,
+    record_activations: &mut bool,
+    slots: &mut ResultSlots,
+    map: &Map,
+) -> ConfigOutcome {
     use egui_macroquad::egui;
 
-    let mut start = false;
+    let mut outcome = ConfigOutcome::Idle;
+    let mut pending_action: Option<ConfigAction> = None;
+    let vehicle_ids: Vec<&str> = configs.iter().map(|c| c.vehicle_type.id()).collect();
+    let colors = assign_vehicle_colors(&vehicle_ids);
 
     egui::CentralPanel::default().show(egui_ctx, |ui| {
         ui.vertical_centered(|ui| {
@@ -502,97 +948,144 @@ fn draw_config_screen(egui_ctx: &egui_macroquad::egui::Context, configs: &mut [V
         ui.add_space(20.0);
 
         // Vehicle configurations
-        for (idx, config) in configs.iter_mut().enumerate() {
-            let vehicle_name = config.vehicle_type.name().to_string();
-            let color = Visualizer::get_vehicle_color(&vehicle_name);
-            let egui_color = egui::Color32::from_rgb(
-                (color.r * 255.0) as u8,
-                (color.g * 255.0) as u8,
-                (color.b * 255.0) as u8
-            );
-
-            ui.group(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new(format!("🚢 {} - {}", idx + 1, &vehicle_name))
-                        .size(20.0)
-                        .strong()
-                        .color(egui_color));
-
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.add(egui::Button::new(egui::RichText::new("🎲 Aleatorizar").size(14.0))
-                            .min_size(egui::Vec2::new(130.0, 30.0))).clicked() {
-                            config.randomize(map);
-                        }
+        egui::ScrollArea::vertical()
+            .max_height((screen_height() - 560.0).max(150.0))
+            .show(ui, |ui| {
+                if configs.is_empty() {
+                    ui.vertical_centered(|ui| {
+                        ui.label(egui::RichText::new("No hay vehículos configurados. Agregue al menos uno para iniciar.")
+                            .size(15.0)
+                            .color(egui::Color32::YELLOW));
                     });
-                });
+                }
 
-                // Show vehicle characteristics
-                ui.add_space(5.0);
-                ui.horizontal(|ui| {
-                    use examen_parcial::vehicle::create_vehicle_preset;
-                    let characteristics = create_vehicle_preset(config.vehicle_type);
-
-                    ui.label(egui::RichText::new(format!(
-                        "⚙️ Maniobrabilidad: {:.0}°/s | Vel. Máx: {:.0} u/s",
-                        characteristics.maneuverability.to_degrees(),
-                        characteristics.max_velocity
-                    )).size(13.0).color(egui::Color32::from_gray(180)));
-                });
+                for (idx, config) in configs.iter_mut().enumerate() {
+                    let vehicle_name = config.vehicle_type.display_name(Language::Spanish);
+                    let egui_color = to_egui_color(colors[idx]);
 
-                ui.add_space(10.0);
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(format!("🚢 {} - {}", idx + 1, vehicle_name))
+                                .size(20.0)
+                                .strong()
+                                .color(egui_color));
+
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.add(egui::Button::new(egui::RichText::new("🗑 Eliminar").size(14.0))
+                                    .min_size(egui::Vec2::new(110.0, 30.0))).clicked() {
+                                    pending_action = Some(ConfigAction::Remove(idx));
+                                }
+
+                                if ui.add(egui::Button::new(egui::RichText::new("📋 Duplicar").size(14.0))
+                                    .min_size(egui::Vec2::new(110.0, 30.0))).clicked() {
+                                    pending_action = Some(ConfigAction::Duplicate(idx));
+                                }
+
+                                if ui.add(egui::Button::new(egui::RichText::new("🎲 Aleatorizar").size(14.0))
+                                    .min_size(egui::Vec2::new(130.0, 30.0))).clicked() {
+                                    config.randomize(map);
+                                }
+                            });
+                        });
+
+                        // Show vehicle characteristics
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            use examen_parcial::vehicle::create_vehicle_preset;
+                            let characteristics = create_vehicle_preset(config.vehicle_type);
+
+                            ui.label(egui::RichText::new(format!(
+                                "⚙️ Maniobrabilidad: {:.0}°/s | Vel. Máx: {:.0} u/s",
+                                characteristics.maneuverability.to_degrees(),
+                                characteristics.max_velocity
+                            )).size(13.0).color(egui::Color32::from_gray(180)));
+                        });
+
+                        ui.add_space(10.0);
+
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Posición X:").size(15.0));
+                            ui.add(egui::DragValue::new(&mut config.position_x)
+                                .speed(1.0)
+                                .range(0.0..=1000.0)
+                                .suffix(" u"));
+
+                            ui.add_space(20.0);
+
+                            ui.label(egui::RichText::new("Posición Y:").size(15.0));
+                            ui.add(egui::DragValue::new(&mut config.position_y)
+                                .speed(1.0)
+                                .range(0.0..=64.0)
+                                .suffix(" u"));
+                        });
 
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new("Posición X:").size(15.0));
-                    ui.add(egui::DragValue::new(&mut config.position_x)
-                        .speed(1.0)
-                        .range(0.0..=1000.0)
-                        .suffix(" u"));
+                        ui.add_space(8.0);
 
-                    ui.add_space(20.0);
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Ángulo:").size(15.0));
+                            ui.add(egui::Slider::new(&mut config.angle_degrees, 0.0..=180.0)
+                                .suffix("°")
+                                .text(""));
 
-                    ui.label(egui::RichText::new("Posición Y:").size(15.0));
-                    ui.add(egui::DragValue::new(&mut config.position_y)
-                        .speed(1.0)
-                        .range(0.0..=64.0)
-                        .suffix(" u"));
-                });
+                            ui.label(egui::RichText::new(format!("{:.1}°", config.angle_degrees)).size(14.0));
+                        });
+
+                        ui.add_space(8.0);
 
-                ui.add_space(8.0);
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Velocidad:").size(15.0));
+                            ui.add(egui::Slider::new(&mut config.velocity_percentage, 5.0..=15.0)
+                                .suffix("%")
+                                .text(""));
 
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new("Ángulo:").size(15.0));
-                    ui.add(egui::Slider::new(&mut config.angle_degrees, 0.0..=180.0)
-                        .suffix("°")
-                        .text(""));
+                            ui.label(egui::RichText::new(format!("{:.1}% de velocidad máxima", config.velocity_percentage)).size(14.0));
+                        });
+                    });
 
-                    ui.label(egui::RichText::new(format!("{:.1}°", config.angle_degrees)).size(14.0));
-                });
+                    ui.add_space(15.0);
+                }
+            });
 
-                ui.add_space(8.0);
+        // Apply add/remove/duplicate once the list is no longer borrowed
+        match pending_action {
+            Some(ConfigAction::Remove(idx)) => { remove_vehicle_config(configs, idx); }
+            Some(ConfigAction::Duplicate(idx)) => { duplicate_vehicle_config(configs, idx); }
+            None => {}
+        }
 
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new("Velocidad:").size(15.0));
-                    ui.add(egui::Slider::new(&mut config.velocity_percentage, 5.0..=15.0)
-                        .suffix("%")
-                        .text(""));
+        ui.add_space(10.0);
 
-                    ui.label(egui::RichText::new(format!("{:.1}% de velocidad máxima", config.velocity_percentage)).size(14.0));
+        // Add vehicle controls
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Nuevo vehículo:").size(15.0));
+            egui::ComboBox::from_label("")
+                .selected_text(new_vehicle_type.display_name(Language::Spanish))
+                .show_ui(ui, |ui| {
+                    for vehicle_type in VehicleType::ALL {
+                        ui.selectable_value(new_vehicle_type, vehicle_type, vehicle_type.display_name(Language::Spanish));
+                    }
                 });
-            });
 
-            ui.add_space(15.0);
-        }
+            if ui.add(egui::Button::new(egui::RichText::new("➕ Agregar Vehículo").size(14.0))
+                .min_size(egui::Vec2::new(170.0, 30.0))).clicked() {
+                add_vehicle_config(configs, *new_vehicle_type, map);
+            }
+        });
 
-        ui.add_space(30.0);
+        ui.add_space(20.0);
         ui.separator();
         ui.add_space(20.0);
 
-        // Start simulation button
+        // Start simulation button (disabled without vehicles)
         ui.vertical_centered(|ui| {
-            if ui.add(egui::Button::new(egui::RichText::new("▶ Iniciar Simulación").size(22.0))
+            ui.checkbox(live_mode, egui::RichText::new("📡 Modo en vivo (simular mientras se visualiza)").size(15.0));
+            ui.checkbox(record_activations, egui::RichText::new("🧮 Registrar activación de reglas").size(15.0));
+            ui.add_space(10.0);
+
+            if ui.add_enabled(!configs.is_empty(), egui::Button::new(egui::RichText::new("▶ Iniciar Simulación").size(22.0))
                 .min_size(egui::Vec2::new(300.0, 60.0))
                 .fill(egui::Color32::from_rgb(50, 150, 50))).clicked() {
-                start = true;
+                outcome = ConfigOutcome::Simulate;
             }
 
             ui.add_space(10.0);
@@ -604,13 +1097,52 @@ fn draw_config_screen(egui_ctx: &egui_macroquad::egui::Context, configs: &mut [V
                 }
             }
         });
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        // Saved results: open without simulating and/or compare against a second run
+        ui.group(|ui| {
+            ui.label(egui::RichText::new("📂 Resultados Guardados").strong().size(18.0));
+            ui.add_space(6.0);
+
+            egui::Grid::new("result_slots_grid")
+                .spacing([10.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("Resultado A (principal):").size(14.0));
+                    ui.add(egui::TextEdit::singleline(&mut slots.primary_path).desired_width(420.0));
+                    ui.end_row();
+
+                    ui.label(egui::RichText::new("Resultado B (comparación, opcional):").size(14.0));
+                    ui.add(egui::TextEdit::singleline(&mut slots.comparison_path).desired_width(420.0));
+                    ui.end_row();
+                });
+
+            ui.label(egui::RichText::new("Si B tiene una ruta, también se superpone a una nueva simulación")
+                .size(12.0)
+                .color(egui::Color32::GRAY));
+            ui.add_space(6.0);
+
+            if ui.add(egui::Button::new(egui::RichText::new("📂 Abrir A sin simular").size(16.0))
+                .min_size(egui::Vec2::new(220.0, 35.0))).clicked() {
+                outcome = ConfigOutcome::OpenSaved;
+            }
+
+            if let Some(status) = &slots.status {
+                ui.label(egui::RichText::new(status).size(13.0).color(egui::Color32::from_rgb(255, 120, 120)));
+            }
+        });
     });
 
-    start
+    outcome
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    // Only RUST_LOG here, the default filter cannot be invalid
+    examen_parcial::logging::init_cli(None).ok();
+
     // Create map for initial random values
     let map = Map::new(1000.0, 800.0, 500.0, 700.0);
 
@@ -621,10 +1153,15 @@ async fn main() {
         VehicleConfig::new_random(VehicleType::Agile, &map),
     ];
 
+    let mut new_vehicle_type = VehicleType::UltraAgile;
+    let mut live_mode = false;
+    let mut record_activations = false;
+    let mut slots = ResultSlots::new();
+    let mut pending_comparison: Option<MultiVehicleSimulationResult> = None;
     let mut app_state = AppState::Configuration;
     let mut visualizer: Option<Visualizer> = None;
     let mut loading_start_time: f32 = 0.0;
-    let mut simulation_triggered = false;
+    let mut simulation_rx: Option<mpsc::Receiver<MultiVehicleSimulationResult>> = None;
 
     loop {
         match app_state {
@@ -632,18 +1169,66 @@ async fn main() {
                 // Configuration screen
                 clear_background(Color::from_rgba(20, 20, 30, 255));
 
-                let mut start_simulation = false;
+                let mut outcome = ConfigOutcome::Idle;
 
                 egui_macroquad::ui(|egui_ctx| {
-                    start_simulation = draw_config_screen(egui_ctx, &mut configs, &map);
+                    outcome = draw_config_screen(
+                        egui_ctx, &mut configs, &mut new_vehicle_type, &mut live_mode, &mut record_activations,
+                        &mut slots, &map);
                 });
 
                 egui_macroquad::draw();
 
-                if start_simulation {
+                // The comparison file is read up front so a bad path keeps us on this screen
+                let mut comparison = None;
+                if !matches!(outcome, ConfigOutcome::Idle) {
+                    match slots.load_comparison() {
+                        Ok(loaded) => comparison = loaded,
+                        Err(e) => {
+                            slots.status = Some(format!("✗ No se pudo cargar B: {}", e));
+                            outcome = ConfigOutcome::Idle;
+                        }
+                    }
+                }
+
+                let start_simulation = matches!(outcome, ConfigOutcome::Simulate);
+
+                if let ConfigOutcome::OpenSaved = outcome {
+                    match load_result(slots.primary_path.trim()) {
+                        Ok(result) => {
+                            slots.status = None;
+                            let mut viz = Visualizer::new(result, 1000.0, 800.0);
+                            if let Some(comparison) = comparison {
+                                viz.set_comparison(comparison);
+                            }
+                            visualizer = Some(viz);
+                            app_state = AppState::Visualization;
+                        }
+                        Err(e) => slots.status = Some(format!("✗ No se pudo cargar A: {}", e)),
+                    }
+                } else if start_simulation && live_mode {
+                    println!("\nIniciando simulación en vivo...\n");
+                    slots.status = None;
+                    let mut viz = Visualizer::new_live(build_simulation(&configs, record_activations), 1000.0, 800.0);
+                    if let Some(comparison) = comparison {
+                        viz.set_comparison(comparison);
+                    }
+                    visualizer = Some(viz);
+                    app_state = AppState::Visualization;
+                } else if start_simulation {
+                    slots.status = None;
+                    pending_comparison = comparison;
+                    // Run the batch simulation off the UI thread so the spinner keeps animating
+                    let (tx, rx) = mpsc::channel();
+                    let configs_snapshot = configs.clone();
+                    std::thread::spawn(move || {
+                        println!("\nIniciando simulación de navegación...\n");
+                        let _ = tx.send(run_simulation(&configs_snapshot, record_activations));
+                    });
+
+                    simulation_rx = Some(rx);
                     app_state = AppState::RunningSimulation;
                     loading_start_time = get_time() as f32;
-                    simulation_triggered = false;
                 }
             }
 
@@ -659,18 +1244,28 @@ async fn main() {
 
                 egui_macroquad::draw();
 
-                // Wait one frame to show the loading screen, then run simulation
-                if !simulation_triggered {
-                    simulation_triggered = true;
-                } else {
-                    // Run simulation
-                    println!("\nIniciando simulación de navegación...\n");
-                    let result = run_simulation(&configs);
-
-                    println!("\n✓ Simulación completada. Iniciando visualización...\n");
+                // Poll the simulation thread without blocking the render loop
+                if let Some(rx) = &simulation_rx {
+                    match rx.try_recv() {
+                        Ok(result) => {
+                            println!("\n✓ Simulación completada. Iniciando visualización...\n");
 
-                    visualizer = Some(Visualizer::new(result, 1000.0, 800.0));
-                    app_state = AppState::Visualization;
+                            let mut viz = Visualizer::new(result, 1000.0, 800.0);
+                            if let Some(comparison) = pending_comparison.take() {
+                                viz.set_comparison(comparison);
+                            }
+                            visualizer = Some(viz);
+                            simulation_rx = None;
+                            app_state = AppState::Visualization;
+                        }
+                        Err(mpsc::TryRecvError::Empty) => {}
+                        Err(mpsc::TryRecvError::Disconnected) => {
+                            eprintln!("\n✗ La simulación terminó sin resultados, volviendo a la configuración\n");
+                            simulation_rx = None;
+                            pending_comparison = None;
+                            app_state = AppState::Configuration;
+                        }
+                    }
                 }
             }
 
@@ -680,6 +1275,7 @@ async fn main() {
                     let dt = get_frame_time();
 
                     // Update
+                    viz.relayout(screen_width(), screen_height());
                     viz.update(dt);
 
                     // Draw
@@ -706,257 +1302,563 @@ async fn main() {
 fn draw_sidebar(egui_ctx: &egui_macroquad::egui::Context, viz: &mut Visualizer) {
     use egui_macroquad::egui;
     egui::SidePanel::left("control_panel")
-        .exact_width(SIDEBAR_WIDTH)
+        .exact_width(viz.layout.sidebar_width)
         .resizable(false)
         .show(egui_ctx, |ui| {
-            ui.heading(egui::RichText::new("🚢 Visualizador de Navegación").size(20.0));
-            ui.separator();
-
-            // === VEHICLE SELECTOR ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("🎯 Seleccionar Vehículo").strong().size(16.0));
-                ui.add_space(8.0);
-
-                let vehicle_count = viz.vehicles.len();
-                let mut new_selection: Option<usize> = None;
-
-                ui.horizontal(|ui| {
-                    for idx in 0..vehicle_count {
-                        let is_selected = idx == viz.selected_vehicle;
-                        let vehicle_type = &viz.vehicles[idx].vehicle_type;
-                        let color = Visualizer::get_vehicle_color(vehicle_type);
-                        let button_color = egui::Color32::from_rgb(
-                            (color.r * 255.0) as u8,
-                            (color.g * 255.0) as u8,
-                            (color.b * 255.0) as u8
-                        );
-
-                        let button_text = if is_selected {
-                            egui::RichText::new(vehicle_type).strong().size(16.0)
-                        } else {
-                            egui::RichText::new(vehicle_type).size(15.0)
-                        };
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.heading(egui::RichText::new("🚢 Visualizador de Navegación").size(20.0));
+                ui.separator();
+
+                // === VEHICLE SELECTOR ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("🎯 Seleccionar Vehículo").strong().size(16.0));
+                    ui.add_space(8.0);
 
-                        let button = egui::Button::new(button_text)
-                            .fill(if is_selected { button_color } else { egui::Color32::from_gray(60) })
-                            .min_size(egui::Vec2::new(110.0, 35.0));
+                    let vehicle_count = viz.vehicles.len();
+                    let mut new_selection: Option<usize> = None;
 
-                        if ui.add(button).clicked() && viz.selected_vehicle != idx {
-                            new_selection = Some(idx);
+                    ui.horizontal_wrapped(|ui| {
+                        for idx in 0..vehicle_count {
+                            let is_selected = idx == viz.selected_vehicle;
+                            let label = format!("{}. {}", idx + 1, viz.vehicles[idx].vehicle_type);
+                            let button_color = to_egui_color(viz.get_vehicle_color(idx));
+
+                            let button_text = if is_selected {
+                                egui::RichText::new(label).strong().size(16.0)
+                            } else {
+                                egui::RichText::new(label).size(15.0)
+                            };
+
+                            let button = egui::Button::new(button_text)
+                                .fill(if is_selected { button_color } else { egui::Color32::from_gray(60) })
+                                .min_size(egui::Vec2::new(110.0, 35.0));
+
+                            if ui.add(button).clicked() && viz.selected_vehicle != idx {
+                                new_selection = Some(idx);
+                            }
                         }
+                    });
+
+                    // Update selection after the borrow ends
+                    if let Some(idx) = new_selection {
+                        viz.selected_vehicle = idx;
+                        viz.current_index = 0;
+                        viz.time_accumulator = 0.0;
+                        viz.update_graph_data();
                     }
                 });
 
-                // Update selection after the borrow ends
-                if let Some(idx) = new_selection {
-                    viz.selected_vehicle = idx;
-                    viz.current_index = 0;
-                    viz.time_accumulator = 0.0;
-                    viz.update_graph_data();
-                }
-            });
+                ui.add_space(12.0);
 
-            ui.add_space(12.0);
+                // === PLAYBACK CONTROLS ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("⏯ Controles de Reproducción").strong().size(16.0));
+                    ui.add_space(8.0);
 
-            // === PLAYBACK CONTROLS ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("⏯ Controles de Reproducción").strong().size(16.0));
-                ui.add_space(8.0);
+                    // Play/Pause button
+                    let button_text = if viz.is_playing { "⏸ Pausar" } else { "▶ Reproducir" };
+                    if ui.add(egui::Button::new(egui::RichText::new(button_text).size(15.0))
+                        .min_size(egui::Vec2::new(150.0, 35.0))).clicked() {
+                        viz.is_playing = !viz.is_playing;
+                    }
 
-                // Play/Pause button
-                let button_text = if viz.is_playing { "⏸ Pausar" } else { "▶ Reproducir" };
-                if ui.add(egui::Button::new(egui::RichText::new(button_text).size(15.0))
-                    .min_size(egui::Vec2::new(150.0, 35.0))).clicked() {
-                    viz.is_playing = !viz.is_playing;
-                }
+                    ui.add_space(8.0);
 
-                ui.add_space(8.0);
+                    // Speed slider
+                    ui.label(egui::RichText::new("Velocidad:").size(14.0));
+                    ui.add(egui::Slider::new(&mut viz.playback_speed, 0.1..=100.0)
+                        .text("x")
+                        .logarithmic(true));
+
+                    // Reset button (live runs are read-only until they finish)
+                    if ui.add_enabled(!viz.is_live(), egui::Button::new(egui::RichText::new("🔄 Reiniciar").size(15.0))
+                        .min_size(egui::Vec2::new(150.0, 35.0))).clicked() {
+                        viz.current_index = 0;
+                        viz.time_accumulator = 0.0;
+                    }
 
-                // Speed slider
-                ui.label(egui::RichText::new("Velocidad:").size(14.0));
-                ui.add(egui::Slider::new(&mut viz.playback_speed, 0.1..=100.0)
-                    .text("x")
-                    .logarithmic(true));
+                    // Export full trajectory figure
+                    if ui.add_enabled(!viz.is_live(), egui::Button::new(egui::RichText::new("🖼 Exportar imagen").size(15.0))
+                        .min_size(egui::Vec2::new(150.0, 35.0))).clicked() {
+                        viz.export_status = Some(match viz.export_image() {
+                            Ok(path) => format!("✓ Imagen exportada a: {}", path),
+                            Err(e) => format!("✗ Error al exportar imagen: {}", e),
+                        });
+                    }
 
-                // Reset button
-                if ui.add(egui::Button::new(egui::RichText::new("🔄 Reiniciar").size(15.0))
-                    .min_size(egui::Vec2::new(150.0, 35.0))).clicked() {
-                    viz.current_index = 0;
-                    viz.time_accumulator = 0.0;
-                }
-            });
+                    if let Some(status) = &viz.export_status {
+                        ui.label(egui::RichText::new(status).size(12.0).color(egui::Color32::LIGHT_GRAY));
+                    }
 
-            ui.add_space(12.0);
+                    ui.checkbox(&mut viz.show_debug_overlay,
+                        egui::RichText::new("🔍 Depuración: punto de aproximación e intención").size(14.0));
+                });
 
-            // === PROGRESS ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("📊 Progreso").strong().size(16.0));
-                ui.add_space(8.0);
+                ui.add_space(12.0);
 
-                if viz.selected_vehicle < viz.vehicles.len() {
-                    let selected = &viz.vehicles[viz.selected_vehicle];
-                    let progress = viz.current_index as f32 / selected.trajectory.len() as f32;
+                // === PROGRESS ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("📊 Progreso").strong().size(16.0));
+                    ui.add_space(8.0);
 
-                    let progress_bar = egui::ProgressBar::new(progress)
-                        .text(egui::RichText::new(format!("{:.1}%", progress * 100.0)).size(14.0))
-                        .animate(viz.is_playing);
-                    ui.add(progress_bar);
+                    if let Some(multi) = &viz.live {
+                        let progress = (multi.time / multi.max_time) as f32;
+                        ui.add(egui::ProgressBar::new(progress)
+                            .text(egui::RichText::new(format!("🔴 En vivo: t={:.2}s", multi.time)).size(14.0))
+                            .animate(viz.is_playing));
+                        ui.label(egui::RichText::new(format!("Llegaron: {}/{}", multi.arrived_count(), multi.simulations.len())).size(13.0));
+                    } else if viz.selected_vehicle < viz.vehicles.len() {
+                        let selected = &viz.vehicles[viz.selected_vehicle];
+                        let progress = playback_fraction(viz.current_index, selected.trajectory.len());
+
+                        let progress_bar = egui::ProgressBar::new(progress)
+                            .text(egui::RichText::new(format!("{:.1}%", progress * 100.0)).size(14.0))
+                            .animate(viz.is_playing);
+                        ui.add(progress_bar);
+
+                        ui.label(egui::RichText::new(format!("Fotograma: {}/{}", viz.current_index, selected.trajectory.len())).size(13.0));
+
+                        if viz.current_index < selected.trajectory.len() {
+                            let current = &selected.trajectory[viz.current_index];
+                            ui.label(egui::RichText::new(format!("Tiempo: {:.2}s", current.t)).size(13.0));
+                        }
+                    }
+                });
 
-                    ui.label(egui::RichText::new(format!("Fotograma: {}/{}", viz.current_index, selected.trajectory.len())).size(13.0));
+                ui.add_space(12.0);
 
+                // === REALTIME STATS ===
+                if viz.selected_vehicle < viz.vehicles.len() {
+                    let selected = &viz.vehicles[viz.selected_vehicle];
                     if viz.current_index < selected.trajectory.len() {
                         let current = &selected.trajectory[viz.current_index];
-                        ui.label(egui::RichText::new(format!("Tiempo: {:.2}s", current.t)).size(13.0));
+
+                        ui.group(|ui| {
+                            ui.label(egui::RichText::new("📈 Estado Actual").strong().size(16.0));
+                            ui.add_space(8.0);
+
+                            ui.label(egui::RichText::new(format!("🧭 Posición: ({:.1}, {:.1})", current.x, current.y)).size(13.0));
+                            ui.label(egui::RichText::new(format!("📐 Ángulo: {:.1}°", current.angle)).size(13.0));
+                            ui.label(egui::RichText::new(format!("🎯 Distancia al Objetivo: {:.1} unidades", current.distance_to_target)).size(13.0));
+                            ui.label(egui::RichText::new(format!("⚡ Velocidad: {:.1} u/s", current.velocity)).size(13.0));
+
+                            let angle_error = (90.0 - current.angle.0).abs();
+                            let error_color = if angle_error < 10.0 {
+                                egui::Color32::GREEN
+                            } else if angle_error < 40.0 {
+                                egui::Color32::YELLOW
+                            } else {
+                                egui::Color32::RED
+                            };
+
+                            ui.label(egui::RichText::new(format!("Δ Ángulo desde 90°: {:.1}°", angle_error))
+                                .color(error_color)
+                                .size(13.0));
+                        });
                     }
                 }
-            });
 
-            ui.add_space(12.0);
+                ui.add_space(12.0);
+
+                // === GRAPHS ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("📉 Gráficas de Métricas").strong().size(16.0));
+                    ui.add_space(8.0);
 
-            // === REALTIME STATS ===
-            if viz.selected_vehicle < viz.vehicles.len() {
-                let selected = &viz.vehicles[viz.selected_vehicle];
-                if viz.current_index < selected.trajectory.len() {
-                    let current = &selected.trajectory[viz.current_index];
+                    // Each graph reports the index under the mouse, drawn by all of them next frame
+                    let mut hovered: Option<usize> = None;
+
+                    // Distance graph
+                    ui.label(egui::RichText::new("Distancia al Objetivo:").size(13.0));
+                    hovered = hovered.or(draw_mini_graph(ui,
+                        &[(&viz.graphs.distance, egui::Color32::from_rgb(100, 200, 255))],
+                        viz.current_index, "unid", &[], viz.graph_hover));
+
+                    ui.add_space(10.0);
+
+                    // Angle error graph
+                    ui.label(egui::RichText::new("Error de Ángulo desde 90°:").size(13.0));
+                    hovered = hovered.or(draw_mini_graph(ui,
+                        &[(&viz.graphs.angle_error, egui::Color32::from_rgb(255, 200, 100))],
+                        viz.current_index, "°", &[], viz.graph_hover));
+
+                    // Angular velocity graph
+                    if let Some(angular_velocity) = &viz.graphs.angular_velocity {
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new("Velocidad Angular:").size(13.0));
+                        hovered = hovered.or(draw_mini_graph(ui,
+                            &[(angular_velocity, egui::Color32::from_rgb(120, 255, 160))],
+                            viz.current_index, "°/s", &[], viz.graph_hover));
+                    }
+
+                    // Commanded vs applied adjustment, limited by ±maneuverability
+                    if let (Some(commanded), Some(applied)) = (&viz.graphs.commanded_adjustment, &viz.graphs.applied_adjustment) {
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new("Ajuste Angular (comandado / aplicado):").size(13.0));
+                        hovered = hovered.or(draw_mini_graph(ui,
+                            &[
+                                (commanded, egui::Color32::from_rgb(200, 130, 255)),
+                                (applied, egui::Color32::from_rgb(255, 150, 80)),
+                            ],
+                            viz.current_index, "°/s", &[-viz.maneuverability_deg, viz.maneuverability_deg], viz.graph_hover));
+                    }
 
+                    // Velocity, only with velocity control
+                    if let (Some(velocity), Some(average)) = (&viz.graphs.velocity, &viz.graphs.average_velocity) {
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new("Velocidad (actual / promedio):").size(13.0));
+                        hovered = hovered.or(draw_mini_graph(ui,
+                            &[
+                                (velocity, egui::Color32::from_rgb(255, 220, 90)),
+                                (average, egui::Color32::from_rgb(150, 150, 255)),
+                            ],
+                            viz.current_index, "unid/s", &[], viz.graph_hover));
+                    }
+
+                    viz.graph_hover = hovered;
+                });
+
+                ui.add_space(12.0);
+
+                // === RULE ACTIVATIONS ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("🧮 Activación de Reglas").strong().size(16.0));
+                    ui.add_space(8.0);
+
+                    let current_activations = viz.vehicles.get(viz.selected_vehicle)
+                        .and_then(|v| v.trajectory.get(viz.current_index))
+                        .and_then(|p| p.rule_activations.as_deref());
+
+                    match current_activations {
+                        Some(activations) => {
+                            draw_rule_activations(ui, &viz.rule_labels, activations, &viz.rule_activation_max);
+                            ui.add_space(6.0);
+                            ui.label(egui::RichText::new("Regla dominante en el tiempo:").size(13.0));
+                            draw_dominant_rule_strip(ui, &viz.dominant_rules, viz.rule_labels.len(), viz.current_index);
+                        }
+                        None => {
+                            ui.label(egui::RichText::new("Sin datos: active \"Registrar activación de reglas\" antes de simular")
+                                .size(12.0)
+                                .color(egui::Color32::LIGHT_GRAY));
+                        }
+                    }
+                });
+
+                ui.add_space(12.0);
+
+                // === FINAL METRICS ===
+                if viz.selected_vehicle < viz.vehicles.len() {
+                    let selected = &viz.vehicles[viz.selected_vehicle];
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new("📈 Estado Actual").strong().size(16.0));
+                        ui.label(egui::RichText::new("🏁 Resultados Finales").strong().size(16.0));
                         ui.add_space(8.0);
 
-                        ui.label(egui::RichText::new(format!("🧭 Posición: ({:.1}, {:.1})", current.x, current.y)).size(13.0));
-                        ui.label(egui::RichText::new(format!("📐 Ángulo: {:.1}°", current.angle)).size(13.0));
-                        ui.label(egui::RichText::new(format!("🎯 Distancia al Objetivo: {:.1} unidades", current.distance_to_target)).size(13.0));
-                        ui.label(egui::RichText::new(format!("⚡ Velocidad: {:.1} u/s", current.velocity)).size(13.0));
-
-                        let angle_error = (90.0 - current.angle).abs();
-                        let error_color = if angle_error < 10.0 {
-                            egui::Color32::GREEN
-                        } else if angle_error < 40.0 {
-                            egui::Color32::YELLOW
-                        } else {
-                            egui::Color32::RED
-                        };
+                        let success_icon = if selected.metrics.success { "✅" } else { "❌" };
+                        ui.label(egui::RichText::new(format!("{} Estado: {}", success_icon,
+                            if selected.metrics.success { "Llegó" } else { "No llegó" })).size(13.0));
 
-                        ui.label(egui::RichText::new(format!("Δ Ángulo desde 90°: {:.1}°", angle_error))
-                            .color(error_color)
-                            .size(13.0));
+                        if let Some(time) = selected.metrics.arrival_time {
+                            ui.label(egui::RichText::new(format!("⏱ Tiempo de Llegada: {:.2}s", time)).size(13.0));
+                        }
+
+                        ui.label(egui::RichText::new(format!("📏 Distancia Recorrida: {:.1} unid", selected.metrics.distance_traveled)).size(13.0));
+                        ui.label(egui::RichText::new(format!("🎯 Distancia Final: {:.1} unid", selected.metrics.final_distance_to_target)).size(13.0));
+                        ui.label(egui::RichText::new(format!("📐 Error Angular Final: {:.1}°", selected.metrics.final_angle_error)).size(13.0));
+                        ui.label(egui::RichText::new(format!("〰 Inversiones de Giro: {:.1}/min", selected.metrics.steering_reversals_per_minute)).size(13.0));
+                        ui.label(egui::RichText::new(format!("📈 Jerk de Rumbo: {:.4}°", selected.metrics.heading_jerk)).size(13.0));
+
+                        // Fases de aproximación: llegar cerca del objetivo y alinearse con él
+                        let phase = |time: Option<f64>| time.map_or("—".to_string(), |time| format!("{:.2}s", time));
+                        ui.label(egui::RichText::new(format!("🚀 Tránsito: {}  🧭 Alineación: {}",
+                            phase(selected.metrics.transit_time), phase(selected.metrics.alignment_time))).size(13.0));
+                        ui.label(egui::RichText::new(format!("🎯 Primera Alineación: {}  🔁 Intentos: {}",
+                            phase(selected.metrics.first_aligned_time), selected.metrics.alignment_attempts)).size(13.0));
                     });
                 }
-            }
 
-            ui.add_space(12.0);
+                ui.add_space(12.0);
 
-            // === GRAPHS ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("📉 Gráficas de Métricas").strong().size(16.0));
-                ui.add_space(8.0);
+                // === COMPARISON TABLE ===
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new("📊 Comparación de Vehículos").strong().size(16.0));
+                    ui.add_space(8.0);
 
-                // Distance graph
-                ui.label(egui::RichText::new("Distancia al Objetivo:").size(13.0));
-                draw_mini_graph(ui, &viz.distance_history, viz.current_index, "unid",
-                    egui::Color32::from_rgb(100, 200, 255));
+                    use egui_macroquad::egui::Grid;
+                    egui::ScrollArea::vertical()
+                        .max_height(180.0)
+                        .show(ui, |ui| {
+                            Grid::new("comparison_grid")
+                                .striped(true)
+                                .spacing([10.0, 6.0])
+                                .show(ui, |ui| {
+                                    // Header
+                                    ui.label(egui::RichText::new("Vehículo").strong().size(13.0));
+                                    ui.label(egui::RichText::new("Estado").strong().size(13.0));
+                                    ui.label(egui::RichText::new("Tiempo").strong().size(13.0));
+                                    ui.label(egui::RichText::new("Δ Ángulo").strong().size(13.0));
+                                    ui.end_row();
+
+                                    // Data rows
+                                    for (idx, vehicle) in viz.vehicles.iter().enumerate() {
+                                        let egui_color = to_egui_color(viz.get_vehicle_color(idx));
+
+                                        ui.label(egui::RichText::new(format!("{}. {}", idx + 1, vehicle.vehicle_type)).color(egui_color).size(12.0));
+
+                                        let status = if vehicle.metrics.success { "✅" } else { "❌" };
+                                        ui.label(egui::RichText::new(status).size(12.0));
+
+                                        if let Some(time) = vehicle.metrics.arrival_time {
+                                            ui.label(egui::RichText::new(format!("{:.1}s", time)).size(12.0));
+                                        } else {
+                                            ui.label(egui::RichText::new("N/A").size(12.0));
+                                        }
+
+                                        let angle_color = if vehicle.metrics.final_angle_error < 2.0 {
+                                            egui::Color32::GREEN
+                                        } else if vehicle.metrics.final_angle_error < 10.0 {
+                                            egui::Color32::YELLOW
+                                        } else {
+                                            egui::Color32::RED
+                                        };
+
+                                        ui.label(egui::RichText::new(format!("{:.1}°", vehicle.metrics.final_angle_error))
+                                            .color(angle_color)
+                                            .size(12.0));
+
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                });
 
-                ui.add_space(10.0);
+                // === A/B RUN COMPARISON ===
+                if let Some(comparison) = &viz.comparison {
+                    ui.add_space(12.0);
 
-                // Angle error graph
-                ui.label(egui::RichText::new("Error de Ángulo desde 90°:").size(13.0));
-                draw_mini_graph(ui, &viz.angle_error_history, viz.current_index, "°",
-                    egui::Color32::from_rgb(255, 200, 100));
-            });
+                    ui.group(|ui| {
+                        ui.label(egui::RichText::new("⚖ Comparación A/B (B − A)").strong().size(16.0));
+                        ui.add_space(8.0);
+
+                        let live_deltas;
+                        let deltas = match &viz.comparison_deltas {
+                            Some(deltas) => deltas,
+                            None => {
+                                live_deltas = analysis::compare_metrics(&viz.vehicles, comparison);
+                                &live_deltas
+                            }
+                        };
+
+                        use egui_macroquad::egui::Grid;
+                        egui::ScrollArea::vertical()
+                            .max_height(180.0)
+                            .show(ui, |ui| {
+                                Grid::new("ab_comparison_grid")
+                                    .striped(true)
+                                    .spacing([10.0, 6.0])
+                                    .show(ui, |ui| {
+                                        ui.label(egui::RichText::new("Vehículo").strong().size(13.0));
+                                        ui.label(egui::RichText::new("Δ Tiempo").strong().size(13.0));
+                                        ui.label(egui::RichText::new("Δ Error").strong().size(13.0));
+                                        ui.label(egui::RichText::new("Δ Recorrido").strong().size(13.0));
+                                        ui.label(egui::RichText::new("Fréchet").strong().size(13.0));
+                                        ui.label(egui::RichText::new("Desv. media").strong().size(13.0));
+                                        ui.end_row();
+
+                                        for delta in deltas {
+                                            let color = match (delta.index_a, delta.index_b) {
+                                                (Some(a), _) => to_egui_color(viz.get_vehicle_color(a)),
+                                                (None, Some(b)) => to_egui_color(viz.comparison_colors.get(b).copied().unwrap_or(WHITE)),
+                                                (None, None) => egui::Color32::WHITE,
+                                            };
+                                            ui.label(egui::RichText::new(&delta.vehicle_type).color(color).size(12.0));
+
+                                            match (delta.index_a, delta.index_b) {
+                                                (Some(_), Some(_)) => {
+                                                    delta_label(ui, delta.arrival_time_delta, "s");
+                                                    delta_label(ui, delta.final_angle_error_delta, "°");
+                                                    delta_label(ui, delta.distance_traveled_delta, " u");
+                                                    let similarity = delta.similarity;
+                                                    distance_label(ui, similarity.map(|s| s.frechet_distance));
+                                                    distance_label(ui, similarity.and_then(|s| s.mean_deviation));
+                                                }
+                                                (Some(_), None) => {
+                                                    ui.label(egui::RichText::new("solo en A").size(12.0).color(egui::Color32::GRAY));
+                                                    ui.label("");
+                                                    ui.label("");
+                                                    ui.label("");
+                                                    ui.label("");
+                                                }
+                                                _ => {
+                                                    ui.label(egui::RichText::new("solo en B").size(12.0).color(egui::Color32::GRAY));
+                                                    ui.label("");
+                                                    ui.label("");
+                                                    ui.label("");
+                                                    ui.label("");
+                                                }
+                                            }
+
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+
+                        ui.label(egui::RichText::new("Negativo = B mejor. Δ Tiempo solo si llegó en ambas. Fréchet y desviación media (a igual tiempo) miden cuánto difieren las trayectorias.")
+                            .size(11.0)
+                            .color(egui::Color32::GRAY));
+                    });
+                }
 
-            ui.add_space(12.0);
+                ui.add_space(12.0);
 
-            // === FINAL METRICS ===
-            if viz.selected_vehicle < viz.vehicles.len() {
-                let selected = &viz.vehicles[viz.selected_vehicle];
+                // === KEYBINDINGS ===
                 ui.group(|ui| {
-                    ui.label(egui::RichText::new("🏁 Resultados Finales").strong().size(16.0));
-                    ui.add_space(8.0);
+                    ui.label(egui::RichText::new("⌨ Atajos de Teclado").strong().size(14.0));
+                    ui.add_space(5.0);
+                    ui.label(egui::RichText::new("ESPACIO: Reproducir/Pausar").size(12.0));
+                    ui.label(egui::RichText::new("←/→: Velocidad").size(12.0));
+                    ui.label(egui::RichText::new("R: Reiniciar").size(12.0));
+                });
+            });
+        });
+}
 
-                    let success_icon = if selected.metrics.success { "✅" } else { "❌" };
-                    ui.label(egui::RichText::new(format!("{} Estado: {}", success_icon,
-                        if selected.metrics.success { "Llegó" } else { "No llegó" })).size(13.0));
+/// Signed delta cell of the A/B table, green when run B is lower (better)
+fn delta_label(ui: &mut egui_macroquad::egui::Ui, delta: Option<f64>, unit: &str) {
+    use egui_macroquad::egui;
 
-                    if let Some(time) = selected.metrics.arrival_time {
-                        ui.label(egui::RichText::new(format!("⏱ Tiempo de Llegada: {:.2}s", time)).size(13.0));
-                    }
+    match delta {
+        Some(value) => {
+            let color = if value < 0.0 {
+                egui::Color32::GREEN
+            } else if value > 0.0 {
+                egui::Color32::from_rgb(255, 120, 120)
+            } else {
+                egui::Color32::LIGHT_GRAY
+            };
+            ui.label(egui::RichText::new(format!("{:+.1}{}", value, unit)).color(color).size(12.0));
+        }
+        None => {
+            ui.label(egui::RichText::new("—").size(12.0).color(egui::Color32::GRAY));
+        }
+    }
+}
 
-                    ui.label(egui::RichText::new(format!("📏 Distancia Recorrida: {:.1} unid", selected.metrics.distance_traveled)).size(13.0));
-                    ui.label(egui::RichText::new(format!("🎯 Distancia Final: {:.1} unid", selected.metrics.final_distance_to_target)).size(13.0));
-                    ui.label(egui::RichText::new(format!("📐 Error Angular Final: {:.1}°", selected.metrics.final_angle_error)).size(13.0));
-                });
-            }
+/// Unsigned distance between the A and B paths, "—" while not measured
+fn distance_label(ui: &mut egui_macroquad::egui::Ui, distance: Option<f64>) {
+    use egui_macroquad::egui;
 
-            ui.add_space(12.0);
-
-            // === COMPARISON TABLE ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("📊 Comparación de Vehículos").strong().size(16.0));
-                ui.add_space(8.0);
-
-                use egui_macroquad::egui::Grid;
-                Grid::new("comparison_grid")
-                    .striped(true)
-                    .spacing([10.0, 6.0])
-                    .show(ui, |ui| {
-                        // Header
-                        ui.label(egui::RichText::new("Vehículo").strong().size(13.0));
-                        ui.label(egui::RichText::new("Estado").strong().size(13.0));
-                        ui.label(egui::RichText::new("Tiempo").strong().size(13.0));
-                        ui.label(egui::RichText::new("Δ Ángulo").strong().size(13.0));
-                        ui.end_row();
-
-                        // Data rows
-                        for vehicle in &viz.vehicles {
-                            let color = Visualizer::get_vehicle_color(&vehicle.vehicle_type);
-                            let egui_color = egui::Color32::from_rgb(
-                                (color.r * 255.0) as u8,
-                                (color.g * 255.0) as u8,
-                                (color.b * 255.0) as u8
-                            );
-
-                            ui.label(egui::RichText::new(&vehicle.vehicle_type).color(egui_color).size(12.0));
-
-                            let status = if vehicle.metrics.success { "✅" } else { "❌" };
-                            ui.label(egui::RichText::new(status).size(12.0));
-
-                            if let Some(time) = vehicle.metrics.arrival_time {
-                                ui.label(egui::RichText::new(format!("{:.1}s", time)).size(12.0));
-                            } else {
-                                ui.label(egui::RichText::new("N/A").size(12.0));
-                            }
+    let text = distance.map_or("—".to_string(), |value| format!("{:.1} u", value));
+    ui.label(egui::RichText::new(text).color(egui::Color32::LIGHT_GRAY).size(12.0));
+}
 
-                            let angle_color = if vehicle.metrics.final_angle_error < 2.0 {
-                                egui::Color32::GREEN
-                            } else if vehicle.metrics.final_angle_error < 10.0 {
-                                egui::Color32::YELLOW
-                            } else {
-                                egui::Color32::RED
-                            };
+/// Color of a rule index, spread around the hue circle so neighbouring rules differ
+fn rule_color(rule_idx: usize, rule_count: usize) -> egui_macroquad::egui::Color32 {
+    let hue = rule_idx as f32 / rule_count.max(1) as f32;
+    let color = macroquad::color::hsl_to_rgb(hue, 0.65, 0.55);
+    to_egui_color(color)
+}
 
-                            ui.label(egui::RichText::new(format!("{:.1}°", vehicle.metrics.final_angle_error))
-                                .color(angle_color)
-                                .size(12.0));
+/// One horizontal bar per rule with its firing strength at the current step,
+/// plus a tick at the maximum strength reached over the whole run
+fn draw_rule_activations(ui: &mut egui_macroquad::egui::Ui, labels: &[String], activations: &[f64], max_activations: &[f64]) {
+    use egui_macroquad::egui;
 
-                            ui.end_row();
-                        }
-                    });
-            });
+    let bar_height = 14.0;
+    let label_width = ui.available_width() * 0.55;
 
-            ui.add_space(12.0);
+    for (idx, &activation) in activations.iter().enumerate() {
+        let full_label = labels.get(idx).cloned().unwrap_or_else(|| format!("Regla {}", idx + 1));
 
-            // === KEYBINDINGS ===
-            ui.group(|ui| {
-                ui.label(egui::RichText::new("⌨ Atajos de Teclado").strong().size(14.0));
-                ui.add_space(5.0);
-                ui.label(egui::RichText::new("ESPACIO: Reproducir/Pausar").size(12.0));
-                ui.label(egui::RichText::new("←/→: Velocidad").size(12.0));
-                ui.label(egui::RichText::new("R: Reiniciar").size(12.0));
-            });
+        ui.horizontal(|ui| {
+            ui.add_sized([label_width, bar_height],
+                egui::Label::new(egui::RichText::new(format!("{}. {}", idx + 1, truncate_label(&full_label, (label_width / 6.5) as usize))).size(11.0)))
+                .on_hover_text(&full_label);
+
+            let (response, painter) = ui.allocate_painter(
+                egui::Vec2::new(ui.available_width(), bar_height), egui::Sense::hover());
+            let rect = response.rect;
+
+            painter.rect_filled(rect, 2.0, egui::Color32::from_gray(30));
+
+            let filled = egui::Rect::from_min_size(
+                rect.min,
+                egui::Vec2::new(rect.width() * activation.clamp(0.0, 1.0) as f32, rect.height()),
+            );
+            painter.rect_filled(filled, 2.0, rule_color(idx, activations.len()));
+
+            if let Some(&max) = max_activations.get(idx) {
+                let x = rect.left() + rect.width() * max.clamp(0.0, 1.0) as f32;
+                painter.line_segment(
+                    [egui::Pos2::new(x, rect.top()), egui::Pos2::new(x, rect.bottom())],
+                    egui::Stroke::new(1.5, egui::Color32::WHITE),
+                );
+            }
         });
+    }
 }
 
-fn draw_mini_graph(ui: &mut egui_macroquad::egui::Ui, data: &[f32], current_idx: usize, unit: &str, color: egui_macroquad::egui::Color32) {
+/// Fraction of a trajectory of `points` points played back at frame `current_index`
+///
+/// A single point, a start that already met the arrival criteria, is played back in full.
+fn playback_fraction(current_index: usize, points: usize) -> f32 {
+    match points {
+        0 => 0.0,
+        1 => 1.0,
+        _ => current_index.min(points - 1) as f32 / (points - 1) as f32,
+    }
+}
+
+/// Time strip colored by the dominant rule of every step, with a marker at the current step
+fn draw_dominant_rule_strip(ui: &mut egui_macroquad::egui::Ui, dominant: &[Option<usize>], rule_count: usize, current_idx: usize) {
+    use egui_macroquad::egui;
+
+    let strip_height = 18.0;
+    let (response, painter) = ui.allocate_painter(egui::Vec2::new(ui.available_width(), strip_height), egui::Sense::hover());
+    let rect = response.rect;
+
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(30));
+
+    if dominant.is_empty() {
+        return;
+    }
+
+    let step_width = rect.width() / dominant.len() as f32;
+    for (step, rule) in dominant.iter().enumerate() {
+        if let Some(rule_idx) = rule {
+            let x = rect.left() + step as f32 * step_width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::Pos2::new(x, rect.top()),
+                    egui::Pos2::new(x + step_width.max(1.0), rect.bottom()),
+                ),
+                0.0,
+                rule_color(*rule_idx, rule_count),
+            );
+        }
+    }
+
+    let marker_x = rect.left() + (current_idx.min(dominant.len() - 1) as f32 + 0.5) * step_width;
+    painter.line_segment(
+        [egui::Pos2::new(marker_x, rect.top()), egui::Pos2::new(marker_x, rect.bottom())],
+        egui::Stroke::new(2.0, egui::Color32::WHITE),
+    );
+
+    if let Some(Some(rule_idx)) = dominant.get(current_idx) {
+        response.on_hover_text(format!("Regla dominante: {}", rule_idx + 1));
+    }
+}
+
+/// Line graph of one or more series up to `current_idx`, with optional horizontal reference lines.
+/// `hover_idx` (shared between graphs) draws a vertical cursor with its readout; returns the
+/// index under the mouse when this graph is hovered.
+fn draw_mini_graph(
+    ui: &mut egui_macroquad::egui::Ui,
+    series: &[(&[f32], egui_macroquad::egui::Color32)],
+    current_idx: usize,
+    unit: &str,
+    reference_lines: &[f32],
+    hover_idx: Option<usize>,
+) -> Option<usize> {
     use egui_macroquad::egui;
 
     // Simple canvas-based graph
@@ -964,12 +1866,22 @@ fn draw_mini_graph(ui: &mut egui_macroquad::egui::Ui, data: &[f32], current_idx:
     let (response, painter) = ui.allocate_painter(egui::Vec2::new(ui.available_width(), graph_height), egui::Sense::hover());
     let rect = response.rect;
 
-    if current_idx > 0 && !data.is_empty() {
-        // Find min/max for scaling
-        let visible_data: Vec<f32> = data.iter().take(current_idx + 1).cloned().collect();
-        let max_val = visible_data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-        let min_val = visible_data.iter().cloned().fold(f32::INFINITY, f32::min);
+    let data_len = series.iter().map(|(data, _)| data.len()).max().unwrap_or(0);
+    let visible_len = (current_idx + 1).min(data_len);
+    let index_to_x = |i: usize| rect.left() + (i as f32 / (visible_len.max(2) - 1) as f32) * rect.width();
+
+    let mut hovered = None;
+
+    if current_idx > 0 && visible_len > 1 {
+        // Find min/max for scaling, reference lines included so they stay visible
+        let visible_values = series.iter().flat_map(|(data, _)| data.iter().take(visible_len).copied());
+        let (mut min_val, mut max_val) = visible_values.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+        for &reference in reference_lines {
+            min_val = min_val.min(reference);
+            max_val = max_val.max(reference);
+        }
         let range = (max_val - min_val).max(0.1);
+        let value_to_y = |value: f32| rect.bottom() - (value - min_val) / range * rect.height();
 
         // Draw background
         painter.rect_filled(rect, 2.0, egui::Color32::from_gray(30));
@@ -983,35 +1895,62 @@ fn draw_mini_graph(ui: &mut egui_macroquad::egui::Ui, data: &[f32], current_idx:
             );
         }
 
-        // Draw line graph
-        let mut points: Vec<egui::Pos2> = Vec::new();
-        for (i, &value) in visible_data.iter().enumerate() {
-            let x = rect.left() + (i as f32 / (visible_data.len() - 1) as f32) * rect.width();
-            let normalized = (value - min_val) / range;
-            let y = rect.bottom() - normalized * rect.height();
-            points.push(egui::Pos2::new(x, y));
-        }
-
-        // Draw the line
-        for i in 0..points.len().saturating_sub(1) {
+        // Reference lines
+        for &reference in reference_lines {
+            let y = value_to_y(reference);
             painter.line_segment(
-                [points[i], points[i + 1]],
-                egui::Stroke::new(2.0, color)
+                [egui::Pos2::new(rect.left(), y), egui::Pos2::new(rect.right(), y)],
+                egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 80, 80))
             );
         }
 
-        // Draw current value marker
-        if current_idx < points.len() {
-            painter.circle_filled(points[current_idx], 3.0, egui::Color32::WHITE);
+        // Draw each series and its current value marker
+        for (data, color) in series {
+            let points: Vec<egui::Pos2> = data.iter()
+                .take(visible_len)
+                .enumerate()
+                .map(|(i, &value)| egui::Pos2::new(index_to_x(i), value_to_y(value)))
+                .collect();
+
+            for pair in points.windows(2) {
+                painter.line_segment([pair[0], pair[1]], egui::Stroke::new(2.0, *color));
+            }
+
+            if let Some(&last) = points.last() {
+                painter.circle_filled(last, 3.0, egui::Color32::WHITE);
+            }
+        }
+
+        // Index under the mouse, reported to the other graphs
+        if let Some(pos) = response.hover_pos() {
+            let fraction = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            hovered = Some((fraction * (visible_len - 1) as f32).round() as usize);
+        }
+
+        // Shared cursor
+        if let Some(idx) = hovered.or(hover_idx).filter(|&idx| idx < visible_len) {
+            let x = index_to_x(idx);
+            painter.line_segment(
+                [egui::Pos2::new(x, rect.top()), egui::Pos2::new(x, rect.bottom())],
+                egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY)
+            );
         }
     }
 
-    // Current value label
-    if current_idx < data.len() {
-        ui.label(egui::RichText::new(format!("Current: {:.1} {}", data[current_idx], unit))
+    // Current value label (cursor value when hovering any graph)
+    let readout_idx = hovered.or(hover_idx).filter(|&idx| idx < visible_len).unwrap_or(current_idx);
+    let values: Vec<String> = series.iter()
+        .filter_map(|(data, _)| data.get(readout_idx))
+        .map(|value| format!("{:.1}", value))
+        .collect();
+    if !values.is_empty() {
+        let prefix = if readout_idx == current_idx { "Actual" } else { "Cursor" };
+        ui.label(egui::RichText::new(format!("{}: {} {}", prefix, values.join(" / "), unit))
             .small()
             .color(egui::Color32::LIGHT_GRAY));
     }
+
+    hovered
 }
 
 // Public entry point for the visualizer
@@ -1020,3 +1959,166 @@ pub fn run() {
     // So we can just call main() directly
     main();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(color: Color) -> (u8, u8, u8) {
+        ((color.r * 255.0).round() as u8, (color.g * 255.0).round() as u8, (color.b * 255.0).round() as u8)
+    }
+
+    #[test]
+    fn test_colors_keep_base_color_for_unique_types() {
+        let colors = assign_vehicle_colors(&["Barco", "Lancha", "Avión"]);
+        assert_eq!(rgb(colors[0]), (255, 200, 50));
+        assert_eq!(rgb(colors[1]), (100, 255, 100));
+        assert_eq!(rgb(colors[2]), (100, 150, 255));
+    }
+
+    #[test]
+    fn test_colors_distinct_for_duplicate_types() {
+        let colors = assign_vehicle_colors(&["Barco", "Barco", "Barco", "Lancha"]);
+        assert_eq!(rgb(colors[0]), (255, 200, 50));
+        for i in 0..colors.len() {
+            for j in (i + 1)..colors.len() {
+                assert_ne!(rgb(colors[i]), rgb(colors[j]), "vehicles {} and {} share a color", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_colors_unknown_type_uses_palette() {
+        let colors = assign_vehicle_colors(&["Desconocido"]);
+        assert_eq!(rgb(colors[0]), FALLBACK_PALETTE[0]);
+    }
+
+    #[test]
+    fn test_layout_for_several_window_sizes() {
+        let (map_w, map_h) = (1000.0, 800.0);
+        let sizes = [(1800.0, 1000.0), (1366.0, 768.0), (1280.0, 760.0), (1024.0, 600.0), (2560.0, 1440.0), (900.0, 1200.0)];
+
+        for (screen_w, screen_h) in sizes {
+            let layout = Layout::compute(screen_w, screen_h, map_w, map_h);
+            let rect = layout.map_rect(map_w, map_h);
+
+            assert!(layout.sidebar_width >= MIN_SIDEBAR_WIDTH, "{}x{}", screen_w, screen_h);
+            assert!(layout.offset_x >= 0.0 && layout.offset_y >= 0.0, "{}x{}", screen_w, screen_h);
+            assert!(rect.x >= layout.sidebar_width, "map overlaps sidebar at {}x{}", screen_w, screen_h);
+            assert!(rect.right() <= screen_w && rect.bottom() <= screen_h, "map outside window at {}x{}", screen_w, screen_h);
+            assert!((rect.w / rect.h - map_w / map_h).abs() < 1e-4, "aspect ratio changed at {}x{}", screen_w, screen_h);
+        }
+    }
+
+    #[test]
+    fn test_layout_sidebar_is_proportional() {
+        let small = Layout::compute(1024.0, 600.0, 1000.0, 800.0);
+        let large = Layout::compute(2560.0, 1440.0, 1000.0, 800.0);
+        assert_eq!(small.sidebar_width, MIN_SIDEBAR_WIDTH);
+        assert_eq!(large.sidebar_width, 2560.0 * SIDEBAR_FRACTION);
+        assert!(large.scale > small.scale);
+    }
+
+    #[test]
+    fn test_layout_degenerate_window_keeps_offsets_nonnegative() {
+        let layout = Layout::compute(200.0, 100.0, 1000.0, 800.0);
+        assert!(layout.scale > 0.0);
+        assert!(layout.offset_x >= layout.sidebar_width && layout.offset_y >= 0.0);
+    }
+
+    #[test]
+    fn test_graph_series_from_synthetic_trajectory() {
+        let mut trajectory: Vec<TrajectoryPoint> = (0..4)
+            .map(|i| TrajectoryPoint {
+                t: i as f64 * 0.05,
+                angle: 80.0 + i as f64,
+                distance_to_target: 100.0 - i as f64 * 10.0,
+                angular_velocity: Some(i as f64 * 2.0),
+                commanded_adjustment: Some(50.0 - i as f64 * 10.0),
+                angular_adjustment: Some((50.0 - i as f64 * 10.0).min(30.0)),
+                ..Default::default()
+            })
+            .collect();
+        // Arrival point carries no control fields
+        trajectory.push(TrajectoryPoint { t: 0.2, angle: 90.0, distance_to_target: 20.0, ..Default::default() });
+
+        let graphs = build_graph_series(&trajectory);
+        assert_eq!(graphs.distance, vec![100.0, 90.0, 80.0, 70.0, 20.0]);
+        assert_eq!(graphs.angle_error, vec![10.0, 9.0, 8.0, 7.0, 0.0]);
+        assert_eq!(graphs.angular_velocity, Some(vec![0.0, 2.0, 4.0, 6.0, 6.0]));
+        assert_eq!(graphs.commanded_adjustment, Some(vec![50.0, 40.0, 30.0, 20.0, 20.0]));
+        assert_eq!(graphs.applied_adjustment, Some(vec![30.0, 30.0, 30.0, 20.0, 20.0]));
+    }
+
+    #[test]
+    fn test_graph_series_without_control_fields() {
+        let trajectory = vec![TrajectoryPoint { distance_to_target: 5.0, angle: 90.0, ..Default::default() }];
+        let graphs = build_graph_series(&trajectory);
+        assert_eq!(graphs.distance, vec![5.0]);
+        assert!(graphs.angular_velocity.is_none());
+        assert!(graphs.commanded_adjustment.is_none());
+        assert!(graphs.applied_adjustment.is_none());
+        assert!(graphs.velocity.is_none());
+        assert!(graphs.average_velocity.is_none());
+    }
+
+    #[test]
+    fn test_velocity_series_with_running_average() {
+        let trajectory: Vec<TrajectoryPoint> = [40.0, 50.0, 30.0, 40.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &velocity)| TrajectoryPoint { t: (i + 1) as f64, velocity, ..Default::default() })
+            .collect();
+
+        let graphs = build_graph_series(&trajectory);
+        assert_eq!(graphs.velocity, Some(vec![40.0, 50.0, 30.0, 40.0]));
+        assert_eq!(graphs.average_velocity, Some(vec![40.0, 45.0, 40.0, 40.0]));
+    }
+
+    #[test]
+    fn test_playback_fraction_of_a_run_that_started_on_the_target() {
+        assert_eq!(playback_fraction(0, 1), 1.0);
+        assert_eq!(playback_fraction(0, 0), 0.0);
+        assert_eq!(playback_fraction(0, 11), 0.0);
+        assert_eq!(playback_fraction(5, 11), 0.5);
+        assert_eq!(playback_fraction(20, 11), 1.0);
+    }
+
+    #[test]
+    fn test_truncate_label() {
+        assert_eq!(truncate_label("if a is b", 20), "if a is b");
+        assert_eq!(truncate_label("if distancia is cerca", 8), "if dist…");
+    }
+
+    #[test]
+    fn test_rule_labels_for_unknown_type_falls_back() {
+        assert_eq!(rule_labels_for("Desconocido"), rule_labels_for("Lancha"));
+        assert!(!rule_labels_for("Barco").is_empty());
+    }
+
+    #[test]
+    fn test_add_remove_duplicate_configs() {
+        let map = Map::new(1000.0, 800.0, 500.0, 700.0);
+        let mut configs = Vec::new();
+
+        add_vehicle_config(&mut configs, VehicleType::Heavy, &map);
+        add_vehicle_config(&mut configs, VehicleType::UltraAgile, &map);
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].vehicle_type, VehicleType::UltraAgile);
+
+        assert!(duplicate_vehicle_config(&mut configs, 0));
+        assert_eq!(configs.len(), 3);
+        assert_eq!(configs[1].vehicle_type, VehicleType::Heavy);
+        assert_eq!(configs[1].position_x, configs[0].position_x);
+        assert_eq!(configs[2].vehicle_type, VehicleType::UltraAgile);
+
+        assert!(remove_vehicle_config(&mut configs, 0));
+        assert_eq!(configs.len(), 2);
+        assert!(!remove_vehicle_config(&mut configs, 5));
+        assert!(!duplicate_vehicle_config(&mut configs, 5));
+
+        assert!(remove_vehicle_config(&mut configs, 0));
+        assert!(remove_vehicle_config(&mut configs, 0));
+        assert!(configs.is_empty());
+    }
+}
@@ -0,0 +1,11 @@
+// Browser entry points (wasm-bindgen), enabled by the `wasm` feature
+
+use wasm_bindgen::prelude::*;
+
+/// Run a multi-vehicle simulation described by a `SimulationConfig` JSON
+///
+/// Returns the `MultiVehicleSimulationResult` as JSON, or throws the error message.
+#[wasm_bindgen]
+pub fn run_simulation(config_json: &str) -> Result<String, JsValue> {
+    crate::simulation::run_config_json(config_json).map_err(|e| JsValue::from_str(&e.to_string()))
+}
@@ -0,0 +1,433 @@
+// Excel workbooks of simulation results and benchmark summaries
+//
+// Every sheet is a plain table: one bold header row, frozen, then one row per
+// record with numbers written as numbers, so filters, charts and conditional
+// formatting work on the columns as they are. Missing values are empty cells.
+//
+// Simulation workbook (`to_xlsx`)
+//   <vehicle id>  one sheet per vehicle, one row per trajectory point
+//   Metrics       one row per vehicle
+//   Info          schema version, simulated time and the build metadata
+//
+// Benchmark workbook (`benchmark_to_xlsx`)
+//   Summary       one row per group of runs (heading mode, vehicle type and bucket)
+//   Info          the full benchmark configuration and the build metadata
+
+use std::path::Path;
+
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde_json::Value;
+
+use crate::build_info::BuildMeta;
+use crate::simulation::benchmark::BenchmarkConfig;
+use crate::simulation::{MultiVehicleSimulationResult, TrajectoryPoint, VehicleResult};
+
+/// Longest sheet name Excel accepts
+const MAX_SHEET_NAME: usize = 31;
+
+/// Typed value of one cell
+enum Cell {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Empty,
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Cell::Number(value)
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(value: usize) -> Self {
+        Cell::Number(value as f64)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Bool(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// Write `headers` as a frozen bold row and `rows` below it
+fn write_table(sheet: &mut Worksheet, headers: &[&str], rows: impl IntoIterator<Item = Vec<Cell>>) -> Result<(), XlsxError> {
+    let bold = Format::new().set_bold();
+    for (column, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, *header, &bold)?;
+    }
+    for (row, cells) in rows.into_iter().enumerate() {
+        let row = row as u32 + 1;
+        for (column, cell) in cells.into_iter().enumerate() {
+            let column = column as u16;
+            match cell {
+                Cell::Number(value) if value.is_finite() => sheet.write_number(row, column, value)?,
+                Cell::Number(_) | Cell::Empty => sheet,
+                Cell::Text(text) => sheet.write_string(row, column, text)?,
+                Cell::Bool(value) => sheet.write_boolean(row, column, value)?,
+            };
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/// `Info` sheet, every leaf of the `sections` as a (key, value) row, keys joined by dots
+fn write_info(workbook: &mut Workbook, sections: &[(&str, Value)]) -> Result<(), XlsxError> {
+    fn flatten(key: String, value: &Value, rows: &mut Vec<Vec<Cell>>) {
+        let cell = match value {
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    flatten(format!("{}.{}", key, name), field, rows);
+                }
+                return;
+            }
+            Value::Null => Cell::Empty,
+            Value::Bool(value) => Cell::Bool(*value),
+            Value::Number(number) => number.as_f64().map_or(Cell::Empty, Cell::Number),
+            Value::String(text) => Cell::Text(text.clone()),
+            Value::Array(_) => Cell::Text(value.to_string()),
+        };
+        rows.push(vec![Cell::Text(key), cell]);
+    }
+
+    let mut rows = Vec::new();
+    for (name, value) in sections {
+        flatten(name.to_string(), value, &mut rows);
+    }
+    write_table(workbook.add_worksheet().set_name("Info")?, &["key", "value"], rows)
+}
+
+/// Sheet name of a vehicle id, without the characters Excel rejects and at most 31 characters,
+/// numbered when it would repeat one of the `taken` names (compared without case, like Excel)
+fn sheet_name(id: &str, taken: &[String]) -> String {
+    let clean: String = id.chars()
+        .map(|c| if matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') { '_' } else { c })
+        .collect();
+    let is_taken = |name: &str| taken.iter().any(|other| other.eq_ignore_ascii_case(name));
+    let mut name: String = clean.chars().take(MAX_SHEET_NAME).collect();
+    let mut copy = 1;
+    while name.is_empty() || is_taken(&name) {
+        copy += 1;
+        let suffix = format!(" ({})", copy);
+        name = clean.chars().take(MAX_SHEET_NAME - suffix.len()).collect::<String>() + &suffix;
+    }
+    name
+}
+
+const TRAJECTORY_HEADERS: [&str; 11] = [
+    "t", "x", "y", "angle", "velocity", "distance_to_target", "desired_heading",
+    "angular_adjustment", "commanded_adjustment", "angular_velocity", "approach_bias",
+];
+
+fn trajectory_row(point: &TrajectoryPoint) -> Vec<Cell> {
+    vec![
+        point.t.into(),
+        point.x.into(),
+        point.y.into(),
+        point.angle.0.into(),
+        point.velocity.into(),
+        point.distance_to_target.into(),
+        point.desired_heading.map(|heading| heading.0).into(),
+        point.angular_adjustment.into(),
+        point.commanded_adjustment.into(),
+        point.angular_velocity.into(),
+        point.approach_bias.map(|bias| bias.0).into(),
+    ]
+}
+
+const METRICS_HEADERS: [&str; 17] = [
+    "id", "vehicle_type", "success", "arrival_time", "distance_traveled", "final_distance_to_target",
+    "final_angle_error", "termination", "steering_reversals_per_minute", "heading_jerk", "steps_with_no_rule_fired",
+    "transit_time", "alignment_time", "first_aligned_time", "alignment_attempts", "optimal_time_lower_bound",
+    "time_optimality_ratio",
+];
+
+fn metrics_row(vehicle: &VehicleResult) -> Vec<Cell> {
+    let metrics = &vehicle.metrics;
+    vec![
+        vehicle.id.as_str().into(),
+        vehicle.vehicle_type.as_str().into(),
+        metrics.success.into(),
+        metrics.arrival_time.into(),
+        metrics.distance_traveled.into(),
+        metrics.final_distance_to_target.into(),
+        metrics.final_angle_error.into(),
+        metrics.termination.map(|reason| format!("{:?}", reason)).as_deref().into(),
+        metrics.steering_reversals_per_minute.into(),
+        metrics.heading_jerk.into(),
+        metrics.steps_with_no_rule_fired.into(),
+        metrics.transit_time.into(),
+        metrics.alignment_time.into(),
+        metrics.first_aligned_time.into(),
+        metrics.alignment_attempts.into(),
+        metrics.optimal_time_lower_bound.into(),
+        metrics.time_optimality_ratio.into(),
+    ]
+}
+
+/// Workbook of a simulation: a trajectory sheet per vehicle, `Metrics` and `Info`
+pub fn simulation_workbook(result: &MultiVehicleSimulationResult) -> Result<Workbook, XlsxError> {
+    let mut workbook = Workbook::new();
+    let mut names = vec!["Metrics".to_string(), "Info".to_string()];
+    for vehicle in &result.vehicles {
+        let name = sheet_name(&vehicle.id, &names);
+        let sheet = workbook.add_worksheet().set_name(&name)?;
+        write_table(sheet, &TRAJECTORY_HEADERS, vehicle.trajectory.iter().map(trajectory_row))?;
+        names.push(name);
+    }
+    write_table(workbook.add_worksheet().set_name("Metrics")?, &METRICS_HEADERS, result.vehicles.iter().map(metrics_row))?;
+    write_info(
+        &mut workbook,
+        &[
+            ("schema_version", Value::from(result.schema_version)),
+            ("total_simulation_time", Value::from(result.total_simulation_time)),
+            ("meta", serde_json::to_value(&result.meta).unwrap_or_default()),
+        ],
+    )?;
+    Ok(workbook)
+}
+
+/// Write the simulation workbook to `path`
+pub fn to_xlsx(result: &MultiVehicleSimulationResult, path: impl AsRef<Path>) -> Result<(), XlsxError> {
+    simulation_workbook(result)?.save(path)
+}
+
+/// The simulation workbook as the bytes of an xlsx file
+pub fn to_xlsx_buffer(result: &MultiVehicleSimulationResult) -> Result<Vec<u8>, XlsxError> {
+    simulation_workbook(result)?.save_to_buffer()
+}
+
+/// One row of the `Summary` sheet, the statistics of one group of runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryRecord {
+    pub vehicle_type: String,
+    pub heading: String,
+    /// Initial condition bucket, None for the statistics of every run of the group
+    pub bucket: Option<String>,
+    pub total_runs: usize,
+    pub successes: usize,
+    /// Percent
+    pub success_rate: f64,
+    pub success_rate_ci_low: f64,
+    pub success_rate_ci_high: f64,
+    pub avg_arrival_time: f64,
+    pub std_arrival_time: f64,
+    pub min_arrival_time: f64,
+    pub max_arrival_time: f64,
+    pub avg_distance_traveled: f64,
+    pub std_distance_traveled: f64,
+    pub avg_final_distance: f64,
+    pub avg_final_angle_error: f64,
+    pub avg_steering_reversals_per_minute: f64,
+    pub avg_heading_jerk: f64,
+    pub avg_time_optimality_ratio: Option<f64>,
+    pub avg_transit_time: Option<f64>,
+    pub avg_alignment_time: Option<f64>,
+    pub avg_alignment_attempts: f64,
+    /// Wall time of one run (seconds)
+    pub avg_wall_time: f64,
+}
+
+const SUMMARY_HEADERS: [&str; 23] = [
+    "vehicle_type", "heading", "bucket", "total_runs", "successes", "success_rate", "success_rate_ci_low",
+    "success_rate_ci_high", "avg_arrival_time", "std_arrival_time", "min_arrival_time", "max_arrival_time",
+    "avg_distance_traveled", "std_distance_traveled", "avg_final_distance", "avg_final_angle_error",
+    "avg_steering_reversals_per_minute", "avg_heading_jerk", "avg_time_optimality_ratio", "avg_transit_time",
+    "avg_alignment_time", "avg_alignment_attempts", "avg_wall_time",
+];
+
+fn summary_row(record: &SummaryRecord) -> Vec<Cell> {
+    vec![
+        record.vehicle_type.as_str().into(),
+        record.heading.as_str().into(),
+        record.bucket.as_deref().into(),
+        record.total_runs.into(),
+        record.successes.into(),
+        record.success_rate.into(),
+        record.success_rate_ci_low.into(),
+        record.success_rate_ci_high.into(),
+        record.avg_arrival_time.into(),
+        record.std_arrival_time.into(),
+        record.min_arrival_time.into(),
+        record.max_arrival_time.into(),
+        record.avg_distance_traveled.into(),
+        record.std_distance_traveled.into(),
+        record.avg_final_distance.into(),
+        record.avg_final_angle_error.into(),
+        record.avg_steering_reversals_per_minute.into(),
+        record.avg_heading_jerk.into(),
+        record.avg_time_optimality_ratio.into(),
+        record.avg_transit_time.into(),
+        record.avg_alignment_time.into(),
+        record.avg_alignment_attempts.into(),
+        record.avg_wall_time.into(),
+    ]
+}
+
+/// Statistics and inputs of a benchmark, from the API response or the benchmark bin
+pub struct BenchmarkSummary<'a> {
+    pub config: &'a BenchmarkConfig,
+    pub meta: &'a BuildMeta,
+    pub groups: Vec<SummaryRecord>,
+}
+
+/// Workbook of a benchmark: `Summary` and `Info`
+pub fn benchmark_workbook(summary: &BenchmarkSummary) -> Result<Workbook, XlsxError> {
+    let mut workbook = Workbook::new();
+    write_table(workbook.add_worksheet().set_name("Summary")?, &SUMMARY_HEADERS, summary.groups.iter().map(summary_row))?;
+    write_info(
+        &mut workbook,
+        &[
+            ("config", serde_json::to_value(summary.config).unwrap_or_default()),
+            ("meta", serde_json::to_value(summary.meta).unwrap_or_default()),
+        ],
+    )?;
+    Ok(workbook)
+}
+
+/// Write the benchmark workbook to `path`
+pub fn benchmark_to_xlsx(summary: &BenchmarkSummary, path: impl AsRef<Path>) -> Result<(), XlsxError> {
+    benchmark_workbook(summary)?.save(path)
+}
+
+/// The benchmark workbook as the bytes of an xlsx file
+pub fn benchmark_to_xlsx_buffer(summary: &BenchmarkSummary) -> Result<Vec<u8>, XlsxError> {
+    benchmark_workbook(summary)?.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationMetrics;
+    use calamine::{open_workbook_from_rs, Data, Reader, Xlsx};
+    use std::io::Cursor;
+
+    fn read_back(bytes: Vec<u8>) -> Xlsx<Cursor<Vec<u8>>> {
+        open_workbook_from_rs(Cursor::new(bytes)).unwrap()
+    }
+
+    fn vehicle(id: &str, points: usize, arrival_time: Option<f64>) -> VehicleResult {
+        VehicleResult {
+            id: id.to_string(),
+            vehicle_type: "Standard".to_string(),
+            trajectory: (0..points)
+                .map(|i| TrajectoryPoint { t: i as f64 * 0.05, x: i as f64, y: 2.0 * i as f64, ..Default::default() })
+                .collect(),
+            metrics: SimulationMetrics {
+                success: arrival_time.is_some(),
+                arrival_time,
+                distance_traveled: 120.0,
+                final_distance_to_target: 10.0,
+                final_angle_error: 2.5,
+                termination: None,
+                ..Default::default()
+            },
+            target: None,
+        }
+    }
+
+    #[test]
+    fn test_simulation_workbook_has_a_sheet_per_vehicle() {
+        let result = MultiVehicleSimulationResult {
+            schema_version: 3,
+            vehicles: vec![
+                vehicle("standard-1", 40, Some(1.95)),
+                vehicle("agile:2/long id of more than 31 chars", 25, None),
+                vehicle("agile:2/long id of more than 31 chars too", 10, None),
+                vehicle("metrics", 5, None),
+            ],
+            total_simulation_time: 2.0,
+            approach_geometry: None,
+            meta: Some(BuildMeta::current(1)),
+        };
+        let mut workbook = read_back(to_xlsx_buffer(&result).unwrap());
+
+        assert_eq!(
+            workbook.sheet_names(),
+            ["standard-1", "agile_2_long id of more than 31", "agile_2_long id of more tha (2)", "metrics (2)", "Metrics", "Info"]
+        );
+
+        let trajectory = workbook.worksheet_range("standard-1").unwrap();
+        assert_eq!(trajectory.height(), 41);
+        assert_eq!(trajectory.get_value((0, 0)), Some(&Data::String("t".to_string())));
+        assert_eq!(trajectory.get_value((40, 2)), Some(&Data::Float(78.0)));
+
+        let metrics = workbook.worksheet_range("Metrics").unwrap();
+        assert_eq!(metrics.height(), 5);
+        assert_eq!(metrics.get_value((1, 2)), Some(&Data::Bool(true)));
+        assert_eq!(metrics.get_value((1, 3)), Some(&Data::Float(1.95)));
+        // The vehicle that did not arrive has no arrival time
+        assert_eq!(metrics.get_value((2, 3)), Some(&Data::Empty));
+
+        let info = workbook.worksheet_range("Info").unwrap();
+        let schema_row = info.rows().find(|row| row[0] == Data::String("schema_version".to_string())).unwrap();
+        assert_eq!(schema_row[1], Data::Float(3.0));
+        assert!(info.rows().any(|row| row[0] == Data::String("meta.crate_version".to_string())));
+    }
+
+    #[test]
+    fn test_benchmark_workbook_has_a_row_per_group() {
+        let record = |heading: &str, bucket: Option<&str>| SummaryRecord {
+            vehicle_type: "heavy".to_string(),
+            heading: heading.to_string(),
+            bucket: bucket.map(str::to_string),
+            total_runs: 30,
+            successes: 27,
+            success_rate: 90.0,
+            success_rate_ci_low: 74.4,
+            success_rate_ci_high: 96.5,
+            avg_arrival_time: 80.0,
+            std_arrival_time: 12.0,
+            min_arrival_time: 60.0,
+            max_arrival_time: 110.0,
+            avg_distance_traveled: 700.0,
+            std_distance_traveled: 50.0,
+            avg_final_distance: 18.0,
+            avg_final_angle_error: 3.0,
+            avg_steering_reversals_per_minute: 4.0,
+            avg_heading_jerk: 0.2,
+            avg_time_optimality_ratio: None,
+            avg_transit_time: Some(55.0),
+            avg_alignment_time: Some(25.0),
+            avg_alignment_attempts: 1.2,
+            avg_wall_time: 0.01,
+        };
+        let config = BenchmarkConfig::default();
+        let meta = BuildMeta::current(1);
+        let summary = BenchmarkSummary {
+            config: &config,
+            meta: &meta,
+            groups: vec![record("uniform", None), record("toward_target", None), record("uniform", Some("distance < 700"))],
+        };
+        let mut workbook = read_back(benchmark_to_xlsx_buffer(&summary).unwrap());
+
+        assert_eq!(workbook.sheet_names(), ["Summary", "Info"]);
+        let sheet = workbook.worksheet_range("Summary").unwrap();
+        assert_eq!(sheet.height(), 4);
+        assert_eq!(sheet.width(), SUMMARY_HEADERS.len());
+        assert_eq!(sheet.get_value((2, 1)), Some(&Data::String("toward_target".to_string())));
+        assert_eq!(sheet.get_value((1, 2)), Some(&Data::Empty));
+        assert_eq!(sheet.get_value((3, 2)), Some(&Data::String("distance < 700".to_string())));
+        assert_eq!(sheet.get_value((1, 3)), Some(&Data::Float(30.0)));
+        assert_eq!(sheet.get_value((1, 18)), Some(&Data::Empty));
+        assert_eq!(sheet.get_value((1, 19)), Some(&Data::Float(55.0)));
+
+        let info = workbook.worksheet_range("Info").unwrap();
+        assert!(info.rows().any(|row| row[0] == Data::String("config.seed".to_string())));
+    }
+}
@@ -0,0 +1,17 @@
+#!/bin/bash
+# Script para compilar el módulo Python fuzzy_nav en un virtualenv y ejecutar sus tests
+#
+# Uso: ./test-python.sh
+
+set -e
+
+VENV=.venv-python
+
+if [ ! -d "$VENV" ]; then
+    python3 -m venv "$VENV"
+fi
+source "$VENV/bin/activate"
+
+pip install -q maturin pytest numpy
+maturin develop --release
+pytest -q python/tests